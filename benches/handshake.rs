@@ -0,0 +1,168 @@
+//! The steps of the handshake for the groups of 1024, 2048 and 4096 bits,
+//! run with `cargo bench --features norand`.
+//!
+//! The random values come from a seeded generator, so that each run computes
+//! the same numbers. The steps that consume their state get a new one for each
+//! iteration, prepared outside of the measurement.
+use chadehoc_srp6::*;
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+const USERNAME: UsernameRef = "Bob";
+const PASSWORD: &ClearTextPassword = "secret-password";
+
+/// the user after `start_handshake`, the host after `continue_handshake`
+fn started<const LEN: usize>(
+    constants: &OpenConstants<LEN>,
+    user_details: &UserDetails,
+    rng: &mut StdRng,
+) -> (Srp6User<LEN>, ServerHandshake, Srp6WaitingForProof<LEN>) {
+    let mut user = Srp6User::<LEN>::default();
+    let handshake = user
+        .start_handshake_with_rng(USERNAME, constants, rng)
+        .unwrap();
+    let (server_handshake, host) = Srp6::<LEN>::default()
+        .continue_handshake_with_rng(user_details, &handshake.user_publickey, constants, rng)
+        .unwrap();
+    (user, server_handshake, host)
+}
+
+/// both sides waiting for the proof of the other one
+fn updated<const LEN: usize>(
+    constants: &OpenConstants<LEN>,
+    user_details: &UserDetails,
+    rng: &mut StdRng,
+) -> (
+    Proof,
+    Srp6UserWaitingForServerProof<LEN>,
+    Srp6WaitingForProof<LEN>,
+) {
+    let (user, server_handshake, host) = started(constants, user_details, rng);
+    let (proof, user) = user
+        .update_handshake(
+            &server_handshake,
+            constants,
+            &USERNAME.into(),
+            &PASSWORD.into(),
+        )
+        .unwrap();
+    (proof, user, host)
+}
+
+fn bench_group<const LEN: usize>(c: &mut Criterion, constants: OpenConstants<LEN>)
+where
+    OpenConstants<LEN>: Default,
+{
+    let bits = LEN * 8;
+    let id = BenchmarkId::from_parameter(bits);
+
+    // the group is built once, later calls share it
+    c.benchmark_group("default_constants")
+        .bench_function(id.clone(), |b| b.iter(OpenConstants::<LEN>::default));
+    let mut rng = StdRng::seed_from_u64(42);
+    let user_details = Srp6User::<LEN>::generate_new_user_secrets_with_rng(
+        &USERNAME.into(),
+        &PASSWORD.into(),
+        &constants,
+        &mut rng,
+    )
+    .unwrap();
+    let user_publickey = Srp6User::<LEN>::default()
+        .start_handshake_with_rng(USERNAME, &constants, &mut rng)
+        .unwrap()
+        .user_publickey;
+
+    c.benchmark_group("generate_new_user_secrets")
+        .bench_function(id.clone(), |b| {
+            b.iter(|| {
+                Srp6User::<LEN>::generate_new_user_secrets_with_rng(
+                    &USERNAME.into(),
+                    &PASSWORD.into(),
+                    &constants,
+                    &mut rng,
+                )
+                .unwrap()
+            })
+        });
+
+    c.benchmark_group("start_handshake")
+        .bench_function(id.clone(), |b| {
+            b.iter_batched_ref(
+                Srp6User::<LEN>::default,
+                |user| user.start_handshake_with_rng(USERNAME, &constants, &mut rng),
+                BatchSize::SmallInput,
+            )
+        });
+
+    c.benchmark_group("to_array_pad_zero")
+        .bench_function(id.clone(), |b| {
+            b.iter(|| user_publickey.to_array_pad_zero::<LEN>())
+        });
+
+    c.benchmark_group("continue_handshake")
+        .bench_function(id.clone(), |b| {
+            b.iter(|| {
+                Srp6::<LEN>::default()
+                    .continue_handshake_with_rng(
+                        &user_details,
+                        &user_publickey,
+                        &constants,
+                        &mut rng,
+                    )
+                    .unwrap()
+            })
+        });
+
+    c.benchmark_group("update_handshake")
+        .bench_function(id.clone(), |b| {
+            b.iter_batched(
+                || started(&constants, &user_details, &mut rng),
+                |(user, server_handshake, host)| {
+                    let updated = user
+                        .update_handshake(
+                            &server_handshake,
+                            &constants,
+                            &USERNAME.into(),
+                            &PASSWORD.into(),
+                        )
+                        .unwrap();
+                    // dropped outside of the measurement
+                    (updated, host)
+                },
+                BatchSize::SmallInput,
+            )
+        });
+
+    let mut verify_proof = c.benchmark_group("verify_proof");
+    verify_proof.bench_function(BenchmarkId::new("host", bits), |b| {
+        b.iter_batched(
+            || updated(&constants, &user_details, &mut rng),
+            |(proof, user, host)| (host.verify_proof(&proof).unwrap(), user),
+            BatchSize::SmallInput,
+        )
+    });
+    verify_proof.bench_function(BenchmarkId::new("user", bits), |b| {
+        b.iter_batched(
+            || {
+                let (proof, user, host) = updated(&constants, &user_details, &mut rng);
+                let strong_proof = host.verify_proof(&proof).unwrap().server_proof().clone();
+                (strong_proof, user)
+            },
+            |(strong_proof, user)| user.verify_proof(&strong_proof).unwrap(),
+            BatchSize::SmallInput,
+        )
+    });
+    verify_proof.finish();
+}
+
+fn bench_groups(c: &mut Criterion) {
+    // `norand` enables `insecure-key-lengths`
+    #[cfg(feature = "insecure-key-lengths")]
+    bench_group(c, OpenConstants::<128>::default());
+    bench_group(c, OpenConstants::<256>::default());
+    bench_group(c, OpenConstants::<512>::default());
+}
+
+criterion_group!(benches, bench_groups);
+criterion_main!(benches);
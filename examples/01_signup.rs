@@ -5,12 +5,33 @@ fn main() {
     let new_username: UsernameRef = "Bob";
     let user_password: &ClearTextPassword = "secret-password";
 
-    let user_details = Srp6user4096::generate_new_user_secrets(
-        new_username,
-        user_password,
+    // the rules of the signup form, an empty username or password is always rejected
+    let policy = CredentialPolicy {
+        min_password_length: 8,
+        max_password_length: 1024,
+        max_username_length: 64,
+        ..CredentialPolicy::default()
+    };
+    let user_details = Srp6user4096::generate_new_user_secrets_with_policy(
+        &new_username.into(),
+        &user_password.into(),
         &OpenConstants::default(),
+        &policy,
+    )
+    .expect("the credentials should follow the policy");
+    assert_eq!(
+        Srp6user4096::generate_new_user_secrets_with_policy(
+            &new_username.into(),
+            &"short".into(),
+            &OpenConstants::default(),
+            &policy,
+        ),
+        Err(Srp6Error::PasswordTooShort {
+            length: 5,
+            minimum: 8
+        })
     );
-    assert_eq!(user_details.salt.num_bytes(), 4096 / 8);
+    assert!(user_details.salt.num_bytes() <= DEFAULT_SALT_LENGTH);
     assert_eq!(user_details.verifier.num_bytes(), 4096 / 8);
 
     println!("Simulating a server and signup with user {}", new_username);
@@ -2,11 +2,12 @@ use chadehoc_srp6::*;
 use std::time::{Duration, Instant};
 
 fn main() {
-    let username = "Bob";
-    let password: &ClearTextPassword = "secret-password";
+    let username = Username::from("Bob");
+    let password = Password::from("secret-password");
     let constants = OpenConstants::default();
     // new user : those are sent to the server and stored there
-    let user_details = Srp6user4096::generate_new_user_secrets(username, password, &constants);
+    let user_details =
+        Srp6user4096::generate_new_user_secrets(&username, &password, &constants).unwrap();
     // averaging durations
     let mut durations: Duration = Duration::default();
     #[cfg(debug_assertions)]
@@ -16,27 +17,46 @@ fn main() {
     for _ in 0..NLOOPS {
         let start = Instant::now();
         // user creates a handshake
-        let mut srp6_user = Srp6user4096::default();
-        let user_handshake = srp6_user.start_handshake(username, &constants);
+        let mut srp6_user = Srp6user4096::with_credentials(&username, &password);
+        let user_handshake = srp6_user.start_handshake(&constants).unwrap();
         // server retrieves stored details and continues the handshake
-        let mut srp6 = Srp6_4096::default();
-        let server_handshake = srp6
-            .continue_handshake(&user_details, &user_handshake.user_publickey, &constants)
+        let (server_handshake, srp6) = Srp6_4096::new()
+            .for_user(&user_details)
+            .continue_handshake(&user_handshake.user_publickey, &constants)
             .unwrap();
         // client side
-        let proof = srp6_user
-            .update_handshake(&server_handshake, &constants, username, password)
+        let (proof, srp6_user) = srp6_user
+            .update_handshake(&server_handshake, &constants)
             .unwrap();
         // server side
-        let (hamk, secret) = srp6.verify_proof(&proof).expect("invalid client proof");
+        let outcome = srp6.verify_proof(&proof).expect("invalid client proof");
+        let hamk = outcome.server_proof().clone();
+        let secret = outcome.into_session_secret();
         // client side
-        let secret2 = srp6_user.verify_proof(&hamk).expect("invalid server proof");
+        let secret2 = srp6_user
+            .verify_proof(&hamk)
+            .expect("invalid server proof")
+            .into_session_secret();
         // end of processing
         let duration = start.elapsed();
         durations = durations.checked_add(duration).unwrap();
         // secrets are the same
         assert_eq!(secret2, secret, "not same secrets");
+        // e.g. a key for AES-256-GCM
+        assert_eq!(
+            secret.derive_key(b"aes-256-gcm", 32),
+            secret2.derive_key(b"aes-256-gcm", 32)
+        );
     }
 
     println!("Time elapsed in auth is: {:?}", durations / NLOOPS);
+    // with `--features metrics`, the breakdown by phase when run with `--timings`
+    #[cfg(feature = "metrics")]
+    if std::env::args().any(|arg| arg == "--timings") {
+        let timings = chadehoc_srp6::metrics::take_phase_timings();
+        println!("  keys A and B:   {:?}", timings.keygen / NLOOPS);
+        println!("  premaster S:    {:?}", timings.modpow / NLOOPS);
+        println!("  x and K:        {:?}", timings.kdf / NLOOPS);
+        println!("  proofs M, M2:   {:?}", timings.proof / NLOOPS);
+    }
 }
@@ -0,0 +1,15 @@
+//! Needs `cargo run --example 03_tcp_server` running.
+//! The address can be given as first argument, by default `127.0.0.1:4711`.
+mod common;
+
+use std::net::TcpStream;
+
+fn main() -> std::io::Result<()> {
+    let address = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "127.0.0.1:4711".to_owned());
+    let mut stream = TcpStream::connect(&address)?;
+    let secret = common::authenticate(&mut stream, common::USERNAME, common::PASSWORD)?;
+    println!("authenticated, K = {}", secret.strong_session_key());
+    Ok(())
+}
@@ -0,0 +1,32 @@
+//! Run it first, then `cargo run --example 03_tcp_client`.
+//! The address can be given as first argument, by default `127.0.0.1:4711`.
+mod common;
+
+use chadehoc_srp6::*;
+use std::net::TcpListener;
+
+fn main() -> std::io::Result<()> {
+    let address = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "127.0.0.1:4711".to_owned());
+    let constants = OpenConstants::<{ common::LEN }>::default();
+    // stands for the details stored when the user signed up
+    let user_details = common::User::generate_new_user_secrets(
+        &common::USERNAME.into(),
+        &common::PASSWORD.into(),
+        &constants,
+    )
+    .unwrap();
+
+    let listener = TcpListener::bind(&address)?;
+    println!("listening on {address}");
+    for stream in listener.incoming() {
+        let mut stream = stream?;
+        let peer = stream.peer_addr()?;
+        match common::serve(&mut stream, &user_details) {
+            Ok(secret) => println!("{peer}: authenticated, K = {}", secret.strong_session_key()),
+            Err(e) => println!("{peer}: authentication failed: {e}"),
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,54 @@
+use chadehoc_srp6::*;
+use std::time::{Duration, Instant};
+
+/// average duration of a full handshake with the given constants
+fn time_handshakes(constants: &OpenConstants<256>, user_details: &UserDetails) -> Duration {
+    let username = "Bob";
+    let password: &ClearTextPassword = "secret-password";
+    #[cfg(debug_assertions)]
+    const NLOOPS: u32 = 20;
+    #[cfg(not(debug_assertions))]
+    const NLOOPS: u32 = 200;
+    let start = Instant::now();
+    for _ in 0..NLOOPS {
+        let mut srp6_user = Srp6user2048::new();
+        let user_handshake = srp6_user.start_handshake(username, constants).unwrap();
+        let (server_handshake, srp6) = Srp6_2048::new()
+            .continue_handshake(user_details, &user_handshake.user_publickey, constants)
+            .unwrap();
+        let (proof, srp6_user) = srp6_user
+            .update_handshake(
+                &server_handshake,
+                constants,
+                &username.into(),
+                &password.into(),
+            )
+            .unwrap();
+        let hamk = srp6
+            .verify_proof(&proof)
+            .expect("invalid client proof")
+            .server_proof()
+            .clone();
+        srp6_user.verify_proof(&hamk).expect("invalid server proof");
+    }
+    start.elapsed() / NLOOPS
+}
+
+fn main() {
+    // `k` and `H(N) xor H(g)` are computed once, for SHA-1 as used by `Srp6_2048`
+    let cached = OpenConstants::<256>::default();
+    // cached for another hash function, so they are computed at every handshake
+    let uncached = OpenConstants::<256>::default().cache_for::<Sha256>();
+    let user_details =
+        Srp6user2048::generate_new_user_secrets(&"Bob".into(), &"secret-password".into(), &cached)
+            .unwrap();
+
+    println!(
+        "handshake with cached k: {:?}",
+        time_handshakes(&cached, &user_details)
+    );
+    println!(
+        "handshake without cache: {:?}",
+        time_handshakes(&uncached, &user_details)
+    );
+}
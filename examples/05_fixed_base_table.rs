@@ -0,0 +1,50 @@
+use chadehoc_srp6::*;
+use std::time::{Duration, Instant};
+
+#[cfg(debug_assertions)]
+const NLOOPS: u32 = 20;
+#[cfg(not(debug_assertions))]
+const NLOOPS: u32 = 200;
+
+/// average duration of `generate_new_user_secrets` with the given constants
+fn time_new_users(constants: &OpenConstants<256>) -> Duration {
+    let start = Instant::now();
+    for _ in 0..NLOOPS {
+        let _ = Srp6user2048::generate_new_user_secrets(
+            &"Bob".into(),
+            &"secret-password".into(),
+            constants,
+        )
+        .unwrap();
+    }
+    start.elapsed() / NLOOPS
+}
+
+/// average duration of `start_handshake` (computing `A`) with the given constants
+fn time_user_handshakes(constants: &OpenConstants<256>) -> Duration {
+    let start = Instant::now();
+    for _ in 0..NLOOPS {
+        let mut srp6_user = Srp6user2048::new();
+        srp6_user.start_handshake("Bob", constants).unwrap();
+    }
+    start.elapsed() / NLOOPS
+}
+
+fn main() {
+    let plain = OpenConstants::<256>::default();
+    // `g^e` uses a precomputed table, a few MiB for a 2048 bits group
+    let start = Instant::now();
+    let with_table = OpenConstants::<256>::default().with_fixed_base_table();
+    println!("table built in {:?}", start.elapsed());
+
+    println!(
+        "new user secrets: {:?} without table, {:?} with table",
+        time_new_users(&plain),
+        time_new_users(&with_table)
+    );
+    println!(
+        "user handshake:   {:?} without table, {:?} with table",
+        time_user_handshakes(&plain),
+        time_user_handshakes(&with_table)
+    );
+}
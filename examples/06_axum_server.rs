@@ -0,0 +1,46 @@
+//! The login endpoints of `integrations::axum` under `/login`, for the user
+//! of `common` (run with `--features axum`).
+//! The address can be given as first argument, by default `127.0.0.1:4712`.
+//!
+//! A client posts its `UserHandshake` as json to `/login/handshake`, then its
+//! proof to `/login/proof` with the session id in the `x-srp-session-id` header.
+mod common;
+
+use chadehoc_srp6::integrations::axum::{router, SrpServerState, VerifierStore};
+use chadehoc_srp6::*;
+
+/// stands for the database of the application, with the single user of `common`
+struct SingleUser(UserDetails);
+
+impl VerifierStore for SingleUser {
+    fn user_details(&self, username: UsernameRef) -> Option<UserDetails> {
+        (username == self.0.username).then(|| self.0.clone())
+    }
+
+    fn logged_in(&self, username: UsernameRef, secret: SessionSecret) {
+        println!(
+            "{username}: authenticated, K = {}",
+            secret.strong_session_key()
+        );
+    }
+}
+
+#[tokio::main]
+async fn main() -> std::io::Result<()> {
+    let address = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "127.0.0.1:4712".to_owned());
+    let constants = OpenConstants::<{ common::LEN }>::default();
+    let user_details = common::User::generate_new_user_secrets(
+        &common::USERNAME.into(),
+        &common::PASSWORD.into(),
+        &constants,
+    )
+    .unwrap();
+    let state = SrpServerState::<{ common::LEN }, _>::new(constants, SingleUser(user_details));
+
+    let app = axum::Router::new().nest("/login", router(state));
+    let listener = tokio::net::TcpListener::bind(&address).await?;
+    println!("listening on {address}");
+    axum::serve(listener, app).await
+}
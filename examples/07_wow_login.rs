@@ -0,0 +1,86 @@
+//! A logon of the World of Warcraft SRP6 against the stored account of an
+//! emulator (run with `--features wow`).
+//!
+//! The emulators keep the salt and the verifier as 32 bytes little endian,
+//! the numbers of the logon challenge and of the logon proof go on the wire
+//! little endian as well.
+use chadehoc_srp6::wow::{ReconnectChallenge, ReconnectProof, Srp6WowClient, Srp6WowServer};
+use chadehoc_srp6::*;
+
+/// the account `TEST` with the password `TEST`, as an emulator would store it
+const ACCOUNT: (&str, &str, &str) = (
+    "TEST",
+    "AD0A5C0E5E4D8E6C5B4A39281706F5E4D3C2B1A0998877665544332211FFEE0D",
+    "763DD2B08E98E71FE692BC6F6444295F6AD45262CE19B7BD9164BD38A8780576",
+);
+
+#[allow(non_snake_case)]
+fn main() {
+    let constants = OpenConstants::<32>::wow();
+    // server: the account as read from the database
+    let (username, salt, verifier) = ACCOUNT;
+    let user_details = UserDetails::from_parts(
+        username,
+        RawNumber::Hex(salt, ByteOrder::LittleEndian),
+        RawNumber::Hex(verifier, ByteOrder::LittleEndian),
+        &constants,
+    )
+    .unwrap();
+
+    // client: logon challenge with the account name, the case does not matter
+    let client = Srp6WowClient::with_credentials("test", "test");
+    println!("logon challenge from {}", client.username());
+
+    // server: `B` and the salt in the answer to the challenge
+    let (server_handshake, server) = Srp6WowServer::new().start_handshake(&user_details).unwrap();
+    let b_wire = server_handshake
+        .server_publickey
+        .to_array_pad_zero_le::<32>();
+    let salt_wire = server_handshake.salt.to_array_pad_zero_le::<32>();
+    println!("B    = {}", hex(&b_wire));
+    println!("salt = {}", hex(&salt_wire));
+
+    // client: `A` and `M1` in the logon proof
+    let received = ServerHandshake::new(
+        Salt::from_bytes_le(&salt_wire),
+        PublicKey::from_bytes_le(&b_wire),
+    );
+    let (A, M1, client) = client.update_handshake(&received).unwrap();
+    let a_wire = A.to_array_pad_zero_le::<32>();
+    let m1_wire = M1.to_vec();
+    println!("A    = {}", hex(&a_wire));
+    println!("M1   = {}", hex(&m1_wire));
+
+    // server: checks the proof, answers with `M2`
+    let (M2, server_K) = server
+        .verify_proof(
+            &PublicKey::from_bytes_le(&a_wire),
+            &Proof::from_bytes_be(&m1_wire),
+        )
+        .unwrap();
+    println!("M2   = {}", hex(&M2.to_vec()));
+
+    // client: checks the server's proof, both now share the 40 bytes of `K`
+    let client_K = client
+        .verify_proof(&StrongProof::from_bytes_be(&M2.to_vec()))
+        .expect("the server knows the verifier");
+    assert_eq!(client_K, server_K);
+    // the wire order of the client is little endian
+    let mut K = client_K.to_bytes();
+    K.reverse();
+    println!("K    = {}", hex(&K));
+
+    // later, the client reconnects with `K` instead of a new logon
+    let challenge = ReconnectChallenge::new();
+    println!("reconnect challenge = {}", hex(challenge.nonce()));
+    let proof = ReconnectProof::new("test", challenge.nonce(), &client_K);
+    println!("R1   = {}", hex(&proof.client_nonce));
+    println!("R2   = {}", hex(&proof.proof));
+    challenge
+        .verify_proof(username, &server_K, &proof)
+        .expect("the client has the session key");
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02X}")).collect()
+}
@@ -0,0 +1,105 @@
+//! Shared by the TCP examples (and their integration test):
+//! a minimal framing of the messages, and both sides of the authentication.
+//!
+//! Every message is sent in its hex form (e.g. `username:A`), as a frame:
+//! the length of the payload as a big endian `u32`, then the payload.
+#![allow(dead_code)]
+
+use chadehoc_srp6::*;
+use std::io::{self, Read, Write};
+
+/// the user known by the server, in a real application it signed up before
+pub const USERNAME: &str = "Bob";
+pub const PASSWORD: &ClearTextPassword = "secret-password";
+
+/// the key length in bytes, here the 2048 bit group
+pub const LEN: usize = 256;
+pub type Host = Srp6_2048;
+pub type User = Srp6user2048;
+/// the hash function of [`Host`] and [`User`], needed to parse the proofs
+pub type Hash = Sha1;
+
+/// bigger frames are rejected, the messages are much smaller
+const MAX_FRAME_LENGTH: usize = 4096;
+
+/// writes `payload` as one frame
+pub fn write_frame(stream: &mut impl Write, payload: &str) -> io::Result<()> {
+    let len = u32::try_from(payload.len())
+        .ok()
+        .filter(|&len| len as usize <= MAX_FRAME_LENGTH)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "frame too long"))?;
+    stream.write_all(&len.to_be_bytes())?;
+    stream.write_all(payload.as_bytes())?;
+    stream.flush()
+}
+
+/// reads one frame, as written by [`write_frame`]
+pub fn read_frame(stream: &mut impl Read) -> io::Result<String> {
+    let mut len = [0u8; 4];
+    stream.read_exact(&mut len)?;
+    let len = u32::from_be_bytes(len) as usize;
+    if len > MAX_FRAME_LENGTH {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "frame too long"));
+    }
+    let mut payload = vec![0u8; len];
+    stream.read_exact(&mut payload)?;
+    String::from_utf8(payload).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+fn protocol_error(e: Srp6Error) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
+
+/// server side of one authentication, with the stored details of the user
+pub fn serve(
+    stream: &mut (impl Read + Write),
+    user_details: &UserDetails,
+) -> io::Result<SessionSecret> {
+    let constants = OpenConstants::<LEN>::default();
+    // the user starts
+    let user_handshake =
+        UserHandshake::from_hex::<LEN>(&read_frame(stream)?).map_err(protocol_error)?;
+    if user_handshake.username != user_details.username {
+        return Err(io::Error::new(io::ErrorKind::NotFound, "unknown user"));
+    }
+    let (server_handshake, srp6) = Host::new()
+        .for_user(user_details)
+        .continue_handshake(&user_handshake.user_publickey, &constants)
+        .map_err(protocol_error)?;
+    write_frame(stream, &server_handshake.to_hex())?;
+    // the user proves that it knows the password
+    let proof = ProofMessage::from_hex::<Hash>(&read_frame(stream)?).map_err(protocol_error)?;
+    let outcome = srp6.verify_proof(&proof.proof).map_err(protocol_error)?;
+    write_frame(
+        stream,
+        &ProofMessage::from(outcome.server_proof().clone()).to_hex(),
+    )?;
+    Ok(outcome.into_session_secret())
+}
+
+/// user side of one authentication
+pub fn authenticate(
+    stream: &mut (impl Read + Write),
+    username: UsernameRef,
+    password: &ClearTextPassword,
+) -> io::Result<SessionSecret> {
+    let constants = OpenConstants::<LEN>::default();
+    let mut srp6_user = User::with_credentials(&username.into(), &password.into());
+    let user_handshake = srp6_user
+        .start_handshake(&constants)
+        .map_err(protocol_error)?;
+    write_frame(stream, &user_handshake.to_hex())?;
+    let server_handshake =
+        ServerHandshake::from_hex::<LEN>(&read_frame(stream)?).map_err(protocol_error)?;
+    let (proof, srp6_user) = srp6_user
+        .update_handshake(&server_handshake, &constants)
+        .map_err(protocol_error)?;
+    write_frame(stream, &ProofMessage::from(proof).to_hex())?;
+    // the server proves that it knows the verifier
+    let strong_proof =
+        ProofMessage::from_hex::<Hash>(&read_frame(stream)?).map_err(protocol_error)?;
+    srp6_user
+        .verify_proof(&strong_proof.strong_proof())
+        .map(ClientHandshakeOutcome::into_session_secret)
+        .map_err(|_| io::Error::new(io::ErrorKind::PermissionDenied, "invalid server proof"))
+}
@@ -0,0 +1,11 @@
+//! the hex parsing of the numbers never panics, and reads back its own output
+#![no_main]
+
+use chadehoc_srp6::*;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|hex: &str| {
+    if let Ok(x) = PublicKey::from_hex_str_be(hex) {
+        assert_eq!(PublicKey::from_hex_str_be(&String::from(&x)).unwrap(), x);
+    }
+});
@@ -0,0 +1,23 @@
+//! any message from the server must give an error to the user, never a panic
+#![no_main]
+
+use chadehoc_srp6::*;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(json) = core::str::from_utf8(data) else {
+        return;
+    };
+    let Ok(server_handshake) = serde_json::from_str::<ServerHandshake>(json) else {
+        return;
+    };
+    let constants = OpenConstants::<8>::tiny();
+    let mut user = Srp6User::<8>::default();
+    user.start_handshake("alice", &constants).unwrap();
+    let _ = user.update_handshake(
+        &server_handshake,
+        &constants,
+        &"alice".into(),
+        &"password123".into(),
+    );
+});
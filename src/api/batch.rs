@@ -0,0 +1,234 @@
+//! The verifiers of many users at once, on a pool of threads (feature `rayon`)
+//!
+//! Each verifier is an independent modpow `g^x`, a bulk import of users spreads
+//! them over `parallelism` threads. The salts are drawn from the generator of
+//! each thread, which is seeded apart.
+
+use crate::hash::Digest;
+use crate::primitives::*;
+use crate::{Result, Srp6};
+
+use rayon::prelude::*;
+use rayon::ThreadPool;
+
+/// how many users each thread is given at once by
+/// [`Srp6::generate_user_details_streaming`]
+const STREAMING_CHUNK_PER_THREAD: usize = 64;
+
+/// a pool of `parallelism` threads, as many as the CPUs for 0
+fn thread_pool(parallelism: usize) -> ThreadPool {
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(parallelism)
+        .build()
+        .expect("the threads of the batch should start")
+}
+
+#[allow(non_snake_case)]
+impl<const LEN: usize, D: Digest> Srp6<LEN, D> {
+    /// same as [`Srp6::generate_new_user_secrets`] for each `(username, password)`
+    /// of `entries`, on `parallelism` threads (as many as the CPUs for 0)
+    ///
+    /// the details are in the order of `entries`; fails with the error of the
+    /// first invalid entry, e.g. an empty password.
+    ///
+    /// # Panics
+    ///
+    /// if the threads cannot be started
+    pub fn generate_user_details_batch(
+        entries: &[(UsernameRef, &ClearTextPassword)],
+        constants: &OpenConstants<LEN>,
+        parallelism: usize,
+    ) -> Result<Vec<UserDetails>> {
+        thread_pool(parallelism).install(|| {
+            entries
+                .par_iter()
+                .map(|(I, p)| {
+                    Self::user_details_with_salt_length(I, p, constants, DEFAULT_SALT_LENGTH)
+                })
+                .collect()
+        })
+    }
+
+    /// same as [`Srp6::generate_user_details_with_salt`] for each
+    /// `(username, password, salt)` of `entries`, on `parallelism` threads
+    ///
+    /// e.g. for users migrated with their salts; fails with the error of the
+    /// first invalid entry.
+    ///
+    /// # Panics
+    ///
+    /// if the threads cannot be started
+    pub fn generate_user_details_batch_with_salts(
+        entries: &[(UsernameRef, &ClearTextPassword, &Salt)],
+        constants: &OpenConstants<LEN>,
+        parallelism: usize,
+    ) -> Result<Vec<UserDetails>> {
+        thread_pool(parallelism).install(|| {
+            entries
+                .par_iter()
+                .map(|(I, p, s)| Self::user_details_with_salt(I, p, s, constants))
+                .collect()
+        })
+    }
+
+    /// same as [`Srp6::generate_user_details_batch`] for the users of an iterator,
+    /// e.g. the rows of a database: `on_details` is called with the details of
+    /// each one, in the order of `entries`, on the calling thread
+    ///
+    /// only a few users per thread are held at once, whatever the number of users.
+    /// Stops with the error of the first invalid entry, the users of its chunk
+    /// are not given to `on_details`.
+    ///
+    /// # Panics
+    ///
+    /// if the threads cannot be started
+    pub fn generate_user_details_streaming<U, P>(
+        entries: impl IntoIterator<Item = (U, P)>,
+        constants: &OpenConstants<LEN>,
+        parallelism: usize,
+        mut on_details: impl FnMut(UserDetails),
+    ) -> Result<()>
+    where
+        U: AsRef<str> + Send,
+        P: AsRef<str> + Send,
+    {
+        let pool = thread_pool(parallelism);
+        let chunk_size = pool.current_num_threads() * STREAMING_CHUNK_PER_THREAD;
+        let mut entries = entries.into_iter().peekable();
+        let mut chunk = Vec::with_capacity(chunk_size);
+        while entries.peek().is_some() {
+            chunk.extend(entries.by_ref().take(chunk_size));
+            let details: Vec<UserDetails> = pool.install(|| {
+                chunk
+                    .par_drain(..)
+                    .map(|(I, p)| {
+                        Self::user_details_with_salt_length(
+                            I.as_ref(),
+                            p.as_ref(),
+                            constants,
+                            DEFAULT_SALT_LENGTH,
+                        )
+                    })
+                    .collect::<Result<_>>()
+            })?;
+            details.into_iter().for_each(&mut on_details);
+        }
+        Ok(())
+    }
+}
+
+/// creates the [`UserDetails`] of many users with random salts, using the
+/// default hash function, see [`Srp6::generate_user_details_batch`]
+pub fn generate_user_details_batch<const LEN: usize>(
+    entries: &[(UsernameRef, &ClearTextPassword)],
+    constants: &OpenConstants<LEN>,
+    parallelism: usize,
+) -> Result<Vec<UserDetails>> {
+    Srp6::<LEN>::generate_user_details_batch(entries, constants, parallelism)
+}
+
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+    use super::*;
+    use crate::hash::Sha256;
+    use crate::Srp6Error;
+
+    const USERS: [(UsernameRef, &ClearTextPassword); 5] = [
+        ("alice", "password123"),
+        ("bob", "hunter2"),
+        ("carol", "correct horse battery staple"),
+        ("dave", "dave"),
+        ("eve", "s3cr3t"),
+    ];
+
+    #[test]
+    fn should_give_the_details_of_the_single_user_api() {
+        let constants = OpenConstants::<128>::default();
+        let salts: Vec<Salt> = (1..=USERS.len() as u32)
+            .map(|i| Salt::from(i * 0x0101_0101))
+            .collect();
+        let entries: Vec<_> = USERS
+            .iter()
+            .zip(&salts)
+            .map(|((I, p), s)| (*I, *p, s))
+            .collect();
+
+        let batch =
+            Srp6::<128, Sha256>::generate_user_details_batch_with_salts(&entries, &constants, 3)
+                .unwrap();
+        for ((I, p, s), details) in entries.iter().zip(&batch) {
+            assert_eq!(
+                details,
+                &Srp6::<128, Sha256>::generate_user_details_with_salt(
+                    &(*I).into(),
+                    &(*p).into(),
+                    s,
+                    &constants
+                )
+                .unwrap()
+            );
+        }
+
+        let invalid = [("alice", "password123", &Salt::default())];
+        assert!(Srp6::<128, Sha256>::generate_user_details_batch_with_salts(
+            &invalid, &constants, 2
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn should_give_the_same_details_on_more_threads() {
+        let constants = OpenConstants::<128>::default();
+        let verify = |details: &[UserDetails]| {
+            assert_eq!(details.len(), USERS.len());
+            for ((I, p), details) in USERS.iter().zip(details) {
+                assert_eq!(details.username, *I);
+                assert_eq!(
+                    details.verifier,
+                    Srp6::<128>::derive_verifier(
+                        &(*I).into(),
+                        &(*p).into(),
+                        &details.salt,
+                        &constants
+                    )
+                    .unwrap()
+                );
+            }
+        };
+        verify(&generate_user_details_batch(&USERS, &constants, 1).unwrap());
+        verify(&generate_user_details_batch(&USERS, &constants, 4).unwrap());
+
+        let mut streamed = Vec::new();
+        Srp6::<128>::generate_user_details_streaming(
+            USERS.iter().copied().cycle().take(USERS.len() * 20),
+            &constants,
+            2,
+            |details| streamed.push(details),
+        )
+        .unwrap();
+        assert_eq!(streamed.len(), USERS.len() * 20);
+        for chunk in streamed.chunks(USERS.len()) {
+            verify(chunk);
+        }
+    }
+
+    #[test]
+    fn should_fail_on_an_empty_password() {
+        let constants = OpenConstants::<128>::default();
+        let entries = [("alice", "password123"), ("bob", "")];
+        assert_eq!(
+            generate_user_details_batch(&entries, &constants, 2),
+            Err(Srp6Error::EmptyPassword)
+        );
+
+        let mut streamed = Vec::new();
+        assert_eq!(
+            Srp6::<128>::generate_user_details_streaming(entries, &constants, 2, |details| {
+                streamed.push(details)
+            }),
+            Err(Srp6Error::EmptyPassword)
+        );
+        assert!(streamed.is_empty());
+    }
+}
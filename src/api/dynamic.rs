@@ -0,0 +1,403 @@
+use super::host::{calculate_host_keys, verify_proof_M};
+use super::user::{calculate_user_keys, verify_strong_proof_M2};
+use crate::compat::{CompatProfile, ProofScheme};
+use crate::hash::{Digest, HashFunc};
+use crate::primitives::*;
+use crate::{Result, Srp6Error};
+
+use core::fmt;
+use core::marker::PhantomData;
+use rand::{CryptoRng, RngCore};
+#[cfg(feature = "zeroize")]
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+/// Same as [`Srp6`](crate::Srp6), but the group is chosen at runtime, see [`GroupParams`]
+///
+/// `D` is the hash function, SHA-1 by default
+#[allow(non_snake_case)]
+pub struct DynSrp6<D: Digest = HashFunc> {
+    pub A: PublicKey,
+    pub B: PublicKey,
+    b: PrivateKey,
+    pub U: PublicKey,
+    S: PrivateKey,
+    K: StrongSessionKey,
+    M: Proof,
+    len: usize,
+    session_id: SessionId,
+    d: PhantomData<D>,
+}
+
+/// the private key `b`, `S`, `K` and the expected proof `M` are redacted
+impl<D: Digest> fmt::Debug for DynSrp6<D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DynSrp6")
+            .field("A", &self.A)
+            .field("B", &self.B)
+            .field("b", &Secret::sized("PrivateKey", &self.b, self.len))
+            .field("U", &self.U)
+            .field("S", &Secret::sized("SessionKey", &self.S, self.len))
+            .field("K", &Secret::new("StrongSessionKey", &self.K))
+            .field("M", &Secret::new("Proof", &self.M))
+            .field("len", &self.len)
+            .field("session_id", &self.session_id)
+            .finish()
+    }
+}
+
+/// the private key `a`, `S` and `K` are redacted
+impl<D: Digest> fmt::Debug for DynSrp6User<D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DynSrp6User")
+            .field("A", &self.A)
+            .field("B", &self.B)
+            .field("a", &Secret::sized("PrivateKey", &self.a, self.len))
+            .field("U", &self.U)
+            .field("salt", &self.salt)
+            .field("M", &self.M)
+            .field("S", &Secret::sized("SessionKey", &self.S, self.len))
+            .field("K", &Secret::new("StrongSessionKey", &self.K))
+            .field("len", &self.len)
+            .finish()
+    }
+}
+
+impl<D: Digest> Default for DynSrp6<D> {
+    fn default() -> Self {
+        Self {
+            A: Default::default(),
+            B: Default::default(),
+            b: Default::default(),
+            U: Default::default(),
+            S: Default::default(),
+            K: Default::default(),
+            M: Default::default(),
+            len: 0,
+            session_id: SessionId::default(),
+            d: PhantomData,
+        }
+    }
+}
+
+impl<D: Digest> DynSrp6<D> {
+    #[cfg(feature = "std")]
+    pub fn continue_handshake(
+        &mut self,
+        user_details: &UserDetails,
+        user_publickey: &impl UserPublicKey,
+        params: &GroupParams,
+    ) -> Result<ServerHandshake> {
+        self.session_id = generate_session_id();
+        self.continue_handshake_with_b(user_details, user_publickey, params, || {
+            generate_private_key_b_in(params.len, &params.modulus)
+        })
+    }
+
+    /// same as [`DynSrp6::continue_handshake`], the private key `b` comes from `rng`
+    pub fn continue_handshake_with_rng<R: RngCore + CryptoRng + ?Sized>(
+        &mut self,
+        user_details: &UserDetails,
+        user_publickey: &impl UserPublicKey,
+        params: &GroupParams,
+        rng: &mut R,
+    ) -> Result<ServerHandshake> {
+        self.session_id = generate_session_id_with(rng);
+        self.continue_handshake_with_b(user_details, user_publickey, params, || {
+            generate_private_key_in(rng, params.len, &params.modulus)
+        })
+    }
+
+    /// draws `b` with `next_b` until `B` is not trivial
+    fn continue_handshake_with_b(
+        &mut self,
+        user_details: &UserDetails,
+        user_publickey: &impl UserPublicKey,
+        params: &GroupParams,
+        mut next_b: impl FnMut() -> Result<PrivateKey>,
+    ) -> Result<ServerHandshake> {
+        let user_publickey = checked_user_publickey(params.len, user_publickey)?;
+        let hashes = params.group_hashes::<D>()?;
+        let (b, keys) = loop {
+            let b = next_b()?;
+            let keys = calculate_host_keys::<D>(
+                params.len,
+                &params.modulus,
+                &params.generator,
+                None,
+                user_details,
+                &hashes,
+                user_publickey,
+                &b,
+                CompatProfile::default(),
+                ProofScheme::default(),
+            )?;
+            if !is_trivial_public_key(&params.modulus, &keys.B) {
+                break (b, keys);
+            }
+        };
+
+        self.b = b;
+        self.A = user_publickey.clone();
+        self.B = keys.B.clone();
+        self.U = keys.U;
+        self.S = keys.S;
+        self.K = keys.K;
+        self.M = keys.M;
+        self.len = params.len;
+
+        Ok(ServerHandshake {
+            salt: user_details.salt.clone(),
+            server_publickey: keys.B,
+            session_id: self.session_id,
+            group_bits: group_bits(params.len),
+        })
+    }
+
+    /// the id sent in [`ServerHandshake::session_id`]
+    pub fn session_id(&self) -> &SessionId {
+        &self.session_id
+    }
+
+    /// on success, returns the strong proof `M2` and the [`SessionSecret`]
+    pub fn verify_proof(mut self, users_proof: &Proof) -> Result<(StrongProof, SessionSecret)> {
+        let hamk = verify_proof_M::<D>(
+            self.len,
+            &self.A,
+            &self.M,
+            &self.S,
+            &self.K,
+            CompatProfile::default(),
+            ProofScheme::default(),
+            users_proof,
+        )?;
+        Ok((
+            hamk,
+            SessionSecret::new(core::mem::take(&mut self.S), core::mem::take(&mut self.K)),
+        ))
+    }
+}
+
+/// Same as [`Srp6User`](crate::Srp6User), but the group is chosen at runtime, see [`GroupParams`]
+#[allow(non_snake_case)]
+#[derive(Default)]
+pub struct DynSrp6User<D: Digest = HashFunc> {
+    pub A: PublicKey,
+    pub B: PublicKey,
+    a: PrivateKey,
+    pub U: PublicKey,
+    pub salt: Salt,
+    pub M: Proof,
+    S: PrivateKey,
+    K: StrongSessionKey,
+    len: usize,
+    d: PhantomData<D>,
+}
+
+impl<D: Digest> DynSrp6User<D> {
+    /// creates a new [`Salt`] `s` and [`PasswordVerifier`] `v` for a new user
+    #[cfg(feature = "std")]
+    #[allow(non_snake_case)]
+    pub fn generate_new_user_secrets(
+        I: UsernameRef,
+        p: &ClearTextPassword,
+        params: &GroupParams,
+    ) -> Result<UserDetails> {
+        calculate_user_details::<D>(
+            &params.modulus,
+            &params.generator,
+            None,
+            I,
+            p,
+            generate_salt(DEFAULT_SALT_LENGTH),
+            IdentityMode::default(),
+            CompatProfile::default(),
+            params.variant,
+            Some(group_fingerprint(
+                params.len,
+                &params.modulus,
+                &params.generator,
+            )),
+        )
+    }
+
+    #[cfg(feature = "std")]
+    pub fn start_handshake(
+        &mut self,
+        username: UsernameRef,
+        params: &GroupParams,
+    ) -> Result<UserHandshake> {
+        self.start_handshake_with_a(username, params, || {
+            generate_private_key_a_in(params.len, &params.modulus)
+        })
+    }
+
+    /// same as [`DynSrp6User::start_handshake`], the private key `a` comes from `rng`
+    pub fn start_handshake_with_rng<R: RngCore + CryptoRng + ?Sized>(
+        &mut self,
+        username: UsernameRef,
+        params: &GroupParams,
+        rng: &mut R,
+    ) -> Result<UserHandshake> {
+        self.start_handshake_with_a(username, params, || {
+            generate_private_key_in(rng, params.len, &params.modulus)
+        })
+    }
+
+    /// draws `a` with `next_a` until `A` is not trivial
+    #[allow(non_snake_case)]
+    fn start_handshake_with_a(
+        &mut self,
+        username: UsernameRef,
+        params: &GroupParams,
+        mut next_a: impl FnMut() -> Result<PrivateKey>,
+    ) -> Result<UserHandshake> {
+        let (a, A) = timed_phase!(
+            keygen,
+            loop {
+                let a = next_a()?;
+                debug_secret!("a = {:?}", &a);
+                let A = calculate_pubkey_A(&params.modulus, &params.generator, None, &a);
+                if !is_trivial_public_key(&params.modulus, &A) {
+                    break (a, A);
+                }
+            }
+        );
+        self.a = a;
+        self.A = A.clone();
+        self.len = params.len;
+
+        Ok(UserHandshake {
+            username: username.into(),
+            user_publickey: A,
+            group_bits: group_bits(params.len),
+        })
+    }
+
+    /// `params` must be the ones given to [`DynSrp6User::start_handshake`]
+    #[allow(non_snake_case)]
+    pub fn update_handshake(
+        &mut self,
+        server_handshake: &ServerHandshake,
+        params: &GroupParams,
+        I: UsernameRef,
+        p: &ClearTextPassword,
+    ) -> Result<Proof> {
+        step_span!(
+            "update_handshake",
+            params.len,
+            username_hash = crate::span::username_hash::<D>(I)
+        );
+        if params.len != self.len {
+            return Err(Srp6Error::KeyLengthMismatch {
+                given: params.len,
+                expected: self.len,
+            });
+        }
+        check_group_bits(group_bits(params.len), server_handshake.group_bits)?;
+        check_key_length_in_group(
+            params.len,
+            server_handshake.group_bits,
+            &server_handshake.server_publickey,
+        )?;
+        check_public_key(
+            params.len,
+            &params.modulus,
+            &server_handshake.server_publickey,
+        )?;
+        let (I, p) = normalized_credentials(I, p, cfg!(feature = "normalization"))?;
+        self.B = server_handshake.server_publickey.clone();
+        self.salt = server_handshake.salt.clone();
+
+        let x = timed_phase!(
+            kdf,
+            private_key_x::<D>(&I, &p, &self.salt, CompatProfile::default(), params.variant)
+        );
+        let keys = calculate_user_keys::<D>(
+            params.len,
+            &params.modulus,
+            &params.generator,
+            &I,
+            &self.salt,
+            &self.A,
+            &self.B,
+            &params.group_hashes::<D>()?,
+            &self.a,
+            &x,
+            CompatProfile::default(),
+            ProofScheme::default(),
+        )?;
+        self.U = keys.U;
+        self.S = keys.S;
+        self.K = keys.K;
+        self.M = keys.M;
+        Ok(self.M.clone())
+    }
+
+    /// on success, returns the [`SessionSecret`]
+    pub fn verify_proof(mut self, servers_proof: &StrongProof) -> Option<SessionSecret> {
+        if verify_strong_proof_M2::<D>(
+            self.len,
+            &self.A,
+            &self.M,
+            &self.S,
+            &self.K,
+            CompatProfile::default(),
+            ProofScheme::default(),
+            servers_proof,
+        ) {
+            Some(SessionSecret::new(
+                core::mem::take(&mut self.S),
+                core::mem::take(&mut self.K),
+            ))
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl<D: Digest> Zeroize for DynSrp6<D> {
+    fn zeroize(&mut self) {
+        self.A.zeroize();
+        self.B.zeroize();
+        self.b.zeroize();
+        self.U.zeroize();
+        self.S.zeroize();
+        self.K.zeroize();
+        self.M.zeroize();
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl<D: Digest> Drop for DynSrp6<D> {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl<D: Digest> ZeroizeOnDrop for DynSrp6<D> {}
+
+#[cfg(feature = "zeroize")]
+impl<D: Digest> Zeroize for DynSrp6User<D> {
+    fn zeroize(&mut self) {
+        self.A.zeroize();
+        self.B.zeroize();
+        self.a.zeroize();
+        self.U.zeroize();
+        self.salt.zeroize();
+        self.M.zeroize();
+        self.S.zeroize();
+        self.K.zeroize();
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl<D: Digest> Drop for DynSrp6User<D> {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl<D: Digest> ZeroizeOnDrop for DynSrp6User<D> {}
@@ -1,86 +1,1458 @@
-// use super::user::{HandshakeProof, StrongProofVerifier};
+use crate::big_number::{FixedBasePowTable, Zero};
+use crate::compat::{CompatProfile, ProofScheme};
+use crate::hash::{Digest, HashFunc, Sha256};
 use crate::primitives::*;
+#[cfg(feature = "transcript")]
+use crate::transcript::Transcript;
 use crate::Result;
 use crate::Srp6Error;
 
-use log::debug;
+use alloc::sync::Arc;
+use core::fmt;
+use core::marker::PhantomData;
+#[cfg(feature = "std")]
+use hmac::{Hmac, Mac};
+use rand::{CryptoRng, RngCore};
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "zeroize")]
+use zeroize::{Zeroize, ZeroizeOnDrop};
 
 /// Main interaction point for the server
+///
+/// A handshake starts from `Srp6::default()`: [`Srp6::continue_handshake`]
+/// consumes it and returns a [`Srp6WaitingForProof`], which only gives the
+/// strong proof `M2` and the session key once the user's proof is verified.
+///
+/// It holds no ephemeral state: each handshake draws a new private key `b`, a
+/// clone can be kept to retry after an aborted handshake.
+///
+/// `D` is the hash function, SHA-1 by default
+#[derive(Debug)]
+pub struct Srp6<const LEN: usize, D: Digest = HashFunc> {
+    profile: CompatProfile,
+    scheme: ProofScheme,
+    d: PhantomData<D>,
+}
+
+impl<const LEN: usize, D: Digest> Clone for Srp6<LEN, D> {
+    fn clone(&self) -> Self {
+        Self {
+            profile: self.profile,
+            scheme: self.scheme,
+            d: PhantomData,
+        }
+    }
+}
+
+impl<const LEN: usize, D: Digest> Default for Srp6<LEN, D> {
+    fn default() -> Self {
+        Self {
+            profile: CompatProfile::default(),
+            scheme: ProofScheme::default(),
+            d: PhantomData,
+        }
+    }
+}
+
+/// A [`Srp6`] bound to the stored [`UserDetails`] of one user, see [`Srp6::for_user`]
+///
+/// `D` is the hash function, SHA-1 by default
+#[derive(Debug)]
+pub struct Srp6ForUser<'a, const LEN: usize, D: Digest = HashFunc> {
+    srp6: Srp6<LEN, D>,
+    user_details: &'a UserDetails,
+}
+
+impl<const LEN: usize, D: Digest> Clone for Srp6ForUser<'_, LEN, D> {
+    fn clone(&self) -> Self {
+        Self {
+            srp6: self.srp6.clone(),
+            user_details: self.user_details,
+        }
+    }
+}
+
+/// The server after [`Srp6::continue_handshake`], waiting for the user's proof
+///
+/// `D` is the hash function, SHA-1 by default
 #[allow(non_snake_case)]
-#[derive(Debug, Default)]
-pub struct Srp6<const LEN: usize> {
+pub struct Srp6WaitingForProof<const LEN: usize, D: Digest = HashFunc> {
+    #[cfg(feature = "pub-fields")]
     pub A: PublicKey,
+    #[cfg(not(feature = "pub-fields"))]
+    A: PublicKey,
+    #[cfg(feature = "pub-fields")]
     pub B: PublicKey,
-    b: PrivateKey,
+    #[cfg(not(feature = "pub-fields"))]
+    B: PublicKey,
+    #[cfg(feature = "pub-fields")]
     pub U: PublicKey,
+    #[cfg(not(feature = "pub-fields"))]
+    U: PublicKey,
     S: PrivateKey,
-    K: SessionKey,
+    K: StrongSessionKey,
     M: Proof,
+    salt_length: usize,
+    session_id: SessionId,
+    profile: CompatProfile,
+    scheme: ProofScheme,
+    #[cfg(feature = "transcript")]
+    transcript: Transcript,
+    d: PhantomData<D>,
+}
+
+/// The server after [`Srp6::prepare`], with its public key `B` calculated
+/// ahead of the user's public key `A`
+///
+/// it is bound to the [`UserDetails`] it was prepared for, and
+/// [`PreparedHandshake::complete`] consumes it, so that `b` serves a single handshake.
+///
+/// `D` is the hash function, SHA-1 by default
+#[allow(non_snake_case)]
+pub struct PreparedHandshake<const LEN: usize, D: Digest = HashFunc> {
+    user_details: UserDetails,
+    module: Arc<PrimeModulus>,
+    hashes: GroupHashes,
+    b: PrivateKey,
+    B: PublicKey,
+    session_id: SessionId,
+    profile: CompatProfile,
+    scheme: ProofScheme,
+    d: PhantomData<D>,
+}
+
+/// the private key `b` is redacted, the group and the hashes are left out
+impl<const LEN: usize, D: Digest> fmt::Debug for PreparedHandshake<LEN, D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PreparedHandshake")
+            .field("username", &self.user_details.username)
+            .field("b", &Secret::sized("PrivateKey", &self.b, LEN))
+            .field("B", &self.B)
+            .field("session_id", &self.session_id)
+            .field("profile", &self.profile)
+            .field("scheme", &self.scheme)
+            .finish_non_exhaustive()
+    }
 }
 
-impl<const LEN: usize> Srp6<LEN> {
+/// the premaster secret `S`, the session key `K` and the expected proof `M`
+/// are redacted
+impl<const LEN: usize, D: Digest> fmt::Debug for Srp6WaitingForProof<LEN, D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Srp6WaitingForProof")
+            .field("A", &self.A)
+            .field("B", &self.B)
+            .field("U", &self.U)
+            .field("S", &Secret::sized("SessionKey", &self.S, LEN))
+            .field("K", &Secret::new("StrongSessionKey", &self.K))
+            .field("M", &Secret::new("Proof", &self.M))
+            .field("salt_length", &self.salt_length)
+            .field("session_id", &self.session_id)
+            .field("profile", &self.profile)
+            .field("scheme", &self.scheme)
+            .finish()
+    }
+}
+
+/// the premaster secret `S`, the session key `K` and the expected proof `M`
+/// are redacted
+impl<const LEN: usize, D: Digest> fmt::Debug for HostHandshakeState<LEN, D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HostHandshakeState")
+            .field("A", &self.A)
+            .field("B", &self.B)
+            .field("S", &Secret::sized("SessionKey", &self.S, LEN))
+            .field("K", &Secret::new("StrongSessionKey", &self.K))
+            .field("M", &Secret::new("Proof", &self.M))
+            .field("session_id", &self.session_id)
+            .field("profile", &self.profile)
+            .field("scheme", &self.scheme)
+            .finish()
+    }
+}
+
+/// What went into the proof `M` on the host side, when the user's proof did not
+/// match, see [`Srp6WaitingForProof::verify_proof_diagnose`]
+///
+/// `K` and `M` are not included. `A` is never reduced modulo `N`, as values
+/// not lower than `N` are already rejected by the handshake.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ProofDiagnostics {
+    /// size of the group in bits
+    pub group_bits: usize,
+    /// length in bytes of the salt `s`
+    pub salt_length: usize,
+    /// length in bytes of `A`, without padding
+    pub user_publickey_length: usize,
+    /// length in bytes of `B`, without padding
+    pub server_publickey_length: usize,
+    /// length in bytes of `K`, without padding
+    pub strong_session_key_length: usize,
+    pub user_publickey: PublicKey,
+    pub server_publickey: PublicKey,
+    #[serde(skip)]
+    users_proof: Proof,
+}
+
+impl ProofDiagnostics {
+    /// the rejected proof as sent by the user, i.e. untrusted
+    pub fn users_proof(&self) -> &Proof {
+        &self.users_proof
+    }
+}
+
+/// The ephemeral state of the server between [`Srp6::continue_handshake_stateless`]
+/// and [`verify_proof_with_state`]. It can be serialized, e.g. when both
+/// steps happen in different processes.
+///
+/// **Note**: it contains the session key, store it accordingly. It is not
+/// `Clone`, so that each state gives a single try of the proof: whoever stores
+/// it must not load it twice either.
+#[allow(non_snake_case)]
+#[derive(Serialize, Deserialize)]
+pub struct HostHandshakeState<const LEN: usize, D: Digest = HashFunc> {
+    #[cfg(feature = "pub-fields")]
+    pub A: PublicKey,
+    #[cfg(not(feature = "pub-fields"))]
+    A: PublicKey,
+    #[cfg(feature = "pub-fields")]
+    pub B: PublicKey,
+    #[cfg(not(feature = "pub-fields"))]
+    B: PublicKey,
+    S: SessionKey,
+    #[serde(deserialize_with = "StrongSessionKey::deserialize_for::<_, D>")]
+    K: StrongSessionKey,
+    M: Proof,
+    #[serde(default)]
+    session_id: SessionId,
+    #[serde(default)]
+    profile: CompatProfile,
+    #[serde(default)]
+    scheme: ProofScheme,
+    #[serde(skip)]
+    d: PhantomData<D>,
+}
+
+impl<const LEN: usize, D: Digest> Srp6<LEN, D> {
+    /// same as `Srp6::default()`
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// binds the handshake to `user_details`, so that they are not given again
+    /// to each step
+    pub fn for_user(self, user_details: &UserDetails) -> Srp6ForUser<'_, LEN, D> {
+        Srp6ForUser {
+            srp6: self,
+            user_details,
+        }
+    }
+
+    /// creates a new [`Salt`] `s` and [`PasswordVerifier`] `v` for a new user,
+    /// e.g. when the account is created on the server side
+    #[cfg(feature = "std")]
     #[allow(non_snake_case)]
-    pub fn continue_handshake(
-        &mut self,
-        user_details: &UserDetails,
-        user_publickey: &PublicKey,
+    pub fn generate_new_user_secrets(
+        I: &Username,
+        p: &Password,
         constants: &OpenConstants<LEN>,
-    ) -> Result<ServerHandshake> {
-        if user_publickey.num_bytes() > LEN {
-            return Err(Srp6Error::KeyLengthMismatch {
-                given: user_publickey.num_bytes(),
-                expected: LEN,
-            });
-        }
-        let b = generate_private_key_b::<LEN>();
-        debug!("b = {:?}", &b);
+    ) -> Result<UserDetails> {
+        Self::generate_new_user_secrets_with_salt_length(I, p, constants, DEFAULT_SALT_LENGTH)
+    }
 
-        let B = calculate_pubkey_B::<LEN>(
+    /// the former [`Srp6::generate_new_user_secrets`], removed in the next release
+    #[cfg(feature = "std")]
+    #[deprecated(note = "use `generate_new_user_secrets` with a `Username` and a `Password`")]
+    #[allow(non_snake_case)]
+    pub fn generate_new_user_secrets_str(
+        I: UsernameRef,
+        p: &ClearTextPassword,
+        constants: &OpenConstants<LEN>,
+    ) -> Result<UserDetails> {
+        Self::user_details_with_salt_length(I, p, constants, DEFAULT_SALT_LENGTH)
+    }
+
+    /// same as [`Srp6::generate_new_user_secrets`], with the username and the
+    /// password checked against `policy` first
+    #[cfg(feature = "std")]
+    #[allow(non_snake_case)]
+    pub fn generate_new_user_secrets_with_policy(
+        I: &Username,
+        p: &Password,
+        constants: &OpenConstants<LEN>,
+        policy: &CredentialPolicy,
+    ) -> Result<UserDetails> {
+        policy.check(I, p.expose_secret())?;
+        Self::generate_new_user_secrets_with_salt_length(I, p, constants, DEFAULT_SALT_LENGTH)
+    }
+
+    /// same as [`Srp6::generate_new_user_secrets`], with a salt of `salt_length`
+    /// bytes instead of [`DEFAULT_SALT_LENGTH`]
+    #[cfg(feature = "std")]
+    #[allow(non_snake_case)]
+    pub fn generate_new_user_secrets_with_salt_length(
+        I: &Username,
+        p: &Password,
+        constants: &OpenConstants<LEN>,
+        salt_length: usize,
+    ) -> Result<UserDetails> {
+        Self::user_details_with_salt_length(I, p.expose_secret(), constants, salt_length)
+    }
+
+    /// [`Srp6::generate_new_user_secrets_with_salt_length`] for the credentials
+    /// as strings, e.g. of [`Srp6::generate_user_details_batch`]
+    #[cfg(feature = "std")]
+    #[allow(non_snake_case)]
+    pub(crate) fn user_details_with_salt_length(
+        I: UsernameRef,
+        p: &ClearTextPassword,
+        constants: &OpenConstants<LEN>,
+        salt_length: usize,
+    ) -> Result<UserDetails> {
+        calculate_user_details::<D>(
             &constants.module,
             &constants.generator,
-            &user_details.verifier,
-            &b,
-        );
+            constants.fixed_base_table(),
+            I,
+            p,
+            generate_salt(salt_length),
+            IdentityMode::default(),
+            CompatProfile::default(),
+            constants.variant,
+            Some(constants.fingerprint()),
+        )
+    }
 
-        self.b = b;
-        self.B = B.clone();
-        self.A = user_publickey.clone();
-        self.U = calculate_u::<LEN>(&self.A, &self.B);
+    /// same as [`Srp6::generate_new_user_secrets`], the salt comes from `rng`
+    #[allow(non_snake_case)]
+    pub fn generate_new_user_secrets_with_rng<R: RngCore + CryptoRng + ?Sized>(
+        I: &Username,
+        p: &Password,
+        constants: &OpenConstants<LEN>,
+        rng: &mut R,
+    ) -> Result<UserDetails> {
+        calculate_user_details::<D>(
+            &constants.module,
+            &constants.generator,
+            constants.fixed_base_table(),
+            I,
+            p.expose_secret(),
+            generate_salt_with(rng, DEFAULT_SALT_LENGTH),
+            IdentityMode::default(),
+            CompatProfile::default(),
+            constants.variant,
+            Some(constants.fingerprint()),
+        )
+    }
 
-        self.S = calculate_session_key_S_for_host::<LEN>(
+    /// same as [`Srp6::generate_new_user_secrets`], but with the given [`Salt`] `s`,
+    /// e.g. for test vectors or for users migrated from another system
+    ///
+    /// the salt must not be zero and fit in `LEN` bytes
+    #[allow(non_snake_case)]
+    pub fn generate_user_details_with_salt(
+        I: &Username,
+        p: &Password,
+        s: &Salt,
+        constants: &OpenConstants<LEN>,
+    ) -> Result<UserDetails> {
+        Self::user_details_with_salt(I, p.expose_secret(), s, constants)
+    }
+
+    /// [`Srp6::generate_user_details_with_salt`] for the credentials as strings,
+    /// e.g. of [`Srp6::generate_user_details_batch_with_salts`]
+    #[allow(non_snake_case)]
+    pub(crate) fn user_details_with_salt(
+        I: UsernameRef,
+        p: &ClearTextPassword,
+        s: &Salt,
+        constants: &OpenConstants<LEN>,
+    ) -> Result<UserDetails> {
+        check_salt_length(LEN, s)?;
+        let user_details = calculate_user_details::<D>(
             &constants.module,
-            &self.A,
-            &self.B,
-            &self.b,
-            &user_details.verifier,
+            &constants.generator,
+            constants.fixed_base_table(),
+            I,
+            p,
+            s.clone(),
+            IdentityMode::default(),
+            CompatProfile::default(),
+            constants.variant,
+            Some(constants.fingerprint()),
         )?;
-        self.K = calculate_session_key_hash_interleave_K::<LEN>(&self.S);
-        self.M = calculate_proof_M::<LEN>(
+        user_details.validate(constants)?;
+        Ok(user_details)
+    }
+
+    /// the private key `x = H(s | H(I | ":" | p))` of a user, as hashed into
+    /// its verifier, e.g. to check another implementation of the registration
+    ///
+    /// the salt must not be zero and fit in `LEN` bytes, the password must not
+    /// be empty
+    #[allow(non_snake_case)]
+    pub fn derive_private_key(I: &Username, p: &Password, s: &Salt) -> Result<PrivateKey> {
+        derive_x::<D>(LEN, I, p.expose_secret(), s, Srp6Variant::default())
+    }
+
+    /// the verifier `v = g^x % N` of a user, as in the [`UserDetails`] of
+    /// [`Srp6::generate_user_details_with_salt`], see [`Srp6::derive_private_key`]
+    #[allow(non_snake_case)]
+    pub fn derive_verifier(
+        I: &Username,
+        p: &Password,
+        s: &Salt,
+        constants: &OpenConstants<LEN>,
+    ) -> Result<PasswordVerifier> {
+        check_modulus(&constants.module)?;
+        let x = derive_x::<D>(LEN, I, p.expose_secret(), s, constants.variant)?;
+        Ok(calculate_password_verifier_v(
             &constants.module,
             &constants.generator,
-            &user_details.username,
-            &user_details.salt,
-            &self.A,
-            &self.B,
-            &self.K,
+            constants.fixed_base_table(),
+            &x,
+        ))
+    }
+
+    /// the hashing conventions of the user's implementation, [`CompatProfile::Rfc5054`]
+    /// by default
+    pub fn with_profile(mut self, profile: CompatProfile) -> Self {
+        self.profile = profile;
+        self
+    }
+
+    /// how the proofs `M` and `M2` are calculated, [`ProofScheme::HashConcat`]
+    /// by default; the user must use the same, see
+    /// [`Srp6User::with_proof_scheme`](crate::Srp6User::with_proof_scheme)
+    pub fn with_proof_scheme(mut self, scheme: ProofScheme) -> Self {
+        self.scheme = scheme;
+        self
+    }
+
+    #[cfg(feature = "std")]
+    pub fn continue_handshake(
+        self,
+        user_details: &UserDetails,
+        user_publickey: &impl UserPublicKey,
+        constants: &OpenConstants<LEN>,
+    ) -> Result<(ServerHandshake, Srp6WaitingForProof<LEN, D>)> {
+        // before `b` is drawn
+        let user_publickey = checked_user_publickey(LEN, user_publickey)?;
+        validate_public_key(user_publickey, constants)?;
+        self.continue_handshake_with_b(
+            user_details,
+            user_publickey,
+            constants,
+            || generate_private_key_b(constants),
+            generate_session_id(),
+        )
+    }
+
+    /// same as [`Srp6::continue_handshake`], for a username that has no
+    /// [`UserDetails`], so that the answer does not tell whether it exists
+    ///
+    /// the salt and the verifier are derived from the username with HMAC-SHA256
+    /// keyed by `server_secret_seed`: the same username always gets the same
+    /// salt, of [`DEFAULT_SALT_LENGTH`] bytes, and `B` is calculated as for a
+    /// real user. [`Srp6WaitingForProof::verify_proof`] then fails with
+    /// [`Srp6Error::InvalidProof`], as for a wrong password.
+    ///
+    /// `server_secret_seed` must be random, at least 32 bytes, kept secret and
+    /// the same across restarts, otherwise the salts of the unknown users change
+    /// and give them away.
+    #[cfg(feature = "std")]
+    pub fn continue_handshake_for_unknown_user(
+        self,
+        username: UsernameRef,
+        user_publickey: &impl UserPublicKey,
+        server_secret_seed: &[u8],
+        constants: &OpenConstants<LEN>,
+    ) -> Result<(ServerHandshake, Srp6WaitingForProof<LEN, D>)> {
+        let user_details = unknown_user_details(username, server_secret_seed, constants);
+        self.continue_handshake(&user_details, user_publickey, constants)
+    }
+
+    /// same as [`Srp6::continue_handshake`], but the calculations run on the
+    /// blocking pool of tokio, so that they do not block an async executor
+    ///
+    /// the inputs are cloned for it
+    #[cfg(feature = "async")]
+    pub async fn continue_handshake_async(
+        self,
+        user_details: &UserDetails,
+        user_publickey: &impl UserPublicKey,
+        constants: &OpenConstants<LEN>,
+    ) -> Result<(ServerHandshake, Srp6WaitingForProof<LEN, D>)>
+    where
+        D: Send + 'static,
+    {
+        let user_details = user_details.clone();
+        let user_publickey = checked_user_publickey(LEN, user_publickey)?.clone();
+        let constants = constants.clone();
+        super::offload(move || self.continue_handshake(&user_details, &user_publickey, &constants))
+            .await
+    }
+
+    /// same as [`Srp6::continue_handshake`], but the exponentiations are left
+    /// to the steps of the returned [`HandshakeStepper`](crate::HandshakeStepper)
+    ///
+    /// `A` and the details are checked here, and `b` is drawn. Each
+    /// [`HandshakeStepper::step`](crate::HandshakeStepper::step) is bounded by
+    /// a number of limbs of 64 bits of the exponents, e.g. to keep the
+    /// handshakes in the largest groups from holding a thread for long.
+    #[cfg(feature = "std")]
+    pub fn continue_handshake_stepped(
+        self,
+        user_details: &UserDetails,
+        user_publickey: &impl UserPublicKey,
+        constants: &OpenConstants<LEN>,
+    ) -> Result<super::stepper::HandshakeStepper<LEN, D>> {
+        super::stepper::HandshakeStepper::new(
+            user_details,
+            checked_user_publickey(LEN, user_publickey)?,
+            constants,
+            generate_session_id(),
+            self.profile,
+            self.scheme,
+        )
+    }
+
+    /// same as [`Srp6::continue_handshake`], the private key `b` comes from `rng`
+    pub fn continue_handshake_with_rng<R: RngCore + CryptoRng + ?Sized>(
+        self,
+        user_details: &UserDetails,
+        user_publickey: &impl UserPublicKey,
+        constants: &OpenConstants<LEN>,
+        rng: &mut R,
+    ) -> Result<(ServerHandshake, Srp6WaitingForProof<LEN, D>)> {
+        // before anything is drawn from `rng`
+        let user_publickey = checked_user_publickey(LEN, user_publickey)?;
+        validate_public_key(user_publickey, constants)?;
+        let session_id = generate_session_id_with(rng);
+        self.continue_handshake_with_b(
+            user_details,
+            user_publickey,
+            constants,
+            || generate_private_key_with(rng, constants),
+            session_id,
+        )
+    }
+
+    /// same as [`Srp6::continue_handshake`], with the given private key `b`
+    /// instead of a random one (feature `deterministic`)
+    ///
+    /// **only for test vectors and debugging**: a known or reused `b` gives away
+    /// the session key, and with it the password to a dictionary attack.
+    ///
+    /// fails with [`Srp6Error::InvalidConfiguration`] if `b` gives a trivial
+    /// public key `B`, e.g. `0`
+    #[cfg(all(any(test, feature = "deterministic"), feature = "std"))]
+    pub fn continue_handshake_with_ephemeral(
+        self,
+        user_details: &UserDetails,
+        user_publickey: &impl UserPublicKey,
+        constants: &OpenConstants<LEN>,
+        b: PrivateKey,
+    ) -> Result<(ServerHandshake, Srp6WaitingForProof<LEN, D>)> {
+        let user_publickey = checked_user_publickey(LEN, user_publickey)?;
+        validate_public_key(user_publickey, constants)?;
+        self.continue_handshake_with_b(
+            user_details,
+            user_publickey,
+            constants,
+            given_key(&b, "the given `b` gives a trivial public key"),
+            generate_session_id(),
+        )
+    }
+
+    /// same as [`Srp6::continue_handshake`], with the private key `b` of `keys`,
+    /// only for test vectors
+    #[cfg(feature = "test-util")]
+    pub fn continue_handshake_with_keys(
+        self,
+        user_details: &UserDetails,
+        user_publickey: &impl UserPublicKey,
+        constants: &OpenConstants<LEN>,
+        keys: &crate::test_util::DeterministicKeys,
+    ) -> Result<(ServerHandshake, Srp6WaitingForProof<LEN, D>)> {
+        self.continue_handshake_with_ephemeral(
+            user_details,
+            user_publickey,
+            constants,
+            keys.b.clone(),
+        )
+    }
+
+    /// draws `b` with `next_b` until `B` is not trivial, `A` must be checked
+    #[allow(non_snake_case)]
+    fn continue_handshake_with_b(
+        self,
+        user_details: &UserDetails,
+        user_publickey: &PublicKey,
+        constants: &OpenConstants<LEN>,
+        next_b: impl FnMut() -> Result<PrivateKey>,
+        session_id: SessionId,
+    ) -> Result<(ServerHandshake, Srp6WaitingForProof<LEN, D>)> {
+        step_span!(
+            "continue_handshake",
+            LEN,
+            username_hash = crate::span::username_hash::<D>(&user_details.username)
         );
+        // the details are only borrowed here, unlike for `PreparedHandshake`
+        let hashes = constants.group_hashes::<D>()?;
+        let (b, B) = self.draw_b(user_details, constants, &hashes, next_b)?;
+        complete_handshake(
+            &constants.module,
+            user_details,
+            user_details.salt.clone(),
+            &hashes,
+            user_publickey,
+            &b,
+            B,
+            session_id,
+            self.profile,
+            self.scheme,
+        )
+    }
+
+    /// first half of [`Srp6::continue_handshake`]: draws `b` and calculates the
+    /// public key `B = k*v + g^b`, which do not depend on the user's public key `A`
+    ///
+    /// `B` is the costly part of the handshake; it can be prepared as soon as
+    /// the username is known, e.g. on a pool of threads, and completed by
+    /// [`PreparedHandshake::complete`] when `A` comes.
+    #[cfg(feature = "std")]
+    pub fn prepare(
+        self,
+        user_details: &UserDetails,
+        constants: &OpenConstants<LEN>,
+    ) -> Result<PreparedHandshake<LEN, D>> {
+        self.prepare_with_b(
+            user_details,
+            constants,
+            || generate_private_key_b(constants),
+            generate_session_id(),
+        )
+    }
+
+    /// same as [`Srp6::prepare`], the private key `b` comes from `rng`
+    pub fn prepare_with_rng<R: RngCore + CryptoRng + ?Sized>(
+        self,
+        user_details: &UserDetails,
+        constants: &OpenConstants<LEN>,
+        rng: &mut R,
+    ) -> Result<PreparedHandshake<LEN, D>> {
+        let session_id = generate_session_id_with(rng);
+        self.prepare_with_b(
+            user_details,
+            constants,
+            || generate_private_key_with(rng, constants),
+            session_id,
+        )
+    }
 
-        Ok(ServerHandshake {
-            salt: user_details.salt.clone(),
-            server_publickey: B,
+    /// draws `b` with `next_b` until `B` is not trivial
+    #[allow(non_snake_case)]
+    fn prepare_with_b(
+        self,
+        user_details: &UserDetails,
+        constants: &OpenConstants<LEN>,
+        next_b: impl FnMut() -> Result<PrivateKey>,
+        session_id: SessionId,
+    ) -> Result<PreparedHandshake<LEN, D>> {
+        let hashes = constants.group_hashes::<D>()?;
+        let (b, B) = self.draw_b(user_details, constants, &hashes, next_b)?;
+        Ok(PreparedHandshake {
+            user_details: user_details.clone(),
+            module: constants.module.clone(),
+            hashes: hashes.into_owned(),
+            b,
+            B,
+            session_id,
+            profile: self.profile,
+            scheme: self.scheme,
+            d: PhantomData,
         })
     }
 
-    pub fn verify_proof(self, users_proof: &Proof) -> Result<(Proof, PrivateKey)> {
-        if self.M != *users_proof {
-            // println!("{} != {}", self.M, users_proof);
-            // println!("{:?}", self);
-            return Err(Srp6Error::InvalidProof(users_proof.clone()));
+    /// checks the details, then draws `b` with `next_b` until `B` is not trivial
+    #[allow(non_snake_case)]
+    fn draw_b(
+        &self,
+        user_details: &UserDetails,
+        constants: &OpenConstants<LEN>,
+        hashes: &GroupHashes,
+        mut next_b: impl FnMut() -> Result<PrivateKey>,
+    ) -> Result<(PrivateKey, PublicKey)> {
+        step_span!(
+            "prepare_handshake",
+            LEN,
+            username_hash = crate::span::username_hash::<D>(&user_details.username)
+        );
+        user_details.validate_for(LEN, &constants.module)?;
+        Ok(timed_phase!(
+            keygen,
+            loop {
+                let b = next_b()?;
+                let B = calculate_host_publickey(
+                    &constants.module,
+                    &constants.generator,
+                    constants.fixed_base_table(),
+                    user_details,
+                    hashes,
+                    &b,
+                    self.profile,
+                );
+                if !is_trivial_public_key(&constants.module, &B) {
+                    break (b, B);
+                }
+                #[cfg(feature = "zeroize")]
+                {
+                    let mut b = b;
+                    b.zeroize();
+                }
+            }
+        ))
+    }
+
+    /// same as [`Srp6::continue_handshake`], but the ephemeral state is returned
+    /// instead of being kept, to be used later with [`verify_proof_with_state`]
+    #[cfg(feature = "std")]
+    pub fn continue_handshake_stateless(
+        user_details: &UserDetails,
+        user_publickey: &impl UserPublicKey,
+        constants: &OpenConstants<LEN>,
+    ) -> Result<(ServerHandshake, HostHandshakeState<LEN, D>)> {
+        Self::default().continue_handshake_pure(user_details, user_publickey, constants)
+    }
+
+    /// same as [`Srp6::continue_handshake_stateless`], with the profile and the
+    /// proof scheme of `self`, which is only borrowed: one [`Srp6`] can be
+    /// shared by the threads of a server
+    ///
+    /// the state is `Send + Sync`, and verified by [`verify_proof_with_state`]
+    #[cfg(feature = "std")]
+    pub fn continue_handshake_pure(
+        &self,
+        user_details: &UserDetails,
+        user_publickey: &impl UserPublicKey,
+        constants: &OpenConstants<LEN>,
+    ) -> Result<(ServerHandshake, HostHandshakeState<LEN, D>)> {
+        let (server_handshake, waiting) =
+            self.clone()
+                .continue_handshake(user_details, user_publickey, constants)?;
+        Ok((server_handshake, waiting.into_state()))
+    }
+
+    /// same as [`Srp6::continue_handshake_stateless`], the private key `b` comes from `rng`
+    pub fn continue_handshake_stateless_with_rng<R: RngCore + CryptoRng + ?Sized>(
+        user_details: &UserDetails,
+        user_publickey: &impl UserPublicKey,
+        constants: &OpenConstants<LEN>,
+        rng: &mut R,
+    ) -> Result<(ServerHandshake, HostHandshakeState<LEN, D>)> {
+        let (server_handshake, waiting) = Self::default().continue_handshake_with_rng(
+            user_details,
+            user_publickey,
+            constants,
+            rng,
+        )?;
+        Ok((server_handshake, waiting.into_state()))
+    }
+}
+
+impl<const LEN: usize, D: Digest> Srp6ForUser<'_, LEN, D> {
+    /// the hashing conventions of the user's implementation, see [`Srp6::with_profile`]
+    pub fn with_profile(self, profile: CompatProfile) -> Self {
+        Self {
+            srp6: self.srp6.with_profile(profile),
+            ..self
+        }
+    }
+
+    /// how the proofs are calculated, see [`Srp6::with_proof_scheme`]
+    pub fn with_proof_scheme(self, scheme: ProofScheme) -> Self {
+        Self {
+            srp6: self.srp6.with_proof_scheme(scheme),
+            ..self
+        }
+    }
+
+    /// the user this handshake is for
+    pub fn user_details(&self) -> &UserDetails {
+        self.user_details
+    }
+
+    /// [`Srp6::continue_handshake`] for the bound user
+    #[cfg(feature = "std")]
+    pub fn continue_handshake(
+        self,
+        user_publickey: &impl UserPublicKey,
+        constants: &OpenConstants<LEN>,
+    ) -> Result<(ServerHandshake, Srp6WaitingForProof<LEN, D>)> {
+        self.srp6
+            .continue_handshake(self.user_details, user_publickey, constants)
+    }
+
+    /// [`Srp6::continue_handshake_stepped`] for the bound user
+    #[cfg(feature = "std")]
+    pub fn continue_handshake_stepped(
+        self,
+        user_publickey: &impl UserPublicKey,
+        constants: &OpenConstants<LEN>,
+    ) -> Result<super::stepper::HandshakeStepper<LEN, D>> {
+        self.srp6
+            .continue_handshake_stepped(self.user_details, user_publickey, constants)
+    }
+
+    /// [`Srp6::continue_handshake_with_rng`] for the bound user
+    pub fn continue_handshake_with_rng<R: RngCore + CryptoRng + ?Sized>(
+        self,
+        user_publickey: &impl UserPublicKey,
+        constants: &OpenConstants<LEN>,
+        rng: &mut R,
+    ) -> Result<(ServerHandshake, Srp6WaitingForProof<LEN, D>)> {
+        self.srp6
+            .continue_handshake_with_rng(self.user_details, user_publickey, constants, rng)
+    }
+
+    /// [`Srp6::prepare`] for the bound user
+    #[cfg(feature = "std")]
+    pub fn prepare(self, constants: &OpenConstants<LEN>) -> Result<PreparedHandshake<LEN, D>> {
+        self.srp6.prepare(self.user_details, constants)
+    }
+
+    /// [`Srp6::prepare_with_rng`] for the bound user
+    pub fn prepare_with_rng<R: RngCore + CryptoRng + ?Sized>(
+        self,
+        constants: &OpenConstants<LEN>,
+        rng: &mut R,
+    ) -> Result<PreparedHandshake<LEN, D>> {
+        self.srp6
+            .prepare_with_rng(self.user_details, constants, rng)
+    }
+}
+
+impl<const LEN: usize, D: Digest> PreparedHandshake<LEN, D> {
+    /// the id that will be sent in [`ServerHandshake::session_id`]
+    pub fn session_id(&self) -> &SessionId {
+        &self.session_id
+    }
+
+    /// the user it was prepared for
+    pub fn username(&self) -> UsernameRef<'_> {
+        &self.user_details.username
+    }
+
+    /// second half of [`Srp6::continue_handshake`]: checks the user's public key
+    /// `A` and calculates the keys that depend on it, `u`, `S`, `K` and `M`
+    ///
+    /// gives the same [`ServerHandshake`] and proof `M` as [`Srp6::continue_handshake`]
+    /// would have for the same `b`.
+    #[allow(non_snake_case)]
+    pub fn complete(
+        mut self,
+        user_publickey: &impl UserPublicKey,
+    ) -> Result<(ServerHandshake, Srp6WaitingForProof<LEN, D>)> {
+        let user_publickey = checked_user_publickey(LEN, user_publickey)?;
+        // the salt is moved, the details are not needed after `M`
+        let salt = core::mem::take(&mut self.user_details.salt);
+        complete_handshake(
+            &self.module,
+            &self.user_details,
+            salt,
+            &self.hashes,
+            user_publickey,
+            &self.b,
+            core::mem::take(&mut self.B),
+            self.session_id,
+            self.profile,
+            self.scheme,
+        )
+    }
+}
+
+impl<const LEN: usize, D: Digest> Srp6WaitingForProof<LEN, D> {
+    /// the user's public key `A`
+    pub fn public_key_a(&self) -> &PublicKey {
+        &self.A
+    }
+
+    /// the server's public key `B`
+    pub fn server_public_key(&self) -> &PublicKey {
+        &self.B
+    }
+
+    /// the scrambling parameter `u = H(PAD(A) | PAD(B))`
+    pub fn scrambling_parameter(&self) -> &PublicKey {
+        &self.U
+    }
+
+    fn into_state(mut self) -> HostHandshakeState<LEN, D> {
+        HostHandshakeState {
+            A: core::mem::take(&mut self.A),
+            B: core::mem::take(&mut self.B),
+            S: core::mem::take(&mut self.S),
+            K: core::mem::take(&mut self.K),
+            M: core::mem::take(&mut self.M),
+            session_id: self.session_id,
+            profile: self.profile,
+            scheme: self.scheme,
+            d: PhantomData,
         }
-        let hamk = calculate_strong_proof_M2::<LEN>(&self.A, &self.M, &self.K);
-        Ok((hamk, self.S))
+    }
+
+    /// the id sent in [`ServerHandshake::session_id`]
+    pub fn session_id(&self) -> &SessionId {
+        &self.session_id
+    }
+
+    /// the values calculated by the host, in order (feature `transcript`)
+    #[cfg(feature = "transcript")]
+    pub fn transcript(&self) -> &Transcript {
+        &self.transcript
+    }
+
+    /// on success, returns the strong proof `M2` and the [`SessionSecret`]
+    pub fn verify_proof(mut self, users_proof: &Proof) -> Result<HostHandshakeOutcome> {
+        let hamk = verify_proof_M::<D>(
+            LEN,
+            &self.A,
+            &self.M,
+            &self.S,
+            &self.K,
+            self.profile,
+            self.scheme,
+            users_proof,
+        )?;
+        Ok(HostHandshakeOutcome::new(
+            hamk,
+            SessionSecret::new(core::mem::take(&mut self.S), core::mem::take(&mut self.K)),
+        ))
+    }
+
+    /// the former [`Srp6WaitingForProof::verify_proof`], removed in the next release
+    #[deprecated(note = "use `verify_proof`, whose `HostHandshakeOutcome` names its fields")]
+    pub fn verify_proof_tuple(self, users_proof: &Proof) -> Result<(StrongProof, SessionSecret)> {
+        let outcome = self.verify_proof(users_proof)?;
+        Ok((
+            outcome.server_proof().clone(),
+            outcome.into_session_secret(),
+        ))
+    }
+
+    /// same as [`Srp6WaitingForProof::verify_proof`], but a mismatch is reported
+    /// as [`Srp6Error::MismatchedProof`], with diagnostics
+    ///
+    /// the handshake is consumed as by `verify_proof`, so that it cannot check
+    /// several guesses of the proof, and the proofs are compared in constant
+    /// time; the diagnostics hold no secret and no strong proof
+    pub fn verify_proof_diagnose(self, users_proof: &Proof) -> Result<HostHandshakeOutcome> {
+        let diagnostics = ProofDiagnostics {
+            group_bits: LEN * 8,
+            salt_length: self.salt_length,
+            user_publickey_length: self.A.num_bytes(),
+            server_publickey_length: self.B.num_bytes(),
+            strong_session_key_length: self.K.number().num_bytes(),
+            user_publickey: self.A.clone(),
+            server_publickey: self.B.clone(),
+            users_proof: users_proof.clone(),
+        };
+        self.verify_proof(users_proof).map_err(|e| match e {
+            Srp6Error::InvalidProof => Srp6Error::MismatchedProof(diagnostics),
+            e => e,
+        })
+    }
+}
+
+impl<const LEN: usize, D: Digest> HostHandshakeState<LEN, D> {
+    /// the user's public key `A`
+    pub fn public_key_a(&self) -> &PublicKey {
+        &self.A
+    }
+
+    /// the server's public key `B`
+    pub fn server_public_key(&self) -> &PublicKey {
+        &self.B
+    }
+
+    /// the id sent in [`ServerHandshake::session_id`]
+    pub fn session_id(&self) -> &SessionId {
+        &self.session_id
+    }
+}
+
+/// the [`UserDetails`] of an unknown user, see
+/// [`Srp6::continue_handshake_for_unknown_user`]
+///
+/// formula: `s = HMAC(seed, "salt" | I)`, `v = g^HMAC(seed, "verifier" | I) % N`
+#[cfg(feature = "std")]
+fn unknown_user_details<const LEN: usize>(
+    username: UsernameRef,
+    seed: &[u8],
+    constants: &OpenConstants<LEN>,
+) -> UserDetails {
+    let prf = |label: &[u8]| {
+        let mut mac =
+            <Hmac<Sha256> as Mac>::new_from_slice(seed).expect("HMAC accepts keys of any length");
+        mac.update(label);
+        mac.update(username.as_bytes());
+        mac.finalize().into_bytes()
+    };
+    let salt = Salt::from_bytes_be(&prf(b"salt")[..DEFAULT_SALT_LENGTH]);
+    let x = PrivateKey::from_bytes_be(&prf(b"verifier"));
+    UserDetails {
+        username: username.into(),
+        salt,
+        verifier: calculate_password_verifier_v(
+            &constants.module,
+            &constants.generator,
+            constants.fixed_base_table(),
+            &x,
+        ),
+        kdf: None,
+        identity: IdentityMode::default(),
+        normalized: cfg!(feature = "normalization"),
+        version: USER_DETAILS_VERSION,
+        group_fingerprint: None,
+    }
+}
+
+/// server side verification of the user's proof, from a state returned by
+/// [`Srp6::continue_handshake_stateless`]
+///
+/// the state is consumed, so that each state gives a single try. Whoever
+/// stores it must also make sure that it is not loaded twice.
+///
+/// on success, returns the strong proof `M2` and the [`SessionSecret`]
+pub fn verify_proof_with_state<const LEN: usize, D: Digest>(
+    mut state: HostHandshakeState<LEN, D>,
+    users_proof: &Proof,
+) -> Result<HostHandshakeOutcome> {
+    let hamk = verify_proof_M::<D>(
+        LEN,
+        &state.A,
+        &state.M,
+        &state.S,
+        &state.K,
+        state.profile,
+        state.scheme,
+        users_proof,
+    )?;
+    Ok(HostHandshakeOutcome::new(
+        hamk,
+        SessionSecret::new(core::mem::take(&mut state.S), core::mem::take(&mut state.K)),
+    ))
+}
+
+/// the former [`verify_proof_with_state`], removed in the next release
+#[deprecated(note = "use `verify_proof_with_state`, whose `HostHandshakeOutcome` names its fields")]
+pub fn verify_proof_with_state_tuple<const LEN: usize, D: Digest>(
+    state: HostHandshakeState<LEN, D>,
+    users_proof: &Proof,
+) -> Result<(StrongProof, SessionSecret)> {
+    let outcome = verify_proof_with_state(state, users_proof)?;
+    Ok((
+        outcome.server_proof().clone(),
+        outcome.into_session_secret(),
+    ))
+}
+
+/// creates the [`UserDetails`] of a new user with a random [`Salt`], using the
+/// default hash function
+///
+/// see [`Srp6::generate_new_user_secrets`] for another hash function
+#[cfg(feature = "std")]
+#[allow(non_snake_case)]
+pub fn generate_user_details<const LEN: usize>(
+    I: &Username,
+    p: &Password,
+    constants: &OpenConstants<LEN>,
+) -> Result<UserDetails> {
+    Srp6::<LEN>::generate_new_user_secrets_with_salt_length(I, p, constants, DEFAULT_SALT_LENGTH)
+}
+
+/// creates the [`UserDetails`] of a new user with the given [`Salt`], using the
+/// default hash function
+///
+/// see [`Srp6::generate_user_details_with_salt`] for another hash function
+#[allow(non_snake_case)]
+pub fn generate_user_details_with_salt<const LEN: usize>(
+    I: &Username,
+    p: &Password,
+    s: &Salt,
+    constants: &OpenConstants<LEN>,
+) -> Result<UserDetails> {
+    Srp6::<LEN>::generate_user_details_with_salt(I, p, s, constants)
+}
+
+/// the private key `x` of a user, using the default hash function
+///
+/// the salt must not be zero, the password must not be empty; see [`Srp6::derive_private_key`] for another hash function
+#[allow(non_snake_case)]
+pub fn derive_private_key(I: &Username, p: &Password, s: &Salt) -> Result<PrivateKey> {
+    derive_x::<HashFunc>(usize::MAX, I, p.expose_secret(), s, Srp6Variant::default())
+}
+
+/// the verifier `v` of a user, using the default hash function
+///
+/// see [`Srp6::derive_verifier`] for another hash function
+#[allow(non_snake_case)]
+pub fn derive_verifier<const LEN: usize>(
+    I: &Username,
+    p: &Password,
+    s: &Salt,
+    constants: &OpenConstants<LEN>,
+) -> Result<PasswordVerifier> {
+    Srp6::<LEN>::derive_verifier(I, p, s, constants)
+}
+
+/// `x` as hashed into the verifier by [`calculate_user_details`] for `variant`,
+/// the salt must not be zero and fit in `len` bytes, the username and the
+/// password must not be empty
+#[allow(non_snake_case)]
+fn derive_x<D: Digest>(
+    len: usize,
+    I: UsernameRef,
+    p: &ClearTextPassword,
+    s: &Salt,
+    variant: Srp6Variant,
+) -> Result<PrivateKey> {
+    let (I, p) = normalized_credentials(I, p, cfg!(feature = "normalization"))?;
+    if s.is_zero() {
+        return Err(Srp6Error::InvalidSalt);
+    }
+    check_salt_length(len, s)?;
+    Ok(private_key_x::<D>(
+        &I,
+        &p,
+        s,
+        CompatProfile::default(),
+        variant,
+    ))
+}
+
+/// the keys of the host for one handshake
+#[allow(non_snake_case)]
+pub(crate) struct HostKeys {
+    pub(crate) B: PublicKey,
+    pub(crate) U: PublicKey,
+    pub(crate) S: SessionKey,
+    pub(crate) K: StrongSessionKey,
+    pub(crate) M: Proof,
+}
+
+/// checks the user's public key `A` and the stored details, then calculates
+/// the keys of the host for its private key `b`
+#[allow(non_snake_case)]
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn calculate_host_keys<D: Digest>(
+    len: usize,
+    N: &PrimeModulus,
+    g: &Generator,
+    g_pow: Option<&FixedBasePowTable>,
+    user_details: &UserDetails,
+    hashes: &GroupHashes,
+    A: &PublicKey,
+    b: &PrivateKey,
+    profile: CompatProfile,
+    scheme: ProofScheme,
+) -> Result<HostKeys> {
+    step_span!(
+        "continue_handshake",
+        len,
+        username_hash = crate::span::username_hash::<D>(&user_details.username)
+    );
+    check_public_key(len, N, A)?;
+    user_details.validate_for(len, N)?;
+    let B = timed_phase!(
+        keygen,
+        calculate_host_publickey(N, g, g_pow, user_details, hashes, b, profile)
+    );
+    calculate_host_keys_with_B::<D>(
+        len,
+        N,
+        user_details,
+        &user_details.salt,
+        hashes,
+        A,
+        b,
+        B,
+        profile,
+        scheme,
+    )
+}
+
+/// the public key `B` of the host for its private key `b`, the details must be valid
+#[allow(non_snake_case)]
+fn calculate_host_publickey(
+    N: &PrimeModulus,
+    g: &Generator,
+    g_pow: Option<&FixedBasePowTable>,
+    user_details: &UserDetails,
+    hashes: &GroupHashes,
+    b: &PrivateKey,
+    profile: CompatProfile,
+) -> PublicKey {
+    debug_secret!("b = {:?}", b);
+    calculate_pubkey_B(N, g, g_pow, hashes.k(profile), &user_details.verifier, b)
+}
+
+/// the keys of the host that depend on the user's public key `A`, once checked
+#[allow(non_snake_case)]
+#[allow(clippy::too_many_arguments)]
+fn calculate_host_keys_with_B<D: Digest>(
+    len: usize,
+    N: &PrimeModulus,
+    user_details: &UserDetails,
+    s: &Salt,
+    hashes: &GroupHashes,
+    A: &PublicKey,
+    b: &PrivateKey,
+    B: PublicKey,
+    profile: CompatProfile,
+    scheme: ProofScheme,
+) -> Result<HostKeys> {
+    let U = calculate_u::<D>(len, A, &B, profile)?;
+    let S = timed_phase!(
+        modpow,
+        session_key_S_for_host::<D>(
+            len,
+            N,
+            A,
+            &B,
+            b,
+            &user_details.verifier,
+            profile,
+            hashes.blinding_order()
+        )?
+    );
+    calculate_host_keys_with_S::<D>(len, user_details, s, hashes, A, B, U, S, profile, scheme)
+}
+
+/// the session key `K` and the proof `M` of the host, once `S` is calculated
+#[allow(non_snake_case)]
+#[allow(clippy::too_many_arguments)]
+pub(super) fn calculate_host_keys_with_S<D: Digest>(
+    len: usize,
+    user_details: &UserDetails,
+    s: &Salt,
+    hashes: &GroupHashes,
+    A: &PublicKey,
+    B: PublicKey,
+    U: PublicKey,
+    S: SessionKey,
+    profile: CompatProfile,
+    scheme: ProofScheme,
+) -> Result<HostKeys> {
+    let K = timed_phase!(kdf, calculate_session_key_K::<D>(len, &S, profile)?);
+    let M = timed_phase!(
+        proof,
+        calculate_proof::<D>(
+            len,
+            hashes,
+            user_details.identity.identity(&user_details.username),
+            s,
+            A,
+            &B,
+            &S,
+            &K,
+            profile,
+            scheme,
+        )?
+    );
+    Ok(HostKeys { B, U, S, K, M })
+}
+
+/// second half of the handshake of the host, shared by [`Srp6::continue_handshake`]
+/// and [`PreparedHandshake::complete`]
+///
+/// `s` is the salt of `user_details`, given by value so that a caller owning
+/// the details moves it into the [`ServerHandshake`] instead of cloning it.
+/// `B` is still cloned once: it is sent to the user and kept for `M2`.
+#[allow(non_snake_case)]
+#[allow(clippy::too_many_arguments)]
+fn complete_handshake<const LEN: usize, D: Digest>(
+    N: &PrimeModulus,
+    user_details: &UserDetails,
+    s: Salt,
+    hashes: &GroupHashes,
+    A: &PublicKey,
+    b: &PrivateKey,
+    B: PublicKey,
+    session_id: SessionId,
+    profile: CompatProfile,
+    scheme: ProofScheme,
+) -> Result<(ServerHandshake, Srp6WaitingForProof<LEN, D>)> {
+    step_span!("complete_handshake", LEN);
+    check_public_key(LEN, N, A)?;
+    let keys = calculate_host_keys_with_B::<D>(
+        LEN,
+        N,
+        user_details,
+        &s,
+        hashes,
+        A,
+        b,
+        B,
+        profile,
+        scheme,
+    )?;
+    Ok(waiting_for_proof(
+        A,
+        hashes.k(profile),
+        keys,
+        s,
+        session_id,
+        profile,
+        scheme,
+    ))
+}
+
+/// the message to the user and the state of the host until the user's proof
+///
+/// `k` is only kept in the transcript (feature `transcript`)
+#[allow(non_snake_case)]
+#[cfg_attr(not(feature = "transcript"), allow(unused_variables))]
+pub(super) fn waiting_for_proof<const LEN: usize, D: Digest>(
+    A: &PublicKey,
+    k: &MultiplierParameter,
+    keys: HostKeys,
+    s: Salt,
+    session_id: SessionId,
+    profile: CompatProfile,
+    scheme: ProofScheme,
+) -> (ServerHandshake, Srp6WaitingForProof<LEN, D>) {
+    #[cfg(feature = "transcript")]
+    let transcript = {
+        let mut transcript = Transcript::default();
+        transcript.record("A", A);
+        transcript.record("B", &keys.B);
+        transcript.record("salt", &s);
+        transcript.record("u", &keys.U);
+        transcript.record("k", k);
+        transcript.record_secret("S", &keys.S);
+        transcript.record_session_key(&keys.K);
+        transcript.record("M", &keys.M);
+        transcript
+    };
+    let waiting = Srp6WaitingForProof {
+        A: A.clone(),
+        B: keys.B.clone(),
+        U: keys.U,
+        S: keys.S,
+        K: keys.K,
+        M: keys.M,
+        salt_length: s.num_bytes(),
+        session_id,
+        profile,
+        scheme,
+        #[cfg(feature = "transcript")]
+        transcript,
+        d: PhantomData,
+    };
+    let server_handshake = ServerHandshake {
+        salt: s,
+        server_publickey: keys.B,
+        session_id,
+        group_bits: group_bits(LEN),
+    };
+    (server_handshake, waiting)
+}
+
+/// checks the user's proof against `M`, and calculates `M2`
+#[allow(non_snake_case)]
+#[allow(clippy::too_many_arguments)]
+pub(super) fn verify_proof_M<D: Digest>(
+    len: usize,
+    A: &PublicKey,
+    M: &Proof,
+    S: &SessionKey,
+    K: &StrongSessionKey,
+    profile: CompatProfile,
+    scheme: ProofScheme,
+    users_proof: &Proof,
+) -> Result<StrongProof> {
+    step_span!("verify_proof", len);
+    if !M.constant_time_eq(users_proof) {
+        return Err(Srp6Error::InvalidProof);
+    }
+    timed_phase!(
+        proof,
+        calculate_strong_proof::<D>(len, A, M, S, K, profile, scheme)
+    )
+}
+
+#[cfg(feature = "zeroize")]
+impl<const LEN: usize, D: Digest> Zeroize for Srp6WaitingForProof<LEN, D> {
+    fn zeroize(&mut self) {
+        self.A.zeroize();
+        self.B.zeroize();
+        self.U.zeroize();
+        self.S.zeroize();
+        self.K.zeroize();
+        self.M.zeroize();
+        #[cfg(feature = "transcript")]
+        self.transcript.zeroize();
     }
 }
 
+#[cfg(feature = "zeroize")]
+impl<const LEN: usize, D: Digest> Drop for Srp6WaitingForProof<LEN, D> {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl<const LEN: usize, D: Digest> ZeroizeOnDrop for Srp6WaitingForProof<LEN, D> {}
+
+#[cfg(feature = "zeroize")]
+impl<const LEN: usize, D: Digest> Zeroize for PreparedHandshake<LEN, D> {
+    fn zeroize(&mut self) {
+        self.b.zeroize();
+        self.B.zeroize();
+        self.user_details.zeroize();
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl<const LEN: usize, D: Digest> Drop for PreparedHandshake<LEN, D> {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl<const LEN: usize, D: Digest> ZeroizeOnDrop for PreparedHandshake<LEN, D> {}
+
+#[cfg(feature = "zeroize")]
+impl<const LEN: usize, D: Digest> Zeroize for HostHandshakeState<LEN, D> {
+    fn zeroize(&mut self) {
+        self.A.zeroize();
+        self.B.zeroize();
+        self.S.zeroize();
+        self.K.zeroize();
+        self.M.zeroize();
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl<const LEN: usize, D: Digest> Drop for HostHandshakeState<LEN, D> {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl<const LEN: usize, D: Digest> ZeroizeOnDrop for HostHandshakeState<LEN, D> {}
+
+pub type Srp6_8192 = Srp6<1024>;
+pub type Srp6_6144 = Srp6<768>;
 pub type Srp6_4096 = Srp6<512>;
+pub type Srp6_3072 = Srp6<384>;
 pub type Srp6_2048 = Srp6<256>;
+pub type Srp6_1536 = Srp6<192>;
+pub type Srp6_1024 = Srp6<128>;
+pub type Srp6_4096Sha256 = Srp6<512, Sha256>;
+pub type Srp6_2048Sha256 = Srp6<256, Sha256>;
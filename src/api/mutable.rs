@@ -0,0 +1,209 @@
+//! The former handshake API, where [`Srp6`] and [`Srp6User`] are updated in place
+//!
+//! Deprecated: nothing stops calling their methods out of order, use
+//! [`crate::Srp6`] and [`crate::Srp6User`] instead. This module will be
+//! removed in the next release.
+#![allow(deprecated)]
+
+use crate::hash::{Digest, HashFunc};
+use crate::primitives::*;
+use crate::{Result, Srp6Error, Srp6UserWaitingForServerProof, Srp6WaitingForProof};
+
+use rand::{CryptoRng, RngCore};
+
+/// The former [`crate::Srp6`], whose handshake methods take `&mut self`
+#[deprecated(note = "use `Srp6`, whose `continue_handshake` returns a `Srp6WaitingForProof`")]
+#[allow(non_snake_case)]
+#[derive(Debug)]
+pub struct Srp6<const LEN: usize, D: Digest = HashFunc> {
+    pub A: PublicKey,
+    pub B: PublicKey,
+    pub U: PublicKey,
+    waiting: Option<Srp6WaitingForProof<LEN, D>>,
+}
+
+impl<const LEN: usize, D: Digest> Default for Srp6<LEN, D> {
+    fn default() -> Self {
+        Self {
+            A: Default::default(),
+            B: Default::default(),
+            U: Default::default(),
+            waiting: None,
+        }
+    }
+}
+
+impl<const LEN: usize, D: Digest> Srp6<LEN, D> {
+    /// forgets the keys of the last handshake, [`Srp6::continue_handshake`]
+    /// does it first
+    pub fn reset(&mut self) {
+        self.A = PublicKey::default();
+        self.B = PublicKey::default();
+        self.U = PublicKey::default();
+        self.waiting = None;
+    }
+
+    #[cfg(feature = "std")]
+    pub fn continue_handshake(
+        &mut self,
+        user_details: &UserDetails,
+        user_publickey: &PublicKey,
+        constants: &OpenConstants<LEN>,
+    ) -> Result<ServerHandshake> {
+        self.reset();
+        let (server_handshake, waiting) = crate::Srp6::<LEN, D>::default().continue_handshake(
+            user_details,
+            user_publickey,
+            constants,
+        )?;
+        Ok(self.keep(server_handshake, waiting))
+    }
+
+    /// same as [`Srp6::continue_handshake`], the private key `b` comes from `rng`
+    pub fn continue_handshake_with_rng<R: RngCore + CryptoRng + ?Sized>(
+        &mut self,
+        user_details: &UserDetails,
+        user_publickey: &PublicKey,
+        constants: &OpenConstants<LEN>,
+        rng: &mut R,
+    ) -> Result<ServerHandshake> {
+        self.reset();
+        let (server_handshake, waiting) = crate::Srp6::<LEN, D>::default()
+            .continue_handshake_with_rng(user_details, user_publickey, constants, rng)?;
+        Ok(self.keep(server_handshake, waiting))
+    }
+
+    fn keep(
+        &mut self,
+        server_handshake: ServerHandshake,
+        waiting: Srp6WaitingForProof<LEN, D>,
+    ) -> ServerHandshake {
+        self.A = waiting.public_key_a().clone();
+        self.B = waiting.server_public_key().clone();
+        self.U = waiting.scrambling_parameter().clone();
+        self.waiting = Some(waiting);
+        server_handshake
+    }
+
+    /// on success, returns the strong proof `M2` and the [`SessionSecret`],
+    /// see [`Srp6WaitingForProof::verify_proof`]
+    pub fn verify_proof(self, users_proof: &Proof) -> Result<HostHandshakeOutcome> {
+        self.waiting
+            .ok_or(Srp6Error::InvalidProof)?
+            .verify_proof(users_proof)
+    }
+
+    /// see [`Srp6WaitingForProof::verify_proof_diagnose`], the handshake is
+    /// consumed as by [`Srp6::verify_proof`]
+    pub fn verify_proof_diagnose(self, users_proof: &Proof) -> Result<HostHandshakeOutcome> {
+        self.waiting
+            .ok_or(Srp6Error::InvalidProof)?
+            .verify_proof_diagnose(users_proof)
+    }
+}
+
+/// The former [`crate::Srp6User`], whose handshake methods take `&mut self`
+#[deprecated(
+    note = "use `Srp6User`, whose `update_handshake` returns a `Srp6UserWaitingForServerProof`"
+)]
+#[allow(non_snake_case)]
+#[derive(Debug, Default)]
+pub struct Srp6User<const LEN: usize, D: Digest = HashFunc> {
+    pub A: PublicKey,
+    pub B: PublicKey,
+    pub U: PublicKey,
+    pub salt: Salt,
+    pub M: Proof,
+    user: crate::Srp6User<LEN, D>,
+    waiting: Option<Srp6UserWaitingForServerProof<LEN, D>>,
+}
+
+impl<const LEN: usize, D: Digest> Srp6User<LEN, D> {
+    /// forgets the keys, the salt and the proof of the last handshake,
+    /// [`Srp6User::start_handshake`] does it first
+    pub fn reset(&mut self) {
+        self.A = PublicKey::default();
+        self.B = PublicKey::default();
+        self.U = PublicKey::default();
+        self.salt = Salt::default();
+        self.M = Proof::default();
+        self.user.reset();
+        self.waiting = None;
+    }
+
+    #[cfg(feature = "std")]
+    pub fn start_handshake(
+        &mut self,
+        username: UsernameRef,
+        constants: &OpenConstants<LEN>,
+    ) -> Result<UserHandshake> {
+        self.reset();
+        let user_handshake = self.user.start_handshake(username, constants)?;
+        self.A = self.user.public_key_a().clone();
+        Ok(user_handshake)
+    }
+
+    /// same as [`Srp6User::start_handshake`], the private key `a` comes from `rng`
+    pub fn start_handshake_with_rng<R: RngCore + CryptoRng + ?Sized>(
+        &mut self,
+        username: UsernameRef,
+        constants: &OpenConstants<LEN>,
+        rng: &mut R,
+    ) -> Result<UserHandshake> {
+        self.reset();
+        let user_handshake = self
+            .user
+            .start_handshake_with_rng(username, constants, rng)?;
+        self.A = self.user.public_key_a().clone();
+        Ok(user_handshake)
+    }
+
+    #[allow(non_snake_case)]
+    pub fn update_handshake(
+        &mut self,
+        server_handshake: &ServerHandshake,
+        constants: &OpenConstants<LEN>,
+        I: UsernameRef,
+        p: &ClearTextPassword,
+    ) -> Result<Proof> {
+        let (proof, waiting) =
+            self.user
+                .clone()
+                .calculate_proof(server_handshake, constants, I, p, None)?;
+        Ok(self.keep(proof, waiting))
+    }
+
+    /// same as [`Srp6User::update_handshake`], for a stretched private key `x`
+    #[allow(non_snake_case)]
+    pub fn update_handshake_stretched(
+        &mut self,
+        server_handshake: &ServerHandshake,
+        constants: &OpenConstants<LEN>,
+        I: UsernameRef,
+        p: &ClearTextPassword,
+        kdf: &KdfParams,
+    ) -> Result<Proof> {
+        let (proof, waiting) =
+            self.user
+                .clone()
+                .calculate_proof(server_handshake, constants, I, p, Some(kdf))?;
+        Ok(self.keep(proof, waiting))
+    }
+
+    fn keep(&mut self, proof: Proof, waiting: Srp6UserWaitingForServerProof<LEN, D>) -> Proof {
+        self.B = waiting.server_public_key().clone();
+        self.U = waiting.scrambling_parameter().clone();
+        self.salt = waiting.salt().clone();
+        self.M = waiting.proof().clone();
+        self.waiting = Some(waiting);
+        proof
+    }
+
+    /// on success, returns the [`SessionSecret`], see
+    /// [`Srp6UserWaitingForServerProof::verify_proof`]
+    pub fn verify_proof(self, servers_proof: &StrongProof) -> Result<ClientHandshakeOutcome> {
+        self.waiting
+            .ok_or_else(|| Srp6Error::InvalidStrongProof(servers_proof.clone()))?
+            .verify_proof(servers_proof)
+    }
+}
@@ -0,0 +1,322 @@
+//! Password change within an authenticated session
+//!
+//! The user computes a new salt and verifier with
+//! [`ClientSession::prepare_password_change`], authenticated with an
+//! HMAC-SHA256 keyed by the session key `K`. The server checks it with
+//! [`Srp6::apply_password_change`] and stores the returned [`UserDetails`].
+//!
+//! The same goes for a verifier of the same password stretched with another
+//! [`KdfParams`], e.g. when stretching is enabled for the existing users: only
+//! the user knows the password, so the verifier is upgraded at their next
+//! login, with [`ClientSession::prepare_verifier_upgrade`] and
+//! [`Srp6::verify_verifier_upgrade`].
+
+use super::host::Srp6;
+use crate::compat::CompatProfile;
+use crate::hash::{Digest, Sha256};
+use crate::primitives::*;
+use crate::{Result, Srp6Error};
+
+use alloc::vec::Vec;
+use hmac::{Hmac, Mac};
+use rand::{CryptoRng, RngCore};
+use serde::{Deserialize, Serialize};
+
+/// length of the HMAC-SHA256 of a [`PasswordChangeRequest`]
+const MAC_LENGTH: usize = 32;
+
+/// The new salt and verifier of a user, sent within an authenticated session
+///
+/// `mac` is the HMAC-SHA256 of the username, the salt and the verifier, keyed
+/// by the strong session key `K`, see [`Srp6::apply_password_change`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PasswordChangeRequest {
+    pub salt: Salt,
+    pub verifier: PasswordVerifier,
+    pub mac: [u8; MAC_LENGTH],
+}
+
+/// The new salt and verifier of a user for the same password, stretched with
+/// `kdf`, sent within an authenticated session
+///
+/// `mac` is the HMAC-SHA256 of a label, the username, the salt, the verifier
+/// and `kdf`, keyed by the strong session key `K`, see [`Srp6::verify_verifier_upgrade`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VerifierUpgrade {
+    pub salt: Salt,
+    pub verifier: PasswordVerifier,
+    pub kdf: KdfParams,
+    pub mac: [u8; MAC_LENGTH],
+}
+
+/// the label of the HMAC of a [`VerifierUpgrade`], so that it is not taken
+/// for a [`PasswordChangeRequest`]
+const VERIFIER_UPGRADE_LABEL: &[u8] = b"srp6 verifier upgrade";
+
+/// the HMAC of a password change, each field prefixed by its length
+#[allow(non_snake_case)]
+fn password_change_mac<D: Digest>(
+    K: &StrongSessionKey,
+    username: UsernameRef,
+    salt: &Salt,
+    verifier: &PasswordVerifier,
+) -> Result<Hmac<Sha256>> {
+    session_mac::<D>(
+        K,
+        &[username.as_bytes(), &salt.to_vec(), &verifier.to_vec()],
+    )
+}
+
+/// the HMAC of a verifier upgrade, as [`password_change_mac`] after a label
+/// and followed by `kdf`
+#[allow(non_snake_case)]
+fn verifier_upgrade_mac<D: Digest>(
+    K: &StrongSessionKey,
+    username: UsernameRef,
+    salt: &Salt,
+    verifier: &PasswordVerifier,
+    kdf: &KdfParams,
+) -> Result<Hmac<Sha256>> {
+    session_mac::<D>(
+        K,
+        &[
+            VERIFIER_UPGRADE_LABEL,
+            username.as_bytes(),
+            &salt.to_vec(),
+            &verifier.to_vec(),
+            &kdf_bytes(kdf),
+        ],
+    )
+}
+
+/// HMAC-SHA256 keyed by `K`, each field prefixed by its length
+#[allow(non_snake_case)]
+fn session_mac<D: Digest>(K: &StrongSessionKey, fields: &[&[u8]]) -> Result<Hmac<Sha256>> {
+    let key = K
+        .number()
+        .try_to_vec_pad_zero(strong_session_key_length::<D>())
+        .map_err(Srp6Error::from)?;
+    let mut mac =
+        <Hmac<Sha256> as Mac>::new_from_slice(&key).expect("HMAC accepts keys of any length");
+    for field in fields {
+        mac.update(&(field.len() as u32).to_be_bytes());
+        mac.update(field);
+    }
+    Ok(mac)
+}
+
+/// a tag for the function, then its costs as big endian `u32`s
+fn kdf_bytes(kdf: &KdfParams) -> Vec<u8> {
+    let (tag, costs): (u8, &[u32]) = match *kdf {
+        KdfParams::Pbkdf2 { iterations } => (1, &[iterations]),
+        #[cfg(feature = "argon2")]
+        KdfParams::Argon2id {
+            m_cost,
+            t_cost,
+            p_cost,
+        } => (2, &[m_cost, t_cost, p_cost]),
+    };
+    let mut bytes = Vec::with_capacity(1 + 4 * costs.len());
+    bytes.push(tag);
+    for cost in costs {
+        bytes.extend_from_slice(&cost.to_be_bytes());
+    }
+    bytes
+}
+
+impl<D: Digest> ClientSession<D> {
+    /// a new [`Salt`] and [`PasswordVerifier`] for `new_password`, authenticated
+    /// with the session key
+    ///
+    /// the verifier is calculated as by [`Srp6User::generate_new_user_secrets`](crate::Srp6User::generate_new_user_secrets),
+    /// i.e. with the username as identity and without stretching.
+    #[cfg(feature = "std")]
+    pub fn prepare_password_change<const LEN: usize>(
+        &self,
+        new_password: &ClearTextPassword,
+        constants: &OpenConstants<LEN>,
+    ) -> Result<PasswordChangeRequest> {
+        self.password_change_with_salt(new_password, constants, generate_salt(DEFAULT_SALT_LENGTH))
+    }
+
+    /// same as [`ClientSession::prepare_password_change`], the salt comes from `rng`
+    pub fn prepare_password_change_with_rng<const LEN: usize, R: RngCore + CryptoRng + ?Sized>(
+        &self,
+        new_password: &ClearTextPassword,
+        constants: &OpenConstants<LEN>,
+        rng: &mut R,
+    ) -> Result<PasswordChangeRequest> {
+        self.password_change_with_salt(
+            new_password,
+            constants,
+            generate_salt_with(rng, DEFAULT_SALT_LENGTH),
+        )
+    }
+
+    fn password_change_with_salt<const LEN: usize>(
+        &self,
+        new_password: &ClearTextPassword,
+        constants: &OpenConstants<LEN>,
+        salt: Salt,
+    ) -> Result<PasswordChangeRequest> {
+        let details = calculate_user_details::<D>(
+            &constants.module,
+            &constants.generator,
+            constants.fixed_base_table(),
+            &self.username,
+            new_password,
+            salt,
+            IdentityMode::default(),
+            CompatProfile::default(),
+            constants.variant,
+            Some(constants.fingerprint()),
+        )?;
+        let mac = password_change_mac::<D>(
+            self.strong_session_key(),
+            &self.username,
+            &details.salt,
+            &details.verifier,
+        )?;
+        Ok(PasswordChangeRequest {
+            mac: mac.finalize().into_bytes().into(),
+            salt: details.salt.clone(),
+            verifier: details.verifier.clone(),
+        })
+    }
+}
+
+impl<D: Digest> ClientSession<D> {
+    /// a new [`Salt`] and [`PasswordVerifier`] for the same `password`,
+    /// stretched with `new_kdf`, authenticated with the session key
+    ///
+    /// the verifier is calculated as by [`Srp6User::generate_new_user_secrets_stretched`](crate::Srp6User::generate_new_user_secrets_stretched),
+    /// the later logins go through [`Srp6User::update_handshake_stretched`](crate::Srp6User::update_handshake_stretched)
+    /// with `new_kdf`.
+    #[cfg(feature = "std")]
+    pub fn prepare_verifier_upgrade<const LEN: usize>(
+        &self,
+        new_kdf: KdfParams,
+        constants: &OpenConstants<LEN>,
+        password: &Password,
+    ) -> Result<VerifierUpgrade> {
+        self.verifier_upgrade_with_salt(
+            new_kdf,
+            constants,
+            password,
+            generate_salt(DEFAULT_SALT_LENGTH),
+        )
+    }
+
+    /// same as [`ClientSession::prepare_verifier_upgrade`], the salt comes from `rng`
+    pub fn prepare_verifier_upgrade_with_rng<const LEN: usize, R: RngCore + CryptoRng + ?Sized>(
+        &self,
+        new_kdf: KdfParams,
+        constants: &OpenConstants<LEN>,
+        password: &Password,
+        rng: &mut R,
+    ) -> Result<VerifierUpgrade> {
+        self.verifier_upgrade_with_salt(
+            new_kdf,
+            constants,
+            password,
+            generate_salt_with(rng, DEFAULT_SALT_LENGTH),
+        )
+    }
+
+    #[allow(non_snake_case)]
+    fn verifier_upgrade_with_salt<const LEN: usize>(
+        &self,
+        kdf: KdfParams,
+        constants: &OpenConstants<LEN>,
+        password: &Password,
+        salt: Salt,
+    ) -> Result<VerifierUpgrade> {
+        check_credentials(&self.username, password.expose_secret())?;
+        check_modulus(&constants.module)?;
+        let (I, p) = (
+            normalize(&self.username),
+            normalize(password.expose_secret()),
+        );
+        let x = calculate_private_key_x_stretched::<D>(&I, &p, &salt, &kdf)?;
+        let verifier = calculate_password_verifier_v(
+            &constants.module,
+            &constants.generator,
+            constants.fixed_base_table(),
+            &x,
+        );
+        let mac = verifier_upgrade_mac::<D>(
+            self.strong_session_key(),
+            &self.username,
+            &salt,
+            &verifier,
+            &kdf,
+        )?;
+        Ok(VerifierUpgrade {
+            mac: mac.finalize().into_bytes().into(),
+            salt,
+            verifier,
+            kdf,
+        })
+    }
+}
+
+impl<const LEN: usize, D: Digest> Srp6<LEN, D> {
+    /// checks a [`PasswordChangeRequest`] of the user of `old_details`, with the
+    /// strong session key `K` of a session authenticated by the user's former
+    /// password, see [`SessionSecret::strong_session_key`]
+    ///
+    /// on success, returns the [`UserDetails`] to store instead of `old_details`.
+    #[allow(non_snake_case)]
+    pub fn apply_password_change(
+        K: &StrongSessionKey,
+        request: &PasswordChangeRequest,
+        old_details: &UserDetails,
+    ) -> Result<UserDetails> {
+        password_change_mac::<D>(K, &old_details.username, &request.salt, &request.verifier)?
+            .verify_slice(&request.mac)
+            .map_err(|_| Srp6Error::InvalidPasswordChange)?;
+        Ok(UserDetails {
+            username: old_details.username.clone(),
+            salt: request.salt.clone(),
+            verifier: request.verifier.clone(),
+            kdf: None,
+            identity: IdentityMode::default(),
+            normalized: cfg!(feature = "normalization"),
+            version: USER_DETAILS_VERSION,
+            group_fingerprint: old_details.group_fingerprint,
+        })
+    }
+
+    /// checks a [`VerifierUpgrade`] of the user of `old_details`, with the
+    /// strong session key `K` of a session authenticated by the user's current
+    /// verifier, see [`SessionSecret::strong_session_key`]
+    ///
+    /// on success, returns the [`UserDetails`] to store instead of `old_details`,
+    /// with the new [`KdfParams`].
+    #[allow(non_snake_case)]
+    pub fn verify_verifier_upgrade(
+        K: &StrongSessionKey,
+        upgrade: &VerifierUpgrade,
+        old_details: &UserDetails,
+    ) -> Result<UserDetails> {
+        verifier_upgrade_mac::<D>(
+            K,
+            &old_details.username,
+            &upgrade.salt,
+            &upgrade.verifier,
+            &upgrade.kdf,
+        )?
+        .verify_slice(&upgrade.mac)
+        .map_err(|_| Srp6Error::InvalidVerifierUpgrade)?;
+        Ok(UserDetails {
+            username: old_details.username.clone(),
+            salt: upgrade.salt.clone(),
+            verifier: upgrade.verifier.clone(),
+            kdf: Some(upgrade.kdf),
+            identity: IdentityMode::default(),
+            normalized: cfg!(feature = "normalization"),
+            version: USER_DETAILS_VERSION,
+            group_fingerprint: old_details.group_fingerprint,
+        })
+    }
+}
@@ -0,0 +1,373 @@
+//! The host side of the handshake, calculated a bounded part at a time
+//!
+//! In the groups of 6144 or 8192 bits, the two exponentiations `g^b` and
+//! `(A * v^u)^b` of [`Srp6::continue_handshake`](crate::Srp6::continue_handshake)
+//! take long enough to stall a server that runs many handshakes on a few
+//! threads. A [`HandshakeStepper`] splits them in steps of a few limbs of the
+//! exponent `b`, see [`Srp6::continue_handshake_stepped`](crate::Srp6::continue_handshake_stepped).
+
+use super::host::{calculate_host_keys_with_S, waiting_for_proof, HostKeys};
+use crate::big_number::ModPowStepper;
+use crate::compat::{CompatProfile, ProofScheme};
+use crate::hash::{Digest, HashFunc};
+use crate::primitives::*;
+use crate::{Result, Srp6Error, Srp6WaitingForProof};
+
+use core::fmt;
+use core::marker::PhantomData;
+#[cfg(feature = "zeroize")]
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+/// What is left after a [`HandshakeStepper::step`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StepResult {
+    /// the limbs of 64 bits of the exponents still to process
+    Pending { remaining_limbs: usize },
+    /// [`HandshakeStepper::finish`] returns at once
+    Ready,
+}
+
+/// the exponentiation in progress
+#[allow(non_snake_case)]
+enum Phase {
+    /// `g^b`, for the public key `B`
+    PublicKey(ModPowStepper),
+    /// `(A * v^u)^b`, the premaster secret `S`
+    SessionKey {
+        B: PublicKey,
+        U: PublicKey,
+        pow: ModPowStepper,
+    },
+    Ready(HostKeys),
+    Failed(Srp6Error),
+}
+
+/// The host between [`Srp6::continue_handshake_stepped`](crate::Srp6::continue_handshake_stepped)
+/// and its [`ServerHandshake`]
+///
+/// Nothing is calculated outside of [`HandshakeStepper::step`], each one is
+/// bounded by a number of limbs of the exponents. A server can give each
+/// handshake a budget, interleave the steps of many handshakes, or drop the
+/// stepper to cancel a handshake half way.
+///
+/// `D` is the hash function, SHA-1 by default
+#[allow(non_snake_case)]
+pub struct HandshakeStepper<const LEN: usize, D: Digest = HashFunc> {
+    user_details: UserDetails,
+    constants: OpenConstants<LEN>,
+    hashes: GroupHashes,
+    A: PublicKey,
+    b: PrivateKey,
+    session_id: SessionId,
+    profile: CompatProfile,
+    scheme: ProofScheme,
+    phase: Phase,
+    d: PhantomData<D>,
+}
+
+/// `b` and the exponentiations in progress are left out
+impl<const LEN: usize, D: Digest> fmt::Debug for HandshakeStepper<LEN, D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HandshakeStepper")
+            .field("username", &self.user_details.username)
+            .field("A", &self.A)
+            .field("session_id", &self.session_id)
+            .field("remaining_limbs", &self.remaining_limbs())
+            .finish_non_exhaustive()
+    }
+}
+
+#[allow(non_snake_case)]
+impl<const LEN: usize, D: Digest> HandshakeStepper<LEN, D> {
+    /// checks `A` and the details, then draws `b`; `g^b` is only calculated
+    /// here with the fixed base table of the group
+    pub(super) fn new(
+        user_details: &UserDetails,
+        A: &PublicKey,
+        constants: &OpenConstants<LEN>,
+        session_id: SessionId,
+        profile: CompatProfile,
+        scheme: ProofScheme,
+    ) -> Result<Self> {
+        // before `b` is drawn
+        validate_public_key(A, constants)?;
+        user_details.validate_for(LEN, &constants.module)?;
+        let mut stepper = Self {
+            user_details: user_details.clone(),
+            constants: constants.clone(),
+            hashes: constants.group_hashes::<D>()?.into_owned(),
+            A: A.clone(),
+            b: PrivateKey::default(),
+            session_id,
+            profile,
+            scheme,
+            phase: Phase::Failed(Srp6Error::InvalidPublicKey(PublicKey::default())),
+            d: PhantomData,
+        };
+        stepper.draw_b()?;
+        Ok(stepper)
+    }
+
+    /// draws `b` until `B` is not trivial, or until `g^b` is left to the steps
+    fn draw_b(&mut self) -> Result<()> {
+        loop {
+            #[cfg(feature = "zeroize")]
+            self.b.zeroize();
+            self.b = generate_private_key_b(&self.constants)?;
+            let Some(table) = self.constants.fixed_base_table() else {
+                let (g, N) = (&self.constants.generator, &*self.constants.module);
+                self.phase = Phase::PublicKey(ModPowStepper::new(g, &self.b, N));
+                return Ok(());
+            };
+            let g_b = timed_phase!(keygen, table.pow(&self.b));
+            if let Some(phase) = self.session_key_phase(g_b)? {
+                self.phase = phase;
+                return Ok(());
+            }
+        }
+    }
+
+    /// `B = k*v + g^b`, then the exponentiation of `S`; `None` if `B` is trivial
+    fn session_key_phase(&self, g_b: PublicKey) -> Result<Option<Phase>> {
+        let N = &*self.constants.module;
+        let v = &self.user_details.verifier;
+        let B = &((self.hashes.k(self.profile) * v) + g_b) % N;
+        if is_trivial_public_key(N, &B) {
+            return Ok(None);
+        }
+        debug_secret!("b = {:?}", &self.b);
+        let U = calculate_u::<D>(LEN, &self.A, &B, self.profile)?;
+        check_scrambling_parameter(&U)?;
+        let base = &self.A * &v.modpow(&U, N);
+        let pow = ModPowStepper::new(&base, &self.b, N);
+        Ok(Some(Phase::SessionKey { B, U, pow }))
+    }
+
+    /// processes at most `max_limbs` limbs of 64 bits of the exponents, and
+    /// calculates `K` and `M` once `S` is done
+    ///
+    /// the budget is shared by `g^b` and `S`; the short `v^u` and the hashes
+    /// are not counted. After an error, the next steps return it again.
+    pub fn step(&mut self, max_limbs: usize) -> Result<StepResult> {
+        step_span!("continue_handshake_step", LEN);
+        let result = self.advance(max_limbs);
+        if let Err(e) = &result {
+            self.phase = Phase::Failed(e.clone());
+        }
+        result
+    }
+
+    fn advance(&mut self, mut max_limbs: usize) -> Result<StepResult> {
+        loop {
+            match &mut self.phase {
+                Phase::PublicKey(pow) => {
+                    max_limbs -= timed_phase!(keygen, pow.step(max_limbs));
+                    let Some(g_b) = pow.result() else {
+                        return Ok(self.pending());
+                    };
+                    match self.session_key_phase(g_b)? {
+                        Some(phase) => self.phase = phase,
+                        None => self.draw_b()?,
+                    }
+                }
+                Phase::SessionKey { pow, .. } => {
+                    max_limbs -= timed_phase!(modpow, pow.step(max_limbs));
+                    let Some(S) = pow.result() else {
+                        return Ok(self.pending());
+                    };
+                    debug_secret!("S = {:?}", &S);
+                    let Phase::SessionKey { B, U, .. } = self.take_phase() else {
+                        unreachable!("the phase was just matched");
+                    };
+                    let keys = calculate_host_keys_with_S::<D>(
+                        LEN,
+                        &self.user_details,
+                        &self.user_details.salt,
+                        &self.hashes,
+                        &self.A,
+                        B,
+                        U,
+                        S,
+                        self.profile,
+                        self.scheme,
+                    )?;
+                    self.phase = Phase::Ready(keys);
+                }
+                Phase::Ready(_) => return Ok(StepResult::Ready),
+                Phase::Failed(e) => return Err(e.clone()),
+            }
+        }
+    }
+
+    fn pending(&self) -> StepResult {
+        StepResult::Pending {
+            remaining_limbs: self.remaining_limbs(),
+        }
+    }
+
+    fn take_phase(&mut self) -> Phase {
+        core::mem::replace(
+            &mut self.phase,
+            Phase::Failed(Srp6Error::InvalidPublicKey(PublicKey::default())),
+        )
+    }
+
+    /// the limbs of 64 bits of the exponents still to process; until `B` is
+    /// done, the limbs of `b` are counted for `S` too
+    pub fn remaining_limbs(&self) -> usize {
+        match &self.phase {
+            Phase::PublicKey(pow) => pow.remaining_limbs() + self.b.num_bytes().div_ceil(8),
+            Phase::SessionKey { pow, .. } => pow.remaining_limbs(),
+            Phase::Ready(_) | Phase::Failed(_) => 0,
+        }
+    }
+
+    /// the id that will be sent in [`ServerHandshake::session_id`]
+    pub fn session_id(&self) -> &SessionId {
+        &self.session_id
+    }
+
+    /// the user of the handshake
+    pub fn username(&self) -> UsernameRef<'_> {
+        &self.user_details.username
+    }
+
+    /// same result as [`Srp6::continue_handshake`](crate::Srp6::continue_handshake),
+    /// the steps left are done at once
+    pub fn finish(mut self) -> Result<(ServerHandshake, Srp6WaitingForProof<LEN, D>)> {
+        self.step(usize::MAX)?;
+        let Phase::Ready(keys) = self.take_phase() else {
+            unreachable!("a step without limit ends ready or failed");
+        };
+        Ok(waiting_for_proof(
+            &self.A,
+            self.hashes.k(self.profile),
+            keys,
+            core::mem::take(&mut self.user_details.salt),
+            self.session_id,
+            self.profile,
+            self.scheme,
+        ))
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl<const LEN: usize, D: Digest> Zeroize for HandshakeStepper<LEN, D> {
+    fn zeroize(&mut self) {
+        self.b.zeroize();
+        self.user_details.zeroize();
+        // the exponentiations wipe their own exponent
+        self.phase = Phase::Failed(Srp6Error::InvalidPublicKey(PublicKey::default()));
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl<const LEN: usize, D: Digest> Drop for HandshakeStepper<LEN, D> {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl<const LEN: usize, D: Digest> ZeroizeOnDrop for HandshakeStepper<LEN, D> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Srp6, Srp6User};
+
+    /// steps of `budget` limbs until the stepper is ready, returns how many
+    fn run_steps<const LEN: usize>(stepper: &mut HandshakeStepper<LEN>, budget: usize) -> usize {
+        let mut steps = 0;
+        loop {
+            steps += 1;
+            match stepper.step(budget).unwrap() {
+                StepResult::Ready => return steps,
+                StepResult::Pending { remaining_limbs } => {
+                    assert_eq!(remaining_limbs, stepper.remaining_limbs());
+                    assert!(remaining_limbs > 0);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn should_authenticate_after_the_steps() {
+        let constants = OpenConstants::<256>::default();
+        let user_details =
+            Srp6::<256>::generate_new_user_secrets(&"alice".into(), &"secret".into(), &constants)
+                .unwrap();
+        for budget in [1, 3, 64] {
+            let mut srp6_user =
+                Srp6User::<256>::with_credentials(&"alice".into(), &"secret".into());
+            let user_handshake = srp6_user.start_handshake(&constants).unwrap();
+            let mut stepper = Srp6::<256>::default()
+                .for_user(&user_details)
+                .continue_handshake_stepped(&user_handshake.user_publickey, &constants)
+                .unwrap();
+            let total = stepper.remaining_limbs();
+            let steps = run_steps(&mut stepper, budget);
+            assert!(
+                steps >= total.div_ceil(budget),
+                "{steps} steps for {total} limbs"
+            );
+            let session_id = *stepper.session_id();
+            let (server_handshake, srp6) = stepper.finish().unwrap();
+            assert_eq!(server_handshake.session_id, session_id);
+
+            let (proof, srp6_user) = srp6_user
+                .update_handshake(&server_handshake, &constants)
+                .unwrap();
+            let outcome = srp6.verify_proof(&proof).unwrap();
+            let strong_proof = outcome.server_proof().clone();
+            let host_secret = outcome.into_session_secret();
+            let user_secret = srp6_user
+                .verify_proof(&strong_proof)
+                .unwrap()
+                .into_session_secret();
+            assert_eq!(
+                host_secret.strong_session_key(),
+                user_secret.strong_session_key()
+            );
+        }
+    }
+
+    #[test]
+    fn should_only_calculate_within_the_budget() {
+        let constants = OpenConstants::<128>::default();
+        let user_details =
+            Srp6::<128>::generate_new_user_secrets(&"alice".into(), &"secret".into(), &constants)
+                .unwrap();
+        let mut srp6_user = Srp6User::<128>::default();
+        let user_handshake = srp6_user.start_handshake("alice", &constants).unwrap();
+        let mut stepper = Srp6::<128>::default()
+            .continue_handshake_stepped(&user_details, &user_handshake.user_publickey, &constants)
+            .unwrap();
+
+        let total = stepper.remaining_limbs();
+        assert_eq!(
+            stepper.step(0).unwrap(),
+            StepResult::Pending {
+                remaining_limbs: total
+            }
+        );
+        for done in 1..=5 {
+            assert_eq!(
+                stepper.step(1).unwrap(),
+                StepResult::Pending {
+                    remaining_limbs: total - done
+                }
+            );
+        }
+        assert!(!format!("{stepper:?}").contains(&stepper.b.to_string()));
+        // dropped half way, nothing else is calculated
+        drop(stepper);
+
+        assert_eq!(
+            Srp6::<128>::default()
+                .continue_handshake_stepped(&user_details, &PublicKey::default(), &constants)
+                .unwrap_err(),
+            Srp6Error::InvalidPublicKey(PublicKey::default())
+        );
+    }
+}
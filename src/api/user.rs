@@ -1,110 +1,1141 @@
-// use super::host::Handshake;
+use crate::compat::{CompatProfile, ProofScheme};
+use crate::hash::{Digest, HashFunc, Sha256};
 use crate::primitives::*;
-use crate::{Result, Srp6Error};
+#[cfg(feature = "transcript")]
+use crate::transcript::Transcript;
+use crate::Result;
+use crate::Srp6Error;
 
-use log::debug;
+use alloc::sync::Arc;
+use core::fmt;
+use core::marker::PhantomData;
+use core::sync::atomic::{AtomicBool, Ordering};
+use rand::{CryptoRng, RngCore};
+#[cfg(feature = "zeroize")]
+use zeroize::{Zeroize, ZeroizeOnDrop};
 
+/// Main interaction point for the user
+///
+/// [`Srp6User::update_handshake`] consumes it and returns a
+/// [`Srp6UserWaitingForServerProof`], which only gives the session key once
+/// the server's proof is verified.
+///
+/// To retry after an aborted handshake, keep a clone from before
+/// [`Srp6User::update_handshake`]: [`Srp6User::start_handshake`] draws a new
+/// private key `a` each time. The clones share the private key `a`, but only
+/// the first of them to be updated may use it, the others fail with
+/// [`Srp6Error::StaleHandshake`] until they are started again.
+///
+/// `D` is the hash function, SHA-1 by default
 #[allow(non_snake_case)]
-#[derive(Debug, Default)]
-pub struct Srp6User<const LEN: usize> {
+pub struct Srp6User<const LEN: usize, D: Digest = HashFunc> {
+    #[cfg(feature = "pub-fields")]
     pub A: PublicKey,
-    pub B: PublicKey,
+    #[cfg(not(feature = "pub-fields"))]
+    A: PublicKey,
     a: PrivateKey,
+    generation: u64,
+    /// set once `a` is used, shared with the clones, `None` before
+    /// [`Srp6User::start_handshake`]
+    used: Option<Arc<AtomicBool>>,
+    identity: IdentityMode,
+    profile: CompatProfile,
+    scheme: ProofScheme,
+    normalization: bool,
+    d: PhantomData<D>,
+}
+
+/// A [`Srp6User`] that holds the username and the password, see
+/// [`Srp6User::with_credentials`]
+///
+/// The username sent by [`Srp6UserWithCredentials::start_handshake`] is the one
+/// hashed by [`Srp6UserWithCredentials::update_handshake`].
+///
+/// `D` is the hash function, SHA-1 by default
+pub struct Srp6UserWithCredentials<const LEN: usize, D: Digest = HashFunc> {
+    user: Srp6User<LEN, D>,
+    username: Username,
+    password: Password,
+}
+
+/// The user after [`Srp6User::update_handshake`], waiting for the server's proof
+///
+/// `D` is the hash function, SHA-1 by default
+#[allow(non_snake_case)]
+pub struct Srp6UserWaitingForServerProof<const LEN: usize, D: Digest = HashFunc> {
+    #[cfg(feature = "pub-fields")]
+    pub A: PublicKey,
+    #[cfg(not(feature = "pub-fields"))]
+    A: PublicKey,
+    #[cfg(feature = "pub-fields")]
+    pub B: PublicKey,
+    #[cfg(not(feature = "pub-fields"))]
+    B: PublicKey,
+    #[cfg(feature = "pub-fields")]
     pub U: PublicKey,
+    #[cfg(not(feature = "pub-fields"))]
+    U: PublicKey,
+    #[cfg(feature = "pub-fields")]
     pub salt: Salt,
+    #[cfg(not(feature = "pub-fields"))]
+    salt: Salt,
+    #[cfg(feature = "pub-fields")]
     pub M: Proof,
+    #[cfg(not(feature = "pub-fields"))]
+    M: Proof,
     S: PrivateKey,
-    K: SessionKey,
+    K: StrongSessionKey,
+    username: Username,
+    profile: CompatProfile,
+    scheme: ProofScheme,
+    #[cfg(feature = "transcript")]
+    transcript: Transcript,
+    d: PhantomData<D>,
+}
+
+/// the private key `a` is redacted
+impl<const LEN: usize, D: Digest> fmt::Debug for Srp6User<LEN, D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Srp6User")
+            .field("A", &self.A)
+            .field("a", &Secret::sized("PrivateKey", &self.a, LEN))
+            .field("generation", &self.generation)
+            .field("identity", &self.identity)
+            .field("profile", &self.profile)
+            .field("scheme", &self.scheme)
+            .finish()
+    }
+}
+
+/// the premaster secret `S` and the session key `K` are redacted
+impl<const LEN: usize, D: Digest> fmt::Debug for Srp6UserWaitingForServerProof<LEN, D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Srp6UserWaitingForServerProof")
+            .field("A", &self.A)
+            .field("B", &self.B)
+            .field("U", &self.U)
+            .field("salt", &self.salt)
+            .field("M", &self.M)
+            .field("S", &Secret::sized("SessionKey", &self.S, LEN))
+            .field("K", &Secret::new("StrongSessionKey", &self.K))
+            .field("username", &self.username)
+            .field("profile", &self.profile)
+            .field("scheme", &self.scheme)
+            .finish()
+    }
+}
+
+/// the password is redacted
+impl<const LEN: usize, D: Digest> fmt::Debug for Srp6UserWithCredentials<LEN, D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Srp6UserWithCredentials")
+            .field("user", &self.user)
+            .field("username", &self.username)
+            .field("password", &self.password)
+            .finish()
+    }
 }
 
-impl<const LEN: usize> Srp6User<LEN> {
+impl<const LEN: usize, D: Digest> Clone for Srp6UserWithCredentials<LEN, D> {
+    fn clone(&self) -> Self {
+        Self {
+            user: self.user.clone(),
+            username: self.username.clone(),
+            password: self.password.clone(),
+        }
+    }
+}
+
+impl<const LEN: usize, D: Digest> Default for Srp6User<LEN, D> {
+    fn default() -> Self {
+        Self {
+            A: PublicKey::default(),
+            a: PrivateKey::default(),
+            generation: 0,
+            used: None,
+            identity: IdentityMode::default(),
+            profile: CompatProfile::default(),
+            scheme: ProofScheme::default(),
+            normalization: cfg!(feature = "normalization"),
+            d: PhantomData,
+        }
+    }
+}
+
+impl<const LEN: usize, D: Digest> Clone for Srp6User<LEN, D> {
+    fn clone(&self) -> Self {
+        Self {
+            A: self.A.clone(),
+            a: self.a.clone(),
+            generation: self.generation,
+            used: self.used.clone(),
+            identity: self.identity.clone(),
+            profile: self.profile,
+            scheme: self.scheme,
+            normalization: self.normalization,
+            d: PhantomData,
+        }
+    }
+}
+
+impl<const LEN: usize, D: Digest> Srp6User<LEN, D> {
+    /// same as `Srp6User::default()`
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// the public key `A` of the current handshake, zero before
+    /// [`Srp6User::start_handshake`]
+    pub fn public_key_a(&self) -> &PublicKey {
+        &self.A
+    }
+
+    /// the number of handshakes started with [`Srp6User::start_handshake`],
+    /// including those of the user it was cloned from
+    pub fn handshake_generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// a user that keeps `username` and `password` for the whole handshake,
+    /// they are not given again to [`Srp6UserWithCredentials::update_handshake`]
+    #[allow(non_snake_case)]
+    pub fn with_credentials(I: &Username, p: &Password) -> Srp6UserWithCredentials<LEN, D> {
+        Srp6UserWithCredentials {
+            user: Self::default(),
+            username: I.clone(),
+            password: p.clone(),
+        }
+    }
+
     /// creates a new [`Salt`] `s` and [`PasswordVerifier`] `v` for a new user
+    #[cfg(feature = "std")]
     #[allow(non_snake_case)]
     pub fn generate_new_user_secrets(
+        I: &Username,
+        p: &Password,
+        constants: &OpenConstants<LEN>,
+    ) -> Result<UserDetails> {
+        Self::generate_new_user_secrets_with_salt_length(I, p, constants, DEFAULT_SALT_LENGTH)
+    }
+
+    /// the former [`Srp6User::generate_new_user_secrets`], removed in the next release
+    #[cfg(feature = "std")]
+    #[deprecated(note = "use `generate_new_user_secrets` with a `Username` and a `Password`")]
+    #[allow(non_snake_case)]
+    pub fn generate_new_user_secrets_str(
         I: UsernameRef,
         p: &ClearTextPassword,
         constants: &OpenConstants<LEN>,
-    ) -> UserDetails {
-        let salt = generate_salt::<LEN>();
-        // let s = BigNumber::from_hex_str_be("FFFFFFFFFFFFFFFFC90FDAA22168C234C4C6628B80DC1CD129024E088A67CC74020BBEA63B139B22514A08798E3404DDEF9519B3CD3A431B302B0A6DF25F14374FE1356D6D51C245E485B576625E7EC6F44C42E9A637ED6B0BFF5CB6F406B7EDEE386BFB5A899FA5AE9F24117C4B1FE649286651ECE45B3DC2007CB8A163BF0598DA48361C55D39A69163FA8FD24CF5F83655D23DCA3AD961C62F356208552BB9ED5290").unwrap();
-        let x = calculate_private_key_x(I, p, &salt);
-        let verifier = calculate_password_verifier_v(&constants.module, &constants.generator, &x);
+    ) -> Result<UserDetails> {
+        crate::Srp6::<LEN, D>::user_details_with_salt_length(I, p, constants, DEFAULT_SALT_LENGTH)
+    }
+
+    /// same as [`Srp6User::generate_new_user_secrets`], but with the given
+    /// [`Salt`] `s`, e.g. for test vectors, see [`Srp6::generate_user_details_with_salt`](crate::Srp6::generate_user_details_with_salt)
+    #[allow(non_snake_case)]
+    pub fn generate_new_user_secrets_with_salt(
+        I: &Username,
+        p: &Password,
+        s: &Salt,
+        constants: &OpenConstants<LEN>,
+    ) -> Result<UserDetails> {
+        crate::Srp6::<LEN, D>::generate_user_details_with_salt(I, p, s, constants)
+    }
+
+    /// same as [`Srp6User::generate_new_user_secrets`], with the username and
+    /// the password checked against `policy` first
+    #[cfg(feature = "std")]
+    #[allow(non_snake_case)]
+    pub fn generate_new_user_secrets_with_policy(
+        I: &Username,
+        p: &Password,
+        constants: &OpenConstants<LEN>,
+        policy: &CredentialPolicy,
+    ) -> Result<UserDetails> {
+        policy.check(I, p.expose_secret())?;
+        Self::generate_new_user_secrets_with_salt_length(I, p, constants, DEFAULT_SALT_LENGTH)
+    }
+
+    /// same as [`Srp6User::generate_new_user_secrets`], with a salt of `salt_length`
+    /// bytes instead of [`DEFAULT_SALT_LENGTH`]
+    #[cfg(feature = "std")]
+    #[allow(non_snake_case)]
+    pub fn generate_new_user_secrets_with_salt_length(
+        I: &Username,
+        p: &Password,
+        constants: &OpenConstants<LEN>,
+        salt_length: usize,
+    ) -> Result<UserDetails> {
+        calculate_user_details::<D>(
+            &constants.module,
+            &constants.generator,
+            constants.fixed_base_table(),
+            I,
+            p.expose_secret(),
+            generate_salt(salt_length),
+            IdentityMode::default(),
+            CompatProfile::default(),
+            constants.variant,
+            Some(constants.fingerprint()),
+        )
+    }
+
+    /// same as [`Srp6User::generate_new_user_secrets`], with the identity `I`
+    /// given by `identity` instead of the username
+    ///
+    /// `identity` is stored in [`UserDetails`], the application has to give it
+    /// to the user for [`Srp6User::with_identity`].
+    #[cfg(feature = "std")]
+    #[allow(non_snake_case)]
+    pub fn generate_new_user_secrets_with_identity(
+        I: &Username,
+        p: &Password,
+        constants: &OpenConstants<LEN>,
+        identity: IdentityMode,
+    ) -> Result<UserDetails> {
+        calculate_user_details::<D>(
+            &constants.module,
+            &constants.generator,
+            constants.fixed_base_table(),
+            I,
+            p.expose_secret(),
+            generate_salt(DEFAULT_SALT_LENGTH),
+            identity,
+            CompatProfile::default(),
+            constants.variant,
+            Some(constants.fingerprint()),
+        )
+    }
+
+    /// same as [`Srp6User::generate_new_user_secrets`], with the private key `x`
+    /// calculated as by `profile`
+    ///
+    /// only [`CompatProfile::PySrp`] gives another verifier, the user must
+    /// then use the same profile, see [`Srp6User::with_profile`].
+    #[cfg(feature = "std")]
+    #[allow(non_snake_case)]
+    pub fn generate_new_user_secrets_with_profile(
+        I: &Username,
+        p: &Password,
+        constants: &OpenConstants<LEN>,
+        profile: CompatProfile,
+    ) -> Result<UserDetails> {
+        calculate_user_details::<D>(
+            &constants.module,
+            &constants.generator,
+            constants.fixed_base_table(),
+            I,
+            p.expose_secret(),
+            generate_salt(DEFAULT_SALT_LENGTH),
+            IdentityMode::default(),
+            profile,
+            constants.variant,
+            Some(constants.fingerprint()),
+        )
+    }
+
+    /// same as [`Srp6User::generate_new_user_secrets`], the salt comes from `rng`
+    #[allow(non_snake_case)]
+    pub fn generate_new_user_secrets_with_rng<R: RngCore + CryptoRng + ?Sized>(
+        I: &Username,
+        p: &Password,
+        constants: &OpenConstants<LEN>,
+        rng: &mut R,
+    ) -> Result<UserDetails> {
+        calculate_user_details::<D>(
+            &constants.module,
+            &constants.generator,
+            constants.fixed_base_table(),
+            I,
+            p.expose_secret(),
+            generate_salt_with(rng, DEFAULT_SALT_LENGTH),
+            IdentityMode::default(),
+            CompatProfile::default(),
+            constants.variant,
+            Some(constants.fingerprint()),
+        )
+    }
+
+    /// same as [`Srp6User::generate_new_user_secrets`], but the private key `x`
+    /// is stretched with the given key derivation function
+    ///
+    /// `kdf` is stored in [`UserDetails`], the application has to give it
+    /// to the user for [`Srp6User::update_handshake_stretched`].
+    #[cfg(feature = "std")]
+    #[allow(non_snake_case)]
+    pub fn generate_new_user_secrets_stretched(
+        I: &Username,
+        p: &Password,
+        constants: &OpenConstants<LEN>,
+        kdf: KdfParams,
+    ) -> Result<UserDetails> {
+        check_modulus(&constants.module)?;
+        let (I, p) = normalized_credentials(I, p.expose_secret(), cfg!(feature = "normalization"))?;
+        let salt = generate_salt(DEFAULT_SALT_LENGTH);
+        let x = calculate_private_key_x_stretched::<D>(&I, &p, &salt, &kdf)?;
+        let verifier = calculate_password_verifier_v(
+            &constants.module,
+            &constants.generator,
+            constants.fixed_base_table(),
+            &x,
+        );
 
-        UserDetails {
-            username: I.to_owned(),
+        Ok(UserDetails {
+            username: I.into(),
             salt,
             verifier,
-        }
+            kdf: Some(kdf),
+            identity: IdentityMode::default(),
+            normalized: cfg!(feature = "normalization"),
+            version: USER_DETAILS_VERSION,
+            group_fingerprint: Some(constants.fingerprint()),
+        })
     }
 
-    #[allow(non_snake_case)]
+    /// the identity `I` for the handshake, it must be the one stored in
+    /// [`UserDetails`], otherwise the proofs won't match
+    pub fn with_identity(mut self, identity: IdentityMode) -> Self {
+        self.identity = identity;
+        self
+    }
+
+    /// the hashing conventions of the server's implementation, [`CompatProfile::Rfc5054`]
+    /// by default
+    pub fn with_profile(mut self, profile: CompatProfile) -> Self {
+        self.profile = profile;
+        self
+    }
+
+    /// how the proofs `M` and `M2` are calculated, [`ProofScheme::HashConcat`]
+    /// by default; the server must use the same, see
+    /// [`Srp6::with_proof_scheme`](crate::Srp6::with_proof_scheme)
+    pub fn with_proof_scheme(mut self, scheme: ProofScheme) -> Self {
+        self.scheme = scheme;
+        self
+    }
+
+    /// whether the username and the password are NFC normalized before they are
+    /// hashed, and the username checked by [`validate_username`], it must be
+    /// [`UserDetails::normalized`] of the user, otherwise the proofs won't match
+    ///
+    /// on by default with the `normalization` feature
+    #[cfg(feature = "normalization")]
+    pub fn with_normalization(mut self, normalization: bool) -> Self {
+        self.normalization = normalization;
+        self
+    }
+
+    /// draws the private key `a`, the public key `A` is sent with the username
+    ///
+    /// fails with [`Srp6Error::InvalidConfiguration`] for a zero or too small `N`
+    #[cfg(feature = "std")]
     pub fn start_handshake(
         &mut self,
         username: UsernameRef,
         constants: &OpenConstants<LEN>,
-    ) -> UserHandshake {
-        let a = generate_private_key_a::<LEN>();
-        debug!("a = {:?}", &a);
+    ) -> Result<UserHandshake> {
+        self.start_handshake_with_a(username, constants, || generate_private_key_a(constants))
+    }
+
+    /// same as [`Srp6User::start_handshake`], the private key `a` comes from `rng`
+    pub fn start_handshake_with_rng<R: RngCore + CryptoRng + ?Sized>(
+        &mut self,
+        username: UsernameRef,
+        constants: &OpenConstants<LEN>,
+        rng: &mut R,
+    ) -> Result<UserHandshake> {
+        self.start_handshake_with_a(username, constants, || {
+            generate_private_key_with(rng, constants)
+        })
+    }
 
-        let A = calculate_pubkey_A(&constants.module, &constants.generator, &a);
+    /// same as [`Srp6User::start_handshake`], with the given private key `a`
+    /// instead of a random one (feature `deterministic`)
+    ///
+    /// **only for test vectors and debugging**: a known or reused `a` gives away
+    /// the session key, and with it the password to a dictionary attack.
+    ///
+    /// fails with [`Srp6Error::InvalidConfiguration`] if `a` gives a trivial
+    /// public key `A`, e.g. `0`
+    #[cfg(any(test, feature = "deterministic"))]
+    pub fn start_handshake_with_ephemeral(
+        &mut self,
+        username: UsernameRef,
+        constants: &OpenConstants<LEN>,
+        a: PrivateKey,
+    ) -> Result<UserHandshake> {
+        self.start_handshake_with_a(
+            username,
+            constants,
+            given_key(&a, "the given `a` gives a trivial public key"),
+        )
+    }
+
+    /// same as [`Srp6User::start_handshake`], with the private key `a` of `keys`,
+    /// only for test vectors
+    #[cfg(feature = "test-util")]
+    pub fn start_handshake_with_keys(
+        &mut self,
+        username: UsernameRef,
+        constants: &OpenConstants<LEN>,
+        keys: &crate::test_util::DeterministicKeys,
+    ) -> Result<UserHandshake> {
+        self.start_handshake_with_ephemeral(username, constants, keys.a.clone())
+    }
+
+    /// forgets the private key `a` and the public key `A` of the last handshake,
+    /// the identity mode and the profile are kept
+    ///
+    /// [`Srp6User::start_handshake`] does it first. An update fails with
+    /// [`Srp6Error::StaleHandshake`] until the next start.
+    pub fn reset(&mut self) {
+        #[cfg(feature = "zeroize")]
+        {
+            self.A.zeroize();
+            self.a.zeroize();
+        }
+        self.A = PublicKey::default();
+        self.a = PrivateKey::default();
+        self.used = None;
+    }
+
+    /// draws `a` with `next_a` until `A` is not trivial
+    #[allow(non_snake_case)]
+    fn start_handshake_with_a(
+        &mut self,
+        username: UsernameRef,
+        constants: &OpenConstants<LEN>,
+        mut next_a: impl FnMut() -> Result<PrivateKey>,
+    ) -> Result<UserHandshake> {
+        self.reset();
+        // not started, `update_handshake` fails with `Srp6Error::InvalidConfiguration`
+        check_modulus(&constants.module)?;
+        let (a, A) = timed_phase!(
+            keygen,
+            loop {
+                let a = next_a()?;
+                debug_secret!("a = {:?}", &a);
+                let A = calculate_pubkey_A(
+                    &constants.module,
+                    &constants.generator,
+                    constants.fixed_base_table(),
+                    &a,
+                );
+                if !is_trivial_public_key(&constants.module, &A) {
+                    break (a, A);
+                }
+            }
+        );
         self.a = a;
         self.A = A.clone();
+        self.generation += 1;
+        self.used = Some(Arc::new(AtomicBool::new(false)));
 
-        UserHandshake {
-            username: username.to_owned(),
+        Ok(UserHandshake {
+            username: username.into(),
             user_publickey: A,
-        }
+            group_bits: group_bits(LEN),
+        })
     }
 
+    /// checks the server's public key `B`, then calculates the proof `M` for
+    /// the username `I` and the password `p`
     #[allow(non_snake_case)]
     pub fn update_handshake(
-        &mut self,
+        self,
+        server_handshake: &ServerHandshake,
+        constants: &OpenConstants<LEN>,
+        I: &Username,
+        p: &Password,
+    ) -> Result<(Proof, Srp6UserWaitingForServerProof<LEN, D>)> {
+        self.calculate_proof(server_handshake, constants, I, p.expose_secret(), None)
+    }
+
+    /// same as [`Srp6User::update_handshake`], with `self` only borrowed; the
+    /// returned state is `Send + Sync`, and verified by
+    /// [`Srp6UserWaitingForServerProof::verify_proof`]
+    ///
+    /// as with a clone, `a` is only used once: a second update fails with
+    /// [`Srp6Error::StaleHandshake`] until the next start
+    #[allow(non_snake_case)]
+    pub fn update_handshake_pure(
+        &self,
+        server_handshake: &ServerHandshake,
+        constants: &OpenConstants<LEN>,
+        I: &Username,
+        p: &Password,
+    ) -> Result<(Proof, Srp6UserWaitingForServerProof<LEN, D>)> {
+        self.clone()
+            .update_handshake(server_handshake, constants, I, p)
+    }
+
+    /// the former [`Srp6User::update_handshake`], removed in the next release
+    #[deprecated(note = "use `update_handshake` with a `Username` and a `Password`")]
+    #[allow(non_snake_case)]
+    pub fn update_handshake_str(
+        self,
         server_handshake: &ServerHandshake,
         constants: &OpenConstants<LEN>,
         I: UsernameRef,
         p: &ClearTextPassword,
-    ) -> Result<Proof> {
-        if server_handshake.server_publickey.num_bytes() > LEN {
-            return Err(Srp6Error::KeyLengthMismatch {
-                given: server_handshake.server_publickey.num_bytes(),
-                expected: LEN,
-            });
-        }
-        self.B = server_handshake.server_publickey.clone();
-        self.salt = server_handshake.salt.clone();
+    ) -> Result<(Proof, Srp6UserWaitingForServerProof<LEN, D>)> {
+        self.calculate_proof(server_handshake, constants, I, p, None)
+    }
+
+    /// same as [`Srp6User::update_handshake`], for a user created with
+    /// [`Srp6User::generate_new_user_secrets_stretched`]
+    ///
+    /// `kdf` must be the one stored in [`UserDetails`], otherwise the proofs won't match
+    #[allow(non_snake_case)]
+    pub fn update_handshake_stretched(
+        self,
+        server_handshake: &ServerHandshake,
+        constants: &OpenConstants<LEN>,
+        I: &Username,
+        p: &Password,
+        kdf: &KdfParams,
+    ) -> Result<(Proof, Srp6UserWaitingForServerProof<LEN, D>)> {
+        self.calculate_proof(server_handshake, constants, I, p.expose_secret(), Some(kdf))
+    }
+
+    /// same as [`Srp6User::update_handshake`], with the group offered by the
+    /// server, which must be in `trusted`; the password is not used otherwise
+    ///
+    /// the handshake must have been started in the same group, e.g. with
+    /// [`TrustedGroups::resolve`] on the message
+    #[allow(non_snake_case)]
+    pub fn update_handshake_negotiated(
+        self,
+        server_handshake: &ServerHandshake,
+        group_msg: &GroupParamsMessage,
+        trusted: &TrustedGroups,
+        I: &Username,
+        p: &Password,
+    ) -> Result<(Proof, Srp6UserWaitingForServerProof<LEN, D>)> {
+        let constants = trusted.resolve::<LEN>(group_msg)?;
+        self.calculate_proof(server_handshake, &constants, I, p.expose_secret(), None)
+    }
+
+    /// same as [`Srp6User::update_handshake`], but the salt sent by the server
+    /// must be `expected_salt`, e.g. the one of [`ClientSession::salt_to_pin`]
+    /// after the first login; `None` accepts any salt
+    ///
+    /// a server that changes the salt fails with [`Srp6Error::SaltMismatch`],
+    /// before anything is calculated
+    #[allow(non_snake_case)]
+    pub fn update_handshake_pinned(
+        self,
+        server_handshake: &ServerHandshake,
+        constants: &OpenConstants<LEN>,
+        I: &Username,
+        p: &Password,
+        expected_salt: Option<&Salt>,
+    ) -> Result<(Proof, Srp6UserWaitingForServerProof<LEN, D>)> {
+        check_pinned_salt(expected_salt, &server_handshake.salt)?;
+        self.calculate_proof(server_handshake, constants, I, p.expose_secret(), None)
+    }
+
+    /// same as [`Srp6User::update_handshake`], but the calculations run on the
+    /// blocking pool of tokio, so that they do not block an async executor
+    ///
+    /// the inputs, including the password, are copied for it
+    #[cfg(feature = "async")]
+    #[allow(non_snake_case)]
+    pub async fn update_handshake_async(
+        self,
+        server_handshake: &ServerHandshake,
+        constants: &OpenConstants<LEN>,
+        I: &Username,
+        p: &Password,
+    ) -> Result<(Proof, Srp6UserWaitingForServerProof<LEN, D>)>
+    where
+        D: Send + 'static,
+    {
+        self.calculate_proof_async(server_handshake, constants, I, p.expose_secret(), None)
+            .await
+    }
 
-        self.U = calculate_u::<LEN>(&self.A, &self.B);
-        let x = calculate_private_key_x(I, p, &self.salt);
-        self.S = calculate_session_key_S_for_client::<LEN>(
+    /// same as [`Srp6User::update_handshake_stretched`], on the blocking pool
+    /// of tokio as [`Srp6User::update_handshake_async`]
+    #[cfg(feature = "async")]
+    #[allow(non_snake_case)]
+    pub async fn update_handshake_stretched_async(
+        self,
+        server_handshake: &ServerHandshake,
+        constants: &OpenConstants<LEN>,
+        I: &Username,
+        p: &Password,
+        kdf: &KdfParams,
+    ) -> Result<(Proof, Srp6UserWaitingForServerProof<LEN, D>)>
+    where
+        D: Send + 'static,
+    {
+        self.calculate_proof_async(
+            server_handshake,
+            constants,
+            I,
+            p.expose_secret(),
+            Some(*kdf),
+        )
+        .await
+    }
+
+    #[cfg(feature = "async")]
+    #[allow(non_snake_case)]
+    async fn calculate_proof_async(
+        self,
+        server_handshake: &ServerHandshake,
+        constants: &OpenConstants<LEN>,
+        I: UsernameRef<'_>,
+        p: &ClearTextPassword,
+        kdf: Option<KdfParams>,
+    ) -> Result<(Proof, Srp6UserWaitingForServerProof<LEN, D>)>
+    where
+        D: Send + 'static,
+    {
+        let server_handshake = server_handshake.clone();
+        let constants = constants.clone();
+        let I = I.to_owned();
+        let p = p.to_owned();
+        super::offload(move || {
+            self.calculate_proof(&server_handshake, &constants, &I, &p, kdf.as_ref())
+        })
+        .await
+    }
+
+    /// checks `B` and that `a` was not used yet, then calculates the proof `M`,
+    /// with `x` stretched if `kdf` is given
+    #[allow(non_snake_case)]
+    pub(crate) fn calculate_proof(
+        mut self,
+        server_handshake: &ServerHandshake,
+        constants: &OpenConstants<LEN>,
+        I: UsernameRef,
+        p: &ClearTextPassword,
+        kdf: Option<&KdfParams>,
+    ) -> Result<(Proof, Srp6UserWaitingForServerProof<LEN, D>)> {
+        step_span!(
+            "update_handshake",
+            LEN,
+            username_hash = crate::span::username_hash::<D>(I)
+        );
+        // before `B` is checked against the modulus
+        check_group_bits(group_bits(LEN), server_handshake.group_bits)?;
+        check_key_length_in_group(
+            LEN,
+            server_handshake.group_bits,
+            &server_handshake.server_publickey,
+        )?;
+        validate_public_key(&server_handshake.server_publickey, constants)?;
+        check_salt_length(LEN, &server_handshake.salt)?;
+        let B = &server_handshake.server_publickey;
+        let salt = &server_handshake.salt;
+        let (I, p) = normalized_credentials(I, p, self.normalization)?;
+        // never started, reset, or `a` already used by a clone
+        match self.used.take() {
+            Some(used) if !used.swap(true, Ordering::AcqRel) => {}
+            _ => return Err(Srp6Error::StaleHandshake),
+        }
+        let identity = self.identity.identity(&I);
+        let x = timed_phase!(
+            kdf,
+            match kdf {
+                Some(kdf) => calculate_private_key_x_stretched::<D>(identity, &p, salt, kdf)?,
+                None => private_key_x::<D>(identity, &p, salt, self.profile, constants.variant),
+            }
+        );
+        let hashes = constants.group_hashes::<D>()?;
+        let keys = calculate_user_keys::<D>(
+            LEN,
             &constants.module,
             &constants.generator,
-            &self.B,
+            identity,
+            salt,
             &self.A,
+            B,
+            &hashes,
             &self.a,
             &x,
+            self.profile,
+            self.scheme,
         )?;
-        self.K = calculate_session_key_hash_interleave_K::<LEN>(&self.S);
-        self.M = calculate_proof_M::<LEN>(
-            &constants.module,
-            &constants.generator,
-            I,
-            &self.salt,
+        #[cfg(feature = "transcript")]
+        let transcript = {
+            let mut transcript = Transcript::default();
+            transcript.record("A", &self.A);
+            transcript.record("B", B);
+            transcript.record("salt", salt);
+            transcript.record("u", &keys.U);
+            transcript.record("k", hashes.k(self.profile));
+            transcript.record_secret("x", &x);
+            transcript.record_secret("S", &keys.S);
+            transcript.record_session_key(&keys.K);
+            transcript.record("M", &keys.M);
+            transcript
+        };
+        // `B` and the salt are borrowed from the server's message, the proof
+        // is both returned and kept to check the server's proof `M2`
+        let waiting = Srp6UserWaitingForServerProof {
+            A: core::mem::take(&mut self.A),
+            B: B.clone(),
+            U: keys.U,
+            salt: salt.clone(),
+            M: keys.M.clone(),
+            S: keys.S,
+            K: keys.K,
+            username: I.into(),
+            profile: self.profile,
+            scheme: self.scheme,
+            #[cfg(feature = "transcript")]
+            transcript,
+            d: PhantomData,
+        };
+        Ok((keys.M, waiting))
+    }
+}
+
+impl<const LEN: usize, D: Digest> Srp6UserWithCredentials<LEN, D> {
+    /// see [`Srp6User::with_identity`]
+    pub fn with_identity(mut self, identity: IdentityMode) -> Self {
+        self.user = core::mem::take(&mut self.user).with_identity(identity);
+        self
+    }
+
+    /// see [`Srp6User::with_profile`]
+    pub fn with_profile(mut self, profile: CompatProfile) -> Self {
+        self.user = core::mem::take(&mut self.user).with_profile(profile);
+        self
+    }
+
+    /// see [`Srp6User::with_proof_scheme`](crate::Srp6User::with_proof_scheme)
+    pub fn with_proof_scheme(mut self, scheme: ProofScheme) -> Self {
+        self.user = core::mem::take(&mut self.user).with_proof_scheme(scheme);
+        self
+    }
+
+    /// the username given to [`Srp6User::with_credentials`]
+    pub fn username(&self) -> UsernameRef<'_> {
+        &self.username
+    }
+
+    /// the public key `A` of the current handshake
+    pub fn public_key_a(&self) -> &PublicKey {
+        &self.user.A
+    }
+
+    /// [`Srp6User::start_handshake`] with the stored username
+    #[cfg(feature = "std")]
+    pub fn start_handshake(&mut self, constants: &OpenConstants<LEN>) -> Result<UserHandshake> {
+        self.user.start_handshake(&self.username, constants)
+    }
+
+    /// [`Srp6User::start_handshake_with_rng`] with the stored username
+    pub fn start_handshake_with_rng<R: RngCore + CryptoRng + ?Sized>(
+        &mut self,
+        constants: &OpenConstants<LEN>,
+        rng: &mut R,
+    ) -> Result<UserHandshake> {
+        self.user
+            .start_handshake_with_rng(&self.username, constants, rng)
+    }
+
+    /// see [`Srp6User::reset`], the credentials are kept
+    pub fn reset(&mut self) {
+        self.user.reset();
+    }
+
+    /// [`Srp6User::update_handshake`] with the stored credentials
+    pub fn update_handshake(
+        mut self,
+        server_handshake: &ServerHandshake,
+        constants: &OpenConstants<LEN>,
+    ) -> Result<(Proof, Srp6UserWaitingForServerProof<LEN, D>)> {
+        core::mem::take(&mut self.user).calculate_proof(
+            server_handshake,
+            constants,
+            &self.username,
+            self.password.expose_secret(),
+            None,
+        )
+    }
+
+    /// [`Srp6User::update_handshake_pinned`] with the stored credentials
+    pub fn update_handshake_pinned(
+        mut self,
+        server_handshake: &ServerHandshake,
+        constants: &OpenConstants<LEN>,
+        expected_salt: Option<&Salt>,
+    ) -> Result<(Proof, Srp6UserWaitingForServerProof<LEN, D>)> {
+        check_pinned_salt(expected_salt, &server_handshake.salt)?;
+        core::mem::take(&mut self.user).calculate_proof(
+            server_handshake,
+            constants,
+            &self.username,
+            self.password.expose_secret(),
+            None,
+        )
+    }
+
+    /// [`Srp6User::update_handshake_stretched`] with the stored credentials
+    pub fn update_handshake_stretched(
+        mut self,
+        server_handshake: &ServerHandshake,
+        constants: &OpenConstants<LEN>,
+        kdf: &KdfParams,
+    ) -> Result<(Proof, Srp6UserWaitingForServerProof<LEN, D>)> {
+        core::mem::take(&mut self.user).calculate_proof(
+            server_handshake,
+            constants,
+            &self.username,
+            self.password.expose_secret(),
+            Some(kdf),
+        )
+    }
+
+    /// [`Srp6User::update_handshake_async`] with the stored credentials
+    #[cfg(feature = "async")]
+    pub async fn update_handshake_async(
+        mut self,
+        server_handshake: &ServerHandshake,
+        constants: &OpenConstants<LEN>,
+    ) -> Result<(Proof, Srp6UserWaitingForServerProof<LEN, D>)>
+    where
+        D: Send + 'static,
+    {
+        core::mem::take(&mut self.user)
+            .update_handshake_async(server_handshake, constants, &self.username, &self.password)
+            .await
+    }
+}
+
+impl<const LEN: usize, D: Digest> Srp6UserWaitingForServerProof<LEN, D> {
+    /// the user's public key `A`
+    pub fn public_key_a(&self) -> &PublicKey {
+        &self.A
+    }
+
+    /// the server's public key `B`
+    pub fn server_public_key(&self) -> &PublicKey {
+        &self.B
+    }
+
+    /// the scrambling parameter `u = H(PAD(A) | PAD(B))`
+    pub fn scrambling_parameter(&self) -> &PublicKey {
+        &self.U
+    }
+
+    /// the salt `s` sent by the server
+    pub fn salt(&self) -> &Salt {
+        &self.salt
+    }
+
+    /// the user's proof `M`, as returned by [`Srp6User::update_handshake`]
+    pub fn proof(&self) -> &Proof {
+        &self.M
+    }
+
+    /// the values calculated by the user, in order (feature `transcript`)
+    #[cfg(feature = "transcript")]
+    pub fn transcript(&self) -> &Transcript {
+        &self.transcript
+    }
+
+    /// on success, returns the [`SessionSecret`], a wrong `servers_proof` gives
+    /// [`Srp6Error::InvalidStrongProof`]
+    pub fn verify_proof(self, servers_proof: &StrongProof) -> Result<ClientHandshakeOutcome> {
+        self.verify_strong_proof(servers_proof)
+            .map(ClientHandshakeOutcome::new)
+    }
+
+    /// the former [`Srp6UserWaitingForServerProof::verify_proof`], removed in the next release
+    #[deprecated(note = "use `verify_proof`, whose error tells why the proof was rejected")]
+    pub fn verify_proof_option(self, servers_proof: &StrongProof) -> Option<SessionSecret> {
+        self.verify_strong_proof(servers_proof).ok()
+    }
+
+    /// the [`SessionSecret`] if `servers_proof` is the strong proof `M2`
+    pub(crate) fn verify_strong_proof(
+        mut self,
+        servers_proof: &StrongProof,
+    ) -> Result<SessionSecret> {
+        if verify_strong_proof_M2::<D>(
+            LEN,
             &self.A,
-            &self.B,
+            &self.M,
+            &self.S,
             &self.K,
-        );
-        Ok(self.M.clone())
+            self.profile,
+            self.scheme,
+            servers_proof,
+        ) {
+            Ok(SessionSecret::new(
+                core::mem::take(&mut self.S),
+                core::mem::take(&mut self.K),
+            ))
+        } else {
+            Err(Srp6Error::InvalidStrongProof(servers_proof.clone()))
+        }
     }
 
-    pub fn verify_proof(self, servers_proof: &Proof) -> Option<PrivateKey> {
-        let my_strong_proof = calculate_strong_proof_M2::<LEN>(&self.A, &self.M, &self.K);
-        if servers_proof == &my_strong_proof {
-            Some(self.S)
-        } else {
-            None
+    /// same as [`Srp6UserWaitingForServerProof::verify_proof`], on success returns
+    /// a [`ClientSession`] that can be stored to resume the session
+    pub fn verify_session(mut self, servers_proof: &StrongProof) -> Result<ClientSession<D>> {
+        let username = core::mem::take(&mut self.username);
+        let salt = self.salt.clone();
+        let secret = self.verify_strong_proof(servers_proof)?;
+        Ok(ClientSession::new(
+            username,
+            salt,
+            servers_proof.clone(),
+            secret.strong_session_key().clone(),
+        ))
+    }
+}
+
+/// the salt sent by the server must be the pinned one, if any
+fn check_pinned_salt(expected: Option<&Salt>, received: &Salt) -> Result<()> {
+    match expected {
+        Some(expected) if expected != received => Err(Srp6Error::SaltMismatch {
+            expected: expected.clone(),
+            received: received.clone(),
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// the keys of the user for one handshake
+#[allow(non_snake_case)]
+pub(crate) struct UserKeys {
+    pub(crate) U: PublicKey,
+    pub(crate) S: SessionKey,
+    pub(crate) K: StrongSessionKey,
+    pub(crate) M: Proof,
+}
+
+/// calculates the keys of the user, once `B` is checked
+#[allow(non_snake_case)]
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn calculate_user_keys<D: Digest>(
+    len: usize,
+    N: &PrimeModulus,
+    g: &Generator,
+    I: UsernameRef,
+    s: &Salt,
+    A: &PublicKey,
+    B: &PublicKey,
+    hashes: &GroupHashes,
+    a: &PrivateKey,
+    x: &PrivateKey,
+    profile: CompatProfile,
+    scheme: ProofScheme,
+) -> Result<UserKeys> {
+    let U = calculate_u::<D>(len, A, B, profile)?;
+    let k = hashes.k(profile);
+    let S = timed_phase!(
+        modpow,
+        session_key_S_for_client::<D>(len, N, g, k, B, A, a, x, profile, hashes.blinding_order())?
+    );
+    let K = timed_phase!(kdf, calculate_session_key_K::<D>(len, &S, profile)?);
+    let M = timed_phase!(
+        proof,
+        calculate_proof::<D>(len, hashes, I, s, A, B, &S, &K, profile, scheme)?
+    );
+    Ok(UserKeys { U, S, K, M })
+}
+
+/// checks the server's strong proof against `M2`
+#[allow(non_snake_case)]
+#[allow(clippy::too_many_arguments)]
+pub(super) fn verify_strong_proof_M2<D: Digest>(
+    len: usize,
+    A: &PublicKey,
+    M: &Proof,
+    S: &SessionKey,
+    K: &StrongSessionKey,
+    profile: CompatProfile,
+    scheme: ProofScheme,
+    servers_proof: &StrongProof,
+) -> bool {
+    step_span!("verify_proof", len);
+    timed_phase!(
+        proof,
+        calculate_strong_proof::<D>(len, A, M, S, K, profile, scheme)
+    )
+    .map(|M2| M2.constant_time_eq(servers_proof))
+    .unwrap_or(false)
+}
+
+#[cfg(feature = "zeroize")]
+impl<const LEN: usize, D: Digest> Zeroize for Srp6User<LEN, D> {
+    fn zeroize(&mut self) {
+        self.A.zeroize();
+        self.a.zeroize();
+        if let IdentityMode::OpaqueId(id) = &mut self.identity {
+            id.zeroize();
         }
+        self.identity = IdentityMode::default();
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl<const LEN: usize, D: Digest> Drop for Srp6User<LEN, D> {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl<const LEN: usize, D: Digest> ZeroizeOnDrop for Srp6User<LEN, D> {}
+
+#[cfg(feature = "zeroize")]
+impl<const LEN: usize, D: Digest> Zeroize for Srp6UserWaitingForServerProof<LEN, D> {
+    fn zeroize(&mut self) {
+        self.A.zeroize();
+        self.B.zeroize();
+        self.U.zeroize();
+        self.salt.zeroize();
+        self.M.zeroize();
+        self.S.zeroize();
+        self.K.zeroize();
+        #[cfg(feature = "transcript")]
+        self.transcript.zeroize();
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl<const LEN: usize, D: Digest> Drop for Srp6UserWaitingForServerProof<LEN, D> {
+    fn drop(&mut self) {
+        self.zeroize();
     }
 }
 
+#[cfg(feature = "zeroize")]
+impl<const LEN: usize, D: Digest> ZeroizeOnDrop for Srp6UserWaitingForServerProof<LEN, D> {}
+
+#[cfg(feature = "zeroize")]
+impl<const LEN: usize, D: Digest> Zeroize for Srp6UserWithCredentials<LEN, D> {
+    fn zeroize(&mut self) {
+        self.user.zeroize();
+        self.password.zeroize();
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl<const LEN: usize, D: Digest> Drop for Srp6UserWithCredentials<LEN, D> {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl<const LEN: usize, D: Digest> ZeroizeOnDrop for Srp6UserWithCredentials<LEN, D> {}
+
+pub type Srp6user8192 = Srp6User<1024>;
+pub type Srp6user6144 = Srp6User<768>;
 pub type Srp6user4096 = Srp6User<512>;
+pub type Srp6user3072 = Srp6User<384>;
 pub type Srp6user2048 = Srp6User<256>;
+pub type Srp6user1536 = Srp6User<192>;
+pub type Srp6user1024 = Srp6User<128>;
+pub type Srp6user4096Sha256 = Srp6User<512, Sha256>;
+pub type Srp6user2048Sha256 = Srp6User<256, Sha256>;
@@ -0,0 +1,805 @@
+/*!
+The SRP6 of World of Warcraft authentication servers (feature `wow`)
+
+The game client and the emulators (MaNGOS, TrinityCore, AzerothCore) use a
+legacy SRP-6 on a fixed 256-bit group, [`OpenConstants::wow`]: `g = 7`, `k = 3`
+and SHA-1. Unlike the rest of this crate, the numbers are hashed as **little
+endian** byte arrays of 32 bytes, and the username and the password are
+uppercased (ASCII only) before they are hashed.
+
+```plain
+x  = H(s | H(UPPER(I) | ":" | UPPER(p)))    read back little endian
+u  = H(A | B)                               read back little endian
+K  = SHA_Interleave(S)                      over the 32 bytes of S, little endian
+M1 = H(H(N) xor H(g) | H(UPPER(I)) | s | A | B | K)
+M2 = H(A | M1 | K)
+```
+
+The server sends `B` in the logon challenge, before the client's public key
+`A` comes with the logon proof: [`Srp6WowServer::start_handshake`] gives the
+[`ServerHandshake`], [`Srp6WowServerWaitingForProof::verify_proof`] takes `A`
+and `M1`. The proofs are the digest bytes read big endian, as the other
+proofs of this crate: `Proof::from_bytes_be(&m1)` and `M2.to_vec()`. The
+other numbers go on the wire little endian, e.g. `B.to_array_pad_zero_le::<32>()`
+and `PublicKey::from_bytes_le(&a)`.
+
+The emulators store the salt and the verifier as 32 bytes little endian, see
+[`UserDetails::from_parts`] and the example `07_wow_login`.
+
+A client that logged on before can reconnect without a new handshake, with the
+session key `K` of that logon: the server sends 16 random bytes in its
+[`ReconnectChallenge`], the client answers with 16 random bytes of its own and
+a hash over both, see [`ReconnectProof`].
+
+```plain
+R2 = H(UPPER(I) | R1 | challenge | K)       R1 the random bytes of the client
+```
+
+**Note**: the group is far too small for new protocols, it is only provided
+for the existing clients.
+
+**Note**: the formulas are transcribed from `SRP6.cpp` and `AuthSession.cpp` of
+TrinityCore, and checked against a transcription of these files in Python.
+They were not tested against a running server or a game client.
+*/
+use crate::big_number::BigNumber;
+use crate::hash::{Digest, Sha1};
+use crate::primitives::*;
+use crate::Result;
+
+use alloc::string::String;
+use core::fmt;
+use log::debug;
+use rand::{CryptoRng, RngCore};
+#[cfg(feature = "zeroize")]
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+/// length of `N` and of the numbers on the wire
+const LEN: usize = 32;
+
+/// `k` of legacy SRP-6
+const K_MULTIPLIER: u32 = 3;
+
+/// Server side of a WoW logon, see the [module](self) documentation
+#[derive(Debug, Clone, Default)]
+pub struct Srp6WowServer;
+
+/// The server after [`Srp6WowServer::start_handshake`], waiting for `A` and
+/// the client's proof
+#[allow(non_snake_case)]
+pub struct Srp6WowServerWaitingForProof {
+    user_details: UserDetails,
+    b: PrivateKey,
+    B: PublicKey,
+}
+
+/// Client side of a WoW logon, with the account name and the password
+pub struct Srp6WowClient {
+    username: Username,
+    password: String,
+}
+
+/// The client after [`Srp6WowClient::update_handshake`], waiting for the
+/// server's proof
+#[allow(non_snake_case)]
+pub struct Srp6WowClientWaitingForServerProof {
+    A: PublicKey,
+    M: Proof,
+    K: StrongSessionKey,
+}
+
+/// the private key `b` is redacted
+impl fmt::Debug for Srp6WowServerWaitingForProof {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Srp6WowServerWaitingForProof")
+            .field("user_details", &self.user_details)
+            .field("b", &Secret::sized("PrivateKey", &self.b, LEN))
+            .field("B", &self.B)
+            .finish()
+    }
+}
+
+/// the password is redacted
+impl fmt::Debug for Srp6WowClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Srp6WowClient")
+            .field("username", &self.username)
+            .field(
+                "password",
+                &Secret::new("ClearTextPassword", &self.password),
+            )
+            .finish()
+    }
+}
+
+/// the session key `K` is redacted
+impl fmt::Debug for Srp6WowClientWaitingForServerProof {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Srp6WowClientWaitingForServerProof")
+            .field("A", &self.A)
+            .field("M", &self.M)
+            .field("K", &Secret::new("StrongSessionKey", &self.K))
+            .finish()
+    }
+}
+
+impl Srp6WowServer {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// creates a new 32 bytes [`Salt`] `s` and the [`PasswordVerifier`] `v`
+    /// of a new account, the username is stored uppercased
+    #[cfg(feature = "std")]
+    #[allow(non_snake_case)]
+    pub fn generate_new_user_secrets(I: UsernameRef, p: &ClearTextPassword) -> Result<UserDetails> {
+        calculate_wow_user_details(I, p, generate_salt(LEN))
+    }
+
+    /// same as [`Srp6WowServer::generate_new_user_secrets`], the salt comes from `rng`
+    #[allow(non_snake_case)]
+    pub fn generate_new_user_secrets_with_rng<R: RngCore + CryptoRng + ?Sized>(
+        I: UsernameRef,
+        p: &ClearTextPassword,
+        rng: &mut R,
+    ) -> Result<UserDetails> {
+        calculate_wow_user_details(I, p, generate_salt_with(rng, LEN))
+    }
+
+    /// draws `b` and calculates `B = 3 * v + g^b`, for the logon challenge
+    #[cfg(feature = "std")]
+    #[allow(non_snake_case)]
+    pub fn start_handshake(
+        self,
+        user_details: &UserDetails,
+    ) -> Result<(ServerHandshake, Srp6WowServerWaitingForProof)> {
+        let N = OpenConstants::<LEN>::wow().module;
+        self.start_handshake_with_b(user_details, || generate_private_key_b_in(LEN, &N))
+    }
+
+    /// same as [`Srp6WowServer::start_handshake`], the private key `b` comes from `rng`
+    #[allow(non_snake_case)]
+    pub fn start_handshake_with_rng<R: RngCore + CryptoRng + ?Sized>(
+        self,
+        user_details: &UserDetails,
+        rng: &mut R,
+    ) -> Result<(ServerHandshake, Srp6WowServerWaitingForProof)> {
+        let N = OpenConstants::<LEN>::wow().module;
+        self.start_handshake_with_b(user_details, || generate_private_key_in(rng, LEN, &N))
+    }
+
+    /// draws `b` with `next_b` until `B` is not trivial
+    #[allow(non_snake_case)]
+    fn start_handshake_with_b(
+        self,
+        user_details: &UserDetails,
+        mut next_b: impl FnMut() -> Result<PrivateKey>,
+    ) -> Result<(ServerHandshake, Srp6WowServerWaitingForProof)> {
+        let constants = OpenConstants::<LEN>::wow();
+        user_details.validate(&constants)?;
+        let k = MultiplierParameter::from(K_MULTIPLIER);
+        let (b, B) = loop {
+            let b = next_b()?;
+            let B = calculate_pubkey_B(
+                &constants.module,
+                &constants.generator,
+                constants.fixed_base_table(),
+                &k,
+                &user_details.verifier,
+                &b,
+            );
+            if !is_trivial_public_key(&constants.module, &B) {
+                break (b, B);
+            }
+        };
+        let server_handshake = ServerHandshake {
+            salt: user_details.salt.clone(),
+            server_publickey: B.clone(),
+            session_id: SessionId::default(),
+            group_bits: group_bits(LEN),
+        };
+        let waiting = Srp6WowServerWaitingForProof {
+            user_details: user_details.clone(),
+            b,
+            B,
+        };
+        Ok((server_handshake, waiting))
+    }
+}
+
+impl Srp6WowServerWaitingForProof {
+    /// the server's public key `B`
+    pub fn server_public_key(&self) -> &PublicKey {
+        &self.B
+    }
+
+    /// checks the client's public key `A` and proof `M1`, on success returns
+    /// the server's proof `M2` and the session key `K`, whose 40 bytes are
+    /// those of `K.to_bytes()` in reverse order
+    #[allow(non_snake_case)]
+    pub fn verify_proof(
+        self,
+        A: &PublicKey,
+        users_proof: &Proof,
+    ) -> Result<(StrongProof, StrongSessionKey)> {
+        let constants = OpenConstants::<LEN>::wow();
+        let N = &*constants.module;
+        validate_public_key(A, &constants)?;
+        let v = &self.user_details.verifier;
+        let u = calculate_wow_u(A, &self.B);
+        let S = (A * &v.modpow(&u, N)).modpow(&self.b, N);
+        let K = calculate_wow_session_key_K(&S)?;
+        let user_details = &self.user_details;
+        let M = calculate_wow_proof_M(&user_details.username, &user_details.salt, A, &self.B, &K);
+        if !M.constant_time_eq(users_proof) {
+            return Err(crate::Srp6Error::InvalidProof);
+        }
+        let M2 = calculate_wow_strong_proof_M2(A, &M, &K);
+        Ok((M2, K))
+    }
+}
+
+impl Srp6WowClient {
+    /// the account name and the password, both are uppercased
+    #[allow(non_snake_case)]
+    pub fn with_credentials(I: UsernameRef, p: &ClearTextPassword) -> Self {
+        Self {
+            username: I.to_ascii_uppercase().into(),
+            password: p.to_ascii_uppercase(),
+        }
+    }
+
+    /// the uppercased account name, to send in the logon challenge
+    pub fn username(&self) -> UsernameRef<'_> {
+        &self.username
+    }
+
+    /// draws `a`, checks `B`, then calculates `A` and the proof `M1`, for the
+    /// logon proof
+    #[cfg(feature = "std")]
+    #[allow(non_snake_case)]
+    pub fn update_handshake(
+        self,
+        server_handshake: &ServerHandshake,
+    ) -> Result<(PublicKey, Proof, Srp6WowClientWaitingForServerProof)> {
+        let N = OpenConstants::<LEN>::wow().module;
+        self.update_handshake_with_a(server_handshake, || generate_private_key_a_in(LEN, &N))
+    }
+
+    /// same as [`Srp6WowClient::update_handshake`], the private key `a` comes from `rng`
+    #[allow(non_snake_case)]
+    pub fn update_handshake_with_rng<R: RngCore + CryptoRng + ?Sized>(
+        self,
+        server_handshake: &ServerHandshake,
+        rng: &mut R,
+    ) -> Result<(PublicKey, Proof, Srp6WowClientWaitingForServerProof)> {
+        let N = OpenConstants::<LEN>::wow().module;
+        self.update_handshake_with_a(server_handshake, || generate_private_key_in(rng, LEN, &N))
+    }
+
+    /// draws `a` with `next_a` until `A` is not trivial
+    #[allow(non_snake_case)]
+    fn update_handshake_with_a(
+        self,
+        server_handshake: &ServerHandshake,
+        mut next_a: impl FnMut() -> Result<PrivateKey>,
+    ) -> Result<(PublicKey, Proof, Srp6WowClientWaitingForServerProof)> {
+        let constants = OpenConstants::<LEN>::wow();
+        let (N, g) = (&*constants.module, &constants.generator);
+        let B = &server_handshake.server_publickey;
+        let s = &server_handshake.salt;
+        check_credentials(&self.username, &self.password)?;
+        validate_public_key(B, &constants)?;
+        check_salt_length(LEN, s)?;
+        let (a, A) = loop {
+            let a = next_a()?;
+            let A = calculate_pubkey_A(N, g, constants.fixed_base_table(), &a);
+            if !is_trivial_public_key(N, &A) {
+                break (a, A);
+            }
+        };
+        let x = calculate_wow_private_key_x(&self.username, &self.password, s);
+        let u = calculate_wow_u(&A, B);
+        // S = (B - k * g^x) ^ (a + u * x)
+        let k_g_x = &(&MultiplierParameter::from(K_MULTIPLIER) * &g.modpow(&x, N)) % N;
+        let base = if B < &k_g_x {
+            &(N - &k_g_x) + B
+        } else {
+            B - &k_g_x
+        };
+        let S = base.modpow(&(&a + &(&u * &x)), N);
+        let K = calculate_wow_session_key_K(&S)?;
+        let M = calculate_wow_proof_M(&self.username, s, &A, B, &K);
+        let waiting = Srp6WowClientWaitingForServerProof {
+            A: A.clone(),
+            M: M.clone(),
+            K,
+        };
+        Ok((A, M, waiting))
+    }
+}
+
+impl Srp6WowClientWaitingForServerProof {
+    /// checks the server's proof `M2`, on success returns the session key `K`,
+    /// otherwise fails with [`Srp6Error::InvalidProof`](crate::Srp6Error::InvalidProof)
+    #[allow(non_snake_case)]
+    pub fn verify_proof(mut self, servers_proof: &StrongProof) -> Result<StrongSessionKey> {
+        let M2 = calculate_wow_strong_proof_M2(&self.A, &self.M, &self.K);
+        if !M2.constant_time_eq(servers_proof) {
+            return Err(crate::Srp6Error::InvalidProof);
+        }
+        Ok(core::mem::take(&mut self.K))
+    }
+}
+
+/// the random bytes of a [`ReconnectChallenge`] and of a [`ReconnectProof`]
+pub type ReconnectNonce = [u8; 16];
+
+/// The server's reconnect challenge, for a client that still has the session
+/// key `K` of an earlier logon, see the [module](self) documentation
+///
+/// it verifies a single proof, so that a nonce is never accepted twice.
+#[derive(Debug)]
+pub struct ReconnectChallenge {
+    nonce: ReconnectNonce,
+}
+
+/// The client's answer to a [`ReconnectChallenge`]: its random bytes `R1` and
+/// the proof `R2`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReconnectProof {
+    pub client_nonce: ReconnectNonce,
+    pub proof: [u8; 20],
+}
+
+impl ReconnectChallenge {
+    /// a challenge with random bytes
+    #[cfg(feature = "std")]
+    pub fn new() -> Self {
+        Self::new_with_rng(&mut rand::thread_rng())
+    }
+
+    /// same as [`ReconnectChallenge::new`], the random bytes come from `rng`
+    pub fn new_with_rng<R: RngCore + CryptoRng + ?Sized>(rng: &mut R) -> Self {
+        let mut nonce = ReconnectNonce::default();
+        rng.fill_bytes(&mut nonce);
+        Self { nonce }
+    }
+
+    /// the random bytes to send to the client
+    pub fn nonce(&self) -> &ReconnectNonce {
+        &self.nonce
+    }
+
+    /// checks the client's proof with the session key `K` of the last logon of
+    /// the account `I`, fails with [`Srp6Error::InvalidProof`](crate::Srp6Error::InvalidProof)
+    #[allow(non_snake_case)]
+    pub fn verify_proof(
+        self,
+        I: UsernameRef,
+        K: &StrongSessionKey,
+        proof: &ReconnectProof,
+    ) -> Result<()> {
+        let expected = calculate_wow_reconnect_proof(I, &proof.client_nonce, &self.nonce, K);
+        // in constant time
+        let diff = expected
+            .iter()
+            .zip(proof.proof.iter())
+            .fold(0, |diff, (a, b)| diff | (a ^ b));
+        if diff != 0 {
+            return Err(crate::Srp6Error::InvalidProof);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl Default for ReconnectChallenge {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ReconnectProof {
+    /// the answer to the server's `challenge` with the session key `K` of the
+    /// last logon of the account `I`, with random bytes `R1`
+    #[cfg(feature = "std")]
+    #[allow(non_snake_case)]
+    pub fn new(I: UsernameRef, challenge: &ReconnectNonce, K: &StrongSessionKey) -> Self {
+        Self::new_with_rng(I, challenge, K, &mut rand::thread_rng())
+    }
+
+    /// same as [`ReconnectProof::new`], the random bytes come from `rng`
+    #[allow(non_snake_case)]
+    pub fn new_with_rng<R: RngCore + CryptoRng + ?Sized>(
+        I: UsernameRef,
+        challenge: &ReconnectNonce,
+        K: &StrongSessionKey,
+        rng: &mut R,
+    ) -> Self {
+        let mut client_nonce = ReconnectNonce::default();
+        rng.fill_bytes(&mut client_nonce);
+        let proof = calculate_wow_reconnect_proof(I, &client_nonce, challenge, K);
+        Self {
+            client_nonce,
+            proof,
+        }
+    }
+}
+
+/// the number as the 32 bytes little endian of the wire
+fn le(n: &BigNumber) -> [u8; LEN] {
+    n.to_array_pad_zero_le::<LEN>()
+}
+
+/// [`UserDetails`] with the username uppercased
+#[allow(non_snake_case)]
+fn calculate_wow_user_details(
+    I: UsernameRef,
+    p: &ClearTextPassword,
+    s: Salt,
+) -> Result<UserDetails> {
+    check_credentials(I, p)?;
+    let constants = OpenConstants::<LEN>::wow();
+    let I = I.to_ascii_uppercase();
+    let x = calculate_wow_private_key_x(&I, &p.to_ascii_uppercase(), &s);
+    let v = calculate_password_verifier_v(
+        &constants.module,
+        &constants.generator,
+        constants.fixed_base_table(),
+        &x,
+    );
+    Ok(UserDetails {
+        username: I.into(),
+        salt: s,
+        verifier: v,
+        kdf: None,
+        identity: IdentityMode::default(),
+        normalized: false,
+        version: USER_DETAILS_VERSION,
+        group_fingerprint: Some(constants.fingerprint()),
+    })
+}
+
+/// `x = H(s | H(I | ":" | p))`, `I` and `p` already uppercased
+#[allow(non_snake_case)]
+fn calculate_wow_private_key_x(I: UsernameRef, p: &ClearTextPassword, s: &Salt) -> PrivateKey {
+    let ph = Sha1::new()
+        .chain_update(I.as_bytes())
+        .chain_update(b":")
+        .chain_update(p.as_bytes())
+        .finalize();
+    let x = Sha1::new().chain_update(le(s)).chain_update(ph).finalize();
+    let x = PrivateKey::from_bytes_le(&x);
+    debug_secret!("x = {:?}", &x);
+    x
+}
+
+/// `u = H(A | B)`
+#[allow(non_snake_case)]
+fn calculate_wow_u(A: &PublicKey, B: &PublicKey) -> PublicKey {
+    let u = Sha1::new()
+        .chain_update(le(A))
+        .chain_update(le(B))
+        .finalize();
+    PublicKey::from_bytes_le(&u)
+}
+
+/// `SHA_Interleave` over the little endian bytes of `S`: they are read big
+/// endian for [`calculate_session_key_hash_interleave_K`], so that its leading
+/// zero bytes are the low order ones, which the client skips
+#[allow(non_snake_case)]
+fn calculate_wow_session_key_K(S: &SessionKey) -> Result<StrongSessionKey> {
+    calculate_session_key_hash_interleave_K::<Sha1>(LEN, &SessionKey::from_bytes_be(&le(S)))
+}
+
+/// the session key `K` as the 40 bytes that are hashed
+#[allow(non_snake_case)]
+fn K_bytes(K: &StrongSessionKey) -> [u8; 40] {
+    K.number().to_array_pad_zero_le::<40>()
+}
+
+/// `M1 = H(H(N) xor H(g) | H(I) | s | A | B | K)`
+#[allow(non_snake_case)]
+fn calculate_wow_proof_M(
+    I: UsernameRef,
+    s: &Salt,
+    A: &PublicKey,
+    B: &PublicKey,
+    K: &StrongSessionKey,
+) -> Proof {
+    let constants = OpenConstants::<LEN>::wow();
+    let hash_N = Sha1::digest(le(&constants.module));
+    let hash_g = Sha1::digest(constants.generator.to_vec_le());
+    let mut hash_N_xor_g = hash_N;
+    for (n, g) in hash_N_xor_g.iter_mut().zip(hash_g.iter()) {
+        *n ^= g;
+    }
+    let M = Sha1::new()
+        .chain_update(hash_N_xor_g)
+        .chain_update(Sha1::digest(I.as_bytes()))
+        .chain_update(le(s))
+        .chain_update(le(A))
+        .chain_update(le(B))
+        .chain_update(K_bytes(K));
+    let M = Proof::from_digest(M);
+    debug!("M = {:?}", &M);
+    M
+}
+
+/// `M2 = H(A | M1 | K)`
+#[allow(non_snake_case)]
+fn calculate_wow_strong_proof_M2(A: &PublicKey, M: &Proof, K: &StrongSessionKey) -> StrongProof {
+    let M2 = Sha1::new()
+        .chain_update(le(A))
+        .chain_update(M.to_array_pad_zero::<20>())
+        .chain_update(K_bytes(K));
+    StrongProof::from_digest(M2)
+}
+
+/// `R2 = H(UPPER(I) | R1 | challenge | K)`
+#[allow(non_snake_case)]
+fn calculate_wow_reconnect_proof(
+    I: UsernameRef,
+    client_nonce: &ReconnectNonce,
+    challenge: &ReconnectNonce,
+    K: &StrongSessionKey,
+) -> [u8; 20] {
+    Sha1::new()
+        .chain_update(I.to_ascii_uppercase().as_bytes())
+        .chain_update(client_nonce)
+        .chain_update(challenge)
+        .chain_update(K_bytes(K))
+        .finalize()
+        .into()
+}
+
+#[cfg(feature = "zeroize")]
+impl Zeroize for Srp6WowServerWaitingForProof {
+    fn zeroize(&mut self) {
+        self.user_details.zeroize();
+        self.b.zeroize();
+        self.B.zeroize();
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for Srp6WowServerWaitingForProof {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl ZeroizeOnDrop for Srp6WowServerWaitingForProof {}
+
+#[cfg(feature = "zeroize")]
+impl Zeroize for Srp6WowClient {
+    fn zeroize(&mut self) {
+        self.password.zeroize();
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for Srp6WowClient {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl ZeroizeOnDrop for Srp6WowClient {}
+
+#[cfg(feature = "zeroize")]
+impl Zeroize for Srp6WowClientWaitingForServerProof {
+    fn zeroize(&mut self) {
+        self.A.zeroize();
+        self.M.zeroize();
+        self.K.zeroize();
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for Srp6WowClientWaitingForServerProof {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl ZeroizeOnDrop for Srp6WowClientWaitingForServerProof {}
+
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+    //! The vectors below were computed with a transcription in Python of
+    //! `SRP6.cpp` and of the reconnect proof of `AuthSession.cpp` of TrinityCore,
+    //! not captured from a running server: they are no interoperability vectors.
+
+    use super::*;
+    use hex_literal::hex;
+
+    const USERNAME: &str = "test";
+    const PASSWORD: &str = "test";
+    const SALT: [u8; 32] =
+        hex!("AD0A5C0E 5E4D8E6C 5B4A3928 1706F5E4 D3C2B1A0 99887766 55443322 11FFEE0D");
+    const A_PRIVATE: [u8; 32] =
+        hex!("01020304 05060708 090A0B0C 0D0E0F10 11121314 15161718 191A1B1C 1D1E1F20");
+    const B_PRIVATE: [u8; 32] =
+        hex!("3C6A1F0E 2D4B5968 7A8B9CAD BECFD0E1 F2031425 36475869 78899AAB BCCDDEEF");
+
+    // little endian, as on the wire
+    const X: [u8; 20] = hex!("A009CF31 B0DF0115 B927E2CB 2E4FDA28 8595B622");
+    const VERIFIER: [u8; 32] =
+        hex!("763DD2B0 8E98E71F E692BC6F 6444295F 6AD45262 CE19B7BD 9164BD38 A8780576");
+    const B_PUBLIC: [u8; 32] =
+        hex!("571C5514 62BE21D2 8B409A77 48C7AB43 F5B1F786 87352AF1 5C62D58D 99E9FA0A");
+    const A_PUBLIC: [u8; 32] =
+        hex!("39AAC92A E53A17F7 ED3D7E96 1A85C12E F188E2FB A4AB972B 0E5DF393 62115A70");
+    const U: [u8; 20] = hex!("422F85E4 79CD9F74 5749D796 2E7CD4FA DACDD8A2");
+    const SECRET: [u8; 32] =
+        hex!("C0BF0E34 D58D69DE 216329B4 89C5F14C 2FFC5B68 73685825 A4F74BA1 B393DD75");
+    const SESSION_KEY: [u8; 40] = hex!(
+        "1CB103C9 7A52665E E8800910 9F001F0D 8796F3E3 6DEB586C CB082E32 BCE0237B 94941FCC 6186CE5C"
+    );
+    // the digests
+    const M1: [u8; 20] = hex!("4E57E884 5497B555 B53D6883 7D954296 F3F22E9E");
+    const M2: [u8; 20] = hex!("78200027 BC7AA966 55F8A740 F0C33E02 C7C3545E");
+
+    fn user_details() -> UserDetails {
+        calculate_wow_user_details(USERNAME, PASSWORD, Salt::from_bytes_le(&SALT)).unwrap()
+    }
+
+    #[test]
+    fn should_match_srp6_cpp_transcription() {
+        let user_details = user_details();
+        assert_eq!(user_details.username, "TEST");
+        let s = &user_details.salt;
+        assert_eq!(
+            calculate_wow_private_key_x("TEST", "TEST", s),
+            PrivateKey::from_bytes_le(&X)
+        );
+        assert_eq!(le(&user_details.verifier), VERIFIER);
+
+        let b = PrivateKey::from_bytes_be(&B_PRIVATE);
+        let (server_handshake, server) = Srp6WowServer::new()
+            .start_handshake_with_b(&user_details, || Ok(b.clone()))
+            .unwrap();
+        assert_eq!(le(&server_handshake.server_publickey), B_PUBLIC);
+        assert_eq!(&server_handshake.salt, s);
+
+        let a = PrivateKey::from_bytes_be(&A_PRIVATE);
+        let (A, M, client) = Srp6WowClient::with_credentials(USERNAME, PASSWORD)
+            .update_handshake_with_a(&server_handshake, || Ok(a.clone()))
+            .unwrap();
+        assert_eq!(le(&A), A_PUBLIC);
+        let B = PublicKey::from_bytes_le(&B_PUBLIC);
+        assert_eq!(calculate_wow_u(&A, &B), PublicKey::from_bytes_le(&U));
+        let S = SessionKey::from_bytes_le(&SECRET);
+        assert_eq!(
+            calculate_wow_session_key_K(&S)
+                .unwrap()
+                .number()
+                .to_array_pad_zero_le::<40>(),
+            SESSION_KEY
+        );
+        assert_eq!(M, Proof::from_bytes_be(&M1));
+
+        let (strong_proof, K) = server.verify_proof(&A, &M).unwrap();
+        assert_eq!(strong_proof, StrongProof::from_bytes_be(&M2));
+        assert_eq!(K.number().to_array_pad_zero_le::<40>(), SESSION_KEY);
+        assert_eq!(client.verify_proof(&strong_proof), Ok(K));
+    }
+
+    #[test]
+    fn should_match_auth_session_cpp_transcription() {
+        let K = calculate_wow_session_key_K(&SessionKey::from_bytes_le(&SECRET)).unwrap();
+        let client_nonce = hex!("10111213 14151617 18191A1B 1C1D1E1F");
+        let challenge = hex!("F0E1D2C3 B4A59687 78695A4B 3C2D1E0F");
+        assert_eq!(
+            calculate_wow_reconnect_proof("test", &client_nonce, &challenge, &K),
+            hex!("6E9918B1 360D50F3 4705B2A8 E970BAE1 2E0849C0")
+        );
+    }
+
+    #[test]
+    fn should_reconnect_with_the_session_key() {
+        let mut rng = rand::thread_rng();
+        let user_details = user_details();
+        let (server_handshake, server) = Srp6WowServer::new()
+            .start_handshake_with_rng(&user_details, &mut rng)
+            .unwrap();
+        let (A, M, client) = Srp6WowClient::with_credentials(USERNAME, PASSWORD)
+            .update_handshake_with_rng(&server_handshake, &mut rng)
+            .unwrap();
+        let (strong_proof, server_K) = server.verify_proof(&A, &M).unwrap();
+        let client_K = client.verify_proof(&strong_proof).unwrap();
+
+        let challenge = ReconnectChallenge::new_with_rng(&mut rng);
+        let proof = ReconnectProof::new_with_rng(USERNAME, challenge.nonce(), &client_K, &mut rng);
+        assert_eq!(challenge.verify_proof("TEST", &server_K, &proof), Ok(()));
+
+        // the session key of another logon
+        let challenge = ReconnectChallenge::new_with_rng(&mut rng);
+        let other_K = calculate_wow_session_key_K(&SessionKey::from_bytes_le(&SECRET)).unwrap();
+        let other_proof =
+            ReconnectProof::new_with_rng(USERNAME, challenge.nonce(), &other_K, &mut rng);
+        assert_eq!(
+            challenge.verify_proof("TEST", &server_K, &other_proof),
+            Err(crate::Srp6Error::InvalidProof)
+        );
+
+        // a replayed proof, for the nonce of an earlier challenge
+        let challenge = ReconnectChallenge::new_with_rng(&mut rng);
+        assert_eq!(
+            challenge.verify_proof("TEST", &server_K, &proof),
+            Err(crate::Srp6Error::InvalidProof)
+        );
+    }
+
+    #[test]
+    fn should_interleave_from_the_low_bytes() {
+        // the first byte of `S` on the wire is zero: it is skipped with the next one
+        let mut S = SECRET;
+        S[0] = 0;
+        let K = calculate_wow_session_key_K(&SessionKey::from_bytes_le(&S)).unwrap();
+        assert_eq!(
+            K.number().to_array_pad_zero_le::<40>(),
+            hex!(
+                "0F7F6445 6D69B794 923C0AC6 285F26FE 9755B275 12314D84 C706D4A4 0CE3B2D6 504BE78C 4A8DCA24"
+            )
+        );
+    }
+
+    #[test]
+    fn should_reject_a_wrong_password() {
+        let user_details = user_details();
+        let (server_handshake, server) = Srp6WowServer::new()
+            .start_handshake_with_rng(&user_details, &mut rand::thread_rng())
+            .unwrap();
+        // the password is not case sensitive
+        let (A, M, client) = Srp6WowClient::with_credentials("Test", "tEsT")
+            .update_handshake_with_rng(&server_handshake, &mut rand::thread_rng())
+            .unwrap();
+        let (strong_proof, K) = server.verify_proof(&A, &M).unwrap();
+        assert_eq!(client.verify_proof(&strong_proof), Ok(K));
+
+        let (server_handshake, server) = Srp6WowServer::new()
+            .start_handshake_with_rng(&user_details, &mut rand::thread_rng())
+            .unwrap();
+        let (A, M, client) = Srp6WowClient::with_credentials(USERNAME, "wrong")
+            .update_handshake_with_rng(&server_handshake, &mut rand::thread_rng())
+            .unwrap();
+        assert_eq!(
+            server.verify_proof(&A, &M).unwrap_err(),
+            crate::Srp6Error::InvalidProof
+        );
+        assert_eq!(
+            client.verify_proof(&StrongProof::from(1)),
+            Err(crate::Srp6Error::InvalidProof)
+        );
+    }
+
+    #[test]
+    fn should_reject_empty_credentials() {
+        let mut rng = rand::thread_rng();
+        assert_eq!(
+            Srp6WowServer::generate_new_user_secrets_with_rng("", PASSWORD, &mut rng),
+            Err(crate::Srp6Error::EmptyUsername)
+        );
+        assert_eq!(
+            Srp6WowServer::generate_new_user_secrets_with_rng(USERNAME, "", &mut rng),
+            Err(crate::Srp6Error::EmptyPassword)
+        );
+
+        let (server_handshake, _) = Srp6WowServer::new()
+            .start_handshake_with_rng(&user_details(), &mut rng)
+            .unwrap();
+        assert_eq!(
+            Srp6WowClient::with_credentials(USERNAME, "")
+                .update_handshake_with_rng(&server_handshake, &mut rng)
+                .err(),
+            Some(crate::Srp6Error::EmptyPassword)
+        );
+    }
+}
@@ -1,24 +1,62 @@
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::fmt::{Debug, Display, Formatter, LowerHex, UpperHex};
 use derive_more::Error;
-use num_bigint::BigUint;
-use serde::{Deserialize, Serialize};
+use num_bigint::{BigUint, RandBigInt};
+use rand::{CryptoRng, RngCore};
+use serde::de::{self, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use sha1::{Digest, Sha1};
-use std::convert::TryFrom;
-use std::fmt::{Debug, Display, Formatter};
-#[cfg(not(feature = "norand"))]
-use {num_bigint::RandBigInt, rand::thread_rng};
+#[cfg(feature = "zeroize")]
+use zeroize::Zeroize;
 
 /// also exporting the trait here
+pub use core::ops::{Add, Mul, Rem, Sub};
+use num_traits::CheckedSub;
 pub use num_traits::Zero;
-pub use std::ops::{Add, Mul, Rem, Sub};
 
 /// Wraps a `num_bigint::BigUint` to customize it.
-#[derive(PartialEq, Clone, PartialOrd, Serialize, Deserialize)]
+///
+/// Equality, ordering and hashing are by value, leading zeros of a raw
+/// representation do not matter.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
 pub struct BigNumber(BigUint);
 
-#[derive(Error, derive_more::Display, Debug)]
+/// errors of the parsing and of the padding of the numbers
+#[derive(Error, derive_more::Display, Debug, Clone, PartialEq, Eq)]
 pub enum BigNumberError {
     #[display("Invalid hex string.")]
     InvalidHexStr,
+    #[display("Invalid decimal string.")]
+    InvalidDecStr,
+    #[display("Empty number string.")]
+    EmptyStr,
+    #[display("The number ({given} bytes) does not fit in {expected} bytes.")]
+    TooLong { given: usize, expected: usize },
+}
+
+/// removes the ASCII whitespace and `_` separators of a number string
+fn digits_only(str: &str) -> core::result::Result<String, BigNumberError> {
+    let digits: String = str
+        .chars()
+        .filter(|c| !c.is_ascii_whitespace() && *c != '_')
+        .collect();
+    if digits.is_empty() {
+        return Err(BigNumberError::EmptyStr);
+    }
+    Ok(digits)
+}
+
+/// Byte order of a raw (wire) representation of a [`BigNumber`].
+///
+/// RFC 5054 and most implementations are big endian, some (e.g. WoW) are little endian.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ByteOrder {
+    #[default]
+    BigEndian,
+    LittleEndian,
 }
 
 /// new empty unsigned big number
@@ -30,11 +68,14 @@ impl Default for BigNumber {
 
 impl BigNumber {
     /// new random initialized big number
-    #[cfg(not(feature = "norand"))]
+    #[cfg(all(feature = "std", not(feature = "norand")))]
     pub fn new_rand(n_bytes: usize) -> Self {
-        let mut rng = thread_rng();
-        let a = rng.gen_biguint((n_bytes * 8) as u64);
-        Self(a)
+        Self::new_rand_with(&mut rand::thread_rng(), n_bytes)
+    }
+
+    /// new big number, randomly initialized by `rng`
+    pub fn new_rand_with<R: RngCore + CryptoRng + ?Sized>(rng: &mut R, n_bytes: usize) -> Self {
+        Self(rng.gen_biguint((n_bytes * 8) as u64))
     }
 
     /// [`raw`] is expected to be big endian
@@ -47,30 +88,92 @@ impl BigNumber {
         Self(BigUint::from_bytes_le(raw))
     }
 
+    /// [`raw`] is expected to be in the given byte order
+    pub fn from_bytes(raw: &[u8], order: ByteOrder) -> Self {
+        match order {
+            ByteOrder::BigEndian => Self::from_bytes_be(raw),
+            ByteOrder::LittleEndian => Self::from_bytes_le(raw),
+        }
+    }
+
+    /// finalizes the hasher, the digest is read as big endian
+    pub fn from_digest<D: Digest>(hasher: D) -> Self {
+        Self::from_bytes_be(hasher.finalize().as_slice())
+    }
+
     /// from a hex string, hex strings are always big endian:
     /// High
     ///    -> Low
     ///  "123acab"
-    pub fn from_hex_str_be(str: &str) -> std::result::Result<Self, BigNumberError> {
-        let str = if str.len() % 2 != 0 {
-            format!("{:0>len$}", str, len = (str.len() / 2 + 1) * 2)
-        } else {
-            str.to_owned()
-        };
+    ///
+    /// an optional `0x` prefix is stripped, ASCII whitespace (e.g. the line breaks
+    /// of the RFC listings) and `_` separators are ignored
+    pub fn from_hex_str_be(str: &str) -> core::result::Result<Self, BigNumberError> {
+        Self::from_hex_str(str, ByteOrder::BigEndian)
+    }
 
-        Ok(Self::from_bytes_be(
-            hex::decode(str)
+    /// from a hex string whose bytes are in `order`, e.g. a little endian number
+    /// dumped byte by byte as hex; same input format as [`BigNumber::from_hex_str_be`]
+    pub fn from_hex_str(str: &str, order: ByteOrder) -> core::result::Result<Self, BigNumberError> {
+        let str = str.trim_start();
+        let str = str
+            .strip_prefix("0x")
+            .or_else(|| str.strip_prefix("0X"))
+            .unwrap_or(str);
+        let mut digits = digits_only(str)?;
+        if !digits.len().is_multiple_of(2) {
+            digits.insert(0, '0');
+        }
+
+        Ok(Self::from_bytes(
+            hex::decode(digits)
                 .map_err(|_| BigNumberError::InvalidHexStr)?
                 .as_slice(),
+            order,
         ))
     }
 
+    /// from a decimal string, ASCII whitespace and `_` separators are ignored
+    pub fn from_dec_str(str: &str) -> core::result::Result<Self, BigNumberError> {
+        let digits = digits_only(str)?;
+        BigUint::parse_bytes(digits.as_bytes(), 10)
+            .map(Self)
+            .ok_or(BigNumberError::InvalidDecStr)
+    }
+
+    /// `self^exponent % modulo`, on the backend selected by the `fixed-bignum`
+    /// feature, the only operation that the feature moves off `num-bigint`
+    ///
+    /// panics if `modulo` is zero, see [`BigNumber::checked_modpow`]
     pub fn modpow(&self, exponent: &Self, modulo: &Self) -> Self {
-        self.0.modpow(&exponent.0, &modulo.0).into()
+        Backend::modpow(&self.0, &exponent.0, &modulo.0).into()
+    }
+
+    /// same as [`BigNumber::modpow`], `None` if `modulo` is zero
+    pub fn checked_modpow(&self, exponent: &Self, modulo: &Self) -> Option<Self> {
+        (!modulo.is_zero()).then(|| self.modpow(exponent, modulo))
     }
 
     pub fn num_bytes(&self) -> usize {
-        (self.0.bits() as usize + 7) / 8
+        self.bits().div_ceil(8)
+    }
+
+    /// the number of significant bits, `0` for zero
+    pub fn bits(&self) -> usize {
+        self.0.bits() as usize
+    }
+
+    pub fn is_even(&self) -> bool {
+        !self.0.bit(0)
+    }
+
+    pub fn is_odd(&self) -> bool {
+        self.0.bit(0)
+    }
+
+    /// the wrapped `BigUint`, for the arithmetic that is not exposed here
+    pub fn as_biguint(&self) -> &BigUint {
+        &self.0
     }
 
     /// returns the byte vec in big endian byte order
@@ -79,24 +182,150 @@ impl BigNumber {
         self.0.to_bytes_be()
     }
 
+    /// returns the byte vec in big endian byte order, same as [`BigNumber::to_vec`]
+    pub fn to_vec_be(&self) -> Vec<u8> {
+        self.to_vec()
+    }
+
+    /// returns the byte vec in little endian byte order
+    pub fn to_vec_le(&self) -> Vec<u8> {
+        self.0.to_bytes_le()
+    }
+
+    /// returns the byte vec in the given byte order
+    pub fn to_bytes(&self, order: ByteOrder) -> Vec<u8> {
+        match order {
+            ByteOrder::BigEndian => self.to_vec_be(),
+            ByteOrder::LittleEndian => self.to_vec_le(),
+        }
+    }
+
     pub fn to_array<const N: usize>(&self) -> [u8; N] {
         self.to_array_pad_zero::<N>()
     }
 
+    /// big endian, padded by 0 in front, as `PAD()` in RFC 5054,
+    /// same as [`BigNumber::to_array_pad_zero`]
+    pub fn to_array_pad_zero_be<const N: usize>(&self) -> [u8; N] {
+        self.to_array_pad_zero::<N>()
+    }
+
+    /// little endian, padded by 0 at the end
+    pub fn to_array_pad_zero_le<const N: usize>(&self) -> [u8; N] {
+        let mut result = self.to_array_pad_zero::<N>();
+        result.reverse();
+        result
+    }
+
     /// returns the byte vec in big endian byte order, padded by 0 for `len` bytes
+    ///
+    /// **panics** if the number does not fit in `N` bytes,
+    /// see [`BigNumber::try_to_array_pad_zero`] for the fallible version
     pub fn to_array_pad_zero<const N: usize>(&self) -> [u8; N] {
-        // the initial implementation used wrongly little-indian
-        // big-endian padding is in front
-        let nb = self.num_bytes();
         // may happen if client and server not using same LEN,
         // better panic here, should be verified sooner
-        assert!(nb <= N, "Padding to {N} from {nb} bytes");
-        let offset = N - nb;
+        self.try_to_array_pad_zero::<N>()
+            .unwrap_or_else(|e| panic!("Padding failed: {e}"))
+    }
+
+    /// returns the byte vec in big endian byte order, padded by 0 for `len` bytes,
+    /// or an error if the number does not fit in `N` bytes
+    pub fn try_to_array_pad_zero<const N: usize>(
+        &self,
+    ) -> core::result::Result<[u8; N], BigNumberError> {
         let mut result = [0_u8; N];
-        for (i, x) in self.to_vec().iter().take(N).enumerate() {
-            result[i + offset] = *x;
+        self.write_padded_into(&mut result)?;
+        Ok(result)
+    }
+
+    /// writes the big endian bytes at the end of `out`, the bytes in front are
+    /// set to 0, as [`BigNumber::try_to_array_pad_zero`] but into a buffer of
+    /// the caller, without an intermediate allocation
+    pub fn write_padded_into(&self, out: &mut [u8]) -> core::result::Result<(), BigNumberError> {
+        // the initial implementation used wrongly little-indian
+        // big-endian padding is in front
+        let nb = self.num_bytes();
+        if nb > out.len() {
+            return Err(BigNumberError::TooLong {
+                given: nb,
+                expected: out.len(),
+            });
         }
-        result
+        out.fill(0);
+        // least significant digit first, written from the end of `out`
+        let mut end = out.len();
+        for digit in self.0.iter_u64_digits() {
+            let bytes = digit.to_be_bytes();
+            let n = bytes.len().min(end);
+            out[end - n..end].copy_from_slice(&bytes[bytes.len() - n..]);
+            end -= n;
+        }
+        Ok(())
+    }
+
+    /// compares in constant time (for a given length), both values are
+    /// padded to the same length before comparing all the bytes
+    pub fn constant_time_eq(&self, other: &BigNumber) -> bool {
+        let len = self.num_bytes().max(other.num_bytes());
+        let diff = self
+            .to_vec_pad_zero(len)
+            .iter()
+            .zip(other.to_vec_pad_zero(len).iter())
+            .fold(0_u8, |acc, (a, b)| acc | (a ^ b));
+        diff == 0
+    }
+
+    /// same as [`BigNumber::to_array_pad_zero`], for a length only known at runtime
+    pub fn to_vec_pad_zero(&self, len: usize) -> Vec<u8> {
+        self.try_to_vec_pad_zero(len)
+            .unwrap_or_else(|e| panic!("Padding failed: {e}"))
+    }
+
+    /// uppercase hex digits of the big endian bytes, left padded with zeros to
+    /// `len` bytes, e.g. for the fixed width fields of a protocol
+    pub fn to_hex_padded(&self, len: usize) -> core::result::Result<String, BigNumberError> {
+        Ok(hex::encode_upper(self.try_to_vec_pad_zero(len)?))
+    }
+
+    /// same as [`BigNumber::try_to_array_pad_zero`], for a length only known at runtime
+    pub fn try_to_vec_pad_zero(&self, len: usize) -> core::result::Result<Vec<u8>, BigNumberError> {
+        let mut result = vec![0_u8; len];
+        self.write_padded_into(&mut result)?;
+        Ok(result)
+    }
+
+    /// feeds the same bytes as [`BigNumber::try_to_vec_pad_zero`] into `hasher`,
+    /// without building the padded buffer
+    pub(crate) fn update_digest_pad_zero<D: Digest>(
+        &self,
+        hasher: &mut D,
+        len: usize,
+    ) -> core::result::Result<(), BigNumberError> {
+        const ZEROS: [u8; 64] = [0; 64];
+
+        let nb = self.num_bytes();
+        if nb > len {
+            return Err(BigNumberError::TooLong {
+                given: nb,
+                expected: len,
+            });
+        }
+        let mut padding = len - nb;
+        while padding > 0 {
+            let chunk = padding.min(ZEROS.len());
+            hasher.update(&ZEROS[..chunk]);
+            padding -= chunk;
+        }
+        // most significant digit first, without its leading zero bytes
+        let mut digits = self.0.iter_u64_digits().rev();
+        if let Some(top) = digits.next() {
+            let skip = top.leading_zeros() as usize / 8;
+            hasher.update(&top.to_be_bytes()[skip..]);
+        }
+        for digit in digits {
+            hasher.update(digit.to_be_bytes());
+        }
+        Ok(())
     }
 }
 
@@ -116,8 +345,43 @@ fn test_mod_exp() {
     );
 }
 
+#[test]
+fn should_not_modpow_zero_modulus() {
+    let a = BigNumber::from(6);
+    let p = BigNumber::from(3);
+    assert_eq!(
+        a.checked_modpow(&p, &BigNumber::from(7)),
+        Some(a.modpow(&p, &BigNumber::from(7)))
+    );
+    assert_eq!(a.checked_modpow(&p, &BigNumber::zero()), None);
+}
+
+#[test]
+fn should_compare_and_hash_by_value() {
+    use std::collections::{BTreeSet, HashMap};
+
+    let x = BigNumber::from_bytes_be(&[0x01, 0x02]);
+    let padded = BigNumber::from_bytes_be(&[0x00, 0x01, 0x02]);
+    assert_eq!(x, padded);
+    assert_eq!(x, BigNumber::from_bytes_le(&[0x02, 0x01, 0x00, 0x00]));
+
+    let mut seen = HashMap::new();
+    assert!(seen.insert(x.clone(), "first").is_none());
+    assert_eq!(seen.get(&padded), Some(&"first"));
+    assert_eq!(seen.insert(padded, "again"), Some("first"));
+    assert_eq!(seen.len(), 1);
+
+    let ordered: BTreeSet<_> = [0x0102, 0x05, 0x0101, 0x05].map(BigNumber::from).into();
+    let ordered: Vec<_> = ordered.into_iter().collect();
+    assert_eq!(
+        ordered,
+        [0x05, 0x0101, 0x0102].map(BigNumber::from).to_vec()
+    );
+    assert!(x > BigNumber::from(0xff));
+}
+
 impl Debug for BigNumber {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         write!(f, "BigNumber(\"{}\")", self)
     }
 }
@@ -144,7 +408,7 @@ impl<const N: usize> From<[u8; N]> for BigNumber {
 
 impl From<Sha1> for BigNumber {
     fn from(hasher: Sha1) -> Self {
-        hasher.finalize().as_slice().into()
+        Self::from_digest(hasher)
     }
 }
 
@@ -154,9 +418,10 @@ impl From<&[u8]> for BigNumber {
     }
 }
 
+/// uppercase hex digits of the big endian bytes, so always an even number of them
 impl From<&BigNumber> for String {
     fn from(x: &BigNumber) -> Self {
-        x.0.to_str_radix(16).to_uppercase()
+        hex::encode_upper(x.to_vec())
     }
 }
 
@@ -166,10 +431,28 @@ impl From<BigNumber> for String {
     }
 }
 
+/// so that generic code can take the numbers and the messages that wrap one
+/// alike, e.g. a [`PublicKey`](crate::PublicKey) or a [`ProofMessage`](crate::ProofMessage)
+///
+/// ```
+/// use chadehoc_srp6::*;
+///
+/// fn to_hex(number: impl AsRef<Proof>) -> String {
+///     number.as_ref().to_string()
+/// }
+/// assert_eq!(to_hex(Proof::from(42)), "2A");
+/// assert_eq!(to_hex(ProofMessage::from(Proof::from(42))), "2A");
+/// ```
+impl AsRef<BigNumber> for BigNumber {
+    fn as_ref(&self) -> &BigNumber {
+        self
+    }
+}
+
 impl TryFrom<&str> for BigNumber {
     type Error = BigNumberError;
 
-    fn try_from(value: &str) -> std::result::Result<Self, Self::Error> {
+    fn try_from(value: &str) -> core::result::Result<Self, Self::Error> {
         Self::from_hex_str_be(value)
     }
 }
@@ -184,7 +467,7 @@ impl TryFrom<String> for BigNumber {
 
 #[test]
 fn should_try_from_string() {
-    use std::convert::TryInto;
+    use core::convert::TryInto;
 
     let s = "ab11cd".to_string();
     let x: BigNumber = s.try_into().unwrap();
@@ -197,6 +480,24 @@ fn should_from_bytes() {
     assert_eq!(x.to_vec(), &[0xAB, 0x11, 0xCD]);
 }
 
+#[test]
+fn should_round_trip_byte_orders() {
+    let raw = [0_u8, 0, 0xab, 0x11, 0xcd];
+    let x = BigNumber::from_bytes_be(&raw);
+    assert_eq!(x.to_vec_be(), &raw[2..]);
+    assert_eq!(BigNumber::from_bytes_be(&x.to_vec_be()), x);
+    assert_eq!(x.to_array_pad_zero_be::<5>(), raw);
+    assert_eq!(x.to_vec_le(), &[0xcd, 0x11, 0xab]);
+    assert_eq!(BigNumber::from_bytes_le(&x.to_vec_le()), x);
+    assert_eq!(x.to_array_pad_zero_le::<5>(), [0xcd, 0x11, 0xab, 0, 0]);
+    for order in [ByteOrder::BigEndian, ByteOrder::LittleEndian] {
+        assert_eq!(BigNumber::from_bytes(&x.to_bytes(order), order), x);
+    }
+    let mut le = raw;
+    le.reverse();
+    assert_eq!(BigNumber::from_bytes(&le, ByteOrder::LittleEndian), x);
+}
+
 #[test]
 fn should_to_vec() {
     let x = BigNumber::from_hex_str_be("ab11cd").unwrap();
@@ -216,20 +517,217 @@ fn should_pad_0() {
     assert_eq!(x.to_array_pad_zero::<3>(), [0, 0x11, 0xcd_u8]);
 }
 
+#[test]
+fn should_not_pad_too_long() {
+    let x = BigNumber::from_bytes_be(&[1, 0x11, 0xcd]);
+    assert!(matches!(
+        x.try_to_array_pad_zero::<2>(),
+        Err(BigNumberError::TooLong {
+            given: 3,
+            expected: 2
+        })
+    ));
+    assert!(x.try_to_vec_pad_zero(2).is_err());
+    assert_eq!(x.try_to_vec_pad_zero(3).unwrap(), x.to_vec());
+}
+
+#[test]
+#[should_panic]
+fn should_panic_padding_too_long() {
+    BigNumber::from_bytes_be(&[1, 0x11, 0xcd]).to_array_pad_zero::<2>();
+}
+
+#[test]
+fn should_pad_zero_value() {
+    assert_eq!(BigNumber::zero().to_array_pad_zero::<2>(), [0_u8; 2]);
+    assert_eq!(BigNumber::zero().to_vec_pad_zero(2), vec![0_u8; 2]);
+}
+
+#[test]
+fn should_pad_0_vec() {
+    let x = BigNumber::from_bytes_be(&[0x11, 0xcd]);
+    assert_eq!(x.to_vec_pad_zero(3), vec![0, 0x11, 0xcd_u8]);
+    assert_eq!(x.to_vec_pad_zero(3), x.to_array_pad_zero::<3>());
+}
+
+#[test]
+fn should_write_padded_into() {
+    // more than one 64 bit digit, the top one not full
+    let raw: Vec<u8> = (1..=11).collect();
+    let x = BigNumber::from_bytes_be(&raw);
+    let mut out = [0xff_u8; 13];
+    x.write_padded_into(&mut out).unwrap();
+    assert_eq!(&out[..2], &[0, 0]);
+    assert_eq!(&out[2..], raw.as_slice());
+
+    let mut exact = [0_u8; 11];
+    x.write_padded_into(&mut exact).unwrap();
+    assert_eq!(exact.as_slice(), raw.as_slice());
+
+    assert_eq!(
+        x.write_padded_into(&mut [0_u8; 10]),
+        Err(BigNumberError::TooLong {
+            given: 11,
+            expected: 10
+        })
+    );
+    let mut zero = [0xff_u8; 3];
+    BigNumber::zero().write_padded_into(&mut zero).unwrap();
+    assert_eq!(zero, [0_u8; 3]);
+}
+
+#[test]
+fn should_compare_in_constant_time() {
+    let x = BigNumber::from_bytes_be(&[0x11, 0xcd]);
+    let y = BigNumber::from_bytes_be(&[0, 0, 0x11, 0xcd]);
+    assert!(x.constant_time_eq(&y));
+    assert!(y.constant_time_eq(&x));
+    assert!(!x.constant_time_eq(&BigNumber::from_bytes_be(&[0x11, 0xce])));
+    assert!(!x.constant_time_eq(&BigNumber::from_bytes_be(&[1, 0x11, 0xcd])));
+    assert!(BigNumber::default().constant_time_eq(&BigNumber::from(0)));
+}
+
 #[test]
 fn should_should_work_with_odd_byte_count() {
-    assert_eq!(BigNumber::from_hex_str_be("6").unwrap().to_string(), "6");
+    // displayed with an even number of digits, as the bytes of `to_vec`
+    assert_eq!(BigNumber::from_hex_str_be("6").unwrap().to_string(), "06");
+    assert_eq!(
+        BigNumber::from_hex_str_be("abc").unwrap().to_string(),
+        "0ABC"
+    );
+    assert_eq!(BigNumber::zero().to_string(), "00");
 }
-// endregion
 
-// region modulo
-impl Rem for &BigNumber {
-    type Output = BigNumber;
+#[test]
+fn should_format_as_hex() {
+    let x = BigNumber::from_hex_str_be("abc").unwrap();
+    assert_eq!(format!("{x:X}"), "0ABC");
+    assert_eq!(format!("{x:x}"), "0abc");
+    assert_eq!(format!("{x:#X}"), "0x0ABC");
+    assert_eq!(format!("{x:#x}"), "0x0abc");
+    assert_eq!(format!("{x:>6}"), "  0ABC");
+    assert_eq!(format!("{x:08X}"), "00000ABC");
+    assert_eq!(x.to_hex_padded(4).unwrap(), "00000ABC");
+    assert_eq!(
+        x.to_hex_padded(1),
+        Err(BigNumberError::TooLong {
+            given: 2,
+            expected: 1
+        })
+    );
+}
 
-    fn rem(self, rhs: &BigNumber) -> Self::Output {
-        (&self.0).rem(&rhs.0).into()
+#[test]
+fn should_round_trip_hex_with_leading_zero_bytes() {
+    for zeros in 1..=3 {
+        let mut bytes = vec![0; zeros];
+        bytes.extend([0x0f, 0xa0, 0x01]);
+        let x = BigNumber::from_bytes_be(&bytes);
+        let padded = x.to_hex_padded(bytes.len()).unwrap();
+        assert_eq!(padded, hex::encode_upper(&bytes));
+        assert_eq!(BigNumber::from_hex_str_be(&padded).unwrap(), x);
+        assert_eq!(BigNumber::from_hex_str_be(&x.to_string()).unwrap(), x);
+        assert_eq!(x.to_string(), hex::encode_upper(x.to_vec_be()));
+    }
+}
+
+#[test]
+fn should_parse_lenient_hex_strings() {
+    let x = BigNumber::from_bytes_be(&[0x0a, 0xb1, 0x1c, 0xd0]);
+    for str in [
+        "ab11cd0",
+        "0xab11cd0",
+        "0XAB11CD0",
+        "  0xab11cd0\n",
+        "ab11 cd0",
+        "a_b11_cd0",
+        "ab\r\n11\tcd0",
+    ] {
+        assert_eq!(BigNumber::from_hex_str_be(str).unwrap(), x, "{str:?}");
+    }
+    assert_eq!(BigNumber::try_from("0x 6").unwrap().to_string(), "06");
+}
+
+#[test]
+fn should_parse_little_endian_hex_strings() {
+    let x = BigNumber::from_hex_str("0x0100ff", ByteOrder::LittleEndian).unwrap();
+    assert_eq!(x, BigNumber::from_bytes_be(&[0xff, 0x00, 0x01]));
+    assert_eq!(
+        BigNumber::from_hex_str("ab11cd0", ByteOrder::BigEndian),
+        BigNumber::from_hex_str_be("ab11cd0")
+    );
+}
+
+#[test]
+fn should_reject_invalid_hex_strings() {
+    for str in ["", "  \n", "0x", "0x_", "_"] {
+        assert!(
+            matches!(
+                BigNumber::from_hex_str_be(str),
+                Err(BigNumberError::EmptyStr)
+            ),
+            "{str:?}"
+        );
+    }
+    for str in ["xyz", "0xab0x", "ab-cd", "00x1", "-1"] {
+        assert!(
+            matches!(
+                BigNumber::from_hex_str_be(str),
+                Err(BigNumberError::InvalidHexStr)
+            ),
+            "{str:?}"
+        );
+    }
+}
+
+#[test]
+fn should_parse_rfc_prime_with_line_breaks() {
+    use crate::primitives::OpenConstants;
+    // as copied from RFC 5054 appendix A
+    let listing = "
+        AC6BDB41 324A9A9B F166DE5E 1389582F AF72B665 1987EE07 FC319294
+        3DB56050 A37329CB B4A099ED 8193E075 7767A13D D52312AB 4B03310D
+        CD7F48A9 DA04FD50 E8083969 EDB767B0 CF609517 9A163AB3 661A05FB
+        D5FAAAE8 2918A996 2F0B93B8 55F97993 EC975EEA A80D740A DBF4FF74
+        7359D041 D5C33EA7 1D281E44 6B14773B CA97B43A 23FB8016 76BD207A
+        436C6481 F1D2B907 8717461A 5B9D32E6 88F87748 544523B5 24B0D57D
+        5EA77A27 75D2ECFA 032CFBDB F52FB378 61602790 04E57AE6 AF874E73
+        03CE5329 9CCC041C 7BC308D8 2A5698F3 A8D0C382 71AE35F8 E9DBFBB6
+        94B5C803 D89F7AE4 35DE236D 525F5475 9B65E372 FCD68EF2 0FA7111F
+        9E4AFF73
+    ";
+    let n = BigNumber::from_hex_str_be(listing).unwrap();
+    assert_eq!(n, *OpenConstants::<256>::default().module);
+    assert_eq!(BigNumber::from_hex_str_be(&n.to_string()).unwrap(), n);
+}
+
+#[test]
+fn should_parse_decimal_strings() {
+    let x = BigNumber::from(1234567u32);
+    for str in ["1234567", " 1_234_567\n", "1 234 567"] {
+        assert_eq!(BigNumber::from_dec_str(str).unwrap(), x, "{str:?}");
+    }
+    assert_eq!(
+        BigNumber::from_dec_str("18446744073709551616").unwrap(),
+        BigNumber::from_hex_str_be("0x1_0000_0000_0000_0000").unwrap()
+    );
+    assert!(matches!(
+        BigNumber::from_dec_str(" "),
+        Err(BigNumberError::EmptyStr)
+    ));
+    for str in ["12a", "0x12", "-1", "1.5"] {
+        assert!(
+            matches!(
+                BigNumber::from_dec_str(str),
+                Err(BigNumberError::InvalidDecStr)
+            ),
+            "{str:?}"
+        );
     }
 }
+// endregion
+
+// region modulo
 #[test]
 fn should_modulo_ref() {
     let a = &BigNumber::from(10);
@@ -243,77 +741,686 @@ fn should_modulo() {
 }
 // endregion
 
-// region mul, add, sub
-impl Mul for BigNumber {
-    type Output = Self;
+// region modular exponentiation backend
+#[cfg(feature = "fixed-bignum")]
+mod fixed;
 
-    fn mul(self, rhs: Self) -> Self::Output {
-        (self.0 * rhs.0).into()
+/// The modular exponentiation of [`BigNumber::modpow`], the bulk of the
+/// handshake, selected at compile time
+pub(crate) trait ModPowBackend {
+    /// `base^exponent % modulus`
+    fn modpow(base: &BigUint, exponent: &BigUint, modulus: &BigUint) -> BigUint;
+}
+
+/// `num-bigint`, the intermediate numbers are allocated on the heap
+pub(crate) struct HeapBackend;
+
+impl ModPowBackend for HeapBackend {
+    fn modpow(base: &BigUint, exponent: &BigUint, modulus: &BigUint) -> BigUint {
+        base.modpow(exponent, modulus)
     }
 }
 
-impl Mul for &BigNumber {
-    type Output = BigNumber;
+#[cfg(not(feature = "fixed-bignum"))]
+type Backend = HeapBackend;
+#[cfg(feature = "fixed-bignum")]
+type Backend = fixed::FixedBackend;
+// endregion
 
-    fn mul(self, rhs: Self) -> Self::Output {
-        (&self.0 * &rhs.0).into()
+// region fixed base exponentiation
+/// bits of the exponent per row of a [`FixedBasePowTable`]
+const POW_TABLE_WINDOW: usize = 4;
+
+/// Precomputed powers of a fixed base `g` modulo `N`, for a faster `g^e % N`
+///
+/// Row `i` holds `g^(j * 16^i) % N` for `j` in `0..16`, so that `g^e` is a product
+/// of one entry per 4 bits of `e`, instead of a square (and multiply) per bit.
+/// It is big: 16 numbers of the size of `N` per 4 bits of exponent, e.g. 2 MiB
+/// for 2048 bits.
+#[derive(Clone)]
+pub struct FixedBasePowTable {
+    base: BigNumber,
+    modulus: BigNumber,
+    rows: Vec<Vec<BigUint>>,
+}
+
+impl FixedBasePowTable {
+    /// for exponents of at most `max_exponent_bits` bits, longer ones fall back
+    /// to a plain modular exponentiation
+    pub fn new(base: &BigNumber, modulus: &BigNumber, max_exponent_bits: usize) -> Self {
+        let n = &modulus.0;
+        let mut rows = Vec::with_capacity(max_exponent_bits.div_ceil(POW_TABLE_WINDOW));
+        // g^(16^i)
+        let mut g_i = &base.0 % n;
+        for _ in 0..rows.capacity() {
+            let mut row = Vec::with_capacity(1 << POW_TABLE_WINDOW);
+            row.push(BigUint::from(1u32) % n);
+            for j in 1..(1 << POW_TABLE_WINDOW) {
+                row.push(&row[j - 1] * &g_i % n);
+            }
+            g_i = &row[(1 << POW_TABLE_WINDOW) - 1] * &g_i % n;
+            rows.push(row);
+        }
+        Self {
+            base: base.clone(),
+            modulus: modulus.clone(),
+            rows,
+        }
+    }
+
+    /// `g^exponent % N`
+    pub fn pow(&self, exponent: &BigNumber) -> BigNumber {
+        if exponent.0.bits() as usize > self.rows.len() * POW_TABLE_WINDOW {
+            return self.base.modpow(exponent, &self.modulus);
+        }
+        let n = &self.modulus.0;
+        let digits = exponent
+            .0
+            .to_bytes_le()
+            .into_iter()
+            .flat_map(|byte| [byte & 0xf, byte >> 4]);
+        let mut result = BigUint::from(1u32) % n;
+        for (row, digit) in self.rows.iter().zip(digits) {
+            if digit != 0 {
+                result = result * &row[digit as usize] % n;
+            }
+        }
+        result.into()
+    }
+
+    /// whether the table was computed for `base` and `modulus`
+    pub(crate) fn is_for(&self, base: &BigNumber, modulus: &BigNumber) -> bool {
+        &self.base == base && &self.modulus == modulus
+    }
+}
+
+/// the rows are not printed
+impl Debug for FixedBasePowTable {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "FixedBasePowTable({:?}, {:?}, {} rows)",
+            self.base,
+            self.modulus,
+            self.rows.len()
+        )
+    }
+}
+
+#[cfg(test)]
+fn check_pow_table(g: &BigNumber, n: &BigNumber, len: usize) {
+    use rand::{rngs::StdRng, SeedableRng};
+    let table = FixedBasePowTable::new(g, n, len * 8);
+    let mut rng = StdRng::seed_from_u64(len as u64);
+    for n_bytes in [1, 20, len / 2, len] {
+        let e = BigNumber::new_rand_with(&mut rng, n_bytes);
+        assert_eq!(table.pow(&e), g.modpow(&e, n), "{n_bytes} bytes");
+    }
+    for e in [0u32, 1, 2, 15, 16, 17] {
+        let e = BigNumber::from(e);
+        assert_eq!(table.pow(&e), g.modpow(&e, n));
     }
+    // longer than the table
+    let e = BigNumber::new_rand_with(&mut rng, len + 1);
+    assert_eq!(table.pow(&e), g.modpow(&e, n));
 }
 
 #[test]
-fn test_big_num_mul() {
-    let a = BigNumber::from(4);
-    let b = BigNumber::from(2);
-    let exp = BigNumber::from(8);
-    assert_eq!(a * b, exp);
+fn should_pow_with_table_2048() {
+    let constants = crate::primitives::OpenConstants::<256>::default();
+    check_pow_table(&constants.generator, &constants.module, 256);
+}
+
+#[test]
+fn should_pow_with_table_4096() {
+    let constants = crate::primitives::OpenConstants::<512>::default();
+    check_pow_table(&constants.generator, &constants.module, 512);
+}
+// endregion
+
+// region stepped exponentiation
+#[cfg(feature = "std")]
+/// bits of the exponent per window of a [`ModPowStepper`]
+const STEPPED_WINDOW: usize = 4;
+
+#[cfg(feature = "std")]
+/// windows of the exponent per limb of 64 bits
+const WINDOWS_PER_LIMB: usize = 64 / STEPPED_WINDOW;
+
+#[cfg(feature = "std")]
+/// `base^exponent % modulus` with a fixed window of 4 bits, computed a few
+/// limbs of the exponent at a time, see [`ModPowStepper::step`]
+///
+/// Between the steps it keeps the accumulator, the powers `base^j` for `j` in
+/// `0..16` and the windows of the exponent not processed yet. It gives the same
+/// result as [`BigNumber::modpow`], somewhat slower.
+#[derive(Clone)]
+pub(crate) struct ModPowStepper {
+    modulus: BigUint,
+    powers: Vec<BigUint>,
+    /// the windows of the exponent, most significant first
+    digits: Vec<u8>,
+    next: usize,
+    acc: BigUint,
+}
+
+#[cfg(feature = "std")]
+impl ModPowStepper {
+    pub(crate) fn new(base: &BigNumber, exponent: &BigNumber, modulus: &BigNumber) -> Self {
+        let n = &modulus.0;
+        let base = &base.0 % n;
+        let mut powers = Vec::with_capacity(1 << STEPPED_WINDOW);
+        powers.push(BigUint::from(1u32) % n);
+        for j in 1..(1 << STEPPED_WINDOW) {
+            powers.push(&powers[j - 1] * &base % n);
+        }
+        let digits = exponent
+            .0
+            .to_bytes_be()
+            .into_iter()
+            .flat_map(|byte| [byte >> 4, byte & 0xf])
+            .skip_while(|digit| *digit == 0)
+            .collect();
+        Self {
+            acc: powers[0].clone(),
+            modulus: n.clone(),
+            powers,
+            digits,
+            next: 0,
+        }
+    }
+
+    /// processes at most `max_limbs` limbs of 64 bits of the exponent, returns
+    /// how many were processed
+    pub(crate) fn step(&mut self, max_limbs: usize) -> usize {
+        let end = self
+            .next
+            .saturating_add(max_limbs.saturating_mul(WINDOWS_PER_LIMB))
+            .min(self.digits.len());
+        let limbs = (end - self.next).div_ceil(WINDOWS_PER_LIMB);
+        let n = &self.modulus;
+        for &digit in &self.digits[self.next..end] {
+            for _ in 0..STEPPED_WINDOW {
+                self.acc = &self.acc * &self.acc % n;
+            }
+            if digit != 0 {
+                self.acc = &self.acc * &self.powers[usize::from(digit)] % n;
+            }
+        }
+        self.next = end;
+        limbs
+    }
+
+    /// the limbs of the exponent left for the next steps
+    pub(crate) fn remaining_limbs(&self) -> usize {
+        (self.digits.len() - self.next).div_ceil(WINDOWS_PER_LIMB)
+    }
+
+    /// `base^exponent % modulus`, once the whole exponent is processed
+    pub(crate) fn result(&self) -> Option<BigNumber> {
+        (self.next == self.digits.len()).then(|| self.acc.clone().into())
+    }
 }
 
-impl Add for BigNumber {
-    type Output = Self;
+/// the windows of the exponent are wiped, it is usually a private key
+#[cfg(all(feature = "std", feature = "zeroize"))]
+impl Drop for ModPowStepper {
+    fn drop(&mut self) {
+        self.digits.zeroize();
+    }
+}
 
-    fn add(self, rhs: Self) -> Self::Output {
-        self.0.add(rhs.0).into()
+#[cfg(test)]
+fn check_stepped_modpow(base: &BigNumber, n: &BigNumber, len: usize) {
+    use rand::{rngs::StdRng, SeedableRng};
+    let mut rng = StdRng::seed_from_u64(len as u64);
+    for n_bytes in [0, 1, 8, 9, len / 2, len] {
+        let e = BigNumber::new_rand_with(&mut rng, n_bytes);
+        let base = &BigNumber::new_rand_with(&mut rng, len) + base;
+        let expected = base.modpow(&e, n);
+        for max_limbs in [1, 3, usize::MAX] {
+            let mut stepper = ModPowStepper::new(&base, &e, n);
+            let total = stepper.remaining_limbs();
+            let mut steps = 0;
+            while stepper.result().is_none() {
+                assert!(stepper.step(max_limbs) > 0);
+                steps += 1;
+            }
+            assert_eq!(steps, total.div_ceil(max_limbs));
+            assert_eq!(stepper.remaining_limbs(), 0);
+            assert_eq!(stepper.result(), Some(expected.clone()), "{n_bytes} bytes");
+        }
     }
 }
-impl<'b> Add<&'b BigNumber> for &BigNumber {
-    type Output = BigNumber;
 
-    fn add(self, rhs: &'b BigNumber) -> Self::Output {
-        (&self.0).add(&rhs.0).into()
+#[test]
+fn should_step_modpow_2048() {
+    let constants = crate::primitives::OpenConstants::<256>::default();
+    check_stepped_modpow(&constants.generator, &constants.module, 256);
+}
+
+#[test]
+fn should_step_modpow_8192() {
+    let constants = crate::primitives::OpenConstants::<1024>::default();
+    check_stepped_modpow(&constants.generator, &constants.module, 1024);
+}
+
+#[test]
+fn should_not_step_after_the_last_limb() {
+    let n = BigNumber::from(1_000_003);
+    let e = BigNumber::from_bytes_be(&[0xff; 8]);
+    let mut stepper = ModPowStepper::new(&BigNumber::from(2), &e, &n);
+    assert_eq!(stepper.step(0), 0);
+    assert_eq!(stepper.remaining_limbs(), 1);
+    assert_eq!(stepper.result(), None);
+    assert_eq!(stepper.step(5), 1);
+    assert_eq!(stepper.step(5), 0);
+    assert_eq!(stepper.result(), Some(BigNumber::from(2).modpow(&e, &n)));
+}
+// endregion
+
+// region primality
+/// odd primes below 256, to discard most candidates before Miller-Rabin
+const SMALL_PRIMES: [u32; 53] = [
+    3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97,
+    101, 103, 107, 109, 113, 127, 131, 137, 139, 149, 151, 157, 163, 167, 173, 179, 181, 191, 193,
+    197, 199, 211, 223, 227, 229, 233, 239, 241, 251,
+];
+
+/// `None` if `n` is divisible by a small prime, i.e. composite unless it is
+/// that prime, otherwise `Some(n)` is left to Miller-Rabin
+fn sieve(n: &BigUint) -> Option<bool> {
+    if *n < BigUint::from(4u32) {
+        return Some(*n >= BigUint::from(2u32));
+    }
+    if !n.bit(0) {
+        return Some(false);
+    }
+    for p in SMALL_PRIMES {
+        if (n % p).is_zero() {
+            return Some(*n == BigUint::from(p));
+        }
+    }
+    None
+}
+
+/// one round of Miller-Rabin with the base `a`, for `n - 1 = d * 2^s`
+fn miller_rabin_round(n: &BigUint, n_1: &BigUint, d: &BigUint, s: u64, a: &BigUint) -> bool {
+    let mut x = a.modpow(d, n);
+    if x == BigUint::from(1u32) || x == *n_1 {
+        return true;
+    }
+    for _ in 1..s {
+        x = &x * &x % n;
+        if x == *n_1 {
+            return true;
+        }
     }
+    false
 }
 
-impl Sub for BigNumber {
-    type Output = Self;
+impl BigNumber {
+    /// Miller-Rabin test with `rounds` random bases from `rng`, a composite
+    /// number passes with a probability of at most `4^-rounds`
+    pub fn is_probably_prime<R: RngCore + CryptoRng + ?Sized>(
+        &self,
+        rounds: usize,
+        rng: &mut R,
+    ) -> bool {
+        let n = &self.0;
+        if let Some(prime) = sieve(n) {
+            return prime;
+        }
+        let n_1 = n - 1u32;
+        let s = n_1.trailing_zeros().unwrap_or(0);
+        let d = &n_1 >> s;
+        let two = BigUint::from(2u32);
+        (0..rounds).all(|_| {
+            let a = rng.gen_biguint_range(&two, &n_1);
+            miller_rabin_round(n, &n_1, &d, s, &a)
+        })
+    }
+
+    /// `q = (self - 1) / 2` for an odd `self`, the order of the subgroup of the
+    /// squares when `self` is a safe prime
+    pub fn subgroup_order(&self) -> Self {
+        Self(&self.0 >> 1)
+    }
 
-    fn sub(self, rhs: Self) -> Self::Output {
-        self.0.sub(rhs.0).into()
+    /// same as [`BigNumber::is_probably_prime`], for both `self` and `(self - 1) / 2`
+    pub fn is_probably_safe_prime<R: RngCore + CryptoRng + ?Sized>(
+        &self,
+        rounds: usize,
+        rng: &mut R,
+    ) -> bool {
+        if self.0 < BigUint::from(5u32) {
+            return false;
+        }
+        let q = self.subgroup_order();
+        // the cheap checks of both first
+        sieve(&self.0) != Some(false)
+            && sieve(&q.0) != Some(false)
+            && q.is_probably_prime(rounds, rng)
+            && self.is_probably_prime(rounds, rng)
+    }
+
+    /// a random safe prime of exactly `bits` bits (at least 3), slow for big ones
+    #[cfg(feature = "group-generation")]
+    pub fn new_safe_prime_with<R: RngCore + CryptoRng + ?Sized>(
+        rng: &mut R,
+        bits: usize,
+        rounds: usize,
+    ) -> Self {
+        assert!(bits >= 3, "no safe prime below 5");
+        loop {
+            let mut q = rng.gen_biguint(bits as u64 - 1);
+            q.set_bit(bits as u64 - 2, true);
+            q.set_bit(0, true);
+            let n = Self((&q << 1) + 1u32);
+            // a single round discards the composites quickly
+            if n.is_probably_safe_prime(1, rng) && n.is_probably_safe_prime(rounds, rng) {
+                return n;
+            }
+        }
     }
 }
+
 #[test]
-fn should_subtract() {
-    let (a, b) = (BigNumber::from(6), BigNumber::from(1));
-    assert_eq!(a - b, BigNumber::from(5));
+fn should_test_primality() {
+    use rand::{rngs::StdRng, SeedableRng};
+    let mut rng = StdRng::seed_from_u64(1);
+    let primes = [2u32, 3, 5, 251, 257, 65537, 2_147_483_647];
+    for p in primes {
+        assert!(BigNumber::from(p).is_probably_prime(20, &mut rng), "{p}");
+    }
+    // with Carmichael numbers
+    let composites = [0u32, 1, 4, 9, 561, 1105, 62745, 65535, 4_294_967_295];
+    for c in composites {
+        assert!(!BigNumber::from(c).is_probably_prime(20, &mut rng), "{c}");
+    }
+    // 2^127 - 1 is prime, 2^128 + 1 is not
+    let m127 = BigNumber::from_hex_str_be(&format!("7{}", "f".repeat(31))).unwrap();
+    assert!(m127.is_probably_prime(20, &mut rng));
+    let f7 = BigNumber::from_hex_str_be(&format!("1{}1", "0".repeat(31))).unwrap();
+    assert!(!f7.is_probably_prime(20, &mut rng));
+
+    // 23 = 2 * 11 + 1, but 29 = 2 * 14 + 1
+    assert!(BigNumber::from(23).is_probably_safe_prime(20, &mut rng));
+    assert!(BigNumber::from(5).is_probably_safe_prime(20, &mut rng));
+    assert!(!BigNumber::from(29).is_probably_safe_prime(20, &mut rng));
+    assert!(!BigNumber::from(3).is_probably_safe_prime(20, &mut rng));
+    assert!(!m127.is_probably_safe_prime(20, &mut rng));
+}
+
+#[cfg(feature = "group-generation")]
+#[test]
+fn should_generate_safe_primes() {
+    use rand::{rngs::StdRng, SeedableRng};
+    let mut rng = StdRng::seed_from_u64(2);
+    for bits in [3, 8, 64, 128] {
+        let n = BigNumber::new_safe_prime_with(&mut rng, bits, 20);
+        assert_eq!(n.0.bits(), bits as u64);
+        assert!(n.is_probably_safe_prime(20, &mut rng));
+    }
 }
+// endregion
+
+// region mul, add, sub, rem
+/// implements the operator for the four combinations of owned and borrowed
+/// operands, with the ones of [`BigUint`]; `$check` runs first on both
+macro_rules! impl_binop {
+    ($trait:ident, $method:ident $(, $check:path)?) => {
+        impl $trait<BigNumber> for BigNumber {
+            type Output = BigNumber;
+
+            fn $method(self, rhs: BigNumber) -> Self::Output {
+                $($check(&self, &rhs);)?
+                self.0.$method(rhs.0).into()
+            }
+        }
+
+        impl<'b> $trait<&'b BigNumber> for BigNumber {
+            type Output = BigNumber;
+
+            fn $method(self, rhs: &'b BigNumber) -> Self::Output {
+                $($check(&self, rhs);)?
+                self.0.$method(&rhs.0).into()
+            }
+        }
 
-impl<'b> Sub<&'b BigNumber> for &BigNumber {
-    type Output = BigNumber;
+        impl $trait<BigNumber> for &BigNumber {
+            type Output = BigNumber;
 
-    fn sub(self, rhs: &'b BigNumber) -> Self::Output {
-        (&self.0).sub(&rhs.0).into()
+            fn $method(self, rhs: BigNumber) -> Self::Output {
+                $($check(self, &rhs);)?
+                (&self.0).$method(rhs.0).into()
+            }
+        }
+
+        impl<'b> $trait<&'b BigNumber> for &BigNumber {
+            type Output = BigNumber;
+
+            fn $method(self, rhs: &'b BigNumber) -> Self::Output {
+                $($check(self, rhs);)?
+                (&self.0).$method(&rhs.0).into()
+            }
+        }
+    };
+}
+
+/// `a - b` underflows if `b > a`, see [`BigNumber::checked_sub`] and
+/// [`BigNumber::sub_mod`] for when it may happen
+fn debug_assert_no_underflow(a: &BigNumber, b: &BigNumber) {
+    debug_assert!(a >= b, "BigNumber subtraction underflows");
+}
+
+impl_binop!(Add, add);
+impl_binop!(Sub, sub, debug_assert_no_underflow);
+impl_binop!(Mul, mul);
+impl_binop!(Rem, rem);
+
+impl BigNumber {
+    /// `self - rhs`, or `None` if `rhs > self`
+    pub fn checked_sub(&self, rhs: &Self) -> Option<Self> {
+        self.0.checked_sub(&rhs.0).map(Self)
+    }
+
+    /// `(self - rhs) % modulus`, also when `rhs % modulus > self % modulus`
+    pub fn sub_mod(&self, rhs: &Self, modulus: &Self) -> Self {
+        let (a, b) = (self % modulus, rhs % modulus);
+        match a.checked_sub(&b) {
+            Some(diff) => diff,
+            None => modulus - b + a,
+        }
     }
 }
+
+#[test]
+fn test_big_num_mul() {
+    let a = BigNumber::from(4);
+    let b = BigNumber::from(2);
+    let exp = BigNumber::from(8);
+    assert_eq!(a * b, exp);
+}
+
+#[test]
+fn should_subtract() {
+    let (a, b) = (BigNumber::from(6), BigNumber::from(1));
+    assert_eq!(a - b, BigNumber::from(5));
+}
+
 #[test]
 fn should_subtract_refs() {
     let (a, b) = (BigNumber::from(6), BigNumber::from(6));
     assert_eq!(&a - &b, BigNumber::from(0));
 }
+
+#[test]
+fn should_apply_operators_to_owned_and_borrowed() {
+    let (a, b) = (BigNumber::from(23), BigNumber::from(5));
+    let exp = BigNumber::from(28);
+    assert_eq!(a.clone() + b.clone(), exp);
+    assert_eq!(a.clone() + &b, exp);
+    assert_eq!(&a + b.clone(), exp);
+    assert_eq!(&a + &b, exp);
+    let exp = BigNumber::from(18);
+    assert_eq!(a.clone() - b.clone(), exp);
+    assert_eq!(a.clone() - &b, exp);
+    assert_eq!(&a - b.clone(), exp);
+    assert_eq!(&a - &b, exp);
+    let exp = BigNumber::from(115);
+    assert_eq!(a.clone() * b.clone(), exp);
+    assert_eq!(a.clone() * &b, exp);
+    assert_eq!(&a * b.clone(), exp);
+    assert_eq!(&a * &b, exp);
+    let exp = BigNumber::from(3);
+    assert_eq!(a.clone() % b.clone(), exp);
+    assert_eq!(a.clone() % &b, exp);
+    assert_eq!(&a % b.clone(), exp);
+    assert_eq!(&a % &b, exp);
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "BigNumber subtraction underflows")]
+fn should_assert_no_underflow() {
+    let _ = BigNumber::from(1) - BigNumber::from(2);
+}
+
+#[test]
+fn should_subtract_checked_and_modulo() {
+    let (a, b, n) = (BigNumber::from(3), BigNumber::from(5), BigNumber::from(7));
+    assert_eq!(b.checked_sub(&a), Some(BigNumber::from(2)));
+    assert_eq!(a.checked_sub(&a), Some(BigNumber::zero()));
+    assert_eq!(a.checked_sub(&b), None);
+    assert_eq!(b.sub_mod(&a, &n), BigNumber::from(2));
+    // 3 - 5 = -2 = 5 (mod 7)
+    assert_eq!(a.sub_mod(&b, &n), BigNumber::from(5));
+    // the operands are reduced first: 10 - 12 = 3 - 5 (mod 7)
+    assert_eq!(
+        BigNumber::from(10).sub_mod(&BigNumber::from(12), &n),
+        BigNumber::from(5)
+    );
+    assert_eq!(a.sub_mod(&a, &n), BigNumber::zero());
+}
 // endregion
 
+// region gcd and inverse
+impl BigNumber {
+    /// the greatest common divisor, `gcd(0, 0) = 0`
+    pub fn gcd(&self, other: &Self) -> Self {
+        let (mut a, mut b) = (self.clone(), other.clone());
+        while !b.is_zero() {
+            let r = &a % &b;
+            a = b;
+            b = r;
+        }
+        a
+    }
+
+    /// `x` such that `self * x = 1 (mod modulus)`, in `[0, modulus)`, by the
+    /// extended Euclidean algorithm
+    ///
+    /// `None` if `self` and `modulus` are not coprime, or `modulus` is zero
+    pub fn mod_inverse(&self, modulus: &Self) -> Option<Self> {
+        if modulus.is_zero() {
+            return None;
+        }
+        // invariant: r = s * self (mod modulus), for both rows
+        let (mut old_r, mut r) = (modulus.clone(), self % modulus);
+        let (mut old_s, mut s) = (Self::zero(), Self::from(1));
+        while !r.is_zero() {
+            let q = Self(&old_r.0 / &r.0);
+            let next_r = &old_r % &r;
+            let next_s = old_s.sub_mod(&(&q * &s), modulus);
+            old_r = core::mem::replace(&mut r, next_r);
+            old_s = core::mem::replace(&mut s, next_s);
+        }
+        (old_r == Self::from(1)).then(|| old_s % modulus)
+    }
+}
+
+#[test]
+fn should_compute_the_gcd() {
+    let gcd = |a: u32, b: u32| BigNumber::from(a).gcd(&BigNumber::from(b));
+    assert_eq!(gcd(12, 18), BigNumber::from(6));
+    assert_eq!(gcd(18, 12), BigNumber::from(6));
+    assert_eq!(gcd(17, 5), BigNumber::from(1));
+    assert_eq!(gcd(0, 7), BigNumber::from(7));
+    assert_eq!(gcd(7, 0), BigNumber::from(7));
+    assert_eq!(gcd(0, 0), BigNumber::zero());
+}
+
+#[test]
+fn should_invert_modulo() {
+    let n = BigNumber::from(7);
+    for a in 1..7 {
+        let a = BigNumber::from(a);
+        let inverse = a.mod_inverse(&n).unwrap();
+        assert!(inverse < n);
+        assert_eq!(&(&a * &inverse) % &n, BigNumber::from(1));
+    }
+    // reduced first
+    assert_eq!(
+        BigNumber::from(10).mod_inverse(&n),
+        Some(BigNumber::from(5))
+    );
+    // in the group of RFC 5054, `g^-1`
+    let constants = crate::primitives::OpenConstants::<256>::default();
+    let inverse = constants.generator.mod_inverse(&constants.module).unwrap();
+    assert_eq!(
+        &(&constants.generator * &inverse) % &*constants.module,
+        BigNumber::from(1)
+    );
+}
+
+#[test]
+fn should_not_invert_without_coprime() {
+    let n = BigNumber::from(12);
+    for a in [0, 2, 3, 4, 6, 8, 12] {
+        assert_eq!(BigNumber::from(a).mod_inverse(&n), None, "{a}");
+    }
+    assert_eq!(BigNumber::from(5).mod_inverse(&BigNumber::zero()), None);
+    // everything is 0 modulo 1, and 0 * 0 = 1 (mod 1)
+    assert_eq!(
+        BigNumber::from(5).mod_inverse(&BigNumber::from(1)),
+        Some(BigNumber::zero())
+    );
+}
+
+#[test]
+fn should_tell_the_parity_and_the_bits() {
+    assert!(BigNumber::zero().is_even());
+    assert!(BigNumber::from(7).is_odd());
+    assert!(!BigNumber::from(7).is_even());
+    assert_eq!(BigNumber::zero().bits(), 0);
+    assert_eq!(BigNumber::from(0x100).bits(), 9);
+    assert_eq!(BigNumber::from(0x100).num_bytes(), 2);
+    assert_eq!(
+        BigNumber::from(0x100).as_biguint(),
+        &BigUint::from(0x100_u32)
+    );
+}
+// endregion
+
+/// same as `{:X}`
 impl Display for BigNumber {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        let x: String = self.into();
-        write!(f, "{}", x)
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.pad(&String::from(self))
+    }
+}
+
+/// hex digits of the big endian bytes, `0x` prefixed with `{:#X}`
+impl UpperHex for BigNumber {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.pad_integral(true, "0x", &hex::encode_upper(self.to_vec()))
+    }
+}
+
+/// hex digits of the big endian bytes, `0x` prefixed with `{:#x}`
+impl LowerHex for BigNumber {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.pad_integral(true, "0x", &hex::encode(self.to_vec()))
     }
 }
 
@@ -340,6 +1447,212 @@ fn test_into_string_and_display() {
     );
 }
 
+impl BigNumber {
+    /// a short form for the error messages: the first 8 hex digits and the length
+    pub(crate) fn fingerprint(&self) -> String {
+        let hex: String = self.into();
+        let digits = hex.get(..8).unwrap_or(&hex);
+        let ellipsis = if hex.len() > 8 { "..." } else { "" };
+        alloc::format!("{digits}{ellipsis}, {} bytes", self.num_bytes())
+    }
+}
+
+#[test]
+fn should_fingerprint() {
+    let x = BigNumber::from_hex_str_be("AB11CD0123456789").unwrap();
+    assert_eq!(x.fingerprint(), "AB11CD01..., 8 bytes");
+    assert_eq!(BigNumber::from(0xab).fingerprint(), "AB, 1 bytes");
+}
+
+/// overwrites the digits in place before releasing them
+#[cfg(feature = "zeroize")]
+impl Zeroize for BigNumber {
+    fn zeroize(&mut self) {
+        let n_digits = self.0.iter_u32_digits().len();
+        self.0.assign_from_slice(&vec![0; n_digits]);
+    }
+}
+
+#[cfg(feature = "zeroize")]
+#[test]
+fn should_zeroize() {
+    let mut x = BigNumber::from_hex_str_be("ab11cd0123456789").unwrap();
+    x.zeroize();
+    assert!(x.is_zero());
+    assert_eq!(x.to_array_pad_zero::<8>(), [0_u8; 8]);
+}
+
+// region serde
+/// human readable formats get the (uppercase) hex string, as [`Display`],
+/// others get the big endian bytes
+impl Serialize for BigNumber {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&String::from(self))
+        } else {
+            serializer.serialize_bytes(&self.to_vec())
+        }
+    }
+}
+
+/// rejects the numbers longer than `max` bytes before they are decoded
+struct BigNumberVisitor {
+    max: usize,
+}
+
+impl BigNumberVisitor {
+    const UNBOUNDED: Self = Self { max: usize::MAX };
+
+    fn check_length<E: de::Error>(&self, given: usize) -> Result<(), E> {
+        if given > self.max {
+            return Err(E::custom(BigNumberError::TooLong {
+                given,
+                expected: self.max,
+            }));
+        }
+        Ok(())
+    }
+}
+
+impl<'de> Visitor<'de> for BigNumberVisitor {
+    type Value = BigNumber;
+
+    fn expecting(&self, formatter: &mut Formatter) -> core::fmt::Result {
+        formatter.write_str("a hex string or big endian bytes")
+    }
+
+    /// the separators and the leading zeros count in the length
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        self.check_length(v.len().div_ceil(2))?;
+        BigNumber::from_hex_str_be(v).map_err(E::custom)
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+        self.check_length(v.len())?;
+        Ok(BigNumber::from_bytes_be(v))
+    }
+
+    /// the former format: the u32 limbs of `BigUint`, least significant first
+    #[cfg(feature = "legacy-serde")]
+    fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut digits = Vec::new();
+        while let Some(digit) = seq.next_element::<u32>()? {
+            digits.push(digit);
+            self.check_length(4 * (digits.len() - 1) + 1)?;
+        }
+        let number = BigNumber::from(BigUint::new(digits));
+        self.check_length(number.num_bytes())?;
+        Ok(number)
+    }
+}
+
+/// deserializes with `visitor`, see [`BigNumber`]'s `Deserialize`
+fn deserialize_with<'de, D: Deserializer<'de>>(
+    deserializer: D,
+    visitor: BigNumberVisitor,
+) -> Result<BigNumber, D::Error> {
+    if deserializer.is_human_readable() {
+        #[cfg(feature = "legacy-serde")]
+        return deserializer.deserialize_any(visitor);
+        #[cfg(not(feature = "legacy-serde"))]
+        deserializer.deserialize_str(visitor)
+    } else {
+        deserializer.deserialize_bytes(visitor)
+    }
+}
+
+impl<'de> Deserialize<'de> for BigNumber {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_with(deserializer, BigNumberVisitor::UNBOUNDED)
+    }
+}
+
+/// A number (e.g. a [`PublicKey`](crate::PublicKey)) of at most `MAX` bytes,
+/// a longer one fails to deserialize with [`BigNumberError::TooLong`] before
+/// it is decoded
+///
+/// a hex string may have leading zeros up to `MAX` bytes, see [`crate::Bounded`]
+/// for the messages
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(transparent)]
+pub struct BoundedBigNumber<const MAX: usize>(pub BigNumber);
+
+impl<const MAX: usize> BoundedBigNumber<MAX> {
+    pub fn into_inner(self) -> BigNumber {
+        self.0
+    }
+}
+
+impl<'de, const MAX: usize> Deserialize<'de> for BoundedBigNumber<MAX> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_with(deserializer, BigNumberVisitor { max: MAX }).map(Self)
+    }
+}
+
+#[test]
+fn should_serialize_as_hex() {
+    let x = BigNumber::from_hex_str_be("0ab11cd").unwrap();
+    assert_eq!(serde_json::to_string(&x).unwrap(), "\"AB11CD\"");
+    let y: BigNumber = serde_json::from_str("\"ab11cd\"").unwrap();
+    assert_eq!(x, y);
+    let y: BigNumber = serde_json::from_str("\"b11cd\"").unwrap();
+    assert_eq!(y, BigNumber::from_bytes_be(&[0x0b, 0x11, 0xcd]));
+    assert!(serde_json::from_str::<BigNumber>("\"xyz\"").is_err());
+    assert!(serde_json::from_str::<BigNumber>("[1, 2]").is_err() || cfg!(feature = "legacy-serde"));
+}
+
+#[test]
+fn should_serialize_as_bytes() {
+    let x = BigNumber::from_hex_str_be("ab11cd").unwrap();
+    let encoded = bincode::serialize(&x).unwrap();
+    // u64 length, then the bytes
+    assert_eq!(encoded, [3, 0, 0, 0, 0, 0, 0, 0, 0xab, 0x11, 0xcd]);
+    assert_eq!(bincode::deserialize::<BigNumber>(&encoded).unwrap(), x);
+}
+
+#[test]
+fn should_bound_the_deserialized_length() {
+    let hex = "\"00AB11CD\"";
+    let x: BoundedBigNumber<4> = serde_json::from_str(hex).unwrap();
+    assert_eq!(
+        x.into_inner(),
+        BigNumber::from_hex_str_be("ab11cd").unwrap()
+    );
+    // the length is checked first, the invalid digits are not even read
+    let err = serde_json::from_str::<BoundedBigNumber<3>>("\"xxxxxxxx\"").unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("(4 bytes) does not fit in 3 bytes"),
+        "{err}"
+    );
+
+    let encoded = bincode::serialize(&BigNumber::from_bytes_be(&[1; 5])).unwrap();
+    assert!(bincode::deserialize::<BoundedBigNumber<5>>(&encoded).is_ok());
+    let err = bincode::deserialize::<BoundedBigNumber<4>>(&encoded).unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("(5 bytes) does not fit in 4 bytes"),
+        "{err}"
+    );
+    assert_eq!(
+        serde_json::to_string(&BoundedBigNumber::<4>(BigNumber::from(0xab))).unwrap(),
+        "\"AB\""
+    );
+}
+
+#[cfg(feature = "legacy-serde")]
+#[test]
+fn should_deserialize_legacy_limbs() {
+    // 0x0000_0001_ab11_cd00 as serialized by BigUint
+    let x: BigNumber = serde_json::from_str("[2870070528, 1]").unwrap();
+    assert_eq!(x, BigNumber::from_hex_str_be("1ab11cd00").unwrap());
+    let x: BigNumber = serde_json::from_str("\"1AB11CD00\"").unwrap();
+    assert_eq!(x, BigNumber::from_hex_str_be("1ab11cd00").unwrap());
+    assert!(serde_json::from_str::<BoundedBigNumber<5>>("[2870070528, 1]").is_ok());
+    assert!(serde_json::from_str::<BoundedBigNumber<4>>("[2870070528, 1]").is_err());
+}
+// endregion
+
 impl Zero for BigNumber {
     fn zero() -> Self {
         BigUint::zero().into()
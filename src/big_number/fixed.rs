@@ -0,0 +1,132 @@
+//! [`BigNumber::modpow`](super::BigNumber::modpow) on the fixed size numbers of
+//! `crypto-bigint`, with the `fixed-bignum` feature
+//!
+//! The numbers are copied into `Uint`s on the stack, of the smallest size that
+//! fits the modulus among the sizes of the groups, and only the result is
+//! allocated. The exponentiation is in Montgomery form, it needs an odd modulus:
+//! the others, and the numbers beyond 8192 bits, are left to [`HeapBackend`].
+//!
+//! Every exponent is raised over the full width of its `Uint`, twice the size of
+//! the modulus, in a time that depends on the size of the modulus only.
+
+use super::{HeapBackend, ModPowBackend};
+use crypto_bigint::modular::runtime_mod::{DynResidue, DynResidueParams};
+use crypto_bigint::{
+    Encoding, Uint, Word, U1024, U16384, U2048, U256, U3072, U4096, U512, U6144, U8192,
+};
+use num_bigint::BigUint;
+
+/// `crypto-bigint` for the odd moduli of at most 8192 bits
+pub(crate) struct FixedBackend;
+
+impl ModPowBackend for FixedBackend {
+    fn modpow(base: &BigUint, exponent: &BigUint, modulus: &BigUint) -> BigUint {
+        if modulus.bits() < 2 || !modulus.bit(0) {
+            return HeapBackend::modpow(base, exponent, modulus);
+        }
+        // e.g. `A * v^u` of the host, twice as long as `N`
+        if base.bits() > modulus.bits() {
+            return Self::modpow(&(base % modulus), exponent, modulus);
+        }
+        // the exponent may be a bit longer than `N`, e.g. `a + u * x` of the user
+        let pow = match modulus.bits() {
+            0..=256 => pow::<{ U256::LIMBS }, { U512::LIMBS }>,
+            257..=512 => pow::<{ U512::LIMBS }, { U1024::LIMBS }>,
+            513..=1024 => pow::<{ U1024::LIMBS }, { U2048::LIMBS }>,
+            1025..=2048 => pow::<{ U2048::LIMBS }, { U4096::LIMBS }>,
+            2049..=3072 => pow::<{ U3072::LIMBS }, { U6144::LIMBS }>,
+            3073..=4096 => pow::<{ U4096::LIMBS }, { U8192::LIMBS }>,
+            4097..=6144 => pow::<{ U6144::LIMBS }, { U16384::LIMBS }>,
+            6145..=8192 => pow::<{ U8192::LIMBS }, { U16384::LIMBS }>,
+            _ => return HeapBackend::modpow(base, exponent, modulus),
+        };
+        pow(base, exponent, modulus).unwrap_or_else(|| HeapBackend::modpow(base, exponent, modulus))
+    }
+}
+
+/// `base^exponent % modulus` with `LIMBS` for the modulus and `EXP_LIMBS` for
+/// the exponent, `None` if one of them does not fit
+fn pow<const LIMBS: usize, const EXP_LIMBS: usize>(
+    base: &BigUint,
+    exponent: &BigUint,
+    modulus: &BigUint,
+) -> Option<BigUint>
+where
+    Uint<LIMBS>: Encoding,
+{
+    let params = DynResidueParams::new(&to_uint::<LIMBS>(modulus)?);
+    let base = DynResidue::new(&to_uint::<LIMBS>(base)?, params);
+    let exponent = to_uint::<EXP_LIMBS>(exponent)?;
+    // over all the bits of `Uint<EXP_LIMBS>`, so that the time does not tell the
+    // length of a secret exponent
+    let result = base.pow(&exponent);
+    Some(BigUint::from_bytes_le(
+        result.retrieve().to_le_bytes().as_ref(),
+    ))
+}
+
+/// the 32 bit digits of `x` packed into the words of a `Uint`, on 32 or 64 bit
+/// targets alike, `None` if it does not fit
+fn to_uint<const LIMBS: usize>(x: &BigUint) -> Option<Uint<LIMBS>> {
+    if x.bits() > Uint::<LIMBS>::BITS as u64 {
+        return None;
+    }
+    let mut words = [Word::default(); LIMBS];
+    for (i, digit) in x.iter_u32_digits().enumerate() {
+        let shift = i * 32;
+        words[shift / Word::BITS as usize] |= Word::from(digit) << (shift % Word::BITS as usize);
+    }
+    Some(Uint::from_words(words))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::big_number::BigNumber;
+    use crate::primitives::OpenConstants;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    /// same results as `num-bigint` for the sizes of each `Uint`
+    #[test]
+    fn should_modpow_as_heap_backend() {
+        let mut rng = StdRng::seed_from_u64(5054);
+        for bytes in [1, 8, 31, 32, 33, 64, 128, 255, 256, 384, 512] {
+            let mut modulus = BigNumber::new_rand_with(&mut rng, bytes).0;
+            modulus.set_bit(0, true);
+            let base = BigNumber::new_rand_with(&mut rng, bytes).0;
+            let exponent = BigNumber::new_rand_with(&mut rng, bytes + 8).0;
+            assert_eq!(
+                FixedBackend::modpow(&base, &exponent, &modulus),
+                HeapBackend::modpow(&base, &exponent, &modulus),
+                "{bytes} bytes"
+            );
+        }
+    }
+
+    #[test]
+    fn should_modpow_edge_cases_as_heap_backend() {
+        let n = (*OpenConstants::<256>::default().module).clone().0;
+        let n_1 = &n - 1_u32;
+        let cases = [
+            // base longer than `N`, `A * v^u` of the host
+            (&n * &n_1, n_1.clone(), n.clone()),
+            // exponent three times as long as `N`, left to `num-bigint`
+            (BigUint::from(2_u32), &n * &n * &n, n.clone()),
+            (BigUint::from(0_u32), n_1.clone(), n.clone()),
+            (n_1.clone(), BigUint::from(0_u32), n.clone()),
+            // a short exponent, raised over the full width all the same
+            (n_1.clone(), BigUint::from(1_u32), n.clone()),
+            (n_1.clone(), n_1.clone(), n.clone()),
+            // even modulus and modulus 1, left to `num-bigint`
+            (BigUint::from(3_u32), n_1.clone(), n_1.clone()),
+            (BigUint::from(3_u32), n_1.clone(), BigUint::from(1_u32)),
+        ];
+        for (base, exponent, modulus) in cases {
+            assert_eq!(
+                FixedBackend::modpow(&base, &exponent, &modulus),
+                HeapBackend::modpow(&base, &exponent, &modulus)
+            );
+        }
+    }
+}
@@ -0,0 +1,339 @@
+//! `srp6-tool`, for operators (feature `cli`): creates the stored details of a
+//! user, checks them, prints the test vectors of RFC 5054 and runs one
+//! handshake over stdin and stdout, to debug the logins of other
+//! implementations.
+//!
+//! The messages of a handshake are read and printed one per line, in their hex
+//! form (e.g. `username:A`, see [`UserHandshake::to_hex`]). The prompts and the
+//! results go to stderr, so that stdout only has the messages. The session key
+//! `K` is a secret, only printed with `--show-key`.
+
+use chadehoc_srp6::hazmat::{self, testdata};
+use chadehoc_srp6::*;
+use clap::{Parser, Subcommand, ValueEnum};
+use sha1::Digest;
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+/// an error of the tool, printed before it fails
+type ToolResult<T = ()> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+#[derive(Parser)]
+#[command(
+    name = "srp6-tool",
+    version,
+    about = "SRP6 verifiers and handshakes for operators"
+)]
+struct Cli {
+    /// the group of RFC 5054, by its size in bits
+    #[arg(long, global = true, value_enum, default_value_t = Group::G2048)]
+    group: Group,
+    /// the hash function `H`
+    #[arg(long, global = true, value_enum, default_value_t = HashName::Sha1)]
+    hash: HashName,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Group {
+    #[value(name = "1024")]
+    G1024,
+    #[value(name = "1536")]
+    G1536,
+    #[value(name = "2048")]
+    G2048,
+    #[value(name = "3072")]
+    G3072,
+    #[value(name = "4096")]
+    G4096,
+    #[value(name = "6144")]
+    G6144,
+    #[value(name = "8192")]
+    G8192,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum HashName {
+    Sha1,
+    Sha256,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    /// the [`UserDetails`] as stored, e.g. for `check`
+    Json,
+    /// the salt and the verifier, one per line
+    Hex,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Role {
+    Server,
+    Client,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// creates the salt and the verifier of a user, the password is the first
+    /// line of stdin
+    Verifier {
+        #[arg(long)]
+        username: String,
+        /// the salt in hex instead of a random one, e.g. for test vectors
+        #[arg(long)]
+        salt: Option<String>,
+        #[arg(long, value_enum, default_value_t = Format::Json)]
+        format: Format,
+    },
+    /// checks the stored details of a user, as printed by `verifier`, against the group
+    Check {
+        /// a JSON file
+        #[arg(long)]
+        details: PathBuf,
+    },
+    /// prints the test vectors of RFC 5054 appendix B, as computed by this
+    /// crate; only for the 1024-bit group and SHA-1
+    Vectors,
+    /// runs one handshake over stdin and stdout, one hex message per line
+    Handshake {
+        #[arg(long, value_enum)]
+        role: Role,
+        /// the stored details of the user, a JSON file (server)
+        #[arg(long, required_if_eq("role", "server"))]
+        details: Option<PathBuf>,
+        /// the username (client), the password is the first line of stdin
+        #[arg(long, required_if_eq("role", "client"))]
+        username: Option<String>,
+        /// also prints the session key `K`, a secret: only to debug another
+        /// implementation, never on a shared terminal or in the logs
+        #[arg(long)]
+        show_key: bool,
+    },
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let result = match cli.hash {
+        HashName::Sha1 => run::<Sha1>(&cli),
+        HashName::Sha256 => run::<Sha256>(&cli),
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// calls `f::<LEN>` or `f::<LEN, D>` with the default constants of the group
+macro_rules! with_group {
+    ($group:expr, $f:ident$(::<$d:ty>)?($($arg:expr),*)) => {
+        match $group {
+            Group::G1024 => $f::<128 $(, $d)?>(&OpenConstants::default() $(, $arg)*),
+            Group::G1536 => $f::<192 $(, $d)?>(&OpenConstants::default() $(, $arg)*),
+            Group::G2048 => $f::<256 $(, $d)?>(&OpenConstants::default() $(, $arg)*),
+            Group::G3072 => $f::<384 $(, $d)?>(&OpenConstants::default() $(, $arg)*),
+            Group::G4096 => $f::<512 $(, $d)?>(&OpenConstants::default() $(, $arg)*),
+            Group::G6144 => $f::<768 $(, $d)?>(&OpenConstants::default() $(, $arg)*),
+            Group::G8192 => $f::<1024 $(, $d)?>(&OpenConstants::default() $(, $arg)*),
+        }
+    };
+}
+
+fn run<D: Digest>(cli: &Cli) -> ToolResult {
+    match &cli.command {
+        Command::Verifier {
+            username,
+            salt,
+            format,
+        } => with_group!(cli.group, verifier::<D>(username, salt.as_deref(), *format)),
+        Command::Check { details } => with_group!(cli.group, check(details)),
+        Command::Vectors => vectors(cli.group, cli.hash),
+        Command::Handshake {
+            role: Role::Server,
+            details,
+            show_key,
+            ..
+        } => with_group!(
+            cli.group,
+            serve::<D>(details.as_deref().unwrap(), *show_key)
+        ),
+        Command::Handshake {
+            role: Role::Client,
+            username,
+            show_key,
+            ..
+        } => with_group!(
+            cli.group,
+            authenticate::<D>(username.as_deref().unwrap(), *show_key)
+        ),
+    }
+}
+
+fn verifier<const LEN: usize, D: Digest>(
+    constants: &OpenConstants<LEN>,
+    username: UsernameRef,
+    salt: Option<&str>,
+    format: Format,
+) -> ToolResult {
+    let password = read_password(&mut io::stdin().lock())?;
+    let username = username.into();
+    let details = match salt {
+        Some(salt) => Srp6::<LEN, D>::generate_user_details_with_salt(
+            &username,
+            &password,
+            &Salt::from_hex_str_be(salt)?,
+            constants,
+        )?,
+        None => Srp6::<LEN, D>::generate_new_user_secrets(&username, &password, constants)?,
+    };
+    match format {
+        Format::Json => println!("{}", serde_json::to_string_pretty(&details)?),
+        Format::Hex => {
+            println!("salt = {}", details.salt);
+            println!("verifier = {}", details.verifier);
+        }
+    }
+    Ok(())
+}
+
+fn check<const LEN: usize>(constants: &OpenConstants<LEN>, path: &Path) -> ToolResult {
+    let details = read_details(path)?;
+    details.validate(constants)?;
+    println!("valid: {} ({})", details.username, details.fingerprint());
+    Ok(())
+}
+
+/// computes the values of RFC 5054 appendix B from its inputs
+#[allow(non_snake_case)]
+fn vectors(group: Group, hash: HashName) -> ToolResult {
+    if !matches!(group, Group::G1024) || hash != HashName::Sha1 {
+        return Err("RFC 5054 only has test vectors for the 1024-bit group and SHA-1".into());
+    }
+    let constants = OpenConstants::<128>::default();
+    let (N, g) = (constants.module(), &constants.generator);
+    let profile = CompatProfile::Rfc5054;
+    let s = Salt::from_bytes_be(&testdata::SALT);
+    let a = PrivateKey::from_bytes_be(&testdata::A_PRIVATE);
+    let b = PrivateKey::from_bytes_be(&testdata::B_PRIVATE);
+
+    let k = hazmat::calculate_k::<Sha1>(128, N, g, constants.variant, profile)?;
+    let x = hazmat::calculate_private_key_x::<Sha1>(
+        testdata::USERNAME,
+        testdata::PASSWORD,
+        &s,
+        profile,
+    );
+    let v = hazmat::calculate_password_verifier_v(N, g, None, &x);
+    let A = hazmat::calculate_pubkey_A(N, g, None, &a);
+    let B = hazmat::calculate_pubkey_B(N, g, None, &k, &v, &b);
+    let u = hazmat::calculate_u::<Sha1>(128, &A, &B, profile)?;
+    let S = hazmat::calculate_session_key_S_for_host::<Sha1>(128, N, &A, &B, &b, &v, profile)?;
+
+    println!("I = {}", testdata::USERNAME);
+    println!("P = {}", testdata::PASSWORD);
+    for (name, value) in [
+        ("s", &s),
+        ("k", &k),
+        ("x", &x),
+        ("v", &v),
+        ("a", &a),
+        ("b", &b),
+        ("A", &A),
+        ("B", &B),
+        ("u", &u),
+        ("S", &S),
+    ] {
+        println!("{name} = {value}");
+    }
+    Ok(())
+}
+
+/// the server side, for the user of the stored details
+fn serve<const LEN: usize, D: Digest>(
+    constants: &OpenConstants<LEN>,
+    path: &Path,
+    show_key: bool,
+) -> ToolResult {
+    let details = read_details(path)?;
+    let mut stdin = io::stdin().lock();
+    let user_handshake =
+        UserHandshake::from_hex::<LEN>(&read_line(&mut stdin, "user handshake (username:A)")?)?;
+    if user_handshake.username != details.username {
+        return Err(format!("unknown user {:?}", user_handshake.username).into());
+    }
+    let (server_handshake, srp6) = Srp6::<LEN, D>::new()
+        .for_user(&details)
+        .continue_handshake(&user_handshake.user_publickey, constants)?;
+    println!("{}", server_handshake.to_hex());
+
+    let proof = ProofMessage::from_hex::<D>(&read_line(&mut stdin, "user proof (M1)")?)?;
+    let outcome = srp6.verify_proof(&proof.proof)?;
+    println!(
+        "{}",
+        ProofMessage::from(outcome.server_proof().clone()).to_hex()
+    );
+    authenticated(outcome.strong_session_key(), show_key);
+    Ok(())
+}
+
+/// the client side, the password is the first line of stdin
+fn authenticate<const LEN: usize, D: Digest>(
+    constants: &OpenConstants<LEN>,
+    username: UsernameRef,
+    show_key: bool,
+) -> ToolResult {
+    let mut stdin = io::stdin().lock();
+    let password = read_password(&mut stdin)?;
+    let mut srp6_user = Srp6User::<LEN, D>::with_credentials(&username.into(), &password);
+    println!("{}", srp6_user.start_handshake(constants)?.to_hex());
+
+    let server_handshake =
+        ServerHandshake::from_hex::<LEN>(&read_line(&mut stdin, "server handshake (salt:B)")?)?;
+    let (proof, srp6_user) = srp6_user.update_handshake(&server_handshake, constants)?;
+    println!("{}", ProofMessage::from(proof).to_hex());
+
+    let strong_proof = ProofMessage::from_hex::<D>(&read_line(&mut stdin, "server proof (M2)")?)?;
+    let outcome = srp6_user.verify_proof(&strong_proof.strong_proof())?;
+    authenticated(outcome.strong_session_key(), show_key);
+    Ok(())
+}
+
+/// the result of a handshake, with `K` only if asked for
+fn authenticated(key: &StrongSessionKey, show_key: bool) {
+    if show_key {
+        eprintln!("authenticated, K = {key}");
+    } else {
+        eprintln!("authenticated");
+    }
+}
+
+fn read_details(path: &Path) -> ToolResult<UserDetails> {
+    Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?)
+}
+
+/// **Note**: the password is echoed on a terminal
+fn read_password(stdin: &mut impl BufRead) -> ToolResult<Password> {
+    let password = read_line(stdin, "password")?;
+    if password.is_empty() {
+        return Err(Srp6Error::EmptyPassword.into());
+    }
+    Ok(password.into())
+}
+
+/// the next line of stdin without its line break, `what` is prompted for on a
+/// terminal
+fn read_line(stdin: &mut impl BufRead, what: &str) -> ToolResult<String> {
+    if io::stdin().is_terminal() {
+        eprint!("{what}: ");
+        io::stderr().flush()?;
+    }
+    let mut line = String::new();
+    if stdin.read_line(&mut line)? == 0 {
+        return Err(format!("stdin closed, expected the {what}").into());
+    }
+    Ok(line.trim_end_matches(['\r', '\n']).to_owned())
+}
@@ -0,0 +1,372 @@
+/*!
+Compatibility with other SRP6a implementations
+
+SRP6a leaves some details open: how the numbers are padded before hashing,
+what goes into the proofs, and what the session key `K` is. This crate follows
+RFC 5054 and its own choices for the proofs, other libraries made other ones.
+A [`CompatProfile`] selects the conventions of a given library, for both sides,
+see [`Srp6::with_profile`](crate::Srp6::with_profile) and
+[`Srp6User::with_profile`](crate::Srp6User::with_profile).
+
+`PAD(x)` is `x` in big endian padded to the length of `N`, `x` alone is in big
+endian without leading zeros, `H(I)` is the hash of the bytes of `I`.
+
+```plain
+          Rfc5054                   Tssrp6a                PySrp
+k         H(N | PAD(g))             H(PAD(N) | PAD(g))     H(N | g)
+u         H(PAD(A) | PAD(B))        H(PAD(A) | PAD(B))     H(A | B)
+x         H(s | H(I | ":" | p))     H(s | H(I | ":" | p))  H(s | H(I | ":" | p)) (1)
+K         SHA_Interleave(S)         H(S)                   H(S)
+M         H(H(N) xor H(g) | H(I)    H(A | B | S)           H(H(N) xor H(g) | H(I)
+            | s | PAD(A) | PAD(B)                            | s | A | B | K)
+            | K)
+M2        H(PAD(A) | M | K)         H(A | M | S)           H(A | M | K)
+```
+
+(1) the inner hash without its leading zero bytes, as the `srp` Python package
+converts it to an integer in between.
+
+`PAD(N)` is `N` itself when `N` has the length of the group, as checked by
+[`OpenConstants::try_new`](crate::OpenConstants::try_new), so both forms of `k`
+agree, and match RFC 5054 appendix B. RFC 5054 pads `g` in `k`, e.g. for its
+groups with `g = 5`, but not in `H(g)` of `M`, which comes from RFC 2945. The
+salt is hashed without its leading zero bytes by all the profiles.
+
+The profiles follow the formulas of those libraries as read from their sources.
+The handshakes captured by running the libraries themselves are replayed by
+`tests/interop.rs`, from the fixtures of `tests/vectors/interop`.
+
+The profiles only change the hashing, the group and the hash function are still
+given by [`OpenConstants`](crate::OpenConstants) and `D`: `tssrp6a` uses SHA-512
+by default and the `srp` package SHA-1, both must be configured alike on the other
+side. `PySrp` is the default mode of the `srp` package, without `rfc5054_enable()`.
+
+[`CompatProfile::LegacyInterleave`] is this crate before it followed RFC 2945 for
+`K`: it interleaved `PAD(S)` instead of `S` without its leading zero bytes. Both
+give the same `K` unless `S` starts with a zero byte, about once in 256
+handshakes, so a server upgraded from such a version must keep that profile for
+the users whose clients were not upgraded.
+
+The proofs themselves can be HMACs keyed by `K` instead of hashes, as in RFC 2945
+and some newer deployments: a [`ProofScheme`] selects them for both sides, see
+[`Srp6::with_proof_scheme`](crate::Srp6::with_proof_scheme) and
+[`Srp6User::with_proof_scheme`](crate::Srp6User::with_proof_scheme). The HMAC
+matches the hash function `D`, e.g. HMAC-SHA1 for SHA-1, and its key is `K`
+padded to twice the hash output. The profile still gives `k`, `u`, `x` and `K`.
+
+```plain
+          HashConcat                Hmac
+M         as the profile            HMAC(PAD(K), H(N) xor H(g) | H(I) | s
+                                      | PAD(A) | PAD(B))
+M2        as the profile            HMAC(PAD(K), PAD(A) | PAD(M))
+```
+
+`M` is padded to the hash output in `M2`. Both sides must use the same scheme,
+otherwise the proof is rejected with [`Srp6Error::InvalidProof`](crate::Srp6Error::InvalidProof).
+
+The stretched private key `x` (see [`KdfParams`](crate::KdfParams)) is an extension
+of this crate, it is the same for all profiles.
+*/
+use serde::{Deserialize, Serialize};
+
+/// The hashing conventions of a peer implementation, see the [module](self) documentation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum CompatProfile {
+    /// this crate, padded as in RFC 5054
+    #[default]
+    Rfc5054,
+    /// the `tssrp6a` TypeScript library
+    Tssrp6a,
+    /// the `srp` Python package
+    PySrp,
+    /// earlier versions of this crate, `K` interleaved over `PAD(S)`, otherwise as
+    /// [`Rfc5054`](Self::Rfc5054)
+    LegacyInterleave,
+}
+
+impl CompatProfile {
+    /// whether `A`, `B` and `g` are padded to the length of `N` in `k` and `u`
+    pub(crate) fn pads_hash_inputs(self) -> bool {
+        self != CompatProfile::PySrp
+    }
+
+    /// whether `N` itself is padded in `k`, only different for an `N` shorter
+    /// than the group
+    pub(crate) fn pads_modulus_in_k(self) -> bool {
+        self == CompatProfile::Tssrp6a
+    }
+}
+
+/// How the proofs `M` and `M2` are calculated, see the [module](self) documentation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ProofScheme {
+    /// hashes of the concatenated values, as given by the [`CompatProfile`]
+    #[default]
+    HashConcat,
+    /// HMACs keyed by the session key `K`, as in RFC 2945
+    Hmac,
+}
+
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+    //! The vectors below use the 1024-bit group and the inputs of RFC 5054
+    //! appendix B. They were computed with a transcription of the formulas of
+    //! `tssrp6a` (`routines.ts`) and of the `srp` package (`_pysrp.py`), not
+    //! by running both libraries: they check the formulas as read, they are not
+    //! interoperability vectors.
+
+    use super::*;
+    use crate::api::host::calculate_host_keys;
+    use crate::api::user::calculate_user_keys;
+    use crate::hash::{Digest, Sha1, Sha256};
+    use crate::primitives::*;
+    use crate::protocol_details::testdata;
+    use hex_literal::hex;
+
+    struct Vectors {
+        k: &'static [u8],
+        x: &'static [u8],
+        B: &'static [u8],
+        u: &'static [u8],
+        S: &'static [u8],
+        M: &'static [u8],
+        M2: &'static [u8],
+    }
+
+    /// transcribed from `routines.ts` of `tssrp6a`, with SHA-256
+    const TSSRP6A_FORMULAS_SHA256: Vectors = Vectors {
+        k: &hex!("1A1A4C14 0CDE70AE 360C1EC3 3A33155B 1022DF95 1732A476 A862EB3A B8206A5C"),
+        x: &hex!("65AC38 DFF8BC34 AE0F259E 91FBD0F4 CA2FA430 81C9050C EC7CAC20 D015F303"),
+        B: &hex!(
+            r"439B7630 EC82C94D 3BBD466A 068D663A 40B8D5B1 D9B006BA 43F5D715
+            498088CC A8547BBE 3DE6406C 79F15FFA 7356BC93 580E4783 22DAF8B2
+            D0143478 59234F01 555C457A B8B7F214 875224FC 9BFD07A6 8F37BAD4
+            D74BC846 7CE10EA3 9301D360 4E91FFF5 F881D52C 558187E6 8FAC3268
+            DF289730 7DA5C58A 8C667E0F A8DC837E"
+        ),
+        u: &hex!("C557AF60 30C3DF27 B4704462 DF2ECEAE AED5D16B 4C7D87FD F992E282 F985293E"),
+        S: &hex!(
+            r"7094D74B 440EA4BF FA275269 4F196002 68D61893 AD55CAC7 59A18378
+            DCE55020 742DF26F 96965154 82626372 AF87D447 88D931E6 0BA0D4D8
+            B31984B3 0BA285D5 DB443753 ADE4504A E124EB63 D16DB568 E6850ADF
+            953B353C 1255E8EC 230E59A9 04F37840 02845A31 D12D8F44 8DD6D1BC
+            3ECDED0B BA328046 B907546F 9E3B338C"
+        ),
+        M: &hex!("049206CD 4FFCA254 C9B01CB1 34501164 FD8E051A 77C79238 8BA9CB4F 25CC20D7"),
+        M2: &hex!("36073D6C 4C503DD3 FDA6E6CF 2EC2D0E2 3558E867 55BC4C3B 299AAF8B 5D30106E"),
+    };
+
+    /// transcribed from `_pysrp.py` of the `srp` package, with SHA-1
+    const PYSRP_FORMULAS_SHA1: Vectors = Vectors {
+        k: &hex!("FE4E7E54 8761718E EF3F3EB7 3454916D D4700F81"),
+        x: &testdata::X,
+        B: &hex!(
+            r"A5210F6B DAA16934 445EFEA7 453E99F1 858003A4 7F9D9D37 3F219597
+            2F598195 59EA0D42 BEC06FCA 61B1C155 20DC8BB4 DB30B045 2BA67CC6
+            DA1ED582 CB98FF6F 4D527FFF 2E6F8318 A8C75D77 770767A1 5DE19C43
+            31096089 89D1C1CB 5587D52A 6EC52222 69EE8C7B 36966AD7 88D160F1
+            A1BFBB1D 1514BB1A 07650B19 EA633A89"
+        ),
+        u: &hex!("2C4FE37E EA08F4D1 DB7C258C CF930970 E5AAAE79"),
+        S: &hex!(
+            r"D3040551 BF9AEF3D 5F6318CC A918BE65 69A1ED8A 6F1CED20 9A1B69DF
+            989B4508 EA6EA6C1 399AF4AF 57AE1111 74C91869 DBA0E117 6367A25D
+            A83083A6 3836B478 CEBE27FE 28A2C2E0 4F9A007D 149602B8 A6A615C3
+            8F9D625F E46EAB9E 27370527 EB04DC8C 51F53B24 145F5643 2971CAAB
+            6CA2E43D 01821B16 49C13108 8CCDEC40"
+        ),
+        M: &hex!("70C83E58 6F4D7F97 154EC61E DD8E2DE1 31A0EF4F"),
+        M2: &hex!("F9B5AEE1 5B6E5002 777FDE6E A6227D06 A96FB915"),
+    };
+
+    /// both sides of a handshake with the fixed `a` and `b` of RFC 5054
+    fn check_vectors<D: Digest>(profile: CompatProfile, expected: &Vectors) {
+        let constants = OpenConstants::<128>::default();
+        let (N, g) = (&constants.module, &constants.generator);
+        let hashes = constants.group_hashes::<D>().unwrap();
+        let I = testdata::USERNAME;
+        let s = Salt::from_bytes_be(&testdata::SALT);
+        let x = calculate_private_key_x::<D>(I, testdata::PASSWORD, &s, profile);
+        assert_eq!(x, PrivateKey::from_bytes_be(expected.x), "x nok");
+        assert_eq!(
+            hashes.k(profile),
+            &MultiplierParameter::from_bytes_be(expected.k),
+            "k nok"
+        );
+
+        let user_details = calculate_user_details::<D>(
+            N,
+            g,
+            None,
+            I,
+            testdata::PASSWORD,
+            s.clone(),
+            IdentityMode::default(),
+            profile,
+            Srp6Variant::Srp6a,
+            None,
+        )
+        .unwrap();
+        let a = PrivateKey::from_bytes_be(&testdata::A_PRIVATE);
+        let b = PrivateKey::from_bytes_be(&testdata::B_PRIVATE);
+        let A = calculate_pubkey_A(N, g, None, &a);
+        let host = calculate_host_keys::<D>(
+            128,
+            N,
+            g,
+            None,
+            &user_details,
+            &hashes,
+            &A,
+            &b,
+            profile,
+            ProofScheme::default(),
+        )
+        .unwrap();
+        assert_eq!(host.B, PublicKey::from_bytes_be(expected.B), "B nok");
+        assert_eq!(host.U, PublicKey::from_bytes_be(expected.u), "u nok");
+        assert_eq!(host.S, SessionKey::from_bytes_be(expected.S), "S nok");
+        assert_eq!(host.M, Proof::from_bytes_be(expected.M), "M nok");
+
+        let user = calculate_user_keys::<D>(
+            128,
+            N,
+            g,
+            I,
+            &s,
+            &A,
+            &host.B,
+            &hashes,
+            &a,
+            &x,
+            profile,
+            ProofScheme::default(),
+        )
+        .unwrap();
+        assert_eq!(user.S, host.S);
+        assert_eq!(user.K, host.K);
+        assert_eq!(user.M, host.M);
+        let M2 = calculate_strong_proof_M2::<D>(128, &A, &host.M, &host.S, &host.K, profile);
+        assert_eq!(
+            M2.unwrap(),
+            StrongProof::from_bytes_be(expected.M2),
+            "M2 nok"
+        );
+    }
+
+    #[test]
+    fn should_follow_the_tssrp6a_formulas() {
+        check_vectors::<Sha256>(CompatProfile::Tssrp6a, &TSSRP6A_FORMULAS_SHA256);
+    }
+
+    #[test]
+    fn should_follow_the_pysrp_formulas() {
+        check_vectors::<Sha1>(CompatProfile::PySrp, &PYSRP_FORMULAS_SHA1);
+    }
+
+    /// `k` over the big endian bytes of `N` and of `g` padded to `N`, for the
+    /// groups of RFC 5054 with `g = 2` and `g = 5`, and with `N` padded by
+    /// `Tssrp6a` only
+    #[test]
+    fn should_pad_g_in_k() {
+        fn check<const LEN: usize>(constants: OpenConstants<LEN>, generator: u32) {
+            assert_eq!(constants.generator, Generator::from(generator));
+            let N = constants.module.to_vec();
+            assert_eq!(N.len(), LEN);
+            let mut g = [0_u8; LEN];
+            g[LEN - 4..].copy_from_slice(&generator.to_be_bytes());
+            let expected = Sha1::new().chain_update(N).chain_update(g).finalize();
+            for profile in [CompatProfile::Rfc5054, CompatProfile::Tssrp6a] {
+                assert_eq!(
+                    calculate_k::<Sha1>(
+                        LEN,
+                        &constants.module,
+                        &constants.generator,
+                        constants.variant,
+                        profile
+                    )
+                    .unwrap(),
+                    MultiplierParameter::from_bytes_be(&expected),
+                    "{LEN} bytes, {profile:?}"
+                );
+            }
+        }
+        check(OpenConstants::<128>::default(), 2);
+        check(OpenConstants::<256>::default(), 2);
+        check(OpenConstants::<384>::default(), 5);
+        check(OpenConstants::<512>::default(), 5);
+
+        // an `N` shorter than the group, only `Tssrp6a` pads it
+        let (N, g) = (
+            PrimeModulus::from_bytes_be(&[0xFF; 127]),
+            Generator::from(2),
+        );
+        let mut padded_g = [0_u8; 128];
+        padded_g[127] = 2;
+        let k = |N: &[u8]| {
+            let hash = Sha1::new().chain_update(N).chain_update(padded_g);
+            MultiplierParameter::from_bytes_be(&hash.finalize())
+        };
+        let k_of = |profile| calculate_k::<Sha1>(128, &N, &g, Srp6Variant::Srp6a, profile).unwrap();
+        assert_eq!(k_of(CompatProfile::Rfc5054), k(&N.to_vec()));
+        assert_eq!(k_of(CompatProfile::Tssrp6a), k(&N.to_vec_pad_zero(128)));
+        assert_ne!(k_of(CompatProfile::Rfc5054), k_of(CompatProfile::Tssrp6a));
+        // the same `k` with `hashlib` in Python, `H(N | PAD(g))` and `H(0 | N | PAD(g))`
+        assert_eq!(
+            k_of(CompatProfile::Rfc5054),
+            MultiplierParameter::from_bytes_be(&hex!(
+                "6ED2C826 BAA72F9D 6B9FDC91 211BBAF2 9C9E471E"
+            ))
+        );
+        let padded_k = hex!("E81E8B1A 58E09AD4 20255692 90FC5473 AD181F5A");
+        assert_eq!(
+            k_of(CompatProfile::Tssrp6a),
+            MultiplierParameter::from_bytes_be(&padded_k)
+        );
+        // and the one of the handshakes, for a group set without its checks
+        let constants = OpenConstants::<128>::new(N.clone(), g.clone(), Srp6Variant::Srp6a);
+        let hashes = constants.group_hashes::<Sha1>().unwrap();
+        assert_eq!(
+            hashes.k(CompatProfile::Tssrp6a),
+            &MultiplierParameter::from_bytes_be(&padded_k)
+        );
+
+        // an `N` longer than the group cannot be padded
+        let N = PrimeModulus::from_bytes_be(&[0xFF; 129]);
+        assert_eq!(
+            calculate_k::<Sha1>(128, &N, &g, Srp6Variant::Srp6a, CompatProfile::Tssrp6a),
+            Err(crate::Srp6Error::KeyLengthMismatch {
+                given: 129,
+                expected: 128
+            })
+        );
+    }
+
+    #[test]
+    fn should_keep_rfc5054_profile() {
+        let constants = OpenConstants::<128>::default();
+        let hashes = constants.group_hashes::<Sha1>().unwrap();
+        for profile in [CompatProfile::Rfc5054, CompatProfile::Tssrp6a] {
+            assert_eq!(
+                hashes.k(profile),
+                &MultiplierParameter::from_bytes_be(&testdata::K_MULTIPLIER),
+                "{profile:?}"
+            );
+        }
+        let s = Salt::from_bytes_be(&testdata::SALT);
+        let x = calculate_private_key_x::<Sha1>(
+            testdata::USERNAME,
+            testdata::PASSWORD,
+            &s,
+            CompatProfile::Rfc5054,
+        );
+        assert_eq!(x, PrivateKey::from_bytes_be(&testdata::X));
+        let A = PublicKey::from_bytes_be(&testdata::A_PUBLIC);
+        let B = PublicKey::from_bytes_be(&testdata::B_PUBLIC);
+        assert_eq!(
+            calculate_u::<Sha1>(128, &A, &B, CompatProfile::Rfc5054).unwrap(),
+            PublicKey::from_bytes_be(&testdata::U)
+        );
+    }
+}
@@ -1,20 +1,74 @@
-pub use sha1::digest::Update;
 pub use sha1::Digest;
+pub use sha1::Sha1;
+pub use sha2::Sha256;
 
-use crate::big_number::BigNumber;
+use crate::big_number::{BigNumber, BigNumberError};
 
-pub const HASH_LENGTH: usize = 20;
-pub type Hash = [u8; HASH_LENGTH];
-pub type HashFunc = sha1::Sha1;
+/// the raw output of a hash function `D`
+pub type Hash<D> = sha1::digest::Output<D>;
+/// the default hash function, as used by RFC 5054
+pub type HashFunc = Sha1;
 
 ///
 /// not yet verified
 ///
-pub fn hash<const KEY_BYTES: usize>(a: &BigNumber, b: &BigNumber) -> BigNumber {
-    HashFunc::new()
-        .chain(a.to_array_pad_zero::<KEY_BYTES>())
-        .chain(b.to_array_pad_zero::<KEY_BYTES>())
-        .into()
+pub fn hash<D: Digest>(
+    key_bytes: usize,
+    a: &BigNumber,
+    b: &BigNumber,
+) -> Result<BigNumber, BigNumberError> {
+    let mut hasher = D::new();
+    chain_padded(&mut hasher, a, key_bytes)?;
+    chain_padded(&mut hasher, b, key_bytes)?;
+    Ok(BigNumber::from_digest(hasher))
+}
+
+/// feeds `value` into `hasher`, big endian with leading zeros up to `len` bytes,
+/// chunk by chunk instead of through a padded buffer of `len` bytes
+pub fn chain_padded<D: Digest>(
+    hasher: &mut D,
+    value: &BigNumber,
+    len: usize,
+) -> Result<(), BigNumberError> {
+    value.update_digest_pad_zero(hasher, len)
+}
+
+/// a hash of several fields that cannot be confused with another split of the
+/// same bytes: a context string first, then each field preceded by its length,
+/// 4 bytes big endian
+///
+/// formula: `H(LEN(context) | context | LEN(f1) | f1 | LEN(f2) | f2 ...)`
+pub struct DomainSeparatedHasher<D: Digest> {
+    hasher: D,
+}
+
+impl<D: Digest> DomainSeparatedHasher<D> {
+    /// a hasher for `context`, e.g. `"srp6 proof v2"`
+    pub fn new(context: &str) -> Self {
+        let mut hasher = Self { hasher: D::new() };
+        hasher.update_field(context.as_bytes());
+        hasher
+    }
+
+    /// feeds the length of `field`, then `field`
+    ///
+    /// **Panics** if `field` is longer than `u32::MAX` bytes
+    pub fn update_field(&mut self, field: &[u8]) {
+        let length = u32::try_from(field.len()).expect("a field is at most 4 GiB");
+        self.hasher.update(length.to_be_bytes());
+        self.hasher.update(field);
+    }
+
+    /// same as [`DomainSeparatedHasher::update_field`], by value for chaining
+    pub fn chain_field(mut self, field: &[u8]) -> Self {
+        self.update_field(field);
+        self
+    }
+
+    /// the hash read back as a big endian number
+    pub fn finalize_number(self) -> BigNumber {
+        BigNumber::from_digest(self.hasher)
+    }
 }
 
 #[cfg(test)]
@@ -28,8 +82,83 @@ mod tests {
         // A from official example
         let A = BigNumber::from_bytes_be(&testdata::A_PUBLIC);
         let B = BigNumber::from_bytes_be(&testdata::B_PUBLIC);
-        let u = hash::<128>(&A, &B);
+        let u = hash::<HashFunc>(128, &A, &B).unwrap();
         let expected = BigNumber::from_bytes_be(&testdata::U);
         assert_eq!(&u, &expected);
     }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn should_hash_with_sha256() {
+        let A = BigNumber::from_bytes_be(&testdata::A_PUBLIC);
+        let B = BigNumber::from_bytes_be(&testdata::B_PUBLIC);
+        let u = hash::<Sha256>(128, &A, &B).unwrap();
+        assert!(u.num_bytes() <= 32);
+        assert_ne!(u, hash::<Sha1>(128, &A, &B).unwrap());
+    }
+
+    #[test]
+    fn should_chain_padded_as_padded_buffer() {
+        use rand::{rngs::StdRng, Rng, SeedableRng};
+
+        let mut rng = StdRng::seed_from_u64(38);
+        for len in [1, 7, 8, 9, 20, 64, 65, 128, 256, 1024] {
+            for _ in 0..8 {
+                let bytes: Vec<u8> = (0..rng.gen_range(0..=len)).map(|_| rng.gen()).collect();
+                let value = BigNumber::from_bytes_be(&bytes);
+                let expected = Sha256::new()
+                    .chain_update(value.try_to_vec_pad_zero(len).unwrap())
+                    .finalize();
+                let mut hasher = Sha256::new();
+                chain_padded(&mut hasher, &value, len).unwrap();
+                assert_eq!(hasher.finalize(), expected, "{len} bytes of {value:?}");
+            }
+        }
+        // zero, and one byte too long
+        let mut hasher = Sha1::new();
+        chain_padded(&mut hasher, &BigNumber::from(0), 3).unwrap();
+        assert_eq!(
+            hasher.finalize(),
+            Sha1::new().chain_update([0; 3]).finalize()
+        );
+        let value = BigNumber::from_bytes_be(&[0xff; 9]);
+        assert!(chain_padded(&mut Sha1::new(), &value, 8).is_err());
+    }
+
+    #[test]
+    fn should_prefix_the_fields_with_their_length() {
+        let hash = DomainSeparatedHasher::<Sha256>::new("ctx")
+            .chain_field(b"ab")
+            .chain_field(b"")
+            .finalize_number();
+        let expected = Sha256::new()
+            .chain_update([0, 0, 0, 3])
+            .chain_update(b"ctx")
+            .chain_update([0, 0, 0, 2])
+            .chain_update(b"ab")
+            .chain_update([0, 0, 0, 0])
+            .finalize();
+        assert_eq!(hash, BigNumber::from_bytes_be(&expected));
+
+        // the same bytes split differently, or in another context
+        let split = |fields: &[&[u8]], context| {
+            fields
+                .iter()
+                .fold(DomainSeparatedHasher::<Sha256>::new(context), |h, f| {
+                    h.chain_field(f)
+                })
+                .finalize_number()
+        };
+        assert_ne!(split(&[b"a:b", b"c"], "x"), split(&[b"a", b"b:c"], "x"));
+        assert_ne!(split(&[b"ab"], "x"), split(&[b"a", b"b"], "x"));
+        assert_ne!(split(&[b"ab"], "x"), split(&[b"ab"], "y"));
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn should_not_hash_too_long_numbers() {
+        let A = BigNumber::from_bytes_be(&testdata::A_PUBLIC);
+        let B = BigNumber::from_bytes_be(&testdata::B_PUBLIC);
+        assert!(hash::<HashFunc>(64, &A, &B).is_err());
+    }
 }
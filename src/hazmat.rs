@@ -0,0 +1,377 @@
+/*!
+The low level protocol functions of SRP6 (feature `hazmat`)
+
+**Hazardous material**: these functions do none of the checks of the handshake
+types. Nothing stops a caller from reusing an ephemeral key, from showing the
+host's proof before the user's one is verified, or from skipping the
+safeguards on `A`, `B` and `u` that are not part of a function. They are meant
+for interoperability with other SRP implementations and for protocol research;
+prefer [`Srp6`](crate::Srp6) and [`Srp6User`](crate::Srp6User) otherwise.
+
+## Conventions
+
+- numbers are hashed big endian, without their leading zero bytes, as given
+  by `to_vec`
+- `PAD(x)` is `x` big endian, left padded with zeros to `len` bytes, the length
+  of `N`; a number longer than `len` gives [`Srp6Error::KeyLengthMismatch`](crate::Srp6Error::KeyLengthMismatch)
+- `|` is the concatenation, `H` the hash function `D`
+- the hashes are read back as big endian numbers, except the interleaved `K`
+  of [`calculate_session_key_hash_interleave_K`] (see there)
+- `profile` selects which numbers are padded, see [`CompatProfile`](crate::CompatProfile)
+- `g_pow` are the precomputed powers of `g`, see
+  [`OpenConstants::with_fixed_base_table`](crate::OpenConstants::with_fixed_base_table);
+  `None` gives the same result
+
+The examples use the test vectors of RFC 5054 appendix B, from [`testdata`],
+with the 1024 bit group and SHA-1.
+*/
+
+/// Test values defined in RFC 5054 appendix B (for the 1024 bit group)
+pub use crate::protocol_details::testdata;
+
+/// the trait of the hash functions `D`, e.g. [`Sha1`](crate::Sha1), for the
+/// code generic over them
+pub use crate::hash::Digest;
+
+/// `k = H(N | PAD(g))`, `3` for [`Srp6Variant::Legacy`](crate::Srp6Variant::Legacy),
+/// `H(N | g)` for [`CompatProfile::PySrp`](crate::CompatProfile::PySrp)
+///
+/// ```
+/// use chadehoc_srp6::{hazmat::*, *};
+///
+/// let constants = OpenConstants::<128>::default();
+/// let k = calculate_k::<Sha1>(
+///     128,
+///     constants.module(),
+///     &constants.generator,
+///     Srp6Variant::Srp6a,
+///     CompatProfile::Rfc5054,
+/// )
+/// .unwrap();
+/// assert_eq!(k, MultiplierParameter::from_bytes_be(&testdata::K_MULTIPLIER));
+/// ```
+pub use crate::primitives::calculate_k;
+
+/// `H(N) xor H(g)`, the first part of the proof `M`, `N` is padded to `len`
+/// but `g` is not
+///
+/// ```
+/// use chadehoc_srp6::{hazmat::*, *};
+///
+/// let constants = OpenConstants::<128>::default();
+/// let hash_N_xor_g =
+///     calculate_hash_N_xor_g::<Sha1>(128, constants.module(), &constants.generator).unwrap();
+/// assert_eq!(hash_N_xor_g.len(), 20);
+/// ```
+pub use crate::primitives::calculate_hash_N_xor_g;
+
+/// `x = H(s | H(I | ":" | p))`, the salt `s` is not padded
+///
+/// ```
+/// use chadehoc_srp6::{hazmat::*, *};
+///
+/// let x = calculate_private_key_x::<Sha1>(
+///     testdata::USERNAME,
+///     testdata::PASSWORD,
+///     &Salt::from_bytes_be(&testdata::SALT),
+///     CompatProfile::Rfc5054,
+/// );
+/// assert_eq!(x, PrivateKey::from_bytes_be(&testdata::X));
+/// ```
+pub use crate::primitives::calculate_private_key_x;
+
+/// `x` of [`Srp6Variant::Srp6aV2`](crate::Srp6Variant::Srp6aV2), with each
+/// field preceded by its length in 4 bytes big endian, after the context
+/// `"srp6 x v2"`
+///
+/// ```
+/// use chadehoc_srp6::{hazmat::*, *};
+///
+/// let x = calculate_private_key_x_v2::<Sha1>(
+///     testdata::USERNAME,
+///     testdata::PASSWORD,
+///     &Salt::from_bytes_be(&testdata::SALT),
+/// );
+/// // not given by the RFC
+/// assert_eq!(x, PrivateKey::from_hex_str_be("72DB9C7CB12B8CE50FD71819CB367460C4A75C6A").unwrap());
+/// ```
+pub use crate::primitives::calculate_private_key_x_v2;
+
+/// `v = g^x % N`
+///
+/// ```
+/// use chadehoc_srp6::{hazmat::*, *};
+///
+/// let constants = OpenConstants::<128>::default();
+/// let x = PrivateKey::from_bytes_be(&testdata::X);
+/// let v = calculate_password_verifier_v(constants.module(), &constants.generator, None, &x);
+/// assert_eq!(v, PasswordVerifier::from_bytes_be(&testdata::VERIFIER));
+/// ```
+pub use crate::primitives::calculate_password_verifier_v;
+
+/// `A = g^a % N`, the ephemeral private key `a` must be random in `[2, N - 2]`
+///
+/// ```
+/// use chadehoc_srp6::{hazmat::*, *};
+///
+/// let constants = OpenConstants::<128>::default();
+/// let a = PrivateKey::from_bytes_be(&testdata::A_PRIVATE);
+/// let A = calculate_pubkey_A(constants.module(), &constants.generator, None, &a);
+/// assert_eq!(A, PublicKey::from_bytes_be(&testdata::A_PUBLIC));
+/// ```
+pub use crate::primitives::calculate_pubkey_A;
+
+/// `B = (k * v + g^b) % N`, the ephemeral private key `b` must be random in `[2, N - 2]`
+///
+/// ```
+/// use chadehoc_srp6::{hazmat::*, *};
+///
+/// let constants = OpenConstants::<128>::default();
+/// let B = calculate_pubkey_B(
+///     constants.module(),
+///     &constants.generator,
+///     None,
+///     &MultiplierParameter::from_bytes_be(&testdata::K_MULTIPLIER),
+///     &PasswordVerifier::from_bytes_be(&testdata::VERIFIER),
+///     &PrivateKey::from_bytes_be(&testdata::B_PRIVATE),
+/// );
+/// assert_eq!(B, PublicKey::from_bytes_be(&testdata::B_PUBLIC));
+/// ```
+pub use crate::primitives::calculate_pubkey_B;
+
+/// `u = H(PAD(A) | PAD(B))`, `H(A | B)` for [`CompatProfile::PySrp`](crate::CompatProfile::PySrp);
+/// both sides must abort if `u == 0`
+///
+/// ```
+/// use chadehoc_srp6::{hazmat::*, *};
+///
+/// let u = calculate_u::<Sha1>(
+///     128,
+///     &PublicKey::from_bytes_be(&testdata::A_PUBLIC),
+///     &PublicKey::from_bytes_be(&testdata::B_PUBLIC),
+///     CompatProfile::Rfc5054,
+/// )
+/// .unwrap();
+/// assert_eq!(u, PublicKey::from_bytes_be(&testdata::U));
+/// ```
+pub use crate::primitives::calculate_u;
+
+/// `S = (A * v^u) ^ b % N`, the host's premaster secret; fails if `A % N == 0`
+/// or `u == 0`
+///
+/// ```
+/// use chadehoc_srp6::{hazmat::*, *};
+///
+/// let constants = OpenConstants::<128>::default();
+/// let S = calculate_session_key_S_for_host::<Sha1>(
+///     128,
+///     constants.module(),
+///     &PublicKey::from_bytes_be(&testdata::A_PUBLIC),
+///     &PublicKey::from_bytes_be(&testdata::B_PUBLIC),
+///     &PrivateKey::from_bytes_be(&testdata::B_PRIVATE),
+///     &PasswordVerifier::from_bytes_be(&testdata::VERIFIER),
+///     CompatProfile::Rfc5054,
+/// )
+/// .unwrap();
+/// assert_eq!(S, SessionKey::from_bytes_be(&testdata::SECRET));
+/// ```
+pub use crate::primitives::calculate_session_key_S_for_host;
+
+/// `S = (B - (k * g^x)) ^ (a + (u * x)) % N`, the user's premaster secret; fails
+/// if `B % N == 0` or `u == 0`
+///
+/// ```
+/// use chadehoc_srp6::{hazmat::*, *};
+///
+/// let constants = OpenConstants::<128>::default();
+/// let S = calculate_session_key_S_for_client::<Sha1>(
+///     128,
+///     constants.module(),
+///     &constants.generator,
+///     &MultiplierParameter::from_bytes_be(&testdata::K_MULTIPLIER),
+///     &PublicKey::from_bytes_be(&testdata::B_PUBLIC),
+///     &PublicKey::from_bytes_be(&testdata::A_PUBLIC),
+///     &PrivateKey::from_bytes_be(&testdata::A_PRIVATE),
+///     &PrivateKey::from_bytes_be(&testdata::X),
+///     CompatProfile::Rfc5054,
+/// )
+/// .unwrap();
+/// assert_eq!(S, SessionKey::from_bytes_be(&testdata::SECRET));
+/// ```
+pub use crate::primitives::calculate_session_key_S_for_client;
+
+/// `K = SHA_Interleave(S)` of RFC 2945, twice the hash output long
+///
+/// the leading zero bytes of `S` are removed, and one more byte if the rest has
+/// an odd length, then the even and odd bytes are hashed apart and their hashes
+/// are interleaved. Unlike the other hashes, the result is read back as a
+/// **little endian** number: the reverse of `K.to_bytes()` gives the bytes in
+/// the order of the RFC, and the proofs, that hash `K` big endian, see them
+/// reversed.
+///
+/// ```
+/// use chadehoc_srp6::{hazmat::*, *};
+///
+/// let S = SessionKey::from_bytes_be(&testdata::SECRET);
+/// let K = calculate_session_key_hash_interleave_K::<Sha1>(128, &S).unwrap();
+/// assert_eq!(K.to_bytes().len(), 40);
+/// assert_eq!(K, calculate_session_key_K::<Sha1>(128, &S, CompatProfile::Rfc5054).unwrap());
+/// ```
+pub use crate::primitives::calculate_session_key_hash_interleave_K;
+
+/// `K` interleaved over `PAD(S)`, as in earlier versions of this crate, see
+/// [`CompatProfile::LegacyInterleave`](crate::CompatProfile::LegacyInterleave)
+///
+/// ```
+/// use chadehoc_srp6::{hazmat::*, *};
+///
+/// let mut secret = testdata::SECRET;
+/// secret[0] = 0;
+/// let S = SessionKey::from_bytes_be(&secret);
+/// assert_ne!(
+///     calculate_session_key_hash_interleave_padded_K::<Sha1>(128, &S).unwrap(),
+///     calculate_session_key_hash_interleave_K::<Sha1>(128, &S).unwrap()
+/// );
+/// ```
+pub use crate::primitives::calculate_session_key_hash_interleave_padded_K;
+
+/// `K` as calculated by `profile`: [`calculate_session_key_hash_interleave_K`]
+/// for [`CompatProfile::Rfc5054`](crate::CompatProfile::Rfc5054), `H(S)` otherwise,
+/// whose bytes are padded to twice the hash output as well
+///
+/// ```
+/// use chadehoc_srp6::{hazmat::*, *};
+///
+/// let S = SessionKey::from_bytes_be(&testdata::SECRET);
+/// let K = calculate_session_key_K::<Sha1>(128, &S, CompatProfile::Tssrp6a).unwrap();
+/// // still padded to twice the hash output
+/// assert_eq!(K.to_bytes()[20..], <Sha1 as sha1::Digest>::digest(S.to_vec())[..]);
+/// ```
+pub use crate::primitives::calculate_session_key_K;
+
+/// `M = H(H(N) xor H(g) | H(I) | s | PAD(A) | PAD(B) | K)`, the user's proof,
+/// with `K` padded to twice the hash output; `H(A | B | S)` for
+/// [`CompatProfile::Tssrp6a`](crate::CompatProfile::Tssrp6a)
+///
+/// ```
+/// use chadehoc_srp6::{hazmat::*, *};
+///
+/// let constants = OpenConstants::<128>::default();
+/// let hash_N_xor_g =
+///     calculate_hash_N_xor_g::<Sha1>(128, constants.module(), &constants.generator).unwrap();
+/// let S = SessionKey::from_bytes_be(&testdata::SECRET);
+/// let K = calculate_session_key_hash_interleave_K::<Sha1>(128, &S).unwrap();
+/// let M = calculate_proof_M::<Sha1>(
+///     128,
+///     &hash_N_xor_g,
+///     testdata::USERNAME,
+///     &Salt::from_bytes_be(&testdata::SALT),
+///     &PublicKey::from_bytes_be(&testdata::A_PUBLIC),
+///     &PublicKey::from_bytes_be(&testdata::B_PUBLIC),
+///     &S,
+///     &K,
+///     CompatProfile::Rfc5054,
+/// )
+/// .unwrap();
+/// // not given by the RFC
+/// assert_eq!(M, Proof::from_hex_str_be("5007FFD25BC1A82A4A4131F6C88BFDDC2587571A").unwrap());
+/// ```
+pub use crate::primitives::calculate_proof_M;
+
+/// `M` of [`Srp6Variant::Srp6aV2`](crate::Srp6Variant::Srp6aV2), with each
+/// field preceded by its length in 4 bytes big endian, after the context
+/// `"srp6 proof v2"`: `I` itself instead of `H(I)`, the numbers without
+/// padding and the canonical bytes of `K`
+///
+/// ```
+/// use chadehoc_srp6::{hazmat::*, *};
+///
+/// let constants = OpenConstants::<128>::default();
+/// let hash_N_xor_g =
+///     calculate_hash_N_xor_g::<Sha1>(128, constants.module(), &constants.generator).unwrap();
+/// let S = SessionKey::from_bytes_be(&testdata::SECRET);
+/// let K = calculate_session_key_hash_interleave_K::<Sha1>(128, &S).unwrap();
+/// let M = calculate_proof_M_v2::<Sha1>(
+///     &hash_N_xor_g,
+///     testdata::USERNAME,
+///     &Salt::from_bytes_be(&testdata::SALT),
+///     &PublicKey::from_bytes_be(&testdata::A_PUBLIC),
+///     &PublicKey::from_bytes_be(&testdata::B_PUBLIC),
+///     &K,
+/// );
+/// assert_ne!(M, Proof::from_hex_str_be("5007FFD25BC1A82A4A4131F6C88BFDDC2587571A").unwrap());
+/// ```
+pub use crate::primitives::calculate_proof_M_v2;
+
+/// `M2 = H(PAD(A) | M | K)`, the host's proof, with `M` padded to the hash
+/// output and `K` to twice the hash output; `H(A | M | S)` for
+/// [`CompatProfile::Tssrp6a`](crate::CompatProfile::Tssrp6a)
+///
+/// ```
+/// use chadehoc_srp6::{hazmat::*, *};
+///
+/// let S = SessionKey::from_bytes_be(&testdata::SECRET);
+/// let K = calculate_session_key_hash_interleave_K::<Sha1>(128, &S).unwrap();
+/// let M2 = calculate_strong_proof_M2::<Sha1>(
+///     128,
+///     &PublicKey::from_bytes_be(&testdata::A_PUBLIC),
+///     &Proof::from_hex_str_be("5007FFD25BC1A82A4A4131F6C88BFDDC2587571A").unwrap(),
+///     &S,
+///     &K,
+///     CompatProfile::Rfc5054,
+/// )
+/// .unwrap();
+/// // not given by the RFC
+/// let expected = "159A0CA8DA4795AEA03B524CB54371530909E121";
+/// assert_eq!(M2, StrongProof::from_hex_str_be(expected).unwrap());
+/// ```
+pub use crate::primitives::calculate_strong_proof_M2;
+
+/// `M = HMAC(PAD(K), H(N) xor H(g) | H(I) | s | PAD(A) | PAD(B))`, the user's
+/// proof for [`ProofScheme::Hmac`](crate::ProofScheme::Hmac), with `K` padded to
+/// twice the hash output
+///
+/// ```
+/// use chadehoc_srp6::{hazmat::*, *};
+///
+/// let constants = OpenConstants::<128>::default();
+/// let hash_N_xor_g =
+///     calculate_hash_N_xor_g::<Sha1>(128, constants.module(), &constants.generator).unwrap();
+/// let S = SessionKey::from_bytes_be(&testdata::SECRET);
+/// let K = calculate_session_key_hash_interleave_K::<Sha1>(128, &S).unwrap();
+/// let M = calculate_proof_hmac::<Sha1>(
+///     128,
+///     &hash_N_xor_g,
+///     testdata::USERNAME,
+///     &Salt::from_bytes_be(&testdata::SALT),
+///     &PublicKey::from_bytes_be(&testdata::A_PUBLIC),
+///     &PublicKey::from_bytes_be(&testdata::B_PUBLIC),
+///     &K,
+/// )
+/// .unwrap();
+/// // not given by the RFC
+/// assert_eq!(M, Proof::from_hex_str_be("B137EEFEA373EEDB61A3FE88C4C18CEBCBF6E017").unwrap());
+/// ```
+pub use crate::primitives::calculate_proof_hmac;
+
+/// `M2 = HMAC(PAD(K), PAD(A) | PAD(M))`, the host's proof for
+/// [`ProofScheme::Hmac`](crate::ProofScheme::Hmac), with `M` padded to the hash
+/// output and `K` to twice the hash output
+///
+/// ```
+/// use chadehoc_srp6::{hazmat::*, *};
+///
+/// let S = SessionKey::from_bytes_be(&testdata::SECRET);
+/// let K = calculate_session_key_hash_interleave_K::<Sha1>(128, &S).unwrap();
+/// let M2 = calculate_strong_proof_hmac::<Sha1>(
+///     128,
+///     &PublicKey::from_bytes_be(&testdata::A_PUBLIC),
+///     &Proof::from_hex_str_be("B137EEFEA373EEDB61A3FE88C4C18CEBCBF6E017").unwrap(),
+///     &K,
+/// )
+/// .unwrap();
+/// // not given by the RFC
+/// let expected = "D58318C93088993DD81B773F1000F1E86185E2A0";
+/// assert_eq!(M2, StrongProof::from_hex_str_be(expected).unwrap());
+/// ```
+pub use crate::primitives::calculate_strong_proof_hmac;
@@ -0,0 +1,203 @@
+//! The two login endpoints of the server for `axum` (feature `axum`)
+//!
+//! The user posts its [`UserHandshake`] to [`post_handshake`] and gets the
+//! [`ServerHandshake`], then posts its [`ProofMessage`] to [`post_proof`] with
+//! the [`ServerHandshake::session_id`] in hex in the [`SESSION_ID_HEADER`],
+//! and gets the server's [`StrongProofMessage`]. [`router`] mounts both.
+//!
+//! Between both steps, the [`HostHandshakeState`] is kept in [`SrpServerState`]
+//! until it expires. The verifiers come from the application, through a
+//! [`VerifierStore`].
+//!
+//! All the failures of the login give `401 Unauthorized`, a malformed request
+//! gives `400 Bad Request`.
+
+use crate::hash::{Digest, HashFunc};
+use crate::primitives::*;
+use crate::{verify_proof_with_state, HostHandshakeState, Srp6};
+
+use ::axum::extract::{Json, State};
+use ::axum::http::{HeaderMap, StatusCode};
+use ::axum::routing::post;
+use ::axum::Router;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// header of [`post_proof`] with the [`ServerHandshake::session_id`] in hex
+pub const SESSION_ID_HEADER: &str = "x-srp-session-id";
+
+/// how long a handshake waits for the user's proof by default
+pub const DEFAULT_HANDSHAKE_EXPIRY: Duration = Duration::from_secs(60);
+
+/// the server's strong proof `M2`, answer of [`post_proof`]
+pub type StrongProofMessage = ProofMessage;
+
+/// The verifiers of the users, implemented by the application over its database
+///
+/// the calls are made from the async handlers, they should not block for long.
+pub trait VerifierStore: Send + Sync + 'static {
+    /// the [`UserDetails`] stored for `username`, `None` if there are none
+    fn user_details(&self, username: UsernameRef) -> Option<UserDetails>;
+
+    /// called once the user is logged in, with the [`SessionSecret`] of the
+    /// server; does nothing by default
+    #[allow(unused_variables)]
+    fn logged_in(&self, username: UsernameRef, secret: SessionSecret) {}
+}
+
+/// a handshake waiting for the user's proof
+struct PendingHandshake<const LEN: usize, D: Digest> {
+    username: Username,
+    state: HostHandshakeState<LEN, D>,
+    started: Instant,
+}
+
+/// The state of the endpoints: the group, the [`VerifierStore`] and the
+/// handshakes in flight, by session id
+///
+/// `D` is the hash function, SHA-1 by default
+pub struct SrpServerState<const LEN: usize, S: VerifierStore, D: Digest = HashFunc> {
+    constants: Arc<OpenConstants<LEN>>,
+    store: Arc<S>,
+    handshakes: Arc<Mutex<HashMap<SessionId, PendingHandshake<LEN, D>>>>,
+    expiry: Duration,
+}
+
+impl<const LEN: usize, S: VerifierStore, D: Digest> Clone for SrpServerState<LEN, S, D> {
+    fn clone(&self) -> Self {
+        Self {
+            constants: self.constants.clone(),
+            store: self.store.clone(),
+            handshakes: self.handshakes.clone(),
+            expiry: self.expiry,
+        }
+    }
+}
+
+impl<const LEN: usize, S: VerifierStore, D: Digest> SrpServerState<LEN, S, D> {
+    /// no handshake in flight, they expire after [`DEFAULT_HANDSHAKE_EXPIRY`]
+    pub fn new(constants: OpenConstants<LEN>, store: S) -> Self {
+        Self {
+            constants: Arc::new(constants),
+            store: Arc::new(store),
+            handshakes: Default::default(),
+            expiry: DEFAULT_HANDSHAKE_EXPIRY,
+        }
+    }
+
+    /// how long a handshake waits for the user's proof
+    pub fn with_expiry(mut self, expiry: Duration) -> Self {
+        self.expiry = expiry;
+        self
+    }
+
+    /// the [`VerifierStore`] given to [`SrpServerState::new`]
+    pub fn store(&self) -> &S {
+        &self.store
+    }
+
+    /// the number of handshakes waiting for a proof, expired ones included
+    pub fn pending_handshakes(&self) -> usize {
+        self.lock().len()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<SessionId, PendingHandshake<LEN, D>>> {
+        // a panic while holding the lock leaves the map consistent
+        self.handshakes.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// first step: the [`ServerHandshake`] for the user's [`UserHandshake`]
+///
+/// the expired handshakes are dropped on the way. A public key `A` longer than
+/// `LEN` bytes is rejected while the body is parsed.
+pub async fn post_handshake<const LEN: usize, S: VerifierStore, D>(
+    State(state): State<SrpServerState<LEN, S, D>>,
+    Json(Bounded(handshake)): Json<Bounded<UserHandshake, LEN>>,
+) -> Result<Json<ServerHandshake>, StatusCode>
+where
+    D: Digest + Send + Sync + 'static,
+{
+    let user_details = state
+        .store
+        .user_details(&handshake.username)
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+    // the whole message goes to the pool, for the bits of its group
+    let username = handshake.username.clone();
+    let constants = state.constants.clone();
+    let (server_handshake, host_state) = crate::api::offload(move || {
+        Srp6::<LEN, D>::continue_handshake_stateless(&user_details, &handshake, &constants)
+    })
+    .await
+    .map_err(|e| {
+        if e.is_authentication_failure() {
+            StatusCode::UNAUTHORIZED
+        } else {
+            StatusCode::BAD_REQUEST
+        }
+    })?;
+
+    let mut handshakes = state.lock();
+    handshakes.retain(|_, pending| pending.started.elapsed() < state.expiry);
+    handshakes.insert(
+        server_handshake.session_id,
+        PendingHandshake {
+            username,
+            state: host_state,
+            started: Instant::now(),
+        },
+    );
+    Ok(Json(server_handshake))
+}
+
+/// second step: the server's [`StrongProofMessage`] for the user's [`ProofMessage`]
+///
+/// the handshake is given by the [`SESSION_ID_HEADER`], it is removed whatever
+/// the outcome, so that each handshake gives a single try.
+pub async fn post_proof<const LEN: usize, S: VerifierStore, D>(
+    State(state): State<SrpServerState<LEN, S, D>>,
+    headers: HeaderMap,
+    Json(proof): Json<ProofMessage>,
+) -> Result<Json<StrongProofMessage>, StatusCode>
+where
+    D: Digest + Send + Sync + 'static,
+{
+    let session_id = headers
+        .get(SESSION_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| {
+            let mut session_id = SessionId::default();
+            hex::decode_to_slice(value, &mut session_id).ok()?;
+            Some(session_id)
+        })
+        .ok_or(StatusCode::BAD_REQUEST)?;
+    let pending = state
+        .lock()
+        .remove(&session_id)
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+    if pending.started.elapsed() >= state.expiry {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    let outcome = verify_proof_with_state(pending.state, &proof.proof)
+        .map_err(|_| StatusCode::UNAUTHORIZED)?;
+    let strong_proof = outcome.server_proof().clone();
+    state
+        .store
+        .logged_in(&pending.username, outcome.into_session_secret());
+    Ok(Json(strong_proof.into()))
+}
+
+/// both endpoints, at `/handshake` for [`post_handshake`] and `/proof` for [`post_proof`]
+///
+/// it can be nested under a path of the application, e.g. `/login`.
+pub fn router<const LEN: usize, S: VerifierStore, D>(state: SrpServerState<LEN, S, D>) -> Router
+where
+    D: Digest + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/handshake", post(post_handshake::<LEN, S, D>))
+        .route("/proof", post(post_proof::<LEN, S, D>))
+        .with_state(state)
+}
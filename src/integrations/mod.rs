@@ -0,0 +1,6 @@
+//! Server side glue for web frameworks
+//!
+//! - [`axum`] (feature `axum`): the two login endpoints and their state
+
+#[cfg(feature = "axum")]
+pub mod axum;
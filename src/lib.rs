@@ -1,25 +1,133 @@
 /*!
 An implementation of Secure Remote Password (SRP6) authentication protocol.
 
-**NOTE**: Please do only use key length >= 2048 bit in production. You can do so by using [`Srp6_2048`] or [`Srp6_4096`].
+**NOTE**: Please do only use key length >= 2048 bit in production. You can do so by using [`Srp6_2048`], [`Srp6_3072`], [`Srp6_4096`], [`Srp6_6144`] or [`Srp6_8192`].
+The handshakes in smaller groups fail with [`Srp6Error::InsecureKeyLength`],
+unless the `insecure-key-lengths` feature is enabled, see [`MIN_KEY_LENGTH`].
 
 # Usage
 See the examples.
 
+# Note on the handshake steps
+the steps cannot be called out of order: [`Srp6::continue_handshake`] consumes
+the server and returns a [`Srp6WaitingForProof`], [`Srp6User::update_handshake`]
+consumes the user and returns a [`Srp6UserWaitingForServerProof`]. Only these
+can verify the other side's proof, and only once. The server tags each handshake
+with a random [`ServerHandshake::session_id`], to tell concurrent ones apart.
+[`Srp6User::with_credentials`] keeps the username and the password for the
+whole handshake, so that the username sent to the server is the one hashed into
+the proof, and [`Srp6::for_user`] binds the server to the user's [`UserDetails`].
+The former API, with `&mut self` methods, is kept for one release in the
+`mutable` module, with the deprecated `mutable-api` feature.
+The public values of a handshake are only read, with accessors such as
+[`Srp6UserWaitingForServerProof::proof`]; the deprecated `pub-fields` feature
+keeps them as public fields for one release.
+
+# Note on hash function
+by default SHA-1 is used, as in [RFC5054]. The hash function is a type parameter
+of [`Srp6`] and [`Srp6User`], e.g. [`Srp6_2048Sha256`] and [`Srp6user2048Sha256`]
+use SHA-256. Both sides must of course agree on it.
+
+# Note on password stretching
+the private key `x` is a plain hash of the salt and password, so a leaked verifier
+can be attacked offline quickly. As an opt-in, `x` can be stretched with PBKDF2
+(or Argon2id with the `argon2` feature), see [`KdfParams`],
+[`Srp6User::generate_new_user_secrets_stretched`] and [`Srp6User::update_handshake_stretched`].
+The server side is unchanged, but the application has to send the stored
+[`UserDetails::kdf`] to the user along with the [`ServerHandshake`].
+
+# Note on the identity
+the username is hashed into `x` and `M`, so renaming a user breaks its verifier.
+[`IdentityMode`] can replace it with an empty or an opaque identity, see
+[`Srp6User::generate_new_user_secrets_with_identity`] and [`Srp6User::with_identity`].
+
+# Note on Unicode
+the username and the password are hashed byte by byte, so the same accented
+characters typed in a composed (NFC) or decomposed (NFD) form give different
+verifiers. With the `normalization` feature, both are NFC normalized before they
+are hashed and the username must not contain `:` or control characters, see
+[`validate_username`]; [`UserDetails::normalized`] records it for each user.
+# Note on other implementations
+the padding of the hashed numbers and the content of the proofs differ between
+SRP6a libraries. A [`CompatProfile`] selects the conventions of `tssrp6a` or of
+the `srp` Python package, see the [`compat`] module.
+
+# Note on `no_std`
+without the default `std` feature, the crate only needs `core` and `alloc`.
+The random private keys must then come from the caller, see
+[`Srp6User::start_handshake_with_rng`] and [`Srp6::continue_handshake_with_rng`].
+With the `fixed-bignum` feature, only the modular exponentiations run on fixed
+size numbers on the stack instead of `num-bigint`, and only allocate their result,
+e.g. for a small heap that their many intermediate numbers would fragment. The
+rest of the arithmetic (additions, products and remainders of `B` and `S`) stays
+on `num-bigint` and allocates, so the handshake is not allocation free.
+
+# Note on logging
+intermediate public values are logged at debug level with [`log`]. The secret ones
+(private keys, `x`, `S` and `K`) are only logged with the `insecure-debug-logging`
+feature, which must never be enabled in production. Likewise, the `Debug` output of
+the handshake types and of [`UserCredentials`] shows them as e.g.
+`PrivateKey(<redacted, 256 bytes>)` without that feature.
+With the `tracing` feature, `continue_handshake`, `update_handshake` and `verify_proof`
+are wrapped in `tracing` spans that only record non sensitive fields: the group size
+in bits, a hash of the username and the duration in microseconds.
+With the `metrics` feature, the durations of their phases (keys, `S`, `x` and `K`,
+proofs) are added up per thread, see the `metrics` module.
+
 # Note on key length
 this crate provides some default keys (as [`OpenConstants`]).
 The modulus prime and generator numbers are taken from [RFC5054].
+Custom ones should be checked with [`OpenConstants::try_new`].
 
 # Further details and domain vocabolary
 - You can find the documentation of SRP6 [variables in a dedicated module][`protocol_details`].
 - [RFC2945](https://datatracker.ietf.org/doc/html/rfc2945) that describes in detail the Secure remote password protocol (SRP).
 - [RFC5054] that describes SRP6 for TLS Authentication
-- [check out the 2 examples](./examples) that illustrates the srp authentication flow as well
+- [check out the examples](./examples) that illustrate the srp authentication flow as well, `03_tcp_server` and `03_tcp_client` over a real connection
 
 [RFC5054]: (https://datatracker.ietf.org/doc/html/rfc5054)
 */
+#![cfg_attr(not(feature = "std"), no_std)]
+extern crate alloc;
+
 use derive_more::{Display, Error};
 
+/// like [`log::debug!`], for secret values (`a`, `b`, `x`, `S`, `K`):
+/// only logs with the `insecure-debug-logging` feature
+macro_rules! debug_secret {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "insecure-debug-logging")]
+        log::debug!($($arg)*);
+    };
+}
+
+/// enters a [`tracing`] span for a handshake step, until the end of the scope
+/// (only with the `tracing` feature); the fields must not be sensitive
+macro_rules! step_span {
+    ($name:literal, $len:expr $(, $field:ident = $value:expr)*) => {
+        #[cfg(feature = "tracing")]
+        let _span = crate::span::StepSpan::enter(tracing::info_span!(
+            $name,
+            group_bits = $len * 8,
+            $($field = %$value,)*
+            duration_us = tracing::field::Empty,
+        ));
+    };
+}
+
+/// evaluates `$body` and adds its duration to the phase `$phase` of the
+/// [`metrics::PhaseTimings`] of this thread (only with the `metrics` feature)
+macro_rules! timed_phase {
+    ($phase:ident, $body:expr) => {{
+        #[cfg(feature = "metrics")]
+        let start = std::time::Instant::now();
+        let value = $body;
+        #[cfg(feature = "metrics")]
+        crate::metrics::record(|timings| timings.$phase += start.elapsed());
+        value
+    }};
+}
+
 #[cfg(doc)]
 pub mod protocol_details;
 #[cfg(not(doc))]
@@ -27,22 +135,66 @@ mod protocol_details;
 
 pub(crate) mod primitives;
 
+pub mod compat;
+#[cfg(feature = "hazmat")]
+pub mod hazmat;
+#[cfg(feature = "axum")]
+pub mod integrations;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+#[cfg(feature = "server")]
+pub mod server;
+pub mod session;
+#[cfg(feature = "test-util")]
+pub mod test_util;
+pub mod tls;
+#[cfg(feature = "transcript")]
+pub mod transcript;
+
 mod api;
 mod big_number;
 mod hash;
+#[cfg(feature = "tracing")]
+mod span;
+#[cfg(feature = "wasm")]
+mod wasm;
 
-pub use api::{host::*, user::*};
+#[cfg(feature = "rayon")]
+pub use api::batch::generate_user_details_batch;
+#[cfg(feature = "mutable-api")]
+pub use api::mutable;
+#[cfg(feature = "std")]
+pub use api::stepper::{HandshakeStepper, StepResult};
+#[cfg(feature = "wow")]
+pub use api::wow;
+pub use api::{dynamic::*, host::*, password_change::*, user::*};
+pub use big_number::{BigNumberError, BoundedBigNumber, ByteOrder, FixedBasePowTable};
+pub use compat::{CompatProfile, ProofScheme};
+pub use core::convert::TryInto;
+pub use hash::{Sha1, Sha256};
 pub use primitives::{
-    ClearTextPassword, Generator, MultiplierParameter, OpenConstants, PasswordVerifier,
-    PrimeModulus, PrivateKey, Proof, PublicKey, Salt, ServerHandshake, SessionKey, StrongProof,
-    StrongSessionKey, UserCredentials, UserDetails, UserHandshake, Username, UsernameRef,
+    authenticate_nonce, proof_from_bytes, strong_session_key_from_bytes, validate_public_key,
+    validate_username, Bounded, ClearTextPassword, ClientHandshakeOutcome, ClientSession,
+    CredentialPolicy, EncKey, Generator, GroupParams, GroupParamsMessage, HostHandshakeOutcome,
+    IdentityMode, KdfParams, MacKey, MultiplierParameter, OpenConstants, Password,
+    PasswordVerifier, PrimeModulus, PrivateKey, Proof, ProofMessage, PublicKey, RawNumber, Salt,
+    ServerHandshake, SessionId, SessionKey, SessionSecret, Srp6Variant, StrongProof,
+    StrongSessionKey, TrustedGroups, UserCredentials, UserDetails, UserHandshake, UserPublicKey,
+    Username, UsernameRef, DEFAULT_SALT_LENGTH, ENC_KEY_INFO, HASH_LENGTH, MAC_KEY_INFO,
+    MILLER_RABIN_ROUNDS, MIN_KEY_LENGTH, SPLIT_KEY_LENGTH, USER_DETAILS_VERSION,
 };
-pub use std::convert::TryInto;
+#[cfg(feature = "wasm")]
+pub use wasm::WasmSrp6User;
 
 /// encapsulates a [`Srp6Error`]
-pub type Result<T> = std::result::Result<T, Srp6Error>;
+pub type Result<T> = core::result::Result<T, Srp6Error>;
 
-#[derive(Error, Display, Debug, PartialEq, serde::Serialize)]
+/// The errors of the handshake and of the parsing
+///
+/// The displayed numbers are shortened to their first hex digits and their
+/// length. It serializes as `{ "kind": .., "message": .. }`, with the message
+/// as displayed, so it can be given to a client as is.
+#[derive(Error, Display, Debug, Clone, PartialEq)]
 pub enum Srp6Error {
     #[display(
         "The provided key length ({given} bytes) does not match the expected ({expected} byte)"
@@ -50,21 +202,209 @@ pub enum Srp6Error {
     KeyLengthMismatch { given: usize, expected: usize },
 
     #[display("The provided proof is invalid")]
-    InvalidProof(#[error(not(source))] Proof),
+    InvalidProof,
+
+    #[display("The provided proof does not match, see the diagnostics")]
+    MismatchedProof(#[error(not(source))] ProofDiagnostics),
 
-    #[display("The provided strong proof is invalid")]
+    #[display("The provided strong proof is invalid ({})", _0.fingerprint())]
     InvalidStrongProof(#[error(not(source))] StrongProof),
 
-    #[display("The provided public key is invalid")]
+    #[display("The provided public key is invalid ({})", _0.fingerprint())]
     InvalidPublicKey(#[error(not(source))] PublicKey),
+
+    #[display("The provided public key is not lower than the modulus ({})", _0.fingerprint())]
+    PublicKeyOutOfRange(#[error(not(source))] PublicKey),
+
+    #[display("The provided public key is 1")]
+    PublicKeyIsOne,
+
+    #[display("The provided public key is the modulus minus 1")]
+    PublicKeyIsModulusMinusOne,
+
+    #[display("The scrambling parameter u is zero")]
+    InvalidScramblingParameter,
+
+    #[display("The password verifier is zero or not lower than the modulus")]
+    InvalidVerifier,
+
+    #[display("The salt is empty or zero")]
+    InvalidSalt,
+
+    #[display("The salt ({given} bytes) is longer than the modulus ({expected} bytes)")]
+    SaltLengthMismatch { given: usize, expected: usize },
+
+    #[display("The salt sent by the server is not the pinned one")]
+    SaltMismatch { expected: Salt, received: Salt },
+
+    #[display("The username is empty")]
+    EmptyUsername,
+
+    #[display("The password is empty")]
+    EmptyPassword,
+
+    #[display("The username of {length} bytes is longer than {maximum} bytes")]
+    UsernameTooLong { length: usize, maximum: usize },
+
+    #[display("The password of {length} characters is shorter than {minimum} characters")]
+    PasswordTooShort { length: usize, minimum: usize },
+
+    #[display("The password of {length} bytes is longer than {maximum} bytes")]
+    PasswordTooLong { length: usize, maximum: usize },
+
+    #[display("The key derivation parameters are invalid")]
+    InvalidKdfParams,
+
+    #[display("The message is malformed")]
+    MalformedMessage,
+
+    #[display("The message contains an invalid hex string")]
+    InvalidHex,
+
+    #[display("The message is truncated")]
+    TruncatedMessage,
+
+    #[display("The message has {count} trailing bytes")]
+    TrailingBytes { count: usize },
+
+    #[display("The username is not valid UTF-8, or contains ':' or a control character")]
+    InvalidUsername,
+
+    #[display("The modulus is not a safe prime")]
+    NotASafePrime,
+
+    #[display("The generator is not between 1 and N - 1")]
+    InvalidGenerator,
+
+    #[display("The number is malformed: {_0}")]
+    Malformed(BigNumberError),
+
+    #[display("The password change request is not authenticated")]
+    InvalidPasswordChange,
+
+    #[display("The verifier upgrade is not authenticated")]
+    InvalidVerifierUpgrade,
+
+    #[display("Too many handshakes are waiting for a proof")]
+    TooManyPendingHandshakes,
+
+    #[display("The handshake is unknown or expired")]
+    UnknownHandshake,
+
+    #[display("The group offered by the server is not trusted ({_0})")]
+    UntrustedGroup(#[error(not(source))] alloc::string::String),
+
+    #[display("The group of {bits} bits is below the minimum of {minimum} bits")]
+    InsecureKeyLength { bits: usize, minimum: usize },
+
+    #[display("The group of the client of {client_bits} bits is not the group of the server of {server_bits} bits")]
+    GroupMismatch { client_bits: u16, server_bits: u16 },
+
+    #[display("The handshake was not started, or its ephemeral key was already used")]
+    StaleHandshake,
+
+    #[display("The configuration is invalid: {_0}")]
+    InvalidConfiguration(#[error(not(source))] &'static str),
+
+    #[display(
+        "The stored details have version {given}, this release reads up to version {supported}"
+    )]
+    UnsupportedVersion { given: u8, supported: u8 },
+}
+
+impl From<BigNumberError> for Srp6Error {
+    fn from(e: BigNumberError) -> Self {
+        Srp6Error::Malformed(e)
+    }
+}
+
+impl Srp6Error {
+    /// whether the other side failed to authenticate, e.g. a wrong password or
+    /// an invalid public key, rather than a malformed message or a local error
+    ///
+    /// a server would answer `401 Unauthorized` for these.
+    pub fn is_authentication_failure(&self) -> bool {
+        matches!(
+            self,
+            Srp6Error::InvalidProof
+                | Srp6Error::MismatchedProof(_)
+                | Srp6Error::InvalidStrongProof(_)
+                | Srp6Error::SaltMismatch { .. }
+                | Srp6Error::InvalidPublicKey(_)
+                | Srp6Error::PublicKeyOutOfRange(_)
+                | Srp6Error::PublicKeyIsOne
+                | Srp6Error::PublicKeyIsModulusMinusOne
+                | Srp6Error::InvalidScramblingParameter
+                | Srp6Error::InvalidPasswordChange
+                | Srp6Error::InvalidVerifierUpgrade
+                | Srp6Error::UnknownHandshake
+        )
+    }
+
+    /// the name of the variant
+    fn kind(&self) -> &'static str {
+        match self {
+            Srp6Error::KeyLengthMismatch { .. } => "KeyLengthMismatch",
+            Srp6Error::InvalidProof => "InvalidProof",
+            Srp6Error::MismatchedProof(_) => "MismatchedProof",
+            Srp6Error::InvalidStrongProof(_) => "InvalidStrongProof",
+            Srp6Error::InvalidPublicKey(_) => "InvalidPublicKey",
+            Srp6Error::PublicKeyOutOfRange(_) => "PublicKeyOutOfRange",
+            Srp6Error::PublicKeyIsOne => "PublicKeyIsOne",
+            Srp6Error::PublicKeyIsModulusMinusOne => "PublicKeyIsModulusMinusOne",
+            Srp6Error::InvalidScramblingParameter => "InvalidScramblingParameter",
+            Srp6Error::InvalidVerifier => "InvalidVerifier",
+            Srp6Error::InvalidSalt => "InvalidSalt",
+            Srp6Error::SaltLengthMismatch { .. } => "SaltLengthMismatch",
+            Srp6Error::SaltMismatch { .. } => "SaltMismatch",
+            Srp6Error::EmptyUsername => "EmptyUsername",
+            Srp6Error::EmptyPassword => "EmptyPassword",
+            Srp6Error::UsernameTooLong { .. } => "UsernameTooLong",
+            Srp6Error::PasswordTooShort { .. } => "PasswordTooShort",
+            Srp6Error::PasswordTooLong { .. } => "PasswordTooLong",
+            Srp6Error::InvalidKdfParams => "InvalidKdfParams",
+            Srp6Error::MalformedMessage => "MalformedMessage",
+            Srp6Error::InvalidHex => "InvalidHex",
+            Srp6Error::TruncatedMessage => "TruncatedMessage",
+            Srp6Error::TrailingBytes { .. } => "TrailingBytes",
+            Srp6Error::InvalidUsername => "InvalidUsername",
+            Srp6Error::NotASafePrime => "NotASafePrime",
+            Srp6Error::InvalidGenerator => "InvalidGenerator",
+            Srp6Error::Malformed(_) => "Malformed",
+            Srp6Error::InvalidPasswordChange => "InvalidPasswordChange",
+            Srp6Error::InvalidVerifierUpgrade => "InvalidVerifierUpgrade",
+            Srp6Error::TooManyPendingHandshakes => "TooManyPendingHandshakes",
+            Srp6Error::UnknownHandshake => "UnknownHandshake",
+            Srp6Error::UntrustedGroup(_) => "UntrustedGroup",
+            Srp6Error::InsecureKeyLength { .. } => "InsecureKeyLength",
+            Srp6Error::GroupMismatch { .. } => "GroupMismatch",
+            Srp6Error::StaleHandshake => "StaleHandshake",
+            Srp6Error::InvalidConfiguration(_) => "InvalidConfiguration",
+            Srp6Error::UnsupportedVersion { .. } => "UnsupportedVersion",
+        }
+    }
+}
+
+/// only the kind and the displayed message, without the numbers of the variants
+impl serde::Serialize for Srp6Error {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> core::result::Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+        let mut state = serializer.serialize_struct("Srp6Error", 2)?;
+        state.serialize_field("kind", self.kind())?;
+        state.serialize_field("message", &alloc::string::ToString::to_string(self))?;
+        state.end()
+    }
 }
 
 #[cfg(test)]
 mod tests {
 
     use super::*;
+    use crate::big_number::Zero;
 
-    #[cfg(feature = "norand")]
     use crate::protocol_details::testdata;
 
     /// Test similar to the example, full handshake but no data transfer.
@@ -74,170 +414,4026 @@ mod tests {
         let password: &ClearTextPassword = "secret-password";
         let constants = OpenConstants::default();
         // new user : those are sent to the server and stored there
-        let user_details = Srp6user4096::generate_new_user_secrets(username, password, &constants);
+        let user_details =
+            Srp6user4096::generate_new_user_secrets(&username.into(), &password.into(), &constants)
+                .unwrap();
         // user creates a handshake
         let mut srp6_user = Srp6user4096::default();
-        let user_handshake = srp6_user.start_handshake(username, &constants);
+        let user_handshake = srp6_user.start_handshake(username, &constants).unwrap();
         // server retrieves stored details and continues the handshake
-        let mut srp6 = Srp6_4096::default();
-        let server_handshake = srp6
+        let (server_handshake, srp6) = Srp6_4096::default()
             .continue_handshake(&user_details, &user_handshake.user_publickey, &constants)
             .unwrap();
         // client side
-        let proof = srp6_user
-            .update_handshake(&server_handshake, &constants, username, password)
+        let (proof, srp6_user) = srp6_user
+            .update_handshake(
+                &server_handshake,
+                &constants,
+                &username.into(),
+                &password.into(),
+            )
             .unwrap();
         // server side
-        let (hamk, secret) = srp6.verify_proof(&proof).unwrap();
+        let outcome = srp6.verify_proof(&proof).unwrap();
+        let hamk = outcome.server_proof().clone();
+        let secret = outcome.into_session_secret();
         // client side
-        let secret2 = srp6_user.verify_proof(&hamk).expect("invalid server proof");
+        let secret2 = srp6_user
+            .verify_proof(&hamk)
+            .expect("invalid server proof")
+            .into_session_secret();
         // both secrets
         assert_eq!(secret2, secret, "not same secrets");
     }
 
-    #[allow(unused_variables)]
-    fn trace(title: &str, val: &str) {
-        #[cfg(feature = "norand")]
-        println!("{title} = {val:#}")
+    #[test]
+    fn test_handshake_with_credentials() {
+        let (username, password) = ("Bob", "secret-password");
+        let constants = OpenConstants::<256>::default();
+        let user_details =
+            Srp6user2048::generate_new_user_secrets(&username.into(), &password.into(), &constants)
+                .unwrap();
+
+        // the username is only given once, see `tests/ui/user_credentials_username_twice.rs`
+        let mut srp6_user = Srp6user2048::with_credentials(&username.into(), &password.into());
+        assert_eq!(srp6_user.username(), username);
+        let user_handshake = srp6_user.start_handshake(&constants).unwrap();
+        assert_eq!(user_handshake.username, username);
+        assert_eq!(&user_handshake.user_publickey, srp6_user.public_key_a());
+        let host = Srp6_2048::new().for_user(&user_details);
+        assert_eq!(host.user_details(), &user_details);
+        let (server_handshake, srp6) = host
+            .continue_handshake(&user_handshake.user_publickey, &constants)
+            .unwrap();
+        let (proof, srp6_user) = srp6_user
+            .update_handshake(&server_handshake, &constants)
+            .unwrap();
+        let outcome = srp6.verify_proof(&proof).unwrap();
+        let hamk = outcome.server_proof().clone();
+        let secret = outcome.into_session_secret();
+        assert_eq!(
+            srp6_user
+                .verify_proof(&hamk)
+                .map(ClientHandshakeOutcome::into_session_secret),
+            Ok(secret)
+        );
+
+        // the former API still works, and still lets the usernames differ
+        let mut srp6_user = Srp6user2048::new();
+        let user_handshake = srp6_user.start_handshake(username, &constants).unwrap();
+        let (server_handshake, srp6) = Srp6_2048::new()
+            .continue_handshake(&user_details, &user_handshake.user_publickey, &constants)
+            .unwrap();
+        let (proof, _) = srp6_user
+            .update_handshake(
+                &server_handshake,
+                &constants,
+                &"Alice".into(),
+                &password.into(),
+            )
+            .unwrap();
+        assert!(matches!(
+            srp6.verify_proof(&proof),
+            Err(Srp6Error::InvalidProof)
+        ));
     }
 
-    /// Test a handshake simulating data transfer (serialize/deserialize)
-    #[test]
-    fn test_handshake_serde_2048() {
-        let username = "fred";
-        let password: &ClearTextPassword = "password_fred";
+    /// Full handshake using SHA-256, for a given server and user type
+    fn handshake_sha256<const LEN: usize>()
+    where
+        OpenConstants<LEN>: Default,
+    {
+        let username = "Bob";
+        let password: &ClearTextPassword = "secret-password";
+        let constants = OpenConstants::<LEN>::default();
+        let user_details = Srp6User::<LEN, Sha256>::generate_new_user_secrets(
+            &username.into(),
+            &password.into(),
+            &constants,
+        )
+        .unwrap();
+        let mut srp6_user = Srp6User::<LEN, Sha256>::default();
+        let user_handshake = srp6_user.start_handshake(username, &constants).unwrap();
+        let (server_handshake, srp6) = Srp6::<LEN, Sha256>::default()
+            .continue_handshake(&user_details, &user_handshake.user_publickey, &constants)
+            .unwrap();
+        let (proof, srp6_user) = srp6_user
+            .update_handshake(
+                &server_handshake,
+                &constants,
+                &username.into(),
+                &password.into(),
+            )
+            .unwrap();
+        assert!(proof.num_bytes() > 20, "not a SHA-256 proof");
+        let outcome = srp6.verify_proof(&proof).unwrap();
+        let hamk = outcome.server_proof().clone();
+        let secret = outcome.into_session_secret();
+        let secret2 = srp6_user
+            .verify_proof(&hamk)
+            .expect("invalid server proof")
+            .into_session_secret();
+        assert_eq!(secret2, secret, "not same secrets");
+    }
+
+    /// Full handshake with the default hash, checking the group size
+    fn handshake_group<const LEN: usize>()
+    where
+        OpenConstants<LEN>: Default,
+    {
+        let username = "Bob";
+        let password: &ClearTextPassword = "secret-password";
+        let constants = OpenConstants::<LEN>::default();
+        assert_eq!(constants.module.num_bytes(), LEN, "modulus length nok");
+        let user_details = Srp6User::<LEN>::generate_new_user_secrets(
+            &username.into(),
+            &password.into(),
+            &constants,
+        )
+        .unwrap();
+        let mut srp6_user = Srp6User::<LEN>::default();
+        let user_handshake = srp6_user.start_handshake(username, &constants).unwrap();
+        let (server_handshake, srp6) = Srp6::<LEN>::default()
+            .continue_handshake(&user_details, &user_handshake.user_publickey, &constants)
+            .unwrap();
+        let (proof, srp6_user) = srp6_user
+            .update_handshake(
+                &server_handshake,
+                &constants,
+                &username.into(),
+                &password.into(),
+            )
+            .unwrap();
+        let outcome = srp6.verify_proof(&proof).unwrap();
+        let hamk = outcome.server_proof().clone();
+        let secret = outcome.into_session_secret();
+        let secret2 = srp6_user
+            .verify_proof(&hamk)
+            .expect("invalid server proof")
+            .into_session_secret();
+        assert_eq!(secret2, secret, "not same secrets");
+    }
+
+    /// a test running `$check::<LEN>()` for each group: the small one of
+    /// `test-small-groups` always, the RFC 5054 ones in release builds or with
+    /// the `expensive-tests` feature
+    macro_rules! group_tests {
+        ($check:ident, $small:ident, $($name:ident => $len:literal),+ $(,)?) => {
+            #[test]
+            fn $small() {
+                $check::<32>();
+            }
+            $(
+                #[test]
+                #[cfg_attr(
+                    all(debug_assertions, not(feature = "expensive-tests")),
+                    ignore = "slow in debug builds, see the `expensive-tests` feature"
+                )]
+                fn $name() {
+                    $check::<$len>();
+                }
+            )+
+        };
+    }
+
+    group_tests!(
+        handshake_group,
+        test_handshake_group_256,
+        test_handshake_group_1024 => 128,
+        test_handshake_group_1536 => 192,
+        test_handshake_group_2048 => 256,
+        test_handshake_group_3072 => 384,
+        test_handshake_group_4096 => 512,
+        test_handshake_group_6144 => 768,
+        test_handshake_group_8192 => 1024,
+    );
+
+    group_tests!(
+        handshake_sha256,
+        test_handshake_sha256_256,
+        test_handshake_sha256_2048 => 256,
+        test_handshake_sha256_4096 => 512,
+    );
+
+    /// a handshake of Bob up to the strong proof of the host
+    fn verified_by_host() -> (HostHandshakeOutcome, Srp6UserWaitingForServerProof<256>) {
+        let username = "Bob";
+        let password: &ClearTextPassword = "secret-password";
         let constants = OpenConstants::default();
-        // new user : those are sent to the server and stored there
-        let user_details = Srp6user2048::generate_new_user_secrets(username, password, &constants);
-        let transfer = serde_json::to_string(&user_details).unwrap();
-        trace("details", &transfer);
-        // server side (stores)
-        let user_details = serde_json::from_str::<UserDetails>(&transfer).unwrap();
-        // user creates a handshake
+        let user_details =
+            Srp6user2048::generate_new_user_secrets(&username.into(), &password.into(), &constants)
+                .unwrap();
         let mut srp6_user = Srp6user2048::default();
-        let user_handshake = srp6_user.start_handshake(username, &constants);
-        let transfer = serde_json::to_string(&user_handshake).unwrap();
-        trace("user_hs", &transfer);
-        // server retrieves stored details and continues the handshake
-        let user_handshake = serde_json::from_str::<UserHandshake>(&transfer).unwrap();
-        let mut srp6 = Srp6_2048::default();
-        let server_handshake = srp6
+        let user_handshake = srp6_user.start_handshake(username, &constants).unwrap();
+        let (server_handshake, srp6) = Srp6_2048::default()
             .continue_handshake(&user_details, &user_handshake.user_publickey, &constants)
             .unwrap();
-        let transfer = serde_json::to_string(&server_handshake).unwrap();
-        trace("server_hs", &transfer);
-        // client side
-        let server_handshake = serde_json::from_str::<ServerHandshake>(&transfer).unwrap();
-        let proof = srp6_user
-            .update_handshake(&server_handshake, &constants, username, password)
+        let (proof, srp6_user) = srp6_user
+            .update_handshake(
+                &server_handshake,
+                &constants,
+                &username.into(),
+                &password.into(),
+            )
             .unwrap();
-        let transfer = serde_json::to_string(&proof).unwrap();
-        trace("proof", &transfer);
-        // server side
-        let proof = serde_json::from_str::<Proof>(&transfer).unwrap();
-        let (hamk, secret) = srp6.verify_proof(&proof).unwrap();
-        let transfer = serde_json::to_string(&hamk).unwrap();
-        trace("sproof", &transfer);
-        // client side
-        let hamk = serde_json::from_str::<Proof>(&transfer).unwrap();
-        let secret2 = srp6_user.verify_proof(&hamk).expect("invalid server proof");
-        // both secrets
-        assert_eq!(secret2, secret, "not same secrets");
+        (srp6.verify_proof(&proof).unwrap(), srp6_user)
     }
 
-    /// Test the handshake against an official test data.
-    #[cfg(feature = "norand")]
     #[test]
-    fn test_official_vectors_1024() {
-        type Srp6User1024 = Srp6User<128>;
-        type Srp61024 = Srp6<128>;
-        let username = testdata::USERNAME;
-        let password: &ClearTextPassword = testdata::PASSWORD;
-        let constants = OpenConstants::default();
-        // new user : those are sent to the server and stored there
-        let user_details = Srp6User1024::generate_new_user_secrets(username, password, &constants);
-        let official_verifier = PublicKey::from_bytes_be(&testdata::VERIFIER);
-        assert_eq!(official_verifier, user_details.verifier, "verifier nok");
-        // user creates a handshake
-        let mut srp6_user = Srp6User1024::default();
-        let user_handshake = srp6_user.start_handshake(username, &constants);
-        let official_user_publickey = PublicKey::from_bytes_be(&testdata::A_PUBLIC);
+    fn test_handshake_outcomes() {
+        let before = std::time::SystemTime::now();
+        let (host, srp6_user) = verified_by_host();
+        let user = srp6_user.verify_proof(host.server_proof()).unwrap();
+        assert_eq!(user.session_secret(), host.session_secret());
+        assert_eq!(user.strong_session_key(), host.strong_session_key());
+        assert!(host.established_at() >= before);
+        assert!(user.established_at() >= host.established_at());
+        assert_eq!(user.into_session_secret(), host.into_session_secret());
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_tampered_server_proof() {
+        let (host, srp6_user) = verified_by_host();
+        let tampered =
+            StrongProof::from(host.server_proof().number() + &big_number::BigNumber::from(1));
+        // formerly `None`, now the error tells which proof was rejected
         assert_eq!(
-            official_user_publickey, user_handshake.user_publickey,
-            "A nok"
+            srp6_user.verify_proof(&tampered).err(),
+            Some(Srp6Error::InvalidStrongProof(tampered))
         );
-        // server retrieves stored details and continues the handshake
-        let mut srp6 = Srp61024::default();
-        let server_handshake = srp6
+
+        let (host, srp6_user) = verified_by_host();
+        let tampered =
+            StrongProof::from(host.server_proof().number() + &big_number::BigNumber::from(1));
+        assert_eq!(srp6_user.verify_proof_option(&tampered), None);
+        let (host, srp6_user) = verified_by_host();
+        assert_eq!(
+            srp6_user.verify_proof_option(host.server_proof()),
+            Some(host.into_session_secret())
+        );
+
+        let (host, srp6_user) = verified_by_host();
+        let tampered =
+            StrongProof::from(host.server_proof().number() + &big_number::BigNumber::from(1));
+        assert_eq!(
+            srp6_user.verify_session(&tampered).err(),
+            Some(Srp6Error::InvalidStrongProof(tampered))
+        );
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_verify_proof_tuple() {
+        let username = "Bob";
+        let password: &ClearTextPassword = "secret-password";
+        let constants = OpenConstants::default();
+        let user_details =
+            Srp6user2048::generate_new_user_secrets(&username.into(), &password.into(), &constants)
+                .unwrap();
+        let mut srp6_user = Srp6user2048::default();
+        let user_handshake = srp6_user.start_handshake(username, &constants).unwrap();
+        let (server_handshake, srp6) = Srp6_2048::default()
             .continue_handshake(&user_details, &user_handshake.user_publickey, &constants)
             .unwrap();
-        let official_server_publickey = PublicKey::from_bytes_be(&testdata::B_PUBLIC);
+        let (proof, srp6_user) = srp6_user
+            .update_handshake(
+                &server_handshake,
+                &constants,
+                &username.into(),
+                &password.into(),
+            )
+            .unwrap();
+        let (strong_proof, secret) = srp6.verify_proof_tuple(&proof).unwrap();
+        let user = srp6_user.verify_proof(&strong_proof).unwrap();
+        assert_eq!(user.into_session_secret(), secret);
+    }
+
+    #[cfg(feature = "outcome-serde")]
+    #[test]
+    fn test_serialize_outcomes() {
+        let (host, srp6_user) = verified_by_host();
+        let user = srp6_user.verify_proof(host.server_proof()).unwrap();
+        let host_json = serde_json::to_value(&host).unwrap();
+        let user_json = serde_json::to_value(&user).unwrap();
         assert_eq!(
-            official_server_publickey, server_handshake.server_publickey,
-            "B nok"
+            host_json["server_proof"],
+            serde_json::to_value(host.server_proof()).unwrap()
         );
-        // client side
-        let proof = srp6_user
-            .update_handshake(&server_handshake, &constants, username, password)
-            .unwrap();
-        // server side
-        let (hamk, secret) = srp6.verify_proof(&proof).unwrap();
-        // client side
-        let secret2 = srp6_user.verify_proof(&hamk).expect("invalid server proof");
-        // both secrets
-        assert_eq!(secret2, secret, "not same secrets");
-        // compare official numbers
-        let expected_secret = PrivateKey::from_bytes_be(&testdata::SECRET);
-        assert_eq!(expected_secret, secret, "S nok");
+        assert_eq!(host_json["session_key"], user_json["session_key"]);
+        assert_eq!(
+            host_json["session_key"],
+            serde_json::to_value(host.strong_session_key()).unwrap()
+        );
+        assert!(host_json["established_at"].is_object());
+        assert!(user_json.get("server_proof").is_none());
+        assert_eq!(host_json.as_object().unwrap().len(), 3);
     }
 
     #[test]
-    fn test_length_mismatch_1() {
+    fn test_hash_mismatch() {
         let username = "Bob";
         let password: &ClearTextPassword = "secret-password";
-        // client is 4096
-        let user_constants = OpenConstants::default();
+        let constants = OpenConstants::default();
         let user_details =
-            Srp6user4096::generate_new_user_secrets(username, password, &user_constants);
-        let mut srp6_user = Srp6user4096::default();
-        let user_handshake = srp6_user.start_handshake(username, &user_constants);
-        // server is 2048
-        let server_constants = OpenConstants::default();
-        let mut srp6 = Srp6_2048::default();
-        let err = srp6
-            .continue_handshake(
-                &user_details,
-                &user_handshake.user_publickey,
-                &server_constants,
+            Srp6user2048::generate_new_user_secrets(&username.into(), &password.into(), &constants)
+                .unwrap();
+        // client uses SHA-256, server SHA-1
+        let mut srp6_user = Srp6user2048Sha256::default();
+        let user_handshake = srp6_user.start_handshake(username, &constants).unwrap();
+        let (server_handshake, srp6) = Srp6_2048::default()
+            .continue_handshake(&user_details, &user_handshake.user_publickey, &constants)
+            .unwrap();
+        let (proof, _) = srp6_user
+            .update_handshake(
+                &server_handshake,
+                &constants,
+                &username.into(),
+                &password.into(),
             )
-            .unwrap_err();
-        assert!(matches!(err, Srp6Error::KeyLengthMismatch { .. }));
+            .unwrap();
+        let err = srp6.verify_proof(&proof).unwrap_err();
+        assert!(matches!(err, Srp6Error::InvalidProof));
+    }
+
+    fn handshake_stretched(kdf: KdfParams, users_kdf: KdfParams) -> Result<StrongProof> {
+        let username = "Bob";
+        let password: &ClearTextPassword = "secret-password";
+        let constants = OpenConstants::default();
+        let user_details = Srp6user2048::generate_new_user_secrets_stretched(
+            &username.into(),
+            &password.into(),
+            &constants,
+            kdf,
+        )
+        .unwrap();
+        assert_eq!(user_details.kdf, Some(kdf));
+
+        let mut srp6_user = Srp6user2048::default();
+        let user_handshake = srp6_user.start_handshake(username, &constants).unwrap();
+        let (server_handshake, srp6) = Srp6_2048::default()
+            .continue_handshake(&user_details, &user_handshake.user_publickey, &constants)
+            .unwrap();
+        let (proof, srp6_user) = srp6_user.update_handshake_stretched(
+            &server_handshake,
+            &constants,
+            &username.into(),
+            &password.into(),
+            &users_kdf,
+        )?;
+        let strong_proof = srp6.verify_proof(&proof)?.server_proof().clone();
+        assert!(srp6_user.verify_proof(&strong_proof).is_ok());
+        Ok(strong_proof)
     }
 
     #[test]
-    fn test_length_mismatch_2() {
+    fn test_handshake_stretched_pbkdf2() {
+        let kdf = KdfParams::Pbkdf2 { iterations: 1000 };
+        assert!(handshake_stretched(kdf, kdf).is_ok());
+    }
+
+    #[test]
+    #[cfg(feature = "argon2")]
+    fn test_handshake_stretched_argon2() {
+        let kdf = KdfParams::Argon2id {
+            m_cost: 64,
+            t_cost: 1,
+            p_cost: 1,
+        };
+        assert!(handshake_stretched(kdf, kdf).is_ok());
+        let err = handshake_stretched(kdf, KdfParams::Pbkdf2 { iterations: 1000 }).unwrap_err();
+        assert!(matches!(err, Srp6Error::InvalidProof));
+    }
+
+    #[test]
+    fn test_kdf_mismatch() {
+        let err = handshake_stretched(
+            KdfParams::Pbkdf2 { iterations: 1000 },
+            KdfParams::Pbkdf2 { iterations: 1001 },
+        )
+        .unwrap_err();
+        assert!(matches!(err, Srp6Error::InvalidProof));
+    }
+
+    /// full handshake, each side with its own constants
+    fn handshake_variants(
+        host_constants: &OpenConstants<256>,
+        user_constants: &OpenConstants<256>,
+    ) -> Result<()> {
         let username = "Bob";
         let password: &ClearTextPassword = "secret-password";
-        // client is 2048
-        let user_constants = OpenConstants::default();
-        let user_details =
-            Srp6user2048::generate_new_user_secrets(username, password, &user_constants);
+        let user_details = Srp6user2048::generate_new_user_secrets(
+            &username.into(),
+            &password.into(),
+            user_constants,
+        )
+        .unwrap();
         let mut srp6_user = Srp6user2048::default();
-        let user_handshake = srp6_user.start_handshake(username, &user_constants);
-        // server is 4096
-        let server_constants = OpenConstants::default();
-        let mut srp6 = Srp6_4096::default();
-        let server_handshake = srp6
-            .continue_handshake(
-                &user_details,
-                &user_handshake.user_publickey,
-                &server_constants,
+        let user_handshake = srp6_user.start_handshake(username, user_constants).unwrap();
+        let (server_handshake, srp6) = Srp6_2048::default().continue_handshake(
+            &user_details,
+            &user_handshake.user_publickey,
+            host_constants,
+        )?;
+        let (proof, srp6_user) = srp6_user.update_handshake(
+            &server_handshake,
+            user_constants,
+            &username.into(),
+            &password.into(),
+        )?;
+        let outcome = srp6.verify_proof(&proof)?;
+        let strong_proof = outcome.server_proof().clone();
+        let secret = outcome.into_session_secret();
+        assert_eq!(
+            srp6_user
+                .verify_proof(&strong_proof)
+                .map(ClientHandshakeOutcome::into_session_secret),
+            Ok(secret)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_handshake_legacy() {
+        let legacy = OpenConstants::<256> {
+            variant: Srp6Variant::Legacy,
+            ..OpenConstants::default()
+        };
+        assert!(handshake_variants(&legacy, &legacy).is_ok());
+        assert_eq!(GroupParams::from(&legacy).variant, Srp6Variant::Legacy);
+    }
+
+    #[test]
+    fn test_mixed_variants() {
+        let srp6a = OpenConstants::<256>::default();
+        let legacy = OpenConstants::<256> {
+            variant: Srp6Variant::Legacy,
+            ..OpenConstants::default()
+        };
+        assert!(matches!(
+            handshake_variants(&legacy, &srp6a),
+            Err(Srp6Error::InvalidProof)
+        ));
+        assert!(matches!(
+            handshake_variants(&srp6a, &legacy),
+            Err(Srp6Error::InvalidProof)
+        ));
+    }
+
+    #[test]
+    fn test_verify_proof_diagnose() {
+        let username = "Bob";
+        let constants = OpenConstants::<256>::default();
+        let user_details = Srp6user2048::generate_new_user_secrets(
+            &username.into(),
+            &"secret-password".into(),
+            &constants,
+        )
+        .unwrap();
+        let mut srp6_user = Srp6user2048::default();
+        let user_handshake = srp6_user.start_handshake(username, &constants).unwrap();
+        let (server_handshake, srp6) = Srp6_2048::default()
+            .continue_handshake(&user_details, &user_handshake.user_publickey, &constants)
+            .unwrap();
+        let (_, srp6_again) = Srp6_2048::default()
+            .continue_handshake(&user_details, &user_handshake.user_publickey, &constants)
+            .unwrap();
+        let (proof, srp6_user) = srp6_user
+            .update_handshake(
+                &server_handshake,
+                &constants,
+                &username.into(),
+                &"wrong-password".into(),
             )
             .unwrap();
-        // client will detect
-        let err = srp6_user
-            .update_handshake(&server_handshake, &user_constants, username, password)
-            .unwrap_err();
-        assert!(matches!(err, Srp6Error::KeyLengthMismatch { .. }));
+
+        let Err(Srp6Error::MismatchedProof(diagnostics)) = srp6.verify_proof_diagnose(&proof)
+        else {
+            panic!("the proof should not match");
+        };
+        // same public keys on both sides, so the proofs differ because of `K`
+        assert_eq!(&diagnostics.user_publickey, srp6_user.public_key_a());
+        assert_eq!(&diagnostics.server_publickey, srp6_user.server_public_key());
+        assert_eq!(diagnostics.users_proof(), &proof);
+        assert_eq!(diagnostics.group_bits, 2048);
+        assert_eq!(diagnostics.salt_length, user_details.salt.num_bytes());
+        assert_eq!(
+            diagnostics.user_publickey_length,
+            srp6_user.public_key_a().num_bytes()
+        );
+        assert!(diagnostics.strong_session_key_length <= 40);
+        // the proof is only in the diagnostics, not in the serialized form
+        let json = serde_json::to_string(&diagnostics).unwrap();
+        assert!(!json.contains(&proof.to_string()));
+        // the normal check does not carry the proof
+        assert_eq!(
+            srp6_again.verify_proof(&proof).err(),
+            Some(Srp6Error::InvalidProof)
+        );
+
+        // a matching proof gives the outcome of `verify_proof`
+        let mut srp6_user = Srp6user2048::default();
+        let user_handshake = srp6_user.start_handshake(username, &constants).unwrap();
+        let (server_handshake, srp6) = Srp6_2048::default()
+            .continue_handshake(&user_details, &user_handshake.user_publickey, &constants)
+            .unwrap();
+        let (proof, srp6_user) = srp6_user
+            .update_handshake(
+                &server_handshake,
+                &constants,
+                &username.into(),
+                &"secret-password".into(),
+            )
+            .unwrap();
+        let outcome = srp6.verify_proof_diagnose(&proof).unwrap();
+        assert!(srp6_user.verify_proof(outcome.server_proof()).is_ok());
+    }
+
+    #[cfg(feature = "mutable-api")]
+    #[test]
+    #[allow(deprecated)]
+    fn test_handshake_mutable_api() {
+        let username = "Bob";
+        let password: &ClearTextPassword = "secret-password";
+        let constants = OpenConstants::<256>::default();
+        let user_details =
+            Srp6user2048::generate_new_user_secrets(&username.into(), &password.into(), &constants)
+                .unwrap();
+        let mut srp6_user = mutable::Srp6User::<256>::default();
+        let user_handshake = srp6_user.start_handshake(username, &constants).unwrap();
+        let mut srp6 = mutable::Srp6::<256>::default();
+        let server_handshake = srp6
+            .continue_handshake(&user_details, &user_handshake.user_publickey, &constants)
+            .unwrap();
+        assert_eq!(srp6.B, server_handshake.server_publickey);
+        let proof = srp6_user
+            .update_handshake(&server_handshake, &constants, username, password)
+            .unwrap();
+        assert_eq!(srp6_user.M, proof);
+        let outcome = srp6.verify_proof_diagnose(&proof).unwrap();
+        let strong_proof = outcome.server_proof().clone();
+        let secret = outcome.into_session_secret();
+        assert_eq!(
+            srp6_user
+                .verify_proof(&strong_proof)
+                .map(ClientHandshakeOutcome::into_session_secret),
+            Ok(secret)
+        );
+        // no handshake, no proof
+        assert_eq!(
+            mutable::Srp6::<256>::default().verify_proof(&proof).err(),
+            Some(Srp6Error::InvalidProof)
+        );
+        assert_eq!(
+            mutable::Srp6User::<256>::default()
+                .verify_proof(&strong_proof)
+                .err(),
+            Some(Srp6Error::InvalidStrongProof(strong_proof))
+        );
+    }
+
+    /// full handshake of a user who may have been renamed, the user side
+    /// with the given identity mode
+    fn handshake_identity(
+        user_details: &UserDetails,
+        username: UsernameRef,
+        identity: IdentityMode,
+    ) -> Result<()> {
+        let constants = OpenConstants::<256>::default();
+        let mut srp6_user = Srp6user2048::default().with_identity(identity);
+        let user_handshake = srp6_user.start_handshake(username, &constants).unwrap();
+        let (server_handshake, srp6) = Srp6_2048::default().continue_handshake(
+            user_details,
+            &user_handshake.user_publickey,
+            &constants,
+        )?;
+        let (proof, srp6_user) = srp6_user.update_handshake(
+            &server_handshake,
+            &constants,
+            &username.into(),
+            &"secret".into(),
+        )?;
+        let outcome = srp6.verify_proof(&proof)?;
+        let strong_proof = outcome.server_proof().clone();
+        let secret = outcome.into_session_secret();
+        assert_eq!(
+            srp6_user
+                .verify_proof(&strong_proof)
+                .map(ClientHandshakeOutcome::into_session_secret),
+            Ok(secret)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_identity_modes() {
+        let constants = OpenConstants::<256>::default();
+        for identity in [IdentityMode::Blank, IdentityMode::OpaqueId("42".to_owned())] {
+            let mut user_details = Srp6user2048::generate_new_user_secrets_with_identity(
+                &"alice".into(),
+                &"secret".into(),
+                &constants,
+                identity.clone(),
+            )
+            .unwrap();
+            assert_eq!(user_details.identity, identity);
+            assert!(handshake_identity(&user_details, "alice", identity.clone()).is_ok());
+            // renamed, with the original verifier
+            user_details.username = "alice2".into();
+            assert!(handshake_identity(&user_details, "alice2", identity.clone()).is_ok());
+            // the user does not use the stored mode
+            assert!(matches!(
+                handshake_identity(&user_details, "alice2", IdentityMode::Username),
+                Err(Srp6Error::InvalidProof)
+            ));
+        }
+
+        // the default mode hashes the username
+        let mut user_details =
+            Srp6user2048::generate_new_user_secrets(&"alice".into(), &"secret".into(), &constants)
+                .unwrap();
+        assert_eq!(user_details.identity, IdentityMode::Username);
+        assert!(handshake_identity(&user_details, "alice", IdentityMode::Username).is_ok());
+        assert!(matches!(
+            handshake_identity(&user_details, "alice", IdentityMode::Blank),
+            Err(Srp6Error::InvalidProof)
+        ));
+        user_details.username = "alice2".into();
+        assert!(matches!(
+            handshake_identity(&user_details, "alice2", IdentityMode::Username),
+            Err(Srp6Error::InvalidProof)
+        ));
+    }
+
+    /// full handshake with the given profiles on the host and user sides
+    fn handshake_profiles(
+        user_details: &UserDetails,
+        host_profile: CompatProfile,
+        user_profile: CompatProfile,
+    ) -> Result<()> {
+        let constants = OpenConstants::<256>::default();
+        let mut srp6_user = Srp6user2048Sha256::default().with_profile(user_profile);
+        let user_handshake = srp6_user.start_handshake("alice", &constants).unwrap();
+        let (server_handshake, srp6) = Srp6_2048Sha256::default()
+            .with_profile(host_profile)
+            .continue_handshake(user_details, &user_handshake.user_publickey, &constants)?;
+        let (proof, srp6_user) = srp6_user.update_handshake(
+            &server_handshake,
+            &constants,
+            &"alice".into(),
+            &"secret".into(),
+        )?;
+        let outcome = srp6.verify_proof(&proof)?;
+        let hamk = outcome.server_proof().clone();
+        let secret = outcome.into_session_secret();
+        let secret2 = srp6_user.verify_proof(&hamk)?.into_session_secret();
+        assert_eq!(secret, secret2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_compat_profiles() {
+        let constants = OpenConstants::<256>::default();
+        for profile in [
+            CompatProfile::Rfc5054,
+            CompatProfile::Tssrp6a,
+            CompatProfile::PySrp,
+            CompatProfile::LegacyInterleave,
+        ] {
+            let user_details = Srp6user2048Sha256::generate_new_user_secrets_with_profile(
+                &"alice".into(),
+                &"secret".into(),
+                &constants,
+                profile,
+            )
+            .unwrap();
+            assert!(handshake_profiles(&user_details, profile, profile).is_ok());
+        }
+
+        // both sides must agree on the profile
+        let user_details = Srp6user2048Sha256::generate_new_user_secrets(
+            &"alice".into(),
+            &"secret".into(),
+            &constants,
+        )
+        .unwrap();
+        assert!(matches!(
+            handshake_profiles(
+                &user_details,
+                CompatProfile::Tssrp6a,
+                CompatProfile::Rfc5054
+            ),
+            Err(Srp6Error::InvalidProof)
+        ));
+        assert!(matches!(
+            handshake_profiles(&user_details, CompatProfile::Rfc5054, CompatProfile::PySrp),
+            Err(Srp6Error::InvalidProof)
+        ));
+    }
+
+    /// full handshake with the given proof schemes on the host and user sides
+    fn handshake_schemes<D: hash::Digest>(
+        host_scheme: ProofScheme,
+        user_scheme: ProofScheme,
+    ) -> Result<()> {
+        let constants = OpenConstants::<128>::default();
+        let user_details = Srp6::<128, D>::generate_new_user_secrets(
+            &"alice".into(),
+            &"secret".into(),
+            &constants,
+        )
+        .unwrap();
+        let mut srp6_user = Srp6User::<128, D>::with_credentials(&"alice".into(), &"secret".into())
+            .with_proof_scheme(user_scheme);
+        let user_handshake = srp6_user.start_handshake(&constants).unwrap();
+        let (server_handshake, srp6) = Srp6::<128, D>::default()
+            .with_proof_scheme(host_scheme)
+            .continue_handshake(&user_details, &user_handshake.user_publickey, &constants)?;
+        let (proof, srp6_user) = srp6_user.update_handshake(&server_handshake, &constants)?;
+        let outcome = srp6.verify_proof(&proof)?;
+        let hamk = outcome.server_proof().clone();
+        let secret = outcome.into_session_secret();
+        let secret2 = srp6_user.verify_proof(&hamk)?.into_session_secret();
+        assert_eq!(secret, secret2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_proof_schemes() {
+        for scheme in [ProofScheme::HashConcat, ProofScheme::Hmac] {
+            assert!(handshake_schemes::<Sha1>(scheme, scheme).is_ok());
+            assert!(handshake_schemes::<Sha256>(scheme, scheme).is_ok());
+        }
+
+        // both sides must agree on the scheme
+        assert!(matches!(
+            handshake_schemes::<Sha1>(ProofScheme::Hmac, ProofScheme::HashConcat),
+            Err(Srp6Error::InvalidProof)
+        ));
+        assert!(matches!(
+            handshake_schemes::<Sha256>(ProofScheme::HashConcat, ProofScheme::Hmac),
+            Err(Srp6Error::InvalidProof)
+        ));
+    }
+
+    #[test]
+    fn test_handshake_fixed_base_table() {
+        let constants = OpenConstants::<256>::default();
+        let with_table = OpenConstants::<256>::default().with_fixed_base_table();
+        assert!(handshake_variants(&with_table, &constants).is_ok());
+        assert!(handshake_variants(&constants, &with_table).is_ok());
+        // same verifier
+        let s = Salt::from(42);
+        assert_eq!(
+            Srp6_2048::generate_user_details_with_salt(
+                &"Bob".into(),
+                &"secret".into(),
+                &s,
+                &with_table
+            )
+            .unwrap()
+            .verifier,
+            Srp6_2048::generate_user_details_with_salt(
+                &"Bob".into(),
+                &"secret".into(),
+                &s,
+                &constants
+            )
+            .unwrap()
+            .verifier
+        );
+    }
+
+    #[test]
+    fn test_user_details_kdf_serde() {
+        let constants = OpenConstants::<256>::default();
+        let details =
+            Srp6user2048::generate_new_user_secrets(&"Bob".into(), &"secret".into(), &constants)
+                .unwrap();
+        let mut json = serde_json::to_value(&details).unwrap();
+        assert_eq!(json["kdf"], serde_json::Value::Null);
+        assert_eq!(json["identity"], "Username");
+        // rows stored by former versions have no `kdf` nor `identity`
+        json.as_object_mut().unwrap().remove("kdf");
+        json.as_object_mut().unwrap().remove("identity");
+        let read: UserDetails = serde_json::from_value(json).unwrap();
+        assert_eq!(read.kdf, None);
+        assert_eq!(read.identity, IdentityMode::Username);
+        assert_eq!(read.verifier, details.verifier);
+
+        let kdf = KdfParams::Pbkdf2 { iterations: 1000 };
+        let details = Srp6user2048::generate_new_user_secrets_stretched(
+            &"Bob".into(),
+            &"secret".into(),
+            &constants,
+            kdf,
+        )
+        .unwrap();
+        let json = serde_json::to_string(&details).unwrap();
+        let read: UserDetails = serde_json::from_str(&json).unwrap();
+        assert_eq!(read.kdf, Some(kdf));
+        assert_eq!(read.verifier, details.verifier);
+    }
+
+    #[test]
+    fn test_user_details_version_serde() {
+        let constants = OpenConstants::<256>::default();
+        let details =
+            Srp6user2048::generate_new_user_secrets(&"Bob".into(), &"secret".into(), &constants)
+                .unwrap();
+        assert_eq!(details.version, USER_DETAILS_VERSION);
+        assert_eq!(details.group_fingerprint, Some(constants.fingerprint()));
+        assert_eq!(details.validate(&constants), Ok(()));
+        // a verifier below both `N`, but recorded for another group
+        let mut small = details.clone();
+        small.verifier = PasswordVerifier::from(2);
+        assert_eq!(small.validate(&constants), Ok(()));
+        assert_eq!(
+            small.validate(&OpenConstants::<384>::default()),
+            Err(Srp6Error::InvalidVerifier)
+        );
+
+        // current version, all the fields
+        let json = serde_json::to_string(&details).unwrap();
+        assert_eq!(serde_json::from_str::<UserDetails>(&json).unwrap(), details);
+
+        // a row of version 1, without `version` nor `group_fingerprint`
+        let mut v1 = serde_json::to_value(&details).unwrap();
+        v1.as_object_mut().unwrap().remove("version");
+        v1.as_object_mut().unwrap().remove("group_fingerprint");
+        let read: UserDetails = serde_json::from_value(v1).unwrap();
+        assert_eq!(read.version, 1);
+        assert_eq!(read.group_fingerprint, None);
+        let migrated = read.migrate();
+        assert_eq!(migrated.version, USER_DETAILS_VERSION);
+        assert_eq!(migrated.verifier, details.verifier);
+
+        // a row of a later release
+        let mut v99 = serde_json::to_value(&details).unwrap();
+        v99["version"] = 99.into();
+        let err = serde_json::from_value::<UserDetails>(v99)
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.contains(
+                &Srp6Error::UnsupportedVersion {
+                    given: 99,
+                    supported: USER_DETAILS_VERSION
+                }
+                .to_string()
+            ),
+            "{err}"
+        );
+    }
+
+    #[allow(unused_variables)]
+    fn trace(title: &str, val: &str) {
+        #[cfg(feature = "norand")]
+        println!("{title} = {val:#}")
+    }
+
+    /// Test a handshake simulating data transfer (serialize/deserialize)
+    #[test]
+    fn test_handshake_serde_2048() {
+        let username = "fred";
+        let password: &ClearTextPassword = "password_fred";
+        let constants = OpenConstants::default();
+        // new user : those are sent to the server and stored there
+        let user_details =
+            Srp6user2048::generate_new_user_secrets(&username.into(), &password.into(), &constants)
+                .unwrap();
+        let transfer = serde_json::to_string(&user_details).unwrap();
+        trace("details", &transfer);
+        // server side (stores)
+        let user_details = serde_json::from_str::<UserDetails>(&transfer).unwrap();
+        // user creates a handshake
+        let mut srp6_user = Srp6user2048::default();
+        let user_handshake = srp6_user.start_handshake(username, &constants).unwrap();
+        let transfer = serde_json::to_string(&user_handshake).unwrap();
+        trace("user_hs", &transfer);
+        // server retrieves stored details and continues the handshake
+        let user_handshake = serde_json::from_str::<UserHandshake>(&transfer).unwrap();
+        let (server_handshake, srp6) = Srp6_2048::default()
+            .continue_handshake(&user_details, &user_handshake.user_publickey, &constants)
+            .unwrap();
+        let transfer = serde_json::to_string(&server_handshake).unwrap();
+        trace("server_hs", &transfer);
+        // client side
+        let server_handshake = serde_json::from_str::<ServerHandshake>(&transfer).unwrap();
+        let (proof, srp6_user) = srp6_user
+            .update_handshake(
+                &server_handshake,
+                &constants,
+                &username.into(),
+                &password.into(),
+            )
+            .unwrap();
+        let transfer = serde_json::to_string(&proof).unwrap();
+        trace("proof", &transfer);
+        // server side
+        let proof = serde_json::from_str::<Proof>(&transfer).unwrap();
+        let outcome = srp6.verify_proof(&proof).unwrap();
+        let hamk = outcome.server_proof().clone();
+        let secret = outcome.into_session_secret();
+        let transfer = serde_json::to_string(&hamk).unwrap();
+        trace("sproof", &transfer);
+        // client side
+        let hamk = serde_json::from_str::<StrongProof>(&transfer).unwrap();
+        let secret2 = srp6_user
+            .verify_proof(&hamk)
+            .expect("invalid server proof")
+            .into_session_secret();
+        // both secrets
+        assert_eq!(secret2, secret, "not same secrets");
+    }
+
+    /// Test that all messages round trip through json (as hex) and bincode
+    #[test]
+    fn test_messages_serde_round_trip() {
+        let username = "fred";
+        let password: &ClearTextPassword = "password_fred";
+        let constants = OpenConstants::default();
+        let user_details =
+            Srp6user2048::generate_new_user_secrets(&username.into(), &password.into(), &constants)
+                .unwrap();
+        let mut srp6_user = Srp6user2048::default();
+        let user_handshake = srp6_user.start_handshake(username, &constants).unwrap();
+        let (server_handshake, _) = Srp6_2048::default()
+            .continue_handshake(&user_details, &user_handshake.user_publickey, &constants)
+            .unwrap();
+        let (proof, _) = srp6_user
+            .update_handshake(
+                &server_handshake,
+                &constants,
+                &username.into(),
+                &password.into(),
+            )
+            .unwrap();
+
+        // json is hex
+        let json = serde_json::to_value(&server_handshake).unwrap();
+        assert_eq!(json["salt"], String::from(&server_handshake.salt));
+
+        let json = serde_json::to_string(&user_details).unwrap();
+        let back = serde_json::from_str::<UserDetails>(&json).unwrap();
+        let bin = bincode::serialize(&user_details).unwrap();
+        let back2 = bincode::deserialize::<UserDetails>(&bin).unwrap();
+        for back in [back, back2] {
+            assert_eq!(back.username, user_details.username);
+            assert_eq!(back.salt, user_details.salt);
+            assert_eq!(back.verifier, user_details.verifier);
+        }
+
+        let json = serde_json::to_string(&user_handshake).unwrap();
+        let back = serde_json::from_str::<UserHandshake>(&json).unwrap();
+        let bin = bincode::serialize(&user_handshake).unwrap();
+        let back2 = bincode::deserialize::<UserHandshake>(&bin).unwrap();
+        for back in [back, back2] {
+            assert_eq!(back.username, user_handshake.username);
+            assert_eq!(back.user_publickey, user_handshake.user_publickey);
+        }
+
+        let json = serde_json::to_string(&server_handshake).unwrap();
+        let back = serde_json::from_str::<ServerHandshake>(&json).unwrap();
+        let bin = bincode::serialize(&server_handshake).unwrap();
+        let back2 = bincode::deserialize::<ServerHandshake>(&bin).unwrap();
+        for back in [back, back2] {
+            assert_eq!(back.salt, server_handshake.salt);
+            assert_eq!(back.server_publickey, server_handshake.server_publickey);
+        }
+
+        let json = serde_json::to_string(&proof).unwrap();
+        assert_eq!(serde_json::from_str::<Proof>(&json).unwrap(), proof);
+        let bin = bincode::serialize(&proof).unwrap();
+        assert_eq!(bincode::deserialize::<Proof>(&bin).unwrap(), proof);
+    }
+
+    /// Test a handshake where the server state is stored between both steps
+    #[test]
+    fn test_handshake_stateless_host() {
+        let username = "fred";
+        let password: &ClearTextPassword = "password_fred";
+        let constants = OpenConstants::default();
+        let user_details =
+            Srp6user2048::generate_new_user_secrets(&username.into(), &password.into(), &constants)
+                .unwrap();
+        let mut srp6_user = Srp6user2048::default();
+        let user_handshake = srp6_user.start_handshake(username, &constants).unwrap();
+        // server continues the handshake and stores its state
+        let (server_handshake, state) = Srp6_2048::continue_handshake_stateless(
+            &user_details,
+            &user_handshake.user_publickey,
+            &constants,
+        )
+        .unwrap();
+        let stored = serde_json::to_string(&state).unwrap();
+        trace("state", &stored);
+        // client side
+        let (proof, srp6_user) = srp6_user
+            .update_handshake(
+                &server_handshake,
+                &constants,
+                &username.into(),
+                &password.into(),
+            )
+            .unwrap();
+        // server side, maybe another process
+        let state = serde_json::from_str::<HostHandshakeState<256>>(&stored).unwrap();
+        assert_eq!(state.session_id(), &server_handshake.session_id);
+        let outcome = verify_proof_with_state(state, &proof).unwrap();
+        let hamk = outcome.server_proof().clone();
+        let secret = outcome.into_session_secret();
+        // a wrong proof is still rejected
+        let state = serde_json::from_str::<HostHandshakeState<256>>(&stored).unwrap();
+        let err = verify_proof_with_state(state, &hamk.number().clone().into()).unwrap_err();
+        assert!(matches!(err, Srp6Error::InvalidProof));
+        // client side
+        let secret2 = srp6_user
+            .verify_proof(&hamk)
+            .expect("invalid server proof")
+            .into_session_secret();
+        assert_eq!(secret2, secret, "not same secrets");
+    }
+
+    /// Test that concurrent handshakes of the server are told apart by their session id
+    #[test]
+    fn test_session_ids() {
+        let username = "fred";
+        let password: &ClearTextPassword = "password_fred";
+        let constants = OpenConstants::default();
+        let user_details =
+            Srp6user2048::generate_new_user_secrets(&username.into(), &password.into(), &constants)
+                .unwrap();
+        let mut srp6_user = Srp6user2048::default();
+        let mut srp6_user2 = Srp6user2048::default();
+        let user_handshake = srp6_user.start_handshake(username, &constants).unwrap();
+        let user_handshake2 = srp6_user2.start_handshake(username, &constants).unwrap();
+        // both handshakes are pending on the server
+        let (server_handshake, srp6) = Srp6_2048::default()
+            .continue_handshake(&user_details, &user_handshake.user_publickey, &constants)
+            .unwrap();
+        let (server_handshake2, srp6_2) = Srp6_2048::default()
+            .continue_handshake(&user_details, &user_handshake2.user_publickey, &constants)
+            .unwrap();
+        assert_eq!(srp6.session_id(), &server_handshake.session_id);
+        assert_eq!(srp6_2.session_id(), &server_handshake2.session_id);
+        assert_ne!(srp6.session_id(), srp6_2.session_id());
+        // the id goes through serde, not through the hex form
+        let transfer = serde_json::to_string(&server_handshake).unwrap();
+        let received = serde_json::from_str::<ServerHandshake>(&transfer).unwrap();
+        assert_eq!(received.session_id, server_handshake.session_id);
+        let received = ServerHandshake::from_hex::<256>(&server_handshake.to_hex()).unwrap();
+        assert_eq!(received.session_id, SessionId::default());
+
+        let mut dyn_srp6: DynSrp6 = DynSrp6::default();
+        let params = GroupParams::from(&constants);
+        let server_handshake3 = dyn_srp6
+            .continue_handshake(&user_details, &user_handshake.user_publickey, &params)
+            .unwrap();
+        assert_eq!(dyn_srp6.session_id(), &server_handshake3.session_id);
+        assert_ne!(dyn_srp6.session_id(), srp6.session_id());
+    }
+
+    /// Test a handshake where messages are transferred as raw little endian bytes
+    #[test]
+    fn test_handshake_raw_little_endian() {
+        let username = "fred";
+        let password: &ClearTextPassword = "password_fred";
+        let order = ByteOrder::LittleEndian;
+        let constants = OpenConstants::default();
+        let user_details =
+            Srp6user2048::generate_new_user_secrets(&username.into(), &password.into(), &constants)
+                .unwrap();
+        let mut srp6_user = Srp6user2048::default();
+        let user_handshake = srp6_user.start_handshake(username, &constants).unwrap();
+        let transfer = user_handshake.to_raw(order);
+        // server side
+        let user_handshake = UserHandshake::from_raw(username, &transfer, order);
+        let (server_handshake, srp6) = Srp6_2048::default()
+            .continue_handshake(&user_details, &user_handshake.user_publickey, &constants)
+            .unwrap();
+        let (salt, server_publickey) = server_handshake.to_raw(order);
+        // client side
+        let server_handshake = ServerHandshake::from_raw(&salt, &server_publickey, order);
+        let (proof, srp6_user) = srp6_user
+            .update_handshake(
+                &server_handshake,
+                &constants,
+                &username.into(),
+                &password.into(),
+            )
+            .unwrap();
+        let transfer = proof.to_bytes(order);
+        // server side
+        let proof = Proof::from_bytes(&transfer, order);
+        let outcome = srp6.verify_proof(&proof).unwrap();
+        let hamk = outcome.server_proof().clone();
+        let secret = outcome.into_session_secret();
+        let secret2 = srp6_user
+            .verify_proof(&hamk)
+            .expect("invalid server proof")
+            .into_session_secret();
+        assert_eq!(secret2, secret, "not same secrets");
+    }
+
+    #[test]
+    fn test_prepared_handshake() {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let username = "fred";
+        let password: &ClearTextPassword = "password_fred";
+        let constants = OpenConstants::<256>::default();
+        let user_details =
+            Srp6user2048::generate_new_user_secrets(&username.into(), &password.into(), &constants)
+                .unwrap();
+        let mut srp6_user = Srp6user2048::default();
+        let user_handshake = srp6_user.start_handshake(username, &constants).unwrap();
+
+        // the same `b` and session id for both paths
+        let mut rng = StdRng::seed_from_u64(42);
+        let (server_handshake, srp6) = Srp6_2048::default()
+            .continue_handshake_with_rng(
+                &user_details,
+                &user_handshake.user_publickey,
+                &constants,
+                &mut rng,
+            )
+            .unwrap();
+        let mut rng = StdRng::seed_from_u64(42);
+        let prepared = Srp6_2048::default()
+            .prepare_with_rng(&user_details, &constants, &mut rng)
+            .unwrap();
+        assert_eq!(prepared.username(), username);
+        assert_eq!(prepared.session_id(), &server_handshake.session_id);
+        let (prepared_handshake, prepared_srp6) =
+            prepared.complete(&user_handshake.user_publickey).unwrap();
+        assert_eq!(
+            prepared_handshake.to_bytes::<256>().unwrap(),
+            server_handshake.to_bytes::<256>().unwrap()
+        );
+
+        // the same `M`, hence the same `M2` for the user's proof
+        let (proof, srp6_user) = srp6_user
+            .update_handshake(
+                &server_handshake,
+                &constants,
+                &username.into(),
+                &password.into(),
+            )
+            .unwrap();
+        let outcome = srp6.verify_proof(&proof).unwrap();
+        let strong_proof = outcome.server_proof().clone();
+        let secret = outcome.into_session_secret();
+        let outcome = prepared_srp6.verify_proof(&proof).unwrap();
+        let prepared_strong_proof = outcome.server_proof().clone();
+        let prepared_secret = outcome.into_session_secret();
+        assert_eq!(prepared_strong_proof, strong_proof);
+        assert_eq!(prepared_secret, secret);
+        assert_eq!(
+            srp6_user
+                .verify_proof(&strong_proof)
+                .map(ClientHandshakeOutcome::into_session_secret),
+            Ok(secret)
+        );
+
+        // `A` is checked when it comes
+        let prepared = Srp6_2048::default()
+            .prepare(&user_details, &constants)
+            .unwrap();
+        assert_eq!(
+            prepared.complete(&*constants.module).unwrap_err(),
+            Srp6Error::InvalidPublicKey((*constants.module).clone())
+        );
+        // and the details when they are prepared
+        let mut details = user_details.clone();
+        details.verifier = PasswordVerifier::zero();
+        assert_eq!(
+            Srp6_2048::default()
+                .prepare(&details, &constants)
+                .unwrap_err(),
+            Srp6Error::InvalidVerifier
+        );
+    }
+
+    #[cfg(not(feature = "norand"))]
+    #[test]
+    fn test_retry_handshake() {
+        let username = "fred";
+        let password: &ClearTextPassword = "password_fred";
+        let constants = OpenConstants::<256>::default();
+        let user_details =
+            Srp6user2048::generate_new_user_secrets(&username.into(), &password.into(), &constants)
+                .unwrap();
+        let mut srp6_user = Srp6user2048::default();
+        let srp6 = Srp6_2048::default();
+
+        // aborted once the user's proof is sent
+        let first_handshake = srp6_user.start_handshake(username, &constants).unwrap();
+        let (first_server_handshake, _) = srp6
+            .clone()
+            .continue_handshake(&user_details, &first_handshake.user_publickey, &constants)
+            .unwrap();
+        srp6_user
+            .clone()
+            .update_handshake(
+                &first_server_handshake,
+                &constants,
+                &username.into(),
+                &password.into(),
+            )
+            .unwrap();
+
+        // the same structs, with new keys
+        let user_handshake = srp6_user.start_handshake(username, &constants).unwrap();
+        assert_ne!(
+            user_handshake.user_publickey,
+            first_handshake.user_publickey
+        );
+        let (server_handshake, srp6) = srp6
+            .continue_handshake(&user_details, &user_handshake.user_publickey, &constants)
+            .unwrap();
+        assert_ne!(
+            server_handshake.server_publickey,
+            first_server_handshake.server_publickey
+        );
+        let (proof, waiting) = srp6_user
+            .clone()
+            .update_handshake(
+                &server_handshake,
+                &constants,
+                &username.into(),
+                &password.into(),
+            )
+            .unwrap();
+        let outcome = srp6.verify_proof(&proof).unwrap();
+        let strong_proof = outcome.server_proof().clone();
+        let secret = outcome.into_session_secret();
+        assert_eq!(
+            waiting
+                .verify_proof(&strong_proof)
+                .map(ClientHandshakeOutcome::into_session_secret),
+            Ok(secret)
+        );
+
+        srp6_user.reset();
+        assert_eq!(srp6_user.public_key_a(), &PublicKey::default());
+    }
+
+    #[cfg(not(feature = "norand"))]
+    #[test]
+    #[allow(deprecated)]
+    fn test_str_credentials_shims() {
+        let constants = OpenConstants::<128>::default();
+        let user_details =
+            Srp6user1024::generate_new_user_secrets_str("alice", "secret", &constants).unwrap();
+        assert_eq!(user_details.username, "alice");
+        let mut srp6_user = Srp6user1024::default();
+        let user_handshake = srp6_user.start_handshake("alice", &constants).unwrap();
+        let (server_handshake, srp6) = Srp6_1024::default()
+            .continue_handshake(&user_details, &user_handshake, &constants)
+            .unwrap();
+        let (proof, _) = srp6_user
+            .update_handshake_str(&server_handshake, &constants, "alice", "secret")
+            .unwrap();
+        assert!(srp6.verify_proof(&proof).is_ok());
+    }
+
+    #[cfg(not(feature = "norand"))]
+    #[test]
+    fn test_reused_ephemeral_key_is_stale() {
+        let username = "fred";
+        let password: &ClearTextPassword = "password_fred";
+        let constants = OpenConstants::<256>::default();
+        let user_details =
+            Srp6user2048::generate_new_user_secrets(&username.into(), &password.into(), &constants)
+                .unwrap();
+        let handshake = |srp6_user: Srp6user2048, user_handshake: &UserHandshake| {
+            let (server_handshake, srp6) = Srp6_2048::default()
+                .continue_handshake(&user_details, &user_handshake.user_publickey, &constants)
+                .unwrap();
+            let (proof, waiting) = srp6_user.update_handshake(
+                &server_handshake,
+                &constants,
+                &username.into(),
+                &password.into(),
+            )?;
+            let outcome = srp6.verify_proof(&proof)?;
+            waiting.verify_proof(outcome.server_proof()).map(|_| ())
+        };
+
+        // never started
+        let user_handshake = Srp6user2048::default()
+            .start_handshake(username, &constants)
+            .unwrap();
+        assert_eq!(
+            handshake(Srp6user2048::default(), &user_handshake),
+            Err(Srp6Error::StaleHandshake)
+        );
+
+        // a complete handshake, then its `a` again from a clone
+        let mut srp6_user = Srp6user2048::default();
+        let user_handshake = srp6_user.start_handshake(username, &constants).unwrap();
+        assert_eq!(srp6_user.handshake_generation(), 1);
+        let cached = srp6_user.clone();
+        assert_eq!(handshake(srp6_user.clone(), &user_handshake), Ok(()));
+        assert_eq!(
+            handshake(cached, &user_handshake),
+            Err(Srp6Error::StaleHandshake)
+        );
+        // and after a reset
+        let mut reset = srp6_user.clone();
+        reset.reset();
+        assert_eq!(
+            handshake(reset, &user_handshake),
+            Err(Srp6Error::StaleHandshake)
+        );
+
+        // a new start draws a new `a`
+        let user_handshake = srp6_user.start_handshake(username, &constants).unwrap();
+        assert_eq!(srp6_user.handshake_generation(), 2);
+        assert_eq!(handshake(srp6_user, &user_handshake), Ok(()));
+    }
+
+    /// both sides of a handshake record the same public values, and the
+    /// transcript round-trips through JSON
+    #[cfg(feature = "transcript")]
+    #[test]
+    fn test_handshake_transcripts() {
+        let constants = OpenConstants::<256>::default();
+        let (username, password) = (Username::from("alice"), Password::from("secret"));
+        let user_details =
+            Srp6_2048::generate_new_user_secrets(&username, &password, &constants).unwrap();
+        let mut srp6_user = Srp6user2048::default();
+        let user_handshake = srp6_user.start_handshake(&username, &constants).unwrap();
+        let (server_handshake, srp6) = Srp6_2048::default()
+            .continue_handshake(&user_details, &user_handshake, &constants)
+            .unwrap();
+        let (_, srp6_user) = srp6_user
+            .update_handshake(&server_handshake, &constants, &username, &password)
+            .unwrap();
+        let (host, user) = (srp6.transcript(), srp6_user.transcript());
+
+        for name in ["A", "B", "salt", "u", "k", "M"] {
+            assert!(host.get(name).is_some(), "{name}");
+            assert_eq!(host.get(name), user.get(name), "{name}");
+        }
+        assert_eq!(host.get("S"), user.get("S"));
+        assert_eq!(host.get("K"), user.get("K"));
+        assert!(host.get("x").is_none());
+        assert!(user.get("x").is_some());
+
+        let redacted = user.redacted();
+        assert_eq!(redacted.entries().len(), user.entries().len());
+        for name in ["x", "S", "K"] {
+            assert!(redacted.get(name).is_none(), "{name}");
+        }
+        assert_eq!(redacted.get("M"), user.get("M"));
+        assert!(!format!("{user:?}").contains(user.get("K").unwrap()));
+
+        let json = user.to_json();
+        assert_eq!(transcript::Transcript::from_json(&json).unwrap(), *user);
+        let json = redacted.to_json();
+        assert_eq!(transcript::Transcript::from_json(&json).unwrap(), redacted);
+    }
+
+    /// the `&self` API of both sides, shared by the threads with the constants
+    #[cfg(not(feature = "norand"))]
+    #[test]
+    fn test_concurrent_pure_handshakes() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<HostHandshakeState<128>>();
+        assert_send_sync::<Srp6UserWaitingForServerProof<128>>();
+
+        let constants = OpenConstants::<128>::default();
+        let (username, password) = (Username::from("alice"), Password::from("secret"));
+        let user_details =
+            Srp6user1024::generate_new_user_secrets(&username, &password, &constants).unwrap();
+        let srp6 = Srp6_1024::default();
+        std::thread::scope(|scope| {
+            let handshakes: Vec<_> = (0..100)
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut srp6_user = Srp6user1024::default();
+                        let user_handshake =
+                            srp6_user.start_handshake("alice", &constants).unwrap();
+                        let (server_handshake, state) = srp6
+                            .continue_handshake_pure(&user_details, &user_handshake, &constants)
+                            .unwrap();
+                        let (proof, waiting) = srp6_user
+                            .update_handshake_pure(
+                                &server_handshake,
+                                &constants,
+                                &username,
+                                &password,
+                            )
+                            .unwrap();
+                        // `a` is used once, even through `&self`
+                        assert_eq!(
+                            srp6_user
+                                .update_handshake_pure(
+                                    &server_handshake,
+                                    &constants,
+                                    &username,
+                                    &password
+                                )
+                                .map(|_| ()),
+                            Err(Srp6Error::StaleHandshake)
+                        );
+                        let outcome = verify_proof_with_state(state, &proof).unwrap();
+                        let user_outcome = waiting.verify_proof(outcome.server_proof()).unwrap();
+                        assert_eq!(user_outcome.session_secret(), outcome.session_secret());
+                    })
+                })
+                .collect();
+            for handshake in handshakes {
+                handshake.join().unwrap();
+            }
+        });
+    }
+
+    /// a zero `N` set on the constants is an error on both sides, not a panic
+    #[cfg(not(feature = "norand"))]
+    #[test]
+    fn test_zero_modulus_is_invalid_configuration() {
+        let (username, password) = (Username::from("alice"), Password::from("secret"));
+        let constants = OpenConstants::<256>::default();
+        let user_details =
+            Srp6user2048::generate_new_user_secrets(&username, &password, &constants).unwrap();
+        let mut srp6_user = Srp6user2048::default();
+        let user_handshake = srp6_user.start_handshake("alice", &constants).unwrap();
+        let (server_handshake, _) = Srp6_2048::default()
+            .continue_handshake(&user_details, &user_handshake, &constants)
+            .unwrap();
+
+        let mut zero = constants.clone();
+        zero.module = alloc::sync::Arc::new(big_number::BigNumber::zero());
+        let invalid: Result<()> = Err(Srp6Error::InvalidConfiguration("zero modulus"));
+        assert_eq!(
+            Srp6user2048::generate_new_user_secrets(&username, &password, &zero).map(|_| ()),
+            invalid
+        );
+        assert_eq!(
+            Srp6_2048::default()
+                .continue_handshake(&user_details, &user_handshake, &zero)
+                .map(|_| ()),
+            invalid
+        );
+        assert_eq!(
+            Srp6_2048::default()
+                .prepare(&user_details, &zero)
+                .map(|_| ()),
+            invalid
+        );
+        assert_eq!(
+            srp6_user
+                .update_handshake(&server_handshake, &zero, &username, &password)
+                .map(|_| ()),
+            invalid
+        );
+        // not started with the zero modulus, no `A` is drawn
+        let mut srp6_user = Srp6user2048::default();
+        assert_eq!(
+            srp6_user.start_handshake("alice", &zero).map(|_| ()),
+            invalid
+        );
+        assert_eq!(
+            srp6_user
+                .update_handshake(&server_handshake, &zero, &username, &password)
+                .map(|_| ()),
+            invalid
+        );
+
+        // `[2, N - 2]` is empty, no private key can be drawn
+        let small = OpenConstants::<256>::new(
+            big_number::BigNumber::from(3),
+            big_number::BigNumber::from(2),
+            Srp6Variant::Srp6a,
+        );
+        let too_small: Result<()> = Err(Srp6Error::InvalidConfiguration("modulus too small"));
+        assert_eq!(
+            Srp6user2048::default()
+                .start_handshake("alice", &small)
+                .map(|_| ()),
+            too_small
+        );
+        let small_details =
+            Srp6user2048::generate_new_user_secrets(&username, &password, &small).unwrap();
+        assert_eq!(
+            Srp6_2048::default()
+                .prepare(&small_details, &small)
+                .map(|_| ()),
+            too_small
+        );
+    }
+
+    #[cfg(all(feature = "mutable-api", not(feature = "norand")))]
+    #[test]
+    #[allow(deprecated)]
+    fn test_reused_ephemeral_key_is_stale_mutable_api() {
+        let username = "Bob";
+        let password: &ClearTextPassword = "secret-password";
+        let constants = OpenConstants::<256>::default();
+        let user_details =
+            Srp6user2048::generate_new_user_secrets(&username.into(), &password.into(), &constants)
+                .unwrap();
+        let mut srp6_user = mutable::Srp6User::<256>::default();
+
+        let user_handshake = srp6_user.start_handshake(username, &constants).unwrap();
+        let server_handshake = Srp6_2048::default()
+            .continue_handshake(&user_details, &user_handshake.user_publickey, &constants)
+            .unwrap()
+            .0;
+        srp6_user
+            .update_handshake(&server_handshake, &constants, username, password)
+            .unwrap();
+        // the same struct, without a new start
+        assert_eq!(
+            srp6_user.update_handshake(&server_handshake, &constants, username, password),
+            Err(Srp6Error::StaleHandshake)
+        );
+
+        srp6_user.start_handshake(username, &constants).unwrap();
+        assert!(srp6_user
+            .update_handshake(&server_handshake, &constants, username, password)
+            .is_ok());
+    }
+
+    #[cfg(all(feature = "mutable-api", not(feature = "norand")))]
+    #[test]
+    #[allow(deprecated)]
+    fn test_retry_handshake_mutable_api() {
+        let username = "Bob";
+        let password: &ClearTextPassword = "secret-password";
+        let constants = OpenConstants::<256>::default();
+        let user_details =
+            Srp6user2048::generate_new_user_secrets(&username.into(), &password.into(), &constants)
+                .unwrap();
+        let mut srp6_user = mutable::Srp6User::<256>::default();
+        let mut srp6 = mutable::Srp6::<256>::default();
+
+        let first_handshake = srp6_user.start_handshake(username, &constants).unwrap();
+        let first_server_handshake = srp6
+            .continue_handshake(&user_details, &first_handshake.user_publickey, &constants)
+            .unwrap();
+        srp6_user
+            .update_handshake(&first_server_handshake, &constants, username, password)
+            .unwrap();
+
+        // nothing is left from the aborted handshake
+        let user_handshake = srp6_user.start_handshake(username, &constants).unwrap();
+        assert_ne!(srp6_user.A, first_handshake.user_publickey);
+        assert_eq!(srp6_user.B, PublicKey::default());
+        assert_eq!(srp6_user.M, Proof::default());
+        let server_handshake = srp6
+            .continue_handshake(&user_details, &user_handshake.user_publickey, &constants)
+            .unwrap();
+        assert_ne!(srp6.B, first_server_handshake.server_publickey);
+        let proof = srp6_user
+            .update_handshake(&server_handshake, &constants, username, password)
+            .unwrap();
+        let outcome = srp6.verify_proof(&proof).unwrap();
+        let strong_proof = outcome.server_proof().clone();
+        let secret = outcome.into_session_secret();
+        assert_eq!(
+            srp6_user
+                .verify_proof(&strong_proof)
+                .map(ClientHandshakeOutcome::into_session_secret),
+            Ok(secret)
+        );
+    }
+
+    #[test]
+    fn test_handshake_with_seeded_rng() {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let username = "fred";
+        let password: &ClearTextPassword = "password_fred";
+        let constants = OpenConstants::default();
+        let handshake = |seed: u64| {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let user_details = Srp6user2048::generate_new_user_secrets_with_rng(
+                &username.into(),
+                &password.into(),
+                &constants,
+                &mut rng,
+            )
+            .unwrap();
+            let mut srp6_user = Srp6user2048::default();
+            let user_handshake = srp6_user
+                .start_handshake_with_rng(username, &constants, &mut rng)
+                .unwrap();
+            let (server_handshake, state) = Srp6_2048::continue_handshake_stateless_with_rng(
+                &user_details,
+                &user_handshake.user_publickey,
+                &constants,
+                &mut rng,
+            )
+            .unwrap();
+            let (proof, srp6_user) = srp6_user
+                .update_handshake(
+                    &server_handshake,
+                    &constants,
+                    &username.into(),
+                    &password.into(),
+                )
+                .unwrap();
+            let outcome = verify_proof_with_state(state, &proof).unwrap();
+            let hamk = outcome.server_proof().clone();
+            let secret = outcome.into_session_secret();
+            assert_eq!(
+                srp6_user
+                    .verify_proof(&hamk)
+                    .map(ClientHandshakeOutcome::into_session_secret),
+                Ok(secret.clone())
+            );
+            (user_handshake, server_handshake, secret)
+        };
+
+        let (user_handshake, server_handshake, secret) = handshake(42);
+        let (user_handshake2, server_handshake2, secret2) = handshake(42);
+        assert_eq!(
+            user_handshake.user_publickey,
+            user_handshake2.user_publickey
+        );
+        assert_eq!(server_handshake.salt, server_handshake2.salt);
+        assert_eq!(
+            server_handshake.server_publickey,
+            server_handshake2.server_publickey
+        );
+        assert_eq!(secret, secret2);
+
+        let (user_handshake3, server_handshake3, _) = handshake(43);
+        assert_ne!(
+            user_handshake.user_publickey,
+            user_handshake3.user_publickey
+        );
+        assert_ne!(
+            server_handshake.server_publickey,
+            server_handshake3.server_publickey
+        );
+
+        // the host keeping its state
+        let mut rng = StdRng::seed_from_u64(42);
+        let user_details = Srp6user2048::generate_new_user_secrets_with_rng(
+            &username.into(),
+            &password.into(),
+            &constants,
+            &mut rng,
+        )
+        .unwrap();
+        let (server_handshake4, _) = Srp6_2048::default()
+            .continue_handshake_with_rng(
+                &user_details,
+                &user_handshake.user_publickey,
+                &constants,
+                &mut StdRng::seed_from_u64(7),
+            )
+            .unwrap();
+        let (server_handshake5, _) = Srp6_2048::default()
+            .continue_handshake_with_rng(
+                &user_details,
+                &user_handshake.user_publickey,
+                &constants,
+                &mut StdRng::seed_from_u64(7),
+            )
+            .unwrap();
+        assert_eq!(
+            server_handshake4.server_publickey,
+            server_handshake5.server_publickey
+        );
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn test_session_secret_derive_key() {
+        let username = "fred";
+        let password: &ClearTextPassword = "password_fred";
+        let constants = OpenConstants::default();
+        let user_details =
+            Srp6user2048::generate_new_user_secrets(&username.into(), &password.into(), &constants)
+                .unwrap();
+        let mut srp6_user = Srp6user2048::default();
+        let user_handshake = srp6_user.start_handshake(username, &constants).unwrap();
+        let (server_handshake, srp6) = Srp6_2048::default()
+            .continue_handshake(&user_details, &user_handshake.user_publickey, &constants)
+            .unwrap();
+        let (proof, srp6_user) = srp6_user
+            .update_handshake(
+                &server_handshake,
+                &constants,
+                &username.into(),
+                &password.into(),
+            )
+            .unwrap();
+        let outcome = srp6.verify_proof(&proof).unwrap();
+        let hamk = outcome.server_proof().clone();
+        let secret = outcome.into_session_secret();
+        let secret2 = srp6_user.verify_proof(&hamk).unwrap().into_session_secret();
+
+        let key = secret.derive_key(b"aes-256-gcm", 32);
+        assert_eq!(key.len(), 32);
+        assert_eq!(key, secret2.derive_key(b"aes-256-gcm", 32));
+        assert_ne!(key, secret.derive_key(b"hmac-sha256", 32));
+        let K = secret.strong_session_key();
+        assert_eq!(K.to_bytes(), secret2.strong_session_key().to_bytes());
+        assert_eq!(K.key_length(), 40);
+        assert_eq!(K.split_keys(), secret2.strong_session_key().split_keys());
+        assert_eq!(
+            K.split_keys().1.to_vec(),
+            secret.derive_key(session::MAC_KEY_INFO, 32)
+        );
+        assert_ne!(K.split_keys().0, K.split_keys().1);
+        assert_eq!(
+            secret.as_padded_bytes::<40>(),
+            secret2.as_padded_bytes::<40>()
+        );
+        assert_eq!(
+            secret.strong_session_key(),
+            &strong_session_key_from_bytes::<Sha1>(
+                &secret.as_padded_bytes::<40>(),
+                ByteOrder::BigEndian
+            )
+            .unwrap()
+        );
+        assert_eq!(format!("{secret:?}"), "SessionSecret(..)");
+    }
+
+    /// Test a password change in a session, then the logins with both passwords
+    #[test]
+    fn test_password_change() {
+        let username = "fred";
+        let password: &ClearTextPassword = "password_fred";
+        let new_password: &ClearTextPassword = "new_password_fred";
+        let constants = OpenConstants::default();
+        // full handshake, returns both sides or the host's error
+        let login = |user_details: &UserDetails, password: &ClearTextPassword| {
+            let mut srp6_user = Srp6user2048::default();
+            let user_handshake = srp6_user.start_handshake(username, &constants).unwrap();
+            let (server_handshake, srp6) = Srp6_2048::default()
+                .continue_handshake(user_details, &user_handshake.user_publickey, &constants)
+                .unwrap();
+            let (proof, srp6_user) = srp6_user
+                .update_handshake(
+                    &server_handshake,
+                    &constants,
+                    &username.into(),
+                    &password.into(),
+                )
+                .unwrap();
+            let outcome = srp6.verify_proof(&proof)?;
+            let hamk = outcome.server_proof().clone();
+            let secret = outcome.into_session_secret();
+            let session = srp6_user.verify_session(&hamk).unwrap();
+            Ok::<_, Srp6Error>((session, secret))
+        };
+        let user_details =
+            Srp6user2048::generate_new_user_secrets(&username.into(), &password.into(), &constants)
+                .unwrap();
+        let (session, secret) = login(&user_details, password).unwrap();
+
+        let request = session
+            .prepare_password_change(new_password, &constants)
+            .unwrap();
+        let transfer = serde_json::to_string(&request).unwrap();
+        let request = serde_json::from_str::<PasswordChangeRequest>(&transfer).unwrap();
+        let new_details =
+            Srp6_2048::apply_password_change(secret.strong_session_key(), &request, &user_details)
+                .unwrap();
+        assert_eq!(new_details.username, username);
+        assert_ne!(new_details.verifier, user_details.verifier);
+
+        assert!(login(&new_details, new_password).is_ok());
+        assert_eq!(
+            login(&new_details, password).unwrap_err(),
+            Srp6Error::InvalidProof
+        );
+
+        // tampered request, or the details of another user
+        let mut tampered = request.clone();
+        tampered.verifier = user_details.verifier.clone();
+        let err =
+            Srp6_2048::apply_password_change(secret.strong_session_key(), &tampered, &user_details)
+                .unwrap_err();
+        assert_eq!(err, Srp6Error::InvalidPasswordChange);
+        let other_details =
+            Srp6user2048::generate_new_user_secrets(&"bob".into(), &password.into(), &constants)
+                .unwrap();
+        let err =
+            Srp6_2048::apply_password_change(secret.strong_session_key(), &request, &other_details)
+                .unwrap_err();
+        assert_eq!(err, Srp6Error::InvalidPasswordChange);
+    }
+
+    /// Test the upgrade of a verifier to a stretched one in a session, then the
+    /// logins with the stretched `x`
+    #[test]
+    fn test_verifier_upgrade() {
+        let (username, password) = (Username::from("alice"), Password::from("secret-password"));
+        let kdf = KdfParams::Pbkdf2 { iterations: 1000 };
+        let constants = OpenConstants::<256>::default();
+        let login = |user_details: &UserDetails, kdf: Option<&KdfParams>| {
+            let mut srp6_user = Srp6user2048::default();
+            let user_handshake = srp6_user.start_handshake("alice", &constants).unwrap();
+            let (server_handshake, srp6) = Srp6_2048::default()
+                .continue_handshake(user_details, &user_handshake, &constants)
+                .unwrap();
+            let (proof, srp6_user) = match kdf {
+                Some(kdf) => srp6_user.update_handshake_stretched(
+                    &server_handshake,
+                    &constants,
+                    &"alice".into(),
+                    &"secret-password".into(),
+                    kdf,
+                ),
+                None => {
+                    srp6_user.update_handshake(&server_handshake, &constants, &username, &password)
+                }
+            }
+            .unwrap();
+            let outcome = srp6.verify_proof(&proof)?;
+            let session = srp6_user.verify_session(outcome.server_proof()).unwrap();
+            Ok::<_, Srp6Error>((session, outcome.into_session_secret()))
+        };
+        let user_details =
+            Srp6user2048::generate_new_user_secrets(&username, &password, &constants).unwrap();
+        let (session, secret) = login(&user_details, None).unwrap();
+
+        let upgrade = session
+            .prepare_verifier_upgrade(kdf, &constants, &password)
+            .unwrap();
+        let transfer = serde_json::to_string(&upgrade).unwrap();
+        let upgrade = serde_json::from_str::<VerifierUpgrade>(&transfer).unwrap();
+        let new_details = Srp6_2048::verify_verifier_upgrade(
+            secret.strong_session_key(),
+            &upgrade,
+            &user_details,
+        )
+        .unwrap();
+        assert_eq!(new_details.username, "alice");
+        assert_eq!(new_details.kdf, Some(kdf));
+        // `norand` gives the salt of RFC 5054 every time
+        #[cfg(not(feature = "norand"))]
+        assert_ne!(new_details.salt, user_details.salt);
+
+        assert!(login(&new_details, Some(&kdf)).is_ok());
+        assert_eq!(
+            login(&new_details, None).unwrap_err(),
+            Srp6Error::InvalidProof
+        );
+
+        // tampered upgrade, or taken for a password change
+        let mut tampered = upgrade.clone();
+        tampered.kdf = KdfParams::Pbkdf2 { iterations: 1 };
+        assert_eq!(
+            Srp6_2048::verify_verifier_upgrade(
+                secret.strong_session_key(),
+                &tampered,
+                &user_details
+            ),
+            Err(Srp6Error::InvalidVerifierUpgrade)
+        );
+        let request = PasswordChangeRequest {
+            salt: upgrade.salt.clone(),
+            verifier: upgrade.verifier.clone(),
+            mac: upgrade.mac,
+        };
+        assert_eq!(
+            Srp6_2048::apply_password_change(secret.strong_session_key(), &request, &user_details),
+            Err(Srp6Error::InvalidPasswordChange)
+        );
+    }
+
+    /// Test the tags of messages with the key of both sides
+    #[test]
+    fn test_authenticated_session() {
+        let constants = OpenConstants::<256>::default();
+        let handshake = |username: &str| {
+            let password: &ClearTextPassword = "secret-password";
+            let user_details = Srp6user2048::generate_new_user_secrets(
+                &username.into(),
+                &password.into(),
+                &constants,
+            )
+            .unwrap();
+            let mut srp6_user = Srp6user2048::default();
+            let user_handshake = srp6_user.start_handshake(username, &constants).unwrap();
+            let (server_handshake, srp6) = Srp6_2048::default()
+                .continue_handshake(&user_details, &user_handshake.user_publickey, &constants)
+                .unwrap();
+            let (proof, srp6_user) = srp6_user
+                .update_handshake(
+                    &server_handshake,
+                    &constants,
+                    &username.into(),
+                    &password.into(),
+                )
+                .unwrap();
+            let outcome = srp6.verify_proof(&proof).unwrap();
+            let strong_proof = outcome.server_proof().clone();
+            let host_secret = outcome.into_session_secret();
+            let user_secret = srp6_user
+                .verify_proof(&strong_proof)
+                .unwrap()
+                .into_session_secret();
+            (user_secret, host_secret)
+        };
+
+        let (user_secret, host_secret) = handshake("Bob");
+        let user = user_secret.authenticated_session();
+        let host = host_secret.authenticated_session();
+        let msg = b"transfer 100 to Alice";
+        let tag = user.sign(msg);
+        assert!(host.verify(msg, &tag));
+        assert_eq!(host.sign(msg), tag);
+
+        let mut tampered = *msg;
+        tampered[0] ^= 1;
+        assert!(!host.verify(&tampered, &tag));
+        let mut tampered = tag;
+        tampered[31] ^= 0x80;
+        assert!(!host.verify(msg, &tampered));
+        assert!(!host.verify(msg, &tag[..16]));
+
+        // K is fixed with `norand`, the username changes it anyway
+        let (other_secret, _) = handshake("Alice");
+        assert_ne!(other_secret.authenticated_session().sign(msg), tag);
+        assert_eq!(format!("{user:?}"), "AuthenticatedSession(..)");
+    }
+
+    /// the secrets are only printed for protocol debugging
+    #[test]
+    #[allow(non_snake_case)]
+    fn test_debug_redacts_secrets() {
+        use crate::primitives::{generate_private_key_with, generate_session_id_with};
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let shown = cfg!(feature = "insecure-debug-logging");
+        let hex = |n: &PublicKey| String::from(n);
+        let username = Username::from("fred");
+        let password = Password::from("password_fred");
+        let constants = OpenConstants::<256>::default();
+        let user_details =
+            Srp6user2048::generate_new_user_secrets(&username, &password, &constants).unwrap();
+
+        let a = generate_private_key_with(&mut StdRng::seed_from_u64(1), &constants).unwrap();
+        let mut srp6_user = Srp6user2048::default();
+        let user_handshake = srp6_user
+            .start_handshake_with_rng(&username, &constants, &mut StdRng::seed_from_u64(1))
+            .unwrap();
+        let debug = format!("{srp6_user:?}");
+        assert!(debug.contains(&hex(&user_handshake.user_publickey)));
+        assert_eq!(debug.contains(&hex(&a)), shown);
+        assert_eq!(debug.contains("PrivateKey(<redacted, 256 bytes>)"), !shown);
+
+        let mut rng = StdRng::seed_from_u64(2);
+        generate_session_id_with(&mut rng);
+        let b = generate_private_key_with(&mut rng, &constants).unwrap();
+        let prepared = Srp6_2048::default()
+            .prepare_with_rng(&user_details, &constants, &mut StdRng::seed_from_u64(2))
+            .unwrap();
+        assert_eq!(format!("{prepared:?}").contains(&hex(&b)), shown);
+        let (server_handshake, srp6) = prepared.complete(&user_handshake.user_publickey).unwrap();
+        let (proof, srp6_user) = srp6_user
+            .update_handshake(&server_handshake, &constants, &username, &password)
+            .unwrap();
+        let host_debug = format!("{srp6:?}");
+        let user_debug = format!("{srp6_user:?}");
+        let outcome = srp6.verify_proof(&proof).unwrap();
+        let strong_proof = outcome.server_proof().clone();
+        let secret = outcome.into_session_secret();
+        let K = hex(secret.strong_session_key().as_ref());
+        let S = hex(&secret.clone().into_raw_session_key());
+        for debug in [&host_debug, &user_debug] {
+            assert!(debug.contains(&hex(&server_handshake.server_publickey)));
+            assert_eq!(debug.contains(&K), shown);
+            assert_eq!(debug.contains(&S), shown);
+        }
+        // the user sends `M`, the host must not show the one it expects
+        assert!(user_debug.contains(&hex(&proof)));
+        assert_eq!(host_debug.contains(&hex(&proof)), shown);
+        let session = srp6_user.verify_session(&strong_proof).unwrap();
+        assert_eq!(format!("{session:?}").contains(&K), shown);
+
+        assert_eq!(format!("{username:?}"), r#""fred""#);
+        assert_eq!(format!("{password:?}") == "Password(<redacted>)", !shown);
+        let credentials = UserCredentials {
+            username: &username,
+            password: &password,
+        };
+        let debug = format!("{credentials:?}");
+        assert!(debug.contains("fred"));
+        assert_eq!(debug.contains("password_fred"), shown);
+        let srp6_user = Srp6user2048::with_credentials(&username, &password);
+        assert_eq!(format!("{srp6_user:?}").contains("password_fred"), shown);
+    }
+
+    /// an unknown username gets the same answer as a known one with a wrong password
+    #[cfg(not(feature = "norand"))]
+    #[test]
+    fn test_unknown_user_handshake() {
+        use std::time::{Duration, Instant};
+
+        const SEED: &[u8] = b"0123456789abcdef0123456789abcdef";
+        let constants = OpenConstants::<256>::default();
+        let fake = |username: &str, seed: &[u8]| {
+            let mut user = Srp6user2048::default();
+            let handshake = user.start_handshake(username, &constants).unwrap();
+            let (server_handshake, host) = Srp6_2048::default()
+                .continue_handshake_for_unknown_user(username, &handshake, seed, &constants)
+                .unwrap();
+            let (proof, _) = user
+                .update_handshake(
+                    &server_handshake,
+                    &constants,
+                    &username.into(),
+                    &"password".into(),
+                )
+                .unwrap();
+            (server_handshake, host.verify_proof(&proof).err())
+        };
+
+        let (server_handshake, error) = fake("mallory", SEED);
+        assert_eq!(error, Some(Srp6Error::InvalidProof));
+        assert!(server_handshake.salt.num_bytes() <= DEFAULT_SALT_LENGTH);
+        assert_eq!(
+            validate_public_key(&server_handshake.server_publickey, &constants),
+            Ok(())
+        );
+        // the salt does not change from one attempt to the next, `B` does
+        let (again, _) = fake("mallory", SEED);
+        assert_eq!(again.salt, server_handshake.salt);
+        assert_ne!(again.server_publickey, server_handshake.server_publickey);
+        assert_ne!(fake("trudy", SEED).0.salt, server_handshake.salt);
+        assert_ne!(
+            fake("mallory", b"another seed").0.salt,
+            server_handshake.salt
+        );
+
+        // as a known user with a wrong password, in time too
+        let user_details =
+            Srp6user2048::generate_new_user_secrets(&"alice".into(), &"secret".into(), &constants)
+                .unwrap();
+        let known = || {
+            let mut user = Srp6user2048::default();
+            let handshake = user.start_handshake("alice", &constants).unwrap();
+            let (server_handshake, host) = Srp6_2048::default()
+                .continue_handshake(&user_details, &handshake, &constants)
+                .unwrap();
+            let (proof, _) = user
+                .update_handshake(
+                    &server_handshake,
+                    &constants,
+                    &"alice".into(),
+                    &"password".into(),
+                )
+                .unwrap();
+            host.verify_proof(&proof).err()
+        };
+        assert_eq!(known(), Some(Srp6Error::InvalidProof));
+        let time = |f: &dyn Fn()| {
+            let start = Instant::now();
+            for _ in 0..4 {
+                f();
+            }
+            start.elapsed()
+        };
+        let known_time = time(&|| {
+            known();
+        });
+        let fake_time = time(&|| {
+            fake("mallory", SEED);
+        });
+        let (shorter, longer) = (known_time.min(fake_time), known_time.max(fake_time));
+        assert!(
+            longer < shorter * 10 + Duration::from_millis(10),
+            "{known_time:?} for a known user, {fake_time:?} for an unknown one"
+        );
+    }
+
+    /// the salt of the first login is pinned for the next ones
+    #[test]
+    fn test_salt_pinning() {
+        let constants = OpenConstants::<128>::default();
+        let user_details =
+            Srp6user1024::generate_new_user_secrets(&"alice".into(), &"secret".into(), &constants)
+                .unwrap();
+        let login = |expected_salt: Option<&Salt>, salt: &Salt| {
+            let mut srp6_user = Srp6user1024::with_credentials(&"alice".into(), &"secret".into());
+            let user_handshake = srp6_user.start_handshake(&constants).unwrap();
+            let (mut server_handshake, srp6) = Srp6_1024::default()
+                .continue_handshake(&user_details, &user_handshake.user_publickey, &constants)
+                .unwrap();
+            server_handshake.salt = salt.clone();
+            let (proof, srp6_user) =
+                srp6_user.update_handshake_pinned(&server_handshake, &constants, expected_salt)?;
+            let hamk = srp6.verify_proof(&proof)?.server_proof().clone();
+            srp6_user.verify_session(&hamk)
+        };
+
+        // the first login has nothing pinned yet
+        let session: ClientSession = login(None, &user_details.salt).unwrap();
+        let pinned = session.salt_to_pin().unwrap();
+        assert_eq!(pinned, &user_details.salt);
+        assert!(login(Some(pinned), &user_details.salt).is_ok());
+
+        let changed = Salt::from(42);
+        let error = login(Some(pinned), &changed).unwrap_err();
+        assert_eq!(
+            error,
+            Srp6Error::SaltMismatch {
+                expected: pinned.clone(),
+                received: changed.clone(),
+            }
+        );
+        assert!(error.is_authentication_failure());
+        assert!(!error.to_string().contains(&changed.to_string()));
+        assert!(!error.to_string().contains(&pinned.to_string()));
+
+        // checked first, before `B`, `x` or `S`
+        let mut srp6_user = Srp6user1024::default();
+        srp6_user.start_handshake("alice", &constants).unwrap();
+        let server_handshake = ServerHandshake::new(changed, PublicKey::default());
+        #[cfg(feature = "metrics")]
+        let _ = metrics::take_phase_timings();
+        assert!(matches!(
+            srp6_user.update_handshake_pinned(
+                &server_handshake,
+                &constants,
+                &"alice".into(),
+                &"secret".into(),
+                Some(pinned)
+            ),
+            Err(Srp6Error::SaltMismatch { .. })
+        ));
+        #[cfg(feature = "metrics")]
+        assert_eq!(
+            metrics::take_phase_timings(),
+            metrics::PhaseTimings::default()
+        );
+    }
+
+    /// Test a stored client session, resumed with a nonce challenge each way
+    #[test]
+    #[allow(non_snake_case)]
+    fn test_client_session_nonce() {
+        let username = "fred";
+        let password: &ClearTextPassword = "password_fred";
+        let constants = OpenConstants::default();
+        let user_details =
+            Srp6user2048::generate_new_user_secrets(&username.into(), &password.into(), &constants)
+                .unwrap();
+        let mut srp6_user = Srp6user2048::default();
+        let user_handshake = srp6_user.start_handshake(username, &constants).unwrap();
+        let (server_handshake, srp6) = Srp6_2048::default()
+            .continue_handshake(&user_details, &user_handshake.user_publickey, &constants)
+            .unwrap();
+        let (proof, srp6_user) = srp6_user
+            .update_handshake(
+                &server_handshake,
+                &constants,
+                &username.into(),
+                &password.into(),
+            )
+            .unwrap();
+        let outcome = srp6.verify_proof(&proof).unwrap();
+        let hamk = outcome.server_proof().clone();
+        let secret = outcome.into_session_secret();
+        let session = srp6_user.verify_session(&hamk).unwrap();
+        assert_eq!(session.username, username);
+        assert_eq!(session.salt.as_ref(), Some(&user_details.salt));
+        assert_eq!(session.strong_proof, hamk);
+        assert_eq!(session.strong_session_key(), secret.strong_session_key());
+        assert!(!format!("{session:?}").contains("strong_proof"));
+        assert_eq!(
+            session.authenticated_session().sign(b"msg"),
+            secret.authenticated_session().sign(b"msg")
+        );
+
+        let stored = serde_json::to_string(&session).unwrap();
+        trace("session", &stored);
+        let session = serde_json::from_str::<ClientSession>(&stored).unwrap();
+        let K = secret.strong_session_key();
+
+        // the server challenges the user
+        let nonce = b"server nonce";
+        let users_proof = session.authenticate_nonce(nonce).unwrap();
+        assert!(users_proof.constant_time_eq(&authenticate_nonce::<Sha1>(K, nonce).unwrap()));
+        // the user challenges the server
+        let nonce = b"user nonce";
+        let servers_proof = authenticate_nonce::<Sha1>(K, nonce).unwrap();
+        assert!(servers_proof.constant_time_eq(&session.authenticate_nonce(nonce).unwrap()));
+        assert_ne!(servers_proof, users_proof);
+        // another key does not match
+        let other = strong_session_key_from_bytes::<Sha1>(&[1; 40], ByteOrder::BigEndian).unwrap();
+        assert_ne!(
+            authenticate_nonce::<Sha1>(&other, nonce).unwrap(),
+            servers_proof
+        );
+    }
+
+    /// the very same server code for any group
+    fn dyn_server(
+        user_details: &UserDetails,
+        user_handshake: &UserHandshake,
+        params: &GroupParams,
+    ) -> Result<(DynSrp6, ServerHandshake)> {
+        let mut srp6 = DynSrp6::default();
+        let server_handshake =
+            srp6.continue_handshake(user_details, &user_handshake.user_publickey, params)?;
+        Ok((srp6, server_handshake))
+    }
+
+    #[test]
+    fn test_handshake_dynamic_group() {
+        let username = "Bob";
+        let password: &ClearTextPassword = "secret-password";
+        for params in [
+            GroupParams::from(OpenConstants::<256>::default()),
+            GroupParams::from(&OpenConstants::<512>::default()),
+        ] {
+            let user_details =
+                DynSrp6User::<Sha1>::generate_new_user_secrets(username, password, &params)
+                    .unwrap();
+            let mut srp6_user = DynSrp6User::<Sha1>::default();
+            let user_handshake = srp6_user.start_handshake(username, &params).unwrap();
+            let (srp6, server_handshake) =
+                dyn_server(&user_details, &user_handshake, &params).unwrap();
+            let proof = srp6_user
+                .update_handshake(&server_handshake, &params, username, password)
+                .unwrap();
+            let (hamk, secret) = srp6.verify_proof(&proof).unwrap();
+            assert_eq!(srp6_user.verify_proof(&hamk), Some(secret));
+        }
+
+        // same keys as the const generic API
+        let constants = OpenConstants::<256>::default();
+        let params = GroupParams::from(&constants);
+        let user_details =
+            Srp6user2048::generate_new_user_secrets(&username.into(), &password.into(), &constants)
+                .unwrap();
+        let mut srp6_user = Srp6user2048::default();
+        let user_handshake = srp6_user.start_handshake(username, &constants).unwrap();
+        let (srp6, server_handshake) = dyn_server(&user_details, &user_handshake, &params).unwrap();
+        let (proof, srp6_user) = srp6_user
+            .update_handshake(
+                &server_handshake,
+                &constants,
+                &username.into(),
+                &password.into(),
+            )
+            .unwrap();
+        let (hamk, secret) = srp6.verify_proof(&proof).unwrap();
+        assert_eq!(
+            srp6_user
+                .verify_proof(&hamk)
+                .map(ClientHandshakeOutcome::into_session_secret),
+            Ok(secret)
+        );
+    }
+
+    #[test]
+    fn test_dynamic_group_length_mismatch() {
+        let username = "Bob";
+        let password: &ClearTextPassword = "secret-password";
+        let params_4096 = GroupParams::from(OpenConstants::<512>::default());
+        let params_2048 = GroupParams::from(OpenConstants::<256>::default());
+        let user_details =
+            DynSrp6User::<Sha1>::generate_new_user_secrets(username, password, &params_2048)
+                .unwrap();
+        let mut srp6_user = DynSrp6User::<Sha1>::default();
+        let user_handshake = srp6_user.start_handshake(username, &params_4096).unwrap();
+        let err = dyn_server(&user_details, &user_handshake, &params_2048).unwrap_err();
+        assert!(matches!(err, Srp6Error::KeyLengthMismatch { .. }));
+
+        let (_, server_handshake) = dyn_server(
+            &user_details,
+            &DynSrp6User::<Sha1>::default()
+                .start_handshake(username, &params_2048)
+                .unwrap(),
+            &params_2048,
+        )
+        .unwrap();
+        let err = srp6_user
+            .update_handshake(&server_handshake, &params_2048, username, password)
+            .unwrap_err();
+        assert_eq!(
+            err,
+            Srp6Error::KeyLengthMismatch {
+                given: 256,
+                expected: 512
+            }
+        );
+    }
+
+    #[test]
+    fn test_handshake_hex_strings() {
+        let username = "fred";
+        let password: &ClearTextPassword = "password_fred";
+        let constants = OpenConstants::default();
+        let user_details =
+            Srp6user2048::generate_new_user_secrets(&username.into(), &password.into(), &constants)
+                .unwrap();
+        let mut srp6_user = Srp6user2048::default();
+        let line = srp6_user
+            .start_handshake(username, &constants)
+            .unwrap()
+            .to_string();
+        // server side
+        let user_handshake = UserHandshake::from_hex::<256>(&line.to_lowercase()).unwrap();
+        let (server_handshake, srp6) = Srp6_2048::default()
+            .continue_handshake(&user_details, &user_handshake.user_publickey, &constants)
+            .unwrap();
+        let line = server_handshake.to_string();
+        // client side
+        let server_handshake = ServerHandshake::from_hex::<256>(&line).unwrap();
+        let (proof, srp6_user) = srp6_user
+            .update_handshake(
+                &server_handshake,
+                &constants,
+                &username.into(),
+                &password.into(),
+            )
+            .unwrap();
+        let line = ProofMessage::from(proof).to_string();
+        // server side
+        let proof = ProofMessage::from_hex::<Sha1>(&line).unwrap();
+        let outcome = srp6.verify_proof(&proof.proof).unwrap();
+        let hamk = outcome.server_proof().clone();
+        let secret = outcome.into_session_secret();
+        let line = ProofMessage::from(hamk).to_hex();
+        // client side
+        let hamk = ProofMessage::from_hex::<Sha1>(&line).unwrap();
+        let secret2 = srp6_user
+            .verify_proof(&hamk.strong_proof())
+            .expect("invalid server proof")
+            .into_session_secret();
+        assert_eq!(secret2, secret, "not same secrets");
+    }
+
+    /// Test the handshake against an official test data, with its salt and
+    /// private keys given explicitly: also without `norand`
+    #[test]
+    #[allow(non_snake_case)]
+    fn test_official_vectors_1024() {
+        let username = testdata::USERNAME;
+        let password: &ClearTextPassword = testdata::PASSWORD;
+        let constants = OpenConstants::default();
+        // new user : those are sent to the server and stored there
+        let user_details = Srp6user1024::generate_new_user_secrets_with_salt(
+            &username.into(),
+            &password.into(),
+            &Salt::from_bytes_be(&testdata::SALT),
+            &constants,
+        )
+        .unwrap();
+        let official_verifier = PublicKey::from_bytes_be(&testdata::VERIFIER);
+        assert_eq!(official_verifier, user_details.verifier, "verifier nok");
+        // user creates a handshake
+        let mut srp6_user = Srp6user1024::default();
+        let user_handshake = srp6_user
+            .start_handshake_with_ephemeral(
+                username,
+                &constants,
+                PrivateKey::from_bytes_be(&testdata::A_PRIVATE),
+            )
+            .unwrap();
+        let official_user_publickey = PublicKey::from_bytes_be(&testdata::A_PUBLIC);
+        assert_eq!(
+            official_user_publickey, user_handshake.user_publickey,
+            "A nok"
+        );
+        // server retrieves stored details and continues the handshake
+        let (server_handshake, srp6) = Srp6_1024::default()
+            .continue_handshake_with_ephemeral(
+                &user_details,
+                &user_handshake.user_publickey,
+                &constants,
+                PrivateKey::from_bytes_be(&testdata::B_PRIVATE),
+            )
+            .unwrap();
+        let official_server_publickey = PublicKey::from_bytes_be(&testdata::B_PUBLIC);
+        assert_eq!(
+            official_server_publickey, server_handshake.server_publickey,
+            "B nok"
+        );
+        // client side
+        let (proof, srp6_user) = srp6_user
+            .update_handshake(
+                &server_handshake,
+                &constants,
+                &username.into(),
+                &password.into(),
+            )
+            .unwrap();
+        // server side
+        let outcome = srp6.verify_proof(&proof).unwrap();
+        let hamk = outcome.server_proof().clone();
+        let secret = outcome.into_session_secret();
+        // client side
+        let secret2 = srp6_user
+            .verify_proof(&hamk)
+            .expect("invalid server proof")
+            .into_session_secret();
+        // both secrets
+        assert_eq!(secret2, secret, "not same secrets");
+        // the keys of `K`, the interleave of `S` of the RFC, same on both sides
+        let K = secret.strong_session_key();
+        assert_eq!(K.to_bytes(), secret2.strong_session_key().to_bytes());
+        assert_eq!(
+            K.to_bytes(),
+            hex_literal::hex!(
+                "C018EDCE A8C0B056 7A85BFC3 C7D264FD C5DAC02B 5156A126 2351DEFB 68FC375A 76B981DE CEAB8C2B"
+            )
+        );
+        let (enc_key, mac_key) = K.split_keys();
+        assert_eq!(
+            secret2.strong_session_key().split_keys(),
+            (enc_key, mac_key)
+        );
+        assert_eq!(
+            enc_key,
+            hex_literal::hex!("9E5D7B207BC65001BDA69FCABE4DB66FC7950C76B7CDE7E3B4E4FC4545425798")
+        );
+        assert_eq!(
+            mac_key,
+            hex_literal::hex!("20AE9611F0D83CBA78CB4D614B9BF8C03D4107ABA17AFD967EFABF1441BE6B8C")
+        );
+        // compare official numbers
+        let expected_secret = PrivateKey::from_bytes_be(&testdata::SECRET);
+        assert_eq!(expected_secret, secret.into_raw_session_key(), "S nok");
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn test_length_prefixed_v2() {
+        let (username, password) = (testdata::USERNAME, testdata::PASSWORD);
+        let salt = Salt::from_bytes_be(&testdata::SALT);
+        let handshake = |constants: &OpenConstants<128>| {
+            let user_details = Srp6user1024::generate_new_user_secrets_with_salt(
+                &username.into(),
+                &password.into(),
+                &salt,
+                constants,
+            )
+            .unwrap();
+            let mut user = Srp6user1024::default();
+            let user_handshake = user
+                .start_handshake_with_ephemeral(
+                    username,
+                    constants,
+                    PrivateKey::from_bytes_be(&testdata::A_PRIVATE),
+                )
+                .unwrap();
+            let (server_handshake, host) = Srp6_1024::default()
+                .continue_handshake_with_ephemeral(
+                    &user_details,
+                    &user_handshake.user_publickey,
+                    constants,
+                    PrivateKey::from_bytes_be(&testdata::B_PRIVATE),
+                )
+                .unwrap();
+            let (M, user) = user
+                .update_handshake(
+                    &server_handshake,
+                    constants,
+                    &username.into(),
+                    &password.into(),
+                )
+                .unwrap();
+            let M2 = host.verify_proof(&M).unwrap().server_proof().clone();
+            assert!(user.verify_proof(&M2).is_ok());
+            (
+                user_details.verifier.clone(),
+                server_handshake.server_publickey,
+                M,
+                M2,
+            )
+        };
+
+        // v1 is unchanged
+        let v1 = OpenConstants::default();
+        let (v, _, M, _) = handshake(&v1);
+        assert_eq!(v, PasswordVerifier::from_bytes_be(&testdata::VERIFIER));
+        assert_eq!(
+            M,
+            Proof::from_hex_str_be("5007FFD25BC1A82A4A4131F6C88BFDDC2587571A").unwrap()
+        );
+
+        // not given by any RFC, computed with an independent transcription of
+        // the formulas in Python
+        let v2 = OpenConstants {
+            variant: Srp6Variant::Srp6aV2,
+            ..OpenConstants::default()
+        };
+        assert_eq!(
+            Srp6_1024::derive_private_key(&username.into(), &password.into(), &salt).unwrap(),
+            PrivateKey::from_bytes_be(&testdata::X),
+            "x of v1"
+        );
+        let (v, B, M, M2) = handshake(&v2);
+        assert_eq!(
+            v,
+            Srp6_1024::derive_verifier(&username.into(), &password.into(), &salt, &v2).unwrap()
+        );
+        assert_eq!(
+            v,
+            PasswordVerifier::from_hex_str_be(
+                "1333DF0E2BF0FB527F269F88CB1160554BC6679769B68656DF5C8733683D5AEE4AC142201BF90C23\
+                 7D399372AE488C665B51B42066A2097AB066A702F529D3D633268BBFAF923356F70E83CB62832E12\
+                 8AF339EC0E77023F3AF3410F50B6F4020EFD391CC16BB03E2F0B85AFEA7DF2A141FF35F5DA1D34A0\
+                 8A965921BC50127"
+            )
+            .unwrap(),
+            "v"
+        );
+        assert!(format!("{B:X}").starts_with("CD10E19581638460367968F7B96DD334CE165B9B"));
+        assert_eq!(
+            M,
+            Proof::from_hex_str_be("A8BA45EA9E6CF4AB8AA8008C77D31D3469F2F873").unwrap(),
+            "M"
+        );
+        assert_eq!(
+            M2,
+            StrongProof::from_hex_str_be("FAB0F20442DC85332B84944011DC935C34ABC591").unwrap(),
+            "M2"
+        );
+    }
+
+    #[test]
+    fn test_v1_user_against_v2_host() {
+        let username = "Bob";
+        let password: &ClearTextPassword = "secret-password";
+        let v1 = OpenConstants::default();
+        let v2 = OpenConstants {
+            variant: Srp6Variant::Srp6aV2,
+            ..OpenConstants::default()
+        };
+        let user_details =
+            Srp6user2048::generate_new_user_secrets(&username.into(), &password.into(), &v2)
+                .unwrap();
+        // client uses v1, server v2
+        let mut srp6_user = Srp6user2048::default();
+        let user_handshake = srp6_user.start_handshake(username, &v1).unwrap();
+        let (server_handshake, srp6) = Srp6_2048::default()
+            .continue_handshake(&user_details, &user_handshake.user_publickey, &v2)
+            .unwrap();
+        let (proof, _) = srp6_user
+            .update_handshake(&server_handshake, &v1, &username.into(), &password.into())
+            .unwrap();
+        let err = srp6.verify_proof(&proof).unwrap_err();
+        assert!(matches!(err, Srp6Error::InvalidProof));
+    }
+
+    #[test]
+    fn test_trivial_ephemeral_key() {
+        let constants = OpenConstants::<128>::default();
+        let mut srp6_user = Srp6user1024::default();
+        // `A = g^0 = 1`
+        let err = srp6_user
+            .start_handshake_with_ephemeral("alice", &constants, PrivateKey::default())
+            .unwrap_err();
+        assert_eq!(
+            err,
+            Srp6Error::InvalidConfiguration("the given `a` gives a trivial public key")
+        );
+        assert!(srp6_user.public_key_a().is_zero(), "not started");
+    }
+
+    #[cfg(feature = "zeroize")]
+    #[test]
+    fn test_zeroize_handshake() {
+        use zeroize::Zeroize;
+
+        let username = "Bob";
+        let password: &ClearTextPassword = "secret-password";
+        let constants = OpenConstants::default();
+        let user_details =
+            Srp6user2048::generate_new_user_secrets(&username.into(), &password.into(), &constants)
+                .unwrap();
+        let mut srp6_user = Srp6user2048::default();
+        let user_handshake = srp6_user.start_handshake(username, &constants).unwrap();
+        let (server_handshake, mut srp6) = Srp6_2048::default()
+            .continue_handshake(&user_details, &user_handshake.user_publickey, &constants)
+            .unwrap();
+        assert!(!srp6.public_key_a().is_zero());
+        srp6.zeroize();
+        assert!(
+            srp6.public_key_a().is_zero()
+                && srp6.server_public_key().is_zero()
+                && srp6.scrambling_parameter().is_zero()
+        );
+        let (_, mut srp6_user) = srp6_user
+            .update_handshake(
+                &server_handshake,
+                &constants,
+                &username.into(),
+                &password.into(),
+            )
+            .unwrap();
+        assert!(!srp6_user.proof().is_zero());
+        srp6_user.zeroize();
+        assert!(srp6_user.public_key_a().is_zero() && srp6_user.proof().is_zero());
+        let mut srp6_user = Srp6user2048::default();
+        srp6_user.start_handshake(username, &constants).unwrap();
+        srp6_user.zeroize();
+        assert!(srp6_user.public_key_a().is_zero());
+    }
+
+    #[test]
+    fn test_invalid_user_details() {
+        let constants = OpenConstants::<256>::default();
+        let user_details = Srp6user2048::generate_new_user_secrets(
+            &"Bob".into(),
+            &"secret-password".into(),
+            &constants,
+        )
+        .unwrap();
+        assert_eq!(user_details.validate(&constants), Ok(()));
+        let mut srp6_user = Srp6user2048::default();
+        let user_handshake = srp6_user.start_handshake("Bob", &constants).unwrap();
+
+        let check = |details: &UserDetails, expected: Srp6Error| {
+            assert_eq!(details.validate(&constants), Err(expected.clone()));
+            let err = Srp6_2048::default()
+                .continue_handshake(details, &user_handshake.user_publickey, &constants)
+                .unwrap_err();
+            assert_eq!(err, expected);
+        };
+        let mut details = user_details.clone();
+        details.verifier = PasswordVerifier::zero();
+        check(&details, Srp6Error::InvalidVerifier);
+        details.verifier = (*constants.module).clone();
+        check(&details, Srp6Error::InvalidVerifier);
+        details.verifier = &*constants.module + &PasswordVerifier::from(1);
+        check(&details, Srp6Error::InvalidVerifier);
+        let mut details = user_details.clone();
+        details.salt = Salt::zero();
+        check(&details, Srp6Error::InvalidSalt);
+        let mut details = user_details.clone();
+        details.username = Username::default();
+        check(&details, Srp6Error::EmptyUsername);
+    }
+
+    #[test]
+    fn test_import_user_details() {
+        use crate::protocol_details::testdata;
+        use ByteOrder::*;
+
+        let constants = OpenConstants::<256>::default();
+        let username = testdata::IMPORTED_USERNAME;
+        let password: &ClearTextPassword = testdata::IMPORTED_PASSWORD;
+        let user_details = UserDetails::from_parts(
+            username,
+            RawNumber::Hex(testdata::IMPORTED_SALT_HEX, BigEndian),
+            RawNumber::Hex(testdata::IMPORTED_VERIFIER_HEX, BigEndian),
+            &constants,
+        )
+        .unwrap();
+        let mut expected = Srp6_2048Sha256::generate_user_details_with_salt(
+            &username.into(),
+            &password.into(),
+            &user_details.salt,
+            &constants,
+        )
+        .unwrap();
+        // imported details are not marked as normalized, the ASCII credentials
+        // hash the same anyway
+        expected.normalized = false;
+        // nor with the group, it is not known from the parts
+        expected.group_fingerprint = None;
+        assert_eq!(user_details, expected);
+        assert_eq!(user_details.fingerprint(), "2F0AB6FD70C93B92");
+
+        let mut srp6_user = Srp6user2048Sha256::default();
+        let user_handshake = srp6_user.start_handshake(username, &constants).unwrap();
+        let (server_handshake, srp6) = Srp6_2048Sha256::default()
+            .continue_handshake(&user_details, &user_handshake.user_publickey, &constants)
+            .unwrap();
+        let (proof, srp6_user) = srp6_user
+            .update_handshake(
+                &server_handshake,
+                &constants,
+                &username.into(),
+                &password.into(),
+            )
+            .unwrap();
+        let strong_proof = srp6.verify_proof(&proof).unwrap().server_proof().clone();
+        srp6_user.verify_proof(&strong_proof).unwrap();
+
+        // the same numbers as raw little endian bytes
+        let salt = user_details.salt.to_vec_le();
+        let verifier = user_details.verifier.to_vec_le();
+        let details = UserDetails::from_parts(
+            username,
+            RawNumber::Bytes(&salt, LittleEndian),
+            RawNumber::Bytes(&verifier, LittleEndian),
+            &constants,
+        )
+        .unwrap();
+        assert_eq!(details, user_details);
+
+        let import = |salt: RawNumber, verifier: RawNumber| {
+            UserDetails::from_parts(username, salt, verifier, &constants).unwrap_err()
+        };
+        let salt = RawNumber::Hex(testdata::IMPORTED_SALT_HEX, BigEndian);
+        let modulus = constants.module.to_vec();
+        assert_eq!(
+            import(salt, RawNumber::Bytes(&modulus, BigEndian)),
+            Srp6Error::InvalidVerifier
+        );
+        assert_eq!(
+            import(salt, RawNumber::Hex("00", BigEndian)),
+            Srp6Error::InvalidVerifier
+        );
+        assert_eq!(
+            import(salt, RawNumber::Bytes(&[1; 257], BigEndian)),
+            Srp6Error::KeyLengthMismatch {
+                given: 257,
+                expected: 256
+            }
+        );
+        assert_eq!(
+            import(salt, RawNumber::Hex("12xy", BigEndian)),
+            Srp6Error::Malformed(BigNumberError::InvalidHexStr)
+        );
+        let verifier = RawNumber::Hex(testdata::IMPORTED_VERIFIER_HEX, BigEndian);
+        assert_eq!(
+            import(RawNumber::Hex(" ", BigEndian), verifier),
+            Srp6Error::InvalidSalt
+        );
+        assert_eq!(
+            import(RawNumber::Bytes(&[], BigEndian), verifier),
+            Srp6Error::InvalidSalt
+        );
+    }
+
+    #[test]
+    fn test_user_details_from_hex_tuple() {
+        use crate::protocol_details::testdata;
+
+        let constants = OpenConstants::<256>::default();
+        let parts = (
+            testdata::IMPORTED_USERNAME,
+            testdata::IMPORTED_SALT_HEX,
+            testdata::IMPORTED_VERIFIER_HEX,
+        );
+        let user_details = UserDetails::try_from(parts).unwrap();
+        assert_eq!(
+            user_details,
+            UserDetails::from_parts(
+                parts.0,
+                RawNumber::Hex(parts.1, ByteOrder::BigEndian),
+                RawNumber::Hex(parts.2, ByteOrder::BigEndian),
+                &constants,
+            )
+            .unwrap()
+        );
+        let (username, salt, verifier) = user_details.clone().into_parts();
+        assert_eq!(
+            (username.as_str(), &salt, &verifier),
+            (parts.0, &user_details.salt, &user_details.verifier)
+        );
+
+        let import = |salt, verifier| UserDetails::try_from(("alice", salt, verifier)).unwrap_err();
+        let bad_hex = Srp6Error::Malformed(BigNumberError::InvalidHexStr);
+        assert_eq!(import("12xy", parts.2), bad_hex);
+        assert_eq!(
+            import(parts.1, "0x"),
+            Srp6Error::Malformed(BigNumberError::EmptyStr)
+        );
+        assert_eq!(import(parts.1, "ABC-DEF"), bad_hex);
+        assert_eq!(import("", parts.2), Srp6Error::InvalidSalt);
+        assert_eq!(import("00", parts.2), Srp6Error::InvalidSalt);
+        assert_eq!(import(parts.1, "0000"), Srp6Error::InvalidVerifier);
+        assert_eq!(
+            UserDetails::try_from(("", parts.1, parts.2)),
+            Err(Srp6Error::EmptyUsername)
+        );
+    }
+
+    #[test]
+    fn test_generate_user_details_on_host() {
+        let username = "Bob";
+        let password: &ClearTextPassword = "secret-password";
+        let constants = OpenConstants::default();
+        let user_details =
+            generate_user_details(&username.into(), &password.into(), &constants).unwrap();
+        assert_eq!(user_details.validate(&constants), Ok(()));
+
+        let mut srp6_user = Srp6user2048::default();
+        let user_handshake = srp6_user.start_handshake(username, &constants).unwrap();
+        let (server_handshake, srp6) = Srp6_2048::default()
+            .continue_handshake(&user_details, &user_handshake.user_publickey, &constants)
+            .unwrap();
+        let (proof, srp6_user) = srp6_user
+            .update_handshake(
+                &server_handshake,
+                &constants,
+                &username.into(),
+                &password.into(),
+            )
+            .unwrap();
+        let outcome = srp6.verify_proof(&proof).unwrap();
+        let hamk = outcome.server_proof().clone();
+        let secret = outcome.into_session_secret();
+        assert_eq!(
+            srp6_user
+                .verify_proof(&hamk)
+                .map(ClientHandshakeOutcome::into_session_secret),
+            Ok(secret)
+        );
+    }
+
+    #[test]
+    fn test_reject_empty_credentials() {
+        use crate::protocol_details::testdata;
+        let constants = OpenConstants::<128>::default();
+        assert_eq!(
+            Srp6user1024::generate_new_user_secrets(&"".into(), &"secret".into(), &constants),
+            Err(Srp6Error::EmptyUsername)
+        );
+        assert_eq!(
+            Srp6_1024::generate_new_user_secrets(&"Bob".into(), &"".into(), &constants),
+            Err(Srp6Error::EmptyPassword)
+        );
+        assert_eq!(
+            DynSrp6User::<Sha1>::generate_new_user_secrets(
+                "Bob",
+                "",
+                &GroupParams::from(&constants)
+            ),
+            Err(Srp6Error::EmptyPassword)
+        );
+        let salt = Salt::from_bytes_be(&testdata::SALT);
+        assert_eq!(
+            generate_user_details_with_salt(&"".into(), &"secret".into(), &salt, &constants),
+            Err(Srp6Error::EmptyUsername)
+        );
+        assert_eq!(
+            derive_private_key(&"".into(), &"secret".into(), &salt),
+            Err(Srp6Error::EmptyUsername)
+        );
+        // the RFC 5054 vectors are still met
+        let details = generate_user_details_with_salt(
+            &testdata::USERNAME.into(),
+            &testdata::PASSWORD.into(),
+            &salt,
+            &constants,
+        )
+        .unwrap();
+        assert_eq!(
+            details.verifier,
+            PasswordVerifier::from_bytes_be(&testdata::VERIFIER)
+        );
+
+        // on the client side
+        let details =
+            Srp6user1024::generate_new_user_secrets(&"Bob".into(), &"secret".into(), &constants)
+                .unwrap();
+        for (username, password, expected) in [
+            ("", "secret", Srp6Error::EmptyUsername),
+            ("Bob", "", Srp6Error::EmptyPassword),
+        ] {
+            let mut srp6_user = Srp6user1024::default();
+            let user_handshake = srp6_user.start_handshake(username, &constants).unwrap();
+            let (server_handshake, _) = Srp6_1024::default()
+                .continue_handshake(&details, &user_handshake, &constants)
+                .unwrap();
+            assert_eq!(
+                srp6_user
+                    .update_handshake(
+                        &server_handshake,
+                        &constants,
+                        &username.into(),
+                        &password.into()
+                    )
+                    .err(),
+                Some(expected.clone())
+            );
+
+            let params = GroupParams::from(&constants);
+            let mut srp6_user = DynSrp6User::<Sha1>::default();
+            srp6_user.start_handshake(username, &params).unwrap();
+            assert_eq!(
+                srp6_user.update_handshake(&server_handshake, &params, username, password),
+                Err(expected)
+            );
+        }
+    }
+
+    #[test]
+    fn test_credential_policy() {
+        let constants = OpenConstants::<128>::default();
+        let policy = CredentialPolicy {
+            min_password_length: 8,
+            max_password_length: 16,
+            max_username_length: 5,
+            username_chars: Some(|c| c.is_ascii_lowercase()),
+        };
+        let generate = |username: &str, password: &str| {
+            Srp6user1024::generate_new_user_secrets_with_policy(
+                &username.into(),
+                &password.into(),
+                &constants,
+                &policy,
+            )
+            .map(|details| details.username.clone())
+        };
+        assert_eq!(generate("alice", "password123"), Ok("alice".into()));
+        assert_eq!(generate("", "password123"), Err(Srp6Error::EmptyUsername));
+        assert_eq!(generate("alice", ""), Err(Srp6Error::EmptyPassword));
+        // in characters, not bytes
+        assert_eq!(
+            generate("alice", "pässwör"),
+            Err(Srp6Error::PasswordTooShort {
+                length: 7,
+                minimum: 8
+            })
+        );
+        assert_eq!(
+            generate("alice", "correct horse battery staple"),
+            Err(Srp6Error::PasswordTooLong {
+                length: 28,
+                maximum: 16
+            })
+        );
+        assert_eq!(
+            generate("alice2", "password123"),
+            Err(Srp6Error::UsernameTooLong {
+                length: 6,
+                maximum: 5
+            })
+        );
+        assert_eq!(
+            generate("Alice", "password123"),
+            Err(Srp6Error::InvalidUsername)
+        );
+        assert_eq!(
+            Srp6_1024::generate_new_user_secrets_with_policy(
+                &"bob".into(),
+                &"x".into(),
+                &constants,
+                &CredentialPolicy::default()
+            )
+            .map(|details| details.username.clone()),
+            Ok("bob".into())
+        );
+    }
+
+    #[test]
+    fn test_handshake_salt_length() {
+        use crate::protocol_details::testdata;
+        let username = "Bob";
+        let password: &ClearTextPassword = "secret-password";
+        let constants = OpenConstants::default();
+        let user_details =
+            Srp6user2048::generate_new_user_secrets(&username.into(), &password.into(), &constants)
+                .unwrap();
+        assert!(user_details.salt.num_bytes() <= DEFAULT_SALT_LENGTH);
+        let long_salt = Srp6_2048::generate_new_user_secrets_with_salt_length(
+            &username.into(),
+            &password.into(),
+            &constants,
+            64,
+        )
+        .unwrap();
+        assert!(long_salt.salt.num_bytes() <= 64);
+        // a 16 bytes salt from another system
+        let migrated = generate_user_details_with_salt(
+            &username.into(),
+            &password.into(),
+            &Salt::from_bytes_be(&testdata::SALT),
+            &constants,
+        )
+        .unwrap();
+
+        for user_details in [user_details, long_salt, migrated] {
+            let mut srp6_user = Srp6user2048::default();
+            let user_handshake = srp6_user.start_handshake(username, &constants).unwrap();
+            let (server_handshake, srp6) = Srp6_2048::default()
+                .continue_handshake(&user_details, &user_handshake.user_publickey, &constants)
+                .unwrap();
+            assert_eq!(server_handshake.salt, user_details.salt);
+            let (proof, srp6_user) = srp6_user
+                .update_handshake(
+                    &server_handshake,
+                    &constants,
+                    &username.into(),
+                    &password.into(),
+                )
+                .unwrap();
+            let outcome = srp6.verify_proof(&proof).unwrap();
+            let hamk = outcome.server_proof().clone();
+            let secret = outcome.into_session_secret();
+            assert_eq!(
+                srp6_user
+                    .verify_proof(&hamk)
+                    .map(ClientHandshakeOutcome::into_session_secret),
+                Ok(secret)
+            );
+        }
+    }
+
+    #[test]
+    fn test_oversized_salt() {
+        let (username, password) = ("Bob", "secret-password");
+        let constants = OpenConstants::<256>::default();
+        let mut user_details =
+            Srp6user2048::generate_new_user_secrets(&username.into(), &password.into(), &constants)
+                .unwrap();
+        let too_long = Salt::from_bytes_be(&[1; 257]);
+        let expected = Srp6Error::SaltLengthMismatch {
+            given: 257,
+            expected: 256,
+        };
+
+        // the server checks the stored salt
+        let mut srp6_user = Srp6user2048::with_credentials(&username.into(), &password.into());
+        let user_handshake = srp6_user.start_handshake(&constants).unwrap();
+        let (mut server_handshake, _) = Srp6_2048::new()
+            .continue_handshake(&user_details, &user_handshake.user_publickey, &constants)
+            .unwrap();
+        user_details.salt = too_long.clone();
+        let err = Srp6_2048::new()
+            .continue_handshake(&user_details, &user_handshake.user_publickey, &constants)
+            .unwrap_err();
+        assert_eq!(err, expected);
+
+        // the user checks the salt sent by the server
+        server_handshake.salt = too_long;
+        let err = srp6_user
+            .update_handshake(&server_handshake, &constants)
+            .unwrap_err();
+        assert_eq!(err, expected);
+        assert!(!err.is_authentication_failure());
+    }
+
+    #[test]
+    fn test_generate_user_details_with_salt() {
+        use crate::protocol_details::testdata;
+        let constants = OpenConstants::<128>::default();
+        let salt = Salt::from_bytes_be(&testdata::SALT);
+        let user_details = generate_user_details_with_salt(
+            &testdata::USERNAME.into(),
+            &testdata::PASSWORD.into(),
+            &salt,
+            &constants,
+        )
+        .unwrap();
+        assert_eq!(
+            user_details.verifier,
+            PasswordVerifier::from_bytes_be(&testdata::VERIFIER)
+        );
+        let same = Srp6_1024::generate_user_details_with_salt(
+            &testdata::USERNAME.into(),
+            &testdata::PASSWORD.into(),
+            &salt,
+            &constants,
+        )
+        .unwrap();
+        assert_eq!(same.verifier, user_details.verifier);
+
+        let err = generate_user_details_with_salt(
+            &"Bob".into(),
+            &"secret".into(),
+            &Salt::zero(),
+            &constants,
+        )
+        .unwrap_err();
+        assert_eq!(err, Srp6Error::InvalidSalt);
+        let too_long = Salt::from_bytes_be(&[1; 129]);
+        let err =
+            generate_user_details_with_salt(&"Bob".into(), &"secret".into(), &too_long, &constants)
+                .unwrap_err();
+        assert_eq!(
+            err,
+            Srp6Error::SaltLengthMismatch {
+                given: 129,
+                expected: 128
+            }
+        );
+    }
+
+    #[test]
+    fn test_validate_username() {
+        assert_eq!(validate_username("alice"), Ok(()));
+        assert_eq!(validate_username("pâté"), Ok(()));
+        for username in ["a:b", ":", "a\nb", "a\u{7f}"] {
+            assert_eq!(
+                validate_username(username),
+                Err(Srp6Error::InvalidUsername),
+                "{username:?}"
+            );
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "normalization")]
+    fn test_invalid_username_is_not_registered() {
+        let constants = OpenConstants::<256>::default();
+        let password: Password = "secret-password".into();
+        assert_eq!(
+            Srp6_2048::generate_new_user_secrets(&"a:b".into(), &password, &constants).err(),
+            Some(Srp6Error::InvalidUsername)
+        );
+        assert_eq!(
+            Srp6user2048::generate_new_user_secrets(&"a\nb".into(), &password, &constants).err(),
+            Some(Srp6Error::InvalidUsername)
+        );
+        #[cfg(feature = "std")]
+        assert_eq!(
+            Srp6user2048::generate_new_user_secrets_stretched(
+                &"a:b".into(),
+                &"secret-password".into(),
+                &constants,
+                KdfParams::Pbkdf2 { iterations: 1000 }
+            )
+            .err(),
+            Some(Srp6Error::InvalidUsername)
+        );
+    }
+
+    /// "pâsswörd" composed (NFC), as typed on Android, and decomposed (NFD), as on macOS
+    const PASSWORD_NFC: &str = "p\u{e2}ssw\u{f6}rd";
+    const PASSWORD_NFD: &str = "pa\u{302}sswo\u{308}rd";
+
+    #[test]
+    fn test_normalized_verifier() {
+        use crate::protocol_details::testdata;
+        let constants = OpenConstants::<128>::default();
+        let salt = Salt::from_bytes_be(&testdata::SALT);
+        let nfc = generate_user_details_with_salt(
+            &"alice".into(),
+            &PASSWORD_NFC.into(),
+            &salt,
+            &constants,
+        )
+        .unwrap();
+        let nfd = generate_user_details_with_salt(
+            &"alice".into(),
+            &PASSWORD_NFD.into(),
+            &salt,
+            &constants,
+        )
+        .unwrap();
+        let normalization = cfg!(feature = "normalization");
+        assert_eq!(nfc.verifier == nfd.verifier, normalization);
+        assert_eq!(nfc.normalized, normalization);
+        assert_eq!(
+            derive_verifier(&"alice".into(), &PASSWORD_NFD.into(), &salt, &constants).unwrap(),
+            nfd.verifier
+        );
+        // ASCII is unchanged by the normalization
+        let ascii = generate_user_details_with_salt(
+            &testdata::USERNAME.into(),
+            &testdata::PASSWORD.into(),
+            &salt,
+            &constants,
+        )
+        .unwrap();
+        assert_eq!(
+            ascii.verifier,
+            PasswordVerifier::from_bytes_be(&testdata::VERIFIER)
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "normalization")]
+    fn test_normalized_handshake() {
+        use crate::protocol_details::testdata;
+        let constants = OpenConstants::<128>::default();
+        let user_details = Srp6user1024::generate_new_user_secrets(
+            &"alice".into(),
+            &PASSWORD_NFC.into(),
+            &constants,
+        )
+        .unwrap();
+        assert!(user_details.normalized);
+
+        let mut user = Srp6user1024::default();
+        let handshake = user.start_handshake("alice", &constants).unwrap();
+        let (server_handshake, host) = Srp6_1024::default()
+            .continue_handshake(&user_details, &handshake.user_publickey, &constants)
+            .unwrap();
+        let (proof, _) = user
+            .clone()
+            .update_handshake(
+                &server_handshake,
+                &constants,
+                &"alice".into(),
+                &PASSWORD_NFD.into(),
+            )
+            .unwrap();
+        assert!(host.verify_proof(&proof).is_ok());
+
+        // without normalization, the user hashes the bytes as typed
+        let handshake = user.start_handshake("alice", &constants).unwrap();
+        let (server_handshake, host) = Srp6_1024::default()
+            .continue_handshake(&user_details, &handshake.user_publickey, &constants)
+            .unwrap();
+        let (proof, _) = user
+            .clone()
+            .with_normalization(false)
+            .update_handshake(
+                &server_handshake,
+                &constants,
+                &"alice".into(),
+                &PASSWORD_NFD.into(),
+            )
+            .unwrap();
+        assert_eq!(
+            host.verify_proof(&proof).err(),
+            Some(Srp6Error::InvalidProof)
+        );
+
+        assert_eq!(
+            user.update_handshake(
+                &server_handshake,
+                &constants,
+                &"a:b".into(),
+                &PASSWORD_NFC.into()
+            )
+            .err(),
+            Some(Srp6Error::InvalidUsername)
+        );
+        let salt = Salt::from_bytes_be(&testdata::SALT);
+        assert_eq!(
+            generate_user_details_with_salt(&"a:b".into(), &PASSWORD_NFC.into(), &salt, &constants)
+                .err(),
+            Some(Srp6Error::InvalidUsername)
+        );
+    }
+
+    #[test]
+    fn test_derive_private_key_and_verifier() {
+        use crate::protocol_details::testdata;
+        let constants = OpenConstants::<128>::default();
+        let salt = Salt::from_bytes_be(&testdata::SALT);
+        let x = derive_private_key(
+            &testdata::USERNAME.into(),
+            &testdata::PASSWORD.into(),
+            &salt,
+        )
+        .unwrap();
+        assert_eq!(x, PrivateKey::from_bytes_be(&testdata::X));
+        let v = derive_verifier(
+            &testdata::USERNAME.into(),
+            &testdata::PASSWORD.into(),
+            &salt,
+            &constants,
+        )
+        .unwrap();
+        assert_eq!(v, PasswordVerifier::from_bytes_be(&testdata::VERIFIER));
+        assert_eq!(
+            Srp6_1024::derive_verifier(
+                &testdata::USERNAME.into(),
+                &testdata::PASSWORD.into(),
+                &salt,
+                &constants
+            )
+            .unwrap(),
+            v
+        );
+
+        assert_eq!(
+            derive_private_key(&"Bob".into(), &"secret".into(), &Salt::zero()),
+            Err(Srp6Error::InvalidSalt)
+        );
+        assert_eq!(
+            derive_private_key(&"Bob".into(), &"".into(), &salt),
+            Err(Srp6Error::EmptyPassword)
+        );
+        assert_eq!(
+            derive_verifier(&"Bob".into(), &"".into(), &salt, &constants),
+            Err(Srp6Error::EmptyPassword)
+        );
+        let too_long = Salt::from_bytes_be(&[1; 129]);
+        assert!(matches!(
+            derive_verifier(&"Bob".into(), &"secret".into(), &too_long, &constants),
+            Err(Srp6Error::SaltLengthMismatch { given: 129, .. })
+        ));
+    }
+
+    #[test]
+    fn test_bounded_messages() {
+        let constants = OpenConstants::<256>::default();
+        let user_details =
+            Srp6user2048::generate_new_user_secrets(&"Bob".into(), &"secret".into(), &constants)
+                .unwrap();
+        let mut user = Srp6user2048::default();
+        let user_handshake = user.start_handshake("Bob", &constants).unwrap();
+        let (server_handshake, _) = Srp6_2048::default()
+            .continue_handshake(&user_details, &user_handshake.user_publickey, &constants)
+            .unwrap();
+
+        // the normal messages round trip
+        let json = serde_json::to_string(&user_handshake).unwrap();
+        let read: Bounded<UserHandshake, 256> = serde_json::from_str(&json).unwrap();
+        assert_eq!(read.into_inner(), user_handshake);
+        let json = serde_json::to_string(&server_handshake).unwrap();
+        let read: Bounded<ServerHandshake, 256> = serde_json::from_str(&json).unwrap();
+        assert_eq!(read.0, server_handshake);
+        let bytes = bincode::serialize(&server_handshake).unwrap();
+        let read: Bounded<ServerHandshake, 256> = bincode::deserialize(&bytes).unwrap();
+        assert_eq!(read.0, server_handshake);
+
+        // 10 times too long, rejected by the parsing itself
+        let mut oversized = server_handshake.clone();
+        oversized.server_publickey = PublicKey::from_bytes_be(&[0xAB; 10 * 256]);
+        let json = serde_json::to_string(&oversized).unwrap();
+        let err = serde_json::from_str::<Bounded<ServerHandshake, 256>>(&json).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("(2560 bytes) does not fit in 256 bytes"),
+            "{err}"
+        );
+        // the unbounded form still reads it
+        assert!(serde_json::from_str::<ServerHandshake>(&json).is_ok());
+        let bytes = bincode::serialize(&oversized).unwrap();
+        assert!(bincode::deserialize::<Bounded<ServerHandshake, 256>>(&bytes).is_err());
+        let mut oversized = server_handshake;
+        oversized.salt = Salt::from_bytes_be(&[1; 257]);
+        let json = serde_json::to_string(&oversized).unwrap();
+        assert!(serde_json::from_str::<Bounded<ServerHandshake, 256>>(&json).is_err());
+
+        let oversized = UserHandshake::new("Bob", PublicKey::from_bytes_be(&[0xAB; 10 * 256]));
+        let json = serde_json::to_string(&oversized).unwrap();
+        assert!(serde_json::from_str::<Bounded<UserHandshake, 256>>(&json).is_err());
+        let bytes = bincode::serialize(&oversized).unwrap();
+        assert!(bincode::deserialize::<Bounded<UserHandshake, 256>>(&bytes).is_err());
+    }
+
+    #[test]
+    fn test_negotiated_handshake() {
+        let username = "Bob";
+        let password: &ClearTextPassword = "secret-password";
+        let constants = OpenConstants::<256>::default();
+        let user_details =
+            Srp6user2048::generate_new_user_secrets(&username.into(), &password.into(), &constants)
+                .unwrap();
+        let trusted = TrustedGroups::default();
+
+        // the server offers its group, the user starts in it if trusted
+        let group_msg = constants.group_message();
+        let user_constants = trusted.resolve::<256>(&group_msg).unwrap();
+        assert_eq!(user_constants, constants);
+        let mut srp6_user = Srp6user2048::default();
+        let user_handshake = srp6_user
+            .start_handshake(username, &user_constants)
+            .unwrap();
+        let (server_handshake, srp6) = Srp6_2048::default()
+            .continue_handshake(&user_details, &user_handshake.user_publickey, &constants)
+            .unwrap();
+        let (proof, srp6_user) = srp6_user
+            .update_handshake_negotiated(
+                &server_handshake,
+                &group_msg,
+                &trusted,
+                &username.into(),
+                &password.into(),
+            )
+            .unwrap();
+        let outcome = srp6.verify_proof(&proof).unwrap();
+        let strong_proof = outcome.server_proof().clone();
+        let session_key_server = outcome.into_session_secret();
+        let session_key_user = srp6_user
+            .verify_proof(&strong_proof)
+            .unwrap()
+            .into_session_secret();
+        assert_eq!(session_key_user, session_key_server);
+
+        // a group of the right size, but not trusted
+        assert!(matches!(
+            trusted.resolve::<32>(&group_msg),
+            Err(Srp6Error::KeyLengthMismatch {
+                given: 256,
+                expected: 32
+            })
+        ));
+        let home_made = OpenConstants::<32>::default();
+        let user_details = Srp6User::<32>::generate_new_user_secrets(
+            &username.into(),
+            &password.into(),
+            &home_made,
+        )
+        .unwrap();
+        let home_made_msg = home_made.group_message();
+        assert_eq!(
+            trusted.resolve::<32>(&home_made_msg).err(),
+            Some(Srp6Error::UntrustedGroup(home_made_msg.fingerprint()))
+        );
+        let mut srp6_user = Srp6User::<32>::default();
+        let user_handshake = srp6_user.start_handshake(username, &home_made).unwrap();
+        let (server_handshake, _) = Srp6::<32>::default()
+            .continue_handshake(&user_details, &user_handshake.user_publickey, &home_made)
+            .unwrap();
+        let result = srp6_user.update_handshake_negotiated(
+            &server_handshake,
+            &home_made_msg,
+            &trusted,
+            &username.into(),
+            &password.into(),
+        );
+        assert_eq!(
+            result.err(),
+            Some(Srp6Error::UntrustedGroup(home_made_msg.fingerprint()))
+        );
+        let trusted = trusted.with_group(home_made);
+        assert!(trusted.contains(&home_made_msg.fingerprint()));
+        assert_eq!(trusted.fingerprints().count(), 8);
+    }
+
+    #[test]
+    fn test_group_params_message() {
+        let group_msg = OpenConstants::<256>::default().group_message();
+        // stable across runs and versions, a user may pin it
+        assert_eq!(
+            group_msg.fingerprint(),
+            "12D25311617FCF70595AA1F3009F430820345B596D42C1A530C4C22D1A3000D1"
+        );
+        assert!(TrustedGroups::default().contains(&group_msg.fingerprint()));
+        assert!(!TrustedGroups::empty().contains(&group_msg.fingerprint()));
+
+        let bytes = group_msg.to_bytes().unwrap();
+        assert_eq!(bytes.len(), 2 + 256 + 2 + 1);
+        assert_eq!(GroupParamsMessage::from_bytes(&bytes).unwrap(), group_msg);
+        assert_eq!(
+            GroupParamsMessage::from_bytes(&bytes[..bytes.len() - 1]).err(),
+            Some(Srp6Error::TruncatedMessage)
+        );
+        assert_eq!(
+            GroupParamsMessage::from_bytes(&[0, 0, 0, 0]).err(),
+            Some(Srp6Error::MalformedMessage)
+        );
+        let json = serde_json::to_string(&group_msg).unwrap();
+        assert_eq!(
+            serde_json::from_str::<GroupParamsMessage>(&json).unwrap(),
+            group_msg
+        );
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn test_invalid_public_keys() {
+        let username = "Bob";
+        let password: &ClearTextPassword = "secret-password";
+        let constants = OpenConstants::<256>::default();
+        let user_details =
+            Srp6user2048::generate_new_user_secrets(&username.into(), &password.into(), &constants)
+                .unwrap();
+        let N: &PrimeModulus = &constants.module;
+        let one = PublicKey::from(1);
+        let too_long = PublicKey::from_bytes_be(&[1; 257]);
+        let invalid_keys = [
+            (
+                PublicKey::zero(),
+                Srp6Error::InvalidPublicKey(PublicKey::zero()),
+            ),
+            (N.clone(), Srp6Error::InvalidPublicKey(N.clone())),
+            (N + &one, Srp6Error::PublicKeyOutOfRange(N + &one)),
+            (one.clone(), Srp6Error::PublicKeyIsOne),
+            (N - &one, Srp6Error::PublicKeyIsModulusMinusOne),
+            (
+                too_long,
+                Srp6Error::KeyLengthMismatch {
+                    given: 257,
+                    expected: 256,
+                },
+            ),
+        ];
+
+        for (A, expected) in &invalid_keys {
+            let err = Srp6_2048::default()
+                .continue_handshake(&user_details, A, &constants)
+                .unwrap_err();
+            assert_eq!(&err, expected);
+        }
+
+        for (B, expected) in invalid_keys {
+            let mut srp6_user = Srp6user2048::default();
+            srp6_user.start_handshake(username, &constants).unwrap();
+            let server_handshake = ServerHandshake::new(user_details.salt.clone(), B);
+            let err = srp6_user
+                .update_handshake(
+                    &server_handshake,
+                    &constants,
+                    &username.into(),
+                    &password.into(),
+                )
+                .unwrap_err();
+            assert_eq!(err, expected);
+        }
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn test_invalid_public_key_rejected_early() {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        /// counts the bytes drawn from it
+        struct CountingRng {
+            drawn: usize,
+            inner: StdRng,
+        }
+
+        impl rand::RngCore for CountingRng {
+            fn next_u32(&mut self) -> u32 {
+                self.drawn += 4;
+                self.inner.next_u32()
+            }
+
+            fn next_u64(&mut self) -> u64 {
+                self.drawn += 8;
+                self.inner.next_u64()
+            }
+
+            fn fill_bytes(&mut self, dest: &mut [u8]) {
+                self.drawn += dest.len();
+                self.inner.fill_bytes(dest)
+            }
+
+            fn try_fill_bytes(&mut self, dest: &mut [u8]) -> core::result::Result<(), rand::Error> {
+                self.fill_bytes(dest);
+                Ok(())
+            }
+        }
+
+        impl rand::CryptoRng for CountingRng {}
+
+        let constants = OpenConstants::<256>::default();
+        let user_details =
+            Srp6user2048::generate_new_user_secrets(&"Bob".into(), &"secret".into(), &constants)
+                .unwrap();
+        let mut rng = CountingRng {
+            drawn: 0,
+            inner: StdRng::seed_from_u64(7),
+        };
+
+        // no session id and no `b` drawn for an invalid `A`
+        let err = Srp6_2048::default()
+            .continue_handshake_with_rng(&user_details, &PublicKey::from(1), &constants, &mut rng)
+            .unwrap_err();
+        assert_eq!(err, Srp6Error::PublicKeyIsOne);
+        assert_eq!(rng.drawn, 0);
+
+        // checked before the details are needed
+        let A = Srp6user2048::default()
+            .start_handshake("Bob", &constants)
+            .unwrap()
+            .user_publickey;
+        assert_eq!(validate_public_key(&A, &constants), Ok(()));
+        Srp6_2048::default()
+            .continue_handshake_with_rng(&user_details, &A, &constants, &mut rng)
+            .unwrap();
+        assert!(rng.drawn > 0);
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn test_error_without_numbers() {
+        use std::error::Error;
+
+        let A = PublicKey::from_hex_str_be("AB11CD0123456789AB11CD0123456789").unwrap();
+        let err = Srp6Error::InvalidPublicKey(A.clone());
+        assert_eq!(
+            err.to_string(),
+            "The provided public key is invalid (AB11CD01..., 16 bytes)"
+        );
+        let json = serde_json::to_value(&err).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "kind": "InvalidPublicKey",
+                "message": "The provided public key is invalid (AB11CD01..., 16 bytes)",
+            })
+        );
+        assert!(!json.to_string().contains(&A.to_string()));
+        assert!(err.is_authentication_failure());
+        assert!(Srp6Error::InvalidProof.is_authentication_failure());
+        assert!(!Srp6Error::MalformedMessage.is_authentication_failure());
+
+        let err: Srp6Error = PublicKey::from_hex_str_be("not hex").unwrap_err().into();
+        assert_eq!(err, Srp6Error::Malformed(BigNumberError::InvalidHexStr));
+        assert!(!err.is_authentication_failure());
+        assert_eq!(
+            err.source().map(ToString::to_string),
+            Some(BigNumberError::InvalidHexStr.to_string())
+        );
+        assert!(Srp6Error::InvalidPublicKey(A).source().is_none());
+    }
+
+    #[test]
+    fn test_length_mismatch_1() {
+        let username = "Bob";
+        let password: &ClearTextPassword = "secret-password";
+        // client is 4096
+        let user_constants = OpenConstants::default();
+        let user_details = Srp6user4096::generate_new_user_secrets(
+            &username.into(),
+            &password.into(),
+            &user_constants,
+        )
+        .unwrap();
+        let mut srp6_user = Srp6user4096::default();
+        let user_handshake = srp6_user
+            .start_handshake(username, &user_constants)
+            .unwrap();
+        // server is 2048
+        let server_constants = OpenConstants::default();
+        let err = Srp6_2048::default()
+            .continue_handshake(
+                &user_details,
+                &user_handshake.user_publickey,
+                &server_constants,
+            )
+            .unwrap_err();
+        assert!(matches!(err, Srp6Error::KeyLengthMismatch { .. }));
+        // the whole message tells the group of the client
+        let err = Srp6_2048::default()
+            .continue_handshake(&user_details, &user_handshake, &server_constants)
+            .unwrap_err();
+        assert_eq!(
+            err,
+            Srp6Error::GroupMismatch {
+                client_bits: 4096,
+                server_bits: 2048
+            }
+        );
+        assert!(!err.is_authentication_failure());
+    }
+
+    /// the salt has the key length (not under norand)
+    #[cfg(not(feature = "norand"))]
+    #[test]
+    fn test_length_mismatch_stored_details() {
+        let username = "Bob";
+        let password: &ClearTextPassword = "secret-password";
+        // details were stored for 4096
+        let user_details = Srp6user4096::generate_new_user_secrets(
+            &username.into(),
+            &password.into(),
+            &OpenConstants::default(),
+        )
+        .unwrap();
+        // but client and server are 2048
+        let constants = OpenConstants::default();
+        let mut srp6_user = Srp6user2048::default();
+        let user_handshake = srp6_user.start_handshake(username, &constants).unwrap();
+        let err = Srp6_2048::default()
+            .continue_handshake(&user_details, &user_handshake.user_publickey, &constants)
+            .unwrap_err();
+        assert!(matches!(err, Srp6Error::KeyLengthMismatch { .. }));
+    }
+
+    #[test]
+    fn test_length_mismatch_2() {
+        let username = "Bob";
+        let password: &ClearTextPassword = "secret-password";
+        // client is 2048
+        let user_constants = OpenConstants::default();
+        let user_details = Srp6user2048::generate_new_user_secrets(
+            &username.into(),
+            &password.into(),
+            &user_constants,
+        )
+        .unwrap();
+        let mut srp6_user = Srp6user2048::default();
+        srp6_user
+            .start_handshake(username, &user_constants)
+            .unwrap();
+        // server is 4096, the `A` of a 4096 user, the 2048 one is rejected by its length
+        let server_constants = OpenConstants::default();
+        let (server_handshake, _) = Srp6_4096::default()
+            .continue_handshake(
+                &user_details,
+                &Srp6user4096::default()
+                    .start_handshake(username, &server_constants)
+                    .unwrap()
+                    .user_publickey,
+                &server_constants,
+            )
+            .unwrap();
+        assert_eq!(server_handshake.group_bits, 4096);
+        // client will detect, before `B` is checked
+        let err = srp6_user
+            .clone()
+            .update_handshake(
+                &server_handshake,
+                &user_constants,
+                &username.into(),
+                &password.into(),
+            )
+            .unwrap_err();
+        assert_eq!(
+            err,
+            Srp6Error::GroupMismatch {
+                client_bits: 2048,
+                server_bits: 4096
+            }
+        );
+        // a server of an earlier version does not tell its group
+        let server_handshake = ServerHandshake {
+            group_bits: 0,
+            ..server_handshake
+        };
+        let err = srp6_user
+            .update_handshake(
+                &server_handshake,
+                &user_constants,
+                &username.into(),
+                &password.into(),
+            )
+            .unwrap_err();
+        assert!(matches!(err, Srp6Error::KeyLengthMismatch { .. }));
+    }
+
+    #[test]
+    fn test_group_mismatch_on_the_server() {
+        let username = "Bob";
+        let password: &ClearTextPassword = "secret-password";
+        let user_constants = OpenConstants::<256>::default();
+        let server_constants = OpenConstants::<512>::default();
+        let user_details = Srp6_4096::generate_new_user_secrets(
+            &username.into(),
+            &password.into(),
+            &server_constants,
+        )
+        .unwrap();
+        let mut srp6_user = Srp6user2048::default();
+        let user_handshake = srp6_user
+            .start_handshake(username, &user_constants)
+            .unwrap();
+        assert_eq!(user_handshake.group_bits, 2048);
+        let expected = Srp6Error::GroupMismatch {
+            client_bits: 2048,
+            server_bits: 4096,
+        };
+        // checked before `b` is drawn, by all the entry points of the host
+        assert_eq!(
+            Srp6_4096::default()
+                .continue_handshake(&user_details, &user_handshake, &server_constants)
+                .unwrap_err(),
+            expected
+        );
+        assert_eq!(
+            Srp6_4096::continue_handshake_stateless(
+                &user_details,
+                &user_handshake,
+                &server_constants
+            )
+            .unwrap_err(),
+            expected
+        );
+        let prepared = Srp6_4096::default()
+            .prepare(&user_details, &server_constants)
+            .unwrap();
+        assert_eq!(prepared.complete(&user_handshake).unwrap_err(), expected);
+        assert_eq!(
+            DynSrp6::<Sha1>::default()
+                .continue_handshake(
+                    &user_details,
+                    &user_handshake,
+                    &GroupParams::from(&server_constants)
+                )
+                .unwrap_err(),
+            expected
+        );
+
+        // without the group, as from a client of an earlier version, only the
+        // lengths are checked: `A` fits in the larger group, but is too short
+        let user_handshake = UserHandshake {
+            group_bits: 0,
+            ..user_handshake
+        };
+        assert!(matches!(
+            Srp6_4096::default().continue_handshake(
+                &user_details,
+                &user_handshake,
+                &server_constants
+            ),
+            Err(Srp6Error::KeyLengthMismatch { expected: 512, .. })
+        ));
+    }
+
+    #[test]
+    fn test_smaller_group_without_group_bits() {
+        let (username, password) = (Username::from("Bob"), Password::from("secret-password"));
+        let constants = OpenConstants::<256>::default();
+        let user_details =
+            Srp6_2048::generate_new_user_secrets(&username, &password, &constants).unwrap();
+        let mut srp6_user = Srp6user2048::default();
+        let user_handshake = srp6_user.start_handshake("Bob", &constants).unwrap();
+        let (server_handshake, srp6) = Srp6_2048::default()
+            .continue_handshake(&user_details, &user_handshake, &constants)
+            .unwrap();
+        // a server of an earlier version does not tell its group
+        let server_handshake = ServerHandshake {
+            group_bits: 0,
+            ..server_handshake
+        };
+
+        // for a user of 4096 bits, `B` fits but is too short
+        let user_constants = OpenConstants::<512>::default();
+        let mut srp6_user_4096 = Srp6user4096::default();
+        srp6_user_4096
+            .start_handshake("Bob", &user_constants)
+            .unwrap();
+        assert!(matches!(
+            srp6_user_4096.update_handshake(
+                &server_handshake,
+                &user_constants,
+                &username,
+                &password
+            ),
+            Err(Srp6Error::KeyLengthMismatch { expected: 512, .. })
+        ));
+
+        // the same group, without the bits either
+        let (proof, _) = srp6_user
+            .update_handshake(&server_handshake, &constants, &username, &password)
+            .unwrap();
+        assert!(srp6.verify_proof(&proof).is_ok());
+    }
+
+    #[test]
+    fn test_group_bits_of_earlier_messages() {
+        // the JSON of the messages of earlier versions, without `group_bits`
+        let json = r#"{"username": "Bob", "user_publickey": "02"}"#;
+        let expected = UserHandshake::new("Bob", PublicKey::from(2));
+        assert_eq!(expected.group_bits, 0);
+        assert_eq!(
+            serde_json::from_str::<UserHandshake>(json).unwrap(),
+            expected
+        );
+        assert_eq!(
+            serde_json::from_str::<Bounded<UserHandshake, 256>>(json)
+                .unwrap()
+                .into_inner(),
+            expected
+        );
+        let json = r#"{"salt": "BEB25379", "server_publickey": "02"}"#;
+        let expected = ServerHandshake::new(Salt::from(0xBEB25379), PublicKey::from(2));
+        assert_eq!(
+            serde_json::from_str::<ServerHandshake>(json).unwrap(),
+            expected
+        );
+        assert_eq!(
+            serde_json::from_str::<Bounded<ServerHandshake, 256>>(json)
+                .unwrap()
+                .into_inner(),
+            expected
+        );
+
+        // and the new fields are ignored by the peers of earlier versions
+        #[derive(serde::Deserialize, Debug, PartialEq)]
+        struct EarlierUserHandshake {
+            username: String,
+            user_publickey: PublicKey,
+        }
+        let mut handshake = UserHandshake::new("Bob", PublicKey::from(2));
+        handshake.group_bits = 2048;
+        let json = serde_json::to_string(&handshake).unwrap();
+        assert!(json.contains(r#""group_bits":2048"#));
+        assert_eq!(
+            serde_json::from_str::<EarlierUserHandshake>(&json).unwrap(),
+            EarlierUserHandshake {
+                username: "Bob".to_owned(),
+                user_publickey: PublicKey::from(2),
+            }
+        );
+        assert_eq!(
+            serde_json::from_str::<UserHandshake>(&json).unwrap(),
+            handshake
+        );
+        // the hex form does not carry it
+        assert_eq!(
+            UserHandshake::from_hex::<256>(&handshake.to_hex())
+                .unwrap()
+                .group_bits,
+            0
+        );
+    }
+
+    /// keeps the output of a [`tracing_subscriber`] in memory
+    #[cfg(feature = "tracing")]
+    #[derive(Clone, Default)]
+    struct CapturedOutput(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+
+    #[cfg(feature = "tracing")]
+    impl std::io::Write for CapturedOutput {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[cfg(feature = "tracing")]
+    impl<'a> tracing_subscriber::fmt::MakeWriter<'a> for CapturedOutput {
+        type Writer = Self;
+
+        fn make_writer(&'a self) -> Self::Writer {
+            self.clone()
+        }
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn test_tracing_spans_without_secrets() {
+        // the `log` records end up in the same output
+        let _ = tracing_log::LogTracer::init();
+        let output = CapturedOutput::default();
+        let subscriber = tracing_subscriber::fmt()
+            .with_max_level(tracing::Level::DEBUG)
+            .with_span_events(tracing_subscriber::fmt::format::FmtSpan::CLOSE)
+            .with_writer(output.clone())
+            .finish();
+
+        let username = "Bob";
+        let password: &ClearTextPassword = "secret-password";
+        let constants = OpenConstants::default();
+        let user_details =
+            Srp6user1024::generate_new_user_secrets(&username.into(), &password.into(), &constants)
+                .unwrap();
+        let secret = tracing::subscriber::with_default(subscriber, || {
+            let mut srp6_user = Srp6user1024::default();
+            let user_handshake = srp6_user.start_handshake(username, &constants).unwrap();
+            let (server_handshake, srp6) = Srp6_1024::default()
+                .continue_handshake(&user_details, &user_handshake.user_publickey, &constants)
+                .unwrap();
+            let (proof, srp6_user) = srp6_user
+                .update_handshake(
+                    &server_handshake,
+                    &constants,
+                    &username.into(),
+                    &password.into(),
+                )
+                .unwrap();
+            let outcome = srp6.verify_proof(&proof).unwrap();
+            let hamk = outcome.server_proof().clone();
+            let secret = outcome.into_session_secret();
+            srp6_user.verify_proof(&hamk).unwrap();
+            secret
+        });
+
+        let output = String::from_utf8(output.0.lock().unwrap().clone()).unwrap();
+        for step in ["continue_handshake", "update_handshake", "verify_proof"] {
+            assert!(output.contains(step), "no span {step}");
+        }
+        assert!(output.contains("group_bits=1024"));
+        assert!(output.contains("username_hash="));
+        assert!(output.contains("duration_us="));
+        assert!(!output.contains(username));
+        let secrets_logged = output.contains(&secret.clone().into_raw_session_key().to_string())
+            || output.contains(&secret.strong_session_key().to_string());
+        assert_eq!(secrets_logged, cfg!(feature = "insecure-debug-logging"));
+    }
+
+    #[cfg(feature = "metrics")]
+    #[test]
+    fn test_phase_timings_of_a_handshake() {
+        use crate::metrics::{take_phase_timings, PhaseTimings};
+        use core::time::Duration;
+
+        let username = "Bob";
+        let password: &ClearTextPassword = "secret-password";
+        let constants = OpenConstants::default();
+        let user_details =
+            Srp6user1024::generate_new_user_secrets(&username.into(), &password.into(), &constants)
+                .unwrap();
+        let _ = take_phase_timings();
+
+        let mut srp6_user = Srp6user1024::default();
+        let user_handshake = srp6_user.start_handshake(username, &constants).unwrap();
+        let (server_handshake, srp6) = Srp6_1024::default()
+            .continue_handshake(&user_details, &user_handshake.user_publickey, &constants)
+            .unwrap();
+        let (proof, srp6_user) = srp6_user
+            .update_handshake(
+                &server_handshake,
+                &constants,
+                &username.into(),
+                &password.into(),
+            )
+            .unwrap();
+        let hamk = srp6.verify_proof(&proof).unwrap().server_proof().clone();
+        srp6_user.verify_proof(&hamk).unwrap();
+
+        let timings = take_phase_timings();
+        for (phase, duration) in [
+            ("keygen", timings.keygen),
+            ("modpow", timings.modpow),
+            ("kdf", timings.kdf),
+            ("proof", timings.proof),
+        ] {
+            assert!(duration > Duration::ZERO, "no time in {phase}");
+        }
+        assert_eq!(take_phase_timings(), PhaseTimings::default());
+
+        let json = serde_json::to_string(&timings).unwrap();
+        let deserialized: PhaseTimings = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized, timings);
     }
 }
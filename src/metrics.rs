@@ -0,0 +1,69 @@
+/*!
+The time spent in each phase of the handshakes (feature `metrics`)
+
+The handshake steps of this crate add the durations of their phases to a
+[`PhaseTimings`] of the current thread, read and reset by [`take_phase_timings`],
+e.g. to feed the histograms of a metrics backend:
+
+```rust
+use chadehoc_srp6::metrics::take_phase_timings;
+use chadehoc_srp6::*;
+
+let constants = OpenConstants::<128>::default();
+let user_details =
+    Srp6user1024::generate_new_user_secrets(&"Bob".into(), &"secret-password".into(), &constants)
+        .unwrap();
+let _ = take_phase_timings();
+
+let mut srp6_user = Srp6user1024::default();
+let user_handshake = srp6_user.start_handshake("Bob", &constants).unwrap();
+let (server_handshake, srp6) = Srp6_1024::default()
+    .continue_handshake(&user_details, &user_handshake.user_publickey, &constants)
+    .unwrap();
+let timings = take_phase_timings();
+assert!(timings.modpow > std::time::Duration::ZERO);
+```
+
+The phases are:
+- `keygen`: the private keys `a` and `b` and the public keys `A` and `B`
+- `modpow`: the premaster secret `S`
+- `kdf`: the private key `x` (with its stretching if any) and the session key `K`
+- `proof`: the proofs `M` and `M2`
+
+**Note**: the timings are recorded on the thread that runs the step, the
+`async` variants run it on the blocking pool of tokio.
+*/
+use core::time::Duration;
+use serde::{Deserialize, Serialize};
+use std::cell::Cell;
+
+/// durations of the phases of the handshake steps, see the [module](self) documentation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct PhaseTimings {
+    /// private and public keys `a`, `A`, `b`, `B`
+    pub keygen: Duration,
+    /// premaster secret `S`
+    pub modpow: Duration,
+    /// private key `x` and session key `K`
+    pub kdf: Duration,
+    /// proofs `M` and `M2`
+    pub proof: Duration,
+}
+
+std::thread_local! {
+    static TIMINGS: Cell<PhaseTimings> = Cell::new(PhaseTimings::default());
+}
+
+/// the timings recorded on this thread since the last call, which are reset
+pub fn take_phase_timings() -> PhaseTimings {
+    TIMINGS.with(Cell::take)
+}
+
+/// adds a duration to a phase of the timings of this thread
+pub(crate) fn record(add: impl FnOnce(&mut PhaseTimings)) {
+    TIMINGS.with(|timings| {
+        let mut current = timings.get();
+        add(&mut current);
+        timings.set(current);
+    });
+}
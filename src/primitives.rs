@@ -13,125 +13,2684 @@ This scheme is applied for all variables used in the calculus.
 
 [RFC2945]: https://datatracker.ietf.org/doc/html/rfc2945
 */
+use alloc::borrow::{Cow, ToOwned};
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::marker::PhantomData;
 use log::debug;
+use rand::{CryptoRng, RngCore};
 use serde::{Deserialize, Serialize};
+#[cfg(feature = "zeroize")]
+use zeroize::{Zeroize, ZeroizeOnDrop};
 
-use crate::big_number::{BigNumber, Zero};
-use crate::hash::{hash, Digest, Hash, HashFunc, Update, HASH_LENGTH};
+use crate::big_number::{
+    BigNumber, BigNumberError, BoundedBigNumber, ByteOrder, FixedBasePowTable, Zero,
+};
+use crate::compat::{CompatProfile, ProofScheme};
+use crate::hash::{chain_padded, hash, Digest, DomainSeparatedHasher, Hash, HashFunc, Sha256};
 #[cfg(feature = "norand")]
 use crate::protocol_details::testdata;
 use crate::{Result, Srp6Error};
 
-const STRONG_SESSION_KEY_LENGTH: usize = HASH_LENGTH * 2;
+/// length of the strong session key `K`, i.e. twice the hash output
+pub(crate) fn strong_session_key_length<D: Digest>() -> usize {
+    <D as Digest>::output_size() * 2
+}
+
+/// a value too long to be padded means that the key lengths do not match
+fn length_mismatch(e: BigNumberError) -> Srp6Error {
+    match e {
+        BigNumberError::TooLong { given, expected } => {
+            Srp6Error::KeyLengthMismatch { given, expected }
+        }
+        e => Srp6Error::Malformed(e),
+    }
+}
+
+/// big endian padding of `x` to `len` bytes, for hashing
+fn pad_vec(x: &BigNumber, len: usize) -> Result<Vec<u8>> {
+    x.try_to_vec_pad_zero(len).map_err(length_mismatch)
+}
+
+/// the salt `s` must fit in `len` bytes, like the other numbers of the handshake
+pub(crate) fn check_salt_length(len: usize, s: &Salt) -> Result<()> {
+    if s.num_bytes() > len {
+        return Err(Srp6Error::SaltLengthMismatch {
+            given: s.num_bytes(),
+            expected: len,
+        });
+    }
+    Ok(())
+}
+
+/// the group of `len` bytes must have at least [`MIN_KEY_LENGTH`] bytes, see
+/// [`Srp6Error::InsecureKeyLength`]
+pub(crate) fn check_key_length(len: usize) -> Result<()> {
+    check_key_length_with(len, ALLOW_INSECURE_KEY_LENGTHS)
+}
+
+fn check_key_length_with(len: usize, allow_insecure: bool) -> Result<()> {
+    if len < MIN_KEY_LENGTH && !allow_insecure {
+        return Err(Srp6Error::InsecureKeyLength {
+            bits: len * 8,
+            minimum: MIN_KEY_LENGTH * 8,
+        });
+    }
+    Ok(())
+}
+
+/// Refers to a large safe prime called `N` (`N = 2q+1`, where `q` is prime)
+#[doc(alias = "N")]
+pub type PrimeModulus = BigNumber;
+
+/// Refers to the modulus generator `g`
+#[doc(alias = "g")]
+pub type Generator = BigNumber;
+
+/// Refers to a User's salt called `s`
+#[doc(alias = "s")]
+pub type Salt = BigNumber;
+
+/// length in bytes of the generated [`Salt`], independent of the key length
+pub const DEFAULT_SALT_LENGTH: usize = 16;
+
+/// rounds of Miller-Rabin to check a custom group, see [`OpenConstants::try_new`]
+pub const MILLER_RABIN_ROUNDS: usize = 40;
+
+/// the smallest group of the handshakes in bytes, 2048 bits, unless the
+/// `insecure-key-lengths` feature is enabled
+pub const MIN_KEY_LENGTH: usize = 256;
+
+/// whether the groups below [`MIN_KEY_LENGTH`] are accepted, e.g. the 1024-bit
+/// group of the RFC 5054 test vectors
+const ALLOW_INSECURE_KEY_LENGTHS: bool = cfg!(any(test, fuzzing, feature = "insecure-key-lengths"));
+
+/// Refers to a Public shared key called A (user), B (server)
+#[doc(alias("A", "B"))]
+pub type PublicKey = BigNumber;
+
+/// Refers to a private secret random number a (user), b (server)
+#[doc(alias("a", "b"))]
+pub type PrivateKey = BigNumber;
+
+/// Password Verifier is the users secret on the server side
+#[doc(alias = "v")]
+pub type PasswordVerifier = BigNumber;
+
+/// Refers to a multiplier parameter `k` (k = H(N, g) in SRP-6a, k = 3 for legacy SRP-6)
+#[doc(alias = "k")]
+pub type MultiplierParameter = BigNumber;
+
+/// Refers to the SessionKey `S`
+#[doc(alias = "S")]
+pub type SessionKey = BigNumber;
+/// Refers to the StrongSessionKey `K`, the hash of `S` of twice the output
+/// size of the hash function
+///
+/// Its canonical bytes are those of [`StrongSessionKey::to_bytes`]: big endian,
+/// padded to [`StrongSessionKey::key_length`]. They are the same on both sides
+/// of a handshake, so are the keys of [`StrongSessionKey::split_keys`] and
+/// [`StrongSessionKey::derive_key`].
+///
+/// It serializes as a number, as the other values of the handshake.
+#[doc(alias = "K")]
+#[allow(non_snake_case)]
+#[derive(Clone, Default)]
+pub struct StrongSessionKey {
+    K: BigNumber,
+    key_length: usize,
+}
+
+/// length of the keys of [`StrongSessionKey::split_keys`]
+pub const SPLIT_KEY_LENGTH: usize = 32;
+
+/// the encryption key of [`StrongSessionKey::split_keys`]
+pub type EncKey = [u8; SPLIT_KEY_LENGTH];
+/// the MAC key of [`StrongSessionKey::split_keys`], the key of the tags of
+/// [`AuthenticatedSession`](crate::session::AuthenticatedSession)
+pub type MacKey = [u8; SPLIT_KEY_LENGTH];
+
+/// the HKDF `info` of the [`EncKey`]
+pub const ENC_KEY_INFO: &[u8] = b"srp6 enc";
+/// the HKDF `info` of the [`MacKey`]
+pub const MAC_KEY_INFO: &[u8] = b"srp6 mac";
+
+#[allow(non_snake_case)]
+impl StrongSessionKey {
+    /// `K` of the hash function `D`
+    pub(crate) fn new<D: Digest>(K: BigNumber) -> Self {
+        Self::with_length(K, strong_session_key_length::<D>())
+    }
+
+    pub(crate) fn with_length(K: BigNumber, key_length: usize) -> Self {
+        Self { K, key_length }
+    }
+
+    /// `K` as a number, as hashed into the proofs
+    pub(crate) fn number(&self) -> &BigNumber {
+        &self.K
+    }
+
+    /// the length of the canonical bytes, twice the output size of the hash
+    /// function, e.g. 40 bytes for SHA-1
+    pub fn key_length(&self) -> usize {
+        self.key_length
+    }
+
+    /// the canonical bytes of `K`: big endian, padded with zeros to
+    /// [`StrongSessionKey::key_length`]
+    ///
+    /// ```
+    /// use chadehoc_srp6::*;
+    ///
+    /// let K = strong_session_key_from_bytes::<Sha1>(&[0x42; 40], ByteOrder::LittleEndian).unwrap();
+    /// assert_eq!(K.to_bytes(), [0x42; 40]);
+    /// ```
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.K.to_vec_pad_zero(self.key_length)
+    }
+
+    /// HKDF-SHA256 over the canonical bytes (no salt), expanded to `out_len`
+    /// bytes for the given `info`
+    ///
+    /// **Panics** if `out_len` is greater than `255 * 32`
+    pub fn derive_key(&self, info: &[u8], out_len: usize) -> Vec<u8> {
+        let mut okm = vec![0_u8; out_len];
+        hkdf::Hkdf::<Sha256>::new(None, &self.to_bytes())
+            .expand(info, &mut okm)
+            .expect("HKDF-SHA256 output is limited to 255 * 32 bytes");
+        okm
+    }
+
+    /// an encryption key and a MAC key, independent of each other, as derived
+    /// by [`StrongSessionKey::derive_key`] with [`ENC_KEY_INFO`] and [`MAC_KEY_INFO`]
+    pub fn split_keys(&self) -> (EncKey, MacKey) {
+        let mut enc_key = EncKey::default();
+        enc_key.copy_from_slice(&self.derive_key(ENC_KEY_INFO, SPLIT_KEY_LENGTH));
+        let mut mac_key = MacKey::default();
+        mac_key.copy_from_slice(&self.derive_key(MAC_KEY_INFO, SPLIT_KEY_LENGTH));
+        (enc_key, mac_key)
+    }
+
+    /// compares in constant time, see `BigNumber::constant_time_eq`
+    pub fn constant_time_eq(&self, other: &Self) -> bool {
+        self.key_length == other.key_length && self.K.constant_time_eq(&other.K)
+    }
+
+    /// the short hex digits of `K` and its length, as in the errors, see
+    /// `BigNumber::fingerprint`
+    pub fn fingerprint(&self) -> String {
+        self.K.fingerprint()
+    }
+
+    /// deserializes a number, as serialized by `Serialize`, as the `K` of the
+    /// hash function `D`
+    pub(crate) fn deserialize_for<'de, De, D>(
+        deserializer: De,
+    ) -> core::result::Result<Self, De::Error>
+    where
+        De: serde::Deserializer<'de>,
+        D: Digest,
+    {
+        let K = BigNumber::deserialize(deserializer)?;
+        let key_length = strong_session_key_length::<D>();
+        if K.num_bytes() > key_length {
+            return Err(serde::de::Error::custom(Srp6Error::KeyLengthMismatch {
+                given: K.num_bytes(),
+                expected: key_length,
+            }));
+        }
+        Ok(Self::with_length(K, key_length))
+    }
+}
+
+/// compared in constant time
+impl PartialEq for StrongSessionKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.constant_time_eq(other)
+    }
+}
+
+impl Eq for StrongSessionKey {}
+
+/// redacted, as the other secrets
+impl fmt::Debug for StrongSessionKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Secret::sized("StrongSessionKey", &self.K, self.key_length).fmt(f)
+    }
+}
+
+/// the canonical bytes in uppercase hex
+impl fmt::Display for StrongSessionKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(&hex::encode_upper(self.to_bytes()))
+    }
+}
+
+/// the number `K`, see [`StrongSessionKey::to_bytes`] for the bytes
+impl AsRef<BigNumber> for StrongSessionKey {
+    fn as_ref(&self) -> &BigNumber {
+        &self.K
+    }
+}
+
+impl Serialize for StrongSessionKey {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> core::result::Result<S::Ok, S::Error> {
+        self.K.serialize(serializer)
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Zeroize for StrongSessionKey {
+    fn zeroize(&mut self) {
+        self.K.zeroize();
+    }
+}
+
+/// The shared secret of a successful handshake, as returned by both sides
+///
+/// Applications should use the strong session key `K` (not `S`), e.g. through
+/// [`SessionSecret::derive_key`] to get an encryption key.
+#[allow(non_snake_case)]
+#[derive(Clone)]
+pub struct SessionSecret {
+    S: SessionKey,
+    K: StrongSessionKey,
+}
+
+impl SessionSecret {
+    #[allow(non_snake_case)]
+    pub(crate) fn new(S: SessionKey, K: StrongSessionKey) -> Self {
+        Self { S, K }
+    }
+
+    /// the session key `S`
+    #[deprecated(
+        note = "use `strong_session_key`, or `into_raw_session_key` if `S` is really needed"
+    )]
+    pub fn session_key(&self) -> &SessionKey {
+        &self.S
+    }
+
+    /// the raw session key `S`, for the protocols that use it instead of `K` (e.g. WoW)
+    pub fn into_raw_session_key(mut self) -> SessionKey {
+        core::mem::take(&mut self.S)
+    }
+
+    /// the strong session key `K`
+    pub fn strong_session_key(&self) -> &StrongSessionKey {
+        &self.K
+    }
+
+    /// the strong session key `K` as big endian bytes, padded to `N`
+    ///
+    /// **Panics** if `K` does not fit in `N` bytes, i.e. twice the hash output
+    pub fn as_padded_bytes<const N: usize>(&self) -> [u8; N] {
+        self.K.number().to_array_pad_zero::<N>()
+    }
+
+    /// same as [`StrongSessionKey::derive_key`] on `K`
+    ///
+    /// **Panics** if `out_len` is greater than `255 * 32`
+    pub fn derive_key(&self, info: &[u8], out_len: usize) -> Vec<u8> {
+        self.K.derive_key(info, out_len)
+    }
+}
+
+/// constant time comparison of `K`
+impl PartialEq for SessionSecret {
+    fn eq(&self, other: &Self) -> bool {
+        self.K.constant_time_eq(&other.K) && self.S.constant_time_eq(&other.S)
+    }
+}
+
+/// A secret in a `Debug` output, e.g. `PrivateKey(<redacted, 256 bytes>)`
+///
+/// printed as is with the `insecure-debug-logging` feature, for protocol debugging
+pub(crate) struct Secret<'a> {
+    name: &'static str,
+    value: &'a dyn fmt::Debug,
+    len: Option<usize>,
+}
+
+impl<'a> Secret<'a> {
+    pub(crate) fn new(name: &'static str, value: &'a dyn fmt::Debug) -> Self {
+        Self {
+            name,
+            value,
+            len: None,
+        }
+    }
+
+    /// with the length of the group, for the numbers padded to it
+    pub(crate) fn sized(name: &'static str, value: &'a dyn fmt::Debug, len: usize) -> Self {
+        Self {
+            name,
+            value,
+            len: Some(len),
+        }
+    }
+}
+
+impl fmt::Debug for Secret<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if cfg!(feature = "insecure-debug-logging") {
+            return self.value.fmt(f);
+        }
+        match self.len {
+            Some(len) => write!(f, "{}(<redacted, {len} bytes>)", self.name),
+            None => write!(f, "{}(<redacted>)", self.name),
+        }
+    }
+}
+
+/// the keys are not printed
+impl fmt::Debug for SessionSecret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SessionSecret(..)")
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Zeroize for SessionSecret {
+    fn zeroize(&mut self) {
+        self.S.zeroize();
+        self.K.zeroize();
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for SessionSecret {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl ZeroizeOnDrop for SessionSecret {}
+
+/// The result of the host's check of the user's proof, returned by
+/// [`Srp6WaitingForProof::verify_proof`](crate::Srp6WaitingForProof::verify_proof)
+///
+/// the strong proof `M2` is sent to the user, the [`SessionSecret`] is kept.
+/// With the `outcome-serde` feature it is serialized as `M2`, `K` and the time
+/// it was established, e.g. for a session store: `S` is left out.
+#[derive(Debug, Clone)]
+pub struct HostHandshakeOutcome {
+    server_proof: StrongProof,
+    session: SessionSecret,
+    #[cfg(feature = "std")]
+    established_at: std::time::SystemTime,
+}
+
+impl HostHandshakeOutcome {
+    pub(crate) fn new(server_proof: StrongProof, session: SessionSecret) -> Self {
+        Self {
+            server_proof,
+            session,
+            #[cfg(feature = "std")]
+            established_at: std::time::SystemTime::now(),
+        }
+    }
+
+    /// the strong proof `M2`, to send to the user
+    pub fn server_proof(&self) -> &StrongProof {
+        &self.server_proof
+    }
+
+    /// the shared secret
+    pub fn session_secret(&self) -> &SessionSecret {
+        &self.session
+    }
+
+    /// the strong session key `K`
+    pub fn strong_session_key(&self) -> &StrongSessionKey {
+        self.session.strong_session_key()
+    }
+
+    /// when the user's proof was verified
+    #[cfg(feature = "std")]
+    pub fn established_at(&self) -> std::time::SystemTime {
+        self.established_at
+    }
+
+    /// the shared secret, once `M2` is sent
+    pub fn into_session_secret(self) -> SessionSecret {
+        self.session
+    }
+}
+
+/// The result of the user's check of the host's strong proof, returned by
+/// [`Srp6UserWaitingForServerProof::verify_proof`](crate::Srp6UserWaitingForServerProof::verify_proof)
+///
+/// With the `outcome-serde` feature it is serialized as `K` and the time it
+/// was established: `S` is left out.
+#[derive(Debug, Clone)]
+pub struct ClientHandshakeOutcome {
+    session: SessionSecret,
+    #[cfg(feature = "std")]
+    established_at: std::time::SystemTime,
+}
+
+impl ClientHandshakeOutcome {
+    pub(crate) fn new(session: SessionSecret) -> Self {
+        Self {
+            session,
+            #[cfg(feature = "std")]
+            established_at: std::time::SystemTime::now(),
+        }
+    }
+
+    /// the shared secret
+    pub fn session_secret(&self) -> &SessionSecret {
+        &self.session
+    }
+
+    /// the strong session key `K`
+    pub fn strong_session_key(&self) -> &StrongSessionKey {
+        self.session.strong_session_key()
+    }
+
+    /// when the host's proof was verified
+    #[cfg(feature = "std")]
+    pub fn established_at(&self) -> std::time::SystemTime {
+        self.established_at
+    }
+
+    /// the shared secret
+    pub fn into_session_secret(self) -> SessionSecret {
+        self.session
+    }
+}
+
+/// the serialized fields of [`HostHandshakeOutcome`] and [`ClientHandshakeOutcome`]
+#[cfg(feature = "outcome-serde")]
+#[derive(Serialize)]
+struct HandshakeOutcomeRef<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    server_proof: Option<&'a StrongProof>,
+    session_key: &'a StrongSessionKey,
+    established_at: std::time::SystemTime,
+}
+
+#[cfg(feature = "outcome-serde")]
+impl Serialize for HostHandshakeOutcome {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> core::result::Result<S::Ok, S::Error> {
+        HandshakeOutcomeRef {
+            server_proof: Some(&self.server_proof),
+            session_key: self.strong_session_key(),
+            established_at: self.established_at,
+        }
+        .serialize(serializer)
+    }
+}
+
+#[cfg(feature = "outcome-serde")]
+impl Serialize for ClientHandshakeOutcome {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> core::result::Result<S::Ok, S::Error> {
+        HandshakeOutcomeRef {
+            server_proof: None,
+            session_key: self.strong_session_key(),
+            established_at: self.established_at,
+        }
+        .serialize(serializer)
+    }
+}
+
+/// The user's side of an authenticated session, returned by
+/// [`Srp6UserWaitingForServerProof::verify_session`](crate::Srp6UserWaitingForServerProof::verify_session)
+///
+/// It can be stored to resume the session later, without a new handshake:
+/// each side proves that it still has `K` with [`ClientSession::authenticate_nonce`]
+/// and [`authenticate_nonce`] over a nonce chosen by the other side.
+///
+/// **Note**: it contains the strong session key, store it accordingly.
+/// The salt can be set to `None` if it should not be stored.
+///
+/// `D` is the hash function of the handshake, SHA-1 by default
+#[allow(non_snake_case)]
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ClientSession<D: Digest = HashFunc> {
+    pub username: Username,
+    pub salt: Option<Salt>,
+    /// the server's strong proof `M2`
+    pub strong_proof: StrongProof,
+    #[serde(deserialize_with = "StrongSessionKey::deserialize_for::<_, D>")]
+    K: StrongSessionKey,
+    #[serde(skip)]
+    d: PhantomData<D>,
+}
+
+impl<D: Digest> ClientSession<D> {
+    #[allow(non_snake_case)]
+    pub(crate) fn new(
+        username: Username,
+        salt: Salt,
+        strong_proof: StrongProof,
+        K: StrongSessionKey,
+    ) -> Self {
+        Self {
+            username,
+            salt: Some(salt),
+            strong_proof,
+            K,
+            d: PhantomData,
+        }
+    }
+
+    /// the strong session key `K`
+    pub fn strong_session_key(&self) -> &StrongSessionKey {
+        &self.K
+    }
+
+    /// the salt of the user, to be stored by the client after its first login
+    /// and given to [`Srp6User::update_handshake_pinned`](crate::Srp6User::update_handshake_pinned)
+    /// at the next ones
+    pub fn salt_to_pin(&self) -> Option<&Salt> {
+        self.salt.as_ref()
+    }
+
+    /// proves the knowledge of `K` for a `nonce` given by the server,
+    /// see [`authenticate_nonce`]
+    pub fn authenticate_nonce(&self, nonce: &[u8]) -> Result<Proof> {
+        authenticate_nonce::<D>(&self.K, nonce)
+    }
+}
+
+/// the key is redacted
+impl<D: Digest> fmt::Debug for ClientSession<D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ClientSession")
+            .field("username", &self.username)
+            .field("salt", &self.salt)
+            .field("K", &Secret::new("StrongSessionKey", &self.K))
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl<D: Digest> Zeroize for ClientSession<D> {
+    fn zeroize(&mut self) {
+        self.strong_proof.zeroize();
+        self.K.zeroize();
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl<D: Digest> Drop for ClientSession<D> {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl<D: Digest> ZeroizeOnDrop for ClientSession<D> {}
+
+/// proves the knowledge of the strong session key `K` for a `nonce`, on either
+/// side of a stored session
+///
+/// the server gives it the `K` of its [`SessionSecret`], the user calls
+/// [`ClientSession::authenticate_nonce`]. The proofs are compared in constant
+/// time, with `Proof::constant_time_eq`.
+///
+/// formula: `H(K | nonce)`, with `K` padded to twice the hash output
+#[allow(non_snake_case)]
+pub fn authenticate_nonce<D: Digest>(K: &StrongSessionKey, nonce: &[u8]) -> Result<Proof> {
+    let mut hasher = D::new();
+    chain_padded(&mut hasher, K.number(), strong_session_key_length::<D>())
+        .map_err(length_mismatch)?;
+    hasher.update(nonce);
+    Ok(Proof::from_digest(hasher))
+}
+
+/// output length of the default hash `HashFunc`, SHA-1, in bytes: the length of
+/// a [`Proof`] or a [`StrongProof`] converted from a slice
+pub const HASH_LENGTH: usize = 20;
+
+/// a proof of the handshake, a hash as a number, see [`Proof`] and [`StrongProof`]
+///
+/// it derefs to [`BigNumber`], and serializes, prints and compares as it.
+macro_rules! proof_type {
+    ($(#[$attr:meta])* $name:ident) => {
+        $(#[$attr])*
+        #[derive(Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+        #[serde(transparent)]
+        pub struct $name(BigNumber);
+
+        impl $name {
+            /// `raw` is expected to be big endian
+            pub fn from_bytes_be(raw: &[u8]) -> Self {
+                Self(BigNumber::from_bytes_be(raw))
+            }
+
+            /// `raw` is expected to be little endian
+            pub fn from_bytes_le(raw: &[u8]) -> Self {
+                Self(BigNumber::from_bytes_le(raw))
+            }
+
+            /// from `raw` in the given byte order, of any length
+            pub fn from_bytes(raw: &[u8], order: ByteOrder) -> Self {
+                Self(BigNumber::from_bytes(raw, order))
+            }
+
+            /// from the big endian bytes of the default hash, e.g. of a peer that
+            /// frames them, see [`HASH_LENGTH`] and [`TryFrom<&[u8]>`] for a slice
+            pub fn from_proof_bytes_be(bytes: &[u8; HASH_LENGTH]) -> Self {
+                Self::from_bytes_be(bytes)
+            }
+
+            /// same as [`Self::from_proof_bytes_be`], in little endian
+            pub fn from_proof_bytes_le(bytes: &[u8; HASH_LENGTH]) -> Self {
+                Self::from_bytes_le(bytes)
+            }
+
+            /// from big endian hex digits
+            pub fn from_hex_str_be(str: &str) -> core::result::Result<Self, BigNumberError> {
+                BigNumber::from_hex_str_be(str).map(Self)
+            }
+
+            /// the output of `hasher`, big endian
+            pub fn from_digest<D: Digest>(hasher: D) -> Self {
+                Self(BigNumber::from_digest(hasher))
+            }
+
+            /// a random one of `n_bytes`, e.g. for a wrong proof in the tests
+            pub fn new_rand_with<R: RngCore + CryptoRng + ?Sized>(
+                rng: &mut R,
+                n_bytes: usize,
+            ) -> Self {
+                Self(BigNumber::new_rand_with(rng, n_bytes))
+            }
+
+            /// the proof as a number
+            pub fn number(&self) -> &BigNumber {
+                &self.0
+            }
+        }
+
+        impl core::ops::Deref for $name {
+            type Target = BigNumber;
+
+            fn deref(&self) -> &BigNumber {
+                &self.0
+            }
+        }
+
+        impl From<BigNumber> for $name {
+            fn from(number: BigNumber) -> Self {
+                Self(number)
+            }
+        }
+
+        impl From<$name> for BigNumber {
+            fn from(proof: $name) -> Self {
+                proof.0
+            }
+        }
+
+        impl From<u32> for $name {
+            fn from(n: u32) -> Self {
+                Self(BigNumber::from(n))
+            }
+        }
+
+        /// exactly [`HASH_LENGTH`] big endian bytes, see [`proof_from_bytes`] for
+        /// another hash
+        ///
+        /// fails with [`Srp6Error::KeyLengthMismatch`] for any other length
+        impl TryFrom<&[u8]> for $name {
+            type Error = Srp6Error;
+
+            fn try_from(bytes: &[u8]) -> Result<Self> {
+                exact_from_bytes(bytes, HASH_LENGTH, ByteOrder::BigEndian).map(Self)
+            }
+        }
+
+        impl AsRef<$name> for $name {
+            fn as_ref(&self) -> &$name {
+                self
+            }
+        }
+
+        impl fmt::Debug for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                fmt::Debug::fmt(&self.0, f)
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                fmt::Display::fmt(&self.0, f)
+            }
+        }
+
+        impl fmt::UpperHex for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                fmt::UpperHex::fmt(&self.0, f)
+            }
+        }
+
+        impl fmt::LowerHex for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                fmt::LowerHex::fmt(&self.0, f)
+            }
+        }
+
+        #[cfg(feature = "zeroize")]
+        impl Zeroize for $name {
+            fn zeroize(&mut self) {
+                self.0.zeroize();
+            }
+        }
+    };
+}
+
+proof_type!(
+    /// Refers to `M` and `M1` Proof of server and client
+    #[doc(alias("M", "M1"))]
+    Proof
+);
+proof_type!(
+    /// Refers to `M2` the hash of Proof
+    #[doc(alias = "M2")]
+    StrongProof
+);
+
+/// Username `I`, a [`String`] that cannot be given in place of a [`Password`]
+///
+/// it derefs to [`UsernameRef`], and serializes as a string.
+#[doc(alias = "I")]
+#[derive(Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Username(String);
+/// Username reference `I` as [`&str`]
+pub type UsernameRef<'a> = &'a str;
+/// Clear text password `p` as [`str`], see [`Password`]
+#[doc(alias = "p")]
+pub type ClearTextPassword = str;
+
+impl Username {
+    pub fn as_str(&self) -> UsernameRef<'_> {
+        &self.0
+    }
+
+    pub fn into_string(self) -> String {
+        self.0
+    }
+}
+
+impl From<&str> for Username {
+    fn from(username: &str) -> Self {
+        Self(username.to_owned())
+    }
+}
+
+impl From<String> for Username {
+    fn from(username: String) -> Self {
+        Self(username)
+    }
+}
+
+impl From<Cow<'_, str>> for Username {
+    fn from(username: Cow<'_, str>) -> Self {
+        Self(username.into_owned())
+    }
+}
+
+impl From<Username> for String {
+    fn from(username: Username) -> Self {
+        username.0
+    }
+}
+
+impl core::ops::Deref for Username {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for Username {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl core::borrow::Borrow<str> for Username {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl PartialEq<str> for Username {
+    fn eq(&self, other: &str) -> bool {
+        self.0 == other
+    }
+}
+
+impl PartialEq<&str> for Username {
+    fn eq(&self, other: &&str) -> bool {
+        self.0 == *other
+    }
+}
+
+impl PartialEq<Username> for str {
+    fn eq(&self, other: &Username) -> bool {
+        self == other.0
+    }
+}
+
+impl PartialEq<Username> for &str {
+    fn eq(&self, other: &Username) -> bool {
+        *self == other.0
+    }
+}
+
+/// as the [`String`], e.g. `"alice"`
+impl fmt::Debug for Username {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl fmt::Display for Username {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Zeroize for Username {
+    fn zeroize(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+/// Clear text password `p`, redacted in `Debug` and zeroized on drop (feature
+/// `zeroize`)
+///
+/// it cannot be given in place of a [`Username`], and it is only read with
+/// [`Password::expose_secret`].
+///
+/// ```
+/// use chadehoc_srp6::Password;
+///
+/// let password = Password::from("hunter2");
+/// assert_eq!(password.expose_secret(), "hunter2");
+/// ```
+#[derive(Clone, Default)]
+pub struct Password(String);
+
+impl Password {
+    /// the clear text password, to be hashed
+    pub fn expose_secret(&self) -> &ClearTextPassword {
+        &self.0
+    }
+}
+
+impl From<&str> for Password {
+    fn from(password: &str) -> Self {
+        Self(password.to_owned())
+    }
+}
+
+impl From<String> for Password {
+    fn from(password: String) -> Self {
+        Self(password)
+    }
+}
+
+impl fmt::Debug for Password {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Secret::new("Password", &self.0).fmt(f)
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Zeroize for Password {
+    fn zeroize(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for Password {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl ZeroizeOnDrop for Password {}
+
+/// Opaque random id of one handshake on the server side, see [`ServerHandshake::session_id`]
+pub type SessionId = [u8; 16];
+
+/// [`Username`] and [`Password`] used on the client side
+#[derive(Clone)]
+pub struct UserCredentials<'a> {
+    pub username: &'a Username,
+    pub password: &'a Password,
+}
+
+/// the password is redacted
+impl fmt::Debug for UserCredentials<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UserCredentials")
+            .field("username", &self.username)
+            .field("password", &self.password)
+            .finish()
+    }
+}
+
+/// The rules for the username and the password of a new user
+///
+/// the default only rejects an empty username or password. The lengths bound
+/// the hashing input, e.g. for the passwords sent to a signup endpoint.
+///
+/// ```
+/// use chadehoc_srp6::*;
+///
+/// let policy = CredentialPolicy {
+///     min_password_length: 8,
+///     username_chars: Some(|c| c.is_ascii_alphanumeric()),
+///     ..CredentialPolicy::default()
+/// };
+/// assert_eq!(policy.check("alice", "password123"), Ok(()));
+/// assert_eq!(
+///     policy.check("alice", "hunter2"),
+///     Err(Srp6Error::PasswordTooShort { length: 7, minimum: 8 })
+/// );
+/// assert_eq!(policy.check("alice!", "password123"), Err(Srp6Error::InvalidUsername));
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct CredentialPolicy {
+    /// the minimum number of characters of the password, at least 1
+    pub min_password_length: usize,
+    /// the maximum number of bytes of the password
+    pub max_password_length: usize,
+    /// the maximum number of bytes of the username
+    pub max_username_length: usize,
+    /// the characters allowed in the username, all of them if `None`
+    pub username_chars: Option<fn(char) -> bool>,
+}
+
+impl Default for CredentialPolicy {
+    fn default() -> Self {
+        Self {
+            min_password_length: 1,
+            max_password_length: usize::MAX,
+            max_username_length: usize::MAX,
+            username_chars: None,
+        }
+    }
+}
+
+impl CredentialPolicy {
+    /// checks the username `I` and the password `p` against the policy, an
+    /// empty one is always rejected
+    #[allow(non_snake_case)]
+    pub fn check(&self, I: UsernameRef, p: &ClearTextPassword) -> Result<()> {
+        check_credentials(I, p)?;
+        if I.len() > self.max_username_length {
+            return Err(Srp6Error::UsernameTooLong {
+                length: I.len(),
+                maximum: self.max_username_length,
+            });
+        }
+        if let Some(allowed) = self.username_chars {
+            if !I.chars().all(allowed) {
+                return Err(Srp6Error::InvalidUsername);
+            }
+        }
+        if p.len() > self.max_password_length {
+            return Err(Srp6Error::PasswordTooLong {
+                length: p.len(),
+                maximum: self.max_password_length,
+            });
+        }
+        let length = p.chars().count();
+        if length < self.min_password_length {
+            return Err(Srp6Error::PasswordTooShort {
+                length,
+                minimum: self.min_password_length,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Parameters of the key derivation function used to stretch the private key `x`
+///
+/// This is an opt-in hardening against offline dictionary attacks on leaked
+/// verifiers, it is not part of RFC 5054.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum KdfParams {
+    /// PBKDF2-HMAC-SHA256 with the given number of iterations
+    Pbkdf2 { iterations: u32 },
+    /// Argon2id with the given memory (in KiB), time and parallelism costs
+    #[cfg(feature = "argon2")]
+    Argon2id {
+        m_cost: u32,
+        t_cost: u32,
+        p_cost: u32,
+    },
+}
+
+/// The identity `I` hashed into the private key `x` and the proof `M`
+///
+/// With [`IdentityMode::Username`], renaming a user breaks the verifier. The
+/// other modes keep the username out of the hashes, so that it can change.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum IdentityMode {
+    /// the username, as in RFC 5054
+    #[default]
+    Username,
+    /// an empty identity, RFC 2945 allows to omit `I` from `x`
+    Blank,
+    /// a stable user id chosen by the application, e.g. an account number
+    OpaqueId(String),
+}
+
+impl IdentityMode {
+    /// the identity `I` of the user with the given username
+    pub fn identity<'a>(&'a self, username: UsernameRef<'a>) -> UsernameRef<'a> {
+        match self {
+            IdentityMode::Username => username,
+            IdentityMode::Blank => "",
+            IdentityMode::OpaqueId(id) => id,
+        }
+    }
+}
+
+/// the version of the serialized [`UserDetails`] written by this release
+pub const USER_DETAILS_VERSION: u8 = 2;
+
+/// User details composes [`Username`], [`Salt`] and [`PasswordVerifier`] in one struct
+///
+/// `kdf` is set when the verifier was created from a stretched private key `x`,
+/// the same [`KdfParams`] must then be given to the user for the handshake.
+/// Likewise for `identity`, see [`Srp6User::with_identity`](crate::Srp6User::with_identity).
+///
+/// When stored with serde, the fields are written in the order of declaration,
+/// with a `version`. A row without it is read as version 1, the format before
+/// `version` and `group_fingerprint`; a version above [`USER_DETAILS_VERSION`]
+/// is rejected with [`Srp6Error::UnsupportedVersion`], so that an older release
+/// does not ignore the fields it does not know. [`UserDetails::migrate`] brings
+/// a row to the current version, to store it back. The binary form of
+/// [`UserDetails::to_bytes`] has neither field.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UserDetails {
+    pub username: Username,
+    pub salt: Salt,
+    pub verifier: PasswordVerifier,
+    /// missing in rows stored by former versions (self describing formats only)
+    #[serde(default)]
+    pub kdf: Option<KdfParams>,
+    /// missing in rows stored by former versions (self describing formats only)
+    #[serde(default)]
+    pub identity: IdentityMode,
+    /// whether the username and the password were NFC normalized before they
+    /// were hashed (feature `normalization`), the user must then normalize them too
+    ///
+    /// missing in rows stored by former versions (self describing formats only)
+    #[serde(default)]
+    pub normalized: bool,
+    /// the version of the stored format, see [`USER_DETAILS_VERSION`]
+    #[serde(
+        default = "user_details_version_1",
+        deserialize_with = "deserialize_user_details_version"
+    )]
+    pub version: u8,
+    /// the [`OpenConstants::fingerprint`] of the group of the verifier, `None`
+    /// if it is unknown, e.g. in the rows of version 1
+    #[serde(default)]
+    pub group_fingerprint: Option<[u8; 32]>,
+}
+
+/// the version of the rows stored without one
+fn user_details_version_1() -> u8 {
+    1
+}
+
+/// rejects the versions of [`UserDetails`] written by a later release
+fn deserialize_user_details_version<'de, De: serde::Deserializer<'de>>(
+    deserializer: De,
+) -> core::result::Result<u8, De::Error> {
+    let version = u8::deserialize(deserializer)?;
+    if version == 0 || version > USER_DETAILS_VERSION {
+        return Err(serde::de::Error::custom(Srp6Error::UnsupportedVersion {
+            given: version,
+            supported: USER_DETAILS_VERSION,
+        }));
+    }
+    Ok(version)
+}
+
+#[cfg(feature = "zeroize")]
+impl Zeroize for UserDetails {
+    fn zeroize(&mut self) {
+        self.username.zeroize();
+        self.salt.zeroize();
+        self.verifier.zeroize();
+        self.kdf = None;
+        if let IdentityMode::OpaqueId(id) = &mut self.identity {
+            id.zeroize();
+        }
+        self.identity = IdentityMode::default();
+        self.normalized = false;
+        self.group_fingerprint = None;
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for UserDetails {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl ZeroizeOnDrop for UserDetails {}
+
+impl UserDetails {
+    /// checks that the stored details are usable with the given constants:
+    /// a non empty username, a non zero salt and a verifier `0 < v < N`
+    ///
+    /// a recorded `group_fingerprint` of another group fails with
+    /// [`Srp6Error::InvalidVerifier`]
+    pub fn validate<const LEN: usize>(&self, constants: &OpenConstants<LEN>) -> Result<()> {
+        self.validate_for(LEN, &constants.module)?;
+        match self.group_fingerprint {
+            Some(fingerprint) if fingerprint != constants.fingerprint() => {
+                Err(Srp6Error::InvalidVerifier)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// the details in the current [`USER_DETAILS_VERSION`], to store them back
+    ///
+    /// from version 1, only `version` changes: the group of the verifier is
+    /// unknown, `group_fingerprint` is left to the application, see
+    /// [`OpenConstants::fingerprint`].
+    pub fn migrate(mut self) -> Self {
+        if self.version < USER_DETAILS_VERSION {
+            self.version = USER_DETAILS_VERSION;
+        }
+        self
+    }
+
+    /// same as [`UserDetails::validate`], for a key length only known at runtime
+    #[allow(non_snake_case)]
+    pub(crate) fn validate_for(&self, len: usize, N: &PrimeModulus) -> Result<()> {
+        check_modulus(N)?;
+        if self.username.is_empty() {
+            return Err(Srp6Error::EmptyUsername);
+        }
+        if self.salt.is_zero() {
+            return Err(Srp6Error::InvalidSalt);
+        }
+        // e.g. imported from another system, or stored for a larger group
+        check_salt_length(len, &self.salt)?;
+        if self.verifier.num_bytes() > len {
+            // most probably stored for another group
+            return Err(Srp6Error::KeyLengthMismatch {
+                given: self.verifier.num_bytes(),
+                expected: len,
+            });
+        }
+        if self.verifier.is_zero() || &self.verifier >= N {
+            return Err(Srp6Error::InvalidVerifier);
+        }
+        Ok(())
+    }
+}
+
+/// `(username, salt, verifier)` with the salt and the verifier as big endian
+/// hex, as imported by [`UserDetails::from_parts`]
+///
+/// the group is not known here: the details are only checked for an empty
+/// username, a zero salt or a zero verifier. [`UserDetails::validate`] checks
+/// them against the group.
+///
+/// ```
+/// use chadehoc_srp6::*;
+///
+/// let details = UserDetails::try_from(("alice", "BEB25379", "7E273DE8")).unwrap();
+/// assert_eq!(details.salt, Salt::from(0xBEB25379));
+/// assert_eq!(
+///     UserDetails::try_from(("alice", "BEB25379", "not hex")),
+///     Err(Srp6Error::Malformed(BigNumberError::InvalidHexStr))
+/// );
+/// ```
+impl TryFrom<(&str, &str, &str)> for UserDetails {
+    type Error = Srp6Error;
+
+    fn try_from((username, salt, verifier): (&str, &str, &str)) -> Result<Self> {
+        let details = Self::from_raw_parts(
+            username,
+            RawNumber::Hex(salt, ByteOrder::BigEndian),
+            RawNumber::Hex(verifier, ByteOrder::BigEndian),
+        )?;
+        if details.username.is_empty() {
+            return Err(Srp6Error::EmptyUsername);
+        }
+        if details.salt.is_zero() {
+            return Err(Srp6Error::InvalidSalt);
+        }
+        if details.verifier.is_zero() {
+            return Err(Srp6Error::InvalidVerifier);
+        }
+        Ok(details)
+    }
+}
+
+/// A number read from an external store, for [`UserDetails::from_parts`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RawNumber<'a> {
+    /// hex digits, as read by `from_hex_str`, with the bytes in the given order
+    Hex(&'a str, ByteOrder),
+    /// raw bytes in the given order
+    Bytes(&'a [u8], ByteOrder),
+}
+
+impl RawNumber<'_> {
+    fn is_empty(&self) -> bool {
+        match self {
+            RawNumber::Hex(hex, _) => hex.trim().is_empty(),
+            RawNumber::Bytes(bytes, _) => bytes.is_empty(),
+        }
+    }
+
+    fn to_number(self) -> Result<BigNumber> {
+        Ok(match self {
+            RawNumber::Hex(hex, order) => BigNumber::from_hex_str(hex, order)?,
+            RawNumber::Bytes(bytes, order) => BigNumber::from_bytes(bytes, order),
+        })
+    }
+}
+
+impl UserDetails {
+    /// imports a salt and a verifier created by another SRP implementation,
+    /// e.g. when migrating its users
+    ///
+    /// the details are checked as by [`UserDetails::validate`], and the salt
+    /// must not be empty. The verifier is expected to be `g^x % N` with
+    /// `x = H(s | H(I | ":" | p))`, as calculated by this crate without stretching.
+    pub fn from_parts<const LEN: usize>(
+        username: UsernameRef,
+        salt: RawNumber,
+        verifier: RawNumber,
+        constants: &OpenConstants<LEN>,
+    ) -> Result<Self> {
+        let details = Self::from_raw_parts(username, salt, verifier)?;
+        details.validate(constants)?;
+        Ok(details)
+    }
+
+    /// the details of [`UserDetails::from_parts`], before they are validated
+    fn from_raw_parts(username: UsernameRef, salt: RawNumber, verifier: RawNumber) -> Result<Self> {
+        if salt.is_empty() {
+            return Err(Srp6Error::InvalidSalt);
+        }
+        Ok(Self {
+            username: username.into(),
+            salt: salt.to_number()?,
+            verifier: verifier.to_number()?,
+            kdf: None,
+            identity: IdentityMode::default(),
+            normalized: false,
+            version: USER_DETAILS_VERSION,
+            group_fingerprint: None,
+        })
+    }
+
+    /// the username, the salt and the verifier, e.g. to store them in columns
+    ///
+    /// ```
+    /// use chadehoc_srp6::*;
+    ///
+    /// let details = UserDetails::try_from(("alice", "BEB25379", "7E273DE8")).unwrap();
+    /// let (username, salt, verifier) = details.into_parts();
+    /// assert_eq!(username, "alice");
+    /// assert_eq!(salt, Salt::from(0xBEB25379));
+    /// assert_eq!(verifier, PasswordVerifier::from(0x7E273DE8));
+    /// ```
+    pub fn into_parts(mut self) -> (Username, Salt, PasswordVerifier) {
+        // moved out with `take`, the details may be wiped on drop
+        (
+            core::mem::take(&mut self.username),
+            core::mem::take(&mut self.salt),
+            core::mem::take(&mut self.verifier),
+        )
+    }
+
+    /// a short hash of the username, the salt and the verifier for audit logs,
+    /// 16 hex digits of SHA-256
+    ///
+    /// it does not depend on the hash function of the handshake and changes with
+    /// the password.
+    pub fn fingerprint(&self) -> String {
+        let mut hasher = Sha256::new();
+        for field in [
+            self.username.as_bytes(),
+            &self.salt.to_vec(),
+            &self.verifier.to_vec(),
+        ] {
+            hasher.update((field.len() as u32).to_be_bytes());
+            hasher.update(field);
+        }
+        hex::encode_upper(&hasher.finalize()[..8])
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UserHandshake {
+    pub username: Username,
+    pub user_publickey: PublicKey,
+    /// bits of the group of the user, checked by the server before any
+    /// calculation, see [`Srp6Error::GroupMismatch`]
+    ///
+    /// `0` if unknown, as in the messages of earlier versions and of other
+    /// implementations: only the lengths of the numbers are checked then. It
+    /// is not part of the hex, raw and binary forms.
+    #[serde(default)]
+    pub group_bits: u16,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ServerHandshake {
+    pub salt: Salt,
+    pub server_publickey: PublicKey,
+    /// id of the handshake on the server, so that it can correlate its messages
+    ///
+    /// it is not part of the hex, raw and binary forms, which only carry the
+    /// protocol values, and is all zeros when parsed from them
+    #[serde(default)]
+    pub session_id: SessionId,
+    /// bits of the group of the server, checked by the user before any
+    /// calculation, `0` if unknown, see [`UserHandshake::group_bits`]
+    #[serde(default)]
+    pub group_bits: u16,
+}
+
+/// the bits of a group of `len` bytes as in the messages, `0` (unknown) if
+/// they do not fit
+pub(crate) fn group_bits(len: usize) -> u16 {
+    u16::try_from(len * 8).unwrap_or_default()
+}
+
+/// fails with [`Srp6Error::GroupMismatch`] if both groups are known and differ
+pub(crate) fn check_group_bits(client_bits: u16, server_bits: u16) -> Result<()> {
+    if client_bits == 0 || server_bits == 0 || client_bits == server_bits {
+        Ok(())
+    } else {
+        Err(Srp6Error::GroupMismatch {
+            client_bits,
+            server_bits,
+        })
+    }
+}
+
+/// The user's public key `A` as given to the handshakes of the host: alone,
+/// or with the bits of the user's group in a [`UserHandshake`]
+///
+/// ```
+/// use chadehoc_srp6::*;
+///
+/// let constants = OpenConstants::<256>::default();
+/// let user_details =
+///     Srp6_2048::generate_new_user_secrets(&"alice".into(), &"secret".into(), &constants).unwrap();
+/// let mut handshake = Srp6user2048::default().start_handshake("alice", &constants).unwrap();
+/// handshake.group_bits = 4096;
+/// assert!(matches!(
+///     Srp6_2048::default().continue_handshake(&user_details, &handshake, &constants),
+///     Err(Srp6Error::GroupMismatch { client_bits: 4096, server_bits: 2048 })
+/// ));
+/// // `A` alone, the group is unknown
+/// assert!(Srp6_2048::default()
+///     .continue_handshake(&user_details, &handshake.user_publickey, &constants)
+///     .is_ok());
+/// ```
+pub trait UserPublicKey {
+    /// the public key `A`
+    fn user_publickey(&self) -> &PublicKey;
+
+    /// the bits of the user's group, `0` if unknown
+    fn group_bits(&self) -> u16 {
+        0
+    }
+}
+
+/// `A`, once the user's group is checked against the group of `len` bytes
+pub(crate) fn checked_user_publickey(
+    len: usize,
+    user_publickey: &impl UserPublicKey,
+) -> Result<&PublicKey> {
+    check_group_bits(user_publickey.group_bits(), group_bits(len))?;
+    check_key_length_in_group(
+        len,
+        user_publickey.group_bits(),
+        user_publickey.user_publickey(),
+    )?;
+    Ok(user_publickey.user_publickey())
+}
+
+/// for the messages without the bits of the group: fails with
+/// [`Srp6Error::KeyLengthMismatch`] if the public key of the other side has at
+/// most half the bytes of our group of `len` bytes, i.e. it most probably
+/// comes from a smaller group, e.g. a server of 2048 bits for a user of 4096 bits
+///
+/// `0` and `1` are left to [`check_public_key`], for its errors
+pub(crate) fn check_key_length_in_group(len: usize, bits: u16, key: &PublicKey) -> Result<()> {
+    if bits == 0 && key > &PublicKey::from(1) && key.num_bytes() <= len / 2 {
+        return Err(Srp6Error::KeyLengthMismatch {
+            given: key.num_bytes(),
+            expected: len,
+        });
+    }
+    Ok(())
+}
+
+impl UserPublicKey for PublicKey {
+    fn user_publickey(&self) -> &PublicKey {
+        self
+    }
+}
+
+impl UserPublicKey for UserHandshake {
+    fn user_publickey(&self) -> &PublicKey {
+        &self.user_publickey
+    }
+
+    fn group_bits(&self) -> u16 {
+        self.group_bits
+    }
+}
+
+/// A message whose numbers are bounded to `LEN` bytes while it is
+/// deserialized, see [`BoundedBigNumber`]
+///
+/// the plain `Deserialize` of [`UserHandshake`] and [`ServerHandshake`]
+/// accepts numbers of any size, and a peer could send huge ones; the
+/// handshakes reject them later, after they are allocated:
+///
+/// ```
+/// use chadehoc_srp6::*;
+///
+/// let json = r#"{"username": "Bob", "user_publickey": "02"}"#;
+/// let handshake = serde_json::from_str::<Bounded<UserHandshake, 256>>(json)
+///     .unwrap()
+///     .into_inner();
+/// assert_eq!(handshake.user_publickey, PublicKey::from(2));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(transparent)]
+pub struct Bounded<T, const LEN: usize>(pub T);
+
+impl<T, const LEN: usize> Bounded<T, LEN> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+/// the fields of [`UserHandshake`], with the bounded numbers
+#[derive(Deserialize)]
+#[serde(rename = "UserHandshake")]
+struct BoundedUserHandshake<const LEN: usize> {
+    username: Username,
+    user_publickey: BoundedBigNumber<LEN>,
+    #[serde(default)]
+    group_bits: u16,
+}
+
+impl<'de, const LEN: usize> Deserialize<'de> for Bounded<UserHandshake, LEN> {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> core::result::Result<Self, D::Error> {
+        let BoundedUserHandshake::<LEN> {
+            username,
+            user_publickey,
+            group_bits,
+        } = Deserialize::deserialize(deserializer)?;
+        Ok(Self(UserHandshake {
+            username,
+            user_publickey: user_publickey.into_inner(),
+            group_bits,
+        }))
+    }
+}
+
+/// the fields of [`ServerHandshake`], with the bounded numbers
+#[derive(Deserialize)]
+#[serde(rename = "ServerHandshake")]
+struct BoundedServerHandshake<const LEN: usize> {
+    salt: BoundedBigNumber<LEN>,
+    server_publickey: BoundedBigNumber<LEN>,
+    #[serde(default)]
+    session_id: SessionId,
+    #[serde(default)]
+    group_bits: u16,
+}
+
+impl<'de, const LEN: usize> Deserialize<'de> for Bounded<ServerHandshake, LEN> {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> core::result::Result<Self, D::Error> {
+        let BoundedServerHandshake::<LEN> {
+            salt,
+            server_publickey,
+            session_id,
+            group_bits,
+        } = Deserialize::deserialize(deserializer)?;
+        Ok(Self(ServerHandshake {
+            salt: salt.into_inner(),
+            server_publickey: server_publickey.into_inner(),
+            session_id,
+            group_bits,
+        }))
+    }
+}
+
+impl UserHandshake {
+    /// ```
+    /// use chadehoc_srp6::*;
+    ///
+    /// let handshake = UserHandshake::new("alice", PublicKey::from(2));
+    /// assert_eq!(handshake.to_hex(), "alice:02");
+    /// ```
+    pub fn new(username: impl Into<Username>, user_publickey: PublicKey) -> Self {
+        Self {
+            username: username.into(),
+            user_publickey,
+            group_bits: 0,
+        }
+    }
+
+    /// from a raw public key `A` in the given byte order
+    pub fn from_raw(username: UsernameRef, user_publickey: &[u8], order: ByteOrder) -> Self {
+        Self::new(username, PublicKey::from_bytes(user_publickey, order))
+    }
+
+    /// the raw public key `A` in the given byte order
+    pub fn to_raw(&self, order: ByteOrder) -> Vec<u8> {
+        self.user_publickey.to_bytes(order)
+    }
+}
+
+impl ServerHandshake {
+    /// the message of a server of another implementation, the session id is
+    /// all zeros and the group unknown
+    ///
+    /// ```
+    /// use chadehoc_srp6::*;
+    ///
+    /// let handshake = ServerHandshake::new(Salt::from(0xBEB25379), PublicKey::from(2));
+    /// assert_eq!(handshake.to_hex(), "BEB25379:02");
+    /// assert_eq!(handshake.session_id, SessionId::default());
+    /// ```
+    pub fn new(salt: Salt, server_publickey: PublicKey) -> Self {
+        Self {
+            salt,
+            server_publickey,
+            session_id: SessionId::default(),
+            group_bits: 0,
+        }
+    }
+
+    /// from a raw salt and public key `B` in the given byte order
+    pub fn from_raw(salt: &[u8], server_publickey: &[u8], order: ByteOrder) -> Self {
+        Self::new(
+            Salt::from_bytes(salt, order),
+            PublicKey::from_bytes(server_publickey, order),
+        )
+    }
+
+    /// the raw salt and public key `B` in the given byte order
+    pub fn to_raw(&self, order: ByteOrder) -> (Vec<u8>, Vec<u8>) {
+        (
+            self.salt.to_bytes(order),
+            self.server_publickey.to_bytes(order),
+        )
+    }
+}
+
+/// same as [`ServerHandshake::new`]
+///
+/// ```
+/// use chadehoc_srp6::*;
+///
+/// let handshake = ServerHandshake::from((Salt::from(0xBEB25379), PublicKey::from(2)));
+/// assert_eq!(handshake, ServerHandshake::new(Salt::from(0xBEB25379), PublicKey::from(2)));
+/// ```
+impl From<(Salt, PublicKey)> for ServerHandshake {
+    fn from((salt, server_publickey): (Salt, PublicKey)) -> Self {
+        Self::new(salt, server_publickey)
+    }
+}
+
+/// Wraps a [`Proof`] `M1` or a [`StrongProof`] `M2` for transport
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProofMessage {
+    pub proof: Proof,
+}
+
+impl ProofMessage {
+    /// the wrapped number as a [`StrongProof`] `M2`, for the user
+    pub fn strong_proof(&self) -> StrongProof {
+        StrongProof::from(BigNumber::from(self.proof.clone()))
+    }
+}
+
+impl From<Proof> for ProofMessage {
+    fn from(proof: Proof) -> Self {
+        Self { proof }
+    }
+}
+
+impl From<StrongProof> for ProofMessage {
+    fn from(proof: StrongProof) -> Self {
+        Self {
+            proof: Proof::from(BigNumber::from(proof)),
+        }
+    }
+}
+
+/// ```
+/// use chadehoc_srp6::*;
+///
+/// let message = ProofMessage::from(Proof::from(42));
+/// let proof: &Proof = message.as_ref();
+/// assert_eq!(proof, &Proof::from(42));
+/// ```
+impl AsRef<Proof> for ProofMessage {
+    fn as_ref(&self) -> &Proof {
+        &self.proof
+    }
+}
+
+/// delimiter of the fields in the hex string form of the messages
+const MESSAGE_DELIMITER: char = ':';
+
+/// parses a big endian hex field of at most `max_len` bytes, lower or upper case
+fn parse_hex_field(field: &str, max_len: usize) -> Result<BigNumber> {
+    if field.is_empty() {
+        return Err(Srp6Error::MalformedMessage);
+    }
+    // checked before parsing, so that leading zeros (padding) are accepted
+    let given = field.len().div_ceil(2);
+    if given > max_len {
+        return Err(Srp6Error::KeyLengthMismatch {
+            given,
+            expected: max_len,
+        });
+    }
+    BigNumber::from_hex_str_be(field).map_err(|_| Srp6Error::InvalidHex)
+}
+
+/// the hex string form is `username:A`
+impl fmt::Display for UserHandshake {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}{MESSAGE_DELIMITER}{}",
+            self.username, self.user_publickey
+        )
+    }
+}
+
+/// the hex string form is `salt:B`
+impl fmt::Display for ServerHandshake {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}{MESSAGE_DELIMITER}{}",
+            self.salt, self.server_publickey
+        )
+    }
+}
+
+/// the hex string form is just the proof
+impl fmt::Display for ProofMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.proof)
+    }
+}
+
+impl UserHandshake {
+    /// same as `to_string()`, `username:A` with `A` in hex
+    pub fn to_hex(&self) -> String {
+        self.to_string()
+    }
+
+    /// parses `username:A`, with `A` of at most `LEN` bytes
+    ///
+    /// the username may contain the delimiter, `A` never does
+    pub fn from_hex<const LEN: usize>(s: &str) -> Result<Self> {
+        let (username, user_publickey) = s
+            .rsplit_once(MESSAGE_DELIMITER)
+            .ok_or(Srp6Error::MalformedMessage)?;
+        if username.is_empty() {
+            return Err(Srp6Error::EmptyUsername);
+        }
+        Ok(Self::new(username, parse_hex_field(user_publickey, LEN)?))
+    }
+}
+
+impl ServerHandshake {
+    /// same as `to_string()`, `salt:B` both in hex
+    pub fn to_hex(&self) -> String {
+        self.to_string()
+    }
+
+    /// parses `salt:B`, both of at most `LEN` bytes
+    pub fn from_hex<const LEN: usize>(s: &str) -> Result<Self> {
+        let (salt, server_publickey) = s
+            .split_once(MESSAGE_DELIMITER)
+            .ok_or(Srp6Error::MalformedMessage)?;
+        Ok(Self::new(
+            parse_hex_field(salt, LEN)?,
+            parse_hex_field(server_publickey, LEN)?,
+        ))
+    }
+}
+
+impl ProofMessage {
+    /// same as `to_string()`, the proof in hex
+    pub fn to_hex(&self) -> String {
+        self.to_string()
+    }
+
+    /// parses a proof `M1` or `M2` of at most the output size of the hash `D`
+    pub fn from_hex<D: Digest>(s: &str) -> Result<Self> {
+        Ok(Self {
+            proof: parse_hex_field(s, <D as Digest>::output_size())?.into(),
+        })
+    }
+}
+
+/// appends a field prefixed by its length as a big endian `u16`
+pub(crate) fn write_prefixed(out: &mut Vec<u8>, field: &[u8]) -> Result<()> {
+    let len = u16::try_from(field.len()).map_err(|_| Srp6Error::MalformedMessage)?;
+    out.extend_from_slice(&len.to_be_bytes());
+    out.extend_from_slice(field);
+    Ok(())
+}
+
+/// reads the fields of the binary form of the messages, see e.g.
+/// [`ServerHandshake::to_bytes`]
+pub(crate) struct MessageReader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> MessageReader<'a> {
+    pub(crate) fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes }
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        if self.bytes.len() < len {
+            return Err(Srp6Error::TruncatedMessage);
+        }
+        let (field, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(field)
+    }
+
+    fn take_u8(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn take_u32(&mut self) -> Result<u32> {
+        let field = self.take(4)?;
+        Ok(u32::from_be_bytes([field[0], field[1], field[2], field[3]]))
+    }
+
+    /// an optional last byte 1, `false` if there are no bytes left
+    fn take_flag(&mut self) -> Result<bool> {
+        match self.bytes {
+            [] => Ok(false),
+            _ => match self.take_u8()? {
+                1 => Ok(true),
+                _ => Err(Srp6Error::MalformedMessage),
+            },
+        }
+    }
+
+    /// a field prefixed by its length, of at most `max_len` bytes
+    pub(crate) fn take_prefixed(&mut self, max_len: usize) -> Result<&'a [u8]> {
+        let prefix = self.take(2)?;
+        let len = usize::from(u16::from_be_bytes([prefix[0], prefix[1]]));
+        if len > max_len {
+            return Err(Srp6Error::KeyLengthMismatch {
+                given: len,
+                expected: max_len,
+            });
+        }
+        self.take(len)
+    }
+
+    /// a field prefixed by its length as a single byte
+    pub(crate) fn take_prefixed_u8(&mut self) -> Result<&'a [u8]> {
+        let len = usize::from(self.take_u8()?);
+        self.take(len)
+    }
+
+    fn take_username(&mut self) -> Result<Username> {
+        let username = core::str::from_utf8(self.take_prefixed(usize::from(u16::MAX))?)
+            .map_err(|_| Srp6Error::InvalidUsername)?;
+        if username.is_empty() {
+            return Err(Srp6Error::EmptyUsername);
+        }
+        Ok(username.into())
+    }
+
+    fn take_salt(&mut self, max_len: usize) -> Result<Salt> {
+        match self.take_prefixed(max_len)? {
+            [] => Err(Srp6Error::MalformedMessage),
+            salt => Ok(Salt::from_bytes_be(salt)),
+        }
+    }
+
+    /// a big endian number padded to `len` bytes
+    fn take_padded(&mut self, len: usize) -> Result<BigNumber> {
+        Ok(BigNumber::from_bytes_be(self.take(len)?))
+    }
+
+    /// fails if bytes are left
+    pub(crate) fn finish(self) -> Result<()> {
+        match self.bytes.len() {
+            0 => Ok(()),
+            count => Err(Srp6Error::TrailingBytes { count }),
+        }
+    }
+}
+
+impl UserHandshake {
+    /// the compact binary form: the length of the username as a big endian
+    /// `u16`, the username in UTF-8, then `A` padded to `LEN` bytes
+    pub fn to_bytes<const LEN: usize>(&self) -> Result<Vec<u8>> {
+        let mut out = Vec::with_capacity(2 + self.username.len() + LEN);
+        write_prefixed(&mut out, self.username.as_bytes())?;
+        out.extend(pad_vec(&self.user_publickey, LEN)?);
+        Ok(out)
+    }
+
+    /// parses the form of [`UserHandshake::to_bytes`], all the bytes must be used
+    pub fn from_bytes<const LEN: usize>(bytes: &[u8]) -> Result<Self> {
+        let mut reader = MessageReader::new(bytes);
+        let username = reader.take_username()?;
+        let user_publickey = reader.take_padded(LEN)?;
+        reader.finish()?;
+        Ok(Self::new(username, user_publickey))
+    }
+}
+
+impl ServerHandshake {
+    /// the compact binary form: the length of the salt as a big endian `u16`,
+    /// the salt, then `B` padded to `LEN` bytes
+    pub fn to_bytes<const LEN: usize>(&self) -> Result<Vec<u8>> {
+        let salt = self.salt.to_vec();
+        if salt.len() > LEN {
+            return Err(Srp6Error::KeyLengthMismatch {
+                given: salt.len(),
+                expected: LEN,
+            });
+        }
+        let mut out = Vec::with_capacity(2 + salt.len() + LEN);
+        write_prefixed(&mut out, &salt)?;
+        out.extend(pad_vec(&self.server_publickey, LEN)?);
+        Ok(out)
+    }
+
+    /// parses the form of [`ServerHandshake::to_bytes`], all the bytes must be used
+    pub fn from_bytes<const LEN: usize>(bytes: &[u8]) -> Result<Self> {
+        let mut reader = MessageReader::new(bytes);
+        let salt = reader.take_salt(LEN)?;
+        let server_publickey = reader.take_padded(LEN)?;
+        reader.finish()?;
+        Ok(Self::new(salt, server_publickey))
+    }
+}
+
+impl ProofMessage {
+    /// the compact binary form: the proof padded to the output size of the hash `D`
+    pub fn to_bytes<D: Digest>(&self) -> Result<Vec<u8>> {
+        pad_vec(&self.proof, <D as Digest>::output_size())
+    }
+
+    /// parses the form of [`ProofMessage::to_bytes`], all the bytes must be used
+    pub fn from_bytes<D: Digest>(bytes: &[u8]) -> Result<Self> {
+        let mut reader = MessageReader::new(bytes);
+        let proof = reader.take_padded(<D as Digest>::output_size())?;
+        reader.finish()?;
+        Ok(Self {
+            proof: proof.into(),
+        })
+    }
+}
+
+/// a [`Proof`] `M1` or a [`StrongProof`] `M2` from exactly the output size of
+/// the hash `D`, in the given byte order, e.g. as framed by another implementation
+///
+/// fails with [`Srp6Error::KeyLengthMismatch`] for any other length
+pub fn proof_from_bytes<D: Digest>(bytes: &[u8], order: ByteOrder) -> Result<Proof> {
+    exact_from_bytes(bytes, <D as Digest>::output_size(), order).map(Proof)
+}
+
+/// the [`StrongSessionKey`] `K` from exactly twice the output size of the hash `D`,
+/// in the given byte order
+///
+/// fails with [`Srp6Error::KeyLengthMismatch`] for any other length
+pub fn strong_session_key_from_bytes<D: Digest>(
+    bytes: &[u8],
+    order: ByteOrder,
+) -> Result<StrongSessionKey> {
+    exact_from_bytes(bytes, strong_session_key_length::<D>(), order).map(StrongSessionKey::new::<D>)
+}
+
+fn exact_from_bytes(bytes: &[u8], len: usize, order: ByteOrder) -> Result<BigNumber> {
+    if bytes.len() != len {
+        return Err(Srp6Error::KeyLengthMismatch {
+            given: bytes.len(),
+            expected: len,
+        });
+    }
+    Ok(BigNumber::from_bytes(bytes, order))
+}
+
+/// tags of [`KdfParams`] in the binary form of [`UserDetails`]
+const KDF_TAG_NONE: u8 = 0;
+const KDF_TAG_PBKDF2: u8 = 1;
+#[cfg(feature = "argon2")]
+const KDF_TAG_ARGON2ID: u8 = 2;
 
-/// Refers to a large safe prime called `N` (`N = 2q+1`, where `q` is prime)
-#[doc(alias = "N")]
-pub type PrimeModulus = BigNumber;
+/// tags of [`IdentityMode`] in the binary form of [`UserDetails`]
+const IDENTITY_TAG_USERNAME: u8 = 0;
+const IDENTITY_TAG_BLANK: u8 = 1;
+const IDENTITY_TAG_OPAQUE_ID: u8 = 2;
 
-/// Refers to the modulus generator `g`
-#[doc(alias = "g")]
-pub type Generator = BigNumber;
+impl UserDetails {
+    /// the compact binary form: the username and the salt as in
+    /// [`UserHandshake::to_bytes`] and [`ServerHandshake::to_bytes`], `v` padded
+    /// to `LEN` bytes, then a tag byte for `kdf` (0 none, 1 PBKDF2, 2 Argon2id)
+    /// followed by its parameters as big endian `u32`, and a tag byte for
+    /// `identity` (0 username, 1 blank, 2 opaque id followed by the id as the username),
+    /// and a last byte 1 only if `normalized`, so that the form of former versions
+    /// is unchanged
+    pub fn to_bytes<const LEN: usize>(&self) -> Result<Vec<u8>> {
+        let salt = self.salt.to_vec();
+        if salt.len() > LEN {
+            return Err(Srp6Error::KeyLengthMismatch {
+                given: salt.len(),
+                expected: LEN,
+            });
+        }
+        let mut out = Vec::new();
+        write_prefixed(&mut out, self.username.as_bytes())?;
+        write_prefixed(&mut out, &salt)?;
+        out.extend(pad_vec(&self.verifier, LEN)?);
+        match self.kdf {
+            None => out.push(KDF_TAG_NONE),
+            Some(KdfParams::Pbkdf2 { iterations }) => {
+                out.push(KDF_TAG_PBKDF2);
+                out.extend_from_slice(&iterations.to_be_bytes());
+            }
+            #[cfg(feature = "argon2")]
+            Some(KdfParams::Argon2id {
+                m_cost,
+                t_cost,
+                p_cost,
+            }) => {
+                out.push(KDF_TAG_ARGON2ID);
+                for cost in [m_cost, t_cost, p_cost] {
+                    out.extend_from_slice(&cost.to_be_bytes());
+                }
+            }
+        }
+        match &self.identity {
+            IdentityMode::Username => out.push(IDENTITY_TAG_USERNAME),
+            IdentityMode::Blank => out.push(IDENTITY_TAG_BLANK),
+            IdentityMode::OpaqueId(id) => {
+                out.push(IDENTITY_TAG_OPAQUE_ID);
+                write_prefixed(&mut out, id.as_bytes())?;
+            }
+        }
+        if self.normalized {
+            out.push(1);
+        }
+        Ok(out)
+    }
 
-/// Refers to a User's salt called `s`
-#[doc(alias = "s")]
-pub type Salt = BigNumber;
+    /// parses the form of [`UserDetails::to_bytes`], all the bytes must be used
+    ///
+    /// Argon2id parameters are only accepted with the `argon2` feature
+    pub fn from_bytes<const LEN: usize>(bytes: &[u8]) -> Result<Self> {
+        let mut reader = MessageReader::new(bytes);
+        let username = reader.take_username()?;
+        let salt = reader.take_salt(LEN)?;
+        let verifier = reader.take_padded(LEN)?;
+        let kdf = match reader.take_u8()? {
+            KDF_TAG_NONE => None,
+            KDF_TAG_PBKDF2 => Some(KdfParams::Pbkdf2 {
+                iterations: reader.take_u32()?,
+            }),
+            #[cfg(feature = "argon2")]
+            KDF_TAG_ARGON2ID => Some(KdfParams::Argon2id {
+                m_cost: reader.take_u32()?,
+                t_cost: reader.take_u32()?,
+                p_cost: reader.take_u32()?,
+            }),
+            _ => return Err(Srp6Error::InvalidKdfParams),
+        };
+        let identity = match reader.take_u8()? {
+            IDENTITY_TAG_USERNAME => IdentityMode::Username,
+            IDENTITY_TAG_BLANK => IdentityMode::Blank,
+            IDENTITY_TAG_OPAQUE_ID => IdentityMode::OpaqueId(reader.take_username()?.into()),
+            _ => return Err(Srp6Error::MalformedMessage),
+        };
+        let normalized = reader.take_flag()?;
+        reader.finish()?;
+        Ok(Self {
+            username,
+            salt,
+            verifier,
+            kdf,
+            identity,
+            normalized,
+            version: USER_DETAILS_VERSION,
+            group_fingerprint: None,
+        })
+    }
+}
 
-/// Refers to a Public shared key called A (user), B (server)
-#[doc(alias("A", "B"))]
-pub type PublicKey = BigNumber;
+/// Version of the protocol, it changes the multiplier parameter `k`, and for
+/// [`Srp6Variant::Srp6aV2`] the hashes of `x` and `M`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Srp6Variant {
+    /// SRP-6a, `k = H(N | PAD(g))`
+    #[default]
+    Srp6a,
+    /// legacy SRP-6, `k = 3` (e.g. for WoW servers)
+    Legacy,
+    /// SRP-6a with the fields of `x` and of the proof `M` each preceded by
+    /// their length and hashed in a context of their own, so that no two
+    /// splits of the same bytes give the same hash (e.g. `I = "a:b"`, `p = "c"`
+    /// and `I = "a"`, `p = "b:c"`), see [`calculate_private_key_x_v2`] and
+    /// [`calculate_proof_M_v2`]
+    ///
+    /// `k`, `M2`, the stretched `x` of [`KdfParams`] and the proofs of
+    /// [`ProofScheme::Hmac`] are the ones of [`Srp6Variant::Srp6a`]. Not compatible
+    /// with other implementations, nor with the verifiers of the other variants.
+    Srp6aV2,
+}
 
-/// Refers to a private secret random number a (user), b (server)
-#[doc(alias("a", "b"))]
-pub type PrivateKey = BigNumber;
+/// The group `N`, `g` and the variant of the protocol
+///
+/// It is serialized as its numbers in hex and the variant. On deserialization
+/// `N` must have `LEN` bytes and `1 < g < N`, the primality of `N` is not
+/// checked, see [`OpenConstants::try_new`] and [`OpenConstants::fingerprint`].
+#[derive(Debug, Clone)]
+pub struct OpenConstants<const LEN: usize> {
+    /// shared by the clones, e.g. of the cached [`Default`] groups, see
+    /// [`OpenConstants::module`]
+    pub(crate) module: Arc<PrimeModulus>,
+    pub generator: Generator,
+    /// both sides must use the same variant
+    pub variant: Srp6Variant,
+    /// `k` and `H(N) xor H(g)`, see [`OpenConstants::cache_for`]
+    pub(crate) cache: Option<Arc<CachedGroupHashes>>,
+    /// see [`OpenConstants::with_fixed_base_table`]
+    pub(crate) g_pow: Option<Arc<FixedBasePowTable>>,
+}
 
-/// Password Verifier is the users secret on the server side
-#[doc(alias = "v")]
-pub type PasswordVerifier = BigNumber;
+/// the cached hashes and the table of powers of `g` are derived from the group,
+/// they are not compared
+impl<const LEN: usize> PartialEq for OpenConstants<LEN> {
+    fn eq(&self, other: &Self) -> bool {
+        self.module == other.module
+            && self.generator == other.generator
+            && self.variant == other.variant
+    }
+}
 
-/// Refers to a multiplier parameter `k` (k = H(N, g) in SRP-6a, k = 3 for legacy SRP-6)
-#[doc(alias = "k")]
-pub type MultiplierParameter = BigNumber;
+impl<const LEN: usize> Eq for OpenConstants<LEN> {}
 
-/// Refers to the SessionKey `S`
-#[doc(alias = "S")]
-pub type SessionKey = BigNumber;
-/// Refers to the StrongSessionKey `K`
-#[doc(alias = "K")]
-pub type StrongSessionKey = BigNumber;
+/// the serialized fields of [`OpenConstants`]
+#[derive(Serialize)]
+#[serde(rename = "OpenConstants")]
+struct OpenConstantsRef<'a> {
+    module: &'a PrimeModulus,
+    generator: &'a Generator,
+    variant: Srp6Variant,
+}
 
-/// Refers to `M` and `M1` Proof of server and client
-#[doc(alias("M", "M1"))]
-pub type Proof = BigNumber;
-/// Refers to `M2` the hash of Proof
-#[doc(alias = "M2")]
-pub type StrongProof = BigNumber;
+/// the deserialized fields of [`OpenConstants`], not checked yet
+#[derive(Deserialize)]
+#[serde(rename = "OpenConstants")]
+struct OpenConstantsFields {
+    module: PrimeModulus,
+    generator: Generator,
+    #[serde(default)]
+    variant: Srp6Variant,
+}
 
-/// Username `I` as [`String`]
-#[doc(alias = "I")]
-pub type Username = String;
-/// Username reference `I` as [`&str`]
-pub type UsernameRef<'a> = &'a str;
-/// Clear text password `p` as [`str`]
-#[doc(alias = "p")]
-pub type ClearTextPassword = str;
+impl<const LEN: usize> Serialize for OpenConstants<LEN> {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> core::result::Result<S::Ok, S::Error> {
+        OpenConstantsRef {
+            module: &self.module,
+            generator: &self.generator,
+            variant: self.variant,
+        }
+        .serialize(serializer)
+    }
+}
 
-/// [`Username`] and [`ClearTextPassword`] used on the client side
-#[derive(Debug, Clone)]
-pub struct UserCredentials<'a> {
-    pub username: UsernameRef<'a>,
-    pub password: &'a ClearTextPassword,
+impl<'de, const LEN: usize> Deserialize<'de> for OpenConstants<LEN> {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> core::result::Result<Self, D::Error> {
+        use serde::de::Error;
+
+        let fields = OpenConstantsFields::deserialize(deserializer)?;
+        if fields.module.num_bytes() != LEN {
+            return Err(D::Error::custom(Srp6Error::KeyLengthMismatch {
+                given: fields.module.num_bytes(),
+                expected: LEN,
+            }));
+        }
+        check_key_length(LEN).map_err(D::Error::custom)?;
+        if fields.generator <= BigNumber::from(1)
+            || fields.generator >= fields.module
+            || fields.generator.gcd(&fields.module) != BigNumber::from(1)
+        {
+            return Err(D::Error::custom(Srp6Error::InvalidGenerator));
+        }
+        Ok(Self::new(fields.module, fields.generator, fields.variant))
+    }
 }
 
-/// User details composes [`Username`], [`Salt`] and [`PasswordVerifier`] in one struct
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct UserDetails {
-    pub username: Username,
-    pub salt: Salt,
-    pub verifier: PasswordVerifier,
+impl<const LEN: usize> OpenConstants<LEN> {
+    /// also computes `k` and `H(N) xor H(g)` for the default hash function,
+    /// see [`OpenConstants::cache_for`] for another one
+    pub fn new(module: PrimeModulus, generator: Generator, variant: Srp6Variant) -> Self {
+        Self {
+            module: Arc::new(module),
+            generator,
+            variant,
+            cache: None,
+            g_pow: None,
+        }
+        .cache_for::<HashFunc>()
+    }
+
+    /// the modulus `N`
+    ///
+    /// not checked by [`OpenConstants::new`]: a zero or too small `N` fails the
+    /// handshakes with [`Srp6Error::InvalidConfiguration`]
+    pub fn module(&self) -> &PrimeModulus {
+        &self.module
+    }
+
+    /// custom group parameters, unlike [`OpenConstants::new`] they are checked:
+    /// `N` must be a safe prime of `LEN` bytes and `1 < g < N - 1`
+    ///
+    /// `N` and `(N - 1) / 2` must pass [`MILLER_RABIN_ROUNDS`] rounds of
+    /// Miller-Rabin. `g` may only generate the subgroup of order `(N - 1) / 2`,
+    /// as does `g = 2` in most RFC 5054 groups.
+    #[cfg(feature = "std")]
+    pub fn try_new(module: PrimeModulus, generator: Generator) -> Result<Self> {
+        Self::try_new_with_rng(
+            module,
+            generator,
+            MILLER_RABIN_ROUNDS,
+            &mut rand::thread_rng(),
+        )
+    }
+
+    /// same as [`OpenConstants::try_new`], with `rounds` of Miller-Rabin whose
+    /// bases come from `rng`
+    pub fn try_new_with_rng<R: RngCore + CryptoRng + ?Sized>(
+        module: PrimeModulus,
+        generator: Generator,
+        rounds: usize,
+        rng: &mut R,
+    ) -> Result<Self> {
+        if module.num_bytes() != LEN {
+            return Err(Srp6Error::KeyLengthMismatch {
+                given: module.num_bytes(),
+                expected: LEN,
+            });
+        }
+        check_key_length(LEN)?;
+        if generator <= BigNumber::from(1) || generator >= &module - &BigNumber::from(1) {
+            return Err(Srp6Error::InvalidGenerator);
+        }
+        // cheaper than the primality test, and any prime passes
+        if generator.gcd(&module) != BigNumber::from(1) {
+            return Err(Srp6Error::InvalidGenerator);
+        }
+        if !module.is_probably_safe_prime(rounds, rng) {
+            return Err(Srp6Error::NotASafePrime);
+        }
+        Ok(Self::new(module, generator, Srp6Variant::Srp6a))
+    }
+
+    /// a fresh group: a random safe prime `N` of `LEN` bytes and `g = 2`
+    ///
+    /// slow, in release count seconds for 1024 bits, tens of seconds or more
+    /// from 2048 bits
+    #[cfg(all(feature = "group-generation", feature = "std"))]
+    pub fn generate() -> Self {
+        Self::generate_with_rng(&mut rand::thread_rng())
+    }
+
+    /// same as [`OpenConstants::generate`], the safe prime comes from `rng`
+    #[cfg(feature = "group-generation")]
+    pub fn generate_with_rng<R: RngCore + CryptoRng + ?Sized>(rng: &mut R) -> Self {
+        let module = PrimeModulus::new_safe_prime_with(rng, LEN * 8, MILLER_RABIN_ROUNDS);
+        Self::new(module, Generator::from(2), Srp6Variant::Srp6a)
+    }
+
+    /// precomputes the powers of `g`, for a faster `A`, `B` and `v`,
+    /// see [`FixedBasePowTable`]
+    ///
+    /// it takes some time and memory, so it is only worth it when the constants
+    /// are kept for many handshakes, e.g. by a server
+    pub fn with_fixed_base_table(mut self) -> Self {
+        self.g_pow = Some(Arc::new(FixedBasePowTable::new(
+            &self.generator,
+            &self.module,
+            LEN * 8,
+        )));
+        self
+    }
+
+    /// the precomputed powers of `g`, if they are still valid
+    pub(crate) fn fixed_base_table(&self) -> Option<&FixedBasePowTable> {
+        self.g_pow
+            .as_deref()
+            .filter(|table| table.is_for(&self.generator, &self.module))
+    }
+
+    /// computes `k` and `H(N) xor H(g)` once for the hash function `D`, instead
+    /// of at every handshake
+    ///
+    /// they are computed again at every handshake if another hash function is
+    /// used, or if a field was changed since
+    pub fn cache_for<D: Digest>(mut self) -> Self {
+        self.cache = GroupHashes::new::<D>(LEN, &self.module, &self.generator, self.variant)
+            .ok()
+            .map(|hashes| {
+                Arc::new(CachedGroupHashes {
+                    digest: core::any::type_name::<D>(),
+                    module: self.module.clone(),
+                    generator: self.generator.clone(),
+                    variant: self.variant,
+                    hashes,
+                })
+            });
+        self
+    }
+
+    /// the cached [`GroupHashes`] if they are still valid for `D`
+    pub(crate) fn group_hashes<D: Digest>(&self) -> Result<Cow<'_, GroupHashes>> {
+        match &self.cache {
+            Some(cache)
+                if cache.digest == core::any::type_name::<D>()
+                    && (Arc::ptr_eq(&cache.module, &self.module)
+                        || cache.module == self.module)
+                    && cache.generator == self.generator
+                    && cache.variant == self.variant =>
+            {
+                Ok(Cow::Borrowed(&cache.hashes))
+            }
+            _ => GroupHashes::new::<D>(LEN, &self.module, &self.generator, self.variant)
+                .map(Cow::Owned),
+        }
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct UserHandshake {
-    pub username: Username,
-    pub user_publickey: PublicKey,
+/// `k` and `H(N) xor H(g)`, they only depend on the group and the hash function
+///
+/// `k` is kept for the paddings of `N` and `g` of each [`CompatProfile`]
+#[allow(non_snake_case)]
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct GroupHashes {
+    k: MultiplierParameter,
+    k_unpadded: MultiplierParameter,
+    k_padded_modulus: MultiplierParameter,
+    pub(crate) hash_N_xor_g: Vec<u8>,
+    /// `(N - 1) / 2`, for the exponent blinding
+    #[cfg(feature = "blinding")]
+    q: BigNumber,
+    /// selects the hash of the proof `M`
+    pub(crate) variant: Srp6Variant,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ServerHandshake {
-    pub salt: Salt,
-    pub server_publickey: PublicKey,
+impl GroupHashes {
+    #[allow(non_snake_case)]
+    pub(crate) fn new<D: Digest>(
+        len: usize,
+        N: &PrimeModulus,
+        g: &Generator,
+        variant: Srp6Variant,
+    ) -> Result<Self> {
+        // every handshake needs them, whichever way its group was built
+        check_key_length(len)?;
+        Ok(Self {
+            k: calculate_k::<D>(len, N, g, variant, CompatProfile::Rfc5054)?,
+            k_unpadded: calculate_k::<D>(len, N, g, variant, CompatProfile::PySrp)?,
+            k_padded_modulus: calculate_k::<D>(len, N, g, variant, CompatProfile::Tssrp6a)?,
+            hash_N_xor_g: calculate_hash_N_xor_g::<D>(len, N, g)?.to_vec(),
+            #[cfg(feature = "blinding")]
+            q: N.subgroup_order(),
+            variant,
+        })
+    }
+
+    /// `q` to blind the exponents of `S` with, only with the `blinding` feature
+    pub(crate) fn blinding_order(&self) -> Option<&BigNumber> {
+        #[cfg(feature = "blinding")]
+        return Some(&self.q);
+        #[cfg(not(feature = "blinding"))]
+        None
+    }
+
+    /// `k` as calculated by `profile`
+    pub(crate) fn k(&self, profile: CompatProfile) -> &MultiplierParameter {
+        if profile.pads_modulus_in_k() {
+            &self.k_padded_modulus
+        } else if profile.pads_hash_inputs() {
+            &self.k
+        } else {
+            &self.k_unpadded
+        }
+    }
 }
 
+/// [`GroupHashes`] with what they were computed from
 #[derive(Debug, Clone)]
-pub struct OpenConstants<const LEN: usize> {
-    pub module: PrimeModulus,
+pub(crate) struct CachedGroupHashes {
+    digest: &'static str,
+    module: Arc<PrimeModulus>,
+    generator: Generator,
+    variant: Srp6Variant,
+    hashes: GroupHashes,
+}
+
+/// Same as [`OpenConstants`], but the key length `len` (in bytes) is only known at runtime
+#[derive(Debug, Clone)]
+pub struct GroupParams {
+    pub modulus: PrimeModulus,
+    pub generator: Generator,
+    pub len: usize,
+    pub variant: Srp6Variant,
+}
+
+impl GroupParams {
+    /// not cached, computed at every handshake
+    pub(crate) fn group_hashes<D: Digest>(&self) -> Result<GroupHashes> {
+        GroupHashes::new::<D>(self.len, &self.modulus, &self.generator, self.variant)
+    }
+}
+
+impl<const LEN: usize> From<OpenConstants<LEN>> for GroupParams {
+    fn from(constants: OpenConstants<LEN>) -> Self {
+        Self {
+            modulus: Arc::unwrap_or_clone(constants.module),
+            generator: constants.generator,
+            len: LEN,
+            variant: constants.variant,
+        }
+    }
+}
+
+impl<const LEN: usize> From<&OpenConstants<LEN>> for GroupParams {
+    fn from(constants: &OpenConstants<LEN>) -> Self {
+        constants.clone().into()
+    }
+}
+
+/// The group `(N, g)` as offered by the server, as in RFC 5054 where it is
+/// sent along with the salt and `B`
+///
+/// the user must only accept a group it trusts, see [`TrustedGroups`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GroupParamsMessage {
+    pub modulus: PrimeModulus,
     pub generator: Generator,
 }
 
+impl GroupParamsMessage {
+    /// SHA-256 over `N` and `g`, each prefixed by its length as a big endian
+    /// `u32`, in upper case hex
+    ///
+    /// it does not depend on the variant, only the numbers are sent
+    pub fn fingerprint(&self) -> String {
+        let mut hasher = Sha256::new();
+        for field in [self.modulus.to_vec(), self.generator.to_vec()] {
+            hasher.update((field.len() as u32).to_be_bytes());
+            hasher.update(&field);
+        }
+        hex::encode_upper(hasher.finalize())
+    }
+
+    /// the compact binary form: `N` then `g`, each prefixed by its length as
+    /// a big endian `u16`
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let modulus = self.modulus.to_vec();
+        let generator = self.generator.to_vec();
+        let mut out = Vec::with_capacity(4 + modulus.len() + generator.len());
+        write_prefixed(&mut out, &modulus)?;
+        write_prefixed(&mut out, &generator)?;
+        Ok(out)
+    }
+
+    /// parses the form of [`GroupParamsMessage::to_bytes`], all the bytes must
+    /// be used; `g` may not be longer than `N`
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let mut reader = MessageReader::new(bytes);
+        let modulus = reader.take_prefixed(usize::from(u16::MAX))?;
+        let generator = reader.take_prefixed(modulus.len())?;
+        reader.finish()?;
+        if modulus.is_empty() || generator.is_empty() {
+            return Err(Srp6Error::MalformedMessage);
+        }
+        Ok(Self {
+            modulus: PrimeModulus::from_bytes_be(modulus),
+            generator: Generator::from_bytes_be(generator),
+        })
+    }
+}
+
+impl<const LEN: usize> From<&OpenConstants<LEN>> for GroupParamsMessage {
+    fn from(constants: &OpenConstants<LEN>) -> Self {
+        Self {
+            modulus: (*constants.module).clone(),
+            generator: constants.generator.clone(),
+        }
+    }
+}
+
+impl From<&GroupParams> for GroupParamsMessage {
+    fn from(group: &GroupParams) -> Self {
+        Self {
+            modulus: group.modulus.clone(),
+            generator: group.generator.clone(),
+        }
+    }
+}
+
+impl<const LEN: usize> OpenConstants<LEN> {
+    /// what the server sends so that the user can check its group, see
+    /// [`TrustedGroups`]
+    pub fn group_message(&self) -> GroupParamsMessage {
+        self.into()
+    }
+
+    /// SHA-256 over `LEN` as a big endian `u32`, `N` and `g`, to check a stored
+    /// group against a known one
+    ///
+    /// the variant is left out, unlike [`GroupParamsMessage::fingerprint`] the
+    /// numbers are not prefixed by their length.
+    pub fn fingerprint(&self) -> [u8; 32] {
+        group_fingerprint(LEN, &self.module, &self.generator)
+    }
+}
+
+/// [`OpenConstants::fingerprint`], for a key length only known at runtime
+#[allow(non_snake_case)]
+pub(crate) fn group_fingerprint(len: usize, N: &PrimeModulus, g: &Generator) -> [u8; 32] {
+    Sha256::new()
+        .chain_update((len as u32).to_be_bytes())
+        .chain_update(N.to_vec())
+        .chain_update(g.to_vec())
+        .finalize()
+        .into()
+}
+
+/// The groups a user accepts from a server, keyed by their
+/// [`GroupParamsMessage::fingerprint`]
+///
+/// the [`Default`] has the groups of RFC 5054, from 1024 to 8192 bits, see
+/// [`TrustedGroups::empty`] to only trust given groups
+#[derive(Debug, Clone)]
+pub struct TrustedGroups {
+    groups: BTreeMap<String, GroupParams>,
+}
+
+impl TrustedGroups {
+    /// trusts no group
+    pub fn empty() -> Self {
+        Self {
+            groups: BTreeMap::new(),
+        }
+    }
+
+    /// also trusts `group`, with its variant
+    pub fn with_group(mut self, group: impl Into<GroupParams>) -> Self {
+        let group = group.into();
+        self.groups
+            .insert(GroupParamsMessage::from(&group).fingerprint(), group);
+        self
+    }
+
+    /// whether the group of this fingerprint is trusted
+    pub fn contains(&self, fingerprint: &str) -> bool {
+        self.groups.contains_key(fingerprint)
+    }
+
+    /// the fingerprints of the trusted groups, in order
+    pub fn fingerprints(&self) -> impl Iterator<Item = &str> {
+        self.groups.keys().map(String::as_str)
+    }
+
+    /// the trusted group of the message, fails with [`Srp6Error::UntrustedGroup`]
+    pub fn get(&self, group_msg: &GroupParamsMessage) -> Result<&GroupParams> {
+        let fingerprint = group_msg.fingerprint();
+        match self.groups.get(&fingerprint) {
+            // the fingerprint is collision resistant, this only guards a bug
+            Some(group)
+                if group.modulus == group_msg.modulus && group.generator == group_msg.generator =>
+            {
+                Ok(group)
+            }
+            _ => Err(Srp6Error::UntrustedGroup(fingerprint)),
+        }
+    }
+
+    /// the constants of the trusted group of the message, whose `N` must
+    /// have `LEN` bytes
+    pub fn resolve<const LEN: usize>(
+        &self,
+        group_msg: &GroupParamsMessage,
+    ) -> Result<OpenConstants<LEN>> {
+        let group = self.get(group_msg)?;
+        if group.len != LEN {
+            return Err(Srp6Error::KeyLengthMismatch {
+                given: group.len,
+                expected: LEN,
+            });
+        }
+        Ok(OpenConstants::new(
+            group.modulus.clone(),
+            group.generator.clone(),
+            group.variant,
+        ))
+    }
+}
+
 /// host version of a session key for a given user
 /// S: is the session key of a user
 /// u: is the hash of user and server pub keys
 ///
 /// u = H(A, B)
 /// S = (Av^u) ^ b
+///
+/// with the `blinding` feature, the exponent is blinded, see [`blind_exponent`]
 #[allow(non_snake_case)]
-pub(crate) fn calculate_session_key_S_for_host<const KEY_LENGTH: usize>(
+#[cfg_attr(not(feature = "hazmat"), allow(dead_code))]
+pub fn calculate_session_key_S_for_host<D: Digest>(
+    len: usize,
     N: &PrimeModulus,
     A: &PublicKey,
     B: &PublicKey,
     b: &PrivateKey,
     v: &PasswordVerifier,
+    profile: CompatProfile,
 ) -> Result<SessionKey> {
+    let q = blinding_order(N);
+    session_key_S_for_host::<D>(len, N, A, B, b, v, profile, q.as_ref())
+}
+
+/// [`calculate_session_key_S_for_host`], with the exponent blinded by `q` if given
+#[allow(non_snake_case)]
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn session_key_S_for_host<D: Digest>(
+    len: usize,
+    N: &PrimeModulus,
+    A: &PublicKey,
+    B: &PublicKey,
+    b: &PrivateKey,
+    v: &PasswordVerifier,
+    profile: CompatProfile,
+    q: Option<&BigNumber>,
+) -> Result<SessionKey> {
+    check_modulus(N)?;
     // safeguard A % N == 0 should be checked
     if (A % N).is_zero() {
         return Err(Srp6Error::InvalidPublicKey(A.clone()));
     }
 
-    let u = &calculate_u::<KEY_LENGTH>(A, B);
+    let u = &calculate_u::<D>(len, A, B, profile)?;
+    check_scrambling_parameter(u)?;
     let base = &(A * &v.modpow(u, N));
-    let S: BigNumber = base.modpow(b, N);
+    let S: BigNumber = match q {
+        #[cfg(feature = "blinding")]
+        Some(q) => base.modpow(&blind_exponent(b, q), N),
+        _ => base.modpow(b, N),
+    };
 
-    debug!("S = {:?}", &S);
+    debug_secret!("S = {:?}", &S);
 
     Ok(S)
 }
@@ -143,62 +2702,139 @@ pub(crate) fn calculate_session_key_S_for_host<const KEY_LENGTH: usize>(
 /// - formulas found so far:
 ///   - `S = (B - (k * g^x)) ^ (a + (u * x)) % N`
 ///   - `S = (B - (k * v)) ^ (a + (u * x)) % N`
+///
+/// with the `blinding` feature, the exponent is blinded, see [`blind_exponent`]
+#[allow(non_snake_case)]
+#[allow(clippy::many_single_char_names, clippy::too_many_arguments)]
+#[cfg_attr(not(feature = "hazmat"), allow(dead_code))]
+pub fn calculate_session_key_S_for_client<D: Digest>(
+    len: usize,
+    N: &PrimeModulus,
+    g: &Generator,
+    k: &MultiplierParameter,
+    B: &PublicKey,
+    A: &PublicKey,
+    a: &PrivateKey,
+    x: &PrivateKey,
+    profile: CompatProfile,
+) -> Result<SessionKey> {
+    let q = blinding_order(N);
+    session_key_S_for_client::<D>(len, N, g, k, B, A, a, x, profile, q.as_ref())
+}
+
+/// [`calculate_session_key_S_for_client`], with the exponent blinded by `q` if given
 #[allow(non_snake_case)]
-#[allow(clippy::many_single_char_names)]
-pub(crate) fn calculate_session_key_S_for_client<const KEY_LENGTH: usize>(
+#[allow(clippy::many_single_char_names, clippy::too_many_arguments)]
+pub(crate) fn session_key_S_for_client<D: Digest>(
+    len: usize,
     N: &PrimeModulus,
     g: &Generator,
+    k: &MultiplierParameter,
     B: &PublicKey,
     A: &PublicKey,
     a: &PrivateKey,
     x: &PrivateKey,
+    profile: CompatProfile,
+    q: Option<&BigNumber>,
 ) -> Result<SessionKey> {
+    check_modulus(N)?;
     // safeguard B % N == 0
     if (B % N).is_zero() {
         return Err(Srp6Error::InvalidPublicKey(B.clone()));
     }
 
-    let u = &calculate_u::<KEY_LENGTH>(A, B);
+    let u = &calculate_u::<D>(len, A, B, profile)?;
+    check_scrambling_parameter(u)?;
     let exp: BigNumber = a + &(u * x);
-    let g_mod_x = &g.modpow(x, N);
-    let to_sub = &(&calculate_k::<KEY_LENGTH>(N, g) * g_mod_x) % N;
-    // let base = B - ;
-    let base = if B < &to_sub {
-        &(N - &to_sub) + B
-    } else {
-        B - &to_sub
+    let g_mod_x = g.modpow(x, N);
+    let base = B.sub_mod(&(k * g_mod_x), N);
+    let S = match q {
+        #[cfg(feature = "blinding")]
+        Some(q) => base.modpow(&blind_exponent(&exp, q), N),
+        _ => base.modpow(&exp, N),
     };
-    let S = base.modpow(&exp, N);
-    debug!("S = {:?}", &S);
+    debug_secret!("S = {:?}", &S);
 
     Ok(S)
 }
 
+/// `q` of `N` with the `blinding` feature, for the functions without the
+/// cached [`GroupHashes`]
+#[allow(non_snake_case)]
+#[cfg_attr(not(feature = "blinding"), allow(unused_variables))]
+#[cfg_attr(not(feature = "hazmat"), allow(dead_code))]
+fn blinding_order(N: &PrimeModulus) -> Option<BigNumber> {
+    #[cfg(feature = "blinding")]
+    return Some(N.subgroup_order());
+    #[cfg(not(feature = "blinding"))]
+    None
+}
+
+/// the exponent `e` of `S` plus `2 * r * q`, for a random `r` of 64 bits, so
+/// that its bits change at every handshake, against the cache timing attacks
+/// of a local attacker
+///
+/// `2q = N - 1` is a multiple of the order of any base for a prime `N`, so
+/// the power is the same. `q` alone is not: `g = 5` generates the whole group
+/// in the RFC 5054 groups of 3072 bits and more.
+#[cfg(feature = "blinding")]
+pub(crate) fn blind_exponent(e: &BigNumber, q: &BigNumber) -> BigNumber {
+    let r = BigNumber::from_bytes_be(&rand::thread_rng().next_u64().to_be_bytes());
+    e + &(r * BigNumber::from(2) * q)
+}
+
 /// the hash of a session key `S` that is called `K`
 /// S: is the session key of a user
 /// K: is the hash of S, just not that straight
+///
+/// `SHA_Interleave` of RFC 2945: the leading zero bytes of `S` are removed, and
+/// one more byte if the rest has an odd length, then the even and odd bytes are
+/// hashed apart. `len` only bounds `S`.
+///
+/// `K` is twice the hash output whatever `len`: in a small group, e.g. 256 bits
+/// with SHA-256, the halves of `S` are shorter than the hash output and `K` is
+/// longer than `N`.
 #[allow(non_snake_case)]
-pub(crate) fn calculate_session_key_hash_interleave_K<const KEY_LENGTH: usize>(
+pub fn calculate_session_key_hash_interleave_K<D: Digest>(
+    len: usize,
     S: &SessionKey,
-) -> StrongSessionKey {
-    let S = S.to_array_pad_zero::<KEY_LENGTH>();
+) -> Result<StrongSessionKey> {
+    let S = pad_vec(S, len)?;
+    let T = &S[S.iter().take_while(|Si| **Si == 0).count()..];
+    let T = &T[T.len() % 2..];
 
-    // take the even bytes out of S
-    let mut half = [0_u8; KEY_LENGTH];
-    for (i, Si) in S.iter().step_by(2).enumerate() {
-        half[i] = *Si;
-    }
-    // hash the even portion of S
-    let even_half_of_S_hash = HashFunc::new().chain(&half[..KEY_LENGTH / 2]).finalize();
+    Ok(interleave_hashes::<D>(T))
+}
 
-    // take the odd bytes of S
-    for (i, Si) in S.iter().skip(1).step_by(2).enumerate() {
-        half[i] = *Si;
-    }
-    // hash the odd portion of S
-    let odd_half_of_S_hash = HashFunc::new().chain(&half[..KEY_LENGTH / 2]).finalize();
+/// `K` over `PAD(S)`, as this crate calculated it before it followed RFC 2945,
+/// see [`CompatProfile::LegacyInterleave`]
+///
+/// It differs from [`calculate_session_key_hash_interleave_K`] only when `S`
+/// has leading zero bytes.
+#[allow(non_snake_case)]
+pub fn calculate_session_key_hash_interleave_padded_K<D: Digest>(
+    len: usize,
+    S: &SessionKey,
+) -> Result<StrongSessionKey> {
+    let S = pad_vec(S, len)?;
+    let T = &S[..S.len() - S.len() % 2];
+
+    Ok(interleave_hashes::<D>(T))
+}
+
+/// hashes the even and the odd bytes of `T` apart and interleaves both hashes
+#[allow(non_snake_case)]
+fn interleave_hashes<D: Digest>(T: &[u8]) -> StrongSessionKey {
+    // hash the even portion of T
+    let even_half_of_S_hash = D::new()
+        .chain_update(T.iter().step_by(2).copied().collect::<Vec<u8>>())
+        .finalize();
+    // hash the odd portion of T
+    let odd_half_of_S_hash = D::new()
+        .chain_update(T.iter().skip(1).step_by(2).copied().collect::<Vec<u8>>())
+        .finalize();
 
-    let mut vK = [0_u8; STRONG_SESSION_KEY_LENGTH];
+    let mut vK = vec![0_u8; strong_session_key_length::<D>()];
     for (i, h_Si) in even_half_of_S_hash
         .iter()
         .zip(odd_half_of_S_hash.iter())
@@ -208,79 +2844,298 @@ pub(crate) fn calculate_session_key_hash_interleave_K<const KEY_LENGTH: usize>(
         vK[i * 2 + 1] = *h_Si.1;
     }
 
-    let K = BigNumber::from_bytes_le(&vK);
-    debug!("K = {:?}", &K);
+    let K = StrongSessionKey::new::<D>(BigNumber::from_bytes_le(&vK));
+    debug_secret!("K = {:?}", &K);
+
+    K
+}
+
+/// `K` as calculated by `profile`, the interleaved hash of RFC 2945 or `H(S)`
+#[allow(non_snake_case)]
+pub fn calculate_session_key_K<D: Digest>(
+    len: usize,
+    S: &SessionKey,
+    profile: CompatProfile,
+) -> Result<StrongSessionKey> {
+    match profile {
+        CompatProfile::Rfc5054 => calculate_session_key_hash_interleave_K::<D>(len, S),
+        CompatProfile::LegacyInterleave => {
+            calculate_session_key_hash_interleave_padded_K::<D>(len, S)
+        }
+        CompatProfile::Tssrp6a | CompatProfile::PySrp => {
+            let K = StrongSessionKey::new::<D>(BigNumber::from_digest(
+                D::new().chain_update(S.to_vec()),
+            ));
+            debug_secret!("K = {:?}", &K);
+            Ok(K)
+        }
+    }
+}
+
+/// the proof `M` of the user, as calculated by `profile`
+#[allow(non_snake_case)]
+#[allow(clippy::too_many_arguments)]
+pub fn calculate_proof_M<D: Digest>(
+    len: usize,
+    hash_N_xor_g: &[u8],
+    I: UsernameRef,
+    s: &Salt,
+    A: &PublicKey,
+    B: &PublicKey,
+    S: &SessionKey,
+    K: &StrongSessionKey,
+    profile: CompatProfile,
+) -> Result<Proof> {
+    if profile == CompatProfile::Tssrp6a {
+        let M = Proof::from_digest(
+            D::new()
+                .chain_update(A.to_vec())
+                .chain_update(B.to_vec())
+                .chain_update(S.to_vec()),
+        );
+        debug!("M = {:?}", &M);
+        return Ok(M);
+    }
+
+    let username_hash = D::new().chain_update(I.as_bytes()).finalize();
+    debug!("H(I) = {:?}", &username_hash);
+
+    let mut hasher = D::new()
+        .chain_update(hash_N_xor_g)
+        .chain_update(username_hash)
+        .chain_update(s.to_vec());
+    match profile {
+        CompatProfile::PySrp => {
+            hasher.update(A.to_vec());
+            hasher.update(B.to_vec());
+            chain_padded(&mut hasher, K.number(), <D as Digest>::output_size())
+                .map_err(length_mismatch)?;
+        }
+        _ => {
+            chain_padded(&mut hasher, A, len).map_err(length_mismatch)?;
+            chain_padded(&mut hasher, B, len).map_err(length_mismatch)?;
+            chain_padded(&mut hasher, K.number(), strong_session_key_length::<D>())
+                .map_err(length_mismatch)?;
+        }
+    }
+    let M = Proof::from_digest(hasher);
+
+    debug!("M = {:?}", &M);
+
+    Ok(M)
+}
+
+/// the proof `M` of the user for [`Srp6Variant::Srp6aV2`], the profile does not
+/// change it: each field is preceded by its length in 4 bytes big endian, and
+/// `K` is given by its canonical bytes, see [`StrongSessionKey::to_bytes`]
+///
+/// formula: `H(LEN("srp6 proof v2") | "srp6 proof v2" | LEN(H(N) xor H(g)) | H(N) xor H(g) | LEN(I) | I | LEN(s) | s | LEN(A) | A | LEN(B) | B | LEN(K) | K)`
+#[allow(non_snake_case)]
+pub fn calculate_proof_M_v2<D: Digest>(
+    hash_N_xor_g: &[u8],
+    I: UsernameRef,
+    s: &Salt,
+    A: &PublicKey,
+    B: &PublicKey,
+    K: &StrongSessionKey,
+) -> Proof {
+    let M: Proof = DomainSeparatedHasher::<D>::new("srp6 proof v2")
+        .chain_field(hash_N_xor_g)
+        .chain_field(I.as_bytes())
+        .chain_field(&s.to_vec())
+        .chain_field(&A.to_vec())
+        .chain_field(&B.to_vec())
+        .chain_field(&K.to_bytes())
+        .finalize_number()
+        .into();
+    debug!("M = {:?}", &M);
+
+    M
+}
+
+/// todo(verify): check if padding is needed or not
+/// formula: `H(A | M | K)`, `H(A | M | S)` for [`CompatProfile::Tssrp6a`]
+#[allow(non_snake_case)]
+pub fn calculate_strong_proof_M2<D: Digest>(
+    len: usize,
+    A: &PublicKey,
+    M: &Proof,
+    S: &SessionKey,
+    K: &StrongSessionKey,
+    profile: CompatProfile,
+) -> Result<StrongProof> {
+    let mut hasher = D::new();
+    match profile {
+        CompatProfile::Rfc5054 | CompatProfile::LegacyInterleave => {
+            chain_padded(&mut hasher, A, len).map_err(length_mismatch)?;
+            chain_padded(&mut hasher, M, <D as Digest>::output_size()).map_err(length_mismatch)?;
+            chain_padded(&mut hasher, K.number(), strong_session_key_length::<D>())
+                .map_err(length_mismatch)?;
+        }
+        CompatProfile::Tssrp6a => {
+            hasher.update(A.to_vec());
+            hasher.update(M.to_vec());
+            hasher.update(S.to_vec());
+        }
+        CompatProfile::PySrp => {
+            hasher.update(A.to_vec());
+            chain_padded(&mut hasher, M, <D as Digest>::output_size()).map_err(length_mismatch)?;
+            chain_padded(&mut hasher, K.number(), <D as Digest>::output_size())
+                .map_err(length_mismatch)?;
+        }
+    }
+    let M2 = StrongProof::from_digest(hasher);
+    debug!("M2 = {:?}", &M2);
+
+    Ok(M2)
+}
+
+/// HMAC of RFC 2104 over the parts of `message`, for the hash functions of
+/// SHA-1 and SHA-2: their block is 64 bytes up to SHA-256, 128 bytes above
+fn hmac<D: Digest>(key: &[u8], message: impl FnOnce(&mut D) -> Result<()>) -> Result<Hash<D>> {
+    let block_size = if <D as Digest>::output_size() > 32 {
+        128
+    } else {
+        64
+    };
+    let mut block = if key.len() > block_size {
+        D::digest(key).to_vec()
+    } else {
+        key.to_vec()
+    };
+    block.resize(block_size, 0);
+
+    let pad = |byte: u8| block.iter().map(|b| b ^ byte).collect::<Vec<u8>>();
+    let mut inner = D::new().chain_update(pad(0x36));
+    message(&mut inner)?;
+    Ok(D::new()
+        .chain_update(pad(0x5c))
+        .chain_update(inner.finalize())
+        .finalize())
+}
+
+/// the proof `M` of the user for [`ProofScheme::Hmac`], keyed by `PAD(K)`, `K`
+/// padded to twice the hash output
+///
+/// formula: `HMAC(PAD(K), H(N) xor H(g) | H(I) | s | PAD(A) | PAD(B))`
+#[allow(non_snake_case)]
+pub fn calculate_proof_hmac<D: Digest>(
+    len: usize,
+    hash_N_xor_g: &[u8],
+    I: UsernameRef,
+    s: &Salt,
+    A: &PublicKey,
+    B: &PublicKey,
+    K: &StrongSessionKey,
+) -> Result<Proof> {
+    let key = pad_vec(K.number(), strong_session_key_length::<D>())?;
+    let mac = hmac::<D>(&key, |h| {
+        h.update(hash_N_xor_g);
+        h.update(D::digest(I.as_bytes()));
+        h.update(s.to_vec());
+        chain_padded(h, A, len).map_err(length_mismatch)?;
+        chain_padded(h, B, len).map_err(length_mismatch)
+    })?;
+    let M = Proof::from_bytes_be(&mac);
+    debug!("M = {:?}", &M);
+
+    Ok(M)
+}
+
+/// the proof `M2` of the host for [`ProofScheme::Hmac`], `M` padded to the
+/// hash output
+///
+/// formula: `HMAC(PAD(K), PAD(A) | PAD(M))`
+#[allow(non_snake_case)]
+pub fn calculate_strong_proof_hmac<D: Digest>(
+    len: usize,
+    A: &PublicKey,
+    M: &Proof,
+    K: &StrongSessionKey,
+) -> Result<StrongProof> {
+    let key = pad_vec(K.number(), strong_session_key_length::<D>())?;
+    let mac = hmac::<D>(&key, |h| {
+        chain_padded(h, A, len).map_err(length_mismatch)?;
+        chain_padded(h, M, <D as Digest>::output_size()).map_err(length_mismatch)
+    })?;
+    let M2 = StrongProof::from_bytes_be(&mac);
+    debug!("M2 = {:?}", &M2);
 
-    K
+    Ok(M2)
 }
 
+/// the proof `M` of the user, as calculated by `scheme`, and by the variant of
+/// `hashes` for [`ProofScheme::HashConcat`]
 #[allow(non_snake_case)]
-pub(crate) fn calculate_proof_M<const LEN: usize>(
-    N: &PrimeModulus,
-    g: &Generator,
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn calculate_proof<D: Digest>(
+    len: usize,
+    hashes: &GroupHashes,
     I: UsernameRef,
     s: &Salt,
     A: &PublicKey,
     B: &PublicKey,
+    S: &SessionKey,
     K: &StrongSessionKey,
-) -> Proof {
-    let xor_hash: Hash = calculate_hash_N_xor_g::<LEN>(N, g);
-    let username_hash = HashFunc::new().chain(I.as_bytes()).finalize();
-    debug!("H(I) = {:?}", &username_hash);
-
-    let M: Proof = HashFunc::new()
-        .chain(xor_hash)
-        .chain(username_hash)
-        .chain(s.to_array_pad_zero::<LEN>())
-        .chain(A.to_array_pad_zero::<LEN>())
-        .chain(B.to_array_pad_zero::<LEN>())
-        .chain(K.to_array_pad_zero::<STRONG_SESSION_KEY_LENGTH>())
-        .into();
-
-    debug!("M = {:?}", &M);
-
-    M
+    profile: CompatProfile,
+    scheme: ProofScheme,
+) -> Result<Proof> {
+    let hash_N_xor_g = &hashes.hash_N_xor_g;
+    match scheme {
+        ProofScheme::HashConcat if hashes.variant == Srp6Variant::Srp6aV2 => {
+            Ok(calculate_proof_M_v2::<D>(hash_N_xor_g, I, s, A, B, K))
+        }
+        ProofScheme::HashConcat => {
+            calculate_proof_M::<D>(len, hash_N_xor_g, I, s, A, B, S, K, profile)
+        }
+        ProofScheme::Hmac => calculate_proof_hmac::<D>(len, hash_N_xor_g, I, s, A, B, K),
+    }
 }
 
-/// todo(verify): check if padding is needed or not
-/// formula: `H(A | M | K)`
+/// the proof `M2` of the host, as calculated by `scheme`
 #[allow(non_snake_case)]
-pub(crate) fn calculate_strong_proof_M2<const LEN: usize>(
+pub(crate) fn calculate_strong_proof<D: Digest>(
+    len: usize,
     A: &PublicKey,
     M: &Proof,
+    S: &SessionKey,
     K: &StrongSessionKey,
-) -> StrongProof {
-    let M2: StrongProof = HashFunc::new()
-        .chain(A.to_array_pad_zero::<LEN>())
-        .chain(M.to_array_pad_zero::<HASH_LENGTH>())
-        .chain(K.to_array_pad_zero::<STRONG_SESSION_KEY_LENGTH>())
-        .into();
-    debug!("M2 = {:?}", &M2);
-
-    M2
+    profile: CompatProfile,
+    scheme: ProofScheme,
+) -> Result<StrongProof> {
+    match scheme {
+        ProofScheme::HashConcat => calculate_strong_proof_M2::<D>(len, A, M, S, K, profile),
+        ProofScheme::Hmac => calculate_strong_proof_hmac::<D>(len, A, M, K),
+    }
 }
 
 /// here we hash g and xor it with the hash of N
 ///
+/// `g` is not padded here, as in RFC 2945, by all the profiles
+///
 /// ```plain
 /// M = H(H(N) xor H(g), H(I), s, A, B, K)
 ///       `````````````
 ///                    // this portion is calculated here
 /// ```
 #[allow(non_snake_case)]
-fn calculate_hash_N_xor_g<const KEY_LENGTH: usize>(N: &PrimeModulus, g: &Generator) -> Hash {
-    let mut h = HashFunc::new()
-        .chain(N.to_array_pad_zero::<KEY_LENGTH>())
-        .finalize();
-    let h_g = HashFunc::new().chain(g.to_vec().as_slice()).finalize();
+pub fn calculate_hash_N_xor_g<D: Digest>(
+    len: usize,
+    N: &PrimeModulus,
+    g: &Generator,
+) -> Result<Hash<D>> {
+    let mut h = D::new();
+    chain_padded(&mut h, N, len).map_err(length_mismatch)?;
+    let mut h = h.finalize();
+    let h_g = D::new().chain_update(g.to_vec().as_slice()).finalize();
     for (i, v) in h.iter_mut().enumerate() {
         *v ^= h_g[i];
     }
 
-    let H_n_g: Hash = h.into();
+    let H_n_g: Hash<D> = h;
     debug!("H(N) xor H(g) = {:X?}", &H_n_g);
 
-    H_n_g
+    Ok(H_n_g)
 }
 
 /// here we calculate the `PasswordVerifier` called `v` based on `x`
@@ -291,46 +3146,234 @@ fn calculate_hash_N_xor_g<const KEY_LENGTH: usize>(N: &PrimeModulus, g: &Generat
 /// `N`:  A large safe prime (N = 2q+1, where q is prime)
 /// formula: `v = g^x % N`
 #[allow(non_snake_case)]
-pub(crate) fn calculate_password_verifier_v(
+pub fn calculate_password_verifier_v(
     N: &PrimeModulus,
     g: &Generator,
+    g_pow: Option<&FixedBasePowTable>,
     x: &PrivateKey,
 ) -> PasswordVerifier {
-    g.modpow(x, N)
+    pow_g(N, g, g_pow, x)
+}
+
+/// `g^e % N`, with the precomputed powers of `g` if there are some
+#[allow(non_snake_case)]
+fn pow_g(
+    N: &PrimeModulus,
+    g: &Generator,
+    g_pow: Option<&FixedBasePowTable>,
+    e: &BigNumber,
+) -> BigNumber {
+    match g_pow {
+        Some(table) => table.pow(e),
+        None => g.modpow(e, N),
+    }
+}
+
+/// [`UserDetails`] for the [`Salt`] `s`, with the [`PasswordVerifier`] `v` from [`calculate_private_key_x`]
+///
+/// `x` is calculated with the identity given by `identity`, not always the username `I`,
+/// and with the `normalization` feature the username is checked by [`validate_username`]
+#[allow(non_snake_case)]
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn calculate_user_details<D: Digest>(
+    N: &PrimeModulus,
+    g: &Generator,
+    g_pow: Option<&FixedBasePowTable>,
+    I: UsernameRef,
+    p: &ClearTextPassword,
+    s: Salt,
+    identity: IdentityMode,
+    profile: CompatProfile,
+    variant: Srp6Variant,
+    group_fingerprint: Option<[u8; 32]>,
+) -> Result<UserDetails> {
+    check_modulus(N)?;
+    let (I, p) = normalized_credentials(I, p, cfg!(feature = "normalization"))?;
+    let x = private_key_x::<D>(identity.identity(&I), &p, &s, profile, variant);
+    let verifier = calculate_password_verifier_v(N, g, g_pow, &x);
+
+    Ok(UserDetails {
+        username: I.into(),
+        salt: s,
+        verifier,
+        kdf: None,
+        identity,
+        normalized: cfg!(feature = "normalization"),
+        version: USER_DETAILS_VERSION,
+        group_fingerprint,
+    })
+}
+
+/// checks that neither the username `I` nor the password `p` is empty, on both
+/// sides: the verifier of an empty password is a constant of the salt
+#[allow(non_snake_case)]
+pub(crate) fn check_credentials(I: UsernameRef, p: &ClearTextPassword) -> Result<()> {
+    if I.is_empty() {
+        return Err(Srp6Error::EmptyUsername);
+    }
+    if p.is_empty() {
+        return Err(Srp6Error::EmptyPassword);
+    }
+    Ok(())
+}
+
+/// `s` in Unicode NFC with the `normalization` feature, unchanged otherwise, so
+/// that the same characters typed on different platforms give the same hash
+pub(crate) fn normalize(s: &str) -> Cow<'_, str> {
+    #[cfg(feature = "normalization")]
+    {
+        use unicode_normalization::{is_nfc, UnicodeNormalization};
+        if !is_nfc(s) {
+            return Cow::Owned(s.nfc().collect());
+        }
+    }
+    Cow::Borrowed(s)
+}
+
+/// the username `I` and the password `p` as hashed by a user, not empty, and with
+/// `normalization`: checked by [`validate_username`] and normalized, or unchanged
+#[allow(non_snake_case)]
+pub(crate) fn normalized_credentials<'a>(
+    I: UsernameRef<'a>,
+    p: &'a ClearTextPassword,
+    normalization: bool,
+) -> Result<(Cow<'a, str>, Cow<'a, str>)> {
+    check_credentials(I, p)?;
+    if !normalization {
+        return Ok((Cow::Borrowed(I), Cow::Borrowed(p)));
+    }
+    validate_username(I)?;
+    Ok((normalize(I), normalize(p)))
+}
+
+/// checks that the username `I` has no `:`, which separates it from the
+/// password in `H(I | ":" | p)`, and no control characters
+///
+/// it is checked with the `normalization` feature, when the user normalizes
+/// the username and the password, see [`UserDetails::normalized`]
+#[allow(non_snake_case)]
+pub fn validate_username(I: UsernameRef) -> Result<()> {
+    if I.chars().any(|c| c == ':' || c.is_control()) {
+        return Err(Srp6Error::InvalidUsername);
+    }
+    Ok(())
 }
 
 /// `u` is the hash of host's and client's [`PublicKey`]
-/// formula: `H(PAD(A) | PAD(B))`
+/// formula: `H(PAD(A) | PAD(B))`, `H(A | B)` for [`CompatProfile::PySrp`]
 #[allow(non_snake_case)]
-pub(crate) fn calculate_u<const KEY_LENGTH: usize>(A: &PublicKey, B: &PublicKey) -> BigNumber {
-    let u = hash::<KEY_LENGTH>(A, B);
+pub fn calculate_u<D: Digest>(
+    len: usize,
+    A: &PublicKey,
+    B: &PublicKey,
+    profile: CompatProfile,
+) -> Result<BigNumber> {
+    let u = if profile.pads_hash_inputs() {
+        hash::<D>(len, A, B).map_err(length_mismatch)?
+    } else {
+        BigNumber::from_digest(D::new().chain_update(A.to_vec()).chain_update(B.to_vec()))
+    };
     debug!("u = {:?}", &u);
 
-    u
+    Ok(u)
+}
+
+/// safeguard: both sides abort if `u == 0`
+pub(crate) fn check_scrambling_parameter(u: &BigNumber) -> Result<()> {
+    if u.is_zero() {
+        return Err(Srp6Error::InvalidScramblingParameter);
+    }
+    Ok(())
+}
+
+/// Checks the peer's [`PublicKey`], `A` on the host or `B` on the user side
+///
+/// It must fit in `LEN` bytes and be in `[2, N - 2]`: a key that is `0`
+/// modulo `N`, `1` or `N - 1` gives the attacker a known premaster secret `S`,
+/// up to its sign. The handshakes run it first; a server can run it on `A`
+/// before it loads the user's details.
+///
+/// Every reason has its own error: [`Srp6Error::KeyLengthMismatch`],
+/// [`Srp6Error::InvalidPublicKey`] if it is `0` modulo `N`,
+/// [`Srp6Error::PublicKeyOutOfRange`], [`Srp6Error::PublicKeyIsOne`] and
+/// [`Srp6Error::PublicKeyIsModulusMinusOne`].
+pub fn validate_public_key<const LEN: usize>(
+    key: &PublicKey,
+    constants: &OpenConstants<LEN>,
+) -> Result<()> {
+    check_public_key(LEN, &constants.module, key)
+}
+
+/// safeguard: see [`validate_public_key`], for a group of `len` bytes
+#[allow(non_snake_case)]
+pub(crate) fn check_public_key(len: usize, N: &PrimeModulus, key: &PublicKey) -> Result<()> {
+    check_modulus(N)?;
+    if key.num_bytes() > len {
+        return Err(Srp6Error::KeyLengthMismatch {
+            given: key.num_bytes(),
+            expected: len,
+        });
+    }
+    if (key % N).is_zero() {
+        return Err(Srp6Error::InvalidPublicKey(key.clone()));
+    }
+    if key >= N {
+        return Err(Srp6Error::PublicKeyOutOfRange(key.clone()));
+    }
+    if key == &PublicKey::from(1) {
+        return Err(Srp6Error::PublicKeyIsOne);
+    }
+    if key == &(N - &PublicKey::from(1)) {
+        return Err(Srp6Error::PublicKeyIsModulusMinusOne);
+    }
+    Ok(())
+}
+
+/// safeguard: `N` must not be zero, [`BigNumber::modpow`] would panic; it only
+/// comes from a misconfigured [`OpenConstants`]
+#[allow(non_snake_case)]
+pub(crate) fn check_modulus(N: &PrimeModulus) -> Result<()> {
+    if N.is_zero() {
+        return Err(Srp6Error::InvalidConfiguration("zero modulus"));
+    }
+    Ok(())
+}
+
+/// safeguard: our own [`PublicKey`] `A` or `B` must not be `0`, `1` or `N - 1`,
+/// the private key is drawn again otherwise
+#[allow(non_snake_case)]
+pub(crate) fn is_trivial_public_key(N: &PrimeModulus, key: &PublicKey) -> bool {
+    key.is_zero() || key == &PublicKey::from(1) || key == &(N - &PublicKey::from(1))
 }
 
 /// `A` is the [`PublicKey`] of the client
 /// formula: `A = g^a % N`
 #[allow(non_snake_case)]
-pub(crate) fn calculate_pubkey_A(N: &PrimeModulus, g: &Generator, a: &PrivateKey) -> PublicKey {
-    let A = g.modpow(a, N);
+pub fn calculate_pubkey_A(
+    N: &PrimeModulus,
+    g: &Generator,
+    g_pow: Option<&FixedBasePowTable>,
+    a: &PrivateKey,
+) -> PublicKey {
+    let A = pow_g(N, g, g_pow, a);
     debug!("A = {:?}", &A);
 
     A
 }
 
-/// [`PublicKey`][B] is the hosts public key
+/// `B` is the hosts [`PublicKey`]
 /// `B = kv + g^b`
 #[allow(non_snake_case)]
-pub(crate) fn calculate_pubkey_B<const LEN: usize>(
+pub fn calculate_pubkey_B(
     N: &PrimeModulus,
     g: &Generator,
+    g_pow: Option<&FixedBasePowTable>,
+    k: &MultiplierParameter,
     v: &PasswordVerifier,
     b: &PrivateKey,
 ) -> PublicKey {
-    let g_mod_N = g.modpow(b, N);
-    let k = calculate_k::<LEN>(N, g);
-    let B = &((&k * v) + g_mod_N) % N;
+    let g_mod_N = pow_g(N, g, g_pow, b);
+    let B = &((k * v) + g_mod_N) % N;
     debug!("B = {:?}", &B);
 
     B
@@ -344,64 +3387,1262 @@ pub(crate) fn calculate_pubkey_B<const LEN: usize>(
 /// x:  Private key (derived from p and s)
 /// ph = H(I, ':', p)           (':' is a string literal)
 /// x = H(s, ph)                (s is chosen randomly)
+///
+/// `s` is hashed in big endian without its leading zero bytes, as the salt is
+/// a number; with [`CompatProfile::PySrp`], the leading zero bytes of `ph` are
+/// left out too
 #[allow(non_snake_case)]
 #[allow(dead_code)]
-pub(crate) fn calculate_private_key_x(
+pub fn calculate_private_key_x<D: Digest>(
+    I: UsernameRef,
+    p: &ClearTextPassword,
+    s: &Salt,
+    profile: CompatProfile,
+) -> PrivateKey {
+    let ph = calculate_p_hash::<D>(I, p);
+    let ph = match profile {
+        CompatProfile::PySrp => BigNumber::from_bytes_be(&ph).to_vec(),
+        _ => ph.to_vec(),
+    };
+    let x = D::new()
+        .chain_update(s.to_vec().as_slice())
+        .chain_update(ph);
+    let x: PrivateKey = BigNumber::from_digest(x);
+    debug_secret!("x = {:?}", &x);
+
+    x
+}
+
+/// `x` of [`Srp6Variant::Srp6aV2`], the profile does not change it
+///
+/// formula: `x = H(LEN("srp6 x v2") | "srp6 x v2" | LEN(s) | s | LEN(I) | I | LEN(p) | p)`,
+/// where `LEN` is the length in 4 bytes big endian
+#[allow(non_snake_case)]
+pub fn calculate_private_key_x_v2<D: Digest>(
     I: UsernameRef,
     p: &ClearTextPassword,
     s: &Salt,
 ) -> PrivateKey {
-    let ph = calculate_p_hash(I, p);
-    let x = HashFunc::new().chain(s.to_vec().as_slice()).chain(ph);
-    let x: PrivateKey = x.into();
-    debug!("x = {:?}", &x);
+    let x: PrivateKey = DomainSeparatedHasher::<D>::new("srp6 x v2")
+        .chain_field(&s.to_vec())
+        .chain_field(I.as_bytes())
+        .chain_field(p.as_bytes())
+        .finalize_number();
+    debug_secret!("x = {:?}", &x);
 
     x
 }
 
+/// `x` as calculated by `variant`, and by `profile` before [`Srp6Variant::Srp6aV2`]
+#[allow(non_snake_case)]
+pub(crate) fn private_key_x<D: Digest>(
+    I: UsernameRef,
+    p: &ClearTextPassword,
+    s: &Salt,
+    profile: CompatProfile,
+    variant: Srp6Variant,
+) -> PrivateKey {
+    match variant {
+        Srp6Variant::Srp6aV2 => calculate_private_key_x_v2::<D>(I, p, s),
+        Srp6Variant::Srp6a | Srp6Variant::Legacy => calculate_private_key_x::<D>(I, p, s, profile),
+    }
+}
+
+/// length of the stretched password hash fed into `x`
+const STRETCHED_LENGTH: usize = 32;
+
+/// stretched version of [`calculate_private_key_x`]
+///
+/// ph = KDF(H(I, ':', p), s)   (KDF as given by `kdf`)
+/// x = H(s, ph)
+#[allow(non_snake_case)]
+pub(crate) fn calculate_private_key_x_stretched<D: Digest>(
+    I: UsernameRef,
+    p: &ClearTextPassword,
+    s: &Salt,
+    kdf: &KdfParams,
+) -> Result<PrivateKey> {
+    let ph = calculate_p_hash::<D>(I, p);
+    let salt = s.to_vec();
+    let mut stretched = [0_u8; STRETCHED_LENGTH];
+    match *kdf {
+        KdfParams::Pbkdf2 { iterations } => {
+            if iterations == 0 {
+                return Err(Srp6Error::InvalidKdfParams);
+            }
+            pbkdf2::pbkdf2_hmac::<Sha256>(&ph, &salt, iterations, &mut stretched);
+        }
+        #[cfg(feature = "argon2")]
+        KdfParams::Argon2id {
+            m_cost,
+            t_cost,
+            p_cost,
+        } => {
+            let params = argon2::Params::new(m_cost, t_cost, p_cost, Some(STRETCHED_LENGTH))
+                .map_err(|_| Srp6Error::InvalidKdfParams)?;
+            argon2::Argon2::new(argon2::Algorithm::Argon2id, argon2::Version::V0x13, params)
+                .hash_password_into(&ph, &salt, &mut stretched)
+                .map_err(|_| Srp6Error::InvalidKdfParams)?;
+        }
+    }
+    let x = D::new()
+        .chain_update(salt.as_slice())
+        .chain_update(stretched);
+    let x: PrivateKey = BigNumber::from_digest(x);
+    debug_secret!("x = {:?}", &x);
+
+    Ok(x)
+}
+
 /// hashes the user and the password (used for client private key `x`)
 #[allow(non_snake_case)]
-pub(crate) fn calculate_p_hash(I: UsernameRef, p: &ClearTextPassword) -> Hash {
-    HashFunc::new()
-        .chain(I.as_bytes())
-        .chain(":".as_bytes())
-        .chain(p.as_bytes())
+pub(crate) fn calculate_p_hash<D: Digest>(I: UsernameRef, p: &ClearTextPassword) -> Hash<D> {
+    D::new()
+        .chain_update(I.as_bytes())
+        .chain_update(":".as_bytes())
+        .chain_update(p.as_bytes())
         .finalize()
-        .into()
 }
 
-/// `k = H(N | PAD(g))` (k = 3 for legacy SRP-6)
+/// `k = H(N | PAD(g))` (k = 3 for legacy SRP-6), `H(N | g)` for [`CompatProfile::PySrp`]
+/// and `H(PAD(N) | PAD(g))` for [`CompatProfile::Tssrp6a`]
+///
+/// the numbers are hashed in big endian, `PAD(N)` is `N` itself for a group of
+/// `len` bytes; see [`calculate_hash_N_xor_g`] and [`calculate_private_key_x`]
+/// for the other hashes of the group and the salt
+///
+/// fails with [`Srp6Error::KeyLengthMismatch`] if a padded number is longer than
+/// `len` bytes
 #[allow(non_snake_case)]
-pub(crate) fn calculate_k<const LEN: usize>(
+pub fn calculate_k<D: Digest>(
+    len: usize,
     N: &PrimeModulus,
     g: &Generator,
-) -> MultiplierParameter {
-    HashFunc::new()
-        .chain(N.to_vec().as_slice())
-        .chain(g.to_array_pad_zero::<LEN>())
-        .into()
+    variant: Srp6Variant,
+    profile: CompatProfile,
+) -> Result<MultiplierParameter> {
+    if variant == Srp6Variant::Legacy {
+        return Ok(MultiplierParameter::from(3));
+    }
+    let mut hasher = D::new();
+    if profile.pads_modulus_in_k() {
+        chain_padded(&mut hasher, N, len).map_err(length_mismatch)?;
+    } else {
+        hasher.update(N.to_vec());
+    }
+    if profile.pads_hash_inputs() {
+        chain_padded(&mut hasher, g, len).map_err(length_mismatch)?;
+    } else {
+        hasher.update(g.to_vec());
+    }
+    Ok(BigNumber::from_digest(hasher))
+}
+
+/// [`PrivateKey`] `a`, a random number in `[2, N - 2]`
+#[cfg(feature = "std")]
+pub(crate) fn generate_private_key_a<const LEN: usize>(
+    constants: &OpenConstants<LEN>,
+) -> Result<PrivateKey> {
+    generate_private_key_a_in(LEN, &constants.module)
 }
 
-/// [`PrivateKey`] `a` or `b` is in fact just a big (positive) random number
-pub(crate) fn generate_private_key_a<const KEY_LENGTH: usize>() -> PrivateKey {
+/// [`PrivateKey`] `b`, a random number in `[2, N - 2]`
+#[cfg(feature = "std")]
+pub(crate) fn generate_private_key_b<const LEN: usize>(
+    constants: &OpenConstants<LEN>,
+) -> Result<PrivateKey> {
+    generate_private_key_b_in(LEN, &constants.module)
+}
+
+/// same as [`generate_private_key_a`], for a group of `len` bytes given at runtime
+#[cfg(feature = "std")]
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "norand", allow(unused_variables))]
+pub(crate) fn generate_private_key_a_in(len: usize, N: &PrimeModulus) -> Result<PrivateKey> {
     #[cfg(not(feature = "norand"))]
-    return PrivateKey::new_rand(KEY_LENGTH);
+    return generate_private_key_in(&mut rand::thread_rng(), len, N);
     #[cfg(feature = "norand")]
-    PrivateKey::from_bytes_be(&testdata::A_PRIVATE)
+    check_private_key_range(N).map(|_| PrivateKey::from_bytes_be(&testdata::A_PRIVATE))
 }
 
-/// [`PrivateKey`] `a` or `b` is in fact just a big (positive) random number
-pub(crate) fn generate_private_key_b<const KEY_LENGTH: usize>() -> PrivateKey {
+/// same as [`generate_private_key_b`], for a group of `len` bytes given at runtime
+#[cfg(feature = "std")]
+#[allow(non_snake_case)]
+#[cfg_attr(feature = "norand", allow(unused_variables))]
+pub(crate) fn generate_private_key_b_in(len: usize, N: &PrimeModulus) -> Result<PrivateKey> {
     #[cfg(not(feature = "norand"))]
-    return PrivateKey::new_rand(KEY_LENGTH);
+    return generate_private_key_in(&mut rand::thread_rng(), len, N);
     #[cfg(feature = "norand")]
-    PrivateKey::from_bytes_be(&testdata::B_PRIVATE)
+    check_private_key_range(N).map(|_| PrivateKey::from_bytes_be(&testdata::B_PRIVATE))
 }
 
 /// [`Salt`] `s` is a random number
-pub(crate) fn generate_salt<const SALT_LENGTH: usize>() -> Salt {
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "norand", allow(unused_variables))]
+pub(crate) fn generate_salt(salt_length: usize) -> Salt {
     #[cfg(not(feature = "norand"))]
-    return Salt::new_rand(SALT_LENGTH);
+    return Salt::new_rand(salt_length);
     #[cfg(feature = "norand")]
     PrivateKey::from_bytes_be(&testdata::SALT)
 }
+
+/// [`SessionId`] of a new handshake, random even with `norand`
+#[cfg(feature = "std")]
+pub(crate) fn generate_session_id() -> SessionId {
+    generate_session_id_with(&mut rand::thread_rng())
+}
+
+/// [`SessionId`] of a new handshake from the given random number generator
+pub(crate) fn generate_session_id_with<R: RngCore + CryptoRng + ?Sized>(rng: &mut R) -> SessionId {
+    let mut session_id = SessionId::default();
+    rng.fill_bytes(&mut session_id);
+    session_id
+}
+
+/// [`PrivateKey`] `a` or `b` from the given random number generator, in `[2, N - 2]`
+pub(crate) fn generate_private_key_with<R: RngCore + CryptoRng + ?Sized, const LEN: usize>(
+    rng: &mut R,
+    constants: &OpenConstants<LEN>,
+) -> Result<PrivateKey> {
+    generate_private_key_in(rng, LEN, &constants.module)
+}
+
+/// `key` for the first draw of `a` or `b`, a second one would loop forever on the
+/// same key: it fails with [`Srp6Error::InvalidConfiguration`] and `trivial`
+#[cfg(any(test, feature = "deterministic"))]
+pub(crate) fn given_key<'a>(
+    key: &'a PrivateKey,
+    trivial: &'static str,
+) -> impl FnMut() -> Result<PrivateKey> + 'a {
+    let mut drawn = false;
+    move || {
+        if drawn {
+            return Err(Srp6Error::InvalidConfiguration(trivial));
+        }
+        drawn = true;
+        Ok(key.clone())
+    }
+}
+
+/// [`PrivateKey`] `a` or `b` of `len` bytes from `rng`, drawn again until it
+/// is in `[2, N - 2]` (e.g. not `0` from a broken generator)
+///
+/// at most as many bytes as `N` are drawn, so that a short `N` does not make
+/// the redraws endless
+#[allow(non_snake_case)]
+pub(crate) fn generate_private_key_in<R: RngCore + CryptoRng + ?Sized>(
+    rng: &mut R,
+    len: usize,
+    N: &PrimeModulus,
+) -> Result<PrivateKey> {
+    check_private_key_range(N)?;
+    let min = PrivateKey::from(2);
+    let max = N - &min;
+    let len = len.min(N.num_bytes());
+    loop {
+        let key = PrivateKey::new_rand_with(rng, len);
+        if key >= min && key <= max {
+            return Ok(key);
+        }
+    }
+}
+
+/// safeguard: `[2, N - 2]` must not be empty to draw a private key, i.e. `N > 3`
+#[allow(non_snake_case)]
+fn check_private_key_range(N: &PrimeModulus) -> Result<()> {
+    check_modulus(N)?;
+    if N <= &PrimeModulus::from(3) {
+        return Err(Srp6Error::InvalidConfiguration("modulus too small"));
+    }
+    Ok(())
+}
+
+/// [`Salt`] `s` from the given random number generator
+pub(crate) fn generate_salt_with<R: RngCore + CryptoRng + ?Sized>(
+    rng: &mut R,
+    salt_length: usize,
+) -> Salt {
+    Salt::new_rand_with(rng, salt_length)
+}
+
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+    use super::*;
+    use crate::hash::Sha1;
+    use crate::protocol_details::testdata;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn should_reject_zero_scrambling_parameter() {
+        assert_eq!(
+            check_scrambling_parameter(&BigNumber::zero()),
+            Err(Srp6Error::InvalidScramblingParameter)
+        );
+        assert_eq!(
+            check_scrambling_parameter(&BigNumber::from_bytes_be(&[0, 0, 0])),
+            Err(Srp6Error::InvalidScramblingParameter)
+        );
+        assert!(check_scrambling_parameter(&BigNumber::from(1)).is_ok());
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn should_subtract_modulo_in_client_S() {
+        let constants = OpenConstants::<128>::default();
+        let (N, g) = (&*constants.module, &constants.generator);
+        let (k, a, x) = (BigNumber::from(3), BigNumber::from(5), BigNumber::from(7));
+        // forces `B < k * g^x`, which the plain subtraction would underflow on
+        let B = BigNumber::from(2);
+        let A = g.modpow(&a, N);
+        let to_sub = &(&k * g.modpow(&x, N)) % N;
+        assert!(B < to_sub);
+        let base = B.sub_mod(&(&k * g.modpow(&x, N)), N);
+        assert_eq!(base, (N - &to_sub) + &B);
+
+        let S = calculate_session_key_S_for_client::<Sha1>(
+            128,
+            N,
+            g,
+            &k,
+            &B,
+            &A,
+            &a,
+            &x,
+            CompatProfile::default(),
+        )
+        .unwrap();
+        let u = calculate_u::<Sha1>(128, &A, &B, CompatProfile::default()).unwrap();
+        assert_eq!(S, base.modpow(&(a + u * x), N));
+    }
+
+    /// the blinded exponents give the same `S` on both sides, in the groups of
+    /// 2048 bits with `g = 2` and of 4096 bits with `g = 5`
+    #[cfg(feature = "blinding")]
+    #[test]
+    #[allow(non_snake_case)]
+    fn should_blind_the_exponents_of_S() {
+        fn check<const LEN: usize>(constants: OpenConstants<LEN>) {
+            let mut rng = StdRng::seed_from_u64(5054);
+            let (N, g) = (&*constants.module, &constants.generator);
+            let profile = CompatProfile::default();
+            let hashes = constants.group_hashes::<Sha1>().unwrap();
+            let (k, q) = (hashes.k(profile), hashes.blinding_order().unwrap());
+            assert_eq!(&(q * BigNumber::from(2) + BigNumber::from(1)), N);
+            let a = generate_private_key_with(&mut rng, &constants).unwrap();
+            let b = generate_private_key_with(&mut rng, &constants).unwrap();
+            let x = BigNumber::new_rand_with(&mut rng, 20);
+            let v = calculate_password_verifier_v(N, g, None, &x);
+            let A = calculate_pubkey_A(N, g, None, &a);
+            let B = calculate_pubkey_B(N, g, None, k, &v, &b);
+
+            let host = |q| session_key_S_for_host::<Sha1>(LEN, N, &A, &B, &b, &v, profile, q);
+            let user =
+                |q| session_key_S_for_client::<Sha1>(LEN, N, g, k, &B, &A, &a, &x, profile, q);
+            let S = host(None).unwrap();
+            assert_eq!(user(None).unwrap(), S);
+            for _ in 0..3 {
+                assert_eq!(host(Some(q)).unwrap(), S, "{LEN} bytes");
+                assert_eq!(user(Some(q)).unwrap(), S, "{LEN} bytes");
+            }
+            assert_ne!(blind_exponent(&b, q), b);
+        }
+        check(OpenConstants::<256>::default());
+        check(OpenConstants::<512>::default());
+
+        // `g = 5` is not a square, a multiple of `q` alone would change `S`
+        let constants = OpenConstants::<512>::default();
+        let (N, g) = (&*constants.module, &constants.generator);
+        let q = N.subgroup_order();
+        assert_eq!(g, &Generator::from(5));
+        assert_ne!(g.modpow(&q, N), BigNumber::from(1));
+    }
+
+    /// yields zeros for the first `zeros` bytes, then the bytes of `inner`
+    struct ZerosFirst {
+        zeros: usize,
+        inner: StdRng,
+    }
+
+    impl RngCore for ZerosFirst {
+        fn next_u32(&mut self) -> u32 {
+            let mut bytes = [0; 4];
+            self.fill_bytes(&mut bytes);
+            u32::from_le_bytes(bytes)
+        }
+
+        fn next_u64(&mut self) -> u64 {
+            let mut bytes = [0; 8];
+            self.fill_bytes(&mut bytes);
+            u64::from_le_bytes(bytes)
+        }
+
+        fn fill_bytes(&mut self, dest: &mut [u8]) {
+            if self.zeros > 0 {
+                dest.fill(0);
+                self.zeros = self.zeros.saturating_sub(dest.len());
+            } else {
+                self.inner.fill_bytes(dest);
+            }
+        }
+
+        fn try_fill_bytes(&mut self, dest: &mut [u8]) -> core::result::Result<(), rand::Error> {
+            self.fill_bytes(dest);
+            Ok(())
+        }
+    }
+
+    impl CryptoRng for ZerosFirst {}
+
+    #[test]
+    fn should_read_proofs_of_exactly_the_hash_length() {
+        // `M` of the RFC 5054 test vectors, with SHA-1
+        let be = hex_literal::hex!("5007FFD2 5BC1A82A 4A4131F6 C88BFDDC 2587571A");
+        let mut le = be;
+        le.reverse();
+        let M = Proof::from_bytes_be(&be);
+        assert_eq!(
+            proof_from_bytes::<Sha1>(&be, ByteOrder::BigEndian),
+            Ok(M.clone())
+        );
+        assert_eq!(
+            proof_from_bytes::<Sha1>(&le, ByteOrder::LittleEndian),
+            Ok(M.clone())
+        );
+        assert_eq!(
+            ProofMessage::from_bytes::<Sha1>(&be).unwrap().proof,
+            proof_from_bytes::<Sha1>(&be, ByteOrder::BigEndian).unwrap()
+        );
+        // leading zero bytes are part of the frame
+        let mut zeros = [0_u8; 20];
+        zeros[19] = 1;
+        assert_eq!(
+            proof_from_bytes::<Sha1>(&zeros, ByteOrder::BigEndian),
+            Ok(Proof::from(1))
+        );
+        for len in [0, 19, 21, 32] {
+            assert_eq!(
+                proof_from_bytes::<Sha1>(&vec![1; len], ByteOrder::BigEndian),
+                Err(Srp6Error::KeyLengthMismatch {
+                    given: len,
+                    expected: 20
+                })
+            );
+        }
+        assert!(proof_from_bytes::<Sha256>(&be, ByteOrder::BigEndian).is_err());
+
+        let S = SessionKey::from_bytes_be(&testdata::SECRET);
+        let K = calculate_session_key_hash_interleave_K::<Sha1>(128, &S).unwrap();
+        let interleaved = K.number().to_vec_le();
+        assert_eq!(
+            strong_session_key_from_bytes::<Sha1>(&interleaved, ByteOrder::LittleEndian),
+            Ok(K.clone())
+        );
+        assert_eq!(
+            strong_session_key_from_bytes::<Sha1>(&interleaved[..20], ByteOrder::LittleEndian),
+            Err(Srp6Error::KeyLengthMismatch {
+                given: 20,
+                expected: 40
+            })
+        );
+    }
+
+    #[test]
+    fn should_convert_proofs_of_exactly_the_hash_length() {
+        // `M` of the RFC 5054 test vectors, with SHA-1
+        let be = hex_literal::hex!("5007FFD2 5BC1A82A 4A4131F6 C88BFDDC 2587571A");
+        let mut le = be;
+        le.reverse();
+        let M = Proof::from_hex_str_be("5007FFD25BC1A82A4A4131F6C88BFDDC2587571A").unwrap();
+        assert_eq!(Proof::from_proof_bytes_be(&be), M);
+        assert_eq!(Proof::from_proof_bytes_le(&le), M);
+        assert_eq!(Proof::try_from(&be[..]), Ok(M.clone()));
+        assert_eq!(
+            StrongProof::from_proof_bytes_le(&le),
+            StrongProof::from(M.number().clone())
+        );
+        assert_eq!(
+            StrongProof::try_from(&be[..]).map(BigNumber::from),
+            Ok(M.number().clone())
+        );
+        for len in [0, 19, 21, 32] {
+            let mismatch = Srp6Error::KeyLengthMismatch {
+                given: len,
+                expected: HASH_LENGTH,
+            };
+            assert_eq!(Proof::try_from(&vec![1; len][..]), Err(mismatch.clone()));
+            assert_eq!(StrongProof::try_from(&vec![1; len][..]), Err(mismatch));
+        }
+        // still serialized and compared as the number
+        let json = serde_json::to_string(&M).unwrap();
+        assert_eq!(json, serde_json::to_string(M.number()).unwrap());
+        assert_eq!(serde_json::from_str::<Proof>(&json).unwrap(), M);
+        assert!(Proof::from(1) < M);
+        assert_eq!(M.to_vec_be(), be);
+    }
+
+    #[test]
+    fn should_validate_public_keys() {
+        let constants = OpenConstants::<256>::default();
+        let N: &PrimeModulus = &constants.module;
+        let one = PublicKey::from(1);
+        assert_eq!(validate_public_key(&PublicKey::from(2), &constants), Ok(()));
+        assert_eq!(
+            validate_public_key(&(N - &PublicKey::from(2)), &constants),
+            Ok(())
+        );
+        assert_eq!(
+            validate_public_key(&PublicKey::zero(), &constants),
+            Err(Srp6Error::InvalidPublicKey(PublicKey::zero()))
+        );
+        assert_eq!(
+            validate_public_key(N, &constants),
+            Err(Srp6Error::InvalidPublicKey(N.clone()))
+        );
+        assert_eq!(
+            validate_public_key(&(N + &one), &constants),
+            Err(Srp6Error::PublicKeyOutOfRange(N + &one))
+        );
+        assert_eq!(
+            validate_public_key(&one, &constants),
+            Err(Srp6Error::PublicKeyIsOne)
+        );
+        assert_eq!(
+            validate_public_key(&(N - &one), &constants),
+            Err(Srp6Error::PublicKeyIsModulusMinusOne)
+        );
+        // longer than `LEN` even if it were reduced
+        let too_long = PublicKey::from_bytes_be(&[0xFF; 257]);
+        assert_eq!(
+            validate_public_key(&too_long, &constants),
+            Err(Srp6Error::KeyLengthMismatch {
+                given: 257,
+                expected: 256,
+            })
+        );
+    }
+
+    #[test]
+    fn should_reject_trivial_public_keys() {
+        let N = &*OpenConstants::<256>::default().module;
+        assert!(is_trivial_public_key(N, &PublicKey::zero()));
+        assert!(is_trivial_public_key(N, &PublicKey::from(1)));
+        assert!(is_trivial_public_key(N, &(N - &PublicKey::from(1))));
+        assert!(!is_trivial_public_key(N, &PublicKey::from(2)));
+    }
+
+    #[test]
+    fn should_draw_private_key_again() {
+        let constants = OpenConstants::<256>::default();
+        let (N, g) = (&*constants.module, &constants.generator);
+        // a whole key of zeros first
+        let mut rng = ZerosFirst {
+            zeros: 256,
+            inner: StdRng::seed_from_u64(1),
+        };
+        let a = generate_private_key_with(&mut rng, &constants).unwrap();
+        assert_eq!(rng.zeros, 0, "the zero key was not drawn");
+        assert!(a >= PrivateKey::from(2) && a <= N - &PrivateKey::from(2));
+        assert!(!is_trivial_public_key(
+            N,
+            &calculate_pubkey_A(N, g, None, &a)
+        ));
+
+        let mut rng = ZerosFirst {
+            zeros: 256,
+            inner: StdRng::seed_from_u64(1),
+        };
+        let handshake = crate::Srp6User::<256>::default()
+            .start_handshake_with_rng("alice", &constants, &mut rng)
+            .unwrap();
+        assert_eq!(rng.zeros, 0, "the zero key was not drawn");
+        assert_eq!(handshake.user_publickey, calculate_pubkey_A(N, g, None, &a));
+    }
+
+    #[test]
+    fn should_round_trip_hex_messages() {
+        let A = PublicKey::from_bytes_be(&testdata::A_PUBLIC);
+        let B = PublicKey::from_bytes_be(&testdata::B_PUBLIC);
+        let s = Salt::from_bytes_be(&testdata::SALT);
+
+        let user_handshake = UserHandshake::new("al:ice".to_owned(), A.clone());
+        let back = UserHandshake::from_hex::<128>(&user_handshake.to_hex()).unwrap();
+        assert_eq!(back.username, "al:ice");
+        assert_eq!(back.user_publickey, A);
+
+        let server_handshake = ServerHandshake::new(s.clone(), B.clone());
+        let back = ServerHandshake::from_hex::<128>(&server_handshake.to_string()).unwrap();
+        assert_eq!(back.salt, s);
+        assert_eq!(back.server_publickey, B);
+
+        let proof = ProofMessage::from(Proof::from_bytes_be(&[0xab; 20]));
+        let back = ProofMessage::from_hex::<crate::hash::Sha1>(&proof.to_hex()).unwrap();
+        assert_eq!(back.proof, proof.proof);
+    }
+
+    #[test]
+    fn should_parse_hex_messages() {
+        // lowercase and padded
+        let m = ServerHandshake::from_hex::<4>("00ab:0000c0de").unwrap();
+        assert_eq!(m.salt, BigNumber::from(0xab));
+        assert_eq!(m.server_publickey, BigNumber::from(0xc0de));
+        assert_eq!(m.to_hex(), "AB:C0DE");
+
+        assert_eq!(
+            ServerHandshake::from_hex::<4>("ab:0102030405").unwrap_err(),
+            Srp6Error::KeyLengthMismatch {
+                given: 5,
+                expected: 4
+            }
+        );
+        assert_eq!(
+            ServerHandshake::from_hex::<4>("abcd").unwrap_err(),
+            Srp6Error::MalformedMessage
+        );
+        assert_eq!(
+            ServerHandshake::from_hex::<4>("ab:").unwrap_err(),
+            Srp6Error::MalformedMessage
+        );
+        assert_eq!(
+            ServerHandshake::from_hex::<4>("ab:xy").unwrap_err(),
+            Srp6Error::InvalidHex
+        );
+        assert_eq!(
+            UserHandshake::from_hex::<4>(":ab").unwrap_err(),
+            Srp6Error::EmptyUsername
+        );
+        assert_eq!(
+            ProofMessage::from_hex::<crate::hash::Sha1>(&"a".repeat(42)).unwrap_err(),
+            Srp6Error::KeyLengthMismatch {
+                given: 21,
+                expected: 20
+            }
+        );
+    }
+
+    #[test]
+    fn should_round_trip_binary_messages() {
+        let A = PublicKey::from_bytes_be(&testdata::A_PUBLIC);
+        let B = PublicKey::from_bytes_be(&testdata::B_PUBLIC);
+        let s = Salt::from_bytes_be(&testdata::SALT);
+
+        let user_handshake = UserHandshake::new("alïce".to_owned(), A.clone());
+        let bytes = user_handshake.to_bytes::<128>().unwrap();
+        assert_eq!(bytes.len(), 2 + "alïce".len() + 128);
+        assert_eq!(
+            UserHandshake::from_bytes::<128>(&bytes).unwrap(),
+            user_handshake
+        );
+
+        let server_handshake = ServerHandshake::new(s.clone(), B.clone());
+        let bytes = server_handshake.to_bytes::<256>().unwrap();
+        assert_eq!(bytes.len(), 2 + testdata::SALT.len() + 256);
+        assert_eq!(
+            ServerHandshake::from_bytes::<256>(&bytes).unwrap(),
+            server_handshake
+        );
+
+        let proof = ProofMessage::from(Proof::from_bytes_be(&[0xab; 20]));
+        let bytes = proof.to_bytes::<crate::hash::Sha1>().unwrap();
+        let back = ProofMessage::from_bytes::<crate::hash::Sha1>(&bytes).unwrap();
+        assert_eq!(back, proof);
+
+        let user_details = UserDetails {
+            username: "alice".into(),
+            salt: s.clone(),
+            verifier: B.clone(),
+            kdf: Some(KdfParams::Pbkdf2 { iterations: 1000 }),
+            identity: IdentityMode::OpaqueId("42".to_owned()),
+            normalized: true,
+            version: USER_DETAILS_VERSION,
+            group_fingerprint: None,
+        };
+        let bytes = user_details.to_bytes::<128>().unwrap();
+        assert_eq!(
+            UserDetails::from_bytes::<128>(&bytes).unwrap(),
+            user_details
+        );
+
+        // too long for the group
+        assert_eq!(
+            server_handshake.to_bytes::<64>().unwrap_err(),
+            Srp6Error::KeyLengthMismatch {
+                given: 128,
+                expected: 64
+            }
+        );
+    }
+
+    #[test]
+    fn should_parse_binary_messages() {
+        // the layout is part of the protocol, it must not change
+        let m =
+            UserHandshake::from_bytes::<4>(&[0, 3, b'B', b'o', b'b', 0, 0, 0xc0, 0xde]).unwrap();
+        assert_eq!(m.username, "Bob");
+        assert_eq!(m.user_publickey, BigNumber::from(0xc0de));
+        assert_eq!(
+            m.to_bytes::<4>().unwrap(),
+            [0, 3, b'B', b'o', b'b', 0, 0, 0xc0, 0xde]
+        );
+
+        let bytes = [0, 1, 0xab, 0, 0, 0xc0, 0xde];
+        let m = ServerHandshake::from_bytes::<4>(&bytes).unwrap();
+        assert_eq!(m.salt, BigNumber::from(0xab));
+        assert_eq!(m.server_publickey, BigNumber::from(0xc0de));
+        assert_eq!(m.to_bytes::<4>().unwrap(), bytes);
+
+        let mut bytes = vec![0; 19];
+        bytes.push(0x2a);
+        let m = ProofMessage::from_bytes::<crate::hash::Sha1>(&bytes).unwrap();
+        assert_eq!(m.proof, Proof::from(0x2a));
+        assert_eq!(m.to_bytes::<crate::hash::Sha1>().unwrap(), bytes);
+
+        let bytes = [
+            0, 3, b'B', b'o', b'b', 0, 1, 0xab, 0, 0, 0xc0, 0xde, 1, 0, 0, 0x03, 0xe8, 0,
+        ];
+        let m = UserDetails::from_bytes::<4>(&bytes).unwrap();
+        assert_eq!(m.username, "Bob");
+        assert_eq!(m.salt, BigNumber::from(0xab));
+        assert_eq!(m.verifier, BigNumber::from(0xc0de));
+        assert_eq!(m.kdf, Some(KdfParams::Pbkdf2 { iterations: 1000 }));
+        assert_eq!(m.identity, IdentityMode::Username);
+        assert_eq!(m.to_bytes::<4>().unwrap(), bytes);
+        assert_eq!(
+            UserDetails::from_bytes::<4>(&bytes[..13]).unwrap_err(),
+            Srp6Error::TruncatedMessage
+        );
+        let m = UserDetails::from_bytes::<4>(&[&bytes[..12], &[0, 1]].concat()).unwrap();
+        assert_eq!(m.kdf, None);
+        assert_eq!(m.identity, IdentityMode::Blank);
+        let m = UserDetails::from_bytes::<4>(&[&bytes[..12], &[0, 2, 0, 2, b'4', b'2']].concat())
+            .unwrap();
+        assert_eq!(m.identity, IdentityMode::OpaqueId("42".to_owned()));
+        assert_eq!(
+            UserDetails::from_bytes::<4>(&[&bytes[..12], &[0, 3]].concat()).unwrap_err(),
+            Srp6Error::MalformedMessage
+        );
+
+        assert_eq!(
+            ServerHandshake::from_bytes::<4>(&[0, 1, 0xab, 0, 0, 0xc0]).unwrap_err(),
+            Srp6Error::TruncatedMessage
+        );
+        assert_eq!(
+            ServerHandshake::from_bytes::<4>(&[0, 1, 0xab, 0, 0, 0xc0, 0xde, 0]).unwrap_err(),
+            Srp6Error::TrailingBytes { count: 1 }
+        );
+        assert_eq!(
+            ServerHandshake::from_bytes::<4>(&[0, 5, 1, 2, 3, 4, 5, 0, 0, 0xc0, 0xde]).unwrap_err(),
+            Srp6Error::KeyLengthMismatch {
+                given: 5,
+                expected: 4
+            }
+        );
+        assert_eq!(
+            ServerHandshake::from_bytes::<4>(&[0, 0, 0, 0, 0xc0, 0xde]).unwrap_err(),
+            Srp6Error::MalformedMessage
+        );
+        assert_eq!(
+            UserHandshake::from_bytes::<4>(&[0, 0, 0, 0, 0xc0, 0xde]).unwrap_err(),
+            Srp6Error::EmptyUsername
+        );
+        assert_eq!(
+            UserHandshake::from_bytes::<4>(&[0, 1, 0xff, 0, 0, 0xc0, 0xde]).unwrap_err(),
+            Srp6Error::InvalidUsername
+        );
+        assert_eq!(
+            UserDetails::from_bytes::<4>(&[&bytes[..12], &[9]].concat()).unwrap_err(),
+            Srp6Error::InvalidKdfParams
+        );
+    }
+
+    /// the RFC 5054 group of `LEN` bytes, checked as a custom one
+    fn check_rfc_group<const LEN: usize>(rounds: usize) -> Result<OpenConstants<LEN>>
+    where
+        OpenConstants<LEN>: Default,
+    {
+        let rfc = OpenConstants::<LEN>::default();
+        let mut rng = StdRng::seed_from_u64(3);
+        OpenConstants::<LEN>::try_new_with_rng(
+            Arc::unwrap_or_clone(rfc.module),
+            rfc.generator,
+            rounds,
+            &mut rng,
+        )
+    }
+
+    #[test]
+    fn should_accept_rfc_groups() {
+        assert!(check_rfc_group::<128>(MILLER_RABIN_ROUNDS).is_ok());
+        assert!(check_rfc_group::<192>(8).is_ok());
+        assert!(check_rfc_group::<256>(8).is_ok());
+        assert!(check_rfc_group::<384>(2).is_ok());
+        assert!(check_rfc_group::<512>(2).is_ok());
+        assert!(check_rfc_group::<32>(MILLER_RABIN_ROUNDS).is_ok());
+    }
+
+    #[test]
+    fn should_interleave_halves_shorter_than_the_hash() {
+        // LEN / 2 = 16 bytes per half, for a hash output of 32 bytes
+        let S = SessionKey::from_bytes_be(&[0xA5; 32]);
+        let K = calculate_session_key_hash_interleave_K::<Sha256>(32, &S).unwrap();
+        let half = <Sha256 as Digest>::digest([0xA5; 16]);
+        let expected: Vec<u8> = half.iter().flat_map(|h| [*h, *h]).collect();
+        assert_eq!(K.number().to_vec_le(), expected);
+        assert_eq!(K.to_bytes().len(), 64);
+
+        // `S` is padded to `len` first by the legacy interleave
+        let S = SessionKey::from(1);
+        let K = calculate_session_key_hash_interleave_padded_K::<Sha256>(32, &S).unwrap();
+        let mut odd = [0_u8; 16];
+        odd[15] = 1;
+        let even = <Sha256 as Digest>::digest([0_u8; 16]);
+        let odd = <Sha256 as Digest>::digest(odd);
+        let expected: Vec<u8> = even
+            .iter()
+            .zip(odd.iter())
+            .flat_map(|(e, o)| [*e, *o])
+            .collect();
+        // the trailing zeros of the little endian bytes are dropped
+        let mut bytes = K.number().to_vec_le();
+        bytes.resize(64, 0);
+        assert_eq!(bytes, expected);
+    }
+
+    #[test]
+    fn should_strip_the_leading_zeros_of_S() {
+        // `SHA_Interleave` of RFC 2945 transcribed in Python with `hashlib`
+        let S = SessionKey::from_bytes_be(&testdata::SECRET);
+        let expected = StrongSessionKey::new::<Sha1>(BigNumber::from_bytes_le(&hex_literal::hex!(
+            "2B8CABCE DE81B976 5A37FC68 FBDE5123 26A15651 2BC0DAC5 FD64D2C7 C3BF857A 56B0C0A8 CEED18C0"
+        )));
+        assert_eq!(
+            calculate_session_key_hash_interleave_K::<Sha1>(128, &S).unwrap(),
+            expected
+        );
+        // no leading zero, both agree
+        assert_eq!(
+            calculate_session_key_hash_interleave_padded_K::<Sha1>(128, &S).unwrap(),
+            expected
+        );
+
+        // two leading zeros are removed, then one more byte for one zero, as the
+        // rest has an odd length: both give the interleave of `SECRET[2..]`
+        let expected = StrongSessionKey::new::<Sha1>(BigNumber::from_bytes_le(&hex_literal::hex!(
+            "DD93ABFD 4BA7D160 5161A053 1AB45CA2 FED3478F 554E150E EE28A4CB FC971329 B16E0F81 C9B19FBD"
+        )));
+        for zeros in [2, 1] {
+            let mut bytes = testdata::SECRET;
+            bytes[..zeros].fill(0);
+            let S = SessionKey::from_bytes_be(&bytes);
+            let K = calculate_session_key_hash_interleave_K::<Sha1>(128, &S).unwrap();
+            assert_eq!(K, expected);
+            assert_eq!(
+                calculate_session_key_K::<Sha1>(128, &S, CompatProfile::Rfc5054),
+                Ok(K.clone())
+            );
+            let legacy = calculate_session_key_K::<Sha1>(128, &S, CompatProfile::LegacyInterleave);
+            assert_ne!(legacy.unwrap(), K);
+        }
+        assert_eq!(
+            calculate_session_key_hash_interleave_K::<Sha1>(127, &S),
+            Err(Srp6Error::KeyLengthMismatch {
+                given: 128,
+                expected: 127
+            })
+        );
+    }
+
+    /// the former implementation, with the padded buffers
+    fn padded_hash<D: Digest>(values: &[(&BigNumber, usize)]) -> BigNumber {
+        let mut hasher = D::new();
+        for (value, len) in values {
+            hasher.update(value.to_vec_pad_zero(*len));
+        }
+        BigNumber::from_digest(hasher)
+    }
+
+    #[test]
+    fn should_hash_as_padded_buffers() {
+        let mut rng = StdRng::seed_from_u64(38);
+        let profile = CompatProfile::Rfc5054;
+        for len in [128, 256, 512, 1024] {
+            for _ in 0..4 {
+                let A = PublicKey::new_rand_with(&mut rng, len);
+                let B = PublicKey::new_rand_with(&mut rng, len);
+                let S = SessionKey::new_rand_with(&mut rng, len);
+                let K = StrongSessionKey::new::<Sha1>(BigNumber::new_rand_with(&mut rng, 40));
+                let M = Proof::new_rand_with(&mut rng, 20);
+                let s = Salt::new_rand_with(&mut rng, 16);
+                let hash_N_xor_g = [0x5a; 20];
+                assert_eq!(
+                    calculate_u::<Sha1>(len, &A, &B, profile).unwrap(),
+                    padded_hash::<Sha1>(&[(&A, len), (&B, len)])
+                );
+                assert_eq!(
+                    calculate_k::<Sha1>(len, &A, &B, Srp6Variant::Srp6a, profile).unwrap(),
+                    padded_hash::<Sha1>(&[(&A, A.num_bytes()), (&B, len)])
+                );
+                let mut hasher = Sha1::new()
+                    .chain_update(hash_N_xor_g)
+                    .chain_update(Sha1::digest("alice"))
+                    .chain_update(s.to_vec());
+                for (value, value_len) in [(&A, len), (&B, len), (K.number(), 40)] {
+                    hasher.update(value.to_vec_pad_zero(value_len));
+                }
+                assert_eq!(
+                    calculate_proof_M::<Sha1>(
+                        len,
+                        &hash_N_xor_g,
+                        "alice",
+                        &s,
+                        &A,
+                        &B,
+                        &S,
+                        &K,
+                        profile
+                    )
+                    .unwrap(),
+                    Proof::from_digest(hasher)
+                );
+                assert_eq!(
+                    calculate_strong_proof_M2::<Sha1>(len, &A, &M, &S, &K, profile).unwrap(),
+                    StrongProof::from(padded_hash::<Sha1>(&[
+                        (&A, len),
+                        (&M, 20),
+                        (K.number(), 40)
+                    ]))
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn should_calculate_hmac_as_the_hmac_crate() {
+        use hmac::{Hmac, Mac};
+
+        fn expected<D: Mac + hmac::digest::KeyInit>(key: &[u8], message: &[u8]) -> Vec<u8> {
+            let mut mac = <D as Mac>::new_from_slice(key).unwrap();
+            mac.update(message);
+            mac.finalize().into_bytes().to_vec()
+        }
+
+        fn message<D: Digest>(h: &mut D) -> Result<()> {
+            h.update(b"Hi ");
+            h.update(b"There");
+            Ok(())
+        }
+
+        // the key of the proofs, and longer than the blocks of 64 and 128 bytes
+        for key in [&[0x0b; 40][..], &[0x0b; 64], &[0xaa; 131]] {
+            assert_eq!(
+                hmac::<Sha1>(key, message).unwrap().to_vec(),
+                expected::<Hmac<Sha1>>(key, b"Hi There")
+            );
+            assert_eq!(
+                hmac::<Sha256>(key, message).unwrap().to_vec(),
+                expected::<Hmac<Sha256>>(key, b"Hi There")
+            );
+            assert_eq!(
+                hmac::<sha2::Sha512>(key, message).unwrap().to_vec(),
+                expected::<Hmac<sha2::Sha512>>(key, b"Hi There")
+            );
+        }
+    }
+
+    /// not given by the RFC, computed apart with the `hmac` module of Python
+    #[test]
+    #[allow(non_snake_case)]
+    fn should_calculate_the_hmac_proofs_of_the_test_vectors() {
+        fn proofs<D: Digest>() -> (Proof, StrongProof) {
+            let constants = OpenConstants::<128>::default();
+            let hash_N_xor_g =
+                calculate_hash_N_xor_g::<D>(128, &constants.module, &constants.generator).unwrap();
+            let A = PublicKey::from_bytes_be(&testdata::A_PUBLIC);
+            let S = SessionKey::from_bytes_be(&testdata::SECRET);
+            let K = calculate_session_key_hash_interleave_K::<D>(128, &S).unwrap();
+            let M = calculate_proof_hmac::<D>(
+                128,
+                &hash_N_xor_g,
+                testdata::USERNAME,
+                &Salt::from_bytes_be(&testdata::SALT),
+                &A,
+                &PublicKey::from_bytes_be(&testdata::B_PUBLIC),
+                &K,
+            )
+            .unwrap();
+            let M2 = calculate_strong_proof_hmac::<D>(128, &A, &M, &K).unwrap();
+            (M, M2)
+        }
+
+        let (M, M2) = proofs::<Sha1>();
+        assert_eq!(
+            M.to_vec(),
+            hex_literal::hex!("B137EEFE A373EEDB 61A3FE88 C4C18CEB CBF6E017")
+        );
+        assert_eq!(
+            M2.to_vec(),
+            hex_literal::hex!("D58318C9 3088993D D81B773F 1000F1E8 6185E2A0")
+        );
+
+        let (M, M2) = proofs::<Sha256>();
+        assert_eq!(
+            M.to_vec(),
+            hex_literal::hex!(
+                "2105535E 818A04AC 87C01FFF 77947221 AF07F8F5 0832769A CD1C46BB 063B9301"
+            )
+        );
+        assert_eq!(
+            M2.to_vec(),
+            hex_literal::hex!(
+                "A14064A2 583404D0 3FE5AA74 47978C09 2DACE6ED 60FFB969 F03C18DC 0B52B3A2"
+            )
+        );
+    }
+
+    #[test]
+    fn should_serialize_the_constants() {
+        let constants = OpenConstants::<256>::default();
+        let json = serde_json::to_string(&constants).unwrap();
+        assert_eq!(
+            serde_json::from_str::<OpenConstants<256>>(&json).unwrap(),
+            constants
+        );
+        let constants = OpenConstants::<512>::default();
+        let json = serde_json::to_string(&constants).unwrap();
+        let read: OpenConstants<512> = serde_json::from_str(&json).unwrap();
+        assert_eq!(read, constants);
+        assert_eq!(read.fingerprint(), constants.fingerprint());
+        // the hashes are computed again
+        assert!(read.cache.is_some());
+
+        // `N` of 4096 bits is not a group of 2048 bits
+        let error = serde_json::from_str::<OpenConstants<256>>(&json).unwrap_err();
+        assert!(error.to_string().contains("512"), "{error}");
+        let mut value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        value["generator"] = serde_json::to_value(&*constants.module).unwrap();
+        assert!(serde_json::from_value::<OpenConstants<512>>(value).is_err());
+    }
+
+    #[test]
+    fn should_pin_the_fingerprints_of_the_constants() {
+        assert_eq!(
+            OpenConstants::<256>::default().fingerprint(),
+            hex_literal::hex!(
+                "661B0F11 B01162BD 5E062216 C0DC625F 53FAB8F2 0CBFC06D F0824730 2195C549"
+            )
+        );
+        assert_eq!(
+            OpenConstants::<512>::default().fingerprint(),
+            hex_literal::hex!(
+                "5478595B 3FF67964 1AECE865 E498640A 4F25255E AE3A4ACA E4377E1E 8C699FAA"
+            )
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn should_share_the_default_groups() {
+        let first = OpenConstants::<512>::default();
+        let second = OpenConstants::<512>::default();
+        assert!(Arc::ptr_eq(&first.module, &second.module));
+        assert!(Arc::ptr_eq(
+            first.cache.as_ref().unwrap(),
+            second.cache.as_ref().unwrap()
+        ));
+        assert_eq!(
+            first,
+            OpenConstants::new(
+                (*first.module).clone(),
+                Generator::from(5),
+                Srp6Variant::Srp6a
+            )
+        );
+
+        // a changed modulus is not shared and not hashed with the cache
+        let mut changed = OpenConstants::<512>::default();
+        changed.module = Arc::new(&*changed.module - &PrimeModulus::from(2));
+        assert!(!Arc::ptr_eq(&changed.module, &first.module));
+        assert!(
+            OpenConstants::<512>::default()
+                .group_hashes::<HashFunc>()
+                .unwrap()
+                != changed.group_hashes::<HashFunc>().unwrap()
+        );
+    }
+
+    #[test]
+    fn should_compare_constants_by_group() {
+        let rfc = OpenConstants::<128>::default();
+        assert_eq!(rfc, rfc.clone().with_fixed_base_table());
+        assert_eq!(rfc, rfc.clone().cache_for::<Sha256>());
+        let legacy = OpenConstants::<128>::new(
+            (*rfc.module).clone(),
+            rfc.generator.clone(),
+            Srp6Variant::Legacy,
+        );
+        assert_ne!(rfc, legacy);
+        let other_generator = OpenConstants::<128>::new(
+            (*rfc.module).clone(),
+            Generator::from(5),
+            Srp6Variant::Srp6a,
+        );
+        assert_ne!(rfc, other_generator);
+    }
+
+    #[test]
+    fn should_reject_invalid_groups() {
+        let rfc = OpenConstants::<128>::default();
+        let mut rng = StdRng::seed_from_u64(4);
+        let mut try_new = |module: &PrimeModulus, generator: u32| {
+            OpenConstants::<128>::try_new_with_rng(
+                module.clone(),
+                Generator::from(generator),
+                MILLER_RABIN_ROUNDS,
+                &mut rng,
+            )
+        };
+        // 2^1024 - 1 is divisible by 3
+        let composite = PrimeModulus::from_bytes_be(&[0xff; 128]);
+        assert_eq!(
+            try_new(&composite, 2).unwrap_err(),
+            Srp6Error::NotASafePrime
+        );
+        // and 3 shares that factor, which is found without the primality test
+        assert_eq!(
+            try_new(&composite, 3).unwrap_err(),
+            Srp6Error::InvalidGenerator
+        );
+        for g in [0, 1] {
+            assert_eq!(
+                try_new(&rfc.module, g).unwrap_err(),
+                Srp6Error::InvalidGenerator
+            );
+        }
+        let n_1 = &*rfc.module - &BigNumber::from(1);
+        assert_eq!(
+            OpenConstants::<128>::try_new_with_rng((*rfc.module).clone(), n_1, 1, &mut rng)
+                .unwrap_err(),
+            Srp6Error::InvalidGenerator
+        );
+        assert_eq!(
+            OpenConstants::<256>::try_new_with_rng(
+                (*rfc.module).clone(),
+                rfc.generator.clone(),
+                1,
+                &mut rng
+            )
+            .unwrap_err(),
+            Srp6Error::KeyLengthMismatch {
+                given: 128,
+                expected: 256
+            }
+        );
+    }
+
+    #[cfg(feature = "group-generation")]
+    #[test]
+    fn should_generate_groups() {
+        let mut rng = StdRng::seed_from_u64(5);
+        let constants = OpenConstants::<32>::generate_with_rng(&mut rng);
+        assert_eq!(constants.module.num_bytes(), 32);
+        let checked = OpenConstants::<32>::try_new_with_rng(
+            Arc::unwrap_or_clone(constants.module),
+            constants.generator,
+            20,
+            &mut rng,
+        )
+        .unwrap();
+        assert_eq!(checked.generator, Generator::from(2));
+    }
+
+    #[test]
+    fn should_reject_insecure_key_lengths() {
+        for len in [8, 32, 128, 192] {
+            let error = check_key_length_with(len, false).unwrap_err();
+            assert_eq!(
+                error,
+                Srp6Error::InsecureKeyLength {
+                    bits: len * 8,
+                    minimum: 2048
+                }
+            );
+            assert!(!error.is_authentication_failure());
+            assert_eq!(check_key_length_with(len, true), Ok(()));
+        }
+        assert_eq!(
+            check_key_length_with(128, false).unwrap_err().to_string(),
+            "The group of 1024 bits is below the minimum of 2048 bits"
+        );
+        // the groups of 2048 bits and more are never affected
+        for len in [256, 384, 512, 768, 1024] {
+            assert_eq!(check_key_length_with(len, false), Ok(()));
+            assert_eq!(check_key_length_with(len, true), Ok(()));
+        }
+    }
+
+    #[test]
+    fn should_cache_group_hashes() {
+        let constants = OpenConstants::<128>::default();
+        let cached = constants.group_hashes::<HashFunc>().unwrap();
+        assert!(matches!(cached, Cow::Borrowed(_)));
+        let fresh = GroupHashes::new::<HashFunc>(
+            128,
+            &constants.module,
+            &constants.generator,
+            constants.variant,
+        )
+        .unwrap();
+        assert_eq!(*cached, fresh);
+        assert_eq!(cached.k, BigNumber::from_bytes_be(&testdata::K_MULTIPLIER));
+
+        // other hash function
+        let sha256 = constants.group_hashes::<Sha256>().unwrap();
+        assert!(matches!(sha256, Cow::Owned(_)));
+        assert_ne!(*sha256, fresh);
+        let constants = constants.clone().cache_for::<Sha256>();
+        assert!(matches!(
+            constants.group_hashes::<Sha256>().unwrap(),
+            Cow::Borrowed(_)
+        ));
+        assert_eq!(*constants.group_hashes::<Sha256>().unwrap(), *sha256);
+
+        // changed field
+        let legacy = OpenConstants::<128> {
+            variant: Srp6Variant::Legacy,
+            ..OpenConstants::default()
+        };
+        let hashes = legacy.group_hashes::<HashFunc>().unwrap();
+        assert!(matches!(hashes, Cow::Owned(_)));
+        assert_eq!(hashes.k, MultiplierParameter::from(3));
+        assert_eq!(hashes.hash_N_xor_g, fresh.hash_N_xor_g);
+    }
+
+    #[test]
+    fn should_stretch_private_key_x() {
+        let s = Salt::from_bytes_be(&[0x5a; 16]);
+        let kdf = KdfParams::Pbkdf2 { iterations: 10 };
+        let x =
+            calculate_private_key_x_stretched::<Sha256>("alice", "password123", &s, &kdf).unwrap();
+        assert_ne!(
+            x,
+            calculate_private_key_x::<Sha256>("alice", "password123", &s, CompatProfile::Rfc5054)
+        );
+        let x2 = calculate_private_key_x_stretched::<Sha256>(
+            "alice",
+            "password123",
+            &s,
+            &KdfParams::Pbkdf2 { iterations: 11 },
+        )
+        .unwrap();
+        assert_ne!(x, x2);
+        assert_eq!(
+            calculate_private_key_x_stretched::<Sha256>(
+                "alice",
+                "password123",
+                &s,
+                &KdfParams::Pbkdf2 { iterations: 0 }
+            ),
+            Err(Srp6Error::InvalidKdfParams)
+        );
+    }
+}
@@ -59,14 +59,19 @@ H(A | M | K)
 1. The user will abort if he receives one of
     - `B mod N == 0`
     - `u == 0`
-2. The host will abort if it detects that `A mod N == 0`.
+
+    this crate also rejects `B == 1` and `B >= N`.
+2. The host will abort if it detects that `A mod N == 0` (or `u == 0`).
+   This crate also rejects `A == 1` and `A >= N`.
 3. The user must show his proof of `K` first. If the server detects that the user's proof is incorrect, it must abort without showing its own proof of `K`.
+   This crate enforces it with types: only [`Srp6WaitingForProof::verify_proof`](crate::Srp6WaitingForProof::verify_proof)
+   gives the server's proof, once the user's proof is verified.
 
 ## Test Data
 
-run tests with the 'norand' feature to test against the data provided in RFC 5054 appendix B.
-
-The test is called `test_official_vectors_1024`.
+`test_official_vectors_1024` tests against the data provided in RFC 5054 appendix B,
+with its salt and private keys given explicitly, see `Srp6User::start_handshake_with_ephemeral`
+and `Srp6::continue_handshake_with_ephemeral` (feature `deterministic`).
 
 ## References
 - [EKE](https://en.wikipedia.org/wiki/Encrypted_key_exchange)
@@ -121,4 +126,19 @@ pub mod testdata {
         3499B200 210DCC1F 10EB3394 3CD67FC8 8A2F39A4 BE5BEC4E C0A3212D
         C346D7E4 74B29EDE 8A469FFE CA686E5A"
     );
+
+    // not from the RFC: a user exported from another SRP-6a implementation, with
+    // SHA-256 and the 2048 bit group of RFC 5054, salt and verifier as big endian hex
+    pub const IMPORTED_USERNAME: &str = "carol";
+    pub const IMPORTED_PASSWORD: &str = "correct horse battery staple";
+    pub const IMPORTED_SALT_HEX: &str = "3F1E9A2C7D5B40E1A86C02B7D94F1E53";
+    pub const IMPORTED_VERIFIER_HEX: &str = "\
+        924AEAB5D2C1A4881145CF047EBC73D620701DD3337E06287F0D39CB7797F3C2\
+        216D95794FAED93F74262F75436A5BCE220AA449FACD2FF29C812DCD2023AAB0\
+        FD36086317E70523A18170A6BB1860B713E13406F9F5DA0CCFC199C21D7B66FA\
+        AD2C0CA8571DC3C7DB3FEA7FF99371456724F5C28E334B20DE6F9E41E743DC5E\
+        694ABA9FD689193B1B735F8D5E23B62852BD20802099CCAFC623B9F1478BBEBD\
+        82AC4012DB248CDD50647E9C1FDA41FA7F5D10A9D59EBF9B9F2C69CAA0340A0D\
+        906881F8CF4E8F5AC0882F9DD0569213725EDF9429567F9673F19F2593455720\
+        DF4B57F050DC5694CB43C6713EF8F27E3F7572ACC427A47288F4BBFEB47EAC5A";
 }
@@ -0,0 +1,184 @@
+//! The in-flight handshakes of a server, between both steps (feature `server`)
+//!
+//! [`HandshakeManager::begin`] continues the user's handshake and keeps the
+//! [`HostHandshakeState`] by [`SessionId`], [`HandshakeManager::finish`] takes
+//! it back to verify the user's proof. A handshake gives a single try and
+//! expires after a time to live; the number of handshakes waiting for a proof
+//! is bounded, so that a login flood cannot grow the memory without limit.
+//!
+//! An expired handshake is dropped when its proof comes, all of them are
+//! dropped when the limit is reached, or by [`HandshakeManager::purge_expired`],
+//! which can be run on a timer to free the memory sooner.
+
+use crate::hash::{Digest, HashFunc};
+use crate::primitives::*;
+use crate::{verify_proof_with_state, HostHandshakeState, Result, Srp6, Srp6Error};
+
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// how long a handshake waits for the user's proof by default
+pub const DEFAULT_HANDSHAKE_TTL: Duration = Duration::from_secs(60);
+
+/// how many handshakes can wait for a proof at once by default
+pub const DEFAULT_MAX_PENDING_HANDSHAKES: usize = 10_000;
+
+/// The source of the time of a [`HandshakeManager`], for the expiry
+///
+/// [`SystemClock`] by default, another one can be given e.g. in tests.
+pub trait Clock: Send + Sync {
+    /// the current time
+    fn now(&self) -> Instant;
+}
+
+/// [`Instant::now`]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// a handshake waiting for the user's proof
+struct PendingHandshake<const LEN: usize, D: Digest> {
+    state: HostHandshakeState<LEN, D>,
+    expires: Instant,
+}
+
+/// The host side handshakes waiting for the user's proof, by [`SessionId`]
+///
+/// it can be shared between threads, e.g. in an `Arc`. `D` is the hash
+/// function, SHA-1 by default, and `C` the [`Clock`] of the expiry.
+pub struct HandshakeManager<const LEN: usize, D: Digest = HashFunc, C: Clock = SystemClock> {
+    handshakes: Mutex<HashMap<SessionId, PendingHandshake<LEN, D>>>,
+    ttl: Duration,
+    max_pending: usize,
+    clock: C,
+}
+
+impl<const LEN: usize, D: Digest> Default for HandshakeManager<LEN, D> {
+    fn default() -> Self {
+        Self::with_clock(SystemClock)
+    }
+}
+
+impl<const LEN: usize, D: Digest> HandshakeManager<LEN, D> {
+    /// no handshake in flight, with [`DEFAULT_HANDSHAKE_TTL`] and
+    /// [`DEFAULT_MAX_PENDING_HANDSHAKES`]
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<const LEN: usize, D: Digest, C: Clock> HandshakeManager<LEN, D, C> {
+    /// same as [`HandshakeManager::new`], with the given [`Clock`]
+    pub fn with_clock(clock: C) -> Self {
+        Self {
+            handshakes: Mutex::new(HashMap::new()),
+            ttl: DEFAULT_HANDSHAKE_TTL,
+            max_pending: DEFAULT_MAX_PENDING_HANDSHAKES,
+            clock,
+        }
+    }
+
+    /// how long a handshake waits for the user's proof
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// how many handshakes can wait for a proof at once, further ones are
+    /// rejected with [`Srp6Error::TooManyPendingHandshakes`]
+    pub fn with_max_pending(mut self, max_pending: usize) -> Self {
+        self.max_pending = max_pending;
+        self
+    }
+
+    /// the number of handshakes waiting for a proof, expired ones included
+    pub fn pending_handshakes(&self) -> usize {
+        self.handshakes.lock().len()
+    }
+
+    /// drops the expired handshakes, returns how many there were
+    pub fn purge_expired(&self) -> usize {
+        let now = self.clock.now();
+        let mut handshakes = self.handshakes.lock();
+        let before = handshakes.len();
+        handshakes.retain(|_, pending| pending.expires > now);
+        before - handshakes.len()
+    }
+
+    /// first step of the host: the [`ServerHandshake`] for the user's public
+    /// key `A`, the handshake is kept until [`HandshakeManager::finish`]
+    ///
+    /// fails with [`Srp6Error::TooManyPendingHandshakes`] when the limit is
+    /// reached, once the expired handshakes are dropped, and otherwise as
+    /// [`Srp6::continue_handshake`].
+    pub fn begin(
+        &self,
+        user_details: &UserDetails,
+        user_publickey: &impl UserPublicKey,
+        constants: &OpenConstants<LEN>,
+    ) -> Result<(SessionId, ServerHandshake)> {
+        // checked before the math, so that a flood costs no computation
+        self.check_capacity(&mut self.handshakes.lock())?;
+        let (server_handshake, state) =
+            Srp6::<LEN, D>::continue_handshake_stateless(user_details, user_publickey, constants)?;
+
+        let mut handshakes = self.handshakes.lock();
+        self.check_capacity(&mut handshakes)?;
+        handshakes.insert(
+            server_handshake.session_id,
+            PendingHandshake {
+                state,
+                expires: self.clock.now() + self.ttl,
+            },
+        );
+        Ok((server_handshake.session_id, server_handshake))
+    }
+
+    /// second step of the host: verifies the user's proof `M` for the handshake
+    /// `session_id`
+    ///
+    /// the handshake is removed whatever the outcome, so that it gives a single
+    /// try. Fails with [`Srp6Error::UnknownHandshake`] if it is unknown or
+    /// expired, and otherwise as [`Srp6WaitingForProof::verify_proof`](crate::Srp6WaitingForProof::verify_proof).
+    ///
+    /// on success, returns the strong proof `M2` and the strong session key `K`
+    pub fn finish(
+        &self,
+        session_id: &SessionId,
+        users_proof: &Proof,
+    ) -> Result<(StrongProof, StrongSessionKey)> {
+        let now = self.clock.now();
+        let pending = self
+            .handshakes
+            .lock()
+            .remove(session_id)
+            .filter(|pending| pending.expires > now)
+            .ok_or(Srp6Error::UnknownHandshake)?;
+        let outcome = verify_proof_with_state(pending.state, users_proof)?;
+        Ok((
+            outcome.server_proof().clone(),
+            outcome.strong_session_key().clone(),
+        ))
+    }
+
+    /// drops the expired handshakes when the limit is reached, fails if it still is
+    fn check_capacity(
+        &self,
+        handshakes: &mut HashMap<SessionId, PendingHandshake<LEN, D>>,
+    ) -> Result<()> {
+        if handshakes.len() >= self.max_pending {
+            let now = self.clock.now();
+            handshakes.retain(|_, pending| pending.expires > now);
+        }
+        if handshakes.len() >= self.max_pending {
+            return Err(Srp6Error::TooManyPendingHandshakes);
+        }
+        Ok(())
+    }
+}
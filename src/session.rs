@@ -0,0 +1,97 @@
+//! Message authentication with the strong session key `K`, once the handshake
+//! is done
+//!
+//! Both sides get the same [`AuthenticatedSession`] from their [`SessionSecret`]
+//! (or [`ClientSession`]), then tag the messages they send with
+//! [`AuthenticatedSession::sign`] and check the ones they get with
+//! [`AuthenticatedSession::verify`].
+//!
+//! The tags are HMAC-SHA256, keyed by `HKDF-SHA256(K, info = "srp6 mac")`,
+//! see [`SessionSecret::derive_key`].
+
+use crate::hash::{Digest, Sha256};
+use crate::primitives::{ClientSession, SessionSecret};
+
+use core::fmt;
+use hmac::{Hmac, Mac};
+#[cfg(feature = "zeroize")]
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+/// the HKDF `info` of the key of the tags
+pub use crate::primitives::MAC_KEY_INFO;
+
+/// HMAC-SHA256 of a message
+pub type Tag = [u8; 32];
+
+/// Signs and verifies messages with a key derived from `K`
+#[derive(Clone)]
+pub struct AuthenticatedSession {
+    key: [u8; 32],
+}
+
+impl AuthenticatedSession {
+    /// signs `msg`
+    pub fn sign(&self, msg: &[u8]) -> Tag {
+        self.mac(msg).finalize().into_bytes().into()
+    }
+
+    /// whether `tag` is the tag of `msg`, compared in constant time
+    pub fn verify(&self, msg: &[u8], tag: &[u8]) -> bool {
+        self.mac(msg).verify_slice(tag).is_ok()
+    }
+
+    fn mac(&self, msg: &[u8]) -> Hmac<Sha256> {
+        let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(&self.key)
+            .expect("HMAC accepts keys of any length");
+        mac.update(msg);
+        mac
+    }
+}
+
+impl From<&SessionSecret> for AuthenticatedSession {
+    fn from(secret: &SessionSecret) -> Self {
+        let mut key = [0_u8; 32];
+        key.copy_from_slice(&secret.derive_key(MAC_KEY_INFO, 32));
+        Self { key }
+    }
+}
+
+impl SessionSecret {
+    /// the [`AuthenticatedSession`] of the handshake, the same on both sides
+    pub fn authenticated_session(&self) -> AuthenticatedSession {
+        self.into()
+    }
+}
+
+impl<D: Digest> ClientSession<D> {
+    /// the [`AuthenticatedSession`] of the stored session, the same as from
+    /// [`SessionSecret::authenticated_session`] at the end of its handshake
+    pub fn authenticated_session(&self) -> AuthenticatedSession {
+        SessionSecret::new(Default::default(), self.strong_session_key().clone())
+            .authenticated_session()
+    }
+}
+
+/// the key is not printed
+impl fmt::Debug for AuthenticatedSession {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("AuthenticatedSession(..)")
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Zeroize for AuthenticatedSession {
+    fn zeroize(&mut self) {
+        self.key.zeroize();
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for AuthenticatedSession {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl ZeroizeOnDrop for AuthenticatedSession {}
@@ -0,0 +1,36 @@
+//! non sensitive [`tracing`] spans around the handshake steps
+
+use crate::hash::Digest;
+use crate::primitives::UsernameRef;
+
+use alloc::string::String;
+use std::time::Instant;
+use tracing::span::EnteredSpan;
+
+/// an entered span, that records its `duration_us` field when dropped
+pub(crate) struct StepSpan {
+    span: EnteredSpan,
+    start: Instant,
+}
+
+impl StepSpan {
+    pub(crate) fn enter(span: tracing::Span) -> Self {
+        Self {
+            span: span.entered(),
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Drop for StepSpan {
+    fn drop(&mut self) {
+        let duration_us = self.start.elapsed().as_micros() as u64;
+        self.span.record("duration_us", duration_us);
+    }
+}
+
+/// `H(I)` in hex, to correlate the steps of one user without logging the username
+#[allow(non_snake_case)]
+pub(crate) fn username_hash<D: Digest>(I: UsernameRef) -> String {
+    hex::encode(D::digest(I.as_bytes()))
+}
@@ -0,0 +1,148 @@
+/*!
+Forged messages of a dishonest peer, for negative tests (feature `test-util`)
+
+An application can check with them that its server rejects what an attacker
+would send, with the expected [`Srp6Error`](crate::Srp6Error) and without a panic:
+
+```rust
+use chadehoc_srp6::test_util::*;
+use chadehoc_srp6::*;
+
+let constants = OpenConstants::<128>::default();
+let user_details =
+    Srp6user1024::generate_new_user_secrets(&"Bob".into(), &"secret-password".into(), &constants)
+        .unwrap();
+
+for forged in ForgedPublicKey::ALL {
+    let result = Srp6_1024::default().continue_handshake(
+        &user_details,
+        &forged.value(&constants),
+        &constants,
+    );
+    assert_eq!(result.err(), Some(forged.expected_error(&constants)));
+}
+```
+
+[`DeterministicKeys`] replays the test vectors of another implementation, with
+its private keys `a` and `b`.
+
+**Note**: these helpers only build values, they are not needed at runtime.
+*/
+use crate::primitives::{
+    ClearTextPassword, OpenConstants, PrivateKey, Proof, PublicKey, UserDetails, UsernameRef,
+};
+use crate::{Result, Srp6, Srp6Error, Srp6User};
+
+/// the proof with its bit `bit` flipped, `0` being the least significant one
+///
+/// the proof grows if `bit` is above its most significant one
+pub fn flip_bit(proof: &Proof, bit: usize) -> Proof {
+    let len = proof.num_bytes().max(bit / 8 + 1);
+    let mut bytes = proof.to_vec_pad_zero(len);
+    bytes[len - 1 - bit / 8] ^= 1 << (bit % 8);
+    Proof::from_bytes_be(&bytes)
+}
+
+/// public keys `A` (or `B`) that make the premaster secret `S` predictable,
+/// a server must reject them
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ForgedPublicKey {
+    /// `0`, then `S = 0`
+    Zero,
+    /// `N`, which is `0` modulo `N`
+    Modulus,
+    /// `2 * N`, also `0` modulo `N`, and longer than the group
+    TwiceModulus,
+}
+
+impl ForgedPublicKey {
+    pub const ALL: [Self; 3] = [Self::Zero, Self::Modulus, Self::TwiceModulus];
+
+    /// the forged key for the group
+    pub fn value<const LEN: usize>(self, constants: &OpenConstants<LEN>) -> PublicKey {
+        match self {
+            Self::Zero => PublicKey::default(),
+            Self::Modulus => (*constants.module).clone(),
+            Self::TwiceModulus => &*constants.module + &*constants.module,
+        }
+    }
+
+    /// the error of the checks of the public keys of the other side for this key
+    ///
+    /// `2 * N` does not fit in `LEN` bytes for the groups of this crate, whose
+    /// `N` has its most significant bit set
+    pub fn expected_error<const LEN: usize>(self, constants: &OpenConstants<LEN>) -> Srp6Error {
+        let key = self.value(constants);
+        if key.num_bytes() > LEN {
+            Srp6Error::KeyLengthMismatch {
+                given: key.num_bytes(),
+                expected: LEN,
+            }
+        } else {
+            Srp6Error::InvalidPublicKey(key)
+        }
+    }
+}
+
+/// the private keys `a` and `b` of a handshake, given instead of drawn, e.g. to
+/// replay the test vectors of another implementation
+///
+/// see [`Srp6User::start_handshake_with_keys`] and
+/// [`Srp6::continue_handshake_with_keys`], for one key at a time see
+/// [`Srp6User::start_handshake_with_ephemeral`] and
+/// [`Srp6::continue_handshake_with_ephemeral`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeterministicKeys {
+    /// the user's private key `a`
+    pub a: PrivateKey,
+    /// the host's private key `b`
+    pub b: PrivateKey,
+}
+
+impl DeterministicKeys {
+    pub fn new(a: PrivateKey, b: PrivateKey) -> Self {
+        Self { a, b }
+    }
+}
+
+/// what an eavesdropper keeps of a successful handshake
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordedHandshake {
+    /// the user's public key `A`
+    pub user_publickey: PublicKey,
+    /// the user's proof `M`
+    pub proof: Proof,
+}
+
+/// runs an honest handshake of the user against a server with `user_details`,
+/// and records the messages of the user, e.g. to replay them later
+pub fn record_handshake<const LEN: usize>(
+    user_details: &UserDetails,
+    username: UsernameRef,
+    password: &ClearTextPassword,
+    constants: &OpenConstants<LEN>,
+) -> Result<RecordedHandshake> {
+    let mut user = Srp6User::<LEN>::default();
+    let user_handshake = user.start_handshake(username, constants)?;
+    let (server_handshake, host) = Srp6::<LEN>::default().continue_handshake(
+        user_details,
+        &user_handshake.user_publickey,
+        constants,
+    )?;
+    let (proof, _) =
+        user.calculate_proof(&server_handshake, constants, username, password, None)?;
+    host.verify_proof(&proof)?;
+    Ok(RecordedHandshake {
+        user_publickey: user_handshake.user_publickey,
+        proof,
+    })
+}
+
+#[test]
+fn should_flip_one_bit() {
+    let proof = Proof::from_bytes_be(&[0x80, 0x01]);
+    assert_eq!(flip_bit(&proof, 0), Proof::from_bytes_be(&[0x80, 0x00]));
+    assert_eq!(flip_bit(&proof, 15), Proof::from(1));
+    assert_eq!(flip_bit(&proof, 16), Proof::from_bytes_be(&[1, 0x80, 0x01]));
+    assert_eq!(flip_bit(&flip_bit(&proof, 9), 9), proof);
+}
@@ -0,0 +1,307 @@
+/*!
+The key exchange messages of SRP for TLS, as in [RFC 5054] section 2.6
+
+A TLS server in an SRP cipher suite sends the group, the salt and `B` in its
+ServerKeyExchange, the client answers with `A` in its ClientKeyExchange. The
+username goes in the `srp` extension of the ClientHello, it is not part of
+these messages.
+
+```plain
+struct {
+    opaque srp_N<1..2^16-1>;
+    opaque srp_g<1..2^16-1>;
+    opaque srp_s<1..2^8-1>;
+    opaque srp_B<1..2^16-1>;
+} ServerSRPParams;
+
+struct {
+    opaque srp_A<1..2^16-1>;
+} ClientSRPPublic;
+```
+
+Each number is in big endian without leading zeros, prefixed by its length as
+a big endian `u16`, or a single byte for the salt. These are the bodies of the
+handshake messages, without their type and length: in the suites that are
+also signed with a certificate, the signature follows the ServerKeyExchange
+parameters and must be split off before [`ServerKeyExchangeParams::decode`].
+
+The user must only accept a group it trusts, see
+[`ServerKeyExchangeParams::constants`], and the hash function is SHA-1 in the
+suites of RFC 5054.
+
+```
+use chadehoc_srp6::tls::{ClientKeyExchange, ServerKeyExchangeParams};
+use chadehoc_srp6::*;
+
+let constants = OpenConstants::<256>::default();
+let (username, password) = (Username::from("alice"), Password::from("password"));
+let user_details = Srp6_2048::generate_new_user_secrets(&username, &password, &constants).unwrap();
+let mut user = Srp6user2048::default();
+let user_handshake = user.start_handshake("alice", &constants).unwrap();
+let cke = ClientKeyExchange::from(&user_handshake).encode().unwrap();
+
+// server, the username comes from the ClientHello
+let user_handshake = ClientKeyExchange::decode(&cke).unwrap().user_handshake("alice");
+let (server_handshake, host) = Srp6_2048::default()
+    .continue_handshake(&user_details, &user_handshake, &constants)
+    .unwrap();
+let params = ServerKeyExchangeParams::new(&constants, &server_handshake)
+    .encode()
+    .unwrap();
+
+// user
+let params = ServerKeyExchangeParams::decode(&params).unwrap();
+let constants = params.constants::<256>(&TrustedGroups::default()).unwrap();
+let (proof, user) = user
+    .update_handshake(&params.server_handshake(), &constants, &username, &password)
+    .unwrap();
+# let outcome = host.verify_proof(&proof).unwrap();
+# user.verify_proof(outcome.server_proof()).unwrap();
+```
+
+[RFC 5054]: https://datatracker.ietf.org/doc/html/rfc5054#section-2.6
+*/
+use alloc::vec::Vec;
+
+use crate::big_number::BigNumber;
+use crate::primitives::{
+    group_bits, write_prefixed, Generator, GroupParamsMessage, MessageReader, OpenConstants,
+    PrimeModulus, PublicKey, Salt, ServerHandshake, TrustedGroups, UserHandshake, UsernameRef,
+};
+use crate::{Result, Srp6Error};
+
+/// The parameters of the ServerKeyExchange: the group, the salt and `B`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServerKeyExchangeParams {
+    pub modulus: PrimeModulus,
+    pub generator: Generator,
+    pub salt: Salt,
+    pub server_publickey: PublicKey,
+}
+
+impl ServerKeyExchangeParams {
+    /// the group of the server and its answer to the user
+    pub fn new<const LEN: usize>(
+        constants: &OpenConstants<LEN>,
+        server_handshake: &ServerHandshake,
+    ) -> Self {
+        Self {
+            modulus: (*constants.module).clone(),
+            generator: constants.generator.clone(),
+            salt: server_handshake.salt.clone(),
+            server_publickey: server_handshake.server_publickey.clone(),
+        }
+    }
+
+    /// the wire form of `ServerSRPParams`
+    ///
+    /// fails with [`Srp6Error::SaltLengthMismatch`] for a salt longer than
+    /// 255 bytes, and with [`Srp6Error::MalformedMessage`] for a number longer
+    /// than 65535 bytes
+    pub fn encode(&self) -> Result<Vec<u8>> {
+        let modulus = self.modulus.to_vec();
+        let generator = self.generator.to_vec();
+        let salt = self.salt.to_vec();
+        let server_publickey = self.server_publickey.to_vec();
+        let salt_len = u8::try_from(salt.len()).map_err(|_| Srp6Error::SaltLengthMismatch {
+            given: salt.len(),
+            expected: usize::from(u8::MAX),
+        })?;
+        let mut out = Vec::with_capacity(
+            7 + modulus.len() + generator.len() + salt.len() + server_publickey.len(),
+        );
+        write_prefixed(&mut out, &modulus)?;
+        write_prefixed(&mut out, &generator)?;
+        out.push(salt_len);
+        out.extend_from_slice(&salt);
+        write_prefixed(&mut out, &server_publickey)?;
+        Ok(out)
+    }
+
+    /// parses the form of [`ServerKeyExchangeParams::encode`], all the bytes
+    /// must be used
+    ///
+    /// an empty field fails with [`Srp6Error::MalformedMessage`], `g` or `B`
+    /// longer than `N` with [`Srp6Error::KeyLengthMismatch`]. The numbers are
+    /// not checked otherwise: the group against [`TrustedGroups`], and `B` by
+    /// the handshake of the user.
+    pub fn decode(bytes: &[u8]) -> Result<Self> {
+        let mut reader = MessageReader::new(bytes);
+        let modulus = non_empty(reader.take_prefixed(usize::from(u16::MAX))?)?;
+        let generator = non_empty(reader.take_prefixed(modulus.len())?)?;
+        let salt = non_empty(reader.take_prefixed_u8()?)?;
+        let server_publickey = non_empty(reader.take_prefixed(modulus.len())?)?;
+        reader.finish()?;
+        Ok(Self {
+            modulus: BigNumber::from_bytes_be(modulus),
+            generator: BigNumber::from_bytes_be(generator),
+            salt: BigNumber::from_bytes_be(salt),
+            server_publickey: BigNumber::from_bytes_be(server_publickey),
+        })
+    }
+
+    /// the group offered by the server
+    pub fn group_message(&self) -> GroupParamsMessage {
+        GroupParamsMessage {
+            modulus: self.modulus.clone(),
+            generator: self.generator.clone(),
+        }
+    }
+
+    /// the constants of the group offered by the server, if it is trusted and
+    /// its `N` has `LEN` bytes, see [`TrustedGroups::resolve`]
+    pub fn constants<const LEN: usize>(
+        &self,
+        trusted_groups: &TrustedGroups,
+    ) -> Result<OpenConstants<LEN>> {
+        trusted_groups.resolve(&self.group_message())
+    }
+
+    /// the salt and `B` for [`Srp6User::update_handshake`](crate::Srp6User::update_handshake),
+    /// with the bits of the offered group
+    pub fn server_handshake(&self) -> ServerHandshake {
+        ServerHandshake {
+            group_bits: group_bits(self.modulus.num_bytes()),
+            ..ServerHandshake::new(self.salt.clone(), self.server_publickey.clone())
+        }
+    }
+}
+
+/// The ClientKeyExchange: `A`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientKeyExchange {
+    pub user_publickey: PublicKey,
+}
+
+impl ClientKeyExchange {
+    pub fn new(user_publickey: PublicKey) -> Self {
+        Self { user_publickey }
+    }
+
+    /// the wire form of `ClientSRPPublic`
+    ///
+    /// fails with [`Srp6Error::MalformedMessage`] for `A` longer than 65535 bytes
+    pub fn encode(&self) -> Result<Vec<u8>> {
+        let user_publickey = self.user_publickey.to_vec();
+        let mut out = Vec::with_capacity(2 + user_publickey.len());
+        write_prefixed(&mut out, &user_publickey)?;
+        Ok(out)
+    }
+
+    /// parses the form of [`ClientKeyExchange::encode`], all the bytes must be
+    /// used; an empty `A` fails with [`Srp6Error::MalformedMessage`]
+    pub fn decode(bytes: &[u8]) -> Result<Self> {
+        let mut reader = MessageReader::new(bytes);
+        let user_publickey = non_empty(reader.take_prefixed(usize::from(u16::MAX))?)?;
+        reader.finish()?;
+        Ok(Self::new(BigNumber::from_bytes_be(user_publickey)))
+    }
+
+    /// the handshake of the user, with the username of the ClientHello; the
+    /// group is unknown
+    pub fn user_handshake(self, username: UsernameRef) -> UserHandshake {
+        UserHandshake::new(username, self.user_publickey)
+    }
+}
+
+impl From<&UserHandshake> for ClientKeyExchange {
+    fn from(user_handshake: &UserHandshake) -> Self {
+        Self::new(user_handshake.user_publickey.clone())
+    }
+}
+
+/// the opaque fields of RFC 5054 have at least one byte
+fn non_empty(field: &[u8]) -> Result<&[u8]> {
+    match field {
+        [] => Err(Srp6Error::MalformedMessage),
+        field => Ok(field),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params() -> ServerKeyExchangeParams {
+        ServerKeyExchangeParams {
+            modulus: BigNumber::from(0xFFFB),
+            generator: BigNumber::from(2),
+            salt: BigNumber::from(0xBEB25379),
+            server_publickey: BigNumber::from(0x1234),
+        }
+    }
+
+    #[test]
+    fn should_encode_server_params() {
+        assert_eq!(
+            params().encode().unwrap(),
+            [0, 2, 0xFF, 0xFB, 0, 1, 2, 4, 0xBE, 0xB2, 0x53, 0x79, 0, 2, 0x12, 0x34]
+        );
+        assert_eq!(
+            ServerKeyExchangeParams::decode(&params().encode().unwrap()).unwrap(),
+            params()
+        );
+    }
+
+    #[test]
+    fn should_reject_malformed_server_params() {
+        let bytes = params().encode().unwrap();
+        for len in 0..bytes.len() {
+            assert_eq!(
+                ServerKeyExchangeParams::decode(&bytes[..len]),
+                Err(Srp6Error::TruncatedMessage),
+                "{len} bytes"
+            );
+        }
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert_eq!(
+            ServerKeyExchangeParams::decode(&trailing),
+            Err(Srp6Error::TrailingBytes { count: 1 })
+        );
+        // empty salt
+        assert_eq!(
+            ServerKeyExchangeParams::decode(&[0, 2, 0xFF, 0xFB, 0, 1, 2, 0, 0, 1, 3]),
+            Err(Srp6Error::MalformedMessage)
+        );
+        // `B` longer than `N`
+        assert_eq!(
+            ServerKeyExchangeParams::decode(&[0, 2, 0xFF, 0xFB, 0, 1, 2, 1, 1, 0, 3, 1, 2, 3]),
+            Err(Srp6Error::KeyLengthMismatch {
+                given: 3,
+                expected: 2
+            })
+        );
+        let long_salt = ServerKeyExchangeParams {
+            salt: BigNumber::from_bytes_be(&[1; 256]),
+            ..params()
+        };
+        assert_eq!(
+            long_salt.encode(),
+            Err(Srp6Error::SaltLengthMismatch {
+                given: 256,
+                expected: 255
+            })
+        );
+    }
+
+    #[test]
+    fn should_reject_malformed_client_key_exchange() {
+        let bytes = ClientKeyExchange::new(BigNumber::from(0x1234))
+            .encode()
+            .unwrap();
+        assert_eq!(bytes, [0, 2, 0x12, 0x34]);
+        assert_eq!(
+            ClientKeyExchange::decode(&bytes[..3]),
+            Err(Srp6Error::TruncatedMessage)
+        );
+        assert_eq!(
+            ClientKeyExchange::decode(&[0, 2, 0x12, 0x34, 0x56]),
+            Err(Srp6Error::TrailingBytes { count: 1 })
+        );
+        assert_eq!(
+            ClientKeyExchange::decode(&[0, 0]),
+            Err(Srp6Error::MalformedMessage)
+        );
+    }
+}
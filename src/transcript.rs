@@ -0,0 +1,129 @@
+//! The intermediate values of a handshake, to compare them with another
+//! implementation, with the `transcript` feature
+//!
+//! Both waiting states keep a [`Transcript`] of the values they calculated, in
+//! order: see [`Srp6WaitingForProof::transcript`](crate::Srp6WaitingForProof::transcript)
+//! and [`Srp6UserWaitingForServerProof::transcript`](crate::Srp6UserWaitingForServerProof::transcript).
+//! The numbers are in big endian hex, as in the JSON of the messages.
+//!
+//! The values derived from a secret (`x`, `S`, `K`) are marked as such:
+//! [`Transcript::redacted`] leaves them out, e.g. for a bug report. The public
+//! values are the same on both sides of a handshake.
+//!
+//! **Note**: the transcript is for debugging only, it contains the session key.
+
+use crate::big_number::BigNumber;
+use crate::primitives::StrongSessionKey;
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "zeroize")]
+use zeroize::Zeroize;
+
+/// A value of a [`Transcript`], `hex` is `None` once redacted
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TranscriptEntry {
+    pub name: String,
+    pub hex: Option<String>,
+    /// derived from a private key or the password
+    pub secret: bool,
+}
+
+/// the secret values are redacted
+impl fmt::Debug for TranscriptEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let hex = match (&self.hex, self.secret) {
+            (Some(_), true) => Some("<redacted>"),
+            (hex, _) => hex.as_deref(),
+        };
+        f.debug_struct("TranscriptEntry")
+            .field("name", &self.name)
+            .field("hex", &hex)
+            .field("secret", &self.secret)
+            .finish()
+    }
+}
+
+/// The ordered values of one side of a handshake, see the [module](self)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Transcript {
+    entries: Vec<TranscriptEntry>,
+}
+
+impl Transcript {
+    /// adds a public value
+    pub(crate) fn record(&mut self, name: &str, value: &BigNumber) {
+        self.push(name, value.into(), false);
+    }
+
+    /// adds a value derived from a secret
+    pub(crate) fn record_secret(&mut self, name: &str, value: &BigNumber) {
+        self.push(name, value.into(), true);
+    }
+
+    /// adds `K`, as its canonical bytes
+    #[allow(non_snake_case)]
+    pub(crate) fn record_session_key(&mut self, K: &StrongSessionKey) {
+        self.push("K", hex::encode_upper(K.to_bytes()), true);
+    }
+
+    fn push(&mut self, name: &str, hex: String, secret: bool) {
+        self.entries.push(TranscriptEntry {
+            name: name.into(),
+            hex: Some(hex),
+            secret,
+        });
+    }
+
+    /// the values in the order they were calculated
+    pub fn entries(&self) -> &[TranscriptEntry] {
+        &self.entries
+    }
+
+    /// the hex of the value `name`, `None` if it was not recorded or is redacted
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|entry| entry.name == name)
+            .and_then(|entry| entry.hex.as_deref())
+    }
+
+    /// the same entries, without the hex of the secret ones
+    pub fn redacted(&self) -> Self {
+        Self {
+            entries: self
+                .entries
+                .iter()
+                .map(|entry| TranscriptEntry {
+                    hex: entry.hex.clone().filter(|_| !entry.secret),
+                    ..entry.clone()
+                })
+                .collect(),
+        }
+    }
+
+    /// the entries as a JSON array, secret values included
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("a transcript is always valid JSON")
+    }
+
+    /// reads the form of [`Transcript::to_json`]
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(json)
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Zeroize for Transcript {
+    fn zeroize(&mut self) {
+        for entry in &mut self.entries {
+            if let Some(hex) = &mut entry.hex {
+                hex.zeroize();
+            }
+        }
+        self.entries.clear();
+    }
+}
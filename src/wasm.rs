@@ -0,0 +1,162 @@
+//! User side bindings for JavaScript, through `wasm-bindgen` (feature `wasm`)
+//!
+//! The messages are plain JS objects, with the big numbers as hex strings,
+//! e.g. `{ username: "Bob", user_publickey: "AB11CD..." }` for a [`UserHandshake`].
+
+use crate::api::dynamic::DynSrp6User;
+use crate::primitives::*;
+use crate::{Result, Srp6Error};
+
+use alloc::borrow::ToOwned;
+use alloc::string::{String, ToString};
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+/// the group of RFC 5054 with `group_bits` bits
+fn rfc5054_group(group_bits: u32) -> Option<GroupParams> {
+    Some(match group_bits {
+        1024 => OpenConstants::<128>::default().into(),
+        1536 => OpenConstants::<192>::default().into(),
+        2048 => OpenConstants::<256>::default().into(),
+        3072 => OpenConstants::<384>::default().into(),
+        4096 => OpenConstants::<512>::default().into(),
+        6144 => OpenConstants::<768>::default().into(),
+        8192 => OpenConstants::<1024>::default().into(),
+        _ => return None,
+    })
+}
+
+fn to_js<T: Serialize>(value: &T) -> core::result::Result<JsValue, JsError> {
+    Ok(serde_wasm_bindgen::to_value(value)?)
+}
+
+/// The user side of one handshake, with SHA-1 and a group of RFC 5054
+#[wasm_bindgen]
+pub struct WasmSrp6User {
+    srp6_user: DynSrp6User,
+    params: GroupParams,
+    username: String,
+}
+
+#[wasm_bindgen]
+impl WasmSrp6User {
+    /// `group_bits` is the size of a group of RFC 5054, from 1024 to 8192
+    #[wasm_bindgen(constructor)]
+    pub fn new(group_bits: u32) -> core::result::Result<WasmSrp6User, JsError> {
+        rfc5054_group(group_bits)
+            .map(Self::with_params)
+            .ok_or_else(|| JsError::new("unsupported group size"))
+    }
+
+    /// returns the [`UserHandshake`] to send to the server
+    pub fn start_handshake_js(&mut self, username: &str) -> core::result::Result<JsValue, JsError> {
+        to_js(&self.start_handshake(username)?)
+    }
+
+    /// from the server's [`ServerHandshake`], returns the [`ProofMessage`] to send
+    pub fn update_handshake_js(
+        &mut self,
+        server_handshake: JsValue,
+        password: &str,
+    ) -> core::result::Result<JsValue, JsError> {
+        let server_handshake = serde_wasm_bindgen::from_value(server_handshake)?;
+        to_js(&self.update_handshake(&server_handshake, password)?)
+    }
+
+    /// checks the server's [`ProofMessage`], returns the strong session key `K` in hex
+    pub fn verify_server_proof_js(
+        &mut self,
+        servers_proof: JsValue,
+    ) -> core::result::Result<String, JsError> {
+        let servers_proof = serde_wasm_bindgen::from_value(servers_proof)?;
+        let secret = self.verify_server_proof(&servers_proof)?;
+        Ok(secret.strong_session_key().to_string())
+    }
+}
+
+impl WasmSrp6User {
+    fn with_params(params: GroupParams) -> Self {
+        Self {
+            srp6_user: DynSrp6User::default(),
+            params,
+            username: String::new(),
+        }
+    }
+
+    fn start_handshake(&mut self, username: UsernameRef) -> Result<UserHandshake> {
+        self.username = username.to_owned();
+        self.srp6_user.start_handshake(username, &self.params)
+    }
+
+    fn update_handshake(
+        &mut self,
+        server_handshake: &ServerHandshake,
+        password: &ClearTextPassword,
+    ) -> Result<ProofMessage> {
+        self.srp6_user
+            .update_handshake(server_handshake, &self.params, &self.username, password)
+            .map(ProofMessage::from)
+    }
+
+    fn verify_server_proof(&mut self, servers_proof: &ProofMessage) -> Result<SessionSecret> {
+        core::mem::take(&mut self.srp6_user)
+            .verify_proof(&servers_proof.strong_proof())
+            .ok_or_else(|| Srp6Error::InvalidStrongProof(servers_proof.strong_proof()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DynSrp6;
+
+    /// the JS objects have the same shape as the json, with hex strings
+    fn through_json<T: Serialize + serde::de::DeserializeOwned>(value: &T) -> T {
+        let json = serde_json::to_value(value).unwrap();
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[test]
+    fn should_run_the_user_flow() {
+        let params = rfc5054_group(2048).unwrap();
+        let user_details =
+            DynSrp6User::<crate::Sha1>::generate_new_user_secrets("Bob", "secret", &params)
+                .unwrap();
+        let mut srp6 = DynSrp6::<crate::Sha1>::default();
+
+        let mut user = WasmSrp6User::with_params(params.clone());
+        let user_handshake = through_json(&user.start_handshake("Bob").unwrap());
+        assert_eq!(user_handshake.username, "Bob");
+        let server_handshake = srp6
+            .continue_handshake(&user_details, &user_handshake.user_publickey, &params)
+            .unwrap();
+        let proof = through_json(
+            &user
+                .update_handshake(&through_json(&server_handshake), "secret")
+                .unwrap(),
+        );
+        let (strong_proof, secret) = srp6.verify_proof(&proof.proof).unwrap();
+        let user_secret = user
+            .verify_server_proof(&through_json(&ProofMessage::from(strong_proof)))
+            .unwrap();
+        assert_eq!(user_secret, secret);
+    }
+
+    #[test]
+    fn should_reject_a_wrong_server_proof() {
+        let params = rfc5054_group(1024).unwrap();
+        let mut user = WasmSrp6User::with_params(params);
+        user.start_handshake("Bob").unwrap();
+        let wrong = ProofMessage::from(Proof::from(42));
+        assert!(matches!(
+            user.verify_server_proof(&wrong),
+            Err(Srp6Error::InvalidStrongProof(_))
+        ));
+    }
+
+    #[test]
+    fn should_only_know_the_rfc_groups() {
+        assert_eq!(rfc5054_group(4096).unwrap().len, 512);
+        assert!(rfc5054_group(2000).is_none());
+    }
+}
@@ -0,0 +1,55 @@
+//! runs concurrent handshakes on a tokio runtime, with the `async` methods
+#![cfg(feature = "async")]
+
+use chadehoc_srp6::*;
+use tokio::task::JoinSet;
+
+const USERNAME: UsernameRef = "alice";
+const PASSWORD: &ClearTextPassword = "password123";
+
+/// one full handshake, both sides in the same task
+async fn authenticate(
+    user_details: UserDetails,
+    constants: OpenConstants<256>,
+) -> Result<(SessionSecret, SessionSecret)> {
+    let mut user = Srp6user2048::default();
+    let handshake = user.start_handshake(USERNAME, &constants).unwrap();
+
+    let (server_handshake, host) = Srp6_2048::default()
+        .continue_handshake_async(&user_details, &handshake.user_publickey, &constants)
+        .await?;
+    let (proof, user) = user
+        .update_handshake_async(
+            &server_handshake,
+            &constants,
+            &USERNAME.into(),
+            &PASSWORD.into(),
+        )
+        .await?;
+    let outcome = host.verify_proof(&proof)?;
+    let strong_proof = outcome.server_proof().clone();
+    let host_secret = outcome.into_session_secret();
+    let user_secret = user.verify_proof(&strong_proof)?.into_session_secret();
+    Ok((host_secret, user_secret))
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn should_run_concurrent_handshakes() {
+    let constants = OpenConstants::<256>::default();
+    let user_details =
+        Srp6user2048::generate_new_user_secrets(&USERNAME.into(), &PASSWORD.into(), &constants)
+            .unwrap();
+
+    let mut handshakes = JoinSet::new();
+    for _ in 0..10 {
+        handshakes.spawn(authenticate(user_details.clone(), constants.clone()));
+    }
+
+    let mut count = 0;
+    while let Some(result) = handshakes.join_next().await {
+        let (host_secret, user_secret) = result.unwrap().unwrap();
+        assert_eq!(host_secret, user_secret);
+        count += 1;
+    }
+    assert_eq!(count, 10);
+}
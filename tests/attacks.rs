@@ -0,0 +1,232 @@
+//! what a dishonest peer may send, with the forged messages of `test_util`:
+//! each one must be rejected with its error, never authenticated nor panic
+#![cfg(feature = "test-util")]
+
+use chadehoc_srp6::test_util::*;
+use chadehoc_srp6::*;
+
+const USERNAME: UsernameRef = "alice";
+const PASSWORD: &ClearTextPassword = "password123";
+
+fn alice(constants: &OpenConstants<256>) -> UserDetails {
+    Srp6user2048::generate_new_user_secrets(&USERNAME.into(), &PASSWORD.into(), constants).unwrap()
+}
+
+/// both sides until the user's proof, which the host has not checked yet
+fn until_proof(
+    user_details: &UserDetails,
+    constants: &OpenConstants<256>,
+) -> (Proof, Srp6WaitingForProof<256>) {
+    let mut user = Srp6user2048::default();
+    let handshake = user.start_handshake(USERNAME, constants).unwrap();
+    let (server_handshake, host) = Srp6_2048::default()
+        .continue_handshake(user_details, &handshake.user_publickey, constants)
+        .unwrap();
+    let (proof, _) = user
+        .update_handshake(
+            &server_handshake,
+            constants,
+            &USERNAME.into(),
+            &PASSWORD.into(),
+        )
+        .unwrap();
+    (proof, host)
+}
+
+#[test]
+fn should_reject_forged_user_publickey() {
+    let constants = OpenConstants::<256>::default();
+    let user_details = alice(&constants);
+    for forged in ForgedPublicKey::ALL {
+        let forged_key = forged.value(&constants);
+        let expected = forged.expected_error(&constants);
+        let result =
+            Srp6_2048::default().continue_handshake(&user_details, &forged_key, &constants);
+        assert_eq!(result.err(), Some(expected.clone()), "{forged:?}");
+
+        let prepared = Srp6_2048::default()
+            .prepare(&user_details, &constants)
+            .unwrap();
+        assert_eq!(
+            prepared.complete(&forged_key).err(),
+            Some(expected),
+            "{forged:?}"
+        );
+    }
+    assert_eq!(
+        ForgedPublicKey::Zero.expected_error(&constants),
+        Srp6Error::InvalidPublicKey(PublicKey::default())
+    );
+    assert!(matches!(
+        ForgedPublicKey::TwiceModulus.expected_error(&constants),
+        Srp6Error::KeyLengthMismatch {
+            given: 257,
+            expected: 256
+        }
+    ));
+}
+
+#[test]
+fn should_reject_forged_server_publickey() {
+    let constants = OpenConstants::<256>::default();
+    let user_details = alice(&constants);
+    for forged in ForgedPublicKey::ALL {
+        let mut user = Srp6user2048::default();
+        user.start_handshake(USERNAME, &constants).unwrap();
+        let server_handshake =
+            ServerHandshake::new(user_details.salt.clone(), forged.value(&constants));
+        let result = user.update_handshake(
+            &server_handshake,
+            &constants,
+            &USERNAME.into(),
+            &PASSWORD.into(),
+        );
+        assert_eq!(
+            result.err(),
+            Some(forged.expected_error(&constants)),
+            "{forged:?}"
+        );
+    }
+}
+
+#[test]
+fn should_reject_proof_with_one_bit_flipped() {
+    let constants = OpenConstants::<256>::default();
+    let user_details = alice(&constants);
+    // SHA-1: the proof has 160 bits, the last one grows it
+    for bit in [0, 1, 80, 159, 160] {
+        let (proof, host) = until_proof(&user_details, &constants);
+        let tampered = flip_bit(&proof, bit);
+        assert_ne!(tampered, proof);
+        assert_eq!(
+            host.verify_proof(&tampered).err(),
+            Some(Srp6Error::InvalidProof),
+            "bit {bit}"
+        );
+    }
+}
+
+// with `norand`, the server would draw the same `b` again
+#[test]
+#[cfg(not(feature = "norand"))]
+fn should_reject_replayed_proof() {
+    let constants = OpenConstants::<256>::default();
+    let user_details = alice(&constants);
+    let recorded = record_handshake(&user_details, USERNAME, PASSWORD, &constants).unwrap();
+
+    // the attacker replays `A` and `M`, the server draws a new `b`
+    let (_, host) = Srp6_2048::default()
+        .continue_handshake(&user_details, &recorded.user_publickey, &constants)
+        .unwrap();
+    assert_eq!(
+        host.verify_proof(&recorded.proof).err(),
+        Some(Srp6Error::InvalidProof)
+    );
+}
+
+// with `norand`, both users would have the same salt
+#[test]
+#[cfg(not(feature = "norand"))]
+fn should_reject_salt_of_another_user() {
+    let constants = OpenConstants::<256>::default();
+    let user_details = alice(&constants);
+    let bob = Srp6user2048::generate_new_user_secrets(&"bob".into(), &PASSWORD.into(), &constants)
+        .unwrap();
+    assert_ne!(bob.salt, user_details.salt);
+
+    let mut user = Srp6user2048::default();
+    let handshake = user.start_handshake(USERNAME, &constants).unwrap();
+    let (mut server_handshake, host) = Srp6_2048::default()
+        .continue_handshake(&user_details, &handshake.user_publickey, &constants)
+        .unwrap();
+    server_handshake.salt = bob.salt.clone();
+    let (proof, _) = user
+        .update_handshake(
+            &server_handshake,
+            &constants,
+            &USERNAME.into(),
+            &PASSWORD.into(),
+        )
+        .unwrap();
+    assert_eq!(
+        host.verify_proof(&proof).err(),
+        Some(Srp6Error::InvalidProof)
+    );
+}
+
+#[test]
+fn should_reject_user_of_another_group() {
+    let constants = OpenConstants::<256>::default();
+    let user_details = alice(&constants);
+
+    // `A` of 4096 bits does not fit in the group of the server
+    let mut user = Srp6user4096::default();
+    let handshake = user
+        .start_handshake(USERNAME, &OpenConstants::<512>::default())
+        .unwrap();
+    let result = Srp6_2048::default().continue_handshake(
+        &user_details,
+        &handshake.user_publickey,
+        &constants,
+    );
+    assert!(matches!(
+        result.err(),
+        Some(Srp6Error::KeyLengthMismatch { expected: 256, .. })
+    ));
+
+    // `A` of 2048 bits would be a valid key of the group of 4096 bits, but it
+    // is too short for it
+    let big_constants = OpenConstants::<512>::default();
+    let big_details =
+        Srp6user4096::generate_new_user_secrets(&USERNAME.into(), &PASSWORD.into(), &big_constants)
+            .unwrap();
+    let mut user = Srp6user2048::default();
+    let handshake = user.start_handshake(USERNAME, &constants).unwrap();
+    let result = Srp6_4096::default().continue_handshake(
+        &big_details,
+        &handshake.user_publickey,
+        &big_constants,
+    );
+    assert!(matches!(
+        result.err(),
+        Some(Srp6Error::KeyLengthMismatch { expected: 512, .. })
+    ));
+
+    // `B` of the group of 4096 bits for the user of 2048 bits
+    let big_handshake = Srp6user4096::default()
+        .start_handshake(USERNAME, &big_constants)
+        .unwrap();
+    let (mut server_handshake, host) = Srp6_4096::default()
+        .continue_handshake(&big_details, &big_handshake.user_publickey, &big_constants)
+        .unwrap();
+    // the server tells its group
+    assert_eq!(
+        user.clone()
+            .update_handshake(
+                &server_handshake,
+                &constants,
+                &USERNAME.into(),
+                &PASSWORD.into()
+            )
+            .err(),
+        Some(Srp6Error::GroupMismatch {
+            client_bits: 2048,
+            server_bits: 4096
+        })
+    );
+    // a server that does not
+    server_handshake.group_bits = 0;
+    match user.update_handshake(
+        &server_handshake,
+        &constants,
+        &USERNAME.into(),
+        &PASSWORD.into(),
+    ) {
+        Ok((proof, _)) => assert_eq!(
+            host.verify_proof(&proof).err(),
+            Some(Srp6Error::InvalidProof)
+        ),
+        // `B` of the bigger group is most likely too long for the user
+        Err(e) => assert!(matches!(e, Srp6Error::KeyLengthMismatch { .. }), "{e}"),
+    }
+}
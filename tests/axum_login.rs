@@ -0,0 +1,149 @@
+//! the login endpoints of `integrations::axum`, against an in-memory store
+#![cfg(feature = "axum")]
+
+use axum::body::{to_bytes, Body};
+use axum::http::{Request, StatusCode};
+use axum::Router;
+use chadehoc_srp6::integrations::axum::*;
+use chadehoc_srp6::*;
+use serde::{de::DeserializeOwned, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use tower::ServiceExt;
+
+const USERNAME: UsernameRef = "alice";
+const PASSWORD: &ClearTextPassword = "password123";
+
+#[derive(Default)]
+struct MemoryStore {
+    users: HashMap<Username, UserDetails>,
+    logged_in: Mutex<HashMap<Username, SessionSecret>>,
+}
+
+impl VerifierStore for MemoryStore {
+    fn user_details(&self, username: UsernameRef) -> Option<UserDetails> {
+        self.users.get(username).cloned()
+    }
+
+    fn logged_in(&self, username: UsernameRef, secret: SessionSecret) {
+        self.logged_in
+            .lock()
+            .unwrap()
+            .insert(username.into(), secret);
+    }
+}
+
+fn state() -> SrpServerState<256, MemoryStore> {
+    let constants = OpenConstants::<256>::default();
+    let mut store = MemoryStore::default();
+    store.users.insert(
+        USERNAME.into(),
+        Srp6user2048::generate_new_user_secrets(&USERNAME.into(), &PASSWORD.into(), &constants)
+            .unwrap(),
+    );
+    SrpServerState::new(constants, store)
+}
+
+/// posts `body` as json, returns the status and the answer if successful
+async fn post<T: Serialize, R: DeserializeOwned>(
+    app: &Router,
+    uri: &str,
+    session_id: Option<&SessionId>,
+    body: &T,
+) -> (StatusCode, Option<R>) {
+    let mut request = Request::post(uri).header("content-type", "application/json");
+    if let Some(session_id) = session_id {
+        request = request.header(SESSION_ID_HEADER, hex::encode(session_id));
+    }
+    let request = request
+        .body(Body::from(serde_json::to_vec(body).unwrap()))
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&bytes).ok())
+}
+
+/// the user side up to its proof, returns the session id and the waiting user
+async fn start_login(
+    app: &Router,
+    password: &ClearTextPassword,
+) -> (SessionId, ProofMessage, Srp6UserWaitingForServerProof<256>) {
+    let constants = OpenConstants::<256>::default();
+    let mut user = Srp6user2048::default();
+    let handshake = user.start_handshake(USERNAME, &constants).unwrap();
+    let (status, server_handshake) =
+        post::<_, ServerHandshake>(app, "/handshake", None, &handshake).await;
+    assert_eq!(status, StatusCode::OK);
+    let server_handshake = server_handshake.unwrap();
+    let (proof, user) = user
+        .update_handshake(
+            &server_handshake,
+            &constants,
+            &USERNAME.into(),
+            &password.into(),
+        )
+        .unwrap();
+    (server_handshake.session_id, proof.into(), user)
+}
+
+#[tokio::test]
+async fn should_login() {
+    let state = state();
+    let app = router(state.clone());
+
+    let (session_id, proof, user) = start_login(&app, PASSWORD).await;
+    assert_eq!(state.pending_handshakes(), 1);
+    let (status, strong_proof) =
+        post::<_, StrongProofMessage>(&app, "/proof", Some(&session_id), &proof).await;
+    assert_eq!(status, StatusCode::OK);
+    let user_secret = user
+        .verify_proof(&strong_proof.unwrap().strong_proof())
+        .unwrap()
+        .into_session_secret();
+    assert_eq!(state.pending_handshakes(), 0);
+    assert_eq!(
+        state.store().logged_in.lock().unwrap().get(USERNAME),
+        Some(&user_secret)
+    );
+
+    // a handshake gives a single try
+    let (status, _) =
+        post::<_, StrongProofMessage>(&app, "/proof", Some(&session_id), &proof).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn should_reject_wrong_password_and_unknown_user() {
+    let app = router(state());
+
+    let (session_id, proof, _) = start_login(&app, "wrong password").await;
+    let (status, _) =
+        post::<_, StrongProofMessage>(&app, "/proof", Some(&session_id), &proof).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let handshake = UserHandshake::new("bob", PublicKey::from(2));
+    let (status, _) = post::<_, ServerHandshake>(&app, "/handshake", None, &handshake).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let (status, _) = post::<_, StrongProofMessage>(&app, "/proof", None, &proof).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // refused by the JSON extractor, before the store is asked
+    let handshake = UserHandshake::new("alice", PublicKey::from_bytes_be(&[1; 10 * 256]));
+    let (status, _) = post::<_, ServerHandshake>(&app, "/handshake", None, &handshake).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+#[tokio::test]
+async fn should_reject_stale_handshake() {
+    let state = state().with_expiry(Duration::ZERO);
+    let app = router(state.clone());
+
+    let (session_id, proof, _) = start_login(&app, PASSWORD).await;
+    let (status, _) =
+        post::<_, StrongProofMessage>(&app, "/proof", Some(&session_id), &proof).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert!(state.store().logged_in.lock().unwrap().is_empty());
+}
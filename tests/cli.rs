@@ -0,0 +1,231 @@
+//! runs the `srp6-tool` binary, checked against the RFC 5054 test vectors
+#![cfg(feature = "cli")]
+
+use chadehoc_srp6::hazmat::testdata;
+use chadehoc_srp6::*;
+use std::io::{self, Write};
+use std::process::{Command, Output, Stdio};
+use std::thread;
+
+fn tool(args: &[&str]) -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_srp6-tool"));
+    command.args(args);
+    command
+}
+
+/// runs the tool with `stdin`, waits for it
+fn run(args: &[&str], stdin: &str) -> Output {
+    let mut child = tool(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+/// the stored details of alice of the test vectors in the group of `bits`, in
+/// a temporary file
+fn alice_file(name: &str, bits: &str) -> std::path::PathBuf {
+    let output = run(
+        &[
+            "verifier",
+            "--group",
+            bits,
+            "--username",
+            testdata::USERNAME,
+            "--salt",
+            &hex::encode(testdata::SALT),
+        ],
+        &format!("{}\n", testdata::PASSWORD),
+    );
+    let path = std::env::temp_dir().join(format!("srp6-tool-{}-{name}.json", std::process::id()));
+    std::fs::write(&path, stdout(&output)).unwrap();
+    path
+}
+
+#[test]
+fn should_print_the_verifier_of_the_test_vectors() {
+    let salt = hex::encode(testdata::SALT);
+    let args = [
+        "verifier",
+        "--group",
+        "1024",
+        "--username",
+        testdata::USERNAME,
+        "--salt",
+        &salt,
+    ];
+    let json = stdout(&run(&args, &format!("{}\n", testdata::PASSWORD)));
+    let details: UserDetails = serde_json::from_str(&json).unwrap();
+    assert_eq!(details.username, testdata::USERNAME);
+    assert_eq!(details.salt, Salt::from_bytes_be(&testdata::SALT));
+    assert_eq!(
+        details.verifier,
+        PasswordVerifier::from_bytes_be(&testdata::VERIFIER)
+    );
+
+    let hex = stdout(&run(
+        &[&args[..], &["--format", "hex"]].concat(),
+        testdata::PASSWORD,
+    ));
+    assert_eq!(
+        hex,
+        format!("salt = {}\nverifier = {}\n", details.salt, details.verifier)
+    );
+
+    let output = run(&args, "\n");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("The password is empty"));
+}
+
+#[test]
+fn should_check_stored_details() {
+    let path = alice_file("check", "1024");
+    let checked = stdout(&run(
+        &[
+            "check",
+            "--group",
+            "1024",
+            "--details",
+            path.to_str().unwrap(),
+        ],
+        "",
+    ));
+    assert!(checked.starts_with("valid: alice"), "{checked}");
+
+    // a zero verifier is out of range in any group
+    let mut details: UserDetails =
+        serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    details.verifier = PasswordVerifier::default();
+    std::fs::write(&path, serde_json::to_string(&details).unwrap()).unwrap();
+    let output = run(
+        &[
+            "check",
+            "--group",
+            "1024",
+            "--details",
+            path.to_str().unwrap(),
+        ],
+        "",
+    );
+    std::fs::remove_file(&path).unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("verifier"));
+}
+
+#[test]
+fn should_print_the_test_vectors() {
+    let vectors = stdout(&run(&["vectors", "--group", "1024"], ""));
+    for (name, value) in [
+        ("v", &testdata::VERIFIER[..]),
+        ("k", &testdata::K_MULTIPLIER),
+        ("x", &testdata::X),
+        ("A", &testdata::A_PUBLIC),
+        ("B", &testdata::B_PUBLIC),
+        ("u", &testdata::U),
+        ("S", &testdata::SECRET),
+    ] {
+        let line = format!("{name} = {}\n", hex::encode_upper(value));
+        assert!(vectors.contains(&line), "{line}");
+    }
+    assert!(!run(&["vectors", "--group", "2048"], "").status.success());
+    assert!(
+        !run(&["vectors", "--group", "1024", "--hash", "sha256"], "")
+            .status
+            .success()
+    );
+}
+
+/// the stderr of the server and of the client after a handshake, with `options`
+fn handshake(name: &str, options: &[&str]) -> (String, String) {
+    // the handshakes of the 1024-bit group need the `insecure-key-lengths` feature
+    let path = alice_file(name, "2048");
+    let spawn = |args: &[&str]| {
+        tool(&[args, options].concat())
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap()
+    };
+    let mut server = spawn(&[
+        "handshake",
+        "--group",
+        "2048",
+        "--role",
+        "server",
+        "--details",
+        path.to_str().unwrap(),
+    ]);
+    let mut client = spawn(&[
+        "handshake",
+        "--group",
+        "2048",
+        "--role",
+        "client",
+        "--username",
+        testdata::USERNAME,
+    ]);
+
+    let mut client_stdin = client.stdin.take().unwrap();
+    writeln!(client_stdin, "{}", testdata::PASSWORD).unwrap();
+    let (mut server_stdin, mut server_stdout) =
+        (server.stdin.take().unwrap(), server.stdout.take().unwrap());
+    let mut client_stdout = client.stdout.take().unwrap();
+    // each relay ends when its reader exits, then it closes the stdin of the other
+    let to_client = thread::spawn(move || io::copy(&mut server_stdout, &mut client_stdin));
+    let to_server = thread::spawn(move || io::copy(&mut client_stdout, &mut server_stdin));
+
+    let server = server.wait_with_output().unwrap();
+    let client = client.wait_with_output().unwrap();
+    // the relays may fail with a broken pipe once the other side exited
+    let _ = (to_client.join().unwrap(), to_server.join().unwrap());
+    std::fs::remove_file(&path).unwrap();
+
+    let (server_err, client_err) = (
+        String::from_utf8_lossy(&server.stderr).into_owned(),
+        String::from_utf8_lossy(&client.stderr).into_owned(),
+    );
+    assert!(server.status.success(), "{server_err}");
+    assert!(client.status.success(), "{client_err}");
+    (server_err, client_err)
+}
+
+/// the line of the result on stderr
+fn authenticated(stderr: &str) -> Option<&str> {
+    stderr.lines().find(|l| l.starts_with("authenticated"))
+}
+
+#[test]
+fn should_run_a_handshake_between_server_and_client() {
+    let (server_err, client_err) = handshake("handshake", &[]);
+    // the session key is a secret, not printed by default
+    assert_eq!(authenticated(&server_err), Some("authenticated"));
+    assert_eq!(authenticated(&client_err), Some("authenticated"));
+    assert!(!server_err.contains("K =") && !client_err.contains("K ="));
+}
+
+#[test]
+fn should_show_the_session_key_on_demand() {
+    let (server_err, client_err) = handshake("show-key", &["--show-key"]);
+    // both print the same `K`
+    let key = authenticated(&server_err).unwrap();
+    assert!(key.starts_with("authenticated, K = "), "{key}");
+    assert_eq!(Some(key), authenticated(&client_err));
+}
@@ -0,0 +1,153 @@
+//! the full handshake against the test vectors of `tests/vectors/*.json`, with
+//! the private keys `a` and `b` of each vector: every intermediate value is
+//! checked, not only the session key
+use chadehoc_srp6::hazmat::*;
+use chadehoc_srp6::test_util::DeterministicKeys;
+use chadehoc_srp6::*;
+use serde::Deserialize;
+use std::path::Path;
+
+/// one vector, the numbers as big endian hex
+#[derive(Debug, Deserialize)]
+#[allow(non_snake_case)]
+struct Vector {
+    description: String,
+    /// `SHA-1` or `SHA-256`
+    hash: String,
+    profile: CompatProfile,
+    I: Username,
+    p: String,
+    s: Salt,
+    N: PrimeModulus,
+    g: Generator,
+    a: PrivateKey,
+    b: PrivateKey,
+    A: PublicKey,
+    B: PublicKey,
+    /// read as the public keys, `u` has no alias of its own
+    u: PublicKey,
+    k: MultiplierParameter,
+    x: PrivateKey,
+    v: PasswordVerifier,
+    S: SessionKey,
+    /// the bytes of the strong session key, in the order of `SHA_Interleave`
+    K: SessionKey,
+    M1: Proof,
+    M2: StrongProof,
+}
+
+fn vectors() -> Vec<(String, Vector)> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/vectors");
+    let mut paths: Vec<_> = std::fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    paths.sort();
+    paths
+        .into_iter()
+        .map(|path| {
+            let json = std::fs::read_to_string(&path).unwrap();
+            let name = path.file_name().unwrap().to_string_lossy().into_owned();
+            let vector = serde_json::from_str(&json)
+                .unwrap_or_else(|e| panic!("{name} should be a vector: {e}"));
+            (name, vector)
+        })
+        .collect()
+}
+
+#[allow(non_snake_case)]
+fn check<const LEN: usize, D: Digest>(name: &str, vector: &Vector) {
+    let constants = OpenConstants::<LEN>::try_new(vector.N.clone(), vector.g.clone()).unwrap();
+    let (I, p, profile) = (vector.I.as_str(), vector.p.as_str(), vector.profile);
+
+    let k = calculate_k::<D>(LEN, &vector.N, &vector.g, Srp6Variant::Srp6a, profile).unwrap();
+    assert_eq!(k, vector.k, "{name}: k");
+    let x = calculate_private_key_x::<D>(I, p, &vector.s, profile);
+    assert_eq!(x, vector.x, "{name}: x");
+    let v = calculate_password_verifier_v(&vector.N, &vector.g, None, &x);
+    assert_eq!(v, vector.v, "{name}: v");
+    if profile == CompatProfile::Rfc5054 {
+        let details = Srp6::<LEN, D>::generate_user_details_with_salt(
+            &I.into(),
+            &p.into(),
+            &vector.s,
+            &constants,
+        )
+        .unwrap();
+        assert_eq!(details.verifier, vector.v, "{name}: v of the details");
+    }
+    let user_details = UserDetails::from_parts(
+        I,
+        RawNumber::Bytes(&vector.s.to_vec(), ByteOrder::BigEndian),
+        RawNumber::Bytes(&vector.v.to_vec(), ByteOrder::BigEndian),
+        &constants,
+    )
+    .unwrap();
+
+    let keys = DeterministicKeys::new(vector.a.clone(), vector.b.clone());
+    let mut user = Srp6User::<LEN, D>::default().with_profile(profile);
+    let user_handshake = user
+        .start_handshake_with_keys(I, &constants, &keys)
+        .unwrap();
+    assert_eq!(user_handshake.user_publickey, vector.A, "{name}: A");
+
+    let (server_handshake, host) = Srp6::<LEN, D>::default()
+        .with_profile(profile)
+        .continue_handshake_with_keys(&user_details, &user_handshake, &constants, &keys)
+        .unwrap();
+    assert_eq!(server_handshake.server_publickey, vector.B, "{name}: B");
+    assert_eq!(server_handshake.salt, vector.s, "{name}: s");
+    assert_eq!(
+        host.scrambling_parameter(),
+        &vector.u,
+        "{name}: u of the host"
+    );
+    assert_eq!(
+        calculate_u::<D>(LEN, &vector.A, &vector.B, profile).unwrap(),
+        vector.u,
+        "{name}: u"
+    );
+
+    let (M1, user) = user
+        .update_handshake(&server_handshake, &constants, &I.into(), &p.into())
+        .unwrap();
+    assert_eq!(M1, vector.M1, "{name}: M1");
+    assert_eq!(
+        user.scrambling_parameter(),
+        &vector.u,
+        "{name}: u of the user"
+    );
+
+    let outcome = host.verify_proof(&M1).unwrap();
+    let M2 = outcome.server_proof().clone();
+    let host_secret = outcome.into_session_secret();
+    assert_eq!(M2, vector.M2, "{name}: M2");
+    let user_secret = user.verify_proof(&M2).unwrap().into_session_secret();
+    for secret in [host_secret, user_secret] {
+        // the interleaved `K` is read back as a little endian number
+        let K = secret.strong_session_key().to_bytes();
+        let K = match profile {
+            CompatProfile::Rfc5054 => SessionKey::from_bytes_le(&K),
+            _ => SessionKey::from_bytes_be(&K),
+        };
+        assert_eq!(K, vector.K, "{name}: K");
+        assert_eq!(secret.into_raw_session_key(), vector.S, "{name}: S");
+    }
+}
+
+#[test]
+fn should_match_the_vectors() {
+    let vectors = vectors();
+    assert!(vectors.len() >= 3, "the vectors should be found");
+    for (name, vector) in &vectors {
+        assert!(!vector.description.is_empty(), "{name}: description");
+        match (vector.N.num_bytes(), vector.hash.as_str()) {
+            (128, "SHA-1") => check::<128, Sha1>(name, vector),
+            (128, "SHA-256") => check::<128, Sha256>(name, vector),
+            (256, "SHA-1") => check::<256, Sha1>(name, vector),
+            (256, "SHA-256") => check::<256, Sha256>(name, vector),
+            (len, hash) => panic!("{name}: no group of {len} bytes with {hash}"),
+        }
+    }
+}
@@ -0,0 +1,165 @@
+//! the modular exponentiations of the `fixed-bignum` feature only allocate
+//! their result, counted by the global allocator of this test
+//!
+//! only `modpow` is checked: the rest of the arithmetic stays on `num-bigint`,
+//! a handshake without allocations is not provided by the feature
+use chadehoc_srp6::*;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.with(|count| count.set(count.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.with(|count| count.set(count.get() + 1));
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// the allocations of `f` on this thread
+fn allocations<T>(f: impl FnOnce() -> T) -> (T, usize) {
+    let before = ALLOCATIONS.with(Cell::get);
+    let value = f();
+    (value, ALLOCATIONS.with(Cell::get) - before)
+}
+
+#[test]
+fn should_only_allocate_the_result_of_modpow() {
+    let constants = OpenConstants::<256>::default();
+    let n = constants.module();
+    let exponent = PrivateKey::from_bytes_be(&[0xA5; 256]);
+    let (_, count) = allocations(|| constants.generator.modpow(&exponent, n));
+    assert!(count <= 2, "{count} allocations");
+    // `a + u * x` of the user is a bit longer than `N`
+    let exponent = PrivateKey::from_bytes_be(&[0xA5; 257]);
+    let base = PublicKey::from_bytes_be(&[0x5A; 255]);
+    let (_, count) = allocations(|| base.modpow(&exponent, n));
+    assert!(count <= 2, "{count} allocations");
+}
+
+#[test]
+fn should_agree_on_secrets_with_fixed_numbers() {
+    let (username, password) = ("alice", "password123");
+    let constants = OpenConstants::<256>::default();
+    let user_details =
+        Srp6_2048::generate_new_user_secrets(&username.into(), &password.into(), &constants)
+            .unwrap();
+    let mut user = Srp6user2048::default();
+    let user_handshake = user.start_handshake(username, &constants).unwrap();
+    let (server_handshake, host) = Srp6_2048::default()
+        .continue_handshake(&user_details, &user_handshake, &constants)
+        .unwrap();
+    let (proof, user) = user
+        .update_handshake(
+            &server_handshake,
+            &constants,
+            &username.into(),
+            &password.into(),
+        )
+        .unwrap();
+    let outcome = host.verify_proof(&proof).unwrap();
+    let user_outcome = user.verify_proof(outcome.server_proof()).unwrap();
+    assert_eq!(
+        outcome.strong_session_key(),
+        user_outcome.strong_session_key()
+    );
+}
+
+/// the RFC 5054 vector of 1024 bits through `crypto-bigint`, cross-checked with
+/// the exponentiations of `num-bigint`
+#[cfg(all(
+    feature = "deterministic",
+    feature = "hazmat",
+    feature = "insecure-key-lengths"
+))]
+#[test]
+#[allow(non_snake_case)]
+fn should_pass_the_rfc_5054_vector_with_fixed_numbers() {
+    use chadehoc_srp6::hazmat::testdata;
+    use num_bigint::BigUint;
+
+    let (username, password) = (testdata::USERNAME, testdata::PASSWORD);
+    let constants = OpenConstants::<128>::default();
+    let user_details = Srp6user1024::generate_new_user_secrets_with_salt(
+        &username.into(),
+        &password.into(),
+        &Salt::from_bytes_be(&testdata::SALT),
+        &constants,
+    )
+    .unwrap();
+    assert_eq!(user_details.verifier.to_vec(), testdata::VERIFIER, "v");
+    let mut user = Srp6user1024::default();
+    let user_handshake = user
+        .start_handshake_with_ephemeral(
+            username,
+            &constants,
+            PrivateKey::from_bytes_be(&testdata::A_PRIVATE),
+        )
+        .unwrap();
+    assert_eq!(
+        user_handshake.user_publickey.to_vec(),
+        testdata::A_PUBLIC,
+        "A"
+    );
+    let (server_handshake, host) = Srp6_1024::default()
+        .continue_handshake_with_ephemeral(
+            &user_details,
+            &user_handshake,
+            &constants,
+            PrivateKey::from_bytes_be(&testdata::B_PRIVATE),
+        )
+        .unwrap();
+    assert_eq!(
+        server_handshake.server_publickey.to_vec(),
+        testdata::B_PUBLIC,
+        "B"
+    );
+    let (proof, user) = user
+        .update_handshake(
+            &server_handshake,
+            &constants,
+            &username.into(),
+            &password.into(),
+        )
+        .unwrap();
+    let outcome = host.verify_proof(&proof).unwrap();
+    let user_secret = user
+        .verify_proof(outcome.server_proof())
+        .unwrap()
+        .into_session_secret();
+    let S = outcome.into_session_secret().into_raw_session_key();
+    assert_eq!(S.to_vec(), testdata::SECRET, "S");
+    assert_eq!(user_secret.into_raw_session_key(), S);
+
+    // `S = (A * v^u)^b % N` of the host, on `num-bigint` alone
+    let big = BigUint::from_bytes_be;
+    let N = big(&constants.module().to_vec());
+    let (A, v, u, b) = (
+        big(&testdata::A_PUBLIC),
+        big(&testdata::VERIFIER),
+        big(&testdata::U),
+        big(&testdata::B_PRIVATE),
+    );
+    assert_eq!(
+        big(&testdata::A_PUBLIC),
+        big(&constants.generator.to_vec()).modpow(&big(&testdata::A_PRIVATE), &N)
+    );
+    let heap_S = (A * v.modpow(&u, &N) % &N).modpow(&b, &N);
+    assert_eq!(heap_S.to_bytes_be(), S.to_vec());
+}
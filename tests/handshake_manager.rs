@@ -0,0 +1,186 @@
+//! the in-flight handshakes of `server::HandshakeManager`
+#![cfg(feature = "server")]
+
+use chadehoc_srp6::server::*;
+use chadehoc_srp6::*;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const USERNAME: UsernameRef = "alice";
+const PASSWORD: &ClearTextPassword = "password123";
+
+/// a clock that only moves when told so
+#[derive(Clone)]
+struct ManualClock(Arc<Mutex<Instant>>);
+
+impl ManualClock {
+    fn new() -> Self {
+        Self(Arc::new(Mutex::new(Instant::now())))
+    }
+
+    fn advance(&self, duration: Duration) {
+        *self.0.lock().unwrap() += duration;
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        *self.0.lock().unwrap()
+    }
+}
+
+fn user_details(constants: &OpenConstants<256>) -> UserDetails {
+    Srp6user2048::generate_new_user_secrets(&USERNAME.into(), &PASSWORD.into(), constants).unwrap()
+}
+
+/// the user side of a login through `manager`, returns the user's proof and
+/// the user waiting for the server's proof
+fn begin<C: Clock>(
+    manager: &HandshakeManager<256, Sha1, C>,
+    user_details: &UserDetails,
+    password: &ClearTextPassword,
+    constants: &OpenConstants<256>,
+) -> Result<(SessionId, Proof, Srp6UserWaitingForServerProof<256>)> {
+    let mut user = Srp6user2048::default();
+    let handshake = user.start_handshake(USERNAME, constants).unwrap();
+    let (session_id, server_handshake) =
+        manager.begin(user_details, &handshake.user_publickey, constants)?;
+    assert_eq!(session_id, server_handshake.session_id);
+    let (proof, user) = user.update_handshake(
+        &server_handshake,
+        constants,
+        &USERNAME.into(),
+        &password.into(),
+    )?;
+    Ok((session_id, proof, user))
+}
+
+#[test]
+fn should_login_once() {
+    let constants = OpenConstants::<256>::default();
+    let user_details = user_details(&constants);
+    let manager = HandshakeManager::<256>::new();
+
+    let (session_id, proof, user) = begin(&manager, &user_details, PASSWORD, &constants).unwrap();
+    assert_eq!(manager.pending_handshakes(), 1);
+    let (strong_proof, strong_session_key) = manager.finish(&session_id, &proof).unwrap();
+    let secret = user
+        .verify_proof(&strong_proof)
+        .unwrap()
+        .into_session_secret();
+    assert_eq!(secret.strong_session_key(), &strong_session_key);
+    assert_eq!(manager.pending_handshakes(), 0);
+
+    assert_eq!(
+        manager.finish(&session_id, &proof),
+        Err(Srp6Error::UnknownHandshake)
+    );
+}
+
+#[test]
+fn should_not_retry_a_wrong_proof() {
+    let constants = OpenConstants::<256>::default();
+    let user_details = user_details(&constants);
+    let manager = HandshakeManager::<256>::new();
+
+    let (session_id, proof, _) =
+        begin(&manager, &user_details, "wrong password", &constants).unwrap();
+    assert_eq!(
+        manager.finish(&session_id, &proof),
+        Err(Srp6Error::InvalidProof)
+    );
+    assert_eq!(manager.pending_handshakes(), 0);
+    assert_eq!(
+        manager.finish(&session_id, &proof),
+        Err(Srp6Error::UnknownHandshake)
+    );
+}
+
+#[test]
+fn should_expire_handshakes() {
+    let constants = OpenConstants::<256>::default();
+    let user_details = user_details(&constants);
+    let clock = ManualClock::new();
+    let manager = HandshakeManager::<256, Sha1, _>::with_clock(clock.clone())
+        .with_ttl(Duration::from_secs(30));
+
+    let (late_id, late_proof, _) = begin(&manager, &user_details, PASSWORD, &constants).unwrap();
+    clock.advance(Duration::from_secs(20));
+    let (session_id, proof, _) = begin(&manager, &user_details, PASSWORD, &constants).unwrap();
+    assert_eq!(manager.purge_expired(), 0);
+
+    clock.advance(Duration::from_secs(10));
+    assert_eq!(manager.pending_handshakes(), 2);
+    assert_eq!(
+        manager.finish(&late_id, &late_proof),
+        Err(Srp6Error::UnknownHandshake)
+    );
+    assert_eq!(manager.pending_handshakes(), 1);
+    assert!(manager.finish(&session_id, &proof).is_ok());
+
+    begin(&manager, &user_details, PASSWORD, &constants).unwrap();
+    begin(&manager, &user_details, PASSWORD, &constants).unwrap();
+    clock.advance(Duration::from_secs(30));
+    assert_eq!(manager.purge_expired(), 2);
+    assert_eq!(manager.pending_handshakes(), 0);
+}
+
+#[test]
+fn should_limit_pending_handshakes() {
+    let constants = OpenConstants::<256>::default();
+    let user_details = user_details(&constants);
+    let clock = ManualClock::new();
+    let manager = HandshakeManager::<256, Sha1, _>::with_clock(clock.clone())
+        .with_ttl(Duration::from_secs(30))
+        .with_max_pending(2);
+
+    let (session_id, proof, _) = begin(&manager, &user_details, PASSWORD, &constants).unwrap();
+    begin(&manager, &user_details, PASSWORD, &constants).unwrap();
+    assert_eq!(
+        begin(&manager, &user_details, PASSWORD, &constants).unwrap_err(),
+        Srp6Error::TooManyPendingHandshakes
+    );
+
+    // a finished handshake frees its place
+    manager.finish(&session_id, &proof).unwrap();
+    begin(&manager, &user_details, PASSWORD, &constants).unwrap();
+
+    // the expired ones too, without an explicit purge
+    clock.advance(Duration::from_secs(30));
+    begin(&manager, &user_details, PASSWORD, &constants).unwrap();
+    assert_eq!(manager.pending_handshakes(), 1);
+}
+
+#[test]
+fn should_login_from_several_threads() {
+    let constants = Arc::new(OpenConstants::<256>::default());
+    let user_details = Arc::new(user_details(&constants));
+    let manager = Arc::new(HandshakeManager::<256>::new());
+
+    let handles: Vec<_> = (0..8)
+        .map(|i| {
+            let (constants, user_details, manager) =
+                (constants.clone(), user_details.clone(), manager.clone());
+            thread::spawn(move || {
+                let password = if i % 2 == 0 { PASSWORD } else { "wrong" };
+                let (session_id, proof, user) =
+                    begin(&manager, &user_details, password, &constants).unwrap();
+                match manager.finish(&session_id, &proof) {
+                    Ok((strong_proof, _)) => user.verify_proof(&strong_proof).is_ok(),
+                    Err(e) => {
+                        assert_eq!(e, Srp6Error::InvalidProof);
+                        false
+                    }
+                }
+            })
+        })
+        .collect();
+    let logged_in = handles
+        .into_iter()
+        .map(|handle| handle.join().unwrap())
+        .filter(|ok| *ok)
+        .count();
+    assert_eq!(logged_in, 4);
+    assert_eq!(manager.pending_handshakes(), 0);
+}
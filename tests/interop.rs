@@ -0,0 +1,130 @@
+//! the handshake against the fixtures of `tests/vectors/interop/*.json`, each
+//! captured by running another SRP6a library with the script named in it, see
+//! `tests/vectors/interop/README.md`
+//!
+//! unlike the vectors of `tests/conformance.rs`, which may be transcriptions of
+//! the formulas of a library, these values come out of the library itself
+use chadehoc_srp6::hazmat::*;
+use chadehoc_srp6::test_util::DeterministicKeys;
+use chadehoc_srp6::*;
+use serde::Deserialize;
+use std::path::Path;
+
+/// one capture, the numbers as big endian hex
+#[derive(Debug, Deserialize)]
+#[allow(non_snake_case)]
+struct Fixture {
+    /// the package, e.g. `tssrp6a` or `srp`
+    library: String,
+    /// the version of the package that produced the values
+    version: String,
+    /// the script that ran it, relative to `tests/vectors/interop`
+    script: String,
+    /// `SHA-1` or `SHA-256`
+    hash: String,
+    profile: CompatProfile,
+    I: Username,
+    p: String,
+    N: PrimeModulus,
+    g: Generator,
+    s: Salt,
+    v: PasswordVerifier,
+    a: PrivateKey,
+    b: PrivateKey,
+    A: PublicKey,
+    B: PublicKey,
+    S: SessionKey,
+    /// the bytes of `K`, when the library gives it
+    K: Option<SessionKey>,
+    M1: Proof,
+    M2: StrongProof,
+}
+
+fn interop_dir() -> std::path::PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/vectors/interop")
+}
+
+fn fixtures() -> Vec<(String, Fixture)> {
+    let mut paths: Vec<_> = std::fs::read_dir(interop_dir())
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    paths.sort();
+    paths
+        .into_iter()
+        .map(|path| {
+            let json = std::fs::read_to_string(&path).unwrap();
+            let name = path.file_name().unwrap().to_string_lossy().into_owned();
+            let fixture = serde_json::from_str(&json)
+                .unwrap_or_else(|e| panic!("{name} should be a fixture: {e}"));
+            (name, fixture)
+        })
+        .collect()
+}
+
+/// both sides of the handshake with the `a` and `b` of the capture, against
+/// the values that the library computed
+#[allow(non_snake_case)]
+fn check<const LEN: usize, D: Digest>(name: &str, fixture: &Fixture) {
+    let constants = OpenConstants::<LEN>::try_new(fixture.N.clone(), fixture.g.clone()).unwrap();
+    let (I, p, profile) = (fixture.I.as_str(), fixture.p.as_str(), fixture.profile);
+
+    let x = calculate_private_key_x::<D>(I, p, &fixture.s, profile);
+    let v = calculate_password_verifier_v(&fixture.N, &fixture.g, None, &x);
+    assert_eq!(v, fixture.v, "{name}: v");
+    let user_details = UserDetails::from_parts(
+        I,
+        RawNumber::Bytes(&fixture.s.to_vec(), ByteOrder::BigEndian),
+        RawNumber::Bytes(&fixture.v.to_vec(), ByteOrder::BigEndian),
+        &constants,
+    )
+    .unwrap();
+
+    let keys = DeterministicKeys::new(fixture.a.clone(), fixture.b.clone());
+    let mut user = Srp6User::<LEN, D>::default().with_profile(profile);
+    let user_handshake = user
+        .start_handshake_with_keys(I, &constants, &keys)
+        .unwrap();
+    assert_eq!(user_handshake.user_publickey, fixture.A, "{name}: A");
+    let (server_handshake, host) = Srp6::<LEN, D>::default()
+        .with_profile(profile)
+        .continue_handshake_with_keys(&user_details, &user_handshake, &constants, &keys)
+        .unwrap();
+    assert_eq!(server_handshake.server_publickey, fixture.B, "{name}: B");
+
+    let (M1, user) = user
+        .update_handshake(&server_handshake, &constants, &I.into(), &p.into())
+        .unwrap();
+    assert_eq!(M1, fixture.M1, "{name}: M1");
+    let outcome = host.verify_proof(&fixture.M1).unwrap();
+    assert_eq!(outcome.server_proof(), &fixture.M2, "{name}: M2");
+    let secret = user
+        .verify_proof(&fixture.M2)
+        .unwrap()
+        .into_session_secret();
+    if let Some(K) = &fixture.K {
+        let bytes = secret.strong_session_key().to_bytes();
+        assert_eq!(&SessionKey::from_bytes_be(&bytes), K, "{name}: K");
+    }
+    assert_eq!(secret.into_raw_session_key(), fixture.S, "{name}: S");
+}
+
+#[test]
+#[ignore = "the fixtures are captured with the scripts of tests/vectors/interop, see its README"]
+fn should_interoperate_with_the_captured_libraries() {
+    let fixtures = fixtures();
+    assert!(!fixtures.is_empty(), "no fixture captured yet");
+    for (name, fixture) in &fixtures {
+        assert!(
+            !fixture.version.is_empty() && interop_dir().join(&fixture.script).is_file(),
+            "{name}: {} should record its version and script",
+            fixture.library
+        );
+        match (fixture.N.num_bytes(), fixture.hash.as_str()) {
+            (256, "SHA-1") => check::<256, Sha1>(name, fixture),
+            (256, "SHA-256") => check::<256, Sha256>(name, fixture),
+            (len, hash) => panic!("{name}: no group of {len} bytes with {hash}"),
+        }
+    }
+}
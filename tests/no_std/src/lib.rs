@@ -0,0 +1,38 @@
+//! The user side of the handshake, as it would run on a device without std.
+#![no_std]
+
+use chadehoc_srp6::*;
+use rand_core::{CryptoRng, RngCore};
+
+/// first step, the returned [`UserHandshake`] is sent to the server
+pub fn start<R: RngCore + CryptoRng>(
+    user: &mut Srp6user2048,
+    username: UsernameRef,
+    rng: &mut R,
+) -> Result<UserHandshake> {
+    user.start_handshake_with_rng(username, &OpenConstants::default(), rng)
+}
+
+/// second step, the returned [`Proof`] is sent to the server
+pub fn prove(
+    user: Srp6user2048,
+    server_handshake: &ServerHandshake,
+    username: &Username,
+    password: &Password,
+) -> Result<(Proof, Srp6UserWaitingForServerProof<256>)> {
+    user.update_handshake(
+        server_handshake,
+        &OpenConstants::default(),
+        username,
+        password,
+    )
+}
+
+/// last step, on success the session secret is returned
+pub fn finish(
+    user: Srp6UserWaitingForServerProof<256>,
+    servers_proof: &StrongProof,
+) -> Result<SessionSecret> {
+    user.verify_proof(servers_proof)
+        .map(ClientHandshakeOutcome::into_session_secret)
+}
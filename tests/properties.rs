@@ -0,0 +1,124 @@
+//! property tests of the handshake and of the parsing of the numbers,
+//! the fuzz targets are in `fuzz/`
+use chadehoc_srp6::*;
+use proptest::prelude::*;
+
+/// both sides with the given user, returns the secrets of the host and the user
+fn handshake<const LEN: usize>(
+    username: UsernameRef,
+    password: &ClearTextPassword,
+    salt_length: usize,
+    constants: &OpenConstants<LEN>,
+) -> (SessionSecret, SessionSecret) {
+    let user_details = Srp6User::<LEN>::generate_new_user_secrets_with_salt_length(
+        &username.into(),
+        &password.into(),
+        constants,
+        salt_length,
+    )
+    .unwrap();
+    let mut user = Srp6User::<LEN>::default();
+    let user_handshake = user.start_handshake(username, constants).unwrap();
+    let (server_handshake, host) = Srp6::<LEN>::default()
+        .continue_handshake(&user_details, &user_handshake.user_publickey, constants)
+        .unwrap();
+    let (proof, user) = user
+        .update_handshake(
+            &server_handshake,
+            constants,
+            &username.into(),
+            &password.into(),
+        )
+        .unwrap();
+    let outcome = host.verify_proof(&proof).unwrap();
+    let strong_proof = outcome.server_proof().clone();
+    let host_secret = outcome.into_session_secret();
+    let user_secret = user
+        .verify_proof(&strong_proof)
+        .unwrap()
+        .into_session_secret();
+    (host_secret, user_secret)
+}
+
+proptest! {
+    // the handshakes are slow, a few cases cover both branches of `B - k * g^x`
+    #![proptest_config(ProptestConfig::with_cases(16))]
+
+    #[test]
+    fn should_agree_on_secrets_2048(
+        username in "[a-zA-Z0-9._@-]{1,32}",
+        password in "\\PC{1,32}",
+        salt_length in 1_usize..64,
+    ) {
+        let (host_secret, user_secret) =
+            handshake(&username, &password, salt_length, &OpenConstants::<256>::default());
+        prop_assert_eq!(host_secret, user_secret);
+    }
+
+    #[test]
+    fn should_agree_on_secrets_4096(
+        username in "[a-zA-Z0-9._@-]{1,32}",
+        password in "\\PC{1,32}",
+        salt_length in 1_usize..64,
+    ) {
+        let (host_secret, user_secret) =
+            handshake(&username, &password, salt_length, &OpenConstants::<512>::default());
+        prop_assert_eq!(host_secret, user_secret);
+    }
+}
+
+proptest! {
+    #[test]
+    fn should_parse_own_hex(bytes in proptest::collection::vec(any::<u8>(), 0..128)) {
+        let x = PublicKey::from_bytes_be(&bytes);
+        // without the leading zeros, the length may be odd
+        let hex = String::from(&x);
+        prop_assert_eq!(PublicKey::from_hex_str_be(&hex).unwrap(), x);
+    }
+
+    #[test]
+    fn should_pad_without_truncating(bytes in proptest::collection::vec(any::<u8>(), 0..=64)) {
+        let x = PublicKey::from_bytes_be(&bytes);
+        let padded = x.to_array_pad_zero::<64>();
+        prop_assert_eq!(&padded[64 - bytes.len()..], &bytes[..]);
+        prop_assert_eq!(PublicKey::from_bytes_be(&padded), x);
+    }
+}
+
+proptest! {
+    #[test]
+    fn should_invert_when_coprime(
+        a in proptest::collection::vec(any::<u8>(), 0..64),
+        n in proptest::collection::vec(any::<u8>(), 1..64),
+    ) {
+        let (a, n) = (PublicKey::from_bytes_be(&a), PublicKey::from_bytes_be(&n));
+        prop_assume!(n > PublicKey::from(1));
+        let one = PublicKey::from(1);
+        match a.mod_inverse(&n) {
+            Some(inverse) => {
+                prop_assert!(inverse < n);
+                prop_assert_eq!(&(&a * &inverse) % &n, one.clone());
+                prop_assert_eq!(a.gcd(&n), one);
+            }
+            None => prop_assert_ne!(a.gcd(&n), one),
+        }
+    }
+
+    #[test]
+    fn should_divide_both_by_the_gcd(
+        a in proptest::collection::vec(any::<u8>(), 0..64),
+        b in proptest::collection::vec(any::<u8>(), 0..64),
+    ) {
+        let (a, b) = (PublicKey::from_bytes_be(&a), PublicKey::from_bytes_be(&b));
+        let gcd = a.gcd(&b);
+        prop_assert_eq!(&gcd, &b.gcd(&a));
+        if gcd != PublicKey::default() {
+            prop_assert_eq!(&a % &gcd, PublicKey::default());
+            prop_assert_eq!(&b % &gcd, PublicKey::default());
+            // and no more: the cofactors are coprime
+            let a = PublicKey::from(a.as_biguint() / gcd.as_biguint());
+            let b = PublicKey::from(b.as_biguint() / gcd.as_biguint());
+            prop_assert_eq!(a.gcd(&b), PublicKey::from(1));
+        }
+    }
+}
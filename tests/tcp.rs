@@ -0,0 +1,75 @@
+//! runs the TCP examples against each other, on an ephemeral port
+
+#[path = "../examples/common/mod.rs"]
+mod common;
+
+use chadehoc_srp6::*;
+use std::io;
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+
+/// serves one connection, then authenticates with `password`
+fn authenticate_over_tcp(
+    password: &ClearTextPassword,
+) -> (io::Result<SessionSecret>, io::Result<SessionSecret>) {
+    let constants = OpenConstants::<{ common::LEN }>::default();
+    let user_details = common::User::generate_new_user_secrets(
+        &common::USERNAME.into(),
+        &common::PASSWORD.into(),
+        &constants,
+    )
+    .unwrap();
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    let server = thread::spawn(move || {
+        let (mut stream, _) = listener.accept()?;
+        common::serve(&mut stream, &user_details)
+    });
+
+    let mut stream = TcpStream::connect(address).unwrap();
+    let user_result = common::authenticate(&mut stream, common::USERNAME, password);
+    drop(stream);
+    (server.join().unwrap(), user_result)
+}
+
+#[test]
+fn should_authenticate_over_tcp() {
+    let (server_secret, user_secret) = authenticate_over_tcp(common::PASSWORD);
+    let server_secret = server_secret.unwrap();
+    let user_secret = user_secret.unwrap();
+    assert_eq!(server_secret, user_secret);
+    assert_eq!(
+        server_secret.clone().into_raw_session_key(),
+        user_secret.clone().into_raw_session_key()
+    );
+    assert_eq!(
+        server_secret.strong_session_key(),
+        user_secret.strong_session_key()
+    );
+}
+
+#[test]
+fn should_fail_over_tcp_with_wrong_password() {
+    let (server_secret, user_secret) = authenticate_over_tcp("wrong-password");
+    assert_eq!(
+        server_secret.unwrap_err().kind(),
+        io::ErrorKind::InvalidData
+    );
+    // the server closes the connection instead of sending its proof
+    assert_eq!(
+        user_secret.unwrap_err().kind(),
+        io::ErrorKind::UnexpectedEof
+    );
+}
+
+#[test]
+fn should_round_trip_frames() {
+    let mut buffer = Vec::new();
+    common::write_frame(&mut buffer, "Bob:ab11cd").unwrap();
+    assert_eq!(&buffer[..4], &[0, 0, 0, 10]);
+    assert_eq!(
+        common::read_frame(&mut buffer.as_slice()).unwrap(),
+        "Bob:ab11cd"
+    );
+    assert!(common::read_frame(&mut [0xff; 8].as_slice()).is_err());
+}
@@ -0,0 +1,177 @@
+//! the TLS-SRP key exchange messages of RFC 5054 against the ones of OpenSSL,
+//! and their round trips
+use chadehoc_srp6::tls::{ClientKeyExchange, ServerKeyExchangeParams};
+use chadehoc_srp6::*;
+use hex_literal::hex;
+use proptest::prelude::*;
+
+/// the bodies of the ServerKeyExchange and ClientKeyExchange of a handshake
+/// between OpenSSL 3.0.19 peers in `SRP-AES-128-CBC-SHA` over TLS 1.2, for the
+/// user `alice` with the password `password` in the 2048-bit group of
+/// `openssl srp -gn 2048`
+///
+/// `s_server -srpvfile` of that version crashes on the lookup of the user,
+/// the server was a minimal one with the same calls (`SRP_VBASE_get1_by_user`
+/// and `SSL_set_srp_server_param`), both messages dumped by the message
+/// callback of the client, and the handshake completed.
+const OPENSSL_SERVER_KEY_EXCHANGE: &[u8] = &hex!(
+    "0100AC6BDB41324A9A9BF166DE5E1389582FAF72B6651987EE07FC3192943DB5"
+    "6050A37329CBB4A099ED8193E0757767A13DD52312AB4B03310DCD7F48A9DA04"
+    "FD50E8083969EDB767B0CF6095179A163AB3661A05FBD5FAAAE82918A9962F0B"
+    "93B855F97993EC975EEAA80D740ADBF4FF747359D041D5C33EA71D281E446B14"
+    "773BCA97B43A23FB801676BD207A436C6481F1D2B9078717461A5B9D32E688F8"
+    "7748544523B524B0D57D5EA77A2775D2ECFA032CFBDBF52FB3786160279004E5"
+    "7AE6AF874E7303CE53299CCC041C7BC308D82A5698F3A8D0C38271AE35F8E9DB"
+    "FBB694B5C803D89F7AE435DE236D525F54759B65E372FCD68EF20FA7111F9E4A"
+    "FF73000102149E3957BCE337822C03E6ADC792B0495E9D6D5DE20100A9C6F213"
+    "4EE4A89BE185C787E0EC49B6F9F43D9B660B94CD2FE36382C1D1C8D75960693C"
+    "FE2EFE41D99FAFB50435C803F01B683657C4889CBE8B4CFA4BC20EC1549A976A"
+    "ECB24DB180AC46DE0C416006545AD0822C26B7496069FE47C0193DBB359B0F4A"
+    "AAB8652A4264D64CA335186250452283AB7683396851FD8D05200763DFAFE6D9"
+    "720A94092E9058ACDD26DC45AD4180E92D97621914AA2B79C2EC96A7EED54275"
+    "CC7FAA60F517BA0A2C3713A4285C3ECB2B86DBFEC4951522562DD0FB2009C39C"
+    "7A6F4DF22954805CE105CC83D09AE6C20694B8754F5620ADA329D807A3714496"
+    "4833DCB47FAC6E454AACDC6FE60294DCFA4766408D5C14081403E8E7"
+);
+
+const OPENSSL_CLIENT_KEY_EXCHANGE: &[u8] = &hex!(
+    "01008AFF1F02CE2D02E5F3BDA6B5D293590BF8ED92C56A08732E2EE2A3649BE6"
+    "8E1BE5DA1CA84012BC1E1838960DB36FDC9E20F853C11989087B6C20B5226DD3"
+    "8979931B7E1A9C8082B0ED9B54E073726C5C12D9E220B59DA4F7B929627D9CAC"
+    "4E7E6866664FEE79758CB0CBE3C0FD2677D228B4D168DF0399DBB571422CA14D"
+    "BD989FD59AD3F7BE1B8093B32FB007C1F75EED25788225F5E8EB818673F6A9DF"
+    "2A15DB574C2F52CDA36B6B23AABDA925DAF67B6A5F146BC745D592BEED975E09"
+    "1DD0621ED0E533CC7282E88CCF4C1A5A5799F6B80BED62142E7F738C6E613A04"
+    "8AAF5E080AB1A025EFF6A0753EB7AB297965A6FB100821C75794D283E734C218"
+    "DD4A"
+);
+
+#[test]
+fn should_decode_openssl_server_key_exchange() {
+    let params = ServerKeyExchangeParams::decode(OPENSSL_SERVER_KEY_EXCHANGE).unwrap();
+    let constants = OpenConstants::<256>::default();
+    assert_eq!(params.group_message(), constants.group_message());
+    assert_eq!(
+        params.constants::<256>(&TrustedGroups::default()).unwrap(),
+        constants
+    );
+    assert_eq!(params.salt.num_bytes(), 20);
+    validate_public_key(&params.server_publickey, &constants).unwrap();
+    assert_eq!(params.server_handshake().group_bits, 2048);
+    assert_eq!(params.encode().unwrap(), OPENSSL_SERVER_KEY_EXCHANGE);
+}
+
+#[test]
+fn should_decode_openssl_client_key_exchange() {
+    let client_key_exchange = ClientKeyExchange::decode(OPENSSL_CLIENT_KEY_EXCHANGE).unwrap();
+    validate_public_key(
+        &client_key_exchange.user_publickey,
+        &OpenConstants::<256>::default(),
+    )
+    .unwrap();
+    assert_eq!(
+        client_key_exchange.encode().unwrap(),
+        OPENSSL_CLIENT_KEY_EXCHANGE
+    );
+}
+
+#[test]
+fn should_reject_openssl_messages_with_trailing_bytes() {
+    let mut bytes = OPENSSL_SERVER_KEY_EXCHANGE.to_vec();
+    bytes.extend([0, 0]);
+    assert_eq!(
+        ServerKeyExchangeParams::decode(&bytes),
+        Err(Srp6Error::TrailingBytes { count: 2 })
+    );
+    assert_eq!(
+        ServerKeyExchangeParams::decode(
+            &OPENSSL_SERVER_KEY_EXCHANGE[..OPENSSL_SERVER_KEY_EXCHANGE.len() - 1]
+        ),
+        Err(Srp6Error::TruncatedMessage)
+    );
+    let mut bytes = OPENSSL_CLIENT_KEY_EXCHANGE.to_vec();
+    bytes.push(0);
+    assert_eq!(
+        ClientKeyExchange::decode(&bytes),
+        Err(Srp6Error::TrailingBytes { count: 1 })
+    );
+}
+
+#[test]
+fn should_handshake_over_tls_messages() {
+    let (username, password) = ("alice", "password");
+    let constants = OpenConstants::<256>::default();
+    let user_details =
+        Srp6_2048::generate_new_user_secrets(&username.into(), &password.into(), &constants)
+            .unwrap();
+    let mut user = Srp6user2048::default();
+    let user_handshake = user.start_handshake(username, &constants).unwrap();
+    let client_key_exchange = ClientKeyExchange::from(&user_handshake).encode().unwrap();
+
+    let user_handshake = ClientKeyExchange::decode(&client_key_exchange)
+        .unwrap()
+        .user_handshake(username);
+    let (server_handshake, host) = Srp6_2048::default()
+        .continue_handshake(&user_details, &user_handshake, &constants)
+        .unwrap();
+    let server_key_exchange = ServerKeyExchangeParams::new(&constants, &server_handshake)
+        .encode()
+        .unwrap();
+
+    let params = ServerKeyExchangeParams::decode(&server_key_exchange).unwrap();
+    let constants = params.constants::<256>(&TrustedGroups::default()).unwrap();
+    let (proof, user) = user
+        .update_handshake(
+            &params.server_handshake(),
+            &constants,
+            &username.into(),
+            &password.into(),
+        )
+        .unwrap();
+    let outcome = host.verify_proof(&proof).unwrap();
+    let user_outcome = user.verify_proof(outcome.server_proof()).unwrap();
+    assert_eq!(
+        outcome.strong_session_key(),
+        user_outcome.strong_session_key()
+    );
+}
+
+/// a number of `min_len` to `max_len` bytes without leading zeros
+fn number(min_len: usize, max_len: usize) -> impl Strategy<Value = PublicKey> {
+    (
+        1..=255_u8,
+        proptest::collection::vec(any::<u8>(), min_len - 1..max_len),
+    )
+        .prop_map(|(first, rest)| {
+            let mut bytes = vec![first];
+            bytes.extend(rest);
+            PublicKey::from_bytes_be(&bytes)
+        })
+}
+
+proptest! {
+    #[test]
+    fn should_round_trip_server_params(
+        modulus in number(64, 320),
+        generator in number(1, 2),
+        salt in number(1, 255),
+        server_publickey in number(1, 64),
+    ) {
+        let params = ServerKeyExchangeParams { modulus, generator, salt, server_publickey };
+        let bytes = params.encode().unwrap();
+        prop_assert_eq!(ServerKeyExchangeParams::decode(&bytes).unwrap(), params);
+    }
+
+    #[test]
+    fn should_round_trip_client_key_exchange(user_publickey in number(1, 1024)) {
+        let client_key_exchange = ClientKeyExchange::new(user_publickey);
+        let bytes = client_key_exchange.encode().unwrap();
+        prop_assert_eq!(ClientKeyExchange::decode(&bytes).unwrap(), client_key_exchange);
+    }
+
+    #[test]
+    fn should_decode_any_bytes_without_panic(bytes in proptest::collection::vec(any::<u8>(), 0..128)) {
+        let _ = ServerKeyExchangeParams::decode(&bytes);
+        let _ = ClientKeyExchange::decode(&bytes);
+    }
+}
@@ -0,0 +1,11 @@
+//! The typestate API: steps of the handshake cannot be called out of order.
+
+/// with `mutable-api`, the compiler qualifies the types, which are then
+/// ambiguous with `mutable::Srp6` and `mutable::Srp6User`; with `pub-fields`,
+/// the handshake values can be overwritten
+#[cfg(not(any(feature = "mutable-api", feature = "pub-fields")))]
+#[test]
+fn ui() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui/*.rs");
+}
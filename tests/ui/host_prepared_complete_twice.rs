@@ -0,0 +1,14 @@
+use chadehoc_srp6::*;
+
+fn main() {
+    let constants = OpenConstants::<256>::default();
+    let user_details = generate_user_details(&"Bob".into(), &"secret-password".into(), &constants).unwrap();
+    let mut srp6_user = Srp6user2048::default();
+    let user_handshake = srp6_user.start_handshake("Bob", &constants).unwrap();
+    let prepared = Srp6_2048::default()
+        .prepare(&user_details, &constants)
+        .unwrap();
+    let _ = prepared.complete(&user_handshake.user_publickey);
+    // the private key `b` serves a single handshake
+    let _ = prepared.complete(&user_handshake.user_publickey);
+}
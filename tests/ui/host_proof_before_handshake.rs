@@ -0,0 +1,7 @@
+use chadehoc_srp6::*;
+
+fn main() {
+    // the server has no proof `M2` to give before the handshake
+    let srp6 = Srp6_2048::default();
+    let _ = srp6.verify_proof(&Proof::from(1));
+}
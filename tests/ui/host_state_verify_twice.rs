@@ -0,0 +1,18 @@
+use chadehoc_srp6::*;
+
+fn main() {
+    let constants = OpenConstants::<256>::default();
+    let user_details = generate_user_details(&"Bob".into(), &"secret-password".into(), &constants).unwrap();
+    let mut srp6_user = Srp6user2048::default();
+    let user_handshake = srp6_user.start_handshake("Bob", &constants).unwrap();
+    let (_, state) = Srp6_2048::continue_handshake_stateless(
+        &user_details,
+        &user_handshake.user_publickey,
+        &constants,
+    )
+    .unwrap();
+    let guess = Proof::default();
+    let _ = verify_proof_with_state(state, &guess);
+    // a failed proof cannot be retried on the same state
+    let _ = verify_proof_with_state(state, &guess);
+}
@@ -0,0 +1,15 @@
+use chadehoc_srp6::*;
+
+fn main() {
+    let constants = OpenConstants::<256>::default();
+    let user_details = generate_user_details(&"Bob".into(), &"secret-password".into(), &constants).unwrap();
+    let mut srp6_user = Srp6user2048::default();
+    let user_handshake = srp6_user.start_handshake("Bob", &constants).unwrap();
+    let (_, srp6) = Srp6_2048::default()
+        .continue_handshake(&user_details, &user_handshake.user_publickey, &constants)
+        .unwrap();
+    let guess = Proof::default();
+    let _ = srp6.verify_proof(&guess);
+    // a failed proof cannot be retried on the same handshake
+    let _ = srp6.verify_proof(&guess);
+}
@@ -0,0 +1,10 @@
+use chadehoc_srp6::*;
+
+fn main() {
+    let constants = OpenConstants::<256>::default();
+    let mut srp6_user = Srp6user2048::with_credentials(&"Bob".into(), &"secret-password".into());
+    let _ = srp6_user.start_handshake(&constants).unwrap();
+    let server_handshake = ServerHandshake::new(Salt::from(1), PublicKey::from(2));
+    // the username is the one given to `with_credentials`, it cannot differ
+    let _ = srp6_user.update_handshake(&server_handshake, &constants, "Alice", "secret-password");
+}
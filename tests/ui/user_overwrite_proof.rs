@@ -0,0 +1,14 @@
+use chadehoc_srp6::*;
+
+fn main() {
+    let constants = OpenConstants::<256>::default();
+    let mut srp6_user = Srp6user2048::with_credentials(&"Bob".into(), &"secret-password".into());
+    let _ = srp6_user.start_handshake(&constants).unwrap();
+    let server_handshake = ServerHandshake::new(Salt::from(1), PublicKey::from(2));
+    let (_, mut waiting) = srp6_user
+        .update_handshake(&server_handshake, &constants)
+        .unwrap();
+    // the proof can be read, not overwritten before the server's proof is verified
+    let _ = waiting.proof();
+    waiting.M = Proof::from(1);
+}
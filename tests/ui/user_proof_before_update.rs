@@ -0,0 +1,9 @@
+use chadehoc_srp6::*;
+
+fn main() {
+    let constants = OpenConstants::<256>::default();
+    let mut srp6_user = Srp6user2048::default();
+    let _ = srp6_user.start_handshake("Bob", &constants).unwrap();
+    // the server's proof cannot be checked before sending the user's proof
+    let _ = srp6_user.verify_proof(&Proof::from(1));
+}
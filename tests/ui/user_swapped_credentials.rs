@@ -0,0 +1,12 @@
+use chadehoc_srp6::*;
+
+fn main() {
+    let constants = OpenConstants::<256>::default();
+    let username = Username::from("Bob");
+    let password = Password::from("secret-password");
+    let mut srp6_user = Srp6user2048::default();
+    let _ = srp6_user.start_handshake(&username, &constants).unwrap();
+    let server_handshake = ServerHandshake::new(Salt::from(1), PublicKey::from(2));
+    // the password cannot be given as the username, nor the other way round
+    let _ = srp6_user.update_handshake(&server_handshake, &constants, &password, &username);
+}
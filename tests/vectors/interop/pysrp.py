@@ -0,0 +1,60 @@
+"""Captures a handshake of the `srp` package into `pysrp_2048_sha1.json`.
+
+    pip install srp==1.0.22
+    python pysrp.py > pysrp_2048_sha1.json
+
+The pure Python implementation `srp._pysrp` is used, in its default mode
+without `rfc5054_enable()`, as the `PySrp` profile. The salt and the private
+values `a` and `b` are drawn by the package and recorded, so that the handshake
+can be replayed.
+"""
+import json
+from importlib.metadata import version
+
+from srp import _pysrp as pysrp
+
+I, p = "alice", "password123"
+hash_alg, ng_type = pysrp.SHA1, pysrp.NG_2048
+
+s, v = pysrp.create_salted_verification_key(I, p, hash_alg, ng_type)
+user = pysrp.User(I, p, hash_alg, ng_type)
+_, A = user.start_authentication()
+server = pysrp.Verifier(I, s, v, A, hash_alg, ng_type)
+_, B = server.get_challenge()
+M1 = user.process_challenge(s, B)
+M2 = server.verify_session(M1)
+user.verify_session(M2)
+assert user.authenticated() and server.authenticated()
+
+N, g = pysrp.get_ng(ng_type, None, None)
+
+
+def number(value):
+    """big endian hex of an integer or of bytes"""
+    if isinstance(value, bytes):
+        value = int.from_bytes(value, "big")
+    return format(value, "X")
+
+
+fixture = {
+    "library": "srp",
+    "version": version("srp"),
+    "script": "pysrp.py",
+    "hash": "SHA-1",
+    "profile": "PySrp",
+    "I": I,
+    "p": p,
+    "N": number(N),
+    "g": number(g),
+    "s": number(s),
+    "v": number(v),
+    "a": number(user.a),
+    "b": number(server.b),
+    "A": number(A),
+    "B": number(B),
+    "S": number(server.S),
+    "K": number(server.K),
+    "M1": number(M1),
+    "M2": number(M2),
+}
+print(json.dumps(fixture, indent=2))
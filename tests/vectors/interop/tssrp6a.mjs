@@ -0,0 +1,67 @@
+// Captures a handshake of `tssrp6a` into `tssrp6a_2048_sha256.json`.
+//
+//   npm install tssrp6a@3
+//   node tssrp6a.mjs > tssrp6a_2048_sha256.json
+//
+// The salt is drawn by the library and recorded; the private values `a` and
+// `b` are drawn by the library as well, through `generatePrivateValue`, and
+// recorded so that the handshake can be replayed.
+import { createRequire } from "node:module";
+import {
+  SRPClientSession,
+  SRPParameters,
+  SRPRoutines,
+  SRPServerSession,
+  createVerifierAndSalt,
+} from "tssrp6a";
+
+const { version } = createRequire(import.meta.url)("tssrp6a/package.json");
+
+/// the routines of the library, remembering the private values they draw
+class RecordingRoutines extends SRPRoutines {
+  generatePrivateValue() {
+    this.privateValue = super.generatePrivateValue();
+    return this.privateValue;
+  }
+}
+
+const hex = (n) => n.toString(16).toUpperCase();
+const [I, p] = ["alice", "password123"];
+const parameters = new SRPParameters(
+  SRPParameters.PrimeGroup[2048],
+  SRPParameters.H.SHA256,
+);
+const server = new RecordingRoutines(parameters);
+const client = new RecordingRoutines(parameters);
+
+const { s, v } = await createVerifierAndSalt(server, I, p);
+const serverStep1 = await new SRPServerSession(server).step1(I, s, v);
+const b = server.privateValue;
+const clientStep1 = await new SRPClientSession(client).step1(I, p);
+const clientStep2 = await clientStep1.step2(s, serverStep1.B);
+const a = client.privateValue;
+const M2 = await serverStep1.step2(clientStep2.A, clientStep2.M1);
+await clientStep2.step3(M2);
+
+const fixture = {
+  library: "tssrp6a",
+  version,
+  script: "tssrp6a.mjs",
+  hash: "SHA-256",
+  profile: "Tssrp6a",
+  I,
+  p,
+  N: hex(parameters.N),
+  g: hex(parameters.g),
+  s: hex(s),
+  v: hex(v),
+  a: hex(a),
+  b: hex(b),
+  A: hex(clientStep2.A),
+  B: hex(serverStep1.B),
+  S: hex(clientStep2.S),
+  K: null,
+  M1: hex(clientStep2.M1),
+  M2: hex(M2),
+};
+console.log(JSON.stringify(fixture, null, 2));
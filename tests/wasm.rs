@@ -0,0 +1,110 @@
+//! the user side of the JavaScript bindings on wasm32, through the `_js` methods
+//! and their conversions of JS objects, against precomputed server messages
+//!
+//! `wasm-pack test --headless --firefox -- --features wasm,deterministic --test wasm`
+#![cfg(all(target_arch = "wasm32", feature = "wasm", feature = "deterministic"))]
+use chadehoc_srp6::*;
+use serde::Serialize;
+use wasm_bindgen::JsValue;
+use wasm_bindgen_test::*;
+
+wasm_bindgen_test_configure!(run_in_browser);
+
+const USERNAME: &str = "alice";
+const PASSWORD: &str = "password123";
+const SALT: &str = "5A1FD9E2C4B7083A6E11F0D2B8C39A47";
+/// `b` of the server, which gives `B` of [`server_handshake`]
+const B_PRIVATE: &str = "3C6A1F0E2D4B59687A8B9CADBECFD0E1F20314253647586978899AABBCCDDEEF";
+
+/// the server's answer to any `A` of `alice`, as the JS client receives it
+fn server_handshake() -> serde_json::Value {
+    serde_json::json!({
+        "salt": SALT,
+        "server_publickey": "AC5F45AFBE50E049176DB872A892644239E2D475340020BFBC00A5B55489F136\
+            401107EF7B35D8426A9A6E4699395148FEF0A462E05D7F1D941022904A1D3CFD\
+            EFAB96C0F1B9175724E83B9E5FAE0ADB805B35C6AEE60AB32275C3D11BB6855C\
+            AB73CDD49C2B49BD3E1B8779EC4F5D5537835965C6E381CA22146578A0BB3A45\
+            BD26A1E7DB25F9425C8C0B81D66B3FA6FEA88D81548501AC78786EDCE67A543A\
+            37E8C0F427A51C26003B2D060B7FBD4139468F7554898623104B60D428EEFE30\
+            C5F54A222DCBF4EFD8EF6D7A5D1E77A95C5A82719A38EB342A68818C12CBBFD3\
+            A3954D5A87F5660AA1F7B8C3EB04910ED6EAC7A737D7CC2B44FA7412935A0A37",
+        "group_bits": 2048,
+    })
+}
+
+/// a plain JS object, as built by a JS client, not a `Map`
+fn to_js(value: &impl Serialize) -> JsValue {
+    value
+        .serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+        .unwrap()
+}
+
+/// the server that answered with [`server_handshake`] to the `A` of `user_handshake`
+fn server(user_handshake: JsValue) -> Srp6WaitingForProof<256> {
+    let constants = OpenConstants::<256>::default();
+    let salt = Salt::from_hex_str_be(SALT).unwrap();
+    let user_details = Srp6_2048::generate_user_details_with_salt(
+        &USERNAME.into(),
+        &PASSWORD.into(),
+        &salt,
+        &constants,
+    )
+    .unwrap();
+    let user_handshake: UserHandshake = serde_wasm_bindgen::from_value(user_handshake).unwrap();
+    assert_eq!(user_handshake.username, USERNAME);
+    let (answer, server) = Srp6_2048::default()
+        .continue_handshake_with_ephemeral(
+            &user_details,
+            &user_handshake,
+            &constants,
+            PrivateKey::from_hex_str_be(B_PRIVATE).unwrap(),
+        )
+        .unwrap();
+    let expected: ServerHandshake = serde_json::from_value(server_handshake()).unwrap();
+    assert_eq!(answer.salt, expected.salt);
+    assert_eq!(answer.server_publickey, expected.server_publickey);
+    server
+}
+
+#[wasm_bindgen_test]
+fn should_run_the_client_flow() {
+    let mut user = WasmSrp6User::new(2048).unwrap();
+    let server = server(user.start_handshake_js(USERNAME).unwrap());
+    let proof = user
+        .update_handshake_js(to_js(&server_handshake()), PASSWORD)
+        .unwrap();
+    let proof: ProofMessage = serde_wasm_bindgen::from_value(proof).unwrap();
+    let outcome = server.verify_proof(&proof.proof).unwrap();
+    let servers_proof = to_js(&ProofMessage::from(outcome.server_proof().clone()));
+    let key = user.verify_server_proof_js(servers_proof).unwrap();
+    assert_eq!(key, outcome.strong_session_key().to_string());
+}
+
+#[wasm_bindgen_test]
+fn should_reject_a_wrong_server_proof() {
+    let mut user = WasmSrp6User::new(2048).unwrap();
+    server(user.start_handshake_js(USERNAME).unwrap());
+    user.update_handshake_js(to_js(&server_handshake()), PASSWORD)
+        .unwrap();
+    let wrong = to_js(&ProofMessage::from(StrongProof::from(42)));
+    assert!(user.verify_server_proof_js(wrong).is_err());
+}
+
+#[wasm_bindgen_test]
+fn should_reject_a_wrong_password() {
+    let mut user = WasmSrp6User::new(2048).unwrap();
+    let server = server(user.start_handshake_js(USERNAME).unwrap());
+    let proof = user
+        .update_handshake_js(to_js(&server_handshake()), "wrong-password")
+        .unwrap();
+    let proof: ProofMessage = serde_wasm_bindgen::from_value(proof).unwrap();
+    assert_eq!(
+        server.verify_proof(&proof.proof).err(),
+        Some(Srp6Error::InvalidProof)
+    );
+}
+
+#[wasm_bindgen_test]
+fn should_reject_an_unknown_group() {
+    assert!(WasmSrp6User::new(2000).is_err());
+}